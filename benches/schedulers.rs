@@ -0,0 +1,254 @@
+//! Benchmarks for the schedulers' hot paths.
+//!
+//! `queue_ops` fills a scheduler with 10, 1 000 and 100 000 processes and
+//! drains it again, measuring `add_process` plus `next_process` throughput.
+//! `simulate` runs a generated workload end to end through a round-robin
+//! simulation loop that reports into a [`Recorder`], like the simulator does.
+//! `sjf_queue` does the same filling and draining for shortest job first
+//! with the run queue kept two ways, `SPN`'s scan of a `Vec` against
+//! `SRTF`'s binary heap, at up to 1 000 processes, as the scan takes
+//! quadratic time.
+//! `timers` keeps 1 000 and 1 000 000 timers pending in each of the
+//! simulator engine's timer queues, a `BTreeMap`, a `BinaryHeap` and a
+//! timing wheel, and fires 1 000 000 of them tick by tick, re-arming each
+//! one a random number of ticks out, the way a kernel's timers churn.
+//! Before each scheduler's benchmarks the peak heap size it reports (see
+//! `Schedule::heap_size`) is printed for the largest queue.
+//!
+//! The schedulers that are still stubs end the process the first time they
+//! are called, so until all of them are implemented, benchmark yours by name:
+//! ```text
+//! cargo bench -- simplerr
+//! ```
+
+use std::hint::black_box;
+use std::time::Duration;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use scheduler::event::EventKind;
+use scheduler::config::SpnConfig;
+use scheduler::generate::Generator;
+use scheduler::policy::{SPN, SRTF};
+use scheduler::random::Rng;
+use scheduler::sink::Recorder;
+use scheduler::timer::{TimerKind, Timers};
+use scheduler::{
+    CLOCK, PCB, Schedule, bsd::BsdSchedule, config::StackConfig, feedback::Stack, fgbg::FgBgSchedule, mlf::MLFSchedule,
+    mlrr::MLRRSchedule, prio_fcfs::PrioFcfsSchedule, simple::SimpleSchedule, simplemlf::SimpleMLFSchedule, simplerr::SimpleRRSchedule,
+};
+
+const SIZES: [u32; 3] = [10, 1_000, 100_000];
+
+type Constructor = fn() -> Box<dyn Schedule>;
+
+/// Every built-in scheduler, by its name on the command line.
+const SCHEDULERS: [(&str, Constructor); 9] = [
+    ("simple", || Box::new(SimpleSchedule::new())),
+    ("simplerr", || Box::new(SimpleRRSchedule::new())),
+    ("mlrr", || Box::new(MLRRSchedule::new())),
+    ("simplemlf", || Box::new(SimpleMLFSchedule::new())),
+    ("mlf", || Box::new(MLFSchedule::new())),
+    ("bsd", || Box::new(BsdSchedule::new())),
+    ("fgbg", || Box::new(FgBgSchedule::new())),
+    ("prio_fcfs", || Box::new(PrioFcfsSchedule::new())),
+    ("stack", || Box::new(Stack::with_config(StackConfig::default()))),
+];
+
+fn pcb(id: u32) -> PCB {
+    PCB { id, priority: id % 4, time_added: None, time_scheduled: None }
+}
+
+fn fill(sched: &mut dyn Schedule, n: u32) {
+    for id in 0..n {
+        sched.add_process(pcb(id));
+    }
+}
+
+fn drain(sched: &mut dyn Schedule) {
+    while sched.has_process() {
+        black_box(sched.next_process());
+    }
+}
+
+fn queue_ops(c: &mut Criterion) {
+    CLOCK.set_tick(Duration::from_nanos(1));
+    for (name, new) in SCHEDULERS {
+        let mut group = c.benchmark_group(format!("queue_ops/{}", name));
+        let largest = SIZES[SIZES.len() - 1];
+        let mut sched = new();
+        fill(sched.as_mut(), largest);
+        eprintln!("{}: heap size with {} processes queued: {} bytes", name, largest, sched.heap_size());
+        for n in SIZES {
+            group.throughput(Throughput::Elements(n as u64));
+            if n >= 100_000 {
+                group.sample_size(10);
+            }
+            group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+                b.iter_batched(
+                    || {
+                        CLOCK.set_now(Duration::ZERO);
+                        new()
+                    },
+                    |mut sched| {
+                        fill(sched.as_mut(), n);
+                        drain(sched.as_mut());
+                    },
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+        group.finish();
+    }
+}
+
+/// Bursts of 1 to 1 000 ticks, in no particular order, for processes `0`
+/// to `n - 1`.
+fn bursts(n: u32) -> Vec<(u32, u32)> {
+    (0..n).map(|id| (id, id.wrapping_mul(7919) % 1_000 + 1)).collect()
+}
+
+type SizedConstructor = fn(u32) -> Box<dyn Schedule>;
+
+/// Shortest job first told the bursts of `n` processes, by how it keeps its
+/// run queue.
+const SJF_QUEUES: [(&str, SizedConstructor); 2] = [
+    ("scan", |n| Box::new(SPN::with_error(bursts(n), &SpnConfig::default()))),
+    ("heap", |n| Box::new(SRTF::non_preemptive(bursts(n)))),
+];
+
+fn sjf_queue(c: &mut Criterion) {
+    CLOCK.set_tick(Duration::from_nanos(1));
+    let mut group = c.benchmark_group("sjf_queue");
+    for n in [10, 100, 1_000] {
+        group.throughput(Throughput::Elements(n as u64));
+        for (name, new) in SJF_QUEUES {
+            group.bench_with_input(BenchmarkId::new(name, n), &n, |b, &n| {
+                b.iter_batched(
+                    || {
+                        CLOCK.set_now(Duration::ZERO);
+                        new(n)
+                    },
+                    |mut sched| {
+                        fill(sched.as_mut(), n);
+                        drain(sched.as_mut());
+                    },
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Timers fired by each `timers` benchmark.
+const FIRED: usize = 1_000_000;
+
+/// `n` timers, each due up to `2 * n` ticks out, so about one fires per
+/// tick.
+fn arm(kind: TimerKind, n: u64, rng: &mut Rng) -> Timers<u64> {
+    let mut timers = Timers::new(kind);
+    for id in 0..n {
+        timers.insert(rng.next_u64() % (2 * n) + 1, id);
+    }
+    timers
+}
+
+/// Fires [`FIRED`] timers, re-arming each up to `2 * n` ticks after it fired.
+fn churn(timers: &mut Timers<u64>, n: u64, rng: &mut Rng) {
+    let (mut now, mut fired) = (0, 0);
+    while fired < FIRED {
+        now += 1;
+        for id in timers.expire(now) {
+            timers.insert(now + rng.next_u64() % (2 * n) + 1, black_box(id));
+            fired += 1;
+        }
+    }
+}
+
+fn timers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("timers");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(FIRED as u64));
+    for n in [1_000, 1_000_000] {
+        for kind in [TimerKind::BTree, TimerKind::Heap, TimerKind::Wheel] {
+            group.bench_with_input(BenchmarkId::new(kind.to_string(), n), &n, |b, &n| {
+                b.iter_batched(
+                    || {
+                        let mut rng = Rng::new(n);
+                        (arm(kind, n, &mut rng), rng)
+                    },
+                    |(mut timers, mut rng)| churn(&mut timers, n, &mut rng),
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Runs `jobs` through `sched` the way the simulator's generic loop does.
+fn simulate(sched: &mut dyn Schedule, jobs: &[scheduler::input::JobSpec]) -> Recorder {
+    let mut out = Recorder::new();
+    let mut left: Vec<u32> = jobs.iter().map(|job| job.burst).collect();
+    let mut next_arrival = 0;
+    let mut finished = 0;
+    CLOCK.set_now(Duration::ZERO);
+    let mut admit = |sched: &mut dyn Schedule, out: &mut Recorder| {
+        while next_arrival < jobs.len() && jobs[next_arrival].arrival <= CLOCK.now_ticks() {
+            let job = &jobs[next_arrival];
+            out.emit(job.id, EventKind::Arrive { priority: job.priority });
+            sched.add_process(PCB { id: job.id, priority: job.priority, time_added: None, time_scheduled: None });
+            next_arrival += 1;
+        }
+    };
+    while finished < jobs.len() {
+        admit(sched, &mut out);
+        let Ok((process, quantum)) = sched.try_next() else {
+            out.emit(u32::MAX, EventKind::Idle);
+            CLOCK.advance(CLOCK.tick());
+            continue;
+        };
+        out.emit(process.id, EventKind::Dispatch { quantum: quantum.raw() });
+        let remaining = &mut left[process.id as usize];
+        let mut ran = 0;
+        while *remaining > 0 && quantum.limit().is_none_or(|limit| ran < limit) {
+            out.emit(process.id, EventKind::Execute);
+            CLOCK.advance(CLOCK.tick());
+            *remaining -= 1;
+            ran += 1;
+            admit(sched, &mut out);
+        }
+        if *remaining == 0 {
+            out.emit(process.id, EventKind::Finish);
+            finished += 1;
+        } else {
+            out.emit(process.id, EventKind::Preempt);
+            sched.add_process(process);
+        }
+    }
+    out
+}
+
+fn end_to_end(c: &mut Criterion) {
+    CLOCK.set_tick(Duration::from_nanos(1));
+    let generator = Generator {
+        jobs: 1_000,
+        arrival: "poisson:0.08".parse().unwrap(),
+        burst: "pareto:1.5,4".parse().unwrap(),
+        priorities: 4,
+        classes: Vec::new(),
+        seed: 1,
+    };
+    let jobs = generator.generate();
+    let mut group = c.benchmark_group("simulate");
+    group.sample_size(10);
+    for (name, new) in SCHEDULERS {
+        group.bench_function(name, |b| {
+            b.iter_batched(new, |mut sched| simulate(sched.as_mut(), &jobs), BatchSize::SmallInput);
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, sjf_queue, timers, queue_ops, end_to_end);
+criterion_main!(benches);
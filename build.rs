@@ -0,0 +1,35 @@
+//! Records the git commit the simulator is built from, for `--version` and
+//! the provenance written into results.
+//!
+//! Sets `SCHEDULER_GIT_HASH` when building from a git checkout, and always
+//! sets `SCHEDULER_VERSION` to the crate version followed by the commit, if
+//! known.
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let version = std::env::var("CARGO_PKG_VERSION").unwrap_or_default();
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty());
+    match &hash {
+        Some(hash) => {
+            println!("cargo:rustc-env=SCHEDULER_GIT_HASH={}", hash);
+            println!("cargo:rustc-env=SCHEDULER_VERSION={} ({})", version, hash);
+        }
+        None => println!("cargo:rustc-env=SCHEDULER_VERSION={}", version),
+    }
+    //rebuild when a commit is made or checked out
+    println!("cargo:rerun-if-changed=build.rs");
+    for path in [".git/HEAD", ".git/refs", ".git/packed-refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Feeds arbitrary bytes to the workload parsers. Malformed input must come
+//! back as an error, never as a panic.
+//!
+//! ```text
+//! cargo +nightly fuzz run parse_workload
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scheduler::clock::TimeUnit;
+use scheduler::input::Workload;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    for unit in TimeUnit::ALL {
+        for with_priority in [false, true] {
+            if let Ok(workload) = Workload::parse(text.lines(), unit, with_priority) {
+                // every job the parser accepted can be found by its arrival
+                for job in workload.jobs.values() {
+                    assert!(workload.arriving_at(job.arrival).contains(&job.id));
+                }
+            }
+        }
+        let _ = Workload::from_json(text, unit);
+    }
+});
@@ -0,0 +1,147 @@
+//! Drives each built-in scheduler through arbitrary sequences of the
+//! operations a simulator performs and checks the `Schedule` invariants:
+//!
+//! - `has_process` is `true` exactly while something is queued;
+//! - `next_process` returns a process if and only if one is queued, and only
+//!   one that was added and not yet returned, never with a time slice of
+//!   zero ticks;
+//! - `peek`, where implemented, names the process `next_process` returns;
+//! - `iter_ready`, where implemented, lists exactly the queued processes;
+//! - ties are broken in the order processes were added (see `strict`),
+//!   except by `bsd`, whose priorities are not the ones in the PCB, by
+//!   `fgbg`, which resumes background processes ahead of new arrivals, and
+//!   by `prio_fcfs`, which breaks ties by id.
+//!
+//! The stubs end the process as soon as they are called, so set `SCHEDULER`
+//! to fuzz only the one you have implemented:
+//! ```text
+//! SCHEDULER=simplerr cargo +nightly fuzz run schedule_ops
+//! ```
+
+#![no_main]
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use scheduler::strict::StrictSchedule;
+use scheduler::{
+    CLOCK, PCB, Schedule, TimeSlice, bsd::BsdSchedule, fgbg::FgBgSchedule, mlf::MLFSchedule, mlrr::MLRRSchedule,
+    prio_fcfs::PrioFcfsSchedule, simple::SimpleSchedule, simplemlf::SimpleMLFSchedule, simplerr::SimpleRRSchedule,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Arbitrary)]
+enum Kind {
+    Simple,
+    SimpleRr,
+    Mlrr,
+    SimpleMlf,
+    Mlf,
+    Bsd,
+    FgBg,
+    PrioFcfs,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Simple => "simple",
+            Kind::SimpleRr => "simplerr",
+            Kind::Mlrr => "mlrr",
+            Kind::SimpleMlf => "simplemlf",
+            Kind::Mlf => "mlf",
+            Kind::Bsd => "bsd",
+            Kind::FgBg => "fgbg",
+            Kind::PrioFcfs => "prio_fcfs",
+        }
+    }
+}
+
+/// One step of a simulation, as seen by the scheduler.
+#[derive(Debug, Arbitrary)]
+enum Op {
+    /// A new process arrives.
+    Add { priority: u8 },
+    /// The running process, if any, has finished: dispatch the next one.
+    Next,
+    /// The running process is preempted and handed back.
+    Requeue,
+    /// Simulated time passes.
+    Tick { ticks: u8 },
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    scheduler: Kind,
+    ops: Vec<Op>,
+}
+
+static ONLY: LazyLock<Option<String>> = LazyLock::new(|| std::env::var("SCHEDULER").ok());
+
+fuzz_target!(|input: Input| {
+    if ONLY.as_deref().is_some_and(|name| name != input.scheduler.name()) {
+        return;
+    }
+    match input.scheduler {
+        Kind::Simple => check(SimpleSchedule::new(), input.ops, true),
+        Kind::SimpleRr => check(SimpleRRSchedule::new(), input.ops, true),
+        Kind::Mlrr => check(MLRRSchedule::new(), input.ops, true),
+        Kind::SimpleMlf => check(SimpleMLFSchedule::new(), input.ops, true),
+        Kind::Mlf => check(MLFSchedule::new(), input.ops, true),
+        Kind::Bsd => check(BsdSchedule::new(), input.ops, false),
+        Kind::FgBg => check(FgBgSchedule::new(), input.ops, false),
+        Kind::PrioFcfs => check(PrioFcfsSchedule::new(), input.ops, false),
+    }
+});
+
+fn check<S: Schedule>(sched: S, ops: Vec<Op>, strict: bool) {
+    CLOCK.set_tick(Duration::from_nanos(1));
+    CLOCK.set_now(Duration::ZERO);
+    let mut sched = StrictSchedule::new(sched, strict);
+    let mut queued: BTreeMap<u32, PCB> = BTreeMap::new();
+    let mut running: Option<PCB> = None;
+    let mut next_id = 0;
+
+    for op in ops {
+        match op {
+            Op::Add { priority } => {
+                let pcb = PCB { id: next_id, priority: priority as u32 % 4, time_added: None, time_scheduled: None };
+                next_id += 1;
+                if sched.add_process(pcb) {
+                    queued.insert(pcb.id, pcb);
+                }
+            }
+            Op::Requeue => {
+                if let Some(pcb) = running.take()
+                    && sched.add_process(pcb)
+                {
+                    queued.insert(pcb.id, pcb);
+                }
+            }
+            Op::Next => {
+                let peeked = sched.peek().map(|pcb| pcb.id);
+                let had = sched.has_process();
+                let (process, slice) = sched.next_process();
+                assert_eq!(had, process.is_some(), "has_process disagrees with next_process");
+                assert_ne!(slice, TimeSlice::Ticks(0), "next_process returned a time slice of 0 ticks");
+                if let Some(process) = process {
+                    assert!(queued.remove(&process.id).is_some(), "P{} was returned but is not queued", process.id);
+                    if let Some(peeked) = peeked {
+                        assert_eq!(peeked, process.id, "peek named a different process");
+                    }
+                }
+                running = process;
+            }
+            Op::Tick { ticks } => CLOCK.advance(Duration::from_nanos(ticks as u64)),
+        }
+        assert_eq!(sched.has_process(), !queued.is_empty(), "has_process is wrong with {} queued", queued.len());
+        let mut ready: Vec<u32> = sched.iter_ready().map(|pcb| pcb.id).collect();
+        if !ready.is_empty() {
+            ready.sort_unstable();
+            assert!(ready.iter().copied().eq(queued.keys().copied()), "iter_ready lists {:?}", ready);
+        }
+    }
+    assert!(sched.violations().is_empty(), "{}", sched.violations()[0]);
+}
@@ -0,0 +1,215 @@
+//! # Adversary Module
+//!
+//! Workloads built to be pathological for one scheduling policy, for the
+//! simulator's `gen --adversarial` subcommand, so robustness labs and test
+//! corpora can start from the textbook worst case instead of hoping a
+//! random workload hits it.
+//!
+//! An [`Adversary`] targets a policy with a given quantum:
+//! - [`Target::Fcfs`] gets a **convoy**: one CPU hog arrives first and a
+//!   stream of one-tick jobs right behind it, so every short job waits for
+//!   the whole hog.
+//! - [`Target::Rr`] gets jobs **one tick longer than the quantum**, all at
+//!   once: every job needs a second slice, so none finishes before every
+//!   other has had its first, and the run takes twice the dispatches.
+//! - [`Target::Mlfq`] gets **quantum gaming**: one honest CPU-bound job next
+//!   to jobs that block for a tick just before each slice runs out, so they
+//!   never use up a quantum, are never demoted, and keep the top level busy
+//!   while the honest job starves below. The gaming jobs need burst scripts,
+//!   so this workload is a [JSON workload](crate::input#json-workloads).
+//! - [`Target::Priority`] gets **thundering arrivals**: a low-priority job
+//!   arrives with the first of several herds of high-priority jobs that all
+//!   arrive at the same tick, each herd just as the last one is done, so the
+//!   low-priority job waits until every herd has run.
+//!
+//! Priorities are lowest number first, as for the built-in schedulers. The
+//! workloads are fixed by the target, the quantum and the number of jobs;
+//! there is nothing random about them.
+//!
+//! ## Example
+//! ```
+//! use scheduler::adversary::{Adversary, Target};
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::{Step, Workload};
+//!
+//! let convoy = Adversary { target: Target::Fcfs, quantum: 4, jobs: 5 }.workload().unwrap();
+//! assert_eq!(convoy.jobs[&0].burst, 20);
+//! assert!(convoy.jobs.values().skip(1).all(|job| job.burst == 1));
+//! assert_eq!(convoy.arrivals.keys().collect::<Vec<_>>(), [&0, &1, &2, &3, &4]);
+//!
+//! let rr = Adversary { target: Target::Rr, quantum: 4, jobs: 3 };
+//! let text = rr.render().unwrap();
+//! assert_eq!(text, "0 0 5 0\n1 0 5 0\n2 0 5 0\n");
+//! assert_eq!(Workload::parse(text.lines(), TimeUnit::Ns, true).unwrap(), rr.workload().unwrap());
+//!
+//! // the gaming jobs run 3 ticks of every 4-tick slice, then block
+//! let gaming = Adversary { target: Target::Mlfq, quantum: 4, jobs: 3 };
+//! let parsed = Workload::from_json(&gaming.render().unwrap(), TimeUnit::Ns).unwrap();
+//! assert_eq!(parsed, gaming.workload().unwrap());
+//! assert_eq!(parsed.scripts[&1][..3], [Step::Cpu(3), Step::Io(1), Step::Cpu(3)]);
+//! assert!(Adversary { quantum: 1, ..gaming }.workload().is_err());
+//!
+//! // a herd of 8 high-priority jobs every 32 ticks, the first with the low-priority job
+//! let herds = Adversary { target: Target::Priority, quantum: 4, jobs: 17 }.workload().unwrap();
+//! assert_eq!((herds.jobs[&0].priority, herds.jobs[&1].priority), (1, 0));
+//! assert_eq!(herds.arriving_at(0), [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+//! assert_eq!(herds.arriving_at(32), [9, 10, 11, 12, 13, 14, 15, 16]);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::input::{JobSpec, Step, Workload};
+use crate::transform;
+
+/// Rounds of CPU and I/O every gaming job of [`Target::Mlfq`] makes.
+pub const GAMING_ROUNDS: u32 = 20;
+
+/// High-priority jobs arriving together in every herd of
+/// [`Target::Priority`].
+pub const HERD: u32 = 8;
+
+/// The policy an [`Adversary`] targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// First come, first served, attacked with a convoy.
+    Fcfs,
+    /// Round robin, attacked with jobs one tick longer than the quantum.
+    Rr,
+    /// A multi-level feedback queue, attacked with quantum gaming.
+    Mlfq,
+    /// Strict priorities, attacked with thundering arrivals.
+    Priority,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Target::Fcfs => "fcfs",
+            Target::Rr => "rr",
+            Target::Mlfq => "mlfq",
+            Target::Priority => "priority",
+        })
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fcfs" => Ok(Target::Fcfs),
+            "rr" => Ok(Target::Rr),
+            "mlfq" => Ok(Target::Mlfq),
+            "priority" => Ok(Target::Priority),
+            _ => Err(format!("unknown policy '{}', expected fcfs, rr, mlfq or priority", s)),
+        }
+    }
+}
+
+/// A pathological workload for a policy, see the
+/// [module documentation](self).
+///
+/// # Fields
+/// - `target`: The policy to attack.
+/// - `quantum`: The policy's time slice, in ticks; also sets the length of
+///   the FCFS hog and of the high-priority jobs.
+/// - `jobs`: How many jobs the workload has, at least one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adversary {
+    pub target: Target,
+    pub quantum: u32,
+    pub jobs: u32,
+}
+
+impl Adversary {
+    /// Builds the workload, with IDs counting up from `0` in arrival order.
+    ///
+    /// # Errors
+    /// Without jobs or a quantum, or for [`Target::Mlfq`] with a quantum of
+    /// one tick, which leaves no room to block before it runs out.
+    pub fn workload(&self) -> Result<Workload, String> {
+        let q = self.quantum;
+        if self.jobs == 0 || q == 0 {
+            return Err("an adversarial workload needs at least one job and a quantum of at least one tick".to_string());
+        }
+        if self.target == Target::Mlfq && q < 2 {
+            return Err("quantum gaming needs a quantum of at least 2 ticks, to block one tick before it runs out".to_string());
+        }
+        let job = |id: u32, arrival: u64, burst: u32, priority: u32| JobSpec {
+            id,
+            arrival,
+            burst,
+            priority,
+            class: None,
+            nice: 0,
+            timeout: None,
+        };
+        let mut workload = Workload::default();
+        let jobs: Vec<JobSpec> = match self.target {
+            Target::Fcfs => std::iter::once(job(0, 0, self.jobs * q, 0))
+                .chain((1..self.jobs).map(|id| job(id, id as u64, 1, 0)))
+                .collect(),
+            Target::Rr => (0..self.jobs).map(|id| job(id, 0, q + 1, 0)).collect(),
+            Target::Mlfq => {
+                let script: Vec<Step> = (0..GAMING_ROUNDS)
+                    .flat_map(|_| [Step::Cpu(q - 1), Step::Io(1)])
+                    .take(GAMING_ROUNDS as usize * 2 - 1)
+                    .collect();
+                for id in 1..self.jobs {
+                    workload.scripts.insert(id, script.clone());
+                }
+                std::iter::once(job(0, 0, GAMING_ROUNDS * q, 0))
+                    .chain((1..self.jobs).map(|id| job(id, 0, GAMING_ROUNDS * (q - 1), 0)))
+                    .collect()
+            }
+            Target::Priority => std::iter::once(job(0, 0, 2 * q, 1))
+                .chain((1..self.jobs).map(|id| job(id, ((id - 1) / HERD * HERD * q) as u64, q, 0)))
+                .collect(),
+        };
+        for spec in jobs {
+            workload.arrivals.entry(spec.arrival).or_default().push(spec.id);
+            workload.jobs.insert(spec.id, spec);
+        }
+        Ok(workload)
+    }
+
+    /// Writes the workload in the input file format, or as a JSON workload
+    /// when it has burst scripts.
+    ///
+    /// # Errors
+    /// When the workload cannot be built, see [`workload`](Self::workload).
+    pub fn render(&self) -> Result<String, String> {
+        let workload = self.workload()?;
+        if workload.scripts.is_empty() {
+            return transform::to_text(&workload, true);
+        }
+        //one job per line, as the built-in JSON workloads are written
+        let jobs: Vec<String> = workload
+            .arrivals
+            .values()
+            .flatten()
+            .map(|id| {
+                let spec = &workload.jobs[id];
+                let burst = match workload.scripts.get(id) {
+                    Some(script) => {
+                        let steps: Vec<String> = script
+                            .iter()
+                            .map(|step| match step {
+                                Step::Cpu(ticks) => format!("{{ \"cpu\": {} }}", ticks),
+                                Step::Io(ticks) => format!("{{ \"io\": {} }}", ticks),
+                            })
+                            .collect();
+                        format!("[{}]", steps.join(", "))
+                    }
+                    None => spec.burst.to_string(),
+                };
+                format!(
+                    "  {{ \"id\": {}, \"arrival\": {}, \"priority\": {}, \"burst\": {} }}",
+                    spec.id, spec.arrival, spec.priority, burst
+                )
+            })
+            .collect();
+        Ok(format!("{{ \"jobs\": [\n{}\n] }}\n", jobs.join(",\n")))
+    }
+}
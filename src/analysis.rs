@@ -0,0 +1,882 @@
+//! # Analysis Module
+//!
+//! Passes over a run's event log that explain *why* the metrics came out the
+//! way they did.
+//!
+//! ## Convoys
+//! The convoy effect is the classic weakness of FCFS scheduling: short jobs
+//! queue up behind one long job and all of them inherit its run time as
+//! waiting time. [`convoys`] walks the event log, charges every tick a job
+//! spends waiting to the process that held the CPU during that tick, and
+//! reports each job whose wait behind a single other process is many times
+//! its own CPU time.
+//!
+//! ```
+//! use scheduler::analysis::{convoys, DEFAULT_CONVOY_RATIO};
+//! use scheduler::event::{Event, EventKind};
+//!
+//! let at = |time, id, kind| Event { time, id, kind };
+//! let events = [
+//!     at(0, 0, EventKind::Arrive { priority: 0 }),
+//!     at(0, 1, EventKind::Arrive { priority: 0 }),
+//!     at(0, 0, EventKind::Dispatch { quantum: 0 }),
+//!     at(100, 0, EventKind::Finish),
+//!     at(100, 1, EventKind::Dispatch { quantum: 0 }),
+//!     at(105, 1, EventKind::Finish),
+//! ];
+//! let report = convoys(&events, DEFAULT_CONVOY_RATIO);
+//! let convoy = &report.convoys[0];
+//! assert_eq!((convoy.id, convoy.blocker), (1, 0));
+//! assert_eq!((convoy.waiting, convoy.blocked, convoy.cpu), (100, 100, 5));
+//! ```
+//!
+//! ## Gaming the Scheduler
+//! A multi-level feedback queue that demotes a job only when it uses up a
+//! whole time slice can be gamed: a job that blocks for a moment just before
+//! its slice runs out keeps its high priority forever, and with it most of
+//! the CPU. [`gaming`] finds the jobs that gave up the CPU with at most a
+//! few ticks of their slice left in most of their slices, and compares the
+//! CPU time each of them got with that of every honest job it shared the
+//! system with. Charging a job's CPU time against a total allotment per
+//! level, however it was split into slices, is how modern MLFQs close the
+//! loophole. The `gaming` built-in workload demonstrates it.
+//!
+//! ```
+//! use scheduler::analysis::{gaming, DEFAULT_GAMING_MARGIN};
+//! use scheduler::event::{Event, EventKind};
+//!
+//! let at = |time, id, kind| Event { time, id, kind };
+//! let events = [
+//!     at(0, 0, EventKind::Arrive { priority: 1 }),
+//!     at(0, 1, EventKind::Arrive { priority: 1 }),
+//!     at(0, 1, EventKind::Dispatch { quantum: 4 }),
+//!     at(3, 1, EventKind::Block),
+//!     at(3, 0, EventKind::Dispatch { quantum: 4 }),
+//!     at(4, 1, EventKind::Wake),
+//!     at(7, 0, EventKind::Preempt),
+//!     at(7, 1, EventKind::Dispatch { quantum: 4 }),
+//!     at(10, 1, EventKind::Block),
+//!     at(10, 0, EventKind::Dispatch { quantum: 1 }),
+//!     at(11, 0, EventKind::Preempt),
+//!     at(11, 1, EventKind::Wake),
+//!     at(11, 1, EventKind::Dispatch { quantum: 4 }),
+//!     at(14, 1, EventKind::Finish),
+//!     at(14, 0, EventKind::Dispatch { quantum: 1 }),
+//!     at(15, 0, EventKind::Finish),
+//! ];
+//! let report = gaming(&events, DEFAULT_GAMING_MARGIN);
+//! let gamer = &report.gamers[0];
+//! assert_eq!((gamer.id, gamer.slices, gamer.gamed), (1, 3, 2));
+//! let rival = &gamer.rivals[0];
+//! assert_eq!((rival.id, rival.overlap, rival.cpu, rival.honest_cpu), (0, 14, 9, 5));
+//! ```
+//!
+//! ## Waiting-Time Breakdown
+//! A job's waiting time says *that* it was slow, not why. [`wait_breakdown`]
+//! splits the time each job spent off the CPU into its causes: ready behind
+//! a process of higher, the same or lower priority (the priority the jobs
+//! arrived with, lower numbers first), held back by its group's CPU limit
+//! (the [`Throttle`](EventKind::Throttle) events), and blocked for I/O. The
+//! simulator switches for free, so context-switch overhead is charged after
+//! the fact, as `experiment rr-quantum` does: every dispatch made while a
+//! job is in the system costs it `switch_cost` ticks.
+//!
+//! ```
+//! use scheduler::analysis::wait_breakdown;
+//! use scheduler::event::{Event, EventKind};
+//!
+//! let at = |time, id, kind| Event { time, id, kind };
+//! let events = [
+//!     at(0, 0, EventKind::Arrive { priority: 1 }),
+//!     at(0, 1, EventKind::Arrive { priority: 1 }),
+//!     at(0, 2, EventKind::Arrive { priority: 0 }),
+//!     at(0, 2, EventKind::Dispatch { quantum: 2 }),
+//!     at(2, 2, EventKind::Block),
+//!     at(2, 0, EventKind::Dispatch { quantum: 2 }),
+//!     at(3, 2, EventKind::Wake),
+//!     at(4, 0, EventKind::Preempt),
+//!     at(4, 2, EventKind::Dispatch { quantum: 2 }),
+//!     at(5, 2, EventKind::Finish),
+//!     at(5, 1, EventKind::Dispatch { quantum: 0 }),
+//!     at(8, 1, EventKind::Finish),
+//!     at(8, 0, EventKind::Dispatch { quantum: 0 }),
+//!     at(9, 0, EventKind::Finish),
+//! ];
+//! let report = wait_breakdown(&events, 1);
+//! let p1 = &report.jobs[1];
+//! assert_eq!((p1.higher, p1.same, p1.lower), (3, 2, 0));
+//! assert_eq!((p1.switches, p1.total()), (4, 9));
+//! // P2 waited one tick behind P0, which kept the CPU after P2's I/O
+//! let p2 = &report.jobs[2];
+//! assert_eq!((p2.lower, p2.io, p2.cause()), (1, 1, Some("switches")));
+//! ```
+//!
+//! ## I/O-Bound and CPU-Bound Jobs
+//! The textbooks claim that MLFQ favors I/O-bound jobs: they give up the CPU
+//! before their slice runs out, so they stay at the top levels, while
+//! CPU-bound jobs sink. [`boundness`] sorts the jobs of a run with burst
+//! scripts by how much of their CPU and I/O time they spent on I/O, and
+//! compares how the two kinds fared. Turnaround includes the I/O itself, so
+//! the fair comparison is the mean *slowdown*, turnaround over the time the
+//! job needed for its CPU and I/O alone.
+//!
+//! ```
+//! use scheduler::analysis::{boundness, DEFAULT_IO_BOUND};
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::metrics::Metrics;
+//!
+//! let mut metrics = Metrics::new();
+//! let at = |time, id, kind| Event { time, id, kind };
+//! let run = |id, from, to| (from..to).map(move |time| at(time, id, EventKind::Execute));
+//! let events = [at(0, 0, EventKind::Arrive { priority: 0 }), at(0, 1, EventKind::Arrive { priority: 0 })]
+//!     .into_iter()
+//!     // job 1 computes for a tick, waits 4 on I/O, then computes another
+//!     .chain([at(0, 1, EventKind::Dispatch { quantum: 4 })])
+//!     .chain(run(1, 0, 1))
+//!     .chain([at(1, 1, EventKind::Block), at(1, 0, EventKind::Dispatch { quantum: 4 })])
+//!     .chain(run(0, 1, 5))
+//!     .chain([at(5, 1, EventKind::Wake), at(5, 0, EventKind::Preempt), at(5, 1, EventKind::Dispatch { quantum: 4 })])
+//!     .chain(run(1, 5, 6))
+//!     .chain([at(6, 1, EventKind::Finish), at(6, 0, EventKind::Dispatch { quantum: 4 })])
+//!     // job 0 only computes, for 8 ticks
+//!     .chain(run(0, 6, 10))
+//!     .chain([at(10, 0, EventKind::Finish)]);
+//! for event in events {
+//!     metrics.record(&event);
+//! }
+//! let report = boundness(&metrics.processes(), DEFAULT_IO_BOUND);
+//! assert_eq!((report.io_bound.ids.as_slice(), report.cpu_bound.ids.as_slice()), (&[1][..], &[0][..]));
+//! assert_eq!(report.io_bound.mean_slowdown, 1.0);
+//! assert_eq!(report.cpu_bound.mean_slowdown, 10.0 / 8.0);
+//! ```
+//!
+//! ## Fair Share
+//! A scheduler that shares the CPU fairly by weight, as Linux's CFS does,
+//! gives every runnable job a share of the CPU in proportion to the weight
+//! of its [nice value](crate::nice), the ideal being generalized processor
+//! sharing. [`fair_share`] replays the event log, hands every stretch of
+//! time out to the jobs runnable during it by weight, following the
+//! [`Renice`](EventKind::Renice) events, and compares what each job was
+//! entitled to with the CPU time it got. Jain's fairness index sums the
+//! comparison up: `1` when every job got the same fraction of its share,
+//! down to `1/n` when one job got everything.
+//!
+//! ```
+//! use scheduler::analysis::fair_share;
+//! use scheduler::event::{Event, EventKind};
+//!
+//! let at = |time, id, kind| Event { time, id, kind };
+//! let events = [
+//!     at(0, 0, EventKind::Arrive { priority: 0 }),
+//!     at(0, 1, EventKind::Arrive { priority: 0 }),
+//!     at(0, 1, EventKind::Renice { nice: 5 }),
+//!     at(0, 0, EventKind::Dispatch { quantum: 0 }),
+//!     at(10, 0, EventKind::Finish),
+//!     at(10, 1, EventKind::Dispatch { quantum: 0 }),
+//!     at(20, 1, EventKind::Finish),
+//! ];
+//! let report = fair_share(&events);
+//! let (p0, p1) = (&report.jobs[0], &report.jobs[1]);
+//! assert_eq!((p1.nice, p1.weight), (5, 335));
+//! // weights 1024 and 335 share the first 10 ticks, then P1 runs alone
+//! assert!((p0.entitled - 7.53).abs() < 0.01 && (p1.entitled - 12.47).abs() < 0.01);
+//! assert!((p0.ratio() - 1.33).abs() < 0.01);
+//! assert!((report.jain() - 0.94).abs() < 0.01);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::clock::TimeUnit;
+use crate::event::{Event, EventKind};
+use crate::gantt::{State, Timeline};
+use crate::metrics::ProcessMetrics;
+use crate::nice;
+
+/// How many times its own CPU time a job must wait behind a single other
+/// process to count as stuck in a convoy, unless told otherwise.
+pub const DEFAULT_CONVOY_RATIO: u64 = 4;
+
+/// A job that spent a long time waiting behind one other process.
+///
+/// # Fields
+/// - `id`: The job that waited.
+/// - `cpu`: The job's own CPU time.
+/// - `waiting`: Its total time spent ready but not running.
+/// - `blocker`: The process that ran for most of that time.
+/// - `blocker_cpu`: The blocker's total CPU time.
+/// - `blocked`: How much of `waiting` the blocker was running for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Convoy {
+    pub id: u32,
+    pub cpu: u64,
+    pub waiting: u64,
+    pub blocker: u32,
+    pub blocker_cpu: u64,
+    pub blocked: u64,
+}
+
+/// The result of [`convoys`]: every job found stuck in a convoy, by id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConvoyReport {
+    pub ratio: u64,
+    pub convoys: Vec<Convoy>,
+}
+
+impl ConvoyReport {
+    /// Renders the report as a table, with times in `unit`.
+    pub fn report(&self, unit: TimeUnit) -> String {
+        let time = |ticks: u64| unit.format_ns(ticks * unit.nanos());
+        if self.convoys.is_empty() {
+            return format!("convoys: none (no job waited over {}x its CPU time behind one process)\n", self.ratio);
+        }
+        let mut out = format!("convoys (jobs that waited over {}x their CPU time behind one process):\n", self.ratio);
+        out += &format!(
+            "{:>6} {:>10} {:>10} {:>8} {:>10} {:>10} {:>6}\n",
+            "id", "cpu", "waiting", "behind", "its cpu", "blocked", "share"
+        );
+        for c in &self.convoys {
+            out += &format!(
+                "{:>6} {:>10} {:>10} {:>8} {:>10} {:>10} {:>5.0}%\n",
+                c.id,
+                time(c.cpu),
+                time(c.waiting),
+                format!("P{}", c.blocker),
+                time(c.blocker_cpu),
+                time(c.blocked),
+                c.blocked as f64 * 100.0 / c.waiting as f64
+            );
+        }
+        out
+    }
+}
+
+/// Finds the jobs that waited at least `ratio` times their own CPU time
+/// behind a single other process.
+///
+/// The events must be in the order they happened, as kept by a
+/// [`Recorder`](crate::sink::Recorder). Waiting time while the CPU is idle is
+/// not charged to anyone.
+pub fn convoys(events: &[Event], ratio: u64) -> ConvoyReport {
+    let mut ready = BTreeSet::new();
+    let mut running: Option<(u32, u64)> = None;
+    let mut cpu: BTreeMap<u32, u64> = BTreeMap::new();
+    let mut waiting: BTreeMap<u32, u64> = BTreeMap::new();
+    let mut blame: BTreeMap<u32, BTreeMap<u32, u64>> = BTreeMap::new();
+    let mut last = 0;
+
+    for event in events {
+        let delta = event.time.saturating_sub(last);
+        last = event.time;
+        for &id in &ready {
+            *waiting.entry(id).or_default() += delta;
+            if let Some((runner, _)) = running {
+                *blame.entry(id).or_default().entry(runner).or_default() += delta;
+            }
+        }
+        match event.kind {
+            EventKind::Arrive { .. } => {
+                ready.insert(event.id);
+            }
+            EventKind::Dispatch { .. } => {
+                ready.remove(&event.id);
+                running = Some((event.id, event.time));
+            }
+            EventKind::Preempt | EventKind::Block | EventKind::Finish | EventKind::Timeout => {
+                if let Some((id, since)) = running.take_if(|(id, _)| *id == event.id) {
+                    *cpu.entry(id).or_default() += event.time - since;
+                }
+                match event.kind {
+                    EventKind::Preempt => ready.insert(event.id),
+                    EventKind::Timeout => ready.remove(&event.id),
+                    _ => false,
+                };
+            }
+            EventKind::Wake | EventKind::Unthrottle => {
+                ready.insert(event.id);
+            }
+            //time held back by a CPU limit is no other process's fault
+            EventKind::Throttle => {
+                ready.remove(&event.id);
+            }
+            EventKind::Execute | EventKind::Idle | EventKind::Renice { .. } => {}
+        }
+    }
+
+    let mut report = ConvoyReport { ratio, convoys: Vec::new() };
+    for (&id, blockers) in &blame {
+        let Some((&blocker, &blocked)) = blockers.iter().max_by_key(|(_, ticks)| **ticks) else {
+            continue;
+        };
+        let own = cpu.get(&id).copied().unwrap_or(0);
+        if blocked > 0 && blocked >= ratio.saturating_mul(own) {
+            report.convoys.push(Convoy {
+                id,
+                cpu: own,
+                waiting: waiting[&id],
+                blocker,
+                blocker_cpu: cpu.get(&blocker).copied().unwrap_or(0),
+                blocked,
+            });
+        }
+    }
+    report
+}
+
+/// How many ticks of its time slice a job may leave unused when it blocks
+/// and still count as gaming the scheduler, unless told otherwise.
+pub const DEFAULT_GAMING_MARGIN: u64 = 1;
+
+/// How a gaming job fared against one honest job it shared the system with.
+///
+/// # Fields
+/// - `id`: The honest job.
+/// - `overlap`: How long both jobs were in the system together.
+/// - `cpu`: The gaming job's CPU time during the overlap.
+/// - `honest_cpu`: The honest job's CPU time during the overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rival {
+    pub id: u32,
+    pub overlap: u64,
+    pub cpu: u64,
+    pub honest_cpu: u64,
+}
+
+/// A job that kept blocking just before its time slice ran out.
+///
+/// # Fields
+/// - `id`: The gaming job.
+/// - `slices`: How many times it was dispatched with a time slice.
+/// - `gamed`: How many of those slices it ended by blocking with at most the
+///   margin left.
+/// - `rivals`: Every honest job it shared the system with, by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gamer {
+    pub id: u32,
+    pub slices: u64,
+    pub gamed: u64,
+    pub rivals: Vec<Rival>,
+}
+
+/// The result of [`gaming`]: every job found gaming the scheduler, by id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GamingReport {
+    pub margin: u64,
+    pub gamers: Vec<Gamer>,
+}
+
+impl GamingReport {
+    /// Renders the report as a table, with times in `unit`.
+    pub fn report(&self, unit: TimeUnit) -> String {
+        let time = |ticks: u64| unit.format_ns(ticks * unit.nanos());
+        let left = format!("{} tick{}", self.margin, if self.margin == 1 { "" } else { "s" });
+        if self.gamers.is_empty() {
+            return format!("gaming: none (no job blocked with at most {} of its slice left in most slices)\n", left);
+        }
+        let mut out = format!("gaming (jobs that blocked with at most {} of their slice left in most slices):\n", left);
+        out += &format!(
+            "{:>6} {:>10} {:>8} {:>10} {:>10} {:>10} {:>7}\n",
+            "id", "gamed", "vs", "overlap", "cpu", "its cpu", "extra"
+        );
+        for gamer in &self.gamers {
+            for rival in &gamer.rivals {
+                let extra = match rival.honest_cpu {
+                    0 => "-".to_string(),
+                    honest => format!("{:.1}x", rival.cpu as f64 / honest as f64),
+                };
+                out += &format!(
+                    "{:>6} {:>10} {:>8} {:>10} {:>10} {:>10} {:>7}\n",
+                    gamer.id,
+                    format!("{}/{}", gamer.gamed, gamer.slices),
+                    format!("P{}", rival.id),
+                    time(rival.overlap),
+                    time(rival.cpu),
+                    time(rival.honest_cpu),
+                    extra
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Finds the jobs that, in at least half of their time slices and at least
+/// twice, blocked with no more than `margin` ticks of the slice left, and
+/// compares their CPU time with that of the other jobs.
+///
+/// The events must be in the order they happened, as kept by a
+/// [`Recorder`](crate::sink::Recorder). Dispatches without a time slice
+/// (quantum `0`) cannot be gamed and are not counted.
+pub fn gaming(events: &[Event], margin: u64) -> GamingReport {
+    // slices and gamed slices, by job
+    let mut slices: BTreeMap<u32, (u64, u64)> = BTreeMap::new();
+    let mut running: Option<(u32, u64, u64)> = None;
+    for event in events {
+        match event.kind {
+            EventKind::Dispatch { quantum } => {
+                running = Some((event.id, event.time, quantum as u64));
+                if quantum > 0 {
+                    slices.entry(event.id).or_default().0 += 1;
+                }
+            }
+            EventKind::Preempt | EventKind::Block | EventKind::Finish => {
+                if let Some((id, since, quantum)) = running.take_if(|(id, ..)| *id == event.id) {
+                    let ran = event.time - since;
+                    if event.kind == EventKind::Block && ran < quantum && quantum - ran <= margin {
+                        slices.entry(id).or_default().1 += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let timeline = Timeline::from_events("", events);
+    let lifetime = |id: u32| {
+        let spans = timeline.processes.get(&id)?;
+        Some((spans.first()?.start, spans.last()?.end))
+    };
+    let cpu = |id: u32, from: u64, until: u64| -> u64 {
+        timeline.processes[&id]
+            .iter()
+            .filter(|span| span.state == State::Running)
+            .map(|span| span.end.min(until).saturating_sub(span.start.max(from)))
+            .sum()
+    };
+    let gaming: BTreeSet<u32> = slices
+        .iter()
+        .filter(|(_, (slices, gamed))| *gamed >= 2 && gamed * 2 >= *slices)
+        .map(|(&id, _)| id)
+        .collect();
+
+    let mut report = GamingReport { margin, gamers: Vec::new() };
+    for &id in &gaming {
+        let Some((start, end)) = lifetime(id) else {
+            continue;
+        };
+        let rivals = timeline
+            .processes
+            .keys()
+            .filter(|other| !gaming.contains(other))
+            .filter_map(|&other| {
+                let (other_start, other_end) = lifetime(other)?;
+                let (from, until) = (start.max(other_start), end.min(other_end));
+                (until > from).then(|| Rival {
+                    id: other,
+                    overlap: until - from,
+                    cpu: cpu(id, from, until),
+                    honest_cpu: cpu(other, from, until),
+                })
+            })
+            .collect();
+        let (slices, gamed) = slices[&id];
+        report.gamers.push(Gamer { id, slices, gamed, rivals });
+    }
+    report
+}
+
+/// The share of its CPU and I/O time, in percent, a job must spend on I/O
+/// to count as I/O-bound, unless told otherwise.
+pub const DEFAULT_IO_BOUND: u64 = 50;
+
+/// How the jobs of one kind fared, see [`boundness`]. Means are taken over
+/// the kind's completed jobs.
+///
+/// # Fields
+/// - `ids`: The jobs of this kind.
+/// - `completed`: How many of them finished.
+/// - `mean_turnaround`, `mean_waiting`, `mean_response`: As in the
+///   [`Aggregates`](crate::metrics::Aggregates).
+/// - `mean_slowdown`: Mean of each job's turnaround over its CPU and I/O
+///   time; `1` means it never waited.
+/// - `interactions`: I/O completions of the kind's jobs.
+/// - `mean_interaction_response`: Mean time from an I/O completion to the
+///   next dispatch, over all of those interactions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoundClass {
+    pub ids: Vec<u32>,
+    pub completed: usize,
+    pub mean_turnaround: f64,
+    pub mean_waiting: f64,
+    pub mean_response: f64,
+    pub mean_slowdown: f64,
+    pub interactions: u64,
+    pub mean_interaction_response: f64,
+}
+
+impl BoundClass {
+    fn new(processes: &[&ProcessMetrics]) -> Self {
+        let completed: Vec<&&ProcessMetrics> = processes.iter().filter(|p| p.finish.is_some()).collect();
+        let mean = |value: &dyn Fn(&ProcessMetrics) -> f64| match completed.len() {
+            0 => 0.0,
+            n => completed.iter().map(|p| value(p)).sum::<f64>() / n as f64,
+        };
+        let interactions: u64 = processes.iter().map(|p| p.interactions as u64).sum();
+        let responses: f64 =
+            processes.iter().filter_map(|p| Some(p.mean_interaction_response? * p.interactions as f64)).sum();
+        Self {
+            ids: processes.iter().map(|p| p.id).collect(),
+            completed: completed.len(),
+            mean_turnaround: mean(&|p| p.turnaround.unwrap_or(0) as f64),
+            mean_waiting: mean(&|p| p.waiting.unwrap_or(0) as f64),
+            mean_response: mean(&|p| p.response.unwrap_or(0) as f64),
+            mean_slowdown: mean(&|p| p.turnaround.unwrap_or(0) as f64 / (p.cpu + p.io).max(1) as f64),
+            interactions,
+            mean_interaction_response: if interactions == 0 { 0.0 } else { responses / interactions as f64 },
+        }
+    }
+}
+
+/// The result of [`boundness`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoundReport {
+    pub percent: u64,
+    pub io_bound: BoundClass,
+    pub cpu_bound: BoundClass,
+}
+
+impl BoundReport {
+    /// Renders the report as a table, with times in `unit`, and says which
+    /// kind of job fared better.
+    pub fn report(&self, unit: TimeUnit) -> String {
+        let mean = |ticks: f64| unit.format_ns((ticks * unit.nanos() as f64).round() as u64);
+        if self.io_bound.ids.is_empty() {
+            return format!("I/O-bound jobs: none (no job spent {}% of its time on I/O)\n", self.percent);
+        }
+        let mut out = format!("I/O-bound vs CPU-bound jobs (I/O-bound: at least {}% of CPU and I/O time on I/O):\n", self.percent);
+        out += &format!(
+            "{:>9} {:>9} {:>10} {:>10} {:>10} {:>9} {:>12}\n",
+            "kind", "completed", "turnaround", "waiting", "response", "slowdown", "interaction"
+        );
+        for (kind, class) in [("I/O-bound", &self.io_bound), ("CPU-bound", &self.cpu_bound)] {
+            let interaction = match class.interactions {
+                0 => "-".to_string(),
+                _ => mean(class.mean_interaction_response),
+            };
+            out += &format!(
+                "{:>9} {:>9} {:>10} {:>10} {:>10} {:>8.2}x {:>12}\n",
+                kind,
+                format!("{}/{}", class.completed, class.ids.len()),
+                mean(class.mean_turnaround),
+                mean(class.mean_waiting),
+                mean(class.mean_response),
+                class.mean_slowdown,
+                interaction
+            );
+        }
+        let (io, cpu) = (self.io_bound.mean_slowdown, self.cpu_bound.mean_slowdown);
+        out += &match (self.io_bound.completed, self.cpu_bound.completed) {
+            (0, _) | (_, 0) => "not enough completed jobs of both kinds to compare\n".to_string(),
+            _ if io < cpu => format!("I/O-bound jobs fared better: mean slowdown {:.2}x vs {:.2}x\n", io, cpu),
+            _ if io > cpu => format!("CPU-bound jobs fared better: mean slowdown {:.2}x vs {:.2}x\n", cpu, io),
+            _ => format!("both kinds fared the same: mean slowdown {:.2}x\n", io),
+        };
+        out
+    }
+}
+
+/// Sorts the `processes` into those that spent at least `percent` of their
+/// CPU and I/O time on I/O and the rest, and measures how each kind fared.
+pub fn boundness(processes: &[ProcessMetrics], percent: u64) -> BoundReport {
+    let (io_bound, cpu_bound): (Vec<&ProcessMetrics>, Vec<&ProcessMetrics>) =
+        processes.iter().partition(|p| p.io > 0 && p.io * 100 >= percent * (p.cpu + p.io));
+    BoundReport { percent, io_bound: BoundClass::new(&io_bound), cpu_bound: BoundClass::new(&cpu_bound) }
+}
+
+/// Where the time one job spent off the CPU went, in ticks; see
+/// [`wait_breakdown`].
+///
+/// # Fields
+/// - `id`: The job.
+/// - `priority`: The priority it arrived with.
+/// - `higher`, `same`, `lower`: Time it was ready while a process of higher,
+///   the same or lower priority ran.
+/// - `throttled`: Time its group's CPU limit kept it off the CPU.
+/// - `io`: Time it was blocked for I/O.
+/// - `switches`: Dispatches made while it was in the system, each costing
+///   it the report's `switch_cost`.
+/// - `switch_cost`: The cost of a dispatch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WaitBreakdown {
+    pub id: u32,
+    pub priority: u32,
+    pub higher: u64,
+    pub same: u64,
+    pub lower: u64,
+    pub throttled: u64,
+    pub io: u64,
+    pub switches: u64,
+    pub switch_cost: u64,
+}
+
+impl WaitBreakdown {
+    /// The time spent on context switches.
+    pub fn switching(&self) -> u64 {
+        self.switches * self.switch_cost
+    }
+
+    /// All of the time the job spent off the CPU.
+    pub fn total(&self) -> u64 {
+        self.higher + self.same + self.lower + self.throttled + self.io + self.switching()
+    }
+
+    /// The component the job lost the most time to, `None` if it never
+    /// waited. Ties go to the first in the order of the report's columns.
+    pub fn cause(&self) -> Option<&'static str> {
+        let components = [
+            ("higher", self.higher),
+            ("same", self.same),
+            ("lower", self.lower),
+            ("throttled", self.throttled),
+            ("I/O", self.io),
+            ("switches", self.switching()),
+        ];
+        let (name, ticks) = components.into_iter().rev().max_by_key(|(_, ticks)| *ticks)?;
+        (ticks > 0).then_some(name)
+    }
+}
+
+/// The result of [`wait_breakdown`]: every job that arrived, by id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WaitReport {
+    pub switch_cost: u64,
+    pub jobs: Vec<WaitBreakdown>,
+}
+
+impl WaitReport {
+    /// Renders the report as a table, with times in `unit`, and a last row
+    /// with the totals over all jobs.
+    pub fn report(&self, unit: TimeUnit) -> String {
+        let time = |ticks: u64| unit.format_ns(ticks * unit.nanos());
+        let mut out = format!(
+            "waiting time breakdown (ready behind higher, same or lower priority, throttled, blocked for I/O, and context switches at {}):\n",
+            time(self.switch_cost)
+        );
+        out += &format!(
+            "{:>6} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+            "id", "total", "higher", "same", "lower", "throttled", "I/O", "switches", "mostly"
+        );
+        let all = self.jobs.iter().fold(WaitBreakdown { switch_cost: self.switch_cost, ..Default::default() }, |all, job| {
+            WaitBreakdown {
+                higher: all.higher + job.higher,
+                same: all.same + job.same,
+                lower: all.lower + job.lower,
+                throttled: all.throttled + job.throttled,
+                io: all.io + job.io,
+                switches: all.switches + job.switches,
+                ..all
+            }
+        });
+        for (id, job) in self.jobs.iter().map(|job| (job.id.to_string(), job)).chain([("all".to_string(), &all)]) {
+            out += &format!(
+                "{:>6} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+                id,
+                time(job.total()),
+                time(job.higher),
+                time(job.same),
+                time(job.lower),
+                time(job.throttled),
+                time(job.io),
+                time(job.switching()),
+                job.cause().unwrap_or("-")
+            );
+        }
+        out
+    }
+}
+
+/// Splits the time every job spent off the CPU into its causes, charging
+/// each dispatch `switch_cost` ticks to every job in the system.
+///
+/// The events must be in the order they happened, as kept by a
+/// [`Recorder`](crate::sink::Recorder). Time a job is ready while the CPU is
+/// idle is not charged to any component.
+pub fn wait_breakdown(events: &[Event], switch_cost: u64) -> WaitReport {
+    let mut jobs: BTreeMap<u32, WaitBreakdown> = BTreeMap::new();
+    let (mut ready, mut throttled, mut blocked, mut done) =
+        (BTreeSet::new(), BTreeSet::new(), BTreeSet::new(), BTreeSet::new());
+    let mut running: Option<u32> = None;
+    let mut last = 0;
+
+    for event in events {
+        let delta = event.time.saturating_sub(last);
+        last = event.time;
+        let runner = running.and_then(|id| jobs.get(&id)).map(|job| job.priority);
+        for (&id, job) in jobs.iter_mut() {
+            match runner {
+                Some(priority) if ready.contains(&id) => match priority.cmp(&job.priority) {
+                    std::cmp::Ordering::Less => job.higher += delta,
+                    std::cmp::Ordering::Equal => job.same += delta,
+                    std::cmp::Ordering::Greater => job.lower += delta,
+                },
+                _ if throttled.contains(&id) => job.throttled += delta,
+                _ if blocked.contains(&id) => job.io += delta,
+                _ => {}
+            }
+        }
+        match event.kind {
+            EventKind::Arrive { priority } => {
+                jobs.entry(event.id).or_insert(WaitBreakdown { id: event.id, priority, switch_cost, ..Default::default() });
+                ready.insert(event.id);
+            }
+            EventKind::Dispatch { .. } => {
+                ready.remove(&event.id);
+                running = Some(event.id);
+                for job in jobs.values_mut().filter(|job| !done.contains(&job.id)) {
+                    job.switches += 1;
+                }
+            }
+            EventKind::Preempt | EventKind::Block | EventKind::Finish => {
+                running.take_if(|id| *id == event.id);
+                match event.kind {
+                    EventKind::Preempt => ready.insert(event.id),
+                    EventKind::Block => blocked.insert(event.id),
+                    _ => done.insert(event.id),
+                };
+            }
+            //a job that timed out was taken from wherever it was
+            EventKind::Timeout => {
+                running.take_if(|id| *id == event.id);
+                ready.remove(&event.id);
+                blocked.remove(&event.id);
+                throttled.remove(&event.id);
+                done.insert(event.id);
+            }
+            EventKind::Wake => {
+                blocked.remove(&event.id);
+                ready.insert(event.id);
+            }
+            EventKind::Throttle => {
+                ready.remove(&event.id);
+                throttled.insert(event.id);
+            }
+            EventKind::Unthrottle => {
+                throttled.remove(&event.id);
+                ready.insert(event.id);
+            }
+            EventKind::Execute | EventKind::Idle | EventKind::Renice { .. } => {}
+        }
+    }
+    WaitReport { switch_cost, jobs: jobs.into_values().collect() }
+}
+
+/// The CPU time one job got against its fair share, see [`fair_share`].
+///
+/// # Fields
+/// - `id`: The job.
+/// - `nice`: Its nice value at the end of the run.
+/// - `weight`: The weight of that nice value.
+/// - `cpu`: The CPU time it got, in ticks.
+/// - `entitled`: Its share, by weight, of the time it was runnable, in
+///   ticks.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FairShare {
+    pub id: u32,
+    pub nice: i8,
+    pub weight: u32,
+    pub cpu: u64,
+    pub entitled: f64,
+}
+
+impl FairShare {
+    /// The CPU time the job got over its share: above `1` it got more than
+    /// its share, below `1` less. A job that was never runnable has a
+    /// ratio of `1`.
+    pub fn ratio(&self) -> f64 {
+        match self.entitled > 0.0 {
+            true => self.cpu as f64 / self.entitled,
+            false => 1.0,
+        }
+    }
+}
+
+/// The result of [`fair_share`]: every job that arrived, by id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FairShareReport {
+    pub jobs: Vec<FairShare>,
+}
+
+impl FairShareReport {
+    /// Jain's fairness index over the jobs' [ratios](FairShare::ratio),
+    /// `1` when they are all the same and `1` without jobs.
+    pub fn jain(&self) -> f64 {
+        let sum: f64 = self.jobs.iter().map(FairShare::ratio).sum();
+        let squares: f64 = self.jobs.iter().map(|job| job.ratio() * job.ratio()).sum();
+        match squares > 0.0 {
+            true => sum * sum / (self.jobs.len() as f64 * squares),
+            false => 1.0,
+        }
+    }
+
+    /// Renders the report as a table, with times in `unit`, followed by
+    /// Jain's fairness index.
+    pub fn report(&self, unit: TimeUnit) -> String {
+        let time = |ticks: f64| unit.format_ns((ticks * unit.nanos() as f64).round() as u64);
+        let mut out = "fair share (CPU time against each job's share, by weight, of the time it was runnable):\n".to_string();
+        out += &format!("{:>6} {:>5} {:>7} {:>10} {:>10} {:>7}\n", "id", "nice", "weight", "cpu", "share", "ratio");
+        for job in &self.jobs {
+            out += &format!(
+                "{:>6} {:>5} {:>7} {:>10} {:>10} {:>6.2}x\n",
+                job.id,
+                job.nice,
+                job.weight,
+                time(job.cpu as f64),
+                time(job.entitled),
+                job.ratio()
+            );
+        }
+        out += &format!("Jain's fairness index: {:.3} (1 is perfectly fair)\n", self.jain());
+        out
+    }
+}
+
+/// Compares the CPU time every job got with its weighted fair share of the
+/// time it was ready or running, see [Fair Share](crate::analysis#fair-share).
+///
+/// The events must be in the order they happened, as kept by a
+/// [`Recorder`](crate::sink::Recorder). Jobs start at nice `0` until a
+/// [`Renice`](EventKind::Renice) event says otherwise; blocked and
+/// throttled jobs are not runnable.
+pub fn fair_share(events: &[Event]) -> FairShareReport {
+    let mut jobs: BTreeMap<u32, FairShare> = BTreeMap::new();
+    let mut runnable: BTreeSet<u32> = BTreeSet::new();
+    let mut running: Option<u32> = None;
+    let mut last = 0;
+
+    for event in events {
+        let delta = event.time.saturating_sub(last);
+        last = event.time;
+        let total: u64 = runnable.iter().map(|id| jobs[id].weight as u64).sum();
+        for id in &runnable {
+            let job = jobs.get_mut(id).expect("runnable jobs have arrived");
+            job.entitled += delta as f64 * job.weight as f64 / total as f64;
+        }
+        if let Some(job) = running.and_then(|id| jobs.get_mut(&id)) {
+            job.cpu += delta;
+        }
+        match event.kind {
+            EventKind::Arrive { .. } => {
+                let weight = nice::weight(0);
+                jobs.entry(event.id).or_insert(FairShare { id: event.id, weight, ..Default::default() });
+                runnable.insert(event.id);
+            }
+            EventKind::Dispatch { .. } => running = Some(event.id),
+            EventKind::Preempt => {
+                running.take_if(|id| *id == event.id);
+            }
+            EventKind::Block | EventKind::Finish | EventKind::Timeout | EventKind::Throttle => {
+                running.take_if(|id| *id == event.id);
+                runnable.remove(&event.id);
+            }
+            EventKind::Wake | EventKind::Unthrottle => {
+                runnable.insert(event.id);
+            }
+            EventKind::Renice { nice } => {
+                if let Some(job) = jobs.get_mut(&event.id) {
+                    (job.nice, job.weight) = (nice, nice::weight(nice));
+                }
+            }
+            EventKind::Execute | EventKind::Idle => {}
+        }
+    }
+    FairShareReport { jobs: jobs.into_values().collect() }
+}
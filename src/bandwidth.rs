@@ -0,0 +1,248 @@
+//! # Bandwidth Module
+//!
+//! CPU bandwidth control in the style of Linux's CFS bandwidth control, the
+//! `cpu.max` file of a cgroup, which is how containers get CPU limits.
+//!
+//! A group of processes may use at most `quota` ticks of CPU time in every
+//! `period` ticks, with periods starting at tick `0`. Once the group has used
+//! up its quota it is *throttled*: the process running is preempted, and no
+//! process of the group runs again, whatever the scheduler picks, until the
+//! next period refills the quota. The scheduler only sees throttled
+//! processes again once they are runnable, in the order they were
+//! throttled. The event log shows when: a process held off the CPU gets an
+//! [`EventKind::Throttle`] event, and an [`EventKind::Unthrottle`] event
+//! once it goes back to the scheduler.
+//!
+//! The simulator has no cgroups; the groups are job classes, the class
+//! column of the input (see [`Metrics::note_class`]). A class without a
+//! limit is never throttled. Throttling is part of the
+//! [`Simulation`] engine, so it works with every scheduler, and
+//! [`Bandwidth::stats`] reports per group what `cpu.stat` does; the run
+//! summary shows them once they are noted with
+//! [`Recorder::note_throttling`](crate::sink::Recorder::note_throttling).
+//!
+//! [`Metrics::note_class`]: crate::metrics::Metrics::note_class
+//! [`EventKind::Throttle`]: crate::event::EventKind::Throttle
+//! [`EventKind::Unthrottle`]: crate::event::EventKind::Unthrottle
+//! [`Simulation`]: crate::simulation::Simulation
+//!
+//! ## Example
+//! ```
+//! use std::collections::VecDeque;
+//! use std::time::Duration;
+//! use scheduler::bandwidth::{Bandwidth, CpuMax, GroupStats};
+//! use scheduler::event::EventKind;
+//! use scheduler::simulation::{Job, Simulation};
+//! use scheduler::sink::Recorder;
+//! use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+//!
+//! struct Fifo(VecDeque<PCB>);
+//! impl Schedule for Fifo {
+//!     fn add_process(&mut self, p: PCB) -> bool { self.0.push_back(p); true }
+//!     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop_front(), TimeSlice::Infinite) }
+//!     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! }
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let mut out = Recorder::new();
+//! out.note_class(0, "container".to_string());
+//! // 2 ticks of CPU every 5 ticks, like `echo "2 5" > cpu.max`
+//! let limits = Bandwidth::new().limit("container", "2 5".parse().unwrap());
+//! let mut sim = Simulation::builder(Fifo(VecDeque::new())).job(Job::new(0, 0, 5)).bandwidth(limits).build();
+//! sim.run(&mut out).unwrap();
+//!
+//! // P0 runs 0-2, 5-7 and 10-11, and sits out 2-5 and 7-10
+//! assert_eq!(out.metrics().process(0).unwrap().finish, Some(11));
+//! let throttled: Vec<u64> = out.events().iter().filter(|e| e.kind == EventKind::Throttle).map(|e| e.time).collect();
+//! assert_eq!(throttled, [2, 7]);
+//! let max = CpuMax { quota: Some(2), period: 5 };
+//! let stats = GroupStats { group: "container".to_string(), max, periods: 3, throttled: 2, throttled_ticks: 6 };
+//! assert_eq!(sim.bandwidth().stats(), [stats]);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::PCB;
+
+/// A group's CPU limit, as in a `cpu.max` file.
+///
+/// # Fields
+/// - `quota`: CPU time the group may use per period, in ticks, `None` for
+///   no limit (`max`).
+/// - `period`: Length of a period, in ticks.
+///
+/// Parsed from `cpu.max`'s `"<quota> <period>"`, e.g. `"20 100"` or
+/// `"max 100"`, or with a slash instead of the space, `"20/100"`, which is
+/// easier to write on a command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuMax {
+    pub quota: Option<u64>,
+    pub period: u64,
+}
+
+impl FromStr for CpuMax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let Some((quota, period)) = s.trim().split_once(['/', ' ']) else {
+            return Err(format!("'{}': expected <quota> <period>, e.g. 20/100", s));
+        };
+        let period = match period.trim().parse::<u64>() {
+            Ok(0) | Err(_) => return Err(format!("'{}': the period must be a positive number of ticks", s)),
+            Ok(period) => period,
+        };
+        let quota = match quota {
+            "max" => None,
+            quota => match quota.parse::<u64>() {
+                Ok(0) | Err(_) => return Err(format!("'{}': the quota must be a positive number of ticks or max", s)),
+                Ok(quota) => Some(quota),
+            },
+        };
+        Ok(Self { quota, period })
+    }
+}
+
+impl fmt::Display for CpuMax {
+    /// Formats the limit the way `cpu.max` shows it, e.g. `20 100`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.quota {
+            Some(quota) => write!(f, "{} {}", quota, self.period),
+            None => write!(f, "max {}", self.period),
+        }
+    }
+}
+
+/// How a group fared under its limit, as in `cpu.stat`.
+///
+/// # Fields
+/// - `group`: The group, a job class.
+/// - `max`: The group's limit.
+/// - `periods`: Periods in which the group ran (`nr_periods`).
+/// - `throttled`: Periods in which the group was throttled while it had a
+///   process that wanted to run (`nr_throttled`).
+/// - `throttled_ticks`: Total time the group spent throttled in those
+///   periods, in ticks (`throttled_usec`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupStats {
+    pub group: String,
+    pub max: CpuMax,
+    pub periods: u64,
+    pub throttled: u64,
+    pub throttled_ticks: u64,
+}
+
+/// A group's limit and where it stands in the current period.
+#[derive(Debug, Clone)]
+struct Group {
+    max: CpuMax,
+    /// CPU time used in the current period
+    used: u64,
+    /// the tick the group was throttled at, while it is
+    since: Option<u64>,
+    /// the group's processes that are waiting for the quota to refill
+    held: Vec<PCB>,
+    stats: GroupStats,
+}
+
+/// The CPU limits of every group, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct Bandwidth {
+    groups: BTreeMap<String, Group>,
+}
+
+impl Bandwidth {
+    /// No limits at all.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits `group` to `max`, replacing any limit it had.
+    pub fn limit(mut self, group: impl Into<String>, max: CpuMax) -> Self {
+        let group = group.into();
+        let stats = GroupStats { group: group.clone(), max, periods: 0, throttled: 0, throttled_ticks: 0 };
+        self.groups.insert(group, Group { max, used: 0, since: None, held: Vec::new(), stats });
+        self
+    }
+
+    /// Whether no group is limited.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The limit of `group`, if it has one.
+    pub fn max(&self, group: &str) -> Option<CpuMax> {
+        self.groups.get(group).map(|group| group.max)
+    }
+
+    /// Whether `group` is throttled.
+    pub fn is_throttled(&self, group: &str) -> bool {
+        self.groups.get(group).is_some_and(|group| group.since.is_some())
+    }
+
+    /// Per-group statistics, up to tick `now`; a group that is still
+    /// throttled counts as throttled until then.
+    pub fn stats_at(&self, now: u64) -> Vec<GroupStats> {
+        self.groups
+            .values()
+            .map(|group| {
+                let mut stats = group.stats.clone();
+                if let Some(since) = group.since.filter(|&since| since < now && !group.held.is_empty()) {
+                    stats.throttled += 1;
+                    stats.throttled_ticks += now - since;
+                }
+                stats
+            })
+            .collect()
+    }
+
+    /// Per-group statistics, up to the current tick of the
+    /// [`CLOCK`](crate::CLOCK).
+    pub fn stats(&self) -> Vec<GroupStats> {
+        self.stats_at(crate::CLOCK.now_ticks())
+    }
+
+    /// Charges one tick of CPU time, ending at tick `now`, to `group`, and
+    /// throttles it if that used up its quota.
+    pub(crate) fn charge(&mut self, group: &str, now: u64) {
+        let Some(group) = self.groups.get_mut(group) else {
+            return;
+        };
+        if group.used == 0 {
+            group.stats.periods += 1;
+        }
+        group.used += 1;
+        if group.max.quota.is_some_and(|quota| group.used >= quota) {
+            group.since = Some(now);
+        }
+    }
+
+    /// Keeps `process` of the throttled `group` off the CPU until the quota
+    /// is refilled.
+    pub(crate) fn hold(&mut self, group: &str, process: PCB) {
+        if let Some(group) = self.groups.get_mut(group) {
+            group.held.push(process);
+        }
+    }
+
+    /// Whether any process is waiting for its group's quota to refill.
+    pub(crate) fn holds_any(&self) -> bool {
+        self.groups.values().any(|group| !group.held.is_empty())
+    }
+
+    /// Starts a new period for every group whose period starts at tick
+    /// `now`, returning the processes that may run again.
+    pub(crate) fn refill(&mut self, now: u64) -> Vec<PCB> {
+        let mut released = Vec::new();
+        for group in self.groups.values_mut().filter(|group| now.is_multiple_of(group.max.period)) {
+            if let Some(since) = group.since.take().filter(|&since| since < now && !group.held.is_empty()) {
+                group.stats.throttled += 1;
+                group.stats.throttled_ticks += now - since;
+            }
+            group.used = 0;
+            released.append(&mut group.held);
+        }
+        released
+    }
+}
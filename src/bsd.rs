@@ -0,0 +1,212 @@
+//! # BSD Module
+//!
+//! A decay-usage scheduler in the style of 4.3BSD Unix, the historical
+//! alternative to moving processes between the queues of an MLFQ.
+//!
+//! There are no fixed levels. Every process has a dynamic priority computed
+//! from how much CPU time it used recently and from its nice value, and the
+//! ready process with the lowest number runs next:
+//! ```text
+//! priority = PUSER + estcpu / 4 + 2 * nice
+//! ```
+//! `estcpu` grows by one for every tick the process runs. Once every
+//! simulated second ([`BsdConfig::second`]) it decays for every process,
+//! ```text
+//! estcpu = estcpu * (2 * load) / (2 * load + 1) + nice
+//! ```
+//! where `load` is the number of processes ready or running, so the more
+//! processes compete, the longer past usage is remembered. A CPU hog sinks
+//! and a process that waits rises again without ever being moved between
+//! queues explicitly. Processes of equal priority take turns, each running
+//! for at most [`BsdConfig::quantum`] ticks.
+//!
+//! The nice value is the job's priority column, `0` by default; a higher
+//! value means a nicer, lower-priority process. 4.3BSD decayed with the
+//! one-minute load average, while here `load` is the exact load at the
+//! moment of the decay.
+//!
+//! `--strict` compares processes by the priority they were added with, here
+//! their nice value, so it reports the dispatches in which a process that
+//! used less CPU overtakes one that was added before it at the same tick.
+//! Those are deliberate.
+//!
+//! ## Example
+//! ```
+//! use std::time::Duration;
+//! use scheduler::bsd::BsdSchedule;
+//! use scheduler::config::BsdConfig;
+//! use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! CLOCK.set_now(Duration::ZERO);
+//! let mut sched = BsdSchedule::with_config(BsdConfig { quantum: 10, second: 100 });
+//! let pcb = |id| PCB { id, priority: 0, time_added: None, time_scheduled: None };
+//! sched.add_process(pcb(0));
+//! sched.add_process(pcb(1));
+//!
+//! // P0 uses its whole slice and comes back behind P1
+//! let (hog, quantum) = sched.next_process();
+//! assert_eq!((hog.unwrap().id, quantum), (0, TimeSlice::Ticks(10)));
+//! CLOCK.advance(Duration::from_nanos(10));
+//! sched.add_process(hog.unwrap());
+//! assert_eq!((sched.estcpu(0), sched.priority(0)), (10, 52));
+//! assert_eq!(sched.next_process().0.unwrap().id, 1);
+//!
+//! // a second later P0's usage has decayed by 2 * load / (2 * load + 1)
+//! CLOCK.set_now(Duration::from_nanos(100));
+//! assert_eq!(sched.next_process().0.unwrap().id, 0);
+//! assert_eq!(sched.estcpu(0), 10 * 4 / 5);
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::config::BsdConfig;
+use crate::{CLOCK, Complexity, PCB, Schedule, SchedulerInfo, TimeSlice};
+
+/// The best priority a user process can have.
+pub const PUSER: u32 = 50;
+
+/// The worst priority a process can sink to.
+pub const MAXPRI: u32 = 127;
+
+/// A decay-usage scheduler, see the [module documentation](self).
+pub struct BsdSchedule {
+    config: BsdConfig,
+    /// ready processes, by priority and then the order they were added in
+    queue: BTreeMap<(u32, u64), PCB>,
+    /// estcpu and nice value of every process that has some usage left
+    usage: BTreeMap<u32, (u64, u32)>,
+    /// the process last dispatched, and the tick its CPU time is charged up to
+    running: Option<(u32, u64)>,
+    added: u64,
+    /// simulated seconds decayed so far
+    seconds: u64,
+}
+
+impl BsdSchedule {
+    /// Creates a new instance of the scheduler with 4.3BSD's parameters.
+    pub fn new() -> Self {
+        Self::with_config(BsdConfig::default())
+    }
+
+    /// Creates a new instance of the scheduler using `config`.
+    pub fn with_config(config: BsdConfig) -> Self {
+        Self { config, queue: BTreeMap::new(), usage: BTreeMap::new(), running: None, added: 0, seconds: 0 }
+    }
+
+    /// The decayed CPU usage of process `id`, in ticks.
+    pub fn estcpu(&self, id: u32) -> u64 {
+        self.usage.get(&id).map_or(0, |&(estcpu, _)| estcpu)
+    }
+
+    /// The current priority of process `id`; lower runs first.
+    pub fn priority(&self, id: u32) -> u32 {
+        let (estcpu, nice) = self.usage.get(&id).copied().unwrap_or_default();
+        (PUSER as u64 + estcpu / 4 + 2 * nice as u64).min(MAXPRI as u64) as u32
+    }
+
+    /// Charges the running process for the CPU time it used up to `tick`.
+    fn charge(&mut self, tick: u64) {
+        if let Some((id, since)) = &mut self.running {
+            let used = tick.saturating_sub(*since);
+            *since = tick;
+            if used > 0 {
+                self.usage.entry(*id).or_default().0 += used;
+            }
+        }
+    }
+
+    /// Applies the decay of every simulated second that has passed, and
+    /// recalculates the priorities of the ready processes if there was one.
+    fn catch_up(&mut self) {
+        let now = CLOCK.now_ticks();
+        let seconds = now / self.config.second;
+        if seconds <= self.seconds {
+            return;
+        }
+        while self.seconds < seconds {
+            self.seconds += 1;
+            self.charge(self.seconds * self.config.second);
+            let load = (self.queue.len() + self.running.is_some() as usize) as u64;
+            self.usage.retain(|_, (estcpu, nice)| {
+                *estcpu = *estcpu * 2 * load / (2 * load + 1) + *nice as u64;
+                *estcpu > 0
+            });
+            // with nothing to run, every estcpu is down to its nice value and stays there
+            if load == 0 {
+                self.seconds = seconds;
+            }
+        }
+        let queue = std::mem::take(&mut self.queue);
+        for ((_, order), process) in queue {
+            self.queue.insert((self.priority(process.id), order), process);
+        }
+    }
+}
+
+impl Default for BsdSchedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Schedule for BsdSchedule {
+    /// Queues `process` at its current priority. A process coming back from
+    /// the CPU is charged for the time it ran first.
+    fn add_process(&mut self, process: PCB) -> bool {
+        self.catch_up();
+        if self.running.is_some_and(|(id, _)| id == process.id) {
+            self.charge(CLOCK.now_ticks());
+            self.running = None;
+        }
+        self.usage.entry(process.id).or_default().1 = process.priority;
+        let key = (self.priority(process.id), self.added);
+        self.added += 1;
+        self.queue.insert(key, process);
+        true
+    }
+
+    /// Dispatches the ready process with the lowest priority number for one
+    /// time slice.
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        self.catch_up();
+        // a process that was not handed back has finished or blocked
+        self.charge(CLOCK.now_ticks());
+        let process = self.queue.pop_first().map(|(_, process)| process);
+        self.running = process.map(|process| (process.id, CLOCK.now_ticks()));
+        (process, TimeSlice::Ticks(self.config.quantum))
+    }
+
+    fn has_process(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn level_quantum(&self, _level: usize) -> TimeSlice {
+        TimeSlice::Ticks(self.config.quantum)
+    }
+
+    fn iter_level(&self, level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        match level {
+            0 => Box::new(self.queue.values()),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// An estimate, since `BTreeMap` does not report its allocations.
+    fn heap_size(&self) -> usize {
+        self.queue.len() * size_of::<((u32, u64), PCB)>() + self.usage.len() * size_of::<(u32, (u64, u32))>()
+    }
+
+    /// Decay brings a process that has not run back up, but only as far as
+    /// its nice value allows, so a niced process can starve behind a steady
+    /// stream of others.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: true,
+            starvation_free: false,
+            levels: 1,
+            default_quantum: TimeSlice::Ticks(self.config.quantum),
+            dispatch: Complexity::Logarithmic,
+        })
+    }
+}
@@ -0,0 +1,246 @@
+//! # Bundle Module
+//!
+//! Report bundles: everything about a run in one zip archive, to attach to a
+//! lab submission and to replay later.
+//!
+//! `scheduler -s <name> -i <input> --bundle run.zip` writes a [`Bundle`]
+//! with these files:
+//!
+//! - [`MANIFEST`]: the [`Manifest`], with the command line and the run's
+//!   [`Provenance`];
+//! - the workload, as the simulator read it, with any included files and
+//!   repeated blocks expanded; its name is in the manifest;
+//! - [`CONFIG`]: the scheduler parameters the run used, defaults included,
+//!   in the `--config` TOML format;
+//! - [`RESULTS`]: the JSON [`Results`](crate::results::Results), with the
+//!   event log and the metrics;
+//! - [`SUMMARY`]: the metrics summary as the simulator prints it;
+//! - [`GANTT`]: the run's Gantt chart as an SVG image.
+//!
+//! `scheduler replay run.zip` runs the scheduler again on the bundled
+//! workload, parameters, time unit, measurement window and stop conditions,
+//! and reports where the new run diverges from the bundled results, like
+//! `scheduler diff`.
+//!
+//! The archive is a plain zip file that any unzip tool opens. Its entries
+//! are stored uncompressed and dated 1980-01-01, the earliest date zip files
+//! know, so the same run always gives the same bytes. [`Bundle::from_zip`]
+//! reads such archives, not compressed ones.
+//!
+//! ## Example
+//! ```
+//! use scheduler::bundle::Bundle;
+//!
+//! let mut bundle = Bundle::new();
+//! bundle.add("workload.in", "0 0 5 1\n");
+//! bundle.add("summary.txt", "completed: 1\n");
+//! let zip = bundle.to_zip();
+//! assert_eq!(&zip[..4], b"PK\x03\x04");
+//! assert_eq!(zip, bundle.to_zip());
+//!
+//! let read = Bundle::from_zip(&zip).unwrap();
+//! assert_eq!(read.names().collect::<Vec<_>>(), ["workload.in", "summary.txt"]);
+//! assert_eq!(read.text("workload.in"), Some("0 0 5 1\n"));
+//! assert!(Bundle::from_zip(b"not a zip").is_err());
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::results::Provenance;
+
+/// The bundle format version this build writes and reads.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Name of the [`Manifest`] in a bundle.
+pub const MANIFEST: &str = "manifest.json";
+/// Name of the scheduler parameters in a bundle.
+pub const CONFIG: &str = "config.toml";
+/// Name of the JSON results in a bundle.
+pub const RESULTS: &str = "results.json";
+/// Name of the metrics summary in a bundle.
+pub const SUMMARY: &str = "summary.txt";
+/// Name of the Gantt chart in a bundle.
+pub const GANTT: &str = "gantt.svg";
+
+/// What a bundle holds and where it came from.
+///
+/// # Fields
+/// - `bundle_version`: [`BUNDLE_VERSION`] of the build that wrote it.
+/// - `workload`: Name of the workload file in the bundle; its extension
+///   tells the format, as for `-i`.
+/// - `command`: The command line of the run, for people; replaying uses the
+///   run's configuration in the results instead.
+/// - `provenance`: The run's [`Provenance`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub bundle_version: u32,
+    pub workload: String,
+    pub command: Vec<String>,
+    pub provenance: Provenance,
+}
+
+/// Named files, written to and read from a zip archive, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bundle {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl Bundle {
+    /// A bundle without files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, replacing any of the same name.
+    pub fn add(&mut self, name: impl Into<String>, contents: impl Into<Vec<u8>>) {
+        let (name, contents) = (name.into(), contents.into());
+        match self.files.iter_mut().find(|(existing, _)| *existing == name) {
+            Some(file) => file.1 = contents,
+            None => self.files.push((name, contents)),
+        }
+    }
+
+    /// The contents of the file `name`.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.files.iter().find(|(existing, _)| existing == name).map(|(_, contents)| contents.as_slice())
+    }
+
+    /// The contents of the file `name`, if it is text.
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|contents| std::str::from_utf8(contents).ok())
+    }
+
+    /// The names of the files, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The bundle as a zip archive.
+    pub fn to_zip(&self) -> Vec<u8> {
+        let mut zip = Vec::new();
+        let mut directory = Vec::new();
+        for (name, contents) in &self.files {
+            let offset = zip.len() as u32;
+            let header = EntryHeader { name, crc: crc32(contents), size: contents.len() as u32 };
+            header.write(&mut zip, LOCAL_SIGNATURE, None);
+            zip.extend_from_slice(contents);
+            header.write(&mut directory, CENTRAL_SIGNATURE, Some(offset));
+        }
+        let (directory_offset, directory_size) = (zip.len() as u32, directory.len() as u32);
+        zip.extend_from_slice(&directory);
+        put32(&mut zip, END_SIGNATURE);
+        put16(&mut zip, 0); // this disk
+        put16(&mut zip, 0); // disk the directory starts on
+        put16(&mut zip, self.files.len() as u16);
+        put16(&mut zip, self.files.len() as u16);
+        put32(&mut zip, directory_size);
+        put32(&mut zip, directory_offset);
+        put16(&mut zip, 0); // comment length
+        zip
+    }
+
+    /// Reads a zip archive of uncompressed files, such as
+    /// [`to_zip`](Bundle::to_zip) writes.
+    ///
+    /// # Errors
+    /// If `zip` is not a zip archive, is damaged, or has compressed files.
+    pub fn from_zip(zip: &[u8]) -> Result<Self, String> {
+        let end = (0..zip.len().saturating_sub(21))
+            .rev()
+            .find(|&at| get32(zip, at) == Some(END_SIGNATURE))
+            .ok_or("not a zip archive")?;
+        let damaged = || "damaged zip archive".to_string();
+        let count = get16(zip, end + 10).ok_or_else(damaged)?;
+        let mut at = get32(zip, end + 16).ok_or_else(damaged)? as usize;
+        let mut bundle = Bundle::new();
+        for _ in 0..count {
+            if get32(zip, at) != Some(CENTRAL_SIGNATURE) {
+                return Err(damaged());
+            }
+            let field = |offset: usize| get16(zip, at + offset).ok_or_else(damaged);
+            let (method, name_len, extra_len, comment_len) = (field(10)?, field(28)?, field(30)?, field(32)?);
+            let crc = get32(zip, at + 16).ok_or_else(damaged)?;
+            let size = get32(zip, at + 20).ok_or_else(damaged)? as usize;
+            let local = get32(zip, at + 42).ok_or_else(damaged)? as usize;
+            let name = zip.get(at + 46..at + 46 + name_len as usize).ok_or_else(damaged)?;
+            let name = String::from_utf8_lossy(name).into_owned();
+            if method != 0 {
+                return Err(format!("'{}' is compressed; only uncompressed files can be read", name));
+            }
+            let local_name_len = get16(zip, local + 26).ok_or_else(damaged)? as usize;
+            let local_extra_len = get16(zip, local + 28).ok_or_else(damaged)? as usize;
+            let start = local + 30 + local_name_len + local_extra_len;
+            let contents = zip.get(start..start + size).ok_or_else(damaged)?;
+            if crc32(contents) != crc {
+                return Err(format!("'{}' is damaged (checksum mismatch)", name));
+            }
+            bundle.files.push((name, contents.to_vec()));
+            at += 46 + name_len as usize + extra_len as usize + comment_len as usize;
+        }
+        Ok(bundle)
+    }
+}
+
+const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+const END_SIGNATURE: u32 = 0x0605_4b50;
+
+/// What the local and the central header of a zip entry share.
+struct EntryHeader<'a> {
+    name: &'a str,
+    crc: u32,
+    size: u32,
+}
+
+impl EntryHeader<'_> {
+    /// Writes the local header, or the central one given the `offset` of
+    /// the local header.
+    fn write(&self, out: &mut Vec<u8>, signature: u32, offset: Option<u32>) {
+        put32(out, signature);
+        if offset.is_some() {
+            put16(out, 20); // made by: zip 2.0
+        }
+        put16(out, 20); // needed to extract: zip 2.0
+        put16(out, 0x0800); // flags: the name is UTF-8
+        put16(out, 0); // method: stored
+        put16(out, 0); // time: 00:00:00
+        put16(out, 0x21); // date: 1980-01-01
+        put32(out, self.crc);
+        put32(out, self.size);
+        put32(out, self.size);
+        put16(out, self.name.len() as u16);
+        put16(out, 0); // extra field length
+        if let Some(offset) = offset {
+            put16(out, 0); // comment length
+            put16(out, 0); // disk
+            put16(out, 0); // internal attributes
+            put32(out, 0); // external attributes
+            put32(out, offset);
+        }
+        out.extend_from_slice(self.name.as_bytes());
+    }
+}
+
+fn put16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn get16(bytes: &[u8], at: usize) -> Option<u16> {
+    bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn get32(bytes: &[u8], at: usize) -> Option<u32> {
+    bytes.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// The CRC-32 zip files check their contents with.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        (0..8).fold(crc ^ b as u32, |crc, _| if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 })
+    })
+}
@@ -0,0 +1,191 @@
+//! # Burst Module
+//!
+//! Exact accounting of a job's CPU burst, shared by the simulators.
+//!
+//! A process is often taken off the CPU before its burst is done: its time
+//! slice runs out, a CPU limit or a higher priority arrival preempts it, or
+//! the run stops. The burst is then split, and the next dispatch carries on
+//! where the last one left off. A [`Burst`] charges every tick the process
+//! runs exactly once, so the ticks it ran and the ticks it has left always
+//! add up to the burst, however often it is split: no tick is lost when the
+//! process is preempted, and none is counted twice.
+//!
+//! Progress is kept in [`UNITS`] work units per tick, so a process running
+//! slower than a tick per tick, like a hardware thread sharing its core with
+//! a busy sibling, makes exact fractional progress: five ticks at a fifth of
+//! the speed do one tick of work, without rounding errors piling up.
+//!
+//! ## Example
+//! ```
+//! use scheduler::burst::{self, Burst};
+//!
+//! let mut job = Burst::new(10);
+//! // a slice of 4 ticks, preempted
+//! for _ in 0..4 {
+//!     job.run();
+//! }
+//! assert_eq!(job.split(), 4 * burst::UNITS);
+//! // 3 ticks sharing a core at half speed, preempted
+//! for _ in 0..3 {
+//!     job.work(burst::units(0.5));
+//! }
+//! assert_eq!((job.ran(), job.left()), (5, 5));
+//! job.split();
+//! // the rest, where the half tick of work left over counts too
+//! let mut ticks = 0;
+//! while !job.run() {
+//!     ticks += 1;
+//! }
+//! assert_eq!((ticks + 1, job.ran(), job.left(), job.pieces()), (5, 10, 0, 2));
+//! ```
+//!
+//! ## Reference Traces
+//! The reference traces in `tests/` were made by loops that do not charge
+//! the first tick of a process dispatched to run to completion: it runs one
+//! tick more than its burst, and one preempted part way through is charged a
+//! tick less than it ran. The classic loops follow the traces by calling
+//! [`skip_first_tick`](Burst::skip_first_tick) on every such dispatch, which
+//! is the only place the rule is kept. The
+//! [`Simulation`](crate::simulation::Simulation) engine never calls it, so it
+//! charges exactly the burst, and a job run to completion there finishes a
+//! tick earlier than on a classic loop; making the two agree would change
+//! every reference trace, so they are left to differ.
+
+/// Work units in one tick of CPU time.
+pub const UNITS: u64 = 1_000_000;
+
+/// The work units `share` of a tick does, rounded to the nearest unit.
+pub fn units(share: f64) -> u64 {
+    (share * UNITS as f64).round() as u64
+}
+
+/// The CPU time a job has had of one of its bursts, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Burst {
+    total: u32,
+    /// work units done, never more than the burst
+    done: u64,
+    /// work units done since the last split
+    piece: u64,
+    pieces: u32,
+    /// whether the next tick of work is not charged
+    skip: bool,
+}
+
+impl Burst {
+    /// A burst of `ticks` ticks of CPU time, none of it run yet.
+    pub fn new(ticks: u32) -> Self {
+        Self { total: ticks, done: 0, piece: 0, pieces: 0, skip: false }
+    }
+
+    /// Ticks of CPU time the burst needs in all.
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Whole ticks of work done.
+    pub fn ran(&self) -> u32 {
+        (self.done / UNITS) as u32
+    }
+
+    /// Ticks of work left, counting a tick that is partly done as left.
+    pub fn left(&self) -> u32 {
+        self.total - self.ran()
+    }
+
+    /// Whether every tick of the burst has been run.
+    pub fn is_done(&self) -> bool {
+        self.left() == 0
+    }
+
+    /// Charges one tick of CPU time.
+    ///
+    /// # Returns
+    /// Whether the burst is done.
+    ///
+    /// # Panics
+    /// If the burst was already done.
+    pub fn run(&mut self) -> bool {
+        self.work(UNITS)
+    }
+
+    /// Charges `units` work units, see [`units`]. Work past the end of the
+    /// burst is dropped, as a process that finishes part way through a tick
+    /// leaves the rest of it unused.
+    ///
+    /// # Returns
+    /// Whether the burst is done.
+    ///
+    /// # Panics
+    /// If the burst was already done, as the work would be charged twice.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::burst::{self, Burst};
+    ///
+    /// // ten tenths of a tick add up to 0.9999999999999999 in floating point,
+    /// // but to exactly one tick of work
+    /// assert!((0..10).map(|_| 0.1).sum::<f64>() < 1.0);
+    /// let mut job = Burst::new(2);
+    /// for _ in 0..10 {
+    ///     job.work(burst::units(0.1));
+    /// }
+    /// assert_eq!((job.ran(), job.left()), (1, 1));
+    /// // finishing part way through a tick leaves the rest of it unused
+    /// job.work(burst::units(0.75));
+    /// assert!(job.work(burst::units(0.75)));
+    /// assert_eq!(job.split(), 2 * burst::UNITS);
+    /// ```
+    pub fn work(&mut self, units: u64) -> bool {
+        assert!(!self.is_done(), "a burst of {} ticks was charged after it was done", self.total);
+        if std::mem::take(&mut self.skip) {
+            return false;
+        }
+        let units = units.min(self.total as u64 * UNITS - self.done);
+        self.done += units;
+        self.piece += units;
+        self.is_done()
+    }
+
+    /// Splits the burst where the process was taken off the CPU, for the
+    /// next dispatch to start a new piece.
+    ///
+    /// # Returns
+    /// The work units done in the piece that ended.
+    pub fn split(&mut self) -> u64 {
+        self.pieces += 1;
+        self.skip = false;
+        std::mem::take(&mut self.piece)
+    }
+
+    /// Leaves the next tick of work uncharged, as the reference traces do
+    /// for a process dispatched to run to completion, see
+    /// [Reference Traces](self#reference-traces).
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::burst::{self, Burst};
+    ///
+    /// // a burst of 3 ticks, run to completion, takes 4
+    /// let mut job = Burst::new(3);
+    /// job.skip_first_tick();
+    /// let ticks = (1..).find(|_| job.run()).unwrap();
+    /// assert_eq!((ticks, job.ran()), (4, 3));
+    ///
+    /// // and one preempted after 2 ticks was charged 1
+    /// let mut job = Burst::new(3);
+    /// job.skip_first_tick();
+    /// job.run();
+    /// job.run();
+    /// assert_eq!((job.split(), job.left()), (burst::UNITS, 2));
+    /// ```
+    pub fn skip_first_tick(&mut self) {
+        self.skip = true;
+    }
+
+    /// How often the burst has been split.
+    pub fn pieces(&self) -> u32 {
+        self.pieces
+    }
+}
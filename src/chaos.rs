@@ -0,0 +1,248 @@
+//! # Chaos Module
+//!
+//! Injects faults into a scheduler, for testing the tools that are meant to
+//! catch a misbehaving one.
+//!
+//! [`ChaosSchedule`] wraps a working scheduler and, at random but from a
+//! fixed seed, breaks the [`Schedule`] contract the way buggy student
+//! schedulers do:
+//! - a [duplicate](ChaosSchedule::duplicates) add keeps two copies of a
+//!   process, so it is returned twice;
+//! - a [lie](ChaosSchedule::lies) flips the next answer of
+//!   [`Schedule::has_process`], claiming a process is ready when none is or
+//!   the other way round;
+//! - a [stale](ChaosSchedule::stale) dispatch returns the last process
+//!   returned again, instead of asking the wrapped scheduler.
+//!
+//! Every fault is recorded, so a test can tell which ones the simulator or
+//! [`selfcheck`](crate::selfcheck) should have noticed. No fault is
+//! injected unless its rate is set, and the same seed always injects the
+//! same faults into the same run.
+//!
+//! ## Example
+//! ```
+//! use std::collections::VecDeque;
+//! use std::time::Duration;
+//! use scheduler::chaos::{ChaosSchedule, FaultKind};
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::Workload;
+//! use scheduler::results::{Results, RunConfig};
+//! use scheduler::simulation::{Simulation, SimulationError};
+//! use scheduler::sink::Recorder;
+//! use scheduler::{selfcheck, CLOCK, PCB, Schedule, TimeSlice};
+//!
+//! #[derive(Default)]
+//! struct Fifo(VecDeque<PCB>);
+//! impl Schedule for Fifo {
+//!     fn add_process(&mut self, p: PCB) -> bool { self.0.push_back(p); true }
+//!     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop_front(), TimeSlice::Infinite) }
+//!     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! }
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let workload = Workload::parse(["0 0 2", "1 0 1", "2 1 3"], TimeUnit::Ns, false).unwrap();
+//!
+//! // every process is added twice, so each one is dispatched after it finished
+//! let sched = ChaosSchedule::new(Fifo::default(), 7).duplicates(1.0);
+//! let mut sim = Simulation::builder(sched).workload(&workload).build();
+//! let mut out = Recorder::new();
+//! sim.run(&mut out).unwrap();
+//! assert_eq!(sim.scheduler().faults().len(), 3);
+//! let problems = selfcheck::check(&workload, &Results::new(RunConfig::default(), &out));
+//! assert!(problems.contains(&"P0 was dispatched after it finished".to_string()));
+//!
+//! // a scheduler that always claims to be empty never dispatches anything
+//! let sched = ChaosSchedule::new(Fifo::default(), 7).lies(1.0);
+//! let mut sim = Simulation::builder(sched).workload(&workload).build();
+//! assert_eq!(sim.run(&mut Recorder::new()), Err(SimulationError::Stuck(vec![0, 1, 2])));
+//! assert_eq!(sim.scheduler().faults()[0].kind, FaultKind::Lie { claimed: false });
+//! ```
+
+use std::ops::{Deref, DerefMut};
+
+use crate::random::Rng;
+use crate::{CLOCK, PCB, Schedule, SchedulerInfo, TimeSlice};
+
+/// A fault a [`ChaosSchedule`] injected.
+///
+/// # Fields
+/// - `time`: Tick the fault was injected at.
+/// - `kind`: What went wrong.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fault {
+    pub time: u64,
+    pub kind: FaultKind,
+}
+
+/// The ways a [`ChaosSchedule`] misbehaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultKind {
+    /// The process was added to the wrapped scheduler twice.
+    Duplicate(u32),
+    /// The next call to `has_process` returns `claimed` instead of the
+    /// wrapped scheduler's answer.
+    Lie { claimed: bool },
+    /// The process was returned again, without asking the wrapped scheduler.
+    Stale(u32),
+}
+
+/// Wraps a scheduler and injects faults into it, see the
+/// [module documentation](self).
+///
+/// Every [`Schedule`] method is forwarded to the wrapped scheduler, and
+/// [`Deref`] gives access to its own methods.
+pub struct ChaosSchedule<S> {
+    inner: S,
+    rng: Rng,
+    duplicates: f64,
+    lies: f64,
+    stale: f64,
+    /// whether the next `has_process` answer is flipped
+    lie: bool,
+    last: Option<(PCB, TimeSlice)>,
+    faults: Vec<Fault>,
+}
+
+impl<S: Schedule> ChaosSchedule<S> {
+    /// Wraps `inner`, drawing faults from a generator seeded with `seed`. No
+    /// faults are injected until a rate is set.
+    pub fn new(inner: S, seed: u64) -> Self {
+        Self {
+            inner,
+            rng: Rng::new(seed),
+            duplicates: 0.0,
+            lies: 0.0,
+            stale: 0.0,
+            lie: false,
+            last: None,
+            faults: Vec::new(),
+        }
+    }
+
+    /// Adds each process twice with probability `rate`.
+    pub fn duplicates(mut self, rate: f64) -> Self {
+        self.duplicates = rate;
+        self
+    }
+
+    /// Flips the answer of `has_process` with probability `rate`, rolled
+    /// after every add and dispatch. A flipped answer holds until the next
+    /// one.
+    pub fn lies(mut self, rate: f64) -> Self {
+        self.lies = rate;
+        self
+    }
+
+    /// Returns the last dispatched process again with probability `rate`.
+    pub fn stale(mut self, rate: f64) -> Self {
+        self.stale = rate;
+        self
+    }
+
+    /// Every fault injected so far, in the order they happened.
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    /// Consumes the wrapper, returning the wrapped scheduler.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Rolls a fault of probability `rate`. A rate of `0` draws nothing, so
+    /// an unused fault does not shift the others.
+    fn roll(&mut self, rate: f64) -> bool {
+        rate > 0.0 && self.rng.next_f64() < rate
+    }
+
+    fn inject(&mut self, kind: FaultKind) {
+        self.faults.push(Fault { time: CLOCK.now_ticks(), kind });
+    }
+
+    /// Decides whether the next `has_process` answer is a lie.
+    fn roll_lie(&mut self) {
+        self.lie = self.roll(self.lies);
+        if self.lie {
+            let claimed = !self.inner.has_process();
+            self.inject(FaultKind::Lie { claimed });
+        }
+    }
+}
+
+impl<S> Deref for ChaosSchedule<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S> DerefMut for ChaosSchedule<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+}
+
+impl<S: Schedule> Schedule for ChaosSchedule<S> {
+    fn add_process(&mut self, process: PCB) -> bool {
+        let added = self.inner.add_process(process);
+        if self.roll(self.duplicates) {
+            self.inject(FaultKind::Duplicate(process.id));
+            self.inner.add_process(process);
+        }
+        self.roll_lie();
+        added
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        let last = self.last;
+        let next = match last {
+            Some((process, slice)) if self.roll(self.stale) => {
+                self.inject(FaultKind::Stale(process.id));
+                (Some(process), slice)
+            }
+            _ => self.inner.next_process(),
+        };
+        if let (Some(process), slice) = next {
+            self.last = Some((process, slice));
+        }
+        self.roll_lie();
+        next
+    }
+
+    fn has_process(&self) -> bool {
+        self.inner.has_process() != self.lie
+    }
+
+    fn levels(&self) -> usize {
+        self.inner.levels()
+    }
+
+    fn level_quantum(&self, level: usize) -> TimeSlice {
+        self.inner.level_quantum(level)
+    }
+
+    fn dispatched_level(&self) -> Option<usize> {
+        self.inner.dispatched_level()
+    }
+
+    fn iter_level(&self, level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        self.inner.iter_level(level)
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        self.inner.peek()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.inner.heap_size()
+    }
+
+    fn info(&self) -> Option<SchedulerInfo> {
+        self.inner.info()
+    }
+
+    fn renice(&mut self, id: u32, nice: i8) {
+        self.inner.renice(id, nice)
+    }
+}
@@ -0,0 +1,378 @@
+//! # Classic Module
+//!
+//! The loops `simple`, `simplerr` and `mlrr` are simulated with, unless a run
+//! needs something only the [`Simulation`](crate::simulation::Simulation)
+//! engine does. They follow the reference traces in `tests/` to the tick:
+//! - [`Run::ToCompletion`], for `simple`, dispatches with a quantum of `0`
+//!   and runs the process until it is done, one tick more than its burst
+//!   (see [Reference Traces](crate::burst#reference-traces));
+//! - [`Run::ByQuantum`], for `simplerr` and `mlrr`, runs the process for at
+//!   most its time slice. When the slice runs out the process is added back
+//!   before the jobs arriving at that tick, unless the run is given another
+//!   [`expiry_order`](Classic::expiry_order). A
+//!   [`non_preemptive`](Classic::non_preemptive) run lets it run until it is
+//!   done instead.
+//!
+//! Jobs are handed to the scheduler at the tick they arrive, in the order
+//! they were added, whether or not a process is running at the time, after
+//! the markers of that tick are made; markers only label the trace and
+//! change nothing else about the run. When
+//! nothing is ready the idle task runs; when nothing is left to arrive
+//! either, the scheduler has lost processes it was given and the run fails
+//! with [`SimulationError::Stuck`].
+//!
+//! A hook set with [`on_tick`](Classic::on_tick) is called after every
+//! tick, and may add jobs and markers as the run goes.
+//!
+//! A run set up to [`catch_panics`](Classic::catch_panics) fails with a
+//! [`Fault`] when the scheduler panics, as a
+//! [`Simulation`](crate::simulation::Simulation) does; see [`crate::fault`].
+//!
+//! ## Example
+//! ```
+//! use scheduler::classic::{Classic, Run};
+//! use scheduler::policy::RR;
+//! use scheduler::simulation::Job;
+//! use scheduler::sink::Recorder;
+//!
+//! // P1 arrives while P0 is running, and waits for P0's slice to run out
+//! let mut run = Classic::new(RR::with_quantum(2), Run::ByQuantum).jobs([Job::new(0, 0, 5), Job::new(1, 1, 2)]);
+//! let mut out = Recorder::new();
+//! run.run(&mut out).unwrap();
+//! let finish = |id| out.metrics().process(id).unwrap().finish;
+//! assert_eq!((finish(0), finish(1)), (Some(7), Some(4)));
+//!
+//! // run to completion, one tick more than the burst
+//! let mut run = Classic::new(RR::with_quantum(2), Run::ToCompletion).jobs([Job::new(0, 0, 5), Job::new(1, 1, 2)]);
+//! let mut out = Recorder::new();
+//! run.run(&mut out).unwrap();
+//! assert_eq!(out.metrics().process(1).unwrap().finish, Some(9));
+//! ```
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::burst::Burst;
+use crate::event::{EventKind, IDLE};
+use crate::fault::{self, Fault};
+use crate::simulation::{self, ExpiryOrder, Job, SimulationError};
+use crate::sink::Recorder;
+use crate::{CLOCK, PCB, Quantum, Schedule};
+
+/// How long a dispatched process runs, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Run {
+    /// Until it is done, whatever time slice the scheduler hands out.
+    ToCompletion,
+    /// For at most the time slice the scheduler hands out.
+    ByQuantum,
+}
+
+type TickHook<'h> = Box<dyn FnMut(&mut Context) + 'h>;
+
+/// What a hook can see and do, see [`Classic::on_tick`].
+pub struct Context<'a> {
+    sched: &'a dyn Schedule,
+    out: &'a mut Recorder,
+    running: Option<&'a PCB>,
+    left: Option<u32>,
+    submitted: &'a mut Vec<Job>,
+    annotations: &'a mut BTreeMap<u64, Vec<String>>,
+}
+
+impl<'a> Context<'a> {
+    /// The scheduler being simulated.
+    pub fn scheduler(&self) -> &dyn Schedule {
+        self.sched
+    }
+
+    /// Where the run's events go, with the metrics recorded so far.
+    pub fn recorder(&mut self) -> &mut Recorder {
+        self.out
+    }
+
+    /// The process on the CPU, `None` while the idle task runs.
+    pub fn running(&self) -> Option<&PCB> {
+        self.running
+    }
+
+    /// Ticks left of the running process's slice, `None` when it runs to
+    /// completion or nothing is running.
+    pub fn quantum_left(&self) -> Option<u32> {
+        self.left
+    }
+
+    /// Adds a job to the run. It arrives at its `arrival` tick, or as soon
+    /// as the loop hands jobs over again if that has already passed. Its
+    /// burst script and timeout are ignored, as by [`Classic::jobs`].
+    pub fn submit(&mut self, job: Job) {
+        self.submitted.push(job);
+    }
+
+    /// Puts a marker labeled `label` at tick `time`, or at the current tick
+    /// if that has already passed.
+    pub fn annotate(&mut self, time: u64, label: impl Into<String>) {
+        match time > CLOCK.now_ticks() {
+            true => self.annotations.entry(time).or_default().push(label.into()),
+            false => self.out.annotate(label),
+        }
+    }
+}
+
+/// A run of a scheduler on a classic loop, see the
+/// [module documentation](self).
+pub struct Classic<'h, S> {
+    sched: S,
+    run: Run,
+    quanta: Vec<u32>,
+    expiry_order: ExpiryOrder,
+    non_preemptive: bool,
+    on_tick: Option<TickHook<'h>>,
+    catch_panics: bool,
+    /// labels of the markers still to be made, keyed by their tick
+    annotations: BTreeMap<u64, Vec<String>>,
+    /// IDs of the jobs, keyed by their arrival tick
+    arrivals: BTreeMap<u64, Vec<u32>>,
+    /// priorities of the jobs, by ID
+    priorities: BTreeMap<u32, u32>,
+    /// bursts of the jobs that have not finished, by ID
+    pending: BTreeMap<u32, Burst>,
+    /// the first tick whose arrivals have not been handed over yet
+    next: u64,
+}
+
+impl<'h, S: Schedule> Classic<'h, S> {
+    /// Sets up a run of `sched` that runs dispatched processes as `run`
+    /// says.
+    pub fn new(sched: S, run: Run) -> Self {
+        Self {
+            sched,
+            run,
+            quanta: Vec::new(),
+            expiry_order: ExpiryOrder::PreemptedFirst,
+            non_preemptive: false,
+            on_tick: None,
+            catch_panics: false,
+            annotations: BTreeMap::new(),
+            arrivals: BTreeMap::new(),
+            priorities: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            next: 0,
+        }
+    }
+
+    /// Adds jobs. Jobs arriving at the same tick are handed to the scheduler
+    /// in the order they were added. Burst scripts and timeouts are left to
+    /// the [`Simulation`](crate::simulation::Simulation) engine and ignored.
+    pub fn jobs(mut self, jobs: impl IntoIterator<Item = Job>) -> Self {
+        for job in jobs {
+            self.add(job);
+        }
+        self
+    }
+
+    /// The scheduler's per-level time slices, see
+    /// [`SimulationBuilder::quanta`](crate::simulation::SimulationBuilder::quanta).
+    pub fn quanta(mut self, quanta: &[u32]) -> Self {
+        self.quanta = quanta.to_vec();
+        self
+    }
+
+    /// Who queues up first when jobs arrive at the tick a time slice runs
+    /// out, see
+    /// [`SimulationBuilder::expiry_order`](crate::simulation::SimulationBuilder::expiry_order).
+    /// [`ExpiryOrder::PreemptedFirst`] by default, as the reference traces
+    /// of `simplerr` and `mlrr` were made with.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::classic::{Classic, Run};
+    /// use scheduler::policy::RR;
+    /// use scheduler::simulation::{ExpiryOrder, Job};
+    /// use scheduler::sink::Recorder;
+    ///
+    /// // P1 arrives as P0's first slice runs out
+    /// let first = |order| {
+    ///     let mut run = Classic::new(RR::with_quantum(2), Run::ByQuantum).expiry_order(order).jobs([Job::new(0, 0, 4), Job::new(1, 2, 2)]);
+    ///     let mut out = Recorder::new();
+    ///     run.run(&mut out).unwrap();
+    ///     let finish = |id| out.metrics().process(id).unwrap().finish;
+    ///     (finish(0), finish(1))
+    /// };
+    /// assert_eq!(first(ExpiryOrder::PreemptedFirst), (Some(4), Some(6)));
+    /// assert_eq!(first(ExpiryOrder::ArrivalsFirst), (Some(6), Some(4)));
+    /// ```
+    pub fn expiry_order(mut self, order: ExpiryOrder) -> Self {
+        self.expiry_order = order;
+        self
+    }
+
+    /// Ignores the time slices the scheduler hands out, see
+    /// [`SimulationBuilder::non_preemptive`](crate::simulation::SimulationBuilder::non_preemptive):
+    /// on [`Run::ByQuantum`] a dispatched process runs until it is done, its
+    /// dispatch reported with the FCFS quantum.
+    pub fn non_preemptive(mut self) -> Self {
+        self.non_preemptive = true;
+        self
+    }
+
+    /// Puts a marker labeled `label` at tick `time`, see
+    /// [`SimulationBuilder::annotate`](crate::simulation::SimulationBuilder::annotate).
+    pub fn annotate(mut self, time: u64, label: impl Into<String>) -> Self {
+        self.annotations.entry(time).or_default().push(label.into());
+        self
+    }
+
+    /// Calls `hook` after every tick, the idle task's included, with a
+    /// [`Context`] of the run.
+    pub fn on_tick(mut self, hook: impl FnMut(&mut Context) + 'h) -> Self {
+        self.on_tick = Some(Box::new(hook));
+        self
+    }
+
+    /// Catches a panic of the scheduler, or of the hook, and fails the run
+    /// with a [`SimulationError::Fault`] instead of unwinding, see
+    /// [`SimulationBuilder::catch_panics`](crate::simulation::SimulationBuilder::catch_panics).
+    pub fn catch_panics(mut self) -> Self {
+        self.catch_panics = true;
+        self
+    }
+
+    /// The scheduler being simulated.
+    pub fn scheduler(&self) -> &S {
+        &self.sched
+    }
+
+    /// Consumes the run, returning the scheduler.
+    pub fn into_scheduler(self) -> S {
+        self.sched
+    }
+
+    /// Runs every job to completion, emitting what happens to `out`. The
+    /// [`CLOCK`] starts at `0`. The run ends early when `out` reaches one of
+    /// its stop conditions.
+    ///
+    /// # Errors
+    /// [`SimulationError::NoProcess`] when the scheduler returns no process
+    /// although it has one, and [`SimulationError::Stuck`] when it has none
+    /// although some have not finished and nothing is left to arrive.
+    /// [`SimulationError::Fault`] when the scheduler panics in a run set up
+    /// to [`catch_panics`](Self::catch_panics).
+    pub fn run(&mut self, out: &mut Recorder) -> Result<(), SimulationError> {
+        if !self.catch_panics {
+            return self.simulate(out);
+        }
+        fault::catch(|| self.simulate(out)).unwrap_or_else(|panic| Err(SimulationError::Fault(Box::new(Fault::new(panic, &self.sched, out)))))
+    }
+
+    /// Runs the jobs, see [`run`](Self::run).
+    fn simulate(&mut self, out: &mut Recorder) -> Result<(), SimulationError> {
+        CLOCK.set_now(Duration::ZERO);
+        self.next = 0;
+        while !self.pending.is_empty() && !out.stopped() {
+            self.admit(out);
+            if !self.sched.has_process() {
+                if self.arrivals.range(CLOCK.now_ticks() + 1..).next().is_none() {
+                    return Err(SimulationError::Stuck(self.pending.keys().copied().collect()));
+                }
+                out.emit(IDLE, EventKind::Idle);
+                CLOCK.advance(CLOCK.tick());
+                self.tick(out, None, None);
+                continue;
+            }
+            while self.sched.has_process() && !out.stopped() {
+                let (Some(process), slice) = self.sched.next_process() else {
+                    return Err(SimulationError::NoProcess);
+                };
+                let quantum = match self.run {
+                    Run::ToCompletion => None,
+                    Run::ByQuantum => Some(Quantum::from(slice)),
+                };
+                //the level is the scheduler's, whatever the process is let run for
+                let level = quantum.map(|quantum| quantum.raw());
+                let quantum = if self.non_preemptive { quantum.map(|_| Quantum::FCFS) } else { quantum };
+                out.emit(process.id, EventKind::Dispatch { quantum: quantum.map_or(0, |quantum| quantum.raw()) });
+                if let Some(level) = level
+                    && let Some(level) = simulation::dispatch_level(self.sched.dispatched_level(), &self.quanta, level)
+                {
+                    out.note_level(process.id, level as u32);
+                }
+                out.note_heap_size(self.sched.heap_size());
+                //a process the scheduler was never given, or one that finished, is not run
+                let Some(mut burst) = self.pending.remove(&process.id) else {
+                    continue;
+                };
+                //ticks left of the quantum, None when it runs to completion
+                let mut left = quantum.and_then(|quantum| quantum.limit());
+                if self.run == Run::ToCompletion {
+                    burst.skip_first_tick();
+                }
+                loop {
+                    out.emit(process.id, EventKind::Execute);
+                    CLOCK.advance(CLOCK.tick());
+                    left = left.map(|left| left - 1);
+                    let ends = burst.run() || left == Some(0);
+                    //the jobs arriving as the slice runs out come after the process, unless they go first
+                    if !ends || (self.expiry_order == ExpiryOrder::ArrivalsFirst && !burst.is_done()) {
+                        self.admit(out);
+                    }
+                    self.tick(out, Some(&process), left);
+                    if ends || out.stopped() {
+                        break;
+                    }
+                }
+                if self.run == Run::ToCompletion || burst.is_done() {
+                    out.emit(process.id, EventKind::Finish);
+                } else {
+                    out.emit(process.id, EventKind::Preempt);
+                    burst.split();
+                    self.pending.insert(process.id, burst);
+                    self.sched.add_process(process);
+                }
+                self.admit(out);
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds `job` to the jobs still to arrive, at the first tick not handed
+    /// over yet if its own has passed.
+    fn add(&mut self, job: Job) {
+        self.arrivals.entry(job.arrival.max(self.next)).or_default().push(job.id);
+        self.priorities.insert(job.id, job.priority);
+        self.pending.insert(job.id, Burst::new(job.burst));
+    }
+
+    /// Calls the tick hook, then adds the jobs it submitted.
+    fn tick(&mut self, out: &mut Recorder, running: Option<&PCB>, left: Option<u32>) {
+        let Some(hook) = &mut self.on_tick else {
+            return;
+        };
+        let mut submitted = Vec::new();
+        hook(&mut Context { sched: &self.sched, out, running, left, submitted: &mut submitted, annotations: &mut self.annotations });
+        for job in submitted {
+            self.add(job);
+        }
+    }
+
+    /// Makes the markers due by now, then hands the scheduler every job
+    /// that has arrived by now and was not handed over yet.
+    fn admit(&mut self, out: &mut Recorder) {
+        let now = CLOCK.now_ticks();
+        if now < self.next {
+            return;
+        }
+        while let Some(marker) = self.annotations.first_entry().filter(|marker| *marker.key() <= now) {
+            for label in marker.remove() {
+                out.annotate(label);
+            }
+        }
+        for id in self.arrivals.range(self.next..=now).flat_map(|(_, ids)| ids) {
+            let priority = self.priorities[id];
+            out.emit(*id, EventKind::Arrive { priority });
+            self.sched.add_process(PCB { id: *id, priority, time_added: None, time_scheduled: None });
+        }
+        self.next = now + 1;
+    }
+}
@@ -13,35 +13,126 @@
 //! to determine when to preempt processes or record CPU burst times.
 //! This simulated clock allows you to:
 //! - Track when processes are added or scheduled.
-/// - Measure elapsed time between events.
-/// - Control time manually for testing and debugging schedulers.
+//! - Measure elapsed time between events.
+//! - Control time manually for testing and debugging schedulers.
+//!
+//! ## Example
+//! ```
+//! use scheduler::clock::{CLOCK, Clock};
+//! use std::time::Duration;
+//!
+//! // Reset the clock to zero
+//! CLOCK.set_now(Duration::from_nanos(0));
+//!
+//! // Advance the clock by 5 milliseconds
+//! CLOCK.advance(Duration::from_millis(5));
+//!
+//! // Get the current simulation time
+//! let current = CLOCK.now();
+//! println!("Simulated time: {:?}", current);
+//! ```
+//!
+//! ## Thread Safety
+//! The [`Clock`] uses atomic operations internally, allowing multiple
+//! threads to safely read or update the simulated time concurrently.
+//! The assignment doesn't need this protection as it is not running
+//! in a multi-threaded behavior but considered best practice for
+//! use of a global static instance of the CLOCK
+//!
+//! ## Ticks and Time Units
+//! The simulators advance the clock one **tick** at a time. By default a tick
+//! is one nanosecond, so "time cycles" and nanoseconds are the same thing.
+//! Running with `--time-unit us` or `--time-unit ms` makes each tick one
+//! microsecond or millisecond instead; see [`TimeUnit`] and [`Clock::set_tick`].
+//!
+//! ## Pacing
+//! A simulation normally runs as fast as it can. To watch one live, e.g. in
+//! a lecture demo, a [`Pacer`] holds the simulated clock back to a fixed
+//! number of ticks per second of real time.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// The unit a workload's times are written in, and the length of one tick.
 ///
-/// ## Example
+/// # Example
 /// ```
-/// use your_crate_name::clock::{CLOCK, Clock};
-/// use std::time::Duration;
-///
-/// // Reset the clock to zero
-/// CLOCK.set_now(Duration::from_nanos(0));
-///
-/// // Advance the clock by 5 milliseconds
-/// CLOCK.advance(Duration::from_millis(5));
+/// use scheduler::clock::TimeUnit;
 ///
-/// // Get the current simulation time
-/// let current = CLOCK.now();
-/// println!("Simulated time: {:?}", current);
+/// let unit: TimeUnit = "us".parse().unwrap();
+/// assert_eq!(unit.nanos(), 1_000);
+/// assert_eq!(unit.format_ns(42_000), "42us");
+/// assert_eq!(TimeUnit::Ms.format_ns(1_500_000), "1.5ms");
 /// ```
-///
-/// ## Thread Safety
-/// The [`Clock`] uses atomic operations internally, allowing multiple
-/// threads to safely read or update the simulated time concurrently.
-/// The assignment doesn't need this protection as it is not running
-/// in a multi-threaded behavior but considered best practice for
-/// use of a global static instance of the CLOCK
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    #[default]
+    Ns,
+    Us,
+    Ms,
+}
 
-use std::sync::LazyLock;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Duration;
+impl TimeUnit {
+    /// Every supported unit, smallest first.
+    pub const ALL: [TimeUnit; 3] = [TimeUnit::Ns, TimeUnit::Us, TimeUnit::Ms];
+
+    /// Returns how many nanoseconds one of this unit is.
+    pub fn nanos(self) -> u64 {
+        match self {
+            TimeUnit::Ns => 1,
+            TimeUnit::Us => 1_000,
+            TimeUnit::Ms => 1_000_000,
+        }
+    }
+
+    /// Returns the suffix used for this unit in input files and reports.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            TimeUnit::Ns => "ns",
+            TimeUnit::Us => "us",
+            TimeUnit::Ms => "ms",
+        }
+    }
+
+    /// Returns the length of one tick when running in this unit.
+    pub fn tick(self) -> Duration {
+        Duration::from_nanos(self.nanos())
+    }
+
+    /// Formats a nanosecond timestamp in this unit, e.g. `1500000` as `1.5ms`.
+    pub fn format_ns(self, ns: u64) -> String {
+        let whole = ns / self.nanos();
+        let frac = ns % self.nanos();
+        if frac == 0 {
+            format!("{}{}", whole, self.suffix())
+        } else {
+            let value = ns as f64 / self.nanos() as f64;
+            format!("{}{}", value, self.suffix())
+        }
+    }
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.suffix())
+    }
+}
+
+impl FromStr for TimeUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TimeUnit::ALL
+            .into_iter()
+            .find(|unit| unit.suffix() == s)
+            .ok_or_else(|| format!("unknown time unit '{}', expected ns, us or ms", s))
+    }
+}
 
 /// A thread-safe simulation clock that tracks virtual time in nanoseconds.
 ///
@@ -51,10 +142,11 @@ use std::time::Duration;
 ///
 /// # Fields
 /// - `now_ns`: The current simulated time in nanoseconds, stored atomically.
+/// - `tick_ns`: The length of one simulation tick in nanoseconds (defaults to `1`).
 ///
 /// # Example
 /// ```
-/// use your_crate_name::clock::Clock;
+/// use scheduler::clock::Clock;
 /// use std::time::Duration;
 ///
 /// let clock = Clock::new();
@@ -63,6 +155,7 @@ use std::time::Duration;
 /// ```
 pub struct Clock {
     now_ns: AtomicU64,
+    tick_ns: AtomicU64,
 }
 
 impl Clock {
@@ -73,19 +166,19 @@ impl Clock {
     ///
     /// # Example
     /// ```
-    /// use your_crate_name::clock::Clock;
+    /// use scheduler::clock::Clock;
     /// let clock = Clock::new();
     /// assert_eq!(clock.now_ns(), 0);
     /// ```
     pub const fn new() -> Self {
-        Self { now_ns: AtomicU64::new(0) }
+        Self { now_ns: AtomicU64::new(0), tick_ns: AtomicU64::new(1) }
     }
 
     /// Returns the current simulated time as a [`Duration`].
     ///
     /// # Example
     /// ```
-    /// use your_crate_name::clock::Clock;
+    /// use scheduler::clock::Clock;
     /// use std::time::Duration;
     ///
     /// let clock = Clock::new();
@@ -99,7 +192,7 @@ impl Clock {
     ///
     /// # Example
     /// ```
-    /// use your_crate_name::clock::Clock;
+    /// use scheduler::clock::Clock;
     ///
     /// let clock = Clock::new();
     /// assert_eq!(clock.now_ns(), 0);
@@ -115,7 +208,7 @@ impl Clock {
     ///
     /// # Example
     /// ```
-    /// use your_crate_name::clock::Clock;
+    /// use scheduler::clock::Clock;
     /// use std::time::Duration;
     ///
     /// let clock = Clock::new();
@@ -133,7 +226,7 @@ impl Clock {
     ///
     /// # Example
     /// ```
-    /// use your_crate_name::clock::Clock;
+    /// use scheduler::clock::Clock;
     /// use std::time::Duration;
     ///
     /// let clock = Clock::new();
@@ -154,7 +247,7 @@ impl Clock {
     ///
     /// # Example
     /// ```
-    /// use your_crate_name::clock::Clock;
+    /// use scheduler::clock::Clock;
     /// use std::time::Duration;
     ///
     /// let clock = Clock::new();
@@ -166,6 +259,109 @@ impl Clock {
         let now = self.now_ns.load(Ordering::Relaxed);
         Duration::from_nanos(now - start_ns)
     }
+
+    /// Sets the length of one simulation tick.
+    ///
+    /// # Parameters
+    /// - `tick`: The duration a single tick represents.
+    ///
+    /// # Example
+    /// ```
+    /// use scheduler::clock::Clock;
+    /// use std::time::Duration;
+    ///
+    /// let clock = Clock::new();
+    /// clock.set_tick(Duration::from_micros(1));
+    /// clock.advance(clock.tick());
+    /// assert_eq!(clock.now_ns(), 1_000);
+    /// assert_eq!(clock.now_ticks(), 1);
+    /// ```
+    pub fn set_tick(&self, tick: Duration) {
+        self.tick_ns.store(tick.as_nanos().max(1) as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the length of one simulation tick as a [`Duration`].
+    pub fn tick(&self) -> Duration {
+        Duration::from_nanos(self.tick_ns.load(Ordering::Relaxed))
+    }
+
+    /// Returns the current simulated time in whole ticks.
+    ///
+    /// With the default one-nanosecond tick this is the same as [`Clock::now_ns`].
+    pub fn now_ticks(&self) -> u64 {
+        self.now_ns() / self.tick_ns.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Paces simulated time against the wall clock, see [Pacing](self#pacing).
+///
+/// # Example
+/// ```
+/// use std::time::{Duration, Instant};
+/// use scheduler::clock::{CLOCK, Pacer};
+///
+/// CLOCK.set_tick(Duration::from_nanos(1));
+/// CLOCK.set_now(Duration::ZERO);
+/// let mut pacer = Pacer::new(1000.0);
+/// let start = Instant::now();
+/// CLOCK.advance(Duration::from_nanos(20));
+/// // 20 ticks at 1000 ticks per second take 20ms
+/// pacer.wait();
+/// assert!(start.elapsed() >= Duration::from_millis(20));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Pacer {
+    ticks_per_second: f64,
+    /// the wall-clock time and tick the pace is measured from
+    start: Instant,
+    origin: u64,
+}
+
+impl Pacer {
+    /// Paces the [`CLOCK`] at `ticks_per_second`, starting from now.
+    ///
+    /// # Panics
+    /// If `ticks_per_second` is not a positive number.
+    pub fn new(ticks_per_second: f64) -> Self {
+        assert!(ticks_per_second > 0.0 && ticks_per_second.is_finite(), "the pace must be a positive number of ticks per second");
+        Self { ticks_per_second, start: Instant::now(), origin: CLOCK.now_ticks() }
+    }
+
+    /// The ticks that pass per second of real time.
+    pub fn ticks_per_second(&self) -> f64 {
+        self.ticks_per_second
+    }
+
+    /// Changes the pace from now on.
+    ///
+    /// # Panics
+    /// If `ticks_per_second` is not a positive number.
+    pub fn set_ticks_per_second(&mut self, ticks_per_second: f64) {
+        *self = Self::new(ticks_per_second);
+    }
+
+    /// Starts measuring the pace afresh from now, so time spent paused is
+    /// not made up for by running faster.
+    pub fn restart(&mut self) {
+        *self = Self::new(self.ticks_per_second);
+    }
+
+    /// Sleeps until the wall clock has caught up with the current tick of
+    /// the [`CLOCK`].
+    pub fn wait(&mut self) {
+        let ticks = CLOCK.now_ticks().saturating_sub(self.origin);
+        let due = self.start + Duration::from_secs_f64(ticks as f64 / self.ticks_per_second);
+        let now = Instant::now();
+        if due > now {
+            std::thread::sleep(due - now);
+        }
+    }
 }
 
 /// A lazily initialized, global simulation clock instance.
@@ -175,10 +371,10 @@ impl Clock {
 ///
 /// # Example
 /// ```
-/// use your_crate_name::clock::CLOCK;
+/// use scheduler::clock::CLOCK;
 /// use std::time::Duration;
 ///
 /// CLOCK.set_now(Duration::from_micros(500));
 /// assert_eq!(CLOCK.now().as_micros(), 500);
 /// ```
-pub static CLOCK: LazyLock<Clock> = LazyLock::new(|| Clock::new());
+pub static CLOCK: LazyLock<Clock> = LazyLock::new(Clock::new);
@@ -0,0 +1,562 @@
+//! # Config Module
+//!
+//! Tunable parameters for the schedulers, one struct per scheduler.
+//!
+//! Every struct's [`Default`] is the configuration the assignment asks for
+//! and the reference outputs were produced with, so a run without a config
+//! file behaves exactly as before. All times are in ticks.
+//!
+//! ## Config Files
+//! The simulator's `--config` option reads a TOML file with one optional
+//! table per scheduler. Missing tables and fields keep their defaults:
+//! ```toml
+//! [simplerr]
+//! quantum = 8
+//!
+//! [mlf]
+//! quanta = [0, 8, 2]
+//! demote_after = 500
+//! demotion = "allotment"
+//!
+//! [mlf.rules]
+//! boost_every = 200
+//! boost_on_io = true
+//!
+//! [stack]
+//! levels = ["rr:2", "rr:4", "sjf"]
+//!
+//! [spn]
+//! error = 50
+//!
+//! [engine]
+//! timers = "wheel"
+//! ```
+//! The configuration used is recorded in the run's results, see
+//! [`RunConfig`](crate::results::RunConfig).
+//!
+//! ## Example
+//! ```
+//! use scheduler::config::SchedulerConfig;
+//!
+//! let config = SchedulerConfig::from_toml("[mlrr]\nquanta = [2, 1]").unwrap();
+//! assert_eq!(config.mlrr.quanta, vec![2, 1]);
+//! assert_eq!(config.simplerr.quantum, 4);
+//!
+//! let err = SchedulerConfig::from_toml("[mlrr]\nquanta = []").unwrap_err();
+//! assert_eq!(err, "mlrr.quanta: must have at least one level");
+//! let err = SchedulerConfig::from_toml("[simplerr]\nquantm = 2").unwrap_err();
+//! assert_eq!(err, "simplerr.quantm: unknown option");
+//! let err = SchedulerConfig::from_toml("[stack]\nlevels = [\"rr:2\", \"lottery\"]").unwrap_err();
+//! assert!(err.starts_with("lottery: unknown policy"), "{}", err);
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::policy::Policy;
+use crate::timer::TimerKind;
+
+/// Configuration for the `simplerr` scheduler.
+///
+/// # Fields
+/// - `quantum`: Time slice every process is given.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimpleRRConfig {
+    pub quantum: u32,
+}
+
+impl Default for SimpleRRConfig {
+    fn default() -> Self {
+        Self { quantum: 4 }
+    }
+}
+
+impl SimpleRRConfig {
+    /// Checks that the configuration describes a usable scheduler.
+    pub fn validate(&self) -> Result<(), String> {
+        positive("quantum", self.quantum as u64)
+    }
+}
+
+/// Configuration for the `mlrr` scheduler.
+///
+/// # Fields
+/// - `quanta`: Time slice of each priority level, highest priority first. The
+///   number of entries is the number of levels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MlrrConfig {
+    pub quanta: Vec<u32>,
+}
+
+impl Default for MlrrConfig {
+    fn default() -> Self {
+        Self { quanta: vec![4, 3, 2, 1] }
+    }
+}
+
+impl MlrrConfig {
+    /// Checks that the configuration describes a usable scheduler.
+    pub fn validate(&self) -> Result<(), String> {
+        levels(&self.quanta)?;
+        self.quanta.iter().try_for_each(|&q| positive("quanta", q as u64))
+    }
+}
+
+/// Configuration for the `simplemlf` scheduler.
+///
+/// # Fields
+/// - `quanta`: Time slice of each priority level, highest priority first; `0`
+///   runs processes at that level to completion (FCFS).
+/// - `promote_after`: How long a process may wait without being scheduled
+///   before it is promoted one level.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimpleMlfConfig {
+    pub quanta: Vec<u32>,
+    pub promote_after: u64,
+}
+
+impl Default for SimpleMlfConfig {
+    fn default() -> Self {
+        Self { quanta: vec![0, 4, 1], promote_after: 1000 }
+    }
+}
+
+impl SimpleMlfConfig {
+    /// Checks that the configuration describes a usable scheduler.
+    pub fn validate(&self) -> Result<(), String> {
+        levels(&self.quanta)?;
+        positive("promote_after", self.promote_after)
+    }
+}
+
+/// Configuration for the full `mlf` scheduler.
+///
+/// # Fields
+/// - `quanta`: Time slice of each priority level, highest priority first; `0`
+///   runs processes at that level to completion (FCFS).
+/// - `promote_after`: How long a process may wait without being scheduled
+///   before it is promoted one level.
+/// - `demote_after`: How long a process may run before it is demoted one
+///   level.
+/// - `demotion`: Which running time `demote_after` limits, see [`Demotion`].
+/// - `rules`: The OSTEP feedback rules to apply on top, see [`MlfqRules`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MlfqConfig {
+    pub quanta: Vec<u32>,
+    pub promote_after: u64,
+    pub demote_after: u64,
+    pub demotion: Demotion,
+    pub rules: MlfqRules,
+}
+
+impl Default for MlfqConfig {
+    fn default() -> Self {
+        Self {
+            quanta: vec![0, 4, 1],
+            promote_after: 1000,
+            demote_after: 1000,
+            demotion: Demotion::Run,
+            rules: MlfqRules::default(),
+        }
+    }
+}
+
+impl MlfqConfig {
+    /// Checks that the configuration describes a usable scheduler.
+    pub fn validate(&self) -> Result<(), String> {
+        levels(&self.quanta)?;
+        positive("promote_after", self.promote_after)?;
+        positive("demote_after", self.demote_after)
+    }
+
+    /// Whether a process has run long enough at its level to be demoted.
+    ///
+    /// # Parameters
+    /// - `run`: How long the process has been running since it was last
+    ///   dispatched.
+    /// - `used`: How much CPU time it has used at its current level in total,
+    ///   including `run`.
+    ///
+    /// # Example
+    /// ```
+    /// use scheduler::config::{Demotion, MlfqConfig};
+    ///
+    /// let run = MlfqConfig { demote_after: 10, ..MlfqConfig::default() };
+    /// let allotment = MlfqConfig { demotion: Demotion::Allotment, ..run.clone() };
+    /// // the sixth dispatch of a job that blocks after 3 ticks every time
+    /// assert!(!run.demotes(3, 18));
+    /// assert!(allotment.demotes(3, 18));
+    /// assert!(run.demotes(11, 11) && !run.demotes(10, 10));
+    /// ```
+    pub fn demotes(&self, run: u64, used: u64) -> bool {
+        match self.demotion {
+            Demotion::Run if !self.rules.allotment => run > self.demote_after,
+            _ => used > self.demote_after,
+        }
+    }
+
+    /// The level a process at `level` goes to when it gives up the CPU,
+    /// following the demotion threshold and the [`rules`](MlfqRules): it
+    /// moves down a level if [`demotes`](Self::demotes) says so or if
+    /// `demote_on_expiry` is on and it used up its whole time slice, and up
+    /// a level if `boost_on_io` is on and it `blocked` for I/O. `run` and
+    /// `used` are as for [`demotes`](Self::demotes).
+    ///
+    /// # Example
+    /// ```
+    /// use scheduler::config::{MlfqConfig, MlfqRules};
+    ///
+    /// let config = MlfqConfig { demote_after: 10, ..MlfqConfig::default() };
+    /// // level 1 has a 4-tick slice
+    /// assert_eq!(config.level_after(1, 4, 4, false), 1);
+    /// let expiry = MlfqConfig { rules: "expiry".parse().unwrap(), ..config.clone() };
+    /// assert_eq!(expiry.level_after(1, 4, 4, false), 2);
+    /// let io: MlfqRules = "io".parse().unwrap();
+    /// assert_eq!(MlfqConfig { rules: io, ..config.clone() }.level_after(1, 2, 2, true), 0);
+    /// // nothing moves past the bottom level
+    /// assert_eq!(config.level_after(2, 11, 11, false), 2);
+    /// ```
+    pub fn level_after(&self, level: usize, run: u64, used: u64, blocked: bool) -> usize {
+        let bottom = self.quanta.len().saturating_sub(1);
+        let expired = matches!(self.quanta.get(level), Some(&quantum) if quantum > 0 && run >= quantum as u64);
+        if self.demotes(run, used) || (self.rules.demote_on_expiry && expired) {
+            return (level + 1).min(bottom);
+        }
+        match blocked && self.rules.boost_on_io {
+            true => level.saturating_sub(1),
+            false => level,
+        }
+    }
+}
+
+/// The OSTEP feedback rules the `mlf` scheduler applies on top of its
+/// thresholds, as data, so that rule combinations can be switched on per
+/// run with a `[mlf.rules]` table and compared with
+/// `scheduler experiment mlfq-rules`.
+///
+/// With every rule off, the default, the scheduler follows the assignment:
+/// `demote_after` and `promote_after` alone.
+///
+/// # Fields
+/// - `demote_on_expiry`: A process that uses up a whole time slice is
+///   demoted one level (OSTEP's rule 4a).
+/// - `allotment`: `demote_after` limits the CPU time a process uses at its
+///   level over all of its runs, not a single run (rule 4); the same as
+///   `demotion = "allotment"`.
+/// - `boost_every`: Every this many ticks all processes are moved back to
+///   the top level (rule 5), `0` for never.
+/// - `boost_on_io`: A process that blocks for I/O moves up one level, the
+///   interactive boost of older Unix schedulers.
+///
+/// A rule set is written on the command line as its rules joined with `+`:
+/// `expiry`, `allotment`, `boost=<ticks>` and `io`, or `none`.
+///
+/// # Example
+/// ```
+/// use scheduler::config::MlfqRules;
+///
+/// let rules: MlfqRules = "io+boost=100+expiry".parse().unwrap();
+/// assert_eq!(rules, MlfqRules { demote_on_expiry: true, boost_every: 100, boost_on_io: true, ..MlfqRules::default() });
+/// assert_eq!(rules.to_string(), "expiry+boost=100+io");
+/// assert_eq!(MlfqRules::default().to_string(), "none");
+/// assert!(rules.boosts_at(200) && !rules.boosts_at(150) && !rules.boosts_at(0));
+/// assert_eq!("boost=0".parse::<MlfqRules>().unwrap_err(), "boost=0: the period must be a positive number of ticks");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MlfqRules {
+    pub demote_on_expiry: bool,
+    pub allotment: bool,
+    pub boost_every: u64,
+    pub boost_on_io: bool,
+}
+
+impl MlfqRules {
+    /// Whether every process is moved back to the top level at tick `tick`.
+    pub fn boosts_at(&self, tick: u64) -> bool {
+        self.boost_every > 0 && tick > 0 && tick.is_multiple_of(self.boost_every)
+    }
+}
+
+impl FromStr for MlfqRules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut rules = MlfqRules::default();
+        if s.trim() == "none" {
+            return Ok(rules);
+        }
+        for rule in s.split('+').map(str::trim) {
+            match rule.split_once('=') {
+                None if rule == "expiry" => rules.demote_on_expiry = true,
+                None if rule == "allotment" => rules.allotment = true,
+                None if rule == "io" => rules.boost_on_io = true,
+                Some(("boost", ticks)) => match ticks.parse::<u64>() {
+                    Ok(0) | Err(_) => return Err(format!("{}: the period must be a positive number of ticks", rule)),
+                    Ok(ticks) => rules.boost_every = ticks,
+                },
+                _ => return Err(format!("{}: unknown rule, expected expiry, allotment, boost=<ticks>, io or none", rule)),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+impl fmt::Display for MlfqRules {
+    /// Formats the rule set the way it is parsed, e.g. `expiry+boost=100`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rules = Vec::new();
+        if self.demote_on_expiry {
+            rules.push("expiry".to_string());
+        }
+        if self.allotment {
+            rules.push("allotment".to_string());
+        }
+        if self.boost_every > 0 {
+            rules.push(format!("boost={}", self.boost_every));
+        }
+        if self.boost_on_io {
+            rules.push("io".to_string());
+        }
+        match rules.is_empty() {
+            true => f.write_str("none"),
+            false => f.write_str(&rules.join("+")),
+        }
+    }
+}
+
+/// Which running time the `mlf` scheduler's `demote_after` limits, set with
+/// `demotion = "run"` or `demotion = "allotment"`.
+///
+/// `rules.allotment = true` (see [`MlfqRules`]) does the same as
+/// `demotion = "allotment"`.
+///
+/// Limiting single runs can be gamed: a process that gives up the CPU just
+/// before the limit keeps its level however much CPU it uses in total (try
+/// the `gaming` built-in workload with `--gaming`). Charging every run
+/// against a total allotment per level, OSTEP's rule 4, closes that
+/// loophole. The scheduler then has to remember how much CPU each process
+/// has used at its level across dispatches, and start counting afresh when
+/// the process changes level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Demotion {
+    /// A process is demoted once a single run, from dispatch to giving up
+    /// the CPU, exceeds `demote_after`. This is what the assignment
+    /// describes.
+    #[default]
+    Run,
+    /// A process is demoted once its CPU time at its level, summed over all
+    /// of its runs there, exceeds `demote_after`.
+    Allotment,
+}
+
+/// Configuration for the `bsd` decay-usage scheduler.
+///
+/// # Fields
+/// - `quantum`: Time slice every process is given.
+/// - `second`: Length of the simulated "second" after which CPU usage is
+///   decayed and every priority recalculated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BsdConfig {
+    pub quantum: u32,
+    pub second: u64,
+}
+
+impl Default for BsdConfig {
+    /// 4.3BSD's values at its 100 Hz clock: a 100 ms time slice and a 1 s
+    /// recalculation period.
+    fn default() -> Self {
+        Self { quantum: 10, second: 100 }
+    }
+}
+
+impl BsdConfig {
+    /// Checks that the configuration describes a usable scheduler.
+    pub fn validate(&self) -> Result<(), String> {
+        positive("quantum", self.quantum as u64)?;
+        positive("second", self.second)
+    }
+}
+
+/// Configuration for the `fgbg` foreground/background scheduler.
+///
+/// # Fields
+/// - `quantum`: Time slice of the round-robin foreground queue.
+/// - `background_slice`: How long the background queue runs at a time
+///   before the foreground may take the CPU back.
+/// - `share`: Percentage of the CPU the foreground gets while both queues
+///   have work.
+/// - `window`: Length of the sliding window the split is enforced over.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FgBgConfig {
+    pub quantum: u32,
+    pub background_slice: u32,
+    pub share: u64,
+    pub window: u64,
+}
+
+impl Default for FgBgConfig {
+    /// The textbook's 80/20 split.
+    fn default() -> Self {
+        Self { quantum: 4, background_slice: 1, share: 80, window: 100 }
+    }
+}
+
+impl FgBgConfig {
+    /// Checks that the configuration describes a usable scheduler.
+    pub fn validate(&self) -> Result<(), String> {
+        positive("quantum", self.quantum as u64)?;
+        positive("background_slice", self.background_slice as u64)?;
+        positive("window", self.window)?;
+        if self.share > 100 {
+            return Err("share: must be a percentage between 0 and 100".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Configuration for the `stack` scheduler, a
+/// [`Stack`](crate::feedback::Stack) of policies.
+///
+/// # Fields
+/// - `levels`: The policy of each level, highest priority first, written as
+///   `"rr:<quantum>"`, `"fcfs"` or `"sjf"`.
+/// - `boost_every`: How often every process is moved back to the top
+///   level, `0` for never.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StackConfig {
+    pub levels: Vec<Policy>,
+    pub boost_every: u64,
+}
+
+impl Default for StackConfig {
+    /// OSTEP's three-level example with a priority boost.
+    fn default() -> Self {
+        Self { levels: vec![Policy::RR(2), Policy::RR(4), Policy::FCFS], boost_every: 100 }
+    }
+}
+
+impl StackConfig {
+    /// Checks that the configuration describes a usable scheduler.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.levels.is_empty() {
+            return Err("levels: must have at least one level".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Configuration for the `spn` scheduler, an [`SPN`](crate::policy::SPN)
+/// told a noisy estimate of every job's CPU time.
+///
+/// # Fields
+/// - `error`: How far off an estimate may be, in percent of the job's true
+///   CPU time; every estimate is drawn uniformly from `burst ± error%`, so
+///   `0` tells the scheduler the exact burst.
+/// - `seed`: Seed of the estimation errors; the same seed always gives every
+///   job the same estimate.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpnConfig {
+    pub error: u32,
+    pub seed: u64,
+}
+
+/// Configuration of the simulator's engine, the
+/// [`Simulation`](crate::simulation::Simulation) every scheduler other than
+/// the student ones runs in. Those keep to the loops their reference outputs
+/// were made with, which have no timers, and refuse a `timers` other than
+/// the default.
+///
+/// # Fields
+/// - `timers`: How the engine keeps its arrivals, I/O completions and
+///   timeouts, see the [`timer`](crate::timer) module.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineConfig {
+    pub timers: TimerKind,
+}
+
+/// The configuration of every scheduler, as read from a `--config` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    pub simplerr: SimpleRRConfig,
+    pub mlrr: MlrrConfig,
+    pub simplemlf: SimpleMlfConfig,
+    pub mlf: MlfqConfig,
+    pub bsd: BsdConfig,
+    pub fgbg: FgBgConfig,
+    pub stack: StackConfig,
+    pub spn: SpnConfig,
+    pub engine: EngineConfig,
+}
+
+impl SchedulerConfig {
+    /// Parses and validates a TOML config file.
+    ///
+    /// Unknown tables and fields are rejected, so a misspelled option is not
+    /// silently replaced by its default.
+    ///
+    /// # Returns
+    /// The configuration, or a message describing the first problem found.
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let table: toml::Table = toml::from_str(text).map_err(|err| err.message().trim().to_string())?;
+        let known = toml::Table::try_from(Self::default()).map_err(|err| err.to_string())?;
+        check_keys(&table, &known, "")?;
+        let config: Self = table.try_into().map_err(|err: toml::de::Error| err.message().trim().to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates every scheduler's configuration, prefixing errors with the
+    /// table they were found in, e.g. `"mlf.demote_after: must be positive"`.
+    pub fn validate(&self) -> Result<(), String> {
+        let section = |name: &str, result: Result<(), String>| result.map_err(|err| format!("{}.{}", name, err));
+        section("simplerr", self.simplerr.validate())?;
+        section("mlrr", self.mlrr.validate())?;
+        section("simplemlf", self.simplemlf.validate())?;
+        section("mlf", self.mlf.validate())?;
+        section("bsd", self.bsd.validate())?;
+        section("fgbg", self.fgbg.validate())?;
+        section("stack", self.stack.validate())
+    }
+}
+
+fn check_keys(given: &toml::Table, known: &toml::Table, prefix: &str) -> Result<(), String> {
+    for (key, value) in given {
+        let name = format!("{}{}", prefix, key);
+        match (value, known.get(key)) {
+            (_, None) => return Err(format!("{}: unknown option", name)),
+            (toml::Value::Table(given), Some(toml::Value::Table(known))) => {
+                check_keys(given, known, &format!("{}.", name))?
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn positive(name: &str, value: u64) -> Result<(), String> {
+    if value == 0 {
+        return Err(format!("{}: must be positive", name));
+    }
+    Ok(())
+}
+
+fn levels(quanta: &[u32]) -> Result<(), String> {
+    if quanta.is_empty() {
+        return Err("quanta: must have at least one level".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,270 @@
+//! # Control Module
+//!
+//! Steering a running simulation from outside, for live demos.
+//!
+//! A [`ControlFifo`] reads lines from a named pipe while the simulation
+//! runs, so another terminal can inject jobs into a paced run (see
+//! [`Pacer`](crate::clock::Pacer)) by writing to the pipe:
+//! ```text
+//! $ scheduler -s fgbg -i demo.in --pace 5 --inject /tmp/sched.fifo
+//! $ echo "7 0 12 1" > /tmp/sched.fifo    # in another terminal
+//! ```
+//! Each line is a job in the input file format with the priority column,
+//! `<id> <arrival> <burst> <priority> [class]`, except that the arrival counts from the moment the
+//! line is read, so `0` means "now". An `%annotate <time> <label>` line puts
+//! a marker in the run the same way, see
+//! [Annotations](crate::input#annotations):
+//! ```text
+//! $ echo "%annotate 0 a CPU hog arrives" > /tmp/sched.fifo
+//! ```
+//!
+//! The pipe is read on a thread of its own, since opening a named pipe
+//! waits for a writer. Lines are handed over whenever the simulation asks
+//! for them, in the order they were written, and writers may come and go.
+//!
+//! ## Keys
+//! [`Controls`] let the presenter narrate a paced run at will, from the
+//! terminal it runs in:
+//!
+//! - space pauses the run, and resumes it;
+//! - `s` runs one more tick and pauses again;
+//! - the up and right arrows double the pace, the down and left arrows
+//!   halve it.
+//!
+//! ```
+//! use std::sync::mpsc;
+//! use std::time::Duration;
+//! use scheduler::clock::{CLOCK, Pacer};
+//! use scheduler::control::{Controls, Key};
+//!
+//! assert_eq!(Key::parse(b" \x1b[As"), [Key::Pause, Key::Faster, Key::Step]);
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let mut pacer = Pacer::new(1000.0);
+//! let (keys, pressed) = mpsc::channel();
+//! let mut controls = Controls::from_keys(pressed);
+//! keys.send(Key::Faster).unwrap();
+//! controls.wait(&mut pacer);
+//! assert_eq!(pacer.ticks_per_second(), 2000.0);
+//!
+//! // paused, the run waits for a key; a step lets one tick through
+//! keys.send(Key::Pause).unwrap();
+//! keys.send(Key::Step).unwrap();
+//! controls.wait(&mut pacer);
+//! assert!(controls.is_paused());
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+
+use crate::CLOCK;
+use crate::clock::Pacer;
+
+/// Lines written to a named pipe, see the [module documentation](self).
+pub struct ControlFifo {
+    path: PathBuf,
+    lines: Receiver<String>,
+}
+
+impl ControlFifo {
+    /// Starts reading the named pipe at `path`, creating it with `mkfifo` if
+    /// it does not exist yet.
+    ///
+    /// # Errors
+    /// If the pipe cannot be created.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            let status = std::process::Command::new("mkfifo").arg(path).status()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("mkfifo {} failed", path.display())));
+            }
+        }
+        let (sender, lines) = mpsc::channel();
+        let reader = path.to_path_buf();
+        std::thread::spawn(move || {
+            //every writer closing the pipe ends a file; wait for the next one
+            while let Ok(file) = File::open(&reader) {
+                for line in BufReader::new(file).lines().map_while(Result::ok) {
+                    if sender.send(line).is_err() {
+                        return;
+                    }
+                }
+                if !is_fifo(&reader) {
+                    return;
+                }
+            }
+        });
+        Ok(Self { path: path.to_path_buf(), lines })
+    }
+
+    /// The path of the pipe.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The non-empty lines written since the last call, without waiting for
+    /// more.
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.lines.try_iter().filter(|line| !line.trim().is_empty())
+    }
+}
+
+/// A key that steers a paced run, see [Keys](self#keys).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// Space: pause or resume.
+    Pause,
+    /// `s`: run one tick, then pause.
+    Step,
+    /// Up or right arrow: double the pace.
+    Faster,
+    /// Down or left arrow: halve the pace.
+    Slower,
+}
+
+impl Key {
+    /// The keys pressed in `bytes`, as a terminal sends them when it does
+    /// not wait for a whole line. Other keys are ignored.
+    pub fn parse(bytes: &[u8]) -> Vec<Key> {
+        let mut keys = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            match &bytes[i..] {
+                [b'\x1b', b'[', arrow, ..] => {
+                    keys.extend(match arrow {
+                        b'A' | b'C' => Some(Key::Faster),
+                        b'B' | b'D' => Some(Key::Slower),
+                        _ => None,
+                    });
+                    i += 2;
+                }
+                [b' ', ..] => keys.push(Key::Pause),
+                [b's' | b'S', ..] => keys.push(Key::Step),
+                _ => {}
+            }
+            i += 1;
+        }
+        keys
+    }
+}
+
+/// Pause, step and speed controls of a paced run, see [Keys](self#keys).
+pub struct Controls {
+    keys: Receiver<Key>,
+    paused: bool,
+    /// the terminal settings to restore, as saved by `stty -g`
+    terminal: Option<String>,
+}
+
+impl Controls {
+    /// Controls read from the keys pressed in the terminal on stdin. The
+    /// terminal stops waiting for whole lines and echoing keys until the
+    /// controls are dropped.
+    ///
+    /// # Returns
+    /// `None` when stdin is not a terminal.
+    pub fn stdin() -> Option<Self> {
+        if !io::stdin().is_terminal() {
+            return None;
+        }
+        let stty = |args: &[&str]| {
+            Command::new("stty").args(args).stdin(Stdio::inherit()).output().ok().filter(|out| out.status.success())
+        };
+        let terminal = stty(&["-g"]).map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string());
+        stty(&["-icanon", "-echo", "min", "1"]);
+        let (sender, keys) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut buf = [0; 16];
+            while let Ok(n @ 1..) = io::stdin().read(&mut buf) {
+                for key in Key::parse(&buf[..n]) {
+                    if sender.send(key).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        eprintln!("[space=pause/resume, s=step, up/down=faster/slower]");
+        Some(Self { keys, paused: false, terminal })
+    }
+
+    /// Controls read from `keys`, e.g. for a GUI or for testing.
+    pub fn from_keys(keys: Receiver<Key>) -> Self {
+        Self { keys, paused: false, terminal: None }
+    }
+
+    /// Whether the run is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Handles the keys pressed since the last tick and waits until the
+    /// next tick is due: by the `pacer` while the run goes on, or for a key
+    /// while it is paused.
+    pub fn wait(&mut self, pacer: &mut Pacer) {
+        let mut pressed: Vec<Key> = self.keys.try_iter().collect();
+        loop {
+            for key in pressed.drain(..) {
+                match key {
+                    Key::Pause => {
+                        self.paused = !self.paused;
+                        match self.paused {
+                            true => eprintln!("[paused at tick {}]", CLOCK.now_ticks()),
+                            false => {
+                                eprintln!("[resumed]");
+                                pacer.restart();
+                            }
+                        }
+                    }
+                    Key::Step if self.paused => {
+                        pacer.restart();
+                        return;
+                    }
+                    Key::Step => {
+                        self.paused = true;
+                        eprintln!("[paused at tick {}]", CLOCK.now_ticks());
+                    }
+                    Key::Faster | Key::Slower => {
+                        let factor = if key == Key::Faster { 2.0 } else { 0.5 };
+                        pacer.set_ticks_per_second(pacer.ticks_per_second() * factor);
+                        eprintln!("[pace: {} ticks per second]", pacer.ticks_per_second());
+                    }
+                }
+            }
+            if !self.paused {
+                break;
+            }
+            match self.keys.recv() {
+                Ok(key) => pressed.push(key),
+                //nobody is left to resume the run
+                Err(_) => {
+                    self.paused = false;
+                    pacer.restart();
+                }
+            }
+        }
+        pacer.wait();
+    }
+}
+
+impl Drop for Controls {
+    /// Restores the terminal settings.
+    fn drop(&mut self) {
+        if let Some(terminal) = &self.terminal {
+            Command::new("stty").arg(terminal).stdin(Stdio::inherit()).status().ok();
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &Path) -> bool {
+    false
+}
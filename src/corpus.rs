@@ -0,0 +1,102 @@
+//! # Corpus Module
+//!
+//! The layout of a regression corpus, for `scheduler corpus`.
+//!
+//! A corpus is a fixed set of seeded random workloads together with the
+//! results every reference scheduler produced on them. `scheduler corpus
+//! build` writes one, and `scheduler corpus verify` re-runs every scheduler
+//! on every workload and [diffs](crate::diff) the new results against the
+//! stored ones, so a corpus built from the reference solutions grades a
+//! submission, and one built from a submission catches its regressions.
+//!
+//! The workloads are the same on every machine: each [`Entry`] is a
+//! [`Generator`] with a fixed seed, drawn from a few profiles (light and
+//! heavy load, heavy-tailed bursts, and a mix of interactive and batch job
+//! classes). A corpus directory holds
+//! ```text
+//! corpus/
+//!   workloads/<entry>.in
+//!   <scheduler>/<entry>.json
+//! ```
+//!
+//! ## Example
+//! ```
+//! use std::path::Path;
+//! use scheduler::corpus;
+//!
+//! let entries = corpus::entries();
+//! assert_eq!(entries.len(), 20);
+//! assert_eq!(entries[0].name, "light-s0");
+//! // the same entry always generates the same workload
+//! assert_eq!(entries[0].generator.generate(), corpus::entries()[0].generator.generate());
+//! assert_eq!(
+//!     corpus::results_path(Path::new("corpus"), "mlf", &entries[0].name),
+//!     Path::new("corpus/mlf/light-s0.json")
+//! );
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::generate::Generator;
+
+/// Seeds every profile is generated with.
+pub const SEEDS: u64 = 5;
+
+/// Name of the directory of a corpus that holds the workloads.
+pub const WORKLOADS: &str = "workloads";
+
+/// One workload of the corpus.
+///
+/// # Fields
+/// - `name`: The workload's file name, without extension, e.g. `heavy-s3`.
+/// - `generator`: Generates the workload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub generator: Generator,
+}
+
+/// Every workload of the corpus, in a fixed order.
+pub fn entries() -> Vec<Entry> {
+    let profile = |arrival: &str, burst: &str, classes: &[&str]| Generator {
+        jobs: 50,
+        arrival: arrival.parse().unwrap(),
+        burst: burst.parse().unwrap(),
+        priorities: 3,
+        classes: classes.iter().map(|class| class.parse().unwrap()).collect(),
+        seed: 0,
+    };
+    let profiles = [
+        ("light", profile("poisson:0.05", "exponential:8", &[])),
+        ("heavy", profile("poisson:0.15", "exponential:8", &[])),
+        ("heavytail", profile("poisson:0.08", "pareto:1.5,4", &[])),
+        (
+            "mixed",
+            profile(
+                "poisson:0.1",
+                "exponential:8",
+                &["interactive/0.9/poisson:0.1/exponential:3", "batch/0.1/poisson:0.01/exponential:60/2"],
+            ),
+        ),
+    ];
+    profiles
+        .iter()
+        .flat_map(|(name, generator)| {
+            (0..SEEDS).map(move |seed| Entry {
+                name: format!("{}-s{}", name, seed),
+                generator: Generator { seed, ..generator.clone() },
+            })
+        })
+        .collect()
+}
+
+/// Where the corpus in `dir` keeps the workload `entry`.
+pub fn workload_path(dir: &Path, entry: &str) -> PathBuf {
+    dir.join(WORKLOADS).join(format!("{}.in", entry))
+}
+
+/// Where the corpus in `dir` keeps the results of `scheduler` on the
+/// workload `entry`.
+pub fn results_path(dir: &Path, scheduler: &str, entry: &str) -> PathBuf {
+    dir.join(scheduler).join(format!("{}.json", entry))
+}
@@ -0,0 +1,375 @@
+//! # Co-simulation Module
+//!
+//! Turn-based runs in which a program outside the simulator, such as a
+//! reinforcement-learning agent, makes every scheduling decision.
+//!
+//! A [`CoSimulation`] hands the decision back to its caller whenever one is
+//! due: [`start`](CoSimulation::start) and [`step`](CoSimulation::step)
+//! return a [`StepOutcome`], which is either the [`Observation`] the next
+//! [`Action`] is decided on, or the end of the run. An action runs one of the
+//! ready processes for a number of ticks, or leaves the CPU idle. While
+//! nothing is ready the simulator idles on its own, so every observation has
+//! a choice to make.
+//!
+//! Everything that happens is emitted to a [`Recorder`], so a learned policy
+//! gets the same trace and metrics from the same workloads as the built-in
+//! schedulers. Like a real scheduler, the agent does not see how long the
+//! bursts are, only how much CPU time each process has had.
+//!
+//! Jobs are [`Job`]s of the single-CPU engine; burst scripts are not
+//! simulated, every job runs its burst in one go.
+//!
+//! ## Example
+//! An agent that always runs the process that has had the least CPU time,
+//! for a tick at a time:
+//! ```
+//! use std::time::Duration;
+//! use scheduler::CLOCK;
+//! use scheduler::cosim::{Action, CoSimulation, StepOutcome};
+//! use scheduler::simulation::Job;
+//! use scheduler::sink::Recorder;
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let mut sim = CoSimulation::new([Job::new(0, 0, 5), Job::new(1, 1, 2), Job::new(2, 2, 1)]);
+//! let mut out = Recorder::new();
+//! let mut outcome = sim.start(&mut out);
+//! while let StepOutcome::Decide(observation) = outcome {
+//!     let least = observation.ready.iter().min_by_key(|p| p.ran).unwrap();
+//!     outcome = sim.step(Action::Dispatch { id: least.id, ticks: Some(1) }, &mut out).unwrap();
+//! }
+//! assert_eq!(outcome, StepOutcome::Done { time: 8 });
+//! let finish = |id| out.metrics().process(id).unwrap().finish;
+//! assert_eq!((finish(2), finish(1), finish(0)), (Some(3), Some(5), Some(8)));
+//! ```
+//!
+//! ## Protocol
+//! [`drive`] plays the simulator's side over a pair of streams, one JSON
+//! object per line, so agents can be written in any language; `scheduler -s
+//! agent --agent <command>` runs a workload with the agent `<command>`
+//! talking to it over its stdin and stdout. The simulator sends a
+//! [`StepOutcome`], the agent answers a `decide` with an [`Action`]:
+//! ```text
+//! > {"outcome":"decide","time":0,"ready":[{"id":0,"priority":0,"arrival":0,"ran":0,"waiting":0}],"finished":[],"left":1}
+//! < {"action":"dispatch","id":0,"ticks":2}
+//! > {"outcome":"decide","time":2,"ready":[{"id":0,"priority":0,"arrival":0,"ran":2,"waiting":0}],"finished":[],"left":1}
+//! < {"action":"dispatch","id":0}
+//! > {"outcome":"done","time":3}
+//! ```
+//! An action that cannot be carried out is answered with `{"outcome":
+//! "error","message":...}`, and the simulator waits for another one.
+//!
+//! ```
+//! use std::time::Duration;
+//! use scheduler::CLOCK;
+//! use scheduler::cosim::{CoSimulation, drive};
+//! use scheduler::simulation::Job;
+//! use scheduler::sink::Recorder;
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let mut sim = CoSimulation::new([Job::new(0, 0, 3)]);
+//! let actions = concat!(
+//!     r#"{"action":"dispatch","id":7}"#, "\n",
+//!     r#"{"action":"dispatch","id":0,"ticks":2}"#, "\n",
+//!     r#"{"action":"dispatch","id":0}"#, "\n",
+//! );
+//! let mut replies = Vec::new();
+//! drive(&mut sim, &mut Recorder::new(), actions.as_bytes(), &mut replies).unwrap();
+//! let replies = String::from_utf8(replies).unwrap();
+//! let replies: Vec<&str> = replies.lines().collect();
+//! assert_eq!(replies[1], r#"{"outcome":"error","message":"process 7 is not ready"}"#);
+//! assert!(replies[2].starts_with(r#"{"outcome":"decide","time":2,"#));
+//! assert_eq!(replies[3], r#"{"outcome":"done","time":3}"#);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::CLOCK;
+use crate::burst::Burst;
+use crate::event::{EventKind, IDLE};
+use crate::simulation::Job;
+use crate::sink::Recorder;
+
+/// A process the agent may dispatch.
+///
+/// # Fields
+/// - `id`: The process.
+/// - `priority`: Its priority.
+/// - `arrival`: Tick it arrived at.
+/// - `ran`: Ticks it has run so far.
+/// - `waiting`: Ticks since it last became ready, by arriving or by being
+///   preempted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ready {
+    pub id: u32,
+    pub priority: u32,
+    pub arrival: u64,
+    pub ran: u32,
+    pub waiting: u64,
+}
+
+/// What the agent sees when it has to decide.
+///
+/// # Fields
+/// - `time`: The current tick.
+/// - `ready`: The ready processes, in the order they became ready.
+/// - `finished`: The processes that finished since the last decision.
+/// - `left`: The jobs that have not finished, including those still to
+///   arrive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Observation {
+    pub time: u64,
+    pub ready: Vec<Ready>,
+    pub finished: Vec<u32>,
+    pub left: usize,
+}
+
+/// The agent's decision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Run the ready process `id` for at most `ticks` ticks, to completion
+    /// without.
+    Dispatch {
+        id: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ticks: Option<u32>,
+    },
+    /// Leave the CPU idle for `ticks` ticks, one without.
+    Idle {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ticks: Option<u32>,
+    },
+}
+
+/// Where a run stands after a step.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum StepOutcome {
+    /// The agent decides the next [`Action`].
+    Decide(Observation),
+    /// Every job finished, or the recorder stopped the run, at tick `time`.
+    Done { time: u64 },
+}
+
+/// Why an [`Action`] cannot be carried out. The run is left as it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionError {
+    /// The process is not ready: unknown, finished or still to arrive.
+    NotReady(u32),
+    /// The action asked for zero ticks.
+    NoTicks,
+    /// The run is over.
+    Done,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::NotReady(id) => write!(f, "process {} is not ready", id),
+            ActionError::NoTicks => f.write_str("an action needs at least one tick"),
+            ActionError::Done => f.write_str("the run is over"),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// A job that has not finished yet.
+#[derive(Debug, Clone, Copy)]
+struct Process {
+    priority: u32,
+    arrival: u64,
+    burst: Burst,
+    /// the tick it last became ready at
+    since: u64,
+}
+
+/// A turn-based run, see the [module documentation](self).
+pub struct CoSimulation {
+    pending: BTreeMap<u32, Process>,
+    /// job IDs keyed by their arrival tick
+    arrivals: BTreeMap<u64, Vec<u32>>,
+    /// the ready processes, in the order they became ready
+    ready: Vec<u32>,
+    /// the processes that finished since the last observation
+    finished: Vec<u32>,
+    done: bool,
+}
+
+impl CoSimulation {
+    /// A run of `jobs`. Jobs arriving at the same tick become ready in the
+    /// order they are given.
+    pub fn new(jobs: impl IntoIterator<Item = Job>) -> Self {
+        let mut pending = BTreeMap::new();
+        let mut arrivals: BTreeMap<u64, Vec<u32>> = BTreeMap::new();
+        for job in jobs {
+            arrivals.entry(job.arrival).or_default().push(job.id);
+            let process = Process { priority: job.priority, arrival: job.arrival, burst: Burst::new(job.burst), since: 0 };
+            pending.insert(job.id, process);
+        }
+        Self { pending, arrivals, ready: Vec::new(), finished: Vec::new(), done: false }
+    }
+
+    /// Starts the run at tick `0` of the [`CLOCK`], up to the first decision.
+    pub fn start(&mut self, out: &mut Recorder) -> StepOutcome {
+        CLOCK.set_now(Duration::ZERO);
+        self.admit(out);
+        self.advance(out)
+    }
+
+    /// Carries out `action`, then runs up to the next decision.
+    ///
+    /// # Errors
+    /// If the action cannot be carried out, see [`ActionError`].
+    pub fn step(&mut self, action: Action, out: &mut Recorder) -> Result<StepOutcome, ActionError> {
+        if self.done {
+            return Err(ActionError::Done);
+        }
+        match action {
+            Action::Dispatch { ticks: Some(0), .. } | Action::Idle { ticks: Some(0) } => return Err(ActionError::NoTicks),
+            Action::Dispatch { id, ticks } => {
+                let Some(at) = self.ready.iter().position(|&ready| ready == id) else {
+                    return Err(ActionError::NotReady(id));
+                };
+                self.ready.remove(at);
+                self.run(id, ticks, out);
+            }
+            Action::Idle { ticks } => {
+                for _ in 0..ticks.unwrap_or(1) {
+                    if out.stopped() {
+                        break;
+                    }
+                    self.idle_tick(out);
+                }
+            }
+        }
+        Ok(self.advance(out))
+    }
+
+    /// What the agent sees now.
+    pub fn observe(&self) -> Observation {
+        let now = CLOCK.now_ticks();
+        let ready = self.ready.iter().map(|id| {
+            let p = &self.pending[id];
+            Ready { id: *id, priority: p.priority, arrival: p.arrival, ran: p.burst.ran(), waiting: now - p.since }
+        });
+        Observation { time: now, ready: ready.collect(), finished: self.finished.clone(), left: self.pending.len() }
+    }
+
+    /// Runs process `id` for at most `ticks` ticks.
+    fn run(&mut self, id: u32, ticks: Option<u32>, out: &mut Recorder) {
+        out.emit(id, EventKind::Dispatch { quantum: ticks.unwrap_or(0) });
+        let mut ran = 0;
+        while !self.pending[&id].burst.is_done() && ticks.is_none_or(|ticks| ran < ticks) && !out.stopped() {
+            out.emit(id, EventKind::Execute);
+            CLOCK.advance(CLOCK.tick());
+            ran += 1;
+            self.pending.entry(id).and_modify(|p| {
+                p.burst.run();
+            });
+            self.admit(out);
+        }
+        if self.pending[&id].burst.is_done() {
+            out.emit(id, EventKind::Finish);
+            self.pending.remove(&id);
+            self.finished.push(id);
+        } else {
+            out.emit(id, EventKind::Preempt);
+            self.pending.entry(id).and_modify(|p| {
+                p.burst.split();
+                p.since = CLOCK.now_ticks();
+            });
+            self.ready.push(id);
+        }
+    }
+
+    /// Idles while nothing is ready, up to the next decision or the end of
+    /// the run.
+    fn advance(&mut self, out: &mut Recorder) -> StepOutcome {
+        while self.ready.is_empty() && !self.pending.is_empty() && !out.stopped() {
+            if self.arrivals.range(CLOCK.now_ticks() + 1..).next().is_none() {
+                break;
+            }
+            self.idle_tick(out);
+        }
+        if self.ready.is_empty() || out.stopped() {
+            self.done = true;
+            return StepOutcome::Done { time: CLOCK.now_ticks() };
+        }
+        let observation = self.observe();
+        self.finished.clear();
+        StepOutcome::Decide(observation)
+    }
+
+    fn idle_tick(&mut self, out: &mut Recorder) {
+        out.emit(IDLE, EventKind::Idle);
+        CLOCK.advance(CLOCK.tick());
+        self.admit(out);
+    }
+
+    /// Makes every job arriving at the current tick ready.
+    fn admit(&mut self, out: &mut Recorder) {
+        let now = CLOCK.now_ticks();
+        for id in self.arrivals.remove(&now).into_iter().flatten() {
+            let Some(p) = self.pending.get_mut(&id) else {
+                continue;
+            };
+            p.since = now;
+            out.emit(id, EventKind::Arrive { priority: p.priority });
+            self.ready.push(id);
+        }
+    }
+}
+
+/// The reply to an [`Action`] that cannot be carried out.
+#[derive(Serialize)]
+struct Rejected {
+    outcome: &'static str,
+    message: String,
+}
+
+/// Runs `sim` to the end with the [`Action`]s read from `actions`, writing
+/// every [`StepOutcome`] to `outcomes`, see [Protocol](self#protocol).
+///
+/// # Errors
+/// On I/O errors, and when `actions` ends before the run does.
+pub fn drive<R, W>(sim: &mut CoSimulation, out: &mut Recorder, actions: R, mut outcomes: W) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut outcome = sim.start(out);
+    let mut lines = actions.lines();
+    loop {
+        serde_json::to_writer(&mut outcomes, &outcome).map_err(io::Error::other)?;
+        writeln!(outcomes)?;
+        outcomes.flush()?;
+        if let StepOutcome::Done { .. } = outcome {
+            return Ok(());
+        }
+        let next = loop {
+            let Some(line) = lines.next().transpose()? else {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the agent stopped before the run was done"));
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let error = match serde_json::from_str(&line) {
+                Ok(action) => match sim.step(action, out) {
+                    Ok(next) => break next,
+                    Err(err) => err.to_string(),
+                },
+                Err(err) => format!("invalid action: {}", err),
+            };
+            serde_json::to_writer(&mut outcomes, &Rejected { outcome: "error", message: error }).map_err(io::Error::other)?;
+            writeln!(outcomes)?;
+            outcomes.flush()?;
+        };
+        outcome = next;
+    }
+}
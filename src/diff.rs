@@ -0,0 +1,165 @@
+//! # Diff Module
+//!
+//! Compares two runs, typically a student's results against a reference
+//! trace, for `scheduler diff expected.json actual.json`.
+//!
+//! Instead of a line-by-line text diff, the two event logs are walked side by
+//! side and the **first divergence** is reported: the tick at which the runs
+//! stop agreeing, and what each of them did there. Everything after the first
+//! divergence is usually a consequence of it. The report then lists the
+//! differences in the aggregate and per-process metrics.
+//!
+//! ## Example
+//! ```
+//! use scheduler::diff::diff;
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::results::{Results, RunConfig};
+//! use scheduler::sink::Recorder;
+//!
+//! let config = RunConfig { scheduler: "simple".into(), input_file: "t.in".into(), ..Default::default() };
+//! let run = |order: [u32; 2]| {
+//!     let mut out = Recorder::new();
+//!     out.emit(1, EventKind::Arrive { priority: 0 });
+//!     out.emit(2, EventKind::Arrive { priority: 0 });
+//!     for id in order {
+//!         out.emit(id, EventKind::Dispatch { quantum: 0 });
+//!         out.emit(id, EventKind::Finish);
+//!     }
+//!     Results::new(config.clone(), &out)
+//! };
+//! let report = diff(&run([1, 2]), &run([2, 1]));
+//! let first = report.divergence.unwrap();
+//! assert_eq!(first.index, 2);
+//! assert_eq!(first.expected.unwrap().id, 1);
+//! assert_eq!(first.actual.unwrap().id, 2);
+//! assert!(diff(&run([1, 2]), &run([1, 2])).is_identical());
+//! ```
+
+use std::fmt;
+
+use crate::event::Event;
+use crate::results::Results;
+
+/// The first point at which two event logs disagree.
+///
+/// One side is `None` when its log ended before the other's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub index: usize,
+    pub expected: Option<Event>,
+    pub actual: Option<Event>,
+}
+
+/// A metric that differs between the two runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricDelta {
+    pub name: String,
+    pub expected: f64,
+    pub actual: f64,
+}
+
+/// The result of comparing two runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffReport {
+    pub divergence: Option<Divergence>,
+    pub aggregates: Vec<MetricDelta>,
+    pub processes: Vec<MetricDelta>,
+}
+
+impl DiffReport {
+    /// Returns `true` when the event logs and all metrics match.
+    pub fn is_identical(&self) -> bool {
+        self.divergence.is_none() && self.aggregates.is_empty() && self.processes.is_empty()
+    }
+}
+
+/// Compares `actual` against `expected`.
+pub fn diff(expected: &Results, actual: &Results) -> DiffReport {
+    let len = expected.events.len().max(actual.events.len());
+    let divergence = (0..len)
+        .map(|index| Divergence {
+            index,
+            expected: expected.events.get(index).copied(),
+            actual: actual.events.get(index).copied(),
+        })
+        .find(|d| d.expected != d.actual);
+
+    let mut aggregates = Vec::new();
+    let (e, a) = (&expected.aggregates, &actual.aggregates);
+    let pairs = [
+        ("processes", e.processes as f64, a.processes as f64),
+        ("completed", e.completed as f64, a.completed as f64),
+        ("makespan", e.makespan as f64, a.makespan as f64),
+        ("cpu_time", e.cpu_time as f64, a.cpu_time as f64),
+        ("mean_turnaround", e.mean_turnaround, a.mean_turnaround),
+        ("mean_waiting", e.mean_waiting, a.mean_waiting),
+        ("mean_response", e.mean_response, a.mean_response),
+        ("throughput", e.throughput, a.throughput),
+        ("utilization", e.utilization, a.utilization),
+    ];
+    for (name, expected, actual) in pairs {
+        push_delta(&mut aggregates, name.to_string(), expected, actual);
+    }
+
+    let mut processes = Vec::new();
+    for p in &expected.processes {
+        let Some(q) = actual.processes.iter().find(|q| q.id == p.id) else {
+            push_delta(&mut processes, format!("P{} completed", p.id), 1.0, 0.0);
+            continue;
+        };
+        let fields = [
+            ("finish", p.finish, q.finish),
+            ("turnaround", p.turnaround, q.turnaround),
+            ("waiting", p.waiting, q.waiting),
+            ("response", p.response, q.response),
+        ];
+        for (name, expected, actual) in fields {
+            let value = |v: Option<u64>| v.map_or(f64::NAN, |v| v as f64);
+            push_delta(&mut processes, format!("P{} {}", p.id, name), value(expected), value(actual));
+        }
+    }
+
+    DiffReport { divergence, aggregates, processes }
+}
+
+fn push_delta(deltas: &mut Vec<MetricDelta>, name: String, expected: f64, actual: f64) {
+    let same = expected == actual || (expected.is_nan() && actual.is_nan());
+    if !same {
+        deltas.push(MetricDelta { name, expected, actual });
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_identical() {
+            return writeln!(f, "runs are identical");
+        }
+        let show = |event: Option<Event>| event.map_or("(no more events)".to_string(), |e| e.to_string());
+        match &self.divergence {
+            Some(d) => {
+                let tick = d.expected.or(d.actual).map_or(0, |e| e.time);
+                writeln!(f, "first divergence at event #{} (tick {}):", d.index, tick)?;
+                writeln!(f, "  expected: {}", show(d.expected))?;
+                writeln!(f, "  actual:   {}", show(d.actual))?;
+            }
+            None => writeln!(f, "event logs are identical")?,
+        }
+        for (title, deltas) in [("aggregate", &self.aggregates), ("per-process", &self.processes)] {
+            if deltas.is_empty() {
+                continue;
+            }
+            writeln!(f, "{} metric deltas (actual - expected):", title)?;
+            for d in deltas {
+                writeln!(
+                    f,
+                    "  {:<20} {:>12.3} -> {:>12.3} ({:+.3})",
+                    d.name,
+                    d.expected,
+                    d.actual,
+                    d.actual - d.expected
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
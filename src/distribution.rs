@@ -0,0 +1,152 @@
+//! # Distribution Module
+//!
+//! Probability distributions for random workloads, shared by the workload
+//! generator and closed-loop think times.
+//!
+//! On the command line a distribution is written as `name:parameters`:
+//!
+//! | Syntax                  | Distribution                                         |
+//! |-------------------------|------------------------------------------------------|
+//! | `constant:<value>`      | Always `value`.                                      |
+//! | `uniform:<low>,<high>`  | Uniform between `low` and `high`.                    |
+//! | `exponential:<mean>`    | Exponential with the given mean.                     |
+//! | `poisson:<rate>`        | Gaps of a Poisson process with `rate` events per tick, i.e. exponential with mean `1 / rate`. |
+//! | `pareto:<alpha>,<xm>`   | Pareto with shape `alpha` and minimum `xm`: heavy tailed, so most values are small and a few are huge. |
+//!
+//! Heavy-tailed bursts are where shortest-job-first and MLFQ scheduling pay
+//! off the most, since a few long jobs hold most of the CPU demand.
+//!
+//! ## Example
+//! ```
+//! use scheduler::distribution::Distribution;
+//! use scheduler::random::Rng;
+//!
+//! let burst: Distribution = "pareto:1.5,10".parse().unwrap();
+//! assert_eq!(burst, Distribution::Pareto { alpha: 1.5, xm: 10.0 });
+//! let mut rng = Rng::new(1);
+//! assert!((0..1000).all(|_| burst.sample(&mut rng) >= 10.0));
+//!
+//! assert_eq!("poisson:0.5".parse::<Distribution>().unwrap().mean(), Some(2.0));
+//! assert_eq!(
+//!     "uniform:5".parse::<Distribution>().unwrap_err(),
+//!     "uniform: expected 2 parameters, got 1"
+//! );
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::random::Rng;
+
+/// A distribution of non-negative numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distribution {
+    Constant { value: f64 },
+    Uniform { low: f64, high: f64 },
+    Exponential { mean: f64 },
+    Pareto { alpha: f64, xm: f64 },
+}
+
+impl Distribution {
+    /// Draws one value.
+    pub fn sample(&self, rng: &mut Rng) -> f64 {
+        match *self {
+            Distribution::Constant { value } => value,
+            Distribution::Uniform { low, high } => low + (high - low) * rng.next_f64(),
+            Distribution::Exponential { mean } => rng.exponential(mean),
+            Distribution::Pareto { alpha, xm } => xm / (1.0 - rng.next_f64()).powf(1.0 / alpha),
+        }
+    }
+
+    /// The distribution's mean, or `None` where it is infinite (Pareto with
+    /// `alpha <= 1`).
+    pub fn mean(&self) -> Option<f64> {
+        match *self {
+            Distribution::Constant { value } => Some(value),
+            Distribution::Uniform { low, high } => Some((low + high) / 2.0),
+            Distribution::Exponential { mean } => Some(mean),
+            Distribution::Pareto { alpha, xm } => (alpha > 1.0).then(|| alpha * xm / (alpha - 1.0)),
+        }
+    }
+
+    /// The distribution's second moment `E[X²]`, or `None` where it is
+    /// infinite (Pareto with `alpha <= 2`).
+    pub fn second_moment(&self) -> Option<f64> {
+        match *self {
+            Distribution::Constant { value } => Some(value * value),
+            Distribution::Uniform { low, high } => Some((low * low + low * high + high * high) / 3.0),
+            Distribution::Exponential { mean } => Some(2.0 * mean * mean),
+            Distribution::Pareto { alpha, xm } => (alpha > 2.0).then(|| alpha * xm * xm / (alpha - 2.0)),
+        }
+    }
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (name, params) = s
+            .split_once(':')
+            .ok_or_else(|| format!("'{}' is not a distribution, expected name:parameters", s))?;
+        let params = params
+            .split(',')
+            .map(|p| {
+                p.trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|p| p.is_finite() && *p >= 0.0)
+                    .ok_or_else(|| format!("{}: '{}' is not a non-negative number", name, p))
+            })
+            .collect::<Result<Vec<f64>, String>>()?;
+        let expect = |n: usize| {
+            if params.len() == n {
+                Ok(())
+            } else {
+                Err(format!("{}: expected {} parameter{}, got {}", name, n, if n == 1 { "" } else { "s" }, params.len()))
+            }
+        };
+        let positive = |what: &str, value: f64| {
+            if value > 0.0 { Ok(value) } else { Err(format!("{}: {} must be positive", name, what)) }
+        };
+        match name {
+            "constant" => {
+                expect(1)?;
+                Ok(Distribution::Constant { value: params[0] })
+            }
+            "uniform" => {
+                expect(2)?;
+                if params[0] > params[1] {
+                    return Err("uniform: low must not be above high".to_string());
+                }
+                Ok(Distribution::Uniform { low: params[0], high: params[1] })
+            }
+            "exponential" => {
+                expect(1)?;
+                Ok(Distribution::Exponential { mean: positive("mean", params[0])? })
+            }
+            "poisson" => {
+                expect(1)?;
+                Ok(Distribution::Exponential { mean: 1.0 / positive("rate", params[0])? })
+            }
+            "pareto" => {
+                expect(2)?;
+                Ok(Distribution::Pareto { alpha: positive("alpha", params[0])?, xm: positive("xm", params[1])? })
+            }
+            other => Err(format!(
+                "unknown distribution '{}', expected constant, uniform, exponential, poisson or pareto",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Distribution::Constant { value } => write!(f, "constant:{}", value),
+            Distribution::Uniform { low, high } => write!(f, "uniform:{},{}", low, high),
+            Distribution::Exponential { mean } => write!(f, "exponential:{}", mean),
+            Distribution::Pareto { alpha, xm } => write!(f, "pareto:{},{}", alpha, xm),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! # Equivalence Module
+//!
+//! Pairs of schedulers that must log the very same events on any workload,
+//! for `scheduler equivalence`.
+//!
+//! Some of the assignment's schedulers are special cases of others. `mlrr`
+//! with a single level is round robin with that level's quantum, which is
+//! all `simplerr` is; `mlf` that never demotes a process and never boosts
+//! them all back to the top is `simplemlf`, which only promotes a process
+//! that waited too long. Configured that way, the two schedulers of a pair
+//! make the same decision at every dispatch, so their event logs match event
+//! for event, and the first event where they do not points at a bug in one
+//! of them. Neither needs a reference solution for that: the check holds for
+//! every workload, and `scheduler equivalence` tries it on the built-in
+//! workloads and on as many random ones as asked for, in the manner of a
+//! property test.
+//!
+//! [`equivalences`] lists the pairs, each with a [`SchedulerConfig`] that
+//! configures both schedulers of the pair alike, starting from the quanta
+//! and thresholds of the configuration given. [`compare`] finds where two
+//! runs part, whatever priorities their jobs arrived with.
+//!
+//! ## Example
+//! ```
+//! use scheduler::config::SchedulerConfig;
+//! use scheduler::equivalence::{self, NEVER};
+//! use scheduler::event::EventKind;
+//! use scheduler::results::{Results, RunConfig};
+//! use scheduler::sink::Recorder;
+//!
+//! let pairs = equivalence::equivalences(&SchedulerConfig::default());
+//! let rr = &pairs[0];
+//! assert_eq!((rr.left, rr.right), ("simplerr", "mlrr"));
+//! assert_eq!(rr.config.mlrr.quanta, [4]);
+//! assert_eq!(rr.to_string(), "simplerr = mlrr with one level of quantum 4");
+//! let mlf = &pairs[1];
+//! assert_eq!((mlf.left, mlf.right), ("simplemlf", "mlf"));
+//! assert_eq!((mlf.config.mlf.quanta.as_slice(), mlf.config.mlf.demote_after), (&[0, 4, 1][..], NEVER));
+//!
+//! let run = |scheduler: &str, order: [u32; 2]| {
+//!     let mut out = Recorder::new();
+//!     out.emit(1, EventKind::Arrive { priority: 0 });
+//!     out.emit(2, EventKind::Arrive { priority: 0 });
+//!     for id in order {
+//!         out.emit(id, EventKind::Dispatch { quantum: 4 });
+//!         out.emit(id, EventKind::Finish);
+//!     }
+//!     Results::new(RunConfig { scheduler: scheduler.into(), ..Default::default() }, &out)
+//! };
+//! // the scheduler's name does not matter, only the events
+//! assert_eq!(equivalence::compare(&run("simplerr", [1, 2]), &run("mlrr", [1, 2])), None);
+//! assert_eq!(equivalence::compare(&run("simplerr", [1, 2]), &run("mlrr", [2, 1])).unwrap().index, 2);
+//!
+//! // nor the priorities the jobs arrive with
+//! let mut mlrr = run("mlrr", [1, 2]);
+//! mlrr.events[0].kind = EventKind::Arrive { priority: 3 };
+//! assert_eq!(equivalence::compare(&run("simplerr", [1, 2]), &mlrr), None);
+//! ```
+
+use std::fmt;
+
+use crate::config::{MlfqConfig, SchedulerConfig};
+use crate::diff::{self, Divergence};
+use crate::event::EventKind;
+use crate::results::Results;
+
+/// A threshold no run reaches, e.g. `demote_after` for an `mlf` that never
+/// demotes. The largest integer a TOML configuration file can hold.
+pub const NEVER: u64 = i64::MAX as u64;
+
+/// Two schedulers that must log the same events, see the
+/// [module documentation](self).
+///
+/// # Fields
+/// - `left`, `right`: The schedulers, the simpler one on the left.
+/// - `config`: Configures both of them so that they behave alike.
+/// - `how`: How `right` is configured, e.g. `"with one level of quantum 4"`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equivalence {
+    pub left: &'static str,
+    pub right: &'static str,
+    pub config: SchedulerConfig,
+    pub how: String,
+}
+
+impl fmt::Display for Equivalence {
+    /// Formats the pair as `left = right how`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {} {}", self.left, self.right, self.how)
+    }
+}
+
+/// The pairs of schedulers that must log the same events, configured from
+/// the `simplerr` and `simplemlf` tables of `config`:
+///
+/// - `simplerr` and `mlrr` with a single level of `simplerr`'s quantum;
+/// - `simplemlf` and `mlf` with `simplemlf`'s quanta and `promote_after`,
+///   `demote_after` set to [`NEVER`] and none of the OSTEP rules on.
+pub fn equivalences(config: &SchedulerConfig) -> Vec<Equivalence> {
+    let mut rr = config.clone();
+    rr.mlrr.quanta = vec![config.simplerr.quantum];
+    let mut mlf = config.clone();
+    mlf.mlf = MlfqConfig {
+        quanta: config.simplemlf.quanta.clone(),
+        promote_after: config.simplemlf.promote_after,
+        demote_after: NEVER,
+        ..MlfqConfig::default()
+    };
+    vec![
+        Equivalence { left: "simplerr", right: "mlrr", config: rr, how: format!("with one level of quantum {}", config.simplerr.quantum) },
+        Equivalence { left: "simplemlf", right: "mlf", config: mlf, how: "without demotion or boosts".to_string() },
+    ]
+}
+
+/// Compares the event logs of two runs, ignoring everything else about
+/// them, such as the scheduler's name, and the priorities jobs arrive with:
+/// `simplerr` hands every job priority `0`, `mlrr` the one of the input, and
+/// with a single level that makes no difference to the run.
+///
+/// # Returns
+/// The first event where they part, or `None` if the logs are the same.
+pub fn compare(left: &Results, right: &Results) -> Option<Divergence> {
+    diff::diff(&without_priorities(left), &without_priorities(right)).divergence
+}
+
+/// `results` with every job arriving at priority `0`.
+fn without_priorities(results: &Results) -> Results {
+    let mut results = results.clone();
+    for event in &mut results.events {
+        if let EventKind::Arrive { priority } = &mut event.kind {
+            *priority = 0;
+        }
+    }
+    results
+}
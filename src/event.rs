@@ -0,0 +1,143 @@
+//! # Event Module
+//!
+//! The simulators describe everything that happens during a run as a stream
+//! of [`Event`]s: a process arriving, being dispatched, executing for a tick,
+//! being preempted, blocking for and waking from I/O, being throttled by a
+//! CPU limit and let go again, being reniced, and finishing or timing out,
+//! plus the idle task running when nothing is ready. Output formats, metrics
+//! and debugging tools are all built by consuming this stream (see
+//! [`crate::sink`]) rather than by printing from inside the simulation loops.
+//!
+//! Next to the events, a run can carry [`Annotation`]s: labeled markers such
+//! as "CPU hog arrives" that the workload or whoever steers the run puts at
+//! a tick, so traces and charts explain themselves.
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//!
+//! let event = Event { time: 4, id: 2, kind: EventKind::Dispatch { quantum: 4 } };
+//! assert_eq!(
+//!     serde_json::to_string(&event).unwrap(),
+//!     r#"{"time":4,"id":2,"kind":"dispatch","quantum":4}"#
+//! );
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The `id` of [`EventKind::Idle`] events, which belong to no process.
+pub const IDLE: u32 = u32::MAX;
+
+/// A single thing that happened to a process during a simulation.
+///
+/// # Fields
+/// - `time`: Simulated time of the event, in ticks.
+/// - `id`: The process the event is about.
+/// - `kind`: What happened, see [`EventKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Event {
+    pub time: u64,
+    pub id: u32,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+/// The different kinds of [`Event`].
+///
+/// `Execute` is emitted once for every tick a process runs, and `Idle` once
+/// for every tick nothing does, which makes them by far the most frequent
+/// events; they are used for tick-level output and CPU accounting but are not
+/// stored in the results event log (see [`EventKind::is_tick`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventKind {
+    /// The process was handed to the scheduler for the first time.
+    Arrive { priority: u32 },
+    /// The scheduler selected the process to run for `quantum` ticks (`0` = to completion).
+    Dispatch { quantum: u32 },
+    /// The process ran for one tick.
+    Execute,
+    /// The process stopped running before finishing and went back to the scheduler.
+    Preempt,
+    /// The process left the CPU to wait for I/O.
+    Block,
+    /// The process's I/O completed and it went back to the scheduler.
+    Wake,
+    /// The process's group used up its CPU quota, so the process is kept off
+    /// the CPU until the quota is refilled (see [`crate::bandwidth`]).
+    Throttle,
+    /// The process's group got its quota back and the process went back to
+    /// the scheduler.
+    Unthrottle,
+    /// The process's nice value became `nice` (see [`crate::nice`]), which
+    /// is also how a job's starting nice value other than `0` is recorded,
+    /// right after it arrives.
+    Renice { nice: i8 },
+    /// The process completed all of its work.
+    Finish,
+    /// The process had not finished by its deadline, so it was cancelled
+    /// wherever it was (see [Timeouts](crate::input#timeouts)).
+    Timeout,
+    /// No process was ready, so the idle task ran for one tick. The event's
+    /// `id` is [`IDLE`].
+    Idle,
+}
+
+impl EventKind {
+    /// Whether this is a per-tick event (`Execute` or `Idle`).
+    pub fn is_tick(&self) -> bool {
+        matches!(self, EventKind::Execute | EventKind::Idle)
+    }
+}
+
+/// A labeled marker at a point of a run, e.g. `"priority boost here"`.
+///
+/// # Fields
+/// - `time`: Simulated time of the marker, in ticks.
+/// - `label`: What happens there.
+///
+/// # Example
+/// ```
+/// use scheduler::event::Annotation;
+///
+/// let annotation = Annotation { time: 12, label: "CPU hog arrives".to_string() };
+/// assert_eq!(annotation.to_string(), "tick 12: CPU hog arrives");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub time: u64,
+    pub label: String,
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tick {}: {}", self.time, self.label)
+    }
+}
+
+impl fmt::Display for Event {
+    /// Formats the event for people, e.g. `tick 4: P2 dispatched for 4`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind == EventKind::Idle {
+            return write!(f, "tick {}: idle", self.time);
+        }
+        write!(f, "tick {}: P{} ", self.time, self.id)?;
+        match self.kind {
+            EventKind::Arrive { priority } => write!(f, "arrived, priority {}", priority),
+            EventKind::Dispatch { quantum: 0 } => write!(f, "dispatched to completion"),
+            EventKind::Dispatch { quantum } => write!(f, "dispatched for {}", quantum),
+            EventKind::Execute => write!(f, "executed"),
+            EventKind::Preempt => write!(f, "preempted"),
+            EventKind::Block => write!(f, "blocked for I/O"),
+            EventKind::Wake => write!(f, "woke from I/O"),
+            EventKind::Throttle => write!(f, "throttled"),
+            EventKind::Unthrottle => write!(f, "unthrottled"),
+            EventKind::Renice { nice } => write!(f, "reniced to {}", nice),
+            EventKind::Finish => write!(f, "finished"),
+            EventKind::Timeout => write!(f, "timed out"),
+            EventKind::Idle => Ok(()),
+        }
+    }
+}
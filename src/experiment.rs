@@ -0,0 +1,499 @@
+//! # Experiment Module
+//!
+//! Canned experiments, for `scheduler experiment`.
+//!
+//! ## Round-Robin Quantum
+//! `scheduler experiment rr-quantum` runs a round-robin scheduler on the same
+//! workload once per quantum and plots mean turnaround against the quantum:
+//! the textbook's U-shaped curve. A tiny quantum spends the CPU on context
+//! switches, a huge one degenerates into first-come, first-served and queues
+//! short jobs behind long ones, and the bottom of the U, the [`knee`], is the
+//! quantum that balances the two.
+//!
+//! The simulator itself switches for free, so the experiment charges every
+//! dispatch `switch_cost` ticks after the fact: each job's turnaround grows
+//! by the cost of every dispatch made while it was in the system, which is
+//! how long those switches would have held it up (see
+//! [`charged_turnaround`]).
+//!
+//! ## Estimate Error
+//! `scheduler experiment estimate-error` runs the `spn` scheduler, which
+//! always runs the job with the shortest estimated CPU time next, once per
+//! estimation error and seed, and plots mean turnaround against the error.
+//! With an error of `0` SPN knows every burst and its mean turnaround is the
+//! best any non-preemptive scheduler can do; the chart shows how quickly
+//! that advantage is lost as the estimates get worse, and the report gives
+//! the [`increase`] over exact estimates at every error.
+//! ```
+//! use scheduler::experiment::{self, ErrorPoint};
+//!
+//! let points = [
+//!     ErrorPoint { error: 0, mean_turnaround: 20.0, mean_waiting: 10.0 },
+//!     ErrorPoint { error: 50, mean_turnaround: 25.0, mean_waiting: 15.0 },
+//! ];
+//! assert_eq!(experiment::increase(&points, &points[1]), Some(25.0));
+//! assert!(experiment::error_csv(&points).ends_with("50,25.0000,15.0000,25.00\n"));
+//! ```
+//!
+//! ## MLFQ Rules
+//! `scheduler experiment mlfq-rules` runs the `mlf` scheduler once per set
+//! of OSTEP feedback rules (see [`MlfqRules`]), switched on through the
+//! scheduler's configuration, so the effect of each rule and of their
+//! combinations shows side by side on the same workload. Next to the usual
+//! means, every [`RulesPoint`] has the mean slowdown of the I/O-bound and
+//! the CPU-bound jobs (see [`boundness`](crate::analysis::boundness)),
+//! which is what the boost rules are meant to trade against each other.
+//! ```
+//! use scheduler::experiment::{self, RulesPoint};
+//!
+//! let point = RulesPoint {
+//!     rules: "expiry+io".parse().unwrap(),
+//!     mean_turnaround: 40.0,
+//!     mean_response: 2.5,
+//!     mean_interaction_response: 1.0,
+//!     io_slowdown: Some(1.25),
+//!     cpu_slowdown: None,
+//! };
+//! assert!(experiment::rules_csv(&[point]).ends_with("expiry+io,40.0000,2.5000,1.0000,1.2500,\n"));
+//! ```
+//!
+//! ## Saturation
+//! `scheduler batch --sweep-rates` runs its batch once per Poisson arrival
+//! rate, keeping the burst distribution, and measures how many jobs per
+//! tick every scheduler got through and how long they took to respond: the
+//! classic saturation curves. While the offered load `λE[S]` is below `1`
+//! the throughput follows the arrival rate; beyond it the CPU cannot keep
+//! up, the throughput levels off at `1 / E[S]` and the response time climbs
+//! steeply. [`saturation`] finds the rate at which a scheduler falls
+//! behind.
+//! ```
+//! use scheduler::experiment::{self, SaturationPoint};
+//! use scheduler::stats::Estimate;
+//!
+//! let point = |rate: f64, throughput: f64, response: f64| SaturationPoint {
+//!     rate,
+//!     load: Some(rate * 10.0),
+//!     scheduler: "fcfs".to_string(),
+//!     throughput: Estimate::from_samples(&[throughput]),
+//!     response: Estimate::from_samples(&[response]),
+//! };
+//! let points = [point(0.05, 0.05, 4.0), point(0.08, 0.078, 20.0), point(0.12, 0.095, 150.0)];
+//! assert_eq!(experiment::saturation(&points, "fcfs"), Some(0.12));
+//! assert!(experiment::saturation_csv(&points).ends_with("0.12,1.2000,fcfs,0.0950,,150.0000,\n"));
+//! ```
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::experiment::{self, QuantumPoint};
+//! use scheduler::metrics::Metrics;
+//!
+//! // two 2-tick jobs, run in four 1-tick slices
+//! let mut metrics = Metrics::new();
+//! metrics.record(&Event { time: 0, id: 0, kind: EventKind::Arrive { priority: 0 } });
+//! metrics.record(&Event { time: 0, id: 1, kind: EventKind::Arrive { priority: 0 } });
+//! let mut events = Vec::new();
+//! for (time, id) in [(0, 0), (1, 1), (2, 0), (3, 1)] {
+//!     events.push(Event { time, id, kind: EventKind::Dispatch { quantum: 1 } });
+//! }
+//! events.push(Event { time: 3, id: 0, kind: EventKind::Finish });
+//! events.push(Event { time: 4, id: 1, kind: EventKind::Finish });
+//! for event in &events {
+//!     metrics.record(event);
+//! }
+//! // P0 sat through 3 switches, P1 through 4
+//! assert_eq!(experiment::charged_turnaround(&metrics.processes(), &events, 1), 7.0);
+//!
+//! let points = [
+//!     QuantumPoint { quantum: 1, mean_turnaround: 30.0, dispatches: 40 },
+//!     QuantumPoint { quantum: 4, mean_turnaround: 21.5, dispatches: 12 },
+//!     QuantumPoint { quantum: 16, mean_turnaround: 26.0, dispatches: 5 },
+//! ];
+//! assert_eq!(experiment::knee(&points).unwrap().quantum, 4);
+//! assert!(experiment::to_csv(&points).starts_with("quantum,mean_turnaround,dispatches\n1,30.0000,40\n"));
+//! ```
+
+use std::fmt::Write;
+
+use crate::analysis;
+use crate::config::MlfqRules;
+use crate::event::{Event, EventKind};
+use crate::metrics::{Aggregates, ProcessMetrics};
+use crate::stats::Estimate;
+
+/// The quanta `rr-quantum` sweeps unless told otherwise.
+pub const DEFAULT_QUANTA: [u32; 8] = [1, 2, 3, 4, 6, 8, 16, 64];
+
+/// The estimation errors `estimate-error` sweeps unless told otherwise, in
+/// percent.
+pub const DEFAULT_ERRORS: [u32; 6] = [0, 10, 25, 50, 100, 200];
+
+/// The rule sets `mlfq-rules` compares unless told otherwise, as written on
+/// the command line.
+pub const DEFAULT_RULES: [&str; 6] =
+    ["none", "expiry", "expiry+boost=100", "expiry+io", "allotment", "allotment+boost=100+io"];
+
+/// One run of the quantum sweep.
+///
+/// # Fields
+/// - `quantum`: The round-robin quantum, in ticks.
+/// - `mean_turnaround`: Mean turnaround of the completed jobs, switch cost
+///   included, in ticks.
+/// - `dispatches`: Context switches the run made.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantumPoint {
+    pub quantum: u32,
+    pub mean_turnaround: f64,
+    pub dispatches: usize,
+}
+
+/// The mean turnaround of the completed `processes`, with every dispatch in
+/// `events` charged `switch_cost` ticks to each job that was in the system
+/// when it happened. `0` when nothing completed.
+pub fn charged_turnaround(processes: &[ProcessMetrics], events: &[Event], switch_cost: u64) -> f64 {
+    let dispatches: Vec<u64> =
+        events.iter().filter(|event| matches!(event.kind, EventKind::Dispatch { .. })).map(|event| event.time).collect();
+    let completed: Vec<u64> = processes
+        .iter()
+        .filter_map(|p| {
+            let (finish, turnaround) = (p.finish?, p.turnaround?);
+            let switches = dispatches.iter().filter(|&&time| time >= p.arrival && time < finish).count() as u64;
+            Some(turnaround + switches * switch_cost)
+        })
+        .collect();
+    match completed.len() {
+        0 => 0.0,
+        n => completed.iter().sum::<u64>() as f64 / n as f64,
+    }
+}
+
+/// The bottom of the curve: the point with the lowest mean turnaround, the
+/// smallest quantum among equals.
+pub fn knee(points: &[QuantumPoint]) -> Option<&QuantumPoint> {
+    points.iter().min_by(|a, b| a.mean_turnaround.total_cmp(&b.mean_turnaround).then(a.quantum.cmp(&b.quantum)))
+}
+
+/// The sweep as CSV, one line per quantum.
+pub fn to_csv(points: &[QuantumPoint]) -> String {
+    let mut out = String::from("quantum,mean_turnaround,dispatches\n");
+    for point in points {
+        writeln!(out, "{},{:.4},{}", point.quantum, point.mean_turnaround, point.dispatches).unwrap();
+    }
+    out
+}
+
+/// One estimation error of the `estimate-error` sweep, averaged over its
+/// runs.
+///
+/// # Fields
+/// - `error`: How far off SPN's estimates may be, in percent.
+/// - `mean_turnaround`: Mean turnaround of the completed jobs, in ticks.
+/// - `mean_waiting`: Mean waiting time of the completed jobs, in ticks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorPoint {
+    pub error: u32,
+    pub mean_turnaround: f64,
+    pub mean_waiting: f64,
+}
+
+/// How much longer `point`'s mean turnaround is than with exact estimates,
+/// in percent; `None` when the sweep has no error of `0` to compare with.
+pub fn increase(points: &[ErrorPoint], point: &ErrorPoint) -> Option<f64> {
+    let exact = points.iter().find(|p| p.error == 0)?;
+    Some(100.0 * (point.mean_turnaround - exact.mean_turnaround) / exact.mean_turnaround.max(f64::MIN_POSITIVE))
+}
+
+/// The error sweep as CSV, one line per error, with the [`increase`] left
+/// empty when it is unknown.
+pub fn error_csv(points: &[ErrorPoint]) -> String {
+    let mut out = String::from("error,mean_turnaround,mean_waiting,turnaround_increase\n");
+    for point in points {
+        let increase = increase(points, point).map_or(String::new(), |increase| format!("{:.2}", increase));
+        writeln!(out, "{},{:.4},{:.4},{}", point.error, point.mean_turnaround, point.mean_waiting, increase).unwrap();
+    }
+    out
+}
+
+/// Draws mean turnaround against the estimation error as an SVG line
+/// chart, with the errors evenly spaced along the x axis and exact
+/// estimates marked.
+pub fn error_svg(points: &[ErrorPoint]) -> String {
+    let dots: Vec<Dot> = points
+        .iter()
+        .map(|point| Dot {
+            label: format!("{}%", point.error),
+            value: point.mean_turnaround,
+            title: format!("error {}%: {:.2} ticks, waiting {:.2}", point.error, point.mean_turnaround, point.mean_waiting),
+            marked: point.error == 0,
+        })
+        .collect();
+    line_chart(&dots, "estimate error")
+}
+
+/// Draws mean turnaround against the quantum as an SVG line chart, with the
+/// quanta evenly spaced along the x axis and the knee marked.
+pub fn svg(points: &[QuantumPoint]) -> String {
+    let knee = knee(points).map(|p| p.quantum);
+    let dots: Vec<Dot> = points
+        .iter()
+        .map(|point| Dot {
+            label: point.quantum.to_string(),
+            value: point.mean_turnaround,
+            title: format!("quantum {}: {:.2} ticks, {} dispatches", point.quantum, point.mean_turnaround, point.dispatches),
+            marked: Some(point.quantum) == knee,
+        })
+        .collect();
+    line_chart(&dots, "quantum")
+}
+
+/// One point of a [`line_chart`].
+struct Dot {
+    label: String,
+    value: f64,
+    title: String,
+    marked: bool,
+}
+
+/// Draws turnarounds as an SVG line chart, the points evenly spaced along
+/// the x axis, which is titled `x_title`, and the marked ones in red.
+fn line_chart(dots: &[Dot], x_title: &str) -> String {
+    const LEFT: f64 = 70.0;
+    const TOP: f64 = 20.0;
+    const WIDTH: f64 = 700.0;
+    const HEIGHT: f64 = 400.0;
+    let highest = dots.iter().map(|dot| dot.value).fold(0.0, f64::max).max(1.0);
+    let step = WIDTH / dots.len().saturating_sub(1).max(1) as f64;
+    let x = |index: usize| LEFT + index as f64 * step;
+    let y = |turnaround: f64| TOP + HEIGHT * (1.0 - turnaround / highest);
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="12">"#,
+        LEFT + WIDTH + 30.0,
+        TOP + HEIGHT + 50.0
+    )
+    .unwrap();
+    // the axes
+    let bottom = TOP + HEIGHT;
+    writeln!(out, r#"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}" stroke="black"/>"#, LEFT, TOP, bottom).unwrap();
+    writeln!(out, r#"<line x1="{}" y1="{2}" x2="{}" y2="{2}" stroke="black"/>"#, LEFT, LEFT + WIDTH, bottom).unwrap();
+    writeln!(out, r#"<text x="{}" y="{}" text-anchor="end">{:.0}</text>"#, LEFT - 4.0, TOP + 10.0, highest).unwrap();
+    writeln!(out, r#"<text x="{}" y="{}" text-anchor="end">0</text>"#, LEFT - 4.0, bottom).unwrap();
+    writeln!(out, r#"<text x="4" y="{}">turnaround</text>"#, TOP + HEIGHT / 2.0).unwrap();
+    writeln!(out, r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#, LEFT + WIDTH / 2.0, bottom + 36.0, x_title).unwrap();
+    let line: Vec<String> = dots.iter().enumerate().map(|(i, dot)| format!("{:.2},{:.2}", x(i), y(dot.value))).collect();
+    writeln!(out, r##"<polyline points="{}" fill="none" stroke="#1f77b4" stroke-width="2"/>"##, line.join(" ")).unwrap();
+    for (i, dot) in dots.iter().enumerate() {
+        let color = if dot.marked { "#d62728" } else { "#1f77b4" };
+        writeln!(
+            out,
+            r#"<circle cx="{:.2}" cy="{:.2}" r="4" fill="{}"><title>{}</title></circle>"#,
+            x(i),
+            y(dot.value),
+            color,
+            dot.title
+        )
+        .unwrap();
+        writeln!(out, r#"<text x="{:.2}" y="{}" text-anchor="middle">{}</text>"#, x(i), bottom + 16.0, dot.label).unwrap();
+    }
+    out += "</svg>\n";
+    out
+}
+
+/// One run of the rules comparison.
+///
+/// # Fields
+/// - `rules`: The rule set the scheduler ran with.
+/// - `mean_turnaround`, `mean_response`, `mean_interaction_response`: As in
+///   the run's [`Aggregates`], in ticks.
+/// - `io_slowdown`, `cpu_slowdown`: Mean slowdown of the I/O-bound and the
+///   CPU-bound jobs, `None` if no job of that kind completed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RulesPoint {
+    pub rules: MlfqRules,
+    pub mean_turnaround: f64,
+    pub mean_response: f64,
+    pub mean_interaction_response: f64,
+    pub io_slowdown: Option<f64>,
+    pub cpu_slowdown: Option<f64>,
+}
+
+impl RulesPoint {
+    /// Measures a run with `rules` from its aggregates and processes.
+    pub fn measure(rules: MlfqRules, aggregates: &Aggregates, processes: &[ProcessMetrics]) -> Self {
+        let kinds = analysis::boundness(processes, analysis::DEFAULT_IO_BOUND);
+        let slowdown = |kind: &analysis::BoundClass| (kind.completed > 0).then_some(kind.mean_slowdown);
+        Self {
+            rules,
+            mean_turnaround: aggregates.mean_turnaround,
+            mean_response: aggregates.mean_response,
+            mean_interaction_response: aggregates.mean_interaction_response,
+            io_slowdown: slowdown(&kinds.io_bound),
+            cpu_slowdown: slowdown(&kinds.cpu_bound),
+        }
+    }
+}
+
+/// Writes the rules comparison as CSV, one row per rule set; a slowdown
+/// that could not be measured is left empty.
+pub fn rules_csv(points: &[RulesPoint]) -> String {
+    let mut out = String::from("rules,mean_turnaround,mean_response,mean_interaction_response,io_slowdown,cpu_slowdown\n");
+    let slowdown = |slowdown: Option<f64>| slowdown.map_or(String::new(), |slowdown| format!("{:.4}", slowdown));
+    for point in points {
+        writeln!(
+            out,
+            "{},{:.4},{:.4},{:.4},{},{}",
+            point.rules,
+            point.mean_turnaround,
+            point.mean_response,
+            point.mean_interaction_response,
+            slowdown(point.io_slowdown),
+            slowdown(point.cpu_slowdown)
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// One scheduler at one arrival rate of the saturation sweep, over the runs
+/// of the batch.
+///
+/// # Fields
+/// - `rate`: The Poisson arrival rate, in jobs per tick.
+/// - `load`: The offered load `rate * E[S]`, `None` if the mean burst is
+///   infinite.
+/// - `scheduler`: The scheduler's name.
+/// - `throughput`: Completed jobs per tick.
+/// - `response`: Mean response time, in ticks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaturationPoint {
+    pub rate: f64,
+    pub load: Option<f64>,
+    pub scheduler: String,
+    pub throughput: Estimate,
+    pub response: Estimate,
+}
+
+/// The lowest arrival rate at which `scheduler` got less than 90% of the
+/// arriving jobs per tick through, `None` if it kept up at every rate.
+pub fn saturation(points: &[SaturationPoint], scheduler: &str) -> Option<f64> {
+    points
+        .iter()
+        .filter(|point| point.scheduler == scheduler && point.throughput.mean < 0.9 * point.rate)
+        .map(|point| point.rate)
+        .min_by(f64::total_cmp)
+}
+
+/// The saturation sweep as CSV, one line per rate and scheduler, with the
+/// half widths of the 95% confidence intervals left empty when there was a
+/// single run.
+pub fn saturation_csv(points: &[SaturationPoint]) -> String {
+    let mut out = String::from("rate,load,scheduler,throughput,throughput_ci,mean_response,response_ci\n");
+    let half_width = |estimate: &Estimate| match estimate.half_width.is_finite() {
+        true => format!("{:.4}", estimate.half_width),
+        false => String::new(),
+    };
+    for point in points {
+        writeln!(
+            out,
+            "{},{},{},{:.4},{},{:.4},{}",
+            point.rate,
+            point.load.map_or(String::new(), |load| format!("{:.4}", load)),
+            point.scheduler,
+            point.throughput.mean,
+            half_width(&point.throughput),
+            point.response.mean,
+            half_width(&point.response)
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Draws the saturation curves as SVG: throughput and mean response time
+/// against the arrival rate, side by side, with one line per scheduler and
+/// the rates evenly spaced along the x axes. The throughput chart has a
+/// dashed line where throughput equals the arrival rate.
+pub fn saturation_svg(points: &[SaturationPoint]) -> String {
+    const LEFT: f64 = 70.0;
+    const TOP: f64 = 40.0;
+    const WIDTH: f64 = 400.0;
+    const HEIGHT: f64 = 300.0;
+    const GAP: f64 = 90.0;
+    const COLORS: [&str; 6] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b"];
+    let mut rates: Vec<f64> = points.iter().map(|point| point.rate).collect();
+    rates.sort_by(f64::total_cmp);
+    rates.dedup();
+    let mut schedulers: Vec<&str> = points.iter().map(|point| point.scheduler.as_str()).collect();
+    schedulers.sort();
+    schedulers.dedup();
+    let step = WIDTH / rates.len().saturating_sub(1).max(1) as f64;
+    let x = |left: f64, rate: f64| left + rates.iter().position(|&r| r == rate).unwrap_or(0) as f64 * step;
+    let bottom = TOP + HEIGHT;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="12">"#,
+        2.0 * (LEFT + WIDTH) + GAP,
+        bottom + 50.0
+    )
+    .unwrap();
+    for (chart, title) in ["throughput", "response"].into_iter().enumerate() {
+        let value = |point: &SaturationPoint| match chart {
+            0 => point.throughput.mean,
+            _ => point.response.mean,
+        };
+        let left = LEFT + chart as f64 * (LEFT + WIDTH + GAP);
+        let mut highest = points.iter().map(value).fold(0.0, f64::max);
+        if chart == 0 {
+            highest = rates.iter().copied().fold(highest, f64::max);
+        }
+        let highest = if highest > 0.0 { highest } else { 1.0 };
+        let y = |value: f64| TOP + HEIGHT * (1.0 - value / highest);
+        // the axes
+        writeln!(out, r#"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}" stroke="black"/>"#, left, TOP, bottom).unwrap();
+        writeln!(out, r#"<line x1="{}" y1="{2}" x2="{}" y2="{2}" stroke="black"/>"#, left, left + WIDTH, bottom).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}" text-anchor="end">{:.3}</text>"#, left - 4.0, TOP + 10.0, highest).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}" text-anchor="end">0</text>"#, left - 4.0, bottom).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#, left + WIDTH / 2.0, TOP - 8.0, title).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}" text-anchor="middle">arrival rate</text>"#, left + WIDTH / 2.0, bottom + 36.0)
+            .unwrap();
+        for &rate in &rates {
+            writeln!(out, r#"<text x="{:.2}" y="{}" text-anchor="middle">{}</text>"#, x(left, rate), bottom + 16.0, rate).unwrap();
+        }
+        if chart == 0 {
+            let line: Vec<String> = rates.iter().map(|&rate| format!("{:.2},{:.2}", x(left, rate), y(rate))).collect();
+            writeln!(out, r#"<polyline points="{}" fill="none" stroke="gray" stroke-dasharray="4"/>"#, line.join(" ")).unwrap();
+        }
+        for (i, scheduler) in schedulers.iter().enumerate() {
+            let color = COLORS[i % COLORS.len()];
+            let mut curve: Vec<&SaturationPoint> = points.iter().filter(|point| point.scheduler == *scheduler).collect();
+            curve.sort_by(|a, b| a.rate.total_cmp(&b.rate));
+            let line: Vec<String> =
+                curve.iter().map(|point| format!("{:.2},{:.2}", x(left, point.rate), y(value(point)))).collect();
+            writeln!(out, r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#, line.join(" "), color).unwrap();
+            for point in curve {
+                writeln!(
+                    out,
+                    r#"<circle cx="{:.2}" cy="{:.2}" r="3" fill="{}"><title>{} at rate {}: {:.4}</title></circle>"#,
+                    x(left, point.rate),
+                    y(value(point)),
+                    color,
+                    scheduler,
+                    point.rate,
+                    value(point)
+                )
+                .unwrap();
+            }
+        }
+    }
+    // the legend
+    for (i, scheduler) in schedulers.iter().enumerate() {
+        writeln!(out, r#"<text x="{}" y="16" fill="{}">{}</text>"#, LEFT + i as f64 * 120.0, COLORS[i % COLORS.len()], scheduler)
+            .unwrap();
+    }
+    out += "</svg>\n";
+    out
+}
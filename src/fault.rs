@@ -0,0 +1,158 @@
+//! # Fault Module
+//!
+//! Diagnostics for a scheduler that panics.
+//!
+//! A panic in a scheduler's `add_process` or `next_process` unwinds through
+//! the simulator and prints a raw message, and maybe a backtrace, in the
+//! middle of the trace. A run set up with
+//! [`catch_panics`](crate::simulation::SimulationBuilder::catch_panics), or
+//! a [classic](crate::classic::Classic::catch_panics) one, catches it instead and fails with a [`Fault`]: what the panic said and
+//! where, the clock, the last [`LAST_EVENTS`] events of the run and the
+//! processes the scheduler still held, as far as it can still tell. With
+//! `--catch-panics` the simulator prints the fault on stderr and exits with
+//! status 70.
+//!
+//! [`catch`] does the catching, for any code: the panic is not printed, and
+//! its message and location come back as a [`Panic`]. Panics on other
+//! threads, or outside of `catch`, are printed as usual.
+//!
+//! ## Example
+//! ```
+//! use scheduler::PCB;
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::fault::{self, Fault};
+//!
+//! let panic = fault::catch(|| {
+//!     let queue: Vec<PCB> = Vec::new();
+//!     queue[0]
+//! })
+//! .unwrap_err();
+//! assert_eq!(panic.message, "index out of bounds: the len is 0 but the index is 0");
+//! assert!(panic.location.is_some());
+//! assert_eq!(fault::catch(|| 6 * 7).ok(), Some(42));
+//!
+//! let fault = Fault {
+//!     panic: fault::Panic { message: "no process".into(), location: Some("src/mlf.rs:40:9".into()) },
+//!     time: 3,
+//!     events: vec![Event { time: 0, id: 1, kind: EventKind::Arrive { priority: 2 } }],
+//!     ready: Some(vec![PCB { id: 1, priority: 2, time_added: Some(0), time_scheduled: None }]),
+//! };
+//! assert_eq!(
+//!     fault.to_string(),
+//!     "SchedulerFault: the scheduler panicked at tick 3 at src/mlf.rs:40:9: no process\n\
+//!      last events:\n  tick 0: P1 arrived, priority 2\n\
+//!      ready: P1 (priority 2)\n"
+//! );
+//! ```
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use crate::event::Event;
+use crate::sink::Recorder;
+use crate::{CLOCK, PCB, Schedule};
+
+/// How many of the last events of a run a [`Fault`] keeps.
+pub const LAST_EVENTS: usize = 10;
+
+thread_local! {
+    /// set while [`catch`] runs on this thread
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    /// where the panic [`catch`] is catching happened
+    static LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Installs the panic hook that keeps [`catch`] quiet, once.
+fn install_hook() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| match CATCHING.get() {
+            true => LOCATION.set(info.location().map(|location| location.to_string())),
+            false => previous(info),
+        }));
+    });
+}
+
+/// A panic [`catch`] caught.
+///
+/// # Fields
+/// - `message`: What the panic said.
+/// - `location`: The file, line and column it happened at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panic {
+    pub message: String,
+    pub location: Option<String>,
+}
+
+/// Runs `f`, catching a panic without printing it.
+///
+/// Whatever `f` was changing when it panicked may be left half done; only
+/// look at it to report the panic.
+pub fn catch<T>(f: impl FnOnce() -> T) -> Result<T, Panic> {
+    install_hook();
+    let catching = CATCHING.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.set(catching);
+    result.map_err(|payload| {
+        let message = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+            (Some(message), _) => message.to_string(),
+            (_, Some(message)) => message.clone(),
+            _ => "a panic without a message".to_string(),
+        };
+        Panic { message, location: LOCATION.take() }
+    })
+}
+
+/// A scheduler that panicked, see the [module documentation](self).
+///
+/// # Fields
+/// - `panic`: The panic.
+/// - `time`: Tick of the clock when it happened.
+/// - `events`: The last events of the run before it, without `Execute`
+///   events, oldest first.
+/// - `ready`: The processes the scheduler held, as it lists them in
+///   [`Schedule::iter_ready`], or `None` if asking it panicked too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    pub panic: Panic,
+    pub time: u64,
+    pub events: Vec<Event>,
+    pub ready: Option<Vec<PCB>>,
+}
+
+impl Fault {
+    /// The fault of `sched` panicking with `panic`, at the current tick of
+    /// the [`CLOCK`], after the events recorded in `out`.
+    pub fn new(panic: Panic, sched: &dyn Schedule, out: &Recorder) -> Self {
+        let ready = catch(|| sched.iter_ready().copied().collect()).ok();
+        let events = out.events();
+        let events = events[events.len().saturating_sub(LAST_EVENTS)..].to_vec();
+        Self { panic, time: CLOCK.now_ticks(), events, ready }
+    }
+}
+
+impl fmt::Display for Fault {
+    /// Describes the panic, then the events and the queue, one per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SchedulerFault: the scheduler panicked at tick {}", self.time)?;
+        if let Some(location) = &self.panic.location {
+            write!(f, " at {}", location)?;
+        }
+        writeln!(f, ": {}", self.panic.message)?;
+        writeln!(f, "last events:")?;
+        for event in &self.events {
+            writeln!(f, "  {}", event)?;
+        }
+        match &self.ready {
+            Some(ready) if ready.is_empty() => writeln!(f, "ready: none"),
+            Some(ready) => {
+                let ready: Vec<String> = ready.iter().map(|p| format!("P{} (priority {})", p.id, p.priority)).collect();
+                writeln!(f, "ready: {}", ready.join(", "))
+            }
+            None => writeln!(f, "ready: unknown, the scheduler panicked again when asked"),
+        }
+    }
+}
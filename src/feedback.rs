@@ -0,0 +1,371 @@
+//! # Feedback Module
+//!
+//! A multi-level feedback queue assembled from ready-made levels.
+//!
+//! [`FeedbackSchedule`] keeps the feedback rules of an MLFQ and leaves the
+//! order *within* each level to a boxed [`Schedule`], so every level can use
+//! its own policy: round robin with different quanta near the top, and
+//! [`FCFS`](crate::policy::FCFS) or [`SJF`](crate::policy::SJF) at the
+//! bottom, say. Any `Schedule` works as a level, including your own.
+//!
+//! The feedback rules are OSTEP's:
+//! - a new process enters the top level, `0`;
+//! - a level runs only while every level above it is empty, and hands out
+//!   the time slice its policy returns;
+//! - a process that uses up a whole time slice is demoted one level, down
+//!   to the bottom one; a process that gives up the CPU before its slice is
+//!   over (because it blocked) stays where it is, and one that runs to
+//!   completion never comes back;
+//! - optionally, every `boost_every` ticks all processes are moved back to
+//!   the top level, so long-running processes cannot starve and a process
+//!   whose behaviour changed gets a second chance.
+//!
+//! How long a process ran is measured from its dispatch until the next
+//! dispatch, or until it is added back if that comes first.
+//!
+//! The levels are reported through [`Schedule::levels`] and friends, so
+//! `--step` shows them and the run summary's level table is exact.
+//!
+//! ## Stacks
+//! [`Stack`] assembles the same scheduler declaratively, a level at a time,
+//! and can be read from the `[stack]` table of a config file (see
+//! [`StackConfig`]), which is what `-s stack` runs:
+//! ```
+//! use scheduler::feedback::Stack;
+//! use scheduler::policy::{FCFS, RR};
+//! use scheduler::{Schedule, TimeSlice};
+//!
+//! let stack = Stack::new().level(RR::with_quantum(2)).level(RR::with_quantum(4)).level(FCFS::new()).boost_every(100);
+//! assert_eq!(stack.levels(), 3);
+//! assert_eq!(stack.level_quantum(1), TimeSlice::Ticks(4));
+//! assert_eq!(stack.boost_period(), Some(100));
+//! ```
+//!
+//! ## Example
+//! ```
+//! use std::time::Duration;
+//! use scheduler::feedback::FeedbackSchedule;
+//! use scheduler::policy::{RR, SJF};
+//! use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! CLOCK.set_now(Duration::ZERO);
+//! let pcb = |id| PCB { id, priority: 0, time_added: None, time_scheduled: None };
+//! // MLFQ with SJF at the bottom
+//! let mut sched = FeedbackSchedule::new(vec![Box::new(RR::with_quantum(2)), Box::new(SJF::new())]);
+//! sched.add_process(pcb(1));
+//! sched.add_process(pcb(2));
+//!
+//! // P1 uses its whole slice and is demoted; P2 blocks after 1 tick and stays
+//! let (p1, slice) = sched.next_process();
+//! assert_eq!((p1.unwrap().id, slice, sched.dispatched_level()), (1, TimeSlice::Ticks(2), Some(0)));
+//! CLOCK.advance(Duration::from_nanos(2));
+//! sched.add_process(p1.unwrap());
+//! let (p2, _) = sched.next_process();
+//! CLOCK.advance(Duration::from_nanos(1));
+//! assert_eq!(sched.level_of(1), Some(1));
+//! sched.add_process(p2.unwrap());
+//! assert_eq!(sched.level_of(2), Some(0));
+//!
+//! // level 0 goes first, then SJF runs P1 to completion
+//! assert_eq!(sched.next_process().0.unwrap().id, 2);
+//! assert_eq!(sched.next_process(), (p1, TimeSlice::Infinite));
+//! assert_eq!(sched.dispatched_level(), Some(1));
+//! ```
+
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+
+use crate::config::StackConfig;
+use crate::{CLOCK, Complexity, PCB, Schedule, SchedulerInfo, TimeSlice};
+
+/// The process on the CPU, as far as the scheduler knows.
+#[derive(Debug, Clone, Copy)]
+struct Running {
+    id: u32,
+    level: usize,
+    since: u64,
+    slice: TimeSlice,
+}
+
+/// A multi-level feedback queue with a policy per level, see the
+/// [module documentation](self).
+pub struct FeedbackSchedule {
+    levels: Vec<Box<dyn Schedule>>,
+    /// the level of every process the scheduler has seen and not lost track of
+    level_of: BTreeMap<u32, usize>,
+    running: Option<Running>,
+    /// processes that stopped running before being added back, with their
+    /// level, slice and how long they ran
+    stopped: BTreeMap<u32, (usize, TimeSlice, u64)>,
+    dispatched: Option<usize>,
+    /// how often processes are moved back to the top level, and when next
+    boost: Option<(u64, u64)>,
+}
+
+impl FeedbackSchedule {
+    /// A scheduler with `levels`, the highest priority first.
+    ///
+    /// # Panics
+    /// If there are no levels.
+    pub fn new(levels: Vec<Box<dyn Schedule>>) -> Self {
+        assert!(!levels.is_empty(), "a feedback scheduler needs at least one level");
+        Self { levels, level_of: BTreeMap::new(), running: None, stopped: BTreeMap::new(), dispatched: None, boost: None }
+    }
+
+    /// Moves every process back to the top level once every `ticks` ticks,
+    /// counted from tick `0`. `0` never does.
+    pub fn boost_every(mut self, ticks: u64) -> Self {
+        self.boost = (ticks > 0).then_some((ticks, ticks));
+        self
+    }
+
+    /// How often processes are moved back to the top level, if ever.
+    pub fn boost_period(&self) -> Option<u64> {
+        self.boost.map(|(period, _)| period)
+    }
+
+    /// The level process `id` is at, or would be added back to.
+    pub fn level_of(&self, id: u32) -> Option<usize> {
+        if let Some(&(level, slice, ran)) = self.stopped.get(&id) {
+            return Some(self.after_run(level, slice, ran));
+        }
+        match self.running {
+            Some(running) if running.id == id => {
+                Some(self.after_run(running.level, running.slice, CLOCK.now_ticks() - running.since))
+            }
+            _ => self.level_of.get(&id).copied(),
+        }
+    }
+
+    /// The level a process goes back to after running for `ran` ticks of a
+    /// `slice` at `level`.
+    fn after_run(&self, level: usize, slice: TimeSlice, ran: u64) -> usize {
+        match slice {
+            TimeSlice::Ticks(quantum) if ran >= quantum as u64 => (level + 1).min(self.levels.len() - 1),
+            _ => level,
+        }
+    }
+
+    /// Notes that the process last dispatched stopped running by now.
+    fn settle(&mut self) {
+        if let Some(running) = self.running.take() {
+            let ran = CLOCK.now_ticks() - running.since;
+            self.stopped.insert(running.id, (running.level, running.slice, ran));
+        }
+    }
+
+    /// Moves every process back to the top level if a boost is due.
+    ///
+    /// Ready processes are taken out of the lower levels in the order those
+    /// levels would have run them, and processes that are not ready will
+    /// come back at the top.
+    fn boost(&mut self) {
+        let Some((period, due)) = self.boost else {
+            return;
+        };
+        let now = CLOCK.now_ticks();
+        if now < due {
+            return;
+        }
+        self.boost = Some((period, (now / period + 1) * period));
+        let (top, lower) = self.levels.split_at_mut(1);
+        for level in lower {
+            while let (Some(process), _) = level.next_process() {
+                top[0].add_process(process);
+            }
+        }
+        for level in self.level_of.values_mut() {
+            *level = 0;
+        }
+        for stopped in self.stopped.values_mut() {
+            *stopped = (0, TimeSlice::Infinite, 0);
+        }
+    }
+}
+
+impl Schedule for FeedbackSchedule {
+    fn add_process(&mut self, process: PCB) -> bool {
+        if self.running.is_some_and(|running| running.id == process.id) {
+            self.settle();
+        }
+        let level = match self.stopped.remove(&process.id) {
+            Some((level, slice, ran)) => self.after_run(level, slice, ran),
+            None => 0,
+        };
+        self.level_of.insert(process.id, level);
+        self.levels[level].add_process(process)
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        self.settle();
+        self.boost();
+        let Some(level) = self.levels.iter().position(|level| level.has_process()) else {
+            self.dispatched = None;
+            return (None, TimeSlice::Infinite);
+        };
+        let (process, slice) = self.levels[level].next_process();
+        self.dispatched = process.map(|_| level);
+        if let Some(process) = process {
+            self.level_of.remove(&process.id);
+            self.running = Some(Running { id: process.id, level, since: CLOCK.now_ticks(), slice });
+        }
+        (process, slice)
+    }
+
+    fn has_process(&self) -> bool {
+        self.levels.iter().any(|level| level.has_process())
+    }
+
+    fn levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    fn level_quantum(&self, level: usize) -> TimeSlice {
+        self.levels.get(level).map_or(TimeSlice::Infinite, |level| level.level_quantum(0))
+    }
+
+    fn iter_level(&self, level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        match self.levels.get(level) {
+            Some(level) => level.iter_level(0),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        self.levels.iter().find(|level| level.has_process()).and_then(|level| level.peek())
+    }
+
+    fn dispatched_level(&self) -> Option<usize> {
+        self.dispatched
+    }
+
+    fn heap_size(&self) -> usize {
+        self.levels.iter().map(|level| level.heap_size()).sum()
+    }
+
+    /// Combines what the levels report, and is unknown if any level's is.
+    /// Without a boost, a process demoted to a lower level can starve
+    /// behind a stream of new processes at the top, so only a boosted
+    /// stack whose top level starves no one is starvation-free. Finding
+    /// the first level with a process adds a scan of the levels to their
+    /// own dispatch, which does not grow with the number of processes.
+    fn info(&self) -> Option<SchedulerInfo> {
+        let levels = self.levels.iter().map(|level| level.info()).collect::<Option<Vec<_>>>()?;
+        let top = levels.first()?;
+        Some(SchedulerInfo {
+            is_preemptive: levels.iter().any(|level| level.is_preemptive),
+            starvation_free: top.starvation_free && (levels.len() == 1 || self.boost.is_some()),
+            levels: levels.len(),
+            default_quantum: top.default_quantum,
+            dispatch: levels.iter().map(|level| level.dispatch).max().unwrap_or(Complexity::Constant),
+        })
+    }
+}
+
+/// A [`FeedbackSchedule`] assembled one level at a time, see the
+/// [module documentation](self).
+pub struct Stack {
+    inner: FeedbackSchedule,
+}
+
+impl Stack {
+    /// A stack without levels yet; add at least one before using it.
+    pub fn new() -> Self {
+        let inner = FeedbackSchedule {
+            levels: Vec::new(),
+            level_of: BTreeMap::new(),
+            running: None,
+            stopped: BTreeMap::new(),
+            dispatched: None,
+            boost: None,
+        };
+        Self { inner }
+    }
+
+    /// The stack with `policy` added below its current levels.
+    pub fn level(mut self, policy: impl Schedule + 'static) -> Self {
+        self.inner.levels.push(Box::new(policy));
+        self
+    }
+
+    /// The stack, moving every process back to the top level once every
+    /// `ticks` ticks. `0` never does.
+    pub fn boost_every(self, ticks: u64) -> Self {
+        Self { inner: self.inner.boost_every(ticks) }
+    }
+
+    /// The stack a `[stack]` config table describes.
+    pub fn with_config(config: StackConfig) -> Self {
+        let inner = FeedbackSchedule::new(config.levels.iter().map(|policy| policy.build()).collect());
+        Self { inner: inner.boost_every(config.boost_every) }
+    }
+
+    /// Returns the assembled scheduler.
+    pub fn into_inner(self) -> FeedbackSchedule {
+        self.inner
+    }
+}
+
+impl Default for Stack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Stack {
+    type Target = FeedbackSchedule;
+
+    fn deref(&self) -> &FeedbackSchedule {
+        &self.inner
+    }
+}
+
+impl DerefMut for Stack {
+    fn deref_mut(&mut self) -> &mut FeedbackSchedule {
+        &mut self.inner
+    }
+}
+
+impl Schedule for Stack {
+    fn add_process(&mut self, process: PCB) -> bool {
+        assert!(!self.inner.levels.is_empty(), "a stack needs at least one level");
+        self.inner.add_process(process)
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        self.inner.next_process()
+    }
+
+    fn has_process(&self) -> bool {
+        self.inner.has_process()
+    }
+
+    fn levels(&self) -> usize {
+        self.inner.levels()
+    }
+
+    fn level_quantum(&self, level: usize) -> TimeSlice {
+        self.inner.level_quantum(level)
+    }
+
+    fn iter_level(&self, level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        self.inner.iter_level(level)
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        self.inner.peek()
+    }
+
+    fn dispatched_level(&self) -> Option<usize> {
+        self.inner.dispatched_level()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.inner.heap_size()
+    }
+
+    fn info(&self) -> Option<SchedulerInfo> {
+        self.inner.info()
+    }
+}
@@ -0,0 +1,213 @@
+//! # Foreground/Background Module
+//!
+//! The two-queue scheduler from Silberschatz's *Operating System Concepts*:
+//! interactive **foreground** processes take turns round robin, **background**
+//! batch processes run first come, first served, and the CPU is time-sliced
+//! between the two queues so that the foreground gets 80% of it and the
+//! background 20%.
+//!
+//! Processes with priority `0` are foreground, any other priority is
+//! background. The split is enforced over a sliding window of the last
+//! [`FgBgConfig::window`] ticks: while both queues have work, the background
+//! runs whenever its share of the CPU time used in the window has fallen
+//! below `100 - share` percent. A queue with nothing to run leaves the whole
+//! CPU to the other one.
+//!
+//! Background processes run in slices of [`FgBgConfig::background_slice`]
+//! ticks, and one that is preempted goes back to the *front* of its queue, so
+//! it keeps running, slice by slice, until it finishes or blocks. With the
+//! defaults a foreground slice of 4 ticks alternates with a background slice
+//! of 1 tick, which is the 80/20 split of the book's figure. Because the two
+//! queues hand out different slices, the level residence table of the run
+//! summary shows how much time each process spent in each queue.
+//!
+//! `--strict` expects a process that is added back at the same tick as a new
+//! arrival of the same priority to run after it, so it reports every
+//! background process that resumes ahead of such an arrival. Those are
+//! deliberate.
+//!
+//! ## Example
+//! ```
+//! use std::time::Duration;
+//! use scheduler::fgbg::FgBgSchedule;
+//! use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! CLOCK.set_now(Duration::ZERO);
+//! let mut sched = FgBgSchedule::new();
+//! let pcb = |id, priority| PCB { id, priority, time_added: None, time_scheduled: None };
+//! sched.add_process(pcb(0, 0));
+//! sched.add_process(pcb(1, 0));
+//! sched.add_process(pcb(2, 1));
+//! sched.add_process(pcb(3, 1));
+//!
+//! // two CPU-bound processes in each queue share the CPU 80/20
+//! let mut ran = [0; 4];
+//! for _ in 0..100 {
+//!     let (Some(process), TimeSlice::Ticks(quantum)) = sched.next_process() else { break };
+//!     assert_eq!(sched.dispatched_level(), Some(process.priority as usize));
+//!     CLOCK.advance(Duration::from_nanos(quantum as u64));
+//!     ran[process.id as usize] += quantum;
+//!     sched.add_process(process);
+//! }
+//! let foreground = ran[0] + ran[1];
+//! assert_eq!(foreground * 100 / (foreground + ran[2] + ran[3]), 80);
+//! // the background queue is FCFS: P3 waits until P2 is done
+//! assert_eq!(ran[3], 0);
+//! ```
+
+use std::collections::VecDeque;
+
+use crate::config::FgBgConfig;
+use crate::{CLOCK, Complexity, PCB, Schedule, SchedulerInfo, TimeSlice};
+
+const FOREGROUND: usize = 0;
+const BACKGROUND: usize = 1;
+
+/// A foreground/background scheduler, see the [module documentation](self).
+pub struct FgBgSchedule {
+    config: FgBgConfig,
+    /// the foreground and the background queue
+    queues: [VecDeque<PCB>; 2],
+    /// CPU time recently given to each queue, as `(queue, start, end)`
+    used: VecDeque<(usize, u64, u64)>,
+    /// the process last dispatched, its queue, and the tick it was dispatched at
+    running: Option<(u32, usize, u64)>,
+}
+
+impl FgBgSchedule {
+    /// Creates a new instance of the scheduler with the textbook's 80/20
+    /// split.
+    pub fn new() -> Self {
+        Self::with_config(FgBgConfig::default())
+    }
+
+    /// Creates a new instance of the scheduler using `config`.
+    pub fn with_config(config: FgBgConfig) -> Self {
+        Self { config, queues: [VecDeque::new(), VecDeque::new()], used: VecDeque::new(), running: None }
+    }
+
+    /// The CPU time given to the foreground and to the background queue
+    /// during the last `window` ticks.
+    pub fn recent_use(&self) -> [u64; 2] {
+        let now = CLOCK.now_ticks();
+        let from = now.saturating_sub(self.config.window);
+        let running = self.running.map(|(_, queue, since)| (queue, since, now));
+        let mut recent = [0; 2];
+        for (queue, start, end) in self.used.iter().copied().chain(running) {
+            recent[queue] += end.saturating_sub(start.max(from));
+        }
+        recent
+    }
+
+    /// Records the CPU time of the last dispatched process, which stopped
+    /// running by now, and forgets what fell out of the window.
+    fn settle(&mut self) {
+        let now = CLOCK.now_ticks();
+        if let Some((_, queue, since)) = self.running.take()
+            && now > since
+        {
+            self.used.push_back((queue, since, now));
+        }
+        let from = now.saturating_sub(self.config.window);
+        while self.used.front().is_some_and(|&(_, _, end)| end <= from) {
+            self.used.pop_front();
+        }
+    }
+
+    /// The queue to dispatch from: the background while it is below its
+    /// share of the window, otherwise the foreground, unless one is empty.
+    fn pick(&self) -> Option<usize> {
+        match self.queues.each_ref().map(|queue| !queue.is_empty()) {
+            [false, false] => None,
+            [true, false] => Some(FOREGROUND),
+            [false, true] => Some(BACKGROUND),
+            [true, true] => {
+                let [foreground, background] = self.recent_use();
+                let starved = background * 100 < (100 - self.config.share) * (foreground + background);
+                Some(if starved { BACKGROUND } else { FOREGROUND })
+            }
+        }
+    }
+}
+
+impl Default for FgBgSchedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Schedule for FgBgSchedule {
+    /// Queues `process` by its priority. A background process coming back
+    /// from the CPU resumes at the front of the background queue.
+    fn add_process(&mut self, process: PCB) -> bool {
+        let resumed = self.running.is_some_and(|(id, _, _)| id == process.id);
+        if resumed {
+            self.settle();
+        }
+        match process.priority {
+            0 => self.queues[FOREGROUND].push_back(process),
+            _ if resumed => self.queues[BACKGROUND].push_front(process),
+            _ => self.queues[BACKGROUND].push_back(process),
+        }
+        true
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        // a process that was not handed back has finished or blocked
+        self.settle();
+        let Some(queue) = self.pick() else {
+            return (None, TimeSlice::Infinite);
+        };
+        let process = self.queues[queue].pop_front();
+        self.running = process.map(|process| (process.id, queue, CLOCK.now_ticks()));
+        (process, self.level_quantum(queue))
+    }
+
+    fn has_process(&self) -> bool {
+        self.queues.iter().any(|queue| !queue.is_empty())
+    }
+
+    fn levels(&self) -> usize {
+        2
+    }
+
+    fn dispatched_level(&self) -> Option<usize> {
+        self.running.map(|(_, queue, _)| queue)
+    }
+
+    fn level_quantum(&self, level: usize) -> TimeSlice {
+        match level {
+            FOREGROUND => TimeSlice::Ticks(self.config.quantum),
+            _ => TimeSlice::Ticks(self.config.background_slice),
+        }
+    }
+
+    fn iter_level(&self, level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        match self.queues.get(level) {
+            Some(queue) => Box::new(queue.iter()),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        self.queues[self.pick()?].front()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.queues.iter().map(|queue| queue.capacity() * size_of::<PCB>()).sum::<usize>()
+            + self.used.capacity() * size_of::<(usize, u64, u64)>()
+    }
+
+    /// The background queue only starves if the foreground is given the
+    /// whole CPU.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: true,
+            starvation_free: self.config.share < 100,
+            levels: 2,
+            default_quantum: TimeSlice::Ticks(self.config.quantum),
+            dispatch: Complexity::Constant,
+        })
+    }
+}
@@ -0,0 +1,349 @@
+//! # Gantt Module
+//!
+//! Draws runs as Gantt charts, for `scheduler compare`.
+//!
+//! A [`Timeline`] rebuilds from a run's event log what every process was
+//! doing at every tick: running, ready and waiting for the CPU, or blocked
+//! for I/O. Several timelines are drawn one above the other on a shared time
+//! axis, either as text ([`ascii`]) or as an SVG image ([`svg`]), so the way
+//! two schedulers treat the same job can be compared at a glance. A run's
+//! [`Annotation`]s are drawn as vertical markers across its rows, labeled,
+//! so a chart pasted into a report explains itself.
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::{Annotation, Event, EventKind};
+//! use scheduler::gantt::{self, State, Timeline};
+//!
+//! let at = |time, id, kind| Event { time, id, kind };
+//! let fcfs = [
+//!     at(0, 0, EventKind::Arrive { priority: 0 }),
+//!     at(0, 1, EventKind::Arrive { priority: 0 }),
+//!     at(0, 0, EventKind::Dispatch { quantum: 0 }),
+//!     at(4, 0, EventKind::Finish),
+//!     at(4, 1, EventKind::Dispatch { quantum: 0 }),
+//!     at(6, 1, EventKind::Finish),
+//! ];
+//! let rr = [
+//!     at(0, 0, EventKind::Arrive { priority: 0 }),
+//!     at(0, 1, EventKind::Arrive { priority: 0 }),
+//!     at(0, 0, EventKind::Dispatch { quantum: 2 }),
+//!     at(2, 0, EventKind::Preempt),
+//!     at(2, 1, EventKind::Dispatch { quantum: 2 }),
+//!     at(4, 1, EventKind::Finish),
+//!     at(4, 0, EventKind::Dispatch { quantum: 2 }),
+//!     at(6, 0, EventKind::Finish),
+//! ];
+//! let fcfs = Timeline::from_events("fcfs", &fcfs);
+//! let timeline = Timeline::from_events("rr", &rr);
+//! assert_eq!(timeline.state_at(0, 3), Some(State::Ready));
+//! assert_eq!(timeline.state_at(0, 6), None);
+//!
+//! let chart = gantt::ascii(&[fcfs, timeline], 80);
+//! assert_eq!(
+//!     chart,
+//!     "   0\n\
+//!      fcfs\n\
+//!      P0 ####\n\
+//!      P1 ....##\n\
+//!      rr\n\
+//!      P0 ##..##\n\
+//!      P1 ..##\n\
+//!      legend: # running  + partly running  . ready  - blocked  (1 column = 1 tick)\n"
+//! );
+//!
+//! // a marker gets a row of its own, above the processes
+//! let boost = Annotation { time: 2, label: "P1 gets its turn".to_string() };
+//! let rr = Timeline::from_events("rr", &rr).with_annotations(&[boost]);
+//! let chart = gantt::ascii(&[rr], 80);
+//! assert_eq!(chart.lines().nth(2), Some("     |  2 P1 gets its turn"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::event::{Annotation, Event, EventKind};
+
+/// What a process is doing during a [`Span`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Ready,
+    Blocked,
+    Running,
+}
+
+/// A stretch of time, `[start, end)` in ticks, that a process spent in one
+/// [`State`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub state: State,
+    pub start: u64,
+    pub end: u64,
+}
+
+/// What every process of one run was doing over time.
+///
+/// # Fields
+/// - `label`: Name of the run in the chart, e.g. its scheduler.
+/// - `processes`: The spans of every process, by id, in time order.
+/// - `end`: The tick of the run's last event.
+/// - `annotations`: The run's markers, in time order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timeline {
+    pub label: String,
+    pub processes: BTreeMap<u32, Vec<Span>>,
+    pub end: u64,
+    pub annotations: Vec<Annotation>,
+}
+
+impl Timeline {
+    /// Rebuilds a timeline from an event log in the order the events
+    /// happened, as kept by a [`Recorder`](crate::sink::Recorder). Processes
+    /// still in the system at the last event are drawn up to it.
+    pub fn from_events(label: impl Into<String>, events: &[Event]) -> Self {
+        let mut timeline = Timeline { label: label.into(), ..Timeline::default() };
+        let mut open: BTreeMap<u32, (State, u64)> = BTreeMap::new();
+        for event in events {
+            timeline.end = timeline.end.max(event.time);
+            let next = match event.kind {
+                // a throttled process is drawn as waiting for the CPU, which it is
+                EventKind::Arrive { .. }
+                | EventKind::Preempt
+                | EventKind::Wake
+                | EventKind::Throttle
+                | EventKind::Unthrottle => Some(State::Ready),
+                EventKind::Dispatch { .. } => Some(State::Running),
+                EventKind::Block => Some(State::Blocked),
+                EventKind::Finish | EventKind::Timeout => None,
+                EventKind::Execute | EventKind::Idle | EventKind::Renice { .. } => continue,
+            };
+            if let Some((state, start)) = open.remove(&event.id) {
+                timeline.close(event.id, state, start, event.time);
+            }
+            match next {
+                Some(state) => {
+                    open.insert(event.id, (state, event.time));
+                }
+                // a finished process keeps its (possibly empty) row
+                None => {
+                    timeline.processes.entry(event.id).or_default();
+                }
+            }
+        }
+        for (id, (state, start)) in open {
+            timeline.close(id, state, start, timeline.end);
+        }
+        timeline
+    }
+
+    /// Adds the run's `annotations`. Markers after the run's last event are
+    /// left out.
+    pub fn with_annotations(mut self, annotations: &[Annotation]) -> Self {
+        self.annotations.extend(annotations.iter().filter(|a| a.time <= self.end).cloned());
+        self.annotations.sort_by_key(|a| a.time);
+        self
+    }
+
+    fn close(&mut self, id: u32, state: State, start: u64, end: u64) {
+        let spans = self.processes.entry(id).or_default();
+        if end > start {
+            spans.push(Span { state, start, end });
+        }
+    }
+
+    /// What process `id` was doing during tick `tick`, or `None` if it was not
+    /// in the system.
+    pub fn state_at(&self, id: u32, tick: u64) -> Option<State> {
+        self.processes
+            .get(&id)?
+            .iter()
+            .find(|span| span.start <= tick && tick < span.end)
+            .map(|span| span.state)
+    }
+}
+
+/// Draws the timelines as text, one row per process, stacked on a shared
+/// time axis of at most `width` columns. When the runs are longer than that,
+/// each column covers several ticks and shows the state the process spent
+/// most of them in, or `+` if it ran for some but not most of them. A run's
+/// annotations get a row of their own above its processes, with a `|` in
+/// the column of each marker, followed by their ticks and labels.
+pub fn ascii(timelines: &[Timeline], width: usize) -> String {
+    let end = timelines.iter().map(|t| t.end).max().unwrap_or(0).max(1);
+    let scale = end.div_ceil(width.max(1) as u64);
+    let columns = end.div_ceil(scale) as usize;
+    let margin = timelines
+        .iter()
+        .flat_map(|t| t.processes.keys())
+        .map(|id| format!("P{}", id).len() + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    // the axis: a tick label every 10 columns, where it fits
+    let mut axis = String::new();
+    for column in (0..columns).step_by(10) {
+        let at = margin + column;
+        if axis.is_empty() || at > axis.len() {
+            axis += &" ".repeat(at - axis.len());
+            axis += &(column as u64 * scale).to_string();
+        }
+    }
+    out += &axis;
+    out.push('\n');
+    for timeline in timelines {
+        out += &timeline.label;
+        out.push('\n');
+        if !timeline.annotations.is_empty() {
+            let mut markers = vec![' '; columns];
+            for annotation in &timeline.annotations {
+                markers[((annotation.time / scale) as usize).min(columns - 1)] = '|';
+            }
+            let labels: Vec<String> = timeline.annotations.iter().map(|a| format!("{} {}", a.time, a.label)).collect();
+            let markers: String = markers.into_iter().collect();
+            writeln!(out, "{:<margin$}{}  {}", "", markers.trim_end(), labels.join("; ")).unwrap();
+        }
+        for (id, spans) in &timeline.processes {
+            // ticks spent ready, blocked and running in each column
+            let mut row = vec![[0; 3]; columns];
+            for span in spans {
+                for column in (span.start / scale)..=((span.end - 1) / scale) {
+                    let from = span.start.max(column * scale);
+                    let until = span.end.min((column + 1) * scale);
+                    row[column as usize][span.state as usize] += until - from;
+                }
+            }
+            let row: String = row
+                .iter()
+                .map(|ticks| match ticks {
+                    [0, 0, 0] => ' ',
+                    [ready, blocked, running] if running >= ready && running >= blocked => '#',
+                    [_, _, running] if *running > 0 => '+',
+                    [ready, blocked, _] if blocked >= ready => '-',
+                    _ => '.',
+                })
+                .collect();
+            writeln!(out, "{:<margin$}{}", format!("P{}", id), row.trim_end()).unwrap();
+        }
+    }
+    writeln!(
+        out,
+        "legend: # running  + partly running  . ready  - blocked  (1 column = {} tick{})",
+        scale,
+        if scale == 1 { "" } else { "s" }
+    )
+    .unwrap();
+    out
+}
+
+/// The process colors of the SVG chart, in the order of
+/// [`PrettySink`](crate::sink::PrettySink)'s terminal colors.
+const COLORS: [&str; 12] = [
+    "#d62728", "#2ca02c", "#bcbd22", "#1f77b4", "#9467bd", "#17becf", "#ff7f7f", "#98df8a", "#dbdb8d",
+    "#aec7e8", "#c5b0d5", "#9edae5",
+];
+
+/// Draws the timelines as an SVG image: each process gets its own color, a
+/// solid bar where it runs, a faint bar where it is ready, and an outline
+/// where it is blocked. A run's annotations are dashed vertical lines across
+/// its rows, labeled above them. The timelines share one time axis.
+pub fn svg(timelines: &[Timeline]) -> String {
+    const LEFT: f64 = 60.0;
+    const WIDTH: f64 = 1000.0;
+    const ROW: f64 = 18.0;
+    const TITLE: f64 = 24.0;
+    let end = timelines.iter().map(|t| t.end).max().unwrap_or(0).max(1);
+    let x = |tick: u64| LEFT + tick as f64 * WIDTH / end as f64;
+    let rows: usize = timelines.iter().map(|t| t.processes.len()).sum();
+    let height = TITLE * timelines.len() as f64 + ROW * rows as f64 + 30.0;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="12">"#,
+        LEFT + WIDTH + 20.0,
+        height
+    )
+    .unwrap();
+    let mut y = 0.0;
+    for timeline in timelines {
+        writeln!(out, r#"<text x="4" y="{}" font-weight="bold">{}</text>"#, y + 16.0, escape(&timeline.label)).unwrap();
+        y += TITLE;
+        let top = y;
+        for (id, spans) in &timeline.processes {
+            let color = COLORS[*id as usize % COLORS.len()];
+            writeln!(out, r#"<text x="4" y="{}">P{}</text>"#, y + 13.0, id).unwrap();
+            for span in spans {
+                let (x0, x1) = (x(span.start), x(span.end));
+                let style = match span.state {
+                    State::Running => format!(r#"fill="{}""#, color),
+                    State::Ready => format!(r#"fill="{}" fill-opacity="0.2""#, color),
+                    State::Blocked => format!(r#"fill="none" stroke="{}""#, color),
+                };
+                writeln!(
+                    out,
+                    r#"<rect x="{:.2}" y="{}" width="{:.2}" height="{}" {}><title>P{} {:?} {}..{}</title></rect>"#,
+                    x0,
+                    y + 2.0,
+                    x1 - x0,
+                    ROW - 4.0,
+                    style,
+                    id,
+                    span.state,
+                    span.start,
+                    span.end
+                )
+                .unwrap();
+            }
+            y += ROW;
+        }
+        for annotation in &timeline.annotations {
+            writeln!(
+                out,
+                r##"<line x1="{0:.2}" y1="{1}" x2="{0:.2}" y2="{2}" stroke="#555" stroke-dasharray="4 2"/><text x="{3:.2}" y="{4}" font-size="10" fill="#555">{5}</text>"##,
+                x(annotation.time),
+                top,
+                y,
+                x(annotation.time) + 3.0,
+                top - 2.0,
+                escape(&annotation.label)
+            )
+            .unwrap();
+        }
+    }
+    // the shared time axis
+    writeln!(out, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black"/>"#, x(0), y + 4.0, x(end), y + 4.0).unwrap();
+    let step = nice_step(end);
+    for tick in (0..=end).step_by(step as usize) {
+        writeln!(
+            out,
+            r#"<line x1="{0:.2}" y1="{1}" x2="{0:.2}" y2="{2}" stroke="black"/><text x="{0:.2}" y="{3}" text-anchor="middle">{4}</text>"#,
+            x(tick),
+            y + 4.0,
+            y + 9.0,
+            y + 22.0,
+            tick
+        )
+        .unwrap();
+    }
+    out += "</svg>\n";
+    out
+}
+
+/// A round step (1, 2 or 5 times a power of ten) giving about ten axis
+/// labels for a run of `end` ticks.
+fn nice_step(end: u64) -> u64 {
+    let mut power = 1;
+    loop {
+        for step in [power, 2 * power, 5 * power] {
+            if end / step <= 10 {
+                return step;
+            }
+        }
+        power *= 10;
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
@@ -0,0 +1,228 @@
+//! # Generate Module
+//!
+//! Produces random workloads for experiments, for the simulator's `gen`
+//! subcommand.
+//!
+//! A workload is described by how many jobs it has, the [`Distribution`] of
+//! the gaps between arrivals and of the CPU bursts, and the number of
+//! priority levels to spread the jobs over. The first job arrives at tick
+//! `0`; every time is rounded to whole ticks and bursts are at least one
+//! tick long. The same [`Generator`] always produces the same workload.
+//!
+//! ## Job Classes
+//! A workload can instead mix several [`JobClass`]es, e.g. 90% short
+//! interactive jobs and 10% long batch jobs. Each class has its own arrival
+//! stream, burst distribution and priority, and its jobs carry the class's
+//! name in the class column so results can be broken down by class. The jobs
+//! are split between the classes by their shares, every class's first job
+//! arrives at tick `0`, and the streams are merged in arrival order.
+//!
+//! ## Example
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::generate::{self, Generator};
+//! use scheduler::input::Workload;
+//!
+//! let generator = Generator {
+//!     jobs: 50,
+//!     arrival: "poisson:0.1".parse().unwrap(),
+//!     burst: "pareto:1.2,2".parse().unwrap(),
+//!     priorities: 3,
+//!     classes: Vec::new(),
+//!     seed: 7,
+//! };
+//! let jobs = generator.generate();
+//! assert_eq!(jobs.len(), 50);
+//! assert!(jobs.iter().all(|job| job.burst >= 2 && job.priority < 3));
+//! assert_eq!(jobs, generator.generate());
+//!
+//! // the output is an ordinary input file
+//! let text = generate::to_text(&jobs);
+//! let workload = Workload::parse(text.lines(), TimeUnit::Ns, true).unwrap();
+//! assert_eq!(workload.jobs.len(), 50);
+//!
+//! // 90% interactive jobs at priority 0, 10% batch jobs at priority 2
+//! let mixed = Generator {
+//!     classes: vec![
+//!         "interactive/0.9/poisson:0.2/exponential:2".parse().unwrap(),
+//!         "batch/0.1/poisson:0.02/constant:100/2".parse().unwrap(),
+//!     ],
+//!     ..generator
+//! };
+//! let jobs = mixed.generate();
+//! let batch: Vec<_> = jobs.iter().filter(|job| job.class.as_deref() == Some("batch")).collect();
+//! assert_eq!(batch.len(), 5);
+//! assert!(batch.iter().all(|job| job.burst == 100 && job.priority == 2));
+//! assert!(jobs.windows(2).all(|pair| pair[0].arrival <= pair[1].arrival));
+//! assert!(generate::to_text(&jobs).lines().any(|line| line.ends_with(" 2 batch")));
+//! ```
+
+use std::str::FromStr;
+
+use crate::distribution::Distribution;
+use crate::input::JobSpec;
+use crate::random::Rng;
+
+/// The parameters of a random workload.
+///
+/// # Fields
+/// - `jobs`: How many jobs to generate.
+/// - `arrival`: Distribution of the gap between one arrival and the next.
+/// - `burst`: Distribution of each job's CPU time.
+/// - `priorities`: Number of priority levels; priorities are drawn uniformly
+///   from `0..priorities`.
+/// - `classes`: The job classes to mix; when there are any, they replace
+///   `arrival`, `burst` and `priorities`.
+/// - `seed`: Seed for the random number generator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generator {
+    pub jobs: u32,
+    pub arrival: Distribution,
+    pub burst: Distribution,
+    pub priorities: u32,
+    pub classes: Vec<JobClass>,
+    pub seed: u64,
+}
+
+/// One class of jobs in a mixed workload.
+///
+/// Written `name/share/arrival/burst[/priority]` on the command line, e.g.
+/// `batch/0.1/poisson:0.01/pareto:1.5,50/2`.
+///
+/// # Fields
+/// - `name`: The label written in the class column.
+/// - `share`: The class's share of the jobs, relative to the other classes.
+/// - `arrival`: Distribution of the gap between two arrivals of this class.
+/// - `burst`: Distribution of the CPU time of this class's jobs.
+/// - `priority`: Priority of every job of the class, `0` by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobClass {
+    pub name: String,
+    pub share: f64,
+    pub arrival: Distribution,
+    pub burst: Distribution,
+    pub priority: u32,
+}
+
+impl FromStr for JobClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = s.split('/').collect();
+        let [name, share, arrival, burst, rest @ ..] = fields.as_slice() else {
+            return Err(format!("'{}' is not a job class, expected name/share/arrival/burst[/priority]", s));
+        };
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("'{}' is not a class name, it must be one word", name));
+        }
+        let share = share
+            .parse::<f64>()
+            .ok()
+            .filter(|share| share.is_finite() && *share > 0.0)
+            .ok_or_else(|| format!("{}: share '{}' is not a positive number", name, share))?;
+        let priority = match rest {
+            [] => 0,
+            [priority] => priority.parse().map_err(|_| format!("{}: '{}' is not a priority", name, priority))?,
+            _ => return Err(format!("{}: too many fields, expected name/share/arrival/burst[/priority]", name)),
+        };
+        Ok(JobClass { name: name.to_string(), share, arrival: arrival.parse()?, burst: burst.parse()?, priority })
+    }
+}
+
+impl Generator {
+    /// Generates the jobs, with IDs counting up from `0` in arrival order.
+    pub fn generate(&self) -> Vec<JobSpec> {
+        if !self.classes.is_empty() {
+            return self.generate_classes();
+        }
+        let mut rng = Rng::new(self.seed);
+        let mut clock = 0.0;
+        (0..self.jobs)
+            .map(|id| {
+                if id > 0 {
+                    clock += self.arrival.sample(&mut rng);
+                }
+                let burst = self.burst.sample(&mut rng).round().clamp(1.0, u32::MAX as f64) as u32;
+                let priority = (rng.next_u64() % self.priorities.max(1) as u64) as u32;
+                JobSpec { id, arrival: clock.round() as u64, burst, priority, class: None, nice: 0, timeout: None }
+            })
+            .collect()
+    }
+
+    /// Generates every class's jobs from its own arrival stream and merges
+    /// the streams; jobs arriving together are ordered by class.
+    fn generate_classes(&self) -> Vec<JobSpec> {
+        let mut rng = Rng::new(self.seed);
+        let mut jobs = Vec::new();
+        for (index, (class, count)) in self.classes.iter().zip(self.split()).enumerate() {
+            let mut clock = 0.0;
+            for seq in 0..count {
+                if seq > 0 {
+                    clock += class.arrival.sample(&mut rng);
+                }
+                let burst = class.burst.sample(&mut rng).round().clamp(1.0, u32::MAX as f64) as u32;
+                let job = JobSpec {
+                    id: 0,
+                    arrival: clock.round() as u64,
+                    burst,
+                    priority: class.priority,
+                    class: Some(class.name.clone()),
+                    nice: 0,
+                    timeout: None,
+                };
+                jobs.push((index, seq, job));
+            }
+        }
+        jobs.sort_by_key(|(index, seq, job)| (job.arrival, *index, *seq));
+        jobs.into_iter()
+            .zip(0..)
+            .map(|((_, _, job), id)| JobSpec { id, ..job })
+            .collect()
+    }
+
+    /// How many jobs each class gets: its share of the total, rounded so
+    /// that the largest remainders get the jobs left over.
+    fn split(&self) -> Vec<u32> {
+        let total: f64 = self.classes.iter().map(|class| class.share).sum();
+        let exact: Vec<f64> =
+            self.classes.iter().map(|class| class.share / total * self.jobs as f64).collect();
+        let mut counts: Vec<u32> = exact.iter().map(|share| share.floor() as u32).collect();
+        let mut order: Vec<usize> = (0..exact.len()).collect();
+        order.sort_by(|&a, &b| (exact[b] - exact[b].floor()).total_cmp(&(exact[a] - exact[a].floor())));
+        let left = self.jobs - counts.iter().sum::<u32>();
+        for &index in order.iter().take(left as usize) {
+            counts[index] += 1;
+        }
+        counts
+    }
+}
+
+/// Writes jobs in the input file format, priority column included, the
+/// class column for jobs that have a class, the nice value of jobs whose
+/// nice value is not `0`, and the timeout of jobs that have one.
+pub fn to_text(jobs: &[JobSpec]) -> String {
+    to_columns(jobs, true)
+}
+
+/// Writes jobs like [`to_text`], leaving out the priority column when
+/// `priority` is `false` unless a job has a class, which comes after it.
+pub fn to_columns(jobs: &[JobSpec], priority: bool) -> String {
+    jobs.iter()
+        .map(|job| {
+            let mut line = format!("{} {} {}", job.id, job.arrival, job.burst);
+            if priority || job.class.is_some() {
+                line += &format!(" {}", job.priority);
+            }
+            if let Some(class) = &job.class {
+                line += &format!(" {}", class);
+            }
+            if job.nice != 0 {
+                line += &format!(" nice={}", job.nice);
+            }
+            if let Some(timeout) = job.timeout {
+                line += &format!(" timeout={}", timeout);
+            }
+            line + "\n"
+        })
+        .collect()
+}
@@ -0,0 +1,264 @@
+//! # Grade Module
+//!
+//! Scores a scheduler against a reference corpus, for `scheduler grade`.
+//!
+//! `scheduler grade -s <name>` runs the scheduler on every workload of a
+//! [corpus](crate::corpus) built from the reference solutions and gives
+//! each workload three checks:
+//!
+//! - **dispatch order**: the processes are dispatched in the same order as
+//!   by the reference solution;
+//! - **metrics**: makespan and mean turnaround, waiting and response time
+//!   are within a relative tolerance of the reference's;
+//! - **invariants**: the run keeps the `Schedule` contract, as checked by
+//!   [`selfcheck`](crate::selfcheck).
+//!
+//! A [`Rubric`] says how many points each check is worth; a check earns its
+//! points in proportion to the workloads that pass it. The default rubric
+//! weighs the three 40, 30 and 30 points, and a rubric file changes that:
+//! ```toml
+//! [dispatch_order]
+//! points = 50
+//!
+//! [metrics]
+//! points = 30
+//! tolerance = 0.1
+//!
+//! [invariants]
+//! points = 20
+//! ```
+//!
+//! The [`Score`] is written as JSON in the format autograders such as
+//! Gradescope take for upload: a total `score` and one entry per check in
+//! `tests`, with its `score`, `max_score` and an `output` listing the
+//! workloads that failed it.
+//!
+//! ## Example
+//! ```
+//! use scheduler::grade::{Outcome, Rubric};
+//!
+//! let rubric = Rubric::from_toml("[metrics]\ntolerance = 0.1").unwrap();
+//! let pass = Outcome { workload: "light-s0".into(), dispatch_order: Ok(()), metrics: Ok(()), invariants: Ok(()) };
+//! let late = Outcome { workload: "heavy-s0".into(), dispatch_order: Err("dispatch 3: expected P2, got P4".into()), ..pass.clone() };
+//!
+//! let score = rubric.score(&[pass, late]);
+//! assert_eq!(score.score, 80.0);
+//! assert_eq!((score.tests[0].score, score.tests[0].max_score), (20.0, 40.0));
+//! assert_eq!(score.tests[0].output, "1 of 2 workloads passed\nheavy-s0: dispatch 3: expected P2, got P4\n");
+//! assert!(score.to_json().starts_with(r#"{"score":80.0,"tests":[{"name":"dispatch order""#));
+//!
+//! let err = Rubric::from_toml("[invariants]\npoints = -5").unwrap_err();
+//! assert_eq!(err, "invariants.points: must be a number of points, 0 or more");
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::event::EventKind;
+use crate::results::Results;
+
+/// A check worth some points.
+///
+/// # Fields
+/// - `points`: What passing the check on every workload is worth.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Criterion {
+    pub points: f64,
+}
+
+/// The metrics check.
+///
+/// # Fields
+/// - `points`: What passing the check on every workload is worth.
+/// - `tolerance`: How far each metric may be off, relative to the
+///   reference, e.g. `0.05` for 5%.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsCriterion {
+    pub points: f64,
+    pub tolerance: f64,
+}
+
+impl Default for MetricsCriterion {
+    fn default() -> Self {
+        Self { points: 30.0, tolerance: 0.05 }
+    }
+}
+
+/// How a run is scored, as read from a rubric file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rubric {
+    pub dispatch_order: Criterion,
+    pub metrics: MetricsCriterion,
+    pub invariants: Criterion,
+}
+
+impl Default for Rubric {
+    /// 40 points for the dispatch order, 30 for metrics within 5% and 30
+    /// for the invariants.
+    fn default() -> Self {
+        Self {
+            dispatch_order: Criterion { points: 40.0 },
+            metrics: MetricsCriterion::default(),
+            invariants: Criterion { points: 30.0 },
+        }
+    }
+}
+
+impl Rubric {
+    /// Parses and validates a TOML rubric file. Missing tables and fields
+    /// keep their defaults, unknown ones are rejected.
+    ///
+    /// # Returns
+    /// The rubric, or a message describing the first problem found.
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let rubric: Self = toml::from_str(text).map_err(|err| err.message().trim().to_string())?;
+        rubric.validate()?;
+        Ok(rubric)
+    }
+
+    /// Checks that every check is worth a sensible number of points.
+    pub fn validate(&self) -> Result<(), String> {
+        let points = |name: &str, points: f64| match points.is_finite() && points >= 0.0 {
+            true => Ok(()),
+            false => Err(format!("{}.points: must be a number of points, 0 or more", name)),
+        };
+        points("dispatch_order", self.dispatch_order.points)?;
+        points("metrics", self.metrics.points)?;
+        points("invariants", self.invariants.points)?;
+        if !(self.metrics.tolerance.is_finite() && self.metrics.tolerance >= 0.0) {
+            return Err("metrics.tolerance: must be a fraction, 0 or more".to_string());
+        }
+        Ok(())
+    }
+
+    /// The most points a run can score.
+    pub fn max_score(&self) -> f64 {
+        self.dispatch_order.points + self.metrics.points + self.invariants.points
+    }
+
+    /// Scores the `outcomes` of every workload.
+    pub fn score(&self, outcomes: &[Outcome]) -> Score {
+        let test = |name: &str, points: f64, check: fn(&Outcome) -> &Result<(), String>| {
+            let failed: Vec<String> = outcomes
+                .iter()
+                .filter_map(|outcome| check(outcome).as_ref().err().map(|err| format!("{}: {}\n", outcome.workload, err)))
+                .collect();
+            let passed = outcomes.len() - failed.len();
+            let score = match outcomes.len() {
+                0 => 0.0,
+                n => points * passed as f64 / n as f64,
+            };
+            let output = format!("{} of {} workloads passed\n{}", passed, outcomes.len(), failed.concat());
+            Test { name: name.to_string(), score, max_score: points, output }
+        };
+        let tests = vec![
+            test("dispatch order", self.dispatch_order.points, |outcome| &outcome.dispatch_order),
+            test("metrics", self.metrics.points, |outcome| &outcome.metrics),
+            test("invariants", self.invariants.points, |outcome| &outcome.invariants),
+        ];
+        Score { score: tests.iter().map(|test| test.score).sum(), tests }
+    }
+}
+
+/// How a run on one workload fared in each check, with a description of
+/// the problem for the checks it failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub workload: String,
+    pub dispatch_order: Result<(), String>,
+    pub metrics: Result<(), String>,
+    pub invariants: Result<(), String>,
+}
+
+impl Outcome {
+    /// The outcome of a run that produced no results, failing every check
+    /// with `reason`.
+    pub fn failed(workload: impl Into<String>, reason: &str) -> Self {
+        let fail = Err(reason.to_string());
+        Self { workload: workload.into(), dispatch_order: fail.clone(), metrics: fail.clone(), invariants: fail }
+    }
+
+    /// The outcome of the run `actual` against the reference run `expected`,
+    /// given what [`selfcheck`](crate::selfcheck) found wrong with it.
+    pub fn new(workload: impl Into<String>, expected: &Results, actual: &Results, tolerance: f64, problems: &[String]) -> Self {
+        Self {
+            workload: workload.into(),
+            dispatch_order: dispatch_order(expected, actual),
+            metrics: metrics(expected, actual, tolerance),
+            invariants: match problems.first() {
+                None => Ok(()),
+                Some(first) => Err(first.lines().next().unwrap_or_default().to_string()),
+            },
+        }
+    }
+}
+
+/// Checks that `actual` dispatches processes in the order `expected` does.
+pub fn dispatch_order(expected: &Results, actual: &Results) -> Result<(), String> {
+    let dispatches = |results: &Results| -> Vec<u32> {
+        results.events.iter().filter(|event| matches!(event.kind, EventKind::Dispatch { .. })).map(|event| event.id).collect()
+    };
+    let (expected, actual) = (dispatches(expected), dispatches(actual));
+    let show = |id: Option<&u32>| id.map_or("nothing".to_string(), |id| format!("P{}", id));
+    match (0..expected.len().max(actual.len())).find(|&i| expected.get(i) != actual.get(i)) {
+        None => Ok(()),
+        Some(i) => Err(format!("dispatch {}: expected {}, got {}", i + 1, show(expected.get(i)), show(actual.get(i)))),
+    }
+}
+
+/// Checks that the makespan and the mean turnaround, waiting and response
+/// times of `actual` are within `tolerance` of those of `expected`,
+/// relative to `expected`.
+pub fn metrics(expected: &Results, actual: &Results, tolerance: f64) -> Result<(), String> {
+    let (e, a) = (&expected.aggregates, &actual.aggregates);
+    let pairs = [
+        ("makespan", e.makespan as f64, a.makespan as f64),
+        ("mean turnaround", e.mean_turnaround, a.mean_turnaround),
+        ("mean waiting", e.mean_waiting, a.mean_waiting),
+        ("mean response", e.mean_response, a.mean_response),
+    ];
+    let off: Vec<String> = pairs
+        .iter()
+        .filter(|(_, expected, actual)| (actual - expected).abs() > tolerance * expected.abs())
+        .map(|(name, expected, actual)| format!("{} {:.2}, expected {:.2}", name, actual, expected))
+        .collect();
+    match off.is_empty() {
+        true => Ok(()),
+        false => Err(off.join(", ")),
+    }
+}
+
+/// The score of a run, as uploaded to the autograder.
+///
+/// # Fields
+/// - `score`: The points earned.
+/// - `tests`: The points earned by each check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Score {
+    pub score: f64,
+    pub tests: Vec<Test>,
+}
+
+/// The points a check earned.
+///
+/// # Fields
+/// - `name`: The check, e.g. `dispatch order`.
+/// - `score`: The points earned.
+/// - `max_score`: The points it is worth.
+/// - `output`: How many workloads passed, and why the others failed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Test {
+    pub name: String,
+    pub score: f64,
+    pub max_score: f64,
+    pub output: String,
+}
+
+impl Score {
+    /// The score as JSON in the autograder's upload format.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("scores serialize")
+    }
+}
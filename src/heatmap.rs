@@ -0,0 +1,201 @@
+//! # Heatmap Module
+//!
+//! Average waiting time by arrival time and burst length, for spotting
+//! systematic biases in large random runs.
+//!
+//! A single mean waiting time hides *who* waited. A [`Heatmap`] bins the
+//! finished jobs of a run by when they arrived and how much CPU time they
+//! used, and keeps the mean waiting time of each bin, so that e.g. an MLFQ
+//! starving the long jobs that arrive late in the run shows up as a hot
+//! corner. It is written as CSV for plotting, or drawn directly as SVG.
+//!
+//! Bins are equally wide and cover every finished job: arrivals from tick `0`
+//! to the last arrival, bursts from the shortest to the longest. Jobs that
+//! did not finish have no waiting time and are left out.
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::heatmap::Heatmap;
+//! use scheduler::metrics::Metrics;
+//!
+//! let mut metrics = Metrics::new();
+//! // (id, arrival, burst, finish)
+//! for (id, arrival, burst, finish) in [(0, 0, 2, 2), (1, 0, 8, 10), (2, 9, 2, 12)] {
+//!     metrics.record(&Event { time: arrival, id, kind: EventKind::Arrive { priority: 0 } });
+//!     for time in finish - burst..finish {
+//!         metrics.record(&Event { time, id, kind: EventKind::Execute });
+//!     }
+//!     metrics.record(&Event { time: finish, id, kind: EventKind::Finish });
+//! }
+//! let heatmap = Heatmap::new(&metrics.processes(), 2, 2);
+//! // P1 is long and waited 2 ticks; P2 arrived late and waited 1 tick
+//! assert_eq!(heatmap.cell(0, 1).mean_waiting(), Some(2.0));
+//! assert_eq!(heatmap.cell(1, 0).mean_waiting(), Some(1.0));
+//! assert_eq!(heatmap.cell(1, 1).jobs, 0);
+//! assert!(heatmap.to_csv().starts_with("arrival_from,arrival_to,burst_from,burst_to,jobs,mean_waiting\n0,5,2,6,1,0\n"));
+//! ```
+
+use std::fmt::Write;
+
+use crate::metrics::ProcessMetrics;
+
+/// The jobs that fell into one bin of a [`Heatmap`].
+///
+/// # Fields
+/// - `jobs`: Number of finished jobs in the bin.
+/// - `waiting`: Their total waiting time, in ticks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cell {
+    pub jobs: u64,
+    pub waiting: u64,
+}
+
+impl Cell {
+    /// The mean waiting time of the bin's jobs, `None` if it has none.
+    pub fn mean_waiting(&self) -> Option<f64> {
+        (self.jobs > 0).then(|| self.waiting as f64 / self.jobs as f64)
+    }
+}
+
+/// Mean waiting time binned by arrival time and burst length, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    /// first arrival tick and width of the arrival bins
+    arrival: (u64, u64),
+    /// shortest burst and width of the burst bins
+    burst: (u64, u64),
+    /// cells by arrival bin, then burst bin
+    cells: Vec<Vec<Cell>>,
+}
+
+impl Heatmap {
+    /// Bins the finished ones of `processes` into `arrival_bins` by
+    /// `burst_bins` cells. Bins never get narrower than one tick, so short
+    /// runs may use fewer of them.
+    pub fn new(processes: &[ProcessMetrics], arrival_bins: usize, burst_bins: usize) -> Self {
+        let finished: Vec<(u64, u64, u64)> =
+            processes.iter().filter_map(|p| p.waiting.map(|waiting| (p.arrival, p.cpu, waiting))).collect();
+        let last_arrival = finished.iter().map(|&(arrival, ..)| arrival).max().unwrap_or(0);
+        let shortest = finished.iter().map(|&(_, cpu, _)| cpu).min().unwrap_or(0);
+        let longest = finished.iter().map(|&(_, cpu, _)| cpu).max().unwrap_or(0);
+        let arrival = (0, bin_width(last_arrival + 1, arrival_bins));
+        let burst = (shortest, bin_width(longest - shortest + 1, burst_bins));
+        let arrival_bins = (last_arrival / arrival.1 + 1) as usize;
+        let burst_bins = ((longest - shortest) / burst.1 + 1) as usize;
+        let mut cells = vec![vec![Cell::default(); burst_bins]; arrival_bins];
+        for (at, cpu, waiting) in finished {
+            let cell = &mut cells[(at / arrival.1) as usize][((cpu - shortest) / burst.1) as usize];
+            cell.jobs += 1;
+            cell.waiting += waiting;
+        }
+        Self { arrival, burst, cells }
+    }
+
+    /// Number of arrival bins.
+    pub fn arrival_bins(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Number of burst bins.
+    pub fn burst_bins(&self) -> usize {
+        self.cells.first().map_or(0, Vec::len)
+    }
+
+    /// The ticks of arrival bin `bin`, as a half-open range.
+    pub fn arrival_range(&self, bin: usize) -> (u64, u64) {
+        let (from, width) = self.arrival;
+        (from + bin as u64 * width, from + (bin as u64 + 1) * width)
+    }
+
+    /// The CPU times of burst bin `bin`, as a half-open range.
+    pub fn burst_range(&self, bin: usize) -> (u64, u64) {
+        let (from, width) = self.burst;
+        (from + bin as u64 * width, from + (bin as u64 + 1) * width)
+    }
+
+    /// The jobs of arrival bin `arrival` and burst bin `burst`.
+    ///
+    /// # Panics
+    /// If either bin is out of range.
+    pub fn cell(&self, arrival: usize, burst: usize) -> Cell {
+        self.cells[arrival][burst]
+    }
+
+    /// The heatmap as CSV, one line per cell, with the mean waiting time
+    /// left empty for cells without jobs.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("arrival_from,arrival_to,burst_from,burst_to,jobs,mean_waiting\n");
+        for (a, row) in self.cells.iter().enumerate() {
+            for (b, cell) in row.iter().enumerate() {
+                let (arrival_from, arrival_to) = self.arrival_range(a);
+                let (burst_from, burst_to) = self.burst_range(b);
+                let mean = cell.mean_waiting().map(|mean| format!("{:.2}", mean)).unwrap_or_default();
+                let mean = mean.trim_end_matches('0').trim_end_matches('.');
+                writeln!(out, "{},{},{},{},{},{}", arrival_from, arrival_to, burst_from, burst_to, cell.jobs, mean).unwrap();
+            }
+        }
+        out
+    }
+
+    /// Draws the heatmap as an SVG image, with arrival time along the x axis
+    /// and burst length up the y axis. Cells are shaded from white (no wait)
+    /// to red (the longest mean wait), and cells without jobs are grey.
+    pub fn svg(&self) -> String {
+        const LEFT: f64 = 70.0;
+        const TOP: f64 = 20.0;
+        const SIZE: f64 = 600.0;
+        let (columns, rows) = (self.arrival_bins().max(1), self.burst_bins().max(1));
+        let (cell_w, cell_h) = (SIZE / columns as f64, SIZE / rows as f64);
+        let hottest =
+            self.cells.iter().flatten().filter_map(Cell::mean_waiting).fold(0.0, f64::max).max(f64::MIN_POSITIVE);
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="12">"#,
+            LEFT + SIZE + 20.0,
+            TOP + SIZE + 50.0
+        )
+        .unwrap();
+        for (a, row) in self.cells.iter().enumerate() {
+            for (b, cell) in row.iter().enumerate() {
+                let x = LEFT + a as f64 * cell_w;
+                let y = TOP + SIZE - (b + 1) as f64 * cell_h;
+                let (fill, label) = match cell.mean_waiting() {
+                    Some(mean) => {
+                        //fade the green and blue channels out as the wait grows
+                        let cool = (255.0 * (1.0 - mean / hottest)).round() as u8;
+                        (format!("#ff{:02x}{:02x}", cool, cool), format!("{:.1} ticks, {} jobs", mean, cell.jobs))
+                    }
+                    None => ("#dddddd".to_string(), "no jobs".to_string()),
+                };
+                let (arrival_from, arrival_to) = self.arrival_range(a);
+                let (burst_from, burst_to) = self.burst_range(b);
+                writeln!(
+                    out,
+                    r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"><title>arrival {}..{}, burst {}..{}: {}</title></rect>"#,
+                    x, y, cell_w, cell_h, fill, arrival_from, arrival_to, burst_from, burst_to, label
+                )
+                .unwrap();
+            }
+        }
+        // the axes, labelled at their ends
+        let bottom = TOP + SIZE;
+        let (arrival_end, burst_end) = (self.arrival_range(columns - 1).1, self.burst_range(rows - 1).1);
+        writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, LEFT, bottom + 16.0, self.arrival.0).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#, LEFT + SIZE, bottom + 16.0, arrival_end).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}" text-anchor="middle">arrival time</text>"#, LEFT + SIZE / 2.0, bottom + 36.0).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#, LEFT - 4.0, bottom, self.burst.0).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#, LEFT - 4.0, TOP + 10.0, burst_end).unwrap();
+        writeln!(out, r#"<text x="4" y="{}">burst</text>"#, TOP + SIZE / 2.0).unwrap();
+        out += "</svg>\n";
+        out
+    }
+}
+
+/// The width of `bins` equal bins covering `span` ticks, at least one tick.
+fn bin_width(span: u64, bins: usize) -> u64 {
+    span.div_ceil(bins.max(1) as u64).max(1)
+}
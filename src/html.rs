@@ -0,0 +1,196 @@
+//! # HTML Module
+//!
+//! A run as a single HTML page, for `--report-html report.html`.
+//!
+//! The page is what a lab write-up needs from a run, in one file that opens
+//! in any browser and can be attached or printed as it is: the scheduler,
+//! workload and provenance of the run, the summary metrics, a table of every
+//! process, the Gantt chart, a chart of where each process's time went and
+//! the heatmap of waiting time by arrival and burst length. The charts are
+//! inline SVG and the styles an inline stylesheet, so the page loads nothing
+//! from anywhere else.
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::EventKind;
+//! use scheduler::html;
+//! use scheduler::results::{Results, RunConfig};
+//! use scheduler::sink::Recorder;
+//!
+//! let mut out = Recorder::new();
+//! out.emit(1, EventKind::Arrive { priority: 0 });
+//! out.emit(1, EventKind::Dispatch { quantum: 0 });
+//! out.emit(1, EventKind::Execute);
+//! out.emit(1, EventKind::Finish);
+//! let config = RunConfig { scheduler: "simple".into(), input_file: "<stdin>".into(), ..Default::default() };
+//! let page = html::report(&Results::new(config, &out));
+//! assert!(page.starts_with("<!DOCTYPE html>"));
+//! assert!(page.contains("<title>simple on &lt;stdin&gt;</title>"));
+//! assert!(page.contains("<td>completed</td><td>1/1</td>"));
+//! assert_eq!(page.matches("<svg").count(), 3);
+//! // nothing is loaded from elsewhere
+//! assert!(!page.contains("src=") && !page.contains("<link"));
+//! ```
+
+use std::fmt::Write;
+
+use crate::gantt::{self, Timeline};
+use crate::heatmap::Heatmap;
+use crate::metrics::ProcessMetrics;
+use crate::results::Results;
+
+/// Bins of the heatmap, by arrival and by burst length.
+const HEATMAP_BINS: usize = 10;
+
+/// The stylesheet of the page.
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em auto; max-width: 1100px; color: #222; }
+h1 { font-size: 1.5em; }
+h2 { font-size: 1.2em; margin-top: 2em; border-bottom: 1px solid #ccc; }
+.meta { color: #666; font-size: 0.9em; }
+table { border-collapse: collapse; font-family: monospace; }
+td, th { padding: 2px 10px; text-align: right; border-bottom: 1px solid #eee; }
+th { background: #f4f4f4; }
+table.summary td:first-child { text-align: left; }
+.chart { overflow-x: auto; }
+";
+
+/// Builds the report page of a finished run.
+pub fn report(results: &Results) -> String {
+    let unit = results.config.time_unit;
+    let time = |ticks: u64| unit.format_ns(ticks * unit.nanos());
+    let opt = |ticks: Option<u64>| ticks.map_or("-".to_string(), time);
+    let mean = |ticks: f64| unit.format_ns((ticks * unit.nanos() as f64).round() as u64);
+    let title = format!("{} on {}", results.config.scheduler, results.config.input_file);
+
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(out, "<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>", escape(&title), STYLE).unwrap();
+    writeln!(out, "<h1>{}</h1>", escape(&title)).unwrap();
+    let mut meta = vec![format!("one tick is 1{}", unit)];
+    if let Some(provenance) = &results.provenance {
+        meta.push(format!("scheduler v{}", provenance.version));
+        if let Some(hash) = &provenance.git_hash {
+            meta.push(format!("commit {}", hash));
+        }
+        meta.push(format!("input {}", provenance.input_hash));
+        meta.push(format!("config {}", provenance.config_hash));
+        if let Some(seed) = provenance.seed {
+            meta.push(format!("seed {}", seed));
+        }
+        meta.extend(provenance.tags.iter().map(|(key, value)| format!("{}={}", key, value)));
+    }
+    writeln!(out, "<p class=\"meta\">{}</p>", escape(&meta.join(" · "))).unwrap();
+
+    let agg = &results.aggregates;
+    writeln!(out, "<h2>Summary</h2>\n<table class=\"summary\">").unwrap();
+    let mut rows = vec![
+        ("completed", format!("{}/{}", agg.completed, agg.processes)),
+        ("makespan", time(agg.makespan)),
+        ("mean turnaround", mean(agg.mean_turnaround)),
+        ("mean waiting", mean(agg.mean_waiting)),
+        ("mean response", mean(agg.mean_response)),
+        ("throughput", format!("{:.4} per tick", agg.throughput)),
+        ("utilization", format!("{:.1}% (idle {})", agg.utilization * 100.0, time(agg.idle))),
+        ("scheduling latency", format!("mean {}, p99 {}, max {}", mean(agg.mean_latency), time(agg.p99_latency), time(agg.max_latency))),
+    ];
+    if agg.interactions > 0 {
+        let response = format!("mean {}, p99 {} over {} interactions", mean(agg.mean_interaction_response), time(agg.p99_interaction_response), agg.interactions);
+        rows.push(("interaction response", response));
+    }
+    if agg.deadlines > 0 {
+        let deadlines = format!("{:.1}% finished in time, {} of {} timed out", agg.within_deadline * 100.0, agg.timed_out, agg.deadlines);
+        rows.push(("deadlines", deadlines));
+    }
+    for (name, value) in rows {
+        writeln!(out, "<tr><td>{}</td><td>{}</td></tr>", name, escape(&value)).unwrap();
+    }
+    writeln!(out, "</table>").unwrap();
+
+    writeln!(out, "<h2>Processes</h2>\n<table>").unwrap();
+    let columns = ["id", "priority", "arrival", "first run", "finish", "CPU", "I/O", "dispatches", "turnaround", "waiting", "response"];
+    writeln!(out, "<tr>{}</tr>", columns.map(|column| format!("<th>{}</th>", column)).concat()).unwrap();
+    for p in &results.processes {
+        let cells = [
+            p.id.to_string(),
+            p.priority.to_string(),
+            time(p.arrival),
+            opt(p.first_run),
+            opt(p.finish),
+            time(p.cpu),
+            time(p.io),
+            p.dispatches.to_string(),
+            opt(p.turnaround),
+            opt(p.waiting),
+            opt(p.response),
+        ];
+        writeln!(out, "<tr>{}</tr>", cells.map(|cell| format!("<td>{}</td>", cell)).concat()).unwrap();
+    }
+    writeln!(out, "</table>").unwrap();
+
+    let timeline = Timeline::from_events(results.config.scheduler.as_str(), &results.events).with_annotations(&results.annotations);
+    writeln!(out, "<h2>Gantt chart</h2>\n<div class=\"chart\">\n{}</div>", gantt::svg(&[timeline])).unwrap();
+    writeln!(out, "<h2>Where the time went</h2>\n<div class=\"chart\">\n{}</div>", breakdown(&results.processes)).unwrap();
+    let heatmap = Heatmap::new(&results.processes, HEATMAP_BINS, HEATMAP_BINS);
+    writeln!(out, "<h2>Waiting by arrival and burst length</h2>\n<div class=\"chart\">\n{}</div>", heatmap.svg()).unwrap();
+    writeln!(out, "</body>\n</html>").unwrap();
+    out
+}
+
+/// Draws a bar per process, from its arrival to its finish: the CPU time it
+/// got, the time it spent blocked for I/O and the time it waited, in ticks.
+fn breakdown(processes: &[ProcessMetrics]) -> String {
+    const LEFT: f64 = 60.0;
+    const WIDTH: f64 = 800.0;
+    const ROW: f64 = 18.0;
+    const PARTS: [(&str, &str); 3] = [("CPU", "#1f77b4"), ("I/O", "#bcbd22"), ("waiting", "#d62728")];
+    let parts = |p: &ProcessMetrics| [p.cpu, p.io, p.waiting.unwrap_or(0)];
+    let longest = processes.iter().map(|p| parts(p).iter().sum::<u64>()).max().unwrap_or(0).max(1);
+    let height = ROW * processes.len() as f64 + 40.0;
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="12">"#,
+        LEFT + WIDTH + 20.0,
+        height
+    )
+    .unwrap();
+    for (row, p) in processes.iter().enumerate() {
+        let y = row as f64 * ROW;
+        writeln!(out, r#"<text x="4" y="{}">P{}</text>"#, y + 13.0, p.id).unwrap();
+        let mut x = LEFT;
+        for (ticks, (name, color)) in parts(p).into_iter().zip(PARTS) {
+            let width = ticks as f64 * WIDTH / longest as f64;
+            if width > 0.0 {
+                writeln!(
+                    out,
+                    r#"<rect x="{:.1}" y="{}" width="{:.1}" height="{}" fill="{}"><title>P{} {}: {} ticks</title></rect>"#,
+                    x,
+                    y + 2.0,
+                    width,
+                    ROW - 4.0,
+                    color,
+                    p.id,
+                    name,
+                    ticks
+                )
+                .unwrap();
+            }
+            x += width;
+        }
+    }
+    let mut x = LEFT;
+    for (name, color) in PARTS {
+        let y = height - 16.0;
+        writeln!(out, r#"<rect x="{}" y="{}" width="12" height="12" fill="{}"/>"#, x, y, color).unwrap();
+        writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, x + 16.0, y + 11.0, name).unwrap();
+        x += 100.0;
+    }
+    writeln!(out, "</svg>").unwrap();
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
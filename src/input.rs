@@ -0,0 +1,1039 @@
+//! # Input Module
+//!
+//! This module parses the workload files found under `tests/` into
+//! [`JobSpec`] values the simulators can work from.
+//!
+//! Each non-empty line describes one job as whitespace separated fields:
+//!
+//! ```text
+//! <id> <time_inserted> <time_to_run> [priority] [class]
+//! ```
+//!
+//! The priority column is only read by the schedulers that use priorities
+//! (`mlrr`, `simplemlf` and `mlf`); `simple` and `simplerr` ignore it. The
+//! optional class column labels the job, e.g. `interactive` or `batch`, so
+//! results can be broken down by class; it needs the priority column in
+//! front of it. A `nice=<n>` field anywhere after the first three gives the
+//! job a [nice value](crate::nice) other than `0`, and a `timeout=<time>`
+//! field gives up on the job if it has not finished that long after it
+//! arrived (see [Timeouts](crate::input#timeouts)).
+//!
+//! ## Time Units
+//! Times are plain numbers of ticks in the run's [`TimeUnit`] (nanoseconds by
+//! default). A time may also carry its own unit suffix such as `5ms` or
+//! `250us`, which is converted into ticks of the run's unit. A suffixed time
+//! that is not a whole number of ticks is rejected rather than rounded.
+//!
+//! ## Arrival Order
+//! A whole file is read into a [`Workload`]. Its tables are ordered maps, so
+//! iterating over them never depends on a hash seed, and jobs arriving at the
+//! same tick are always handed to the scheduler in input-file order.
+//!
+//! ## Example
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::parse_line;
+//!
+//! let job = parse_line("3 2ms 500us 1", TimeUnit::Us, true).unwrap();
+//! assert_eq!((job.id, job.arrival, job.burst, job.priority), (3, 2_000, 500, 1));
+//! ```
+//!
+//! ## Directives
+//! Large structured workloads can be composed from smaller files with two
+//! directives, each on a line of its own. They are expanded by [`expand`]
+//! before the lines are parsed:
+//! - `%include <file>` inserts the lines of another workload file, which
+//!   may use directives itself. The path is relative to the including file,
+//!   and a file that ends up including itself is an error.
+//! - `%repeat <n> offset=<time> [ids=<step>]` ... `%end` writes the lines
+//!   in between `n` times. Copy `k` (from `0`) arrives `k * offset` later and
+//!   its IDs are `k * step` higher; the step defaults to the span of the
+//!   block's IDs, so the copies count up without gaps. Repeats may be nested.
+//!
+//! ```text
+//! %repeat 100 offset=50
+//! 0 0 20 1
+//! 1 5 2 0
+//! %end
+//! ```
+//! describes 100 copies of a long and a short job, 50 ticks apart, with IDs
+//! `0` to `199`.
+//!
+//! ## Annotations
+//! `%annotate <time> <label>` puts a labeled marker at a tick, e.g.
+//! `%annotate 40 CPU hog arrives`, which the trace prints and the Gantt
+//! charts draw as a vertical line; see [`Annotation`]. Inside a `%repeat`
+//! block every copy gets its own marker, moved like the copy's arrivals.
+//! JSON workloads list them as `"annotations": [ { "time": 40, "label": "CPU
+//! hog arrives" } ]`.
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::event::Annotation;
+//! use scheduler::input::Workload;
+//!
+//! let text = "0 0 5\n%annotate 40 CPU hog arrives\n1 40 100\n";
+//! let workload = Workload::parse(text.lines(), TimeUnit::Ns, false).unwrap();
+//! assert_eq!(workload.annotations, [Annotation { time: 40, label: "CPU hog arrives".to_string() }]);
+//! ```
+//!
+//! ## Renice
+//! `%renice <time> <id> <nice>` changes the nice value of job `id` at a
+//! tick, as `renice` does for a running process, e.g. `%renice 40 3 10`.
+//! The change is made after the tick's arrivals, and a job that has not
+//! arrived yet or has already finished is left alone. Inside a `%repeat`
+//! block every copy renices its own copy of the job. JSON workloads list
+//! them as `"renices": [ { "time": 40, "id": 3, "nice": 10 } ]`.
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::{Renice, Workload};
+//!
+//! let text = "0 0 50 nice=-5\n1 0 50\n%renice 20 1 10\n";
+//! let workload = Workload::parse(text.lines(), TimeUnit::Ns, false).unwrap();
+//! assert_eq!((workload.jobs[&0].nice, workload.jobs[&1].nice), (-5, 0));
+//! assert_eq!(workload.renices, [Renice { time: 20, id: 1, nice: 10 }]);
+//! // the starting nice values come first
+//! assert_eq!(workload.nice_changes(), [Renice { time: 0, id: 0, nice: -5 }, Renice { time: 20, id: 1, nice: 10 }]);
+//! ```
+//!
+//! ## Timeouts
+//! A job with a timeout models a request whose client stops waiting: if the
+//! job has not finished by its arrival plus the timeout, the simulator
+//! cancels it wherever it is, ready, running or blocked, and logs that it
+//! timed out. A job finishing exactly at its deadline has made it. The
+//! metrics then report how many jobs finished within their deadline. JSON
+//! workloads give a job a `"timeout"` and CSV workloads a `timeout` column.
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::Workload;
+//!
+//! let workload = Workload::parse("0 0 50 timeout=2ms\n1 0 50\n".lines(), TimeUnit::Us, false).unwrap();
+//! assert_eq!((workload.jobs[&0].timeout, workload.jobs[&1].timeout), (Some(2_000), None));
+//! ```
+//!
+//! ## JSON Workloads
+//! Input files ending in `.json` describe the workload as a JSON object with
+//! a `jobs` array instead. Times are numbers of ticks or strings with a unit
+//! suffix, and `priority`, `class`, `nice` and `timeout` may be left out:
+//! ```json
+//! { "jobs": [ { "id": 0, "arrival": 0, "burst": "5ms", "priority": 1, "class": "batch", "nice": 5, "timeout": "20ms" } ] }
+//! ```
+//!
+//! ### Burst Scripts
+//! Instead of a single total, `burst` may be the job's exact sequence of CPU
+//! bursts and I/O waits, starting and ending with CPU and alternating between
+//! the two:
+//! ```json
+//! { "id": 2, "arrival": 0, "burst": [ { "cpu": 3 }, { "io": 20 }, { "cpu": 1 } ] }
+//! ```
+//! The job's [`JobSpec::burst`] is then its total CPU time, and the script is
+//! kept in [`Workload::scripts`]. Workloads with scripts run in the
+//! simulator's generic loop, which blocks the job for each I/O wait and
+//! hands it back to the scheduler when the wait is over.
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::{Step, Workload};
+//!
+//! let json = r#"{ "jobs": [
+//!   { "id": 2, "arrival": 0, "burst": [ { "cpu": 3 }, { "io": 20 }, { "cpu": 1 } ] }
+//! ] }"#;
+//! let workload = Workload::from_json(json, TimeUnit::Ns).unwrap();
+//! assert_eq!(workload.jobs[&2].burst, 4);
+//! assert_eq!(workload.scripts[&2], [Step::Cpu(3), Step::Io(20), Step::Cpu(1)]);
+//!
+//! let json = r#"{ "jobs": [ { "id": 2, "arrival": 0, "burst": [ { "cpu": 3 }, { "io": 20 } ] } ] }"#;
+//! let err = Workload::from_json(json, TimeUnit::Ns).unwrap_err();
+//! assert_eq!(err, "job 2: a burst script must alternate cpu and io, starting and ending with cpu");
+//! ```
+//!
+//! ## CSV Workloads
+//! Input files ending in `.csv`, as exported from a spreadsheet, start with a
+//! header row naming the columns, in any order:
+//! ```text
+//! id,arrival,burst,priority,class,nice,timeout
+//! 0,0,5ms,1,batch,5,20ms
+//! ```
+//! `id`, `arrival` and `burst` are required and `priority`, `class`, `nice` and `timeout` may be
+//! left out, or left empty in some rows. Header names are matched ignoring
+//! case and surrounding spaces, and columns the simulator does not know, such
+//! as a `deadline` or a spreadsheet's notes, are ignored. Fields may be quoted
+//! with `"`, as spreadsheets do for fields containing commas.
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::Workload;
+//!
+//! let csv = "Burst,ID,deadline,Arrival,Priority\n5,0,50,0,1\n\"3\",1,,2,\n";
+//! let workload = Workload::from_csv(csv, TimeUnit::Ns).unwrap();
+//! assert_eq!((workload.jobs[&0].burst, workload.jobs[&0].priority), (5, 1));
+//! assert_eq!((workload.jobs[&1].arrival, workload.jobs[&1].priority), (2, 0));
+//!
+//! let err = Workload::from_csv("id,arrival\n0,0\n", TimeUnit::Ns).unwrap_err();
+//! assert_eq!(err, "the header has no 'burst' column");
+//! ```
+//!
+//! ### Open and Closed Loops
+//! By default a workload is **open**: every job arrives once, at its own
+//! arrival time, however busy the system is. Setting `"mode": "closed"`
+//! turns every job into a **client** instead. After each of its jobs
+//! finishes, the client thinks for an exponentially distributed time with
+//! mean `think` and then submits the same job again, until it has made
+//! `rounds` requests. A slow scheduler therefore also slows the arrivals
+//! down, which is how interactive users behave and why schedulers compare
+//! differently under the two models. The think times are drawn from a
+//! [`Rng`](crate::random::Rng) seeded with `seed` (default `0`).
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::{Mode, Workload};
+//!
+//! let json = r#"{
+//!   "mode": "closed", "think": "2us", "rounds": 10, "seed": 7,
+//!   "jobs": [ { "id": 0, "arrival": 0, "burst": 5 }, { "id": 1, "arrival": 0, "burst": 3 } ]
+//! }"#;
+//! let workload = Workload::from_json(json, TimeUnit::Ns).unwrap();
+//! assert_eq!(workload.mode, Mode::Closed { think: 2_000, rounds: 10, seed: 7 });
+//! assert_eq!(workload.arriving_at(0), &[0, 1]);
+//!
+//! let err = Workload::from_json(r#"{ "mode": "closed", "jobs": [] }"#, TimeUnit::Ns).unwrap_err();
+//! assert_eq!(err, "a closed workload needs 'think' and 'rounds'");
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::clock::TimeUnit;
+use crate::event::Annotation;
+use crate::nice;
+
+/// One job read from a workload file, with times already converted to ticks.
+///
+/// # Fields
+/// - `id`: Unique identifier for the process.
+/// - `arrival`: Tick at which the job is handed to the scheduler.
+/// - `burst`: Number of ticks of CPU time the job needs, at least one.
+/// - `priority`: Initial priority level (`0` when the column is not read).
+/// - `class`: The job's class label, if it has one.
+/// - `nice`: The job's starting [nice value](crate::nice), `0` unless given.
+/// - `timeout`: Ticks after its arrival the job is cancelled at unless it
+///   has finished, see [Timeouts](crate::input#timeouts).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobSpec {
+    pub id: u32,
+    pub arrival: u64,
+    pub burst: u32,
+    pub priority: u32,
+    pub class: Option<String>,
+    pub nice: i8,
+    pub timeout: Option<u64>,
+}
+
+/// Parses a single workload line.
+///
+/// # Parameters
+/// - `line`: The raw line from the input file.
+/// - `unit`: The run's time unit; unsuffixed times are taken to be in it.
+/// - `with_priority`: Whether the fourth (priority) column should be read.
+///
+/// # Returns
+/// The parsed [`JobSpec`], or a message naming the field that was missing or
+/// invalid, e.g. `"Invalid time_to_run"`.
+///
+/// # Example
+/// ```
+/// use scheduler::clock::TimeUnit;
+/// use scheduler::input::parse_line;
+///
+/// let job = parse_line("0 0 100", TimeUnit::Ns, false).unwrap();
+/// assert_eq!(job.burst, 100);
+/// assert_eq!(job.priority, 0);
+/// assert_eq!(parse_line("0 0 100", TimeUnit::Ns, true).unwrap_err(), "Missing priority");
+/// assert_eq!(parse_line("0 x 100", TimeUnit::Ns, false).unwrap_err(), "Invalid time_inserted");
+/// // a job needs at least one tick of CPU time
+/// assert_eq!(parse_line("0 0 0", TimeUnit::Ns, false).unwrap_err(), "Invalid time_to_run");
+///
+/// // the class follows the priority, whether or not the priority is read
+/// let job = parse_line("0 0 100 2 batch", TimeUnit::Ns, false).unwrap();
+/// assert_eq!((job.priority, job.class.as_deref()), (0, Some("batch")));
+///
+/// // the nice value may go anywhere after the first three fields
+/// let job = parse_line("0 0 100 nice=-3 2 batch", TimeUnit::Ns, true).unwrap();
+/// assert_eq!((job.priority, job.nice), (2, -3));
+/// assert_eq!(parse_line("0 0 100 nice=40", TimeUnit::Ns, false).unwrap_err(), "Invalid nice: nice must be between -20 and 19, got 40");
+///
+/// // and so may the timeout
+/// let job = parse_line("0 0 100 timeout=5us 2", TimeUnit::Ns, true).unwrap();
+/// assert_eq!((job.priority, job.timeout), (2, Some(5_000)));
+/// assert_eq!(parse_line("0 0 100 timeout=0", TimeUnit::Ns, false).unwrap_err(), "Invalid timeout");
+/// ```
+pub fn parse_line(line: &str, unit: TimeUnit, with_priority: bool) -> Result<JobSpec, String> {
+    //the nice value and the timeout are the only fields with a name, so they can go anywhere after the first three
+    let (mut nice, mut timeout, mut fields) = (None, None, Vec::new());
+    for (n, part) in line.split_whitespace().enumerate() {
+        if let Some(value) = part.strip_prefix("nice=").filter(|_| n >= 3) {
+            if nice.is_some() {
+                return Err("Duplicate nice".to_string());
+            }
+            nice = Some(nice::parse(value).map_err(|err| format!("Invalid nice: {}", err))?);
+        } else if let Some(value) = part.strip_prefix("timeout=").filter(|_| n >= 3) {
+            if timeout.is_some() {
+                return Err("Duplicate timeout".to_string());
+            }
+            timeout = Some(parse_timeout(value, unit)?);
+        } else {
+            fields.push(part);
+        }
+    }
+    let nice = nice.unwrap_or(0);
+    let mut parts = fields.into_iter();
+    let mut field = |name: &str| parts.next().ok_or_else(|| format!("Missing {}", name));
+
+    let id = field("ID")?;
+    let time_inserted = field("time_inserted")?;
+    let time_to_run = field("time_to_run")?;
+    let priority = if with_priority { Some(field("priority")?) } else { None };
+    //the class column comes after the priority column, whether that is read or not
+    let class = parts.nth(usize::from(!with_priority)).map(str::to_string);
+
+    let id = id.parse().map_err(|_| "Invalid ID".to_string())?;
+    let arrival = parse_time(time_inserted, unit).map_err(|_| "Invalid time_inserted".to_string())?;
+    let burst = parse_time(time_to_run, unit)
+        .ok()
+        .and_then(|ticks| u32::try_from(ticks).ok())
+        .filter(|&ticks| ticks > 0)
+        .ok_or_else(|| "Invalid time_to_run".to_string())?;
+    let priority = match priority {
+        Some(p) => p.parse().map_err(|_| "Invalid priority".to_string())?,
+        None => 0,
+    };
+    Ok(JobSpec { id, arrival, burst, priority, class, nice, timeout })
+}
+
+/// Whether every job line of a workload file has the priority column, for
+/// the tools that read files written for any scheduler. Blank lines and
+/// `%` directives have no columns to count.
+///
+/// # Example
+/// ```
+/// use scheduler::input::has_priority_column;
+///
+/// assert!(has_priority_column(&["0 0 100 2", "%annotate 5 hog", "1 3 50 0 batch"]));
+/// assert!(!has_priority_column(&["0 0 100", "1 3 50 nice=2"]));
+/// ```
+pub fn has_priority_column<L: AsRef<str>>(lines: &[L]) -> bool {
+    lines.iter().map(|line| line.as_ref().trim()).filter(|line| !line.is_empty() && !line.starts_with('%')).all(|line| {
+        let named = |part: &str| part.starts_with("nice=") || part.starts_with("timeout=");
+        line.split_whitespace().enumerate().filter(|&(n, part)| n < 3 || !named(part)).count() >= 4
+    })
+}
+
+/// Parses a job's timeout, which must be at least one tick.
+fn parse_timeout(text: &str, unit: TimeUnit) -> Result<u64, String> {
+    parse_time(text, unit).ok().filter(|&ticks| ticks > 0).ok_or_else(|| "Invalid timeout".to_string())
+}
+
+/// Parses an `%annotate <time> <label>` line, see
+/// [Annotations](crate::input#annotations).
+///
+/// # Example
+/// ```
+/// use scheduler::clock::TimeUnit;
+/// use scheduler::input::parse_annotation;
+///
+/// let annotation = parse_annotation("%annotate 2ms  CPU hog arrives", TimeUnit::Us).unwrap();
+/// assert_eq!((annotation.time, annotation.label.as_str()), (2_000, "CPU hog arrives"));
+/// assert_eq!(parse_annotation("%annotate 5", TimeUnit::Ns).unwrap_err(), "Missing label");
+/// ```
+pub fn parse_annotation(line: &str, unit: TimeUnit) -> Result<Annotation, String> {
+    let rest = line.trim().strip_prefix("%annotate").ok_or_else(|| "Not an %annotate line".to_string())?;
+    let (time, label) = rest.trim_start().split_once(char::is_whitespace).unwrap_or((rest.trim(), ""));
+    if time.is_empty() {
+        return Err("Missing time".to_string());
+    }
+    let time = parse_time(time, unit).map_err(|_| "Invalid time".to_string())?;
+    match label.trim() {
+        "" => Err("Missing label".to_string()),
+        label => Ok(Annotation { time, label: label.to_string() }),
+    }
+}
+
+/// Parses a `%renice <time> <id> <nice>` line, see
+/// [Renice](crate::input#renice).
+///
+/// # Example
+/// ```
+/// use scheduler::clock::TimeUnit;
+/// use scheduler::input::{Renice, parse_renice};
+///
+/// assert_eq!(parse_renice("%renice 2ms 3 -5", TimeUnit::Us), Ok(Renice { time: 2_000, id: 3, nice: -5 }));
+/// assert_eq!(parse_renice("%renice 5 3", TimeUnit::Ns).unwrap_err(), "Missing nice");
+/// ```
+pub fn parse_renice(line: &str, unit: TimeUnit) -> Result<Renice, String> {
+    let rest = line.trim().strip_prefix("%renice").ok_or_else(|| "Not a %renice line".to_string())?;
+    let mut parts = rest.split_whitespace();
+    let mut field = |name: &str| parts.next().ok_or_else(|| format!("Missing {}", name));
+    let (time, id, value) = (field("time")?, field("ID")?, field("nice")?);
+    if parts.next().is_some() {
+        return Err("Too many fields".to_string());
+    }
+    Ok(Renice {
+        time: parse_time(time, unit).map_err(|_| "Invalid time".to_string())?,
+        id: id.parse().map_err(|_| "Invalid ID".to_string())?,
+        nice: nice::parse(value).map_err(|err| format!("Invalid nice: {}", err))?,
+    })
+}
+
+/// Parses a time field into a number of ticks of `unit`.
+///
+/// A bare number is already a tick count. A number followed by `ns`, `us` or
+/// `ms` is converted, and must come out to a whole number of ticks.
+///
+/// # Example
+/// ```
+/// use scheduler::clock::TimeUnit;
+/// use scheduler::input::parse_time;
+///
+/// assert_eq!(parse_time("7", TimeUnit::Ms), Ok(7));
+/// assert_eq!(parse_time("3ms", TimeUnit::Us), Ok(3_000));
+/// assert!(parse_time("1500ns", TimeUnit::Us).is_err());
+/// ```
+pub fn parse_time(field: &str, unit: TimeUnit) -> Result<u64, String> {
+    let digits = field.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &field[digits.len()..];
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| format!("'{}' is not a time", field))?;
+    if suffix.is_empty() {
+        return Ok(value);
+    }
+    let given: TimeUnit = suffix.parse()?;
+    let ns = value
+        .checked_mul(given.nanos())
+        .ok_or_else(|| format!("'{}' is too large", field))?;
+    if ns % unit.nanos() != 0 {
+        return Err(format!("'{}' is not a whole number of {}", field, unit));
+    }
+    Ok(ns / unit.nanos())
+}
+
+/// How the jobs of a workload arrive, see [Open and Closed
+/// Loops](crate::input#open-and-closed-loops).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Every job arrives once, at its arrival time.
+    #[default]
+    Open,
+    /// Every job is a client that resubmits it `rounds` times in all, after
+    /// thinking for a random time with mean `think` ticks.
+    Closed { think: u64, rounds: u32, seed: u64 },
+}
+
+/// One step of a job's burst script, in ticks. Both kinds of step are at
+/// least one tick long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Run on the CPU for this long.
+    Cpu(u32),
+    /// Wait for I/O for this long, off the CPU.
+    Io(u64),
+}
+
+/// A parsed workload file.
+///
+/// # Fields
+/// - `jobs`: Every job, keyed (and so iterated) by ID.
+/// - `arrivals`: Job IDs keyed by arrival tick, each list in input-file order.
+/// - `mode`: Whether the jobs are one-off arrivals or closed-loop clients.
+/// - `scripts`: The burst script of every job that has one, keyed by ID; see
+///   [Burst Scripts](crate::input#burst-scripts).
+/// - `annotations`: The markers of the file, in file order; see
+///   [Annotations](crate::input#annotations).
+/// - `renices`: The nice value changes of the file, in file order; see
+///   [Renice](crate::input#renice).
+///
+/// # Example
+/// The same file always produces the same run, however often it is read and
+/// simulated:
+/// ```
+/// use std::time::Duration;
+/// use scheduler::CLOCK;
+/// use scheduler::clock::TimeUnit;
+/// use scheduler::event::{Event, EventKind};
+/// use scheduler::input::Workload;
+/// use scheduler::policy::FCFS;
+/// use scheduler::simulation::{Job, Simulation};
+/// use scheduler::sink::Recorder;
+///
+/// // jobs arriving at the same tick, listed out of ID order
+/// let text = "7 0 5\n3 0 5\n9 2 1\n\n1 0 5\n";
+/// CLOCK.set_tick(Duration::from_nanos(1));
+/// let run = || -> Vec<Event> {
+///     let workload = Workload::parse(text.lines(), TimeUnit::Ns, false).unwrap();
+///     assert_eq!(workload.arriving_at(0), &[7, 3, 1]);
+///     let jobs = workload.arrivals.values().flatten().map(|id| &workload.jobs[id]);
+///     let mut sim = Simulation::builder(FCFS::new()).jobs(jobs.map(|job| Job::new(job.id, job.arrival, job.burst))).build();
+///     let mut out = Recorder::new();
+///     sim.run(&mut out).unwrap();
+///     out.events().to_vec()
+/// };
+/// let first = run();
+/// // first come, first served, in the order of the file and not by ID
+/// let dispatched = first.iter().filter(|event| matches!(event.kind, EventKind::Dispatch { .. })).map(|event| event.id);
+/// assert!(dispatched.eq([7, 3, 1, 9]));
+/// for _ in 0..100 {
+///     assert_eq!(run(), first);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Workload {
+    pub jobs: BTreeMap<u32, JobSpec>,
+    pub arrivals: BTreeMap<u64, Vec<u32>>,
+    pub mode: Mode,
+    pub scripts: BTreeMap<u32, Vec<Step>>,
+    pub annotations: Vec<Annotation>,
+    pub renices: Vec<Renice>,
+}
+
+/// A change of a job's nice value, see [Renice](crate::input#renice).
+///
+/// # Fields
+/// - `time`: Tick of the change.
+/// - `id`: The job whose nice value changes.
+/// - `nice`: Its new nice value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Renice {
+    pub time: u64,
+    pub id: u32,
+    pub nice: i8,
+}
+
+/// A time in a JSON workload: ticks, or a string such as `"5ms"`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTime {
+    Ticks(u64),
+    Text(String),
+}
+
+impl JsonTime {
+    fn ticks(&self, unit: TimeUnit) -> Result<u64, String> {
+        match self {
+            JsonTime::Ticks(ticks) => Ok(*ticks),
+            JsonTime::Text(text) => parse_time(text, unit),
+        }
+    }
+}
+
+const SCRIPT_ORDER: &str = "a burst script must alternate cpu and io, starting and ending with cpu";
+
+#[derive(Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum JsonMode {
+    #[default]
+    Open,
+    Closed,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "lowercase")]
+enum JsonStep {
+    Cpu(JsonTime),
+    Io(JsonTime),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonBurst {
+    Total(JsonTime),
+    Script(Vec<JsonStep>),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonJob {
+    id: u32,
+    arrival: JsonTime,
+    burst: JsonBurst,
+    #[serde(default)]
+    priority: u32,
+    #[serde(default)]
+    class: Option<String>,
+    #[serde(default)]
+    nice: i32,
+    #[serde(default)]
+    timeout: Option<JsonTime>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonWorkload {
+    #[serde(default)]
+    mode: JsonMode,
+    think: Option<JsonTime>,
+    rounds: Option<u32>,
+    #[serde(default)]
+    seed: u64,
+    jobs: Vec<JsonJob>,
+    #[serde(default)]
+    annotations: Vec<JsonAnnotation>,
+    #[serde(default)]
+    renices: Vec<JsonRenice>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonAnnotation {
+    time: JsonTime,
+    label: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonRenice {
+    time: JsonTime,
+    id: u32,
+    nice: i32,
+}
+
+impl Workload {
+    /// Parses every line of a workload file, skipping blank lines.
+    ///
+    /// # Returns
+    /// The workload, or the first error from [`parse_line`] together with the
+    /// offending line, e.g. `"Invalid ID on line: x 0 5"`. A job with the ID
+    /// of an earlier one is an error too.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::clock::TimeUnit;
+    /// use scheduler::input::Workload;
+    ///
+    /// let err = Workload::parse(["0 0 5", "1 3 0"], TimeUnit::Ns, false).unwrap_err();
+    /// assert_eq!(err, "Invalid time_to_run on line: 1 3 0");
+    /// let err = Workload::parse(["1 0 5 0", "1 2 3 0", "2 1 4 0"], TimeUnit::Ns, true).unwrap_err();
+    /// assert_eq!(err, "Duplicate ID on line: 1 2 3 0");
+    /// ```
+    pub fn parse<I, L>(lines: I, unit: TimeUnit, with_priority: bool) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = L>,
+        L: AsRef<str>,
+    {
+        let mut workload = Workload::default();
+        for line in lines {
+            let line = line.as_ref();
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.trim_start().starts_with("%annotate") {
+                let annotation = parse_annotation(line, unit).map_err(|err| format!("{} on line: {}", err, line))?;
+                workload.annotations.push(annotation);
+                continue;
+            }
+            if line.trim_start().starts_with("%renice") {
+                let renice = parse_renice(line, unit).map_err(|err| format!("{} on line: {}", err, line))?;
+                workload.renices.push(renice);
+                continue;
+            }
+            let job = parse_line(line, unit, with_priority).map_err(|err| format!("{} on line: {}", err, line))?;
+            if workload.jobs.contains_key(&job.id) {
+                return Err(format!("Duplicate ID on line: {}", line));
+            }
+            workload.arrivals.entry(job.arrival).or_default().push(job.id);
+            workload.jobs.insert(job.id, job);
+        }
+        Ok(workload)
+    }
+
+    /// Parses a JSON workload, see [JSON Workloads](crate::input#json-workloads).
+    ///
+    /// # Returns
+    /// The workload, or a message describing the first problem found, e.g.
+    /// `"job 3: Invalid burst time"`.
+    pub fn from_json(text: &str, unit: TimeUnit) -> Result<Self, String> {
+        let file: JsonWorkload = serde_json::from_str(text).map_err(|err| err.to_string())?;
+        let mut workload = Workload::default();
+        if file.mode == JsonMode::Closed {
+            let (Some(think), Some(rounds)) = (&file.think, file.rounds) else {
+                return Err("a closed workload needs 'think' and 'rounds'".to_string());
+            };
+            if rounds == 0 {
+                return Err("rounds: must be positive".to_string());
+            }
+            workload.mode = Mode::Closed { think: think.ticks(unit)?, rounds, seed: file.seed };
+        } else if file.think.is_some() || file.rounds.is_some() {
+            return Err("'think' and 'rounds' only apply to closed workloads".to_string());
+        }
+        for job in &file.jobs {
+            let error = |err: String| format!("job {}: {}", job.id, err);
+            if workload.jobs.contains_key(&job.id) {
+                return Err(error("Duplicate ID".to_string()));
+            }
+            let arrival = job.arrival.ticks(unit).map_err(error)?;
+            let cpu = |time: &JsonTime| {
+                time.ticks(unit)
+                    .ok()
+                    .and_then(|ticks| u32::try_from(ticks).ok())
+                    .filter(|&ticks| ticks > 0)
+                    .ok_or_else(|| error("Invalid burst time".to_string()))
+            };
+            let burst = match &job.burst {
+                JsonBurst::Total(time) => cpu(time)?,
+                JsonBurst::Script(steps) => {
+                    let mut script = Vec::with_capacity(steps.len());
+                    for (n, step) in steps.iter().enumerate() {
+                        script.push(match (step, n % 2) {
+                            (JsonStep::Cpu(time), 0) => Step::Cpu(cpu(time)?),
+                            (JsonStep::Io(time), 1) => match time.ticks(unit).map_err(error)? {
+                                0 => return Err(error("Invalid io time".to_string())),
+                                ticks => Step::Io(ticks),
+                            },
+                            _ => return Err(error(SCRIPT_ORDER.to_string())),
+                        });
+                    }
+                    if script.len() % 2 == 0 {
+                        return Err(error(SCRIPT_ORDER.to_string()));
+                    }
+                    let total = script.iter().map(|step| match step {
+                        Step::Cpu(ticks) => *ticks as u64,
+                        Step::Io(_) => 0,
+                    });
+                    let total = u32::try_from(total.sum::<u64>()).map_err(|_| error("Invalid burst time".to_string()))?;
+                    workload.scripts.insert(job.id, script);
+                    total
+                }
+            };
+            let nice = nice::parse(&job.nice.to_string()).map_err(error)?;
+            let timeout = match &job.timeout {
+                Some(JsonTime::Ticks(0)) => return Err(error("Invalid timeout".to_string())),
+                Some(JsonTime::Ticks(ticks)) => Some(*ticks),
+                Some(JsonTime::Text(text)) => Some(parse_timeout(text, unit).map_err(error)?),
+                None => None,
+            };
+            let spec = JobSpec { id: job.id, arrival, burst, priority: job.priority, class: job.class.clone(), nice, timeout };
+            workload.jobs.insert(job.id, spec);
+            workload.arrivals.entry(arrival).or_default().push(job.id);
+        }
+        for annotation in file.annotations {
+            let time = annotation.time.ticks(unit).map_err(|err| format!("annotation '{}': {}", annotation.label, err))?;
+            workload.annotations.push(Annotation { time, label: annotation.label });
+        }
+        for renice in file.renices {
+            let error = |err: String| format!("renice of job {}: {}", renice.id, err);
+            let time = renice.time.ticks(unit).map_err(error)?;
+            let nice = nice::parse(&renice.nice.to_string()).map_err(error)?;
+            workload.renices.push(Renice { time, id: renice.id, nice });
+        }
+        Ok(workload)
+    }
+
+    /// Parses a CSV workload, see [CSV Workloads](crate::input#csv-workloads).
+    ///
+    /// # Returns
+    /// The workload, or a message describing the first problem found, e.g.
+    /// `"row 3: Invalid burst time"`, counting the header as row 1.
+    pub fn from_csv(text: &str, unit: TimeUnit) -> Result<Self, String> {
+        let mut rows = text.trim_start_matches('\u{feff}').lines().enumerate();
+        let header = loop {
+            match rows.next() {
+                Some((_, line)) if line.trim().is_empty() => continue,
+                Some((_, line)) => break csv_fields(line)?,
+                None => return Err("the file has no header row".to_string()),
+            }
+        };
+        let mut columns: BTreeMap<String, usize> = BTreeMap::new();
+        for (index, name) in header.iter().enumerate() {
+            let name = name.trim().to_ascii_lowercase();
+            if !name.is_empty() && columns.insert(name.clone(), index).is_some() {
+                return Err(format!("the header has two '{}' columns", name));
+            }
+        }
+        let column = |name: &str| columns.get(name).copied().ok_or_else(|| format!("the header has no '{}' column", name));
+        let (id, arrival, burst) = (column("id")?, column("arrival")?, column("burst")?);
+        let (priority, class, nice) =
+            (columns.get("priority").copied(), columns.get("class").copied(), columns.get("nice").copied());
+        let timeout = columns.get("timeout").copied();
+
+        let mut workload = Workload::default();
+        for (n, line) in rows {
+            let error = |err: &str| format!("row {}: {}", n + 1, err);
+            let fields = csv_fields(line).map_err(|err| error(&err))?;
+            if fields.iter().all(|field| field.trim().is_empty()) {
+                continue;
+            }
+            let field = |index: Option<usize>| index.and_then(|index| fields.get(index)).map_or("", |field| field.trim());
+            let job_id: u32 = field(Some(id)).parse().map_err(|_| error("Invalid ID"))?;
+            if workload.jobs.contains_key(&job_id) {
+                return Err(error("Duplicate ID"));
+            }
+            let job_arrival = parse_time(field(Some(arrival)), unit).map_err(|_| error("Invalid arrival time"))?;
+            let job_burst = parse_time(field(Some(burst)), unit)
+                .ok()
+                .and_then(|ticks| u32::try_from(ticks).ok())
+                .filter(|&ticks| ticks > 0)
+                .ok_or_else(|| error("Invalid burst time"))?;
+            let job_priority = match field(priority) {
+                "" => 0,
+                p => p.parse().map_err(|_| error("Invalid priority"))?,
+            };
+            let job_class = Some(field(class)).filter(|class| !class.is_empty()).map(str::to_string);
+            let job_nice = match field(nice) {
+                "" => 0,
+                n => nice::parse(n).map_err(|err| error(&format!("Invalid nice: {}", err)))?,
+            };
+            let job_timeout = match field(timeout) {
+                "" => None,
+                t => Some(parse_timeout(t, unit).map_err(|err| error(&err))?),
+            };
+            let job = JobSpec {
+                id: job_id,
+                arrival: job_arrival,
+                burst: job_burst,
+                priority: job_priority,
+                class: job_class,
+                nice: job_nice,
+                timeout: job_timeout,
+            };
+            workload.arrivals.entry(job.arrival).or_default().push(job.id);
+            workload.jobs.insert(job.id, job);
+        }
+        Ok(workload)
+    }
+
+    /// The IDs of the jobs arriving at `tick`, in input-file order.
+    pub fn arriving_at(&self, tick: u64) -> &[u32] {
+        self.arrivals.get(&tick).map_or(&[], Vec::as_slice)
+    }
+
+    /// Every change of a nice value the run makes: the starting nice value
+    /// of every job that has one other than `0`, as a renice at its arrival,
+    /// and the [renices](Self::renices), in time order. Changes at the same
+    /// tick keep this order, so a job can be reniced as it arrives.
+    pub fn nice_changes(&self) -> Vec<Renice> {
+        let starting = self.arrivals.values().flatten().map(|id| &self.jobs[id]).filter(|job| job.nice != 0);
+        let mut changes: Vec<Renice> =
+            starting.map(|job| Renice { time: job.arrival, id: job.id, nice: job.nice }).chain(self.renices.iter().copied()).collect();
+        changes.sort_by_key(|renice| renice.time);
+        changes
+    }
+}
+
+/// Splits one CSV row into its fields. A field in double quotes may contain
+/// commas, and `""` inside it stands for one `"`.
+fn csv_fields(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Expands the [directives](crate::input#directives) in the lines of the
+/// workload file at `path`, returning plain workload lines.
+///
+/// Included files are read with `read`, so the expansion can be tried
+/// without touching the file system. Times written by `%repeat` are plain
+/// tick counts of `unit`.
+///
+/// # Returns
+/// The expanded lines, or the first error prefixed with the file and line it
+/// is on, e.g. `"main.in:3: %repeat without %end"`.
+///
+/// # Example
+/// ```
+/// use std::io;
+/// use std::path::Path;
+/// use scheduler::clock::TimeUnit;
+/// use scheduler::input::expand;
+///
+/// let files = |path: &Path| match path.to_str() {
+///     Some("w/pattern.in") => Ok("0 0 20\n1 5 2\n".to_string()),
+///     Some("w/loop.in") => Ok("%include main.in\n".to_string()),
+///     _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+/// };
+/// let main = ["%repeat 3 offset=50", "%include pattern.in", "%end", "9 0 1"];
+/// let lines = expand(&main, Path::new("w/main.in"), TimeUnit::Ns, files).unwrap();
+/// assert_eq!(lines, ["0 0 20", "1 5 2", "2 50 20", "3 55 2", "4 100 20", "5 105 2", "9 0 1"]);
+///
+/// let err = expand(&["%include loop.in"], Path::new("w/main.in"), TimeUnit::Ns, files).unwrap_err();
+/// assert_eq!(err, "w/loop.in:1: include cycle: w/main.in -> w/loop.in -> w/main.in");
+/// ```
+pub fn expand<L, F>(lines: &[L], path: &Path, unit: TimeUnit, mut read: F) -> Result<Vec<String>, String>
+where
+    L: AsRef<str>,
+    F: FnMut(&Path) -> io::Result<String>,
+{
+    let path = normalize(path);
+    let mut stack = vec![path.clone()];
+    expand_lines(lines, &path, unit, &mut read, &mut stack)
+}
+
+/// A `%repeat` block being read.
+struct Repeat {
+    count: u64,
+    offset: u64,
+    ids: Option<u32>,
+    lines: Vec<String>,
+    line: usize,
+}
+
+fn expand_lines<L, F>(
+    lines: &[L],
+    path: &Path,
+    unit: TimeUnit,
+    read: &mut F,
+    stack: &mut Vec<PathBuf>,
+) -> Result<Vec<String>, String>
+where
+    L: AsRef<str>,
+    F: FnMut(&Path) -> io::Result<String>,
+{
+    let mut out = Vec::new();
+    let mut blocks: Vec<Repeat> = Vec::new();
+    for (n, line) in lines.iter().enumerate() {
+        let error = |err: String| format!("{}:{}: {}", path.display(), n + 1, err);
+        let line = line.as_ref();
+        let mut words = line.split_whitespace();
+        let lines = match words.next() {
+            Some("%include") => {
+                let name = words.collect::<Vec<_>>().join(" ");
+                if name.is_empty() {
+                    return Err(error("%include needs a file".to_string()));
+                }
+                let included = normalize(&path.parent().unwrap_or(Path::new("")).join(name));
+                if stack.contains(&included) {
+                    let cycle: Vec<String> = stack.iter().chain([&included]).map(|p| p.display().to_string()).collect();
+                    return Err(error(format!("include cycle: {}", cycle.join(" -> "))));
+                }
+                let text = read(&included).map_err(|err| error(format!("cannot include '{}': {}", included.display(), err)))?;
+                let text: Vec<&str> = text.lines().collect();
+                stack.push(included.clone());
+                let lines = expand_lines(&text, &included, unit, read, stack)?;
+                stack.pop();
+                lines
+            }
+            Some("%repeat") => {
+                let count = words.next().and_then(|count| count.parse().ok());
+                let Some(count) = count else {
+                    return Err(error("%repeat needs a number of copies".to_string()));
+                };
+                let (mut offset, mut ids) = (None, None);
+                for word in words {
+                    match word.split_once('=') {
+                        Some(("offset", time)) => offset = Some(parse_time(time, unit).map_err(error)?),
+                        Some(("ids", step)) => {
+                            ids = Some(step.parse().map_err(|_| error(format!("'{}' is not an ID step", step)))?)
+                        }
+                        _ => return Err(error(format!("unknown %repeat option '{}'", word))),
+                    }
+                }
+                let Some(offset) = offset else {
+                    return Err(error("%repeat needs offset=<time>".to_string()));
+                };
+                blocks.push(Repeat { count, offset, ids, lines: Vec::new(), line: n });
+                continue;
+            }
+            Some("%end") => {
+                let Some(block) = blocks.pop() else {
+                    return Err(error("%end without %repeat".to_string()));
+                };
+                repeat(&block, unit).map_err(error)?
+            }
+            //annotations and renices are read with the jobs
+            Some("%annotate" | "%renice") => vec![line.to_string()],
+            Some(directive) if directive.starts_with('%') => {
+                return Err(error(format!("unknown directive '{}'", directive)));
+            }
+            _ => vec![line.to_string()],
+        };
+        match blocks.last_mut() {
+            Some(block) => block.lines.extend(lines),
+            None => out.extend(lines),
+        }
+    }
+    match blocks.last() {
+        Some(block) => Err(format!("{}:{}: %repeat without %end", path.display(), block.line + 1)),
+        None => Ok(out),
+    }
+}
+
+/// Writes the copies of a `%repeat` block.
+fn repeat(block: &Repeat, unit: TimeUnit) -> Result<Vec<String>, String> {
+    //the lines' directives (empty for jobs), IDs (none for annotations), times and the rest of the line
+    let mut jobs = Vec::new();
+    for line in block.lines.iter().filter(|line| !line.trim().is_empty()) {
+        if line.trim_start().starts_with("%annotate") {
+            let annotation = parse_annotation(line, unit).map_err(|err| format!("{} on line: {}", err, line))?;
+            jobs.push(("%annotate", None, annotation.time, annotation.label));
+            continue;
+        }
+        if line.trim_start().starts_with("%renice") {
+            let renice = parse_renice(line, unit).map_err(|err| format!("{} on line: {}", err, line))?;
+            jobs.push(("%renice", Some(renice.id), renice.time, renice.nice.to_string()));
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let id: u32 = fields.next().and_then(|id| id.parse().ok()).ok_or_else(|| format!("Invalid ID on line: {}", line))?;
+        let arrival = fields
+            .next()
+            .and_then(|time| parse_time(time, unit).ok())
+            .ok_or_else(|| format!("Invalid time_inserted on line: {}", line))?;
+        jobs.push(("", Some(id), arrival, fields.collect::<Vec<_>>().join(" ")));
+    }
+    let ids = jobs.iter().filter(|(directive, ..)| directive.is_empty()).filter_map(|(_, id, ..)| *id);
+    let (low, high) = ids.fold((u32::MAX, 0), |(low, high), id| (low.min(id), high.max(id)));
+    let step = block.ids.unwrap_or_else(|| high.saturating_sub(low).saturating_add(1));
+    let mut out = Vec::new();
+    for k in 0..block.count {
+        for (directive, id, arrival, rest) in &jobs {
+            let arrival = k
+                .checked_mul(block.offset)
+                .and_then(|offset| offset.checked_add(*arrival))
+                .ok_or_else(|| "%repeat: arrival out of range".to_string())?;
+            let Some(id) = id else {
+                out.push(format!("{} {} {}", directive, arrival, rest));
+                continue;
+            };
+            let id = u32::try_from(k * step as u64 + *id as u64).map_err(|_| "%repeat: IDs out of range".to_string())?;
+            out.push(match directive.is_empty() {
+                true => format!("{} {} {}", id, arrival, rest),
+                false => format!("{} {} {} {}", directive, arrival, id, rest),
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Resolves `.` and `..` in a path without touching the file system, so that
+/// the same file reached along two routes is recognized in a cycle.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(normal.components().next_back(), Some(Component::Normal(_))) => {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
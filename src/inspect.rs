@@ -0,0 +1,201 @@
+//! # Inspect Module
+//!
+//! What a workload asks of the CPU, for the simulator's `inspect`
+//! subcommand, so that a workload can be sanity-checked before the
+//! scheduler is blamed for odd results.
+//!
+//! An [`Inspection`] summarizes a workload without running it:
+//! - the arrival rate over time, as the number of jobs arriving in each of
+//!   a number of equally wide bins from the first arrival to the last;
+//! - a histogram of the CPU bursts, in bins that double in width (`1`,
+//!   `2-3`, `4-7`, ...) so that both the many short and the few long jobs
+//!   of a heavy-tailed workload show up;
+//! - how many jobs start at each priority;
+//! - the total offered load: the CPU time of every job over the time its
+//!   arrivals are spread across. Above `1` the CPU cannot keep up while jobs
+//!   are arriving, whatever the scheduler, and the queue grows until the
+//!   arrivals stop.
+//!
+//! A closed-loop workload resubmits its jobs after they finish, so its
+//! arrivals and load depend on the scheduler; only the first round is
+//! summarized.
+//!
+//! ## Example
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::Workload;
+//! use scheduler::inspect::Inspection;
+//!
+//! let text = "1 0 1 0\n2 0 3 0\n3 5 2 1\n4 10 20 2\n5 20 4 0\n";
+//! let workload = Workload::parse(text.lines(), TimeUnit::Ns, true).unwrap();
+//! let inspection = Inspection::new(&workload, 2);
+//! assert_eq!(inspection.cpu, 30);
+//! assert_eq!(inspection.span(), 20);
+//! assert_eq!(inspection.offered_load(), Some(1.5));
+//! // arrivals at ticks 0-10 and 11-20
+//! assert_eq!(inspection.arrivals.iter().map(|bin| bin.jobs).collect::<Vec<_>>(), [4, 1]);
+//! // bursts 1, 2-3, 4-7, 8-15, 16-31
+//! assert_eq!(inspection.bursts.iter().map(|bin| bin.jobs).collect::<Vec<_>>(), [1, 2, 1, 0, 1]);
+//! assert!(inspection.priorities.iter().eq([(&0, &3), (&1, &1), (&2, &1)]));
+//! assert!(inspection.report(TimeUnit::Ns).contains("offered load: 1.50 (5 jobs in 20ns, mean burst 6ns), more than"));
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::clock::TimeUnit;
+use crate::input::{Mode, Step, Workload};
+
+/// The jobs that fell into one bin of a histogram.
+///
+/// # Fields
+/// - `from`: First tick (or burst length) of the bin.
+/// - `to`: Last tick (or burst length) of the bin, inclusive.
+/// - `jobs`: Number of jobs in the bin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bin {
+    pub from: u64,
+    pub to: u64,
+    pub jobs: u64,
+}
+
+/// A summary of a workload, see the [module documentation](self).
+///
+/// # Fields
+/// - `jobs`: Number of jobs.
+/// - `first`: Tick of the first arrival, `0` without jobs.
+/// - `last`: Tick of the last arrival, `0` without jobs.
+/// - `cpu`: Total CPU time of every job, in ticks.
+/// - `io`: Total I/O time of the burst scripts, in ticks.
+/// - `arrivals`: Jobs arriving in each bin of ticks, first to last arrival.
+/// - `bursts`: Jobs by CPU burst, in bins of doubling width from the
+///   shortest burst to the longest.
+/// - `priorities`: Number of jobs starting at each priority.
+/// - `closed`: Whether the jobs are closed-loop clients, whose later
+///   rounds are not included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inspection {
+    pub jobs: u64,
+    pub first: u64,
+    pub last: u64,
+    pub cpu: u64,
+    pub io: u64,
+    pub arrivals: Vec<Bin>,
+    pub bursts: Vec<Bin>,
+    pub priorities: BTreeMap<u32, u64>,
+    pub closed: bool,
+}
+
+impl Inspection {
+    /// Length of the longest bar in [`report`](Self::report).
+    const BAR: u64 = 40;
+
+    /// Summarizes `workload`, with its arrivals in `bins` equally wide bins
+    /// (at least one, and at most one per tick).
+    pub fn new(workload: &Workload, bins: usize) -> Self {
+        let first = workload.arrivals.keys().next().copied().unwrap_or(0);
+        let last = workload.arrivals.keys().next_back().copied().unwrap_or(0);
+        let ticks = last - first + 1;
+        let width = ticks.div_ceil((bins.max(1) as u64).min(ticks));
+        let mut arrivals: Vec<Bin> = (first..=last)
+            .step_by(width as usize)
+            .map(|from| Bin { from, to: (from + width - 1).min(last), jobs: 0 })
+            .collect();
+        for (tick, ids) in &workload.arrivals {
+            arrivals[((tick - first) / width) as usize].jobs += ids.len() as u64;
+        }
+
+        let shortest = workload.jobs.values().map(|job| job.burst.max(1)).min().unwrap_or(1);
+        let longest = workload.jobs.values().map(|job| job.burst as u64).max().unwrap_or(0);
+        let mut bursts: Vec<Bin> = std::iter::successors(Some(1u64 << shortest.ilog2()), |from| Some(from * 2))
+            .take_while(|&from| from <= longest)
+            .map(|from| Bin { from, to: from * 2 - 1, jobs: 0 })
+            .collect();
+        let mut priorities = BTreeMap::new();
+        for job in workload.jobs.values() {
+            if job.burst > 0 {
+                bursts[(job.burst.ilog2() - shortest.ilog2()) as usize].jobs += 1;
+            }
+            *priorities.entry(job.priority).or_default() += 1;
+        }
+
+        let io = workload.scripts.values().flatten().map(|step| if let Step::Io(ticks) = step { *ticks } else { 0 }).sum();
+        Self {
+            jobs: workload.jobs.len() as u64,
+            first,
+            last,
+            cpu: workload.jobs.values().map(|job| job.burst as u64).sum(),
+            io,
+            arrivals,
+            bursts,
+            priorities,
+            closed: matches!(workload.mode, Mode::Closed { .. }),
+        }
+    }
+
+    /// Ticks from the first arrival to the last.
+    pub fn span(&self) -> u64 {
+        self.last - self.first
+    }
+
+    /// The CPU time of every job over the time the arrivals are spread
+    /// across, `None` if they all arrive at the same tick.
+    pub fn offered_load(&self) -> Option<f64> {
+        (self.span() > 0).then(|| self.cpu as f64 / self.span() as f64)
+    }
+
+    /// Renders the summary, with bars for the histograms and times in
+    /// `unit`.
+    pub fn report(&self, unit: TimeUnit) -> String {
+        let time = |ticks: u64| unit.format_ns(ticks * unit.nanos());
+        let mut out = format!(
+            "{} jobs arriving from {} to {}, {} of CPU time and {} of I/O\n",
+            self.jobs,
+            time(self.first),
+            time(self.last),
+            time(self.cpu),
+            time(self.io)
+        );
+        if self.closed {
+            out += "closed-loop clients: only their first round is included\n";
+        }
+        let range = |bin: &Bin| match bin.from == bin.to {
+            true => time(bin.from),
+            false => format!("{}-{}", time(bin.from), time(bin.to)),
+        };
+        let rows = |bins: &[Bin], label: &dyn Fn(&Bin) -> String| {
+            let most = bins.iter().map(|bin| bin.jobs).max().unwrap_or(0).max(1);
+            let labels: Vec<String> = bins.iter().map(label).collect();
+            let width = labels.iter().map(String::len).max().unwrap_or(0);
+            let mut out = String::new();
+            for (bin, label) in bins.iter().zip(labels) {
+                let bar = "#".repeat((bin.jobs * Self::BAR).div_ceil(most) as usize);
+                out += format!("  {:>width$} {:>6} {}", label, bin.jobs, bar, width = width).trim_end();
+                out.push('\n');
+            }
+            out
+        };
+        if self.jobs == 0 {
+            return out;
+        }
+        out += "arrivals over time:\n";
+        out += &rows(&self.arrivals, &range);
+        out += "CPU bursts:\n";
+        out += &rows(&self.bursts, &range);
+        out += "priorities:\n";
+        let priorities: Vec<Bin> =
+            self.priorities.iter().map(|(&priority, &jobs)| Bin { from: priority as u64, to: priority as u64, jobs }).collect();
+        out += &rows(&priorities, &|bin| bin.from.to_string());
+        out += &match self.offered_load() {
+            Some(load) => format!(
+                "offered load: {:.2} ({} jobs in {}, mean burst {}){}\n",
+                load,
+                self.jobs,
+                time(self.span()),
+                unit.format_ns((self.cpu as f64 / self.jobs as f64 * unit.nanos() as f64).round() as u64),
+                if load > 1.0 { ", more than the CPU can keep up with" } else { "" }
+            ),
+            None => format!("offered load: every job arrives at {}, {} of work at once\n", time(self.first), time(self.cpu)),
+        };
+        out
+    }
+}
@@ -19,6 +19,72 @@
 //! - [`Schedule`]: A **trait** that defines the standard interface all schedulers
 //!   must follow (adding, selecting, and checking for processes).
 //! - [`Clock`]: A helper for tracking simulated time within your scheduler.
+//! - [`input`]: Parses workload files, including times written with units
+//!   and JSON workloads with closed-loop clients.
+//! - [`workloads`]: Built-in example workloads such as a convoy.
+//! - [`generate`] and [`distribution`]: Random workloads, e.g. with Poisson
+//!   arrivals and heavy-tailed bursts.
+//! - [`adversary`]: Workloads that are pathological for a chosen policy,
+//!   such as a convoy for FCFS.
+//! - [`inspect`]: Summarizes a workload's arrivals, bursts, priorities and
+//!   offered load before it is run.
+//! - [`nice`]: Nice values and the CPU weights Linux gives them.
+//! - [`config`]: The tunable parameters of each scheduler.
+//! - [`event`], [`sink`], [`metrics`] and [`results`]: Record what happens
+//!   during a simulation and turn it into output and scheduling metrics.
+//! - [`analysis`]: Explains the metrics, e.g. by detecting convoys.
+//! - [`heatmap`]: Shows which jobs waited, by arrival time and burst length.
+//! - [`ps`]: The processor-sharing baseline runs are compared against.
+//! - [`diff`] and [`gantt`]: Compare two runs, event by event or as Gantt
+//!   charts.
+//! - [`stats`]: Confidence intervals over repeated runs of random workloads.
+//! - [`queueing`]: What queueing theory predicts for a random workload.
+//! - [`experiment`]: Canned experiments, such as sweeping the round-robin
+//!   quantum.
+//! - [`selfcheck`]: Sanity checks of a run, to try a scheduler before
+//!   submitting it.
+//! - [`corpus`]: A regression corpus of seeded workloads and reference
+//!   results.
+//! - [`grade`]: Scores a scheduler against a corpus of reference results,
+//!   by a rubric, in the autograder's format.
+//! - [`report`]: Machine-readable pass/fail reports of those checks, for
+//!   CI-based autograding.
+//! - [`limits`]: Wall-clock, memory and event limits of a graded run.
+//! - [`simulation`]: The simulator's engine, for running a scheduler from
+//!   code with hooks at every dispatch, preemption, finish and tick.
+//! - [`fault`]: Diagnostics for a scheduler that panics during a run.
+//! - [`timer`]: The engine's queues of arrivals, I/O completions and
+//!   timeouts, including a hierarchical timing wheel.
+//! - [`slab`]: Values such as PCBs stored once and queued by a small
+//!   handle.
+//! - [`soak`]: Endurance runs that check memory and dispatch latency stay
+//!   flat over a very long simulated time.
+//! - [`equivalence`]: Pairs of schedulers, such as `simplerr` and a
+//!   one-level `mlrr`, that must log the same events on any workload.
+//! - [`messages`]: The catalog of trace lines with stable codes, in
+//!   English, Spanish or any language given as a TOML file.
+//! - [`html`]: A run as a standalone HTML page of tables and charts, for
+//!   lab write-ups.
+//! - [`burst`]: Exact accounting of the CPU time a job has had of its burst,
+//!   shared by the simulators.
+//! - [`bandwidth`]: CPU limits per job class in the style of a cgroup's
+//!   `cpu.max`, enforced by the simulator's engine.
+//! - [`control`]: Injects jobs into a run, paced to the wall clock, through
+//!   a named pipe, for live demos.
+//! - [`smp`]: Runs a scheduler on several CPUs, with pull or push load
+//!   balancing between their run queues.
+//! - [`cosim`]: Turn-based runs in which an outside program, e.g. a learned
+//!   policy, makes every dispatch decision.
+//! - `sqlite` (with the `sqlite` feature): An event sink that writes runs
+//!   into a SQLite database with indexed tables, for querying experiments.
+//! - [`scenario`]: Scheduler unit tests written as a list of arrivals and
+//!   expected events with the [`sim!`] macro.
+//! - [`testing`]: Checks that any scheduler keeps its queue discipline,
+//!   first come first served, round robin or by priority.
+//! - [`starvation`]: Proves that a scheduler starves no job of a tiny
+//!   workload beyond a bound, whatever the order of same-tick arrivals.
+//! - [`chaos`]: A scheduler wrapper that misbehaves on purpose, to test the
+//!   simulator's and [`selfcheck`]'s handling of broken schedulers.
 //!
 //! ## Scheduler Modules
 //! The following modules represent different scheduling strategies. You can
@@ -28,7 +94,19 @@
 //! - [`mlrr`]: **Multi-Level Round Robin** scheduler for layered priorities.
 //! - [`simplemlf`]: Simplified **Multi-Level Feedback Queue (MLFQ)** scheduler.
 //! - [`mlf`]: Full **MLFQ** scheduler for advanced scheduling experiments.
+//!
+//! [`bsd`] is a complete decay-usage scheduler in the style of 4.3BSD, and
+//! [`fgbg`] the textbook's foreground/background scheduler, to compare your
+//! MLFQ with. [`prio_fcfs`] is a plain priority scheduler for checking
+//! workloads. [`feedback`] assembles an MLFQ from a [`policy`] per level,
+//! e.g. round robin at the top and SJF at the bottom.
+//!
+//! Schedulers from other crates can be run as [`plugin`]s.
+
+use std::fmt;
+use std::num::NonZeroU32;
 
+use serde::{Deserialize, Serialize};
 
 /// Represents a **Process Control Block (PCB)** for a simulated process.
 ///
@@ -41,7 +119,7 @@
 /// - `priority`: Current priority level of the process.
 /// - `time_added`: Time (in simulation ticks) when the process was added.
 /// - `time_scheduled`: Time (in simulation ticks) when the process was last scheduled.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PCB {
     pub id: u32,
     pub priority: u32,
@@ -49,6 +127,203 @@ pub struct PCB {
     pub time_scheduled: Option<u64>,
 }
 
+/// How long a dispatched process may run, as returned by
+/// [`Schedule::next_process`].
+///
+/// Return [`TimeSlice::Infinite`] to run the process until it finishes or
+/// blocks (FCFS), and [`TimeSlice::Ticks`] to preempt it and add it back once
+/// it has run for that many ticks. `Ticks(0)` is not a time slice: the process
+/// could never run, and the simulator stops with an error.
+///
+/// Config files, dispatch events and the JSON results still write a time
+/// slice as a number, with `0` for infinite; [`from_raw`](TimeSlice::from_raw)
+/// and [`raw`](TimeSlice::raw) convert.
+///
+/// ## Example
+/// ```
+/// use scheduler::TimeSlice;
+///
+/// assert_eq!(TimeSlice::from_raw(0), TimeSlice::Infinite);
+/// assert_eq!(TimeSlice::from_raw(4), TimeSlice::Ticks(4));
+/// assert_eq!(TimeSlice::Infinite.raw(), 0);
+/// assert_eq!(TimeSlice::Ticks(4).to_string(), "4");
+/// assert_eq!(TimeSlice::Infinite.to_string(), "FCFS");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeSlice {
+    Infinite,
+    Ticks(u32),
+}
+
+impl TimeSlice {
+    /// Converts a time slice written as a number, where `0` means
+    /// [`TimeSlice::Infinite`].
+    pub fn from_raw(ticks: u32) -> Self {
+        match ticks {
+            0 => TimeSlice::Infinite,
+            ticks => TimeSlice::Ticks(ticks),
+        }
+    }
+
+    /// The time slice written as a number, `0` for
+    /// [`TimeSlice::Infinite`].
+    pub fn raw(self) -> u32 {
+        match self {
+            TimeSlice::Infinite => 0,
+            TimeSlice::Ticks(ticks) => ticks,
+        }
+    }
+}
+
+impl fmt::Display for TimeSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeSlice::Infinite => f.pad("FCFS"),
+            TimeSlice::Ticks(ticks) => f.pad(&ticks.to_string()),
+        }
+    }
+}
+
+/// The time slice a dispatched process runs for, as the simulator sees it.
+///
+/// A [`TimeSlice`] that is known to be runnable: a `Quantum` is never zero
+/// ticks long, so the simulator can count it down without checking for a
+/// sentinel first.
+///
+/// ## Example
+/// ```
+/// use scheduler::{Quantum, TimeSlice};
+///
+/// assert_eq!(Quantum::ticks(4).limit(), Some(4));
+/// assert_eq!(Quantum::FCFS.limit(), None);
+/// assert_eq!(Quantum::from(TimeSlice::Infinite), Quantum::FCFS);
+/// assert_eq!(Quantum::from(TimeSlice::Ticks(4)).raw(), 4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quantum(Option<NonZeroU32>);
+
+impl Quantum {
+    /// Runs the process until it finishes or blocks.
+    pub const FCFS: Quantum = Quantum(None);
+
+    /// Runs the process for at most `ticks` ticks.
+    ///
+    /// # Panics
+    /// If `ticks` is `0`; use [`Quantum::FCFS`] to run a process to
+    /// completion.
+    pub fn ticks(ticks: u32) -> Self {
+        match NonZeroU32::new(ticks) {
+            Some(ticks) => Quantum(Some(ticks)),
+            None => panic!("a quantum of 0 ticks, use Quantum::FCFS to run to completion"),
+        }
+    }
+
+    /// The quantum written as a number, `0` for FCFS, as in dispatch events.
+    pub fn raw(self) -> u32 {
+        self.0.map_or(0, NonZeroU32::get)
+    }
+
+    /// The most ticks the process may run for, `None` for FCFS.
+    pub fn limit(self) -> Option<u32> {
+        self.0.map(NonZeroU32::get)
+    }
+
+    /// Returns `true` if the process runs to completion.
+    pub fn is_fcfs(self) -> bool {
+        self.0.is_none()
+    }
+}
+
+impl From<TimeSlice> for Quantum {
+    /// # Panics
+    /// On `TimeSlice::Ticks(0)`, which no process can run for.
+    fn from(slice: TimeSlice) -> Self {
+        match slice {
+            TimeSlice::Infinite => Quantum::FCFS,
+            TimeSlice::Ticks(ticks) => Quantum::ticks(ticks),
+        }
+    }
+}
+
+/// The error [`Schedule::try_next`] returns when no process is ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoProcess;
+
+impl fmt::Display for NoProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the scheduler has no process ready")
+    }
+}
+
+impl std::error::Error for NoProcess {}
+
+/// How the work of a dispatch grows with the number of ready processes `n`,
+/// as [`SchedulerInfo::dispatch`] reports it.
+///
+/// The variants are ordered from cheapest to most expensive, so the cost of
+/// a scheduler built from several is the [`max`](Ord::max) of theirs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Complexity {
+    /// `O(1)`, e.g. popping the front of a queue.
+    Constant,
+    /// `O(log n)`, e.g. popping the first entry of a heap or a `BTreeMap`.
+    Logarithmic,
+    /// `O(n)`, e.g. scanning the whole queue for the shortest job.
+    Linear,
+}
+
+impl fmt::Display for Complexity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Complexity::Constant => "O(1)",
+            Complexity::Logarithmic => "O(log n)",
+            Complexity::Linear => "O(n)",
+        })
+    }
+}
+
+/// What kind of scheduler a [`Schedule`] is, as returned by
+/// [`Schedule::info`], for tools that pick the checks that apply to it:
+/// there is no point in checking that a non-preemptive scheduler rotates
+/// its processes, or in proving that a strict priority scheduler starves
+/// no one.
+///
+/// # Fields
+/// - `is_preemptive`: Whether a process can be taken off the CPU before it
+///   finishes or blocks, i.e. whether any dispatch hands out
+///   [`TimeSlice::Ticks`].
+/// - `starvation_free`: Whether every ready process is guaranteed to run
+///   eventually, however many others keep arriving.
+/// - `levels`: Number of queue levels, as [`Schedule::levels`].
+/// - `default_quantum`: The time slice of the top level.
+/// - `dispatch`: How the cost of `next_process` grows with the number of
+///   ready processes.
+///
+/// ## Example
+/// ```
+/// use scheduler::policy::{FCFS, RR};
+/// use scheduler::{Complexity, Schedule, SchedulerInfo, TimeSlice};
+///
+/// let rr = RR::with_quantum(4).info().unwrap();
+/// assert_eq!(rr, SchedulerInfo {
+///     is_preemptive: true,
+///     starvation_free: true,
+///     levels: 1,
+///     default_quantum: TimeSlice::Ticks(4),
+///     dispatch: Complexity::Constant,
+/// });
+/// assert!(!FCFS::new().info().unwrap().is_preemptive);
+/// assert_eq!(Complexity::Logarithmic.to_string(), "O(log n)");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchedulerInfo {
+    pub is_preemptive: bool,
+    pub starvation_free: bool,
+    pub levels: usize,
+    pub default_quantum: TimeSlice,
+    pub dispatch: Complexity,
+}
+
 /// Defines the **common interface** for all CPU scheduling algorithms.
 ///
 /// Every scheduler in this project implements the `Schedule` trait, which
@@ -58,10 +333,144 @@ pub struct PCB {
 /// - [`add_process`]: Adds a new process to the scheduler.
 /// - [`next_process`]: Retrieves the next process to execute.
 /// - [`has_process`]: Checks whether there are any remaining processes.
+///
+/// [`try_next`] is the same as [`next_process`], without the `Option`: it
+/// returns the process with its [`Quantum`], or [`NoProcess`]. It is
+/// implemented in terms of `next_process`, so there is nothing to override.
+///
+/// # Introspection Methods
+/// The remaining methods have default implementations and are only used by
+/// debugging tools such as the simulator's `--step` mode. Overriding them is
+/// optional, but lets those tools show what is waiting in your queues.
+/// - [`levels`]: Number of queue levels the scheduler maintains.
+/// - [`level_quantum`]: Time slice handed out at a given level.
+/// - [`iter_level`]: Iterates over the processes waiting at a given level.
+/// - [`peek`]: The process that would be returned next, without removing it.
+/// - [`iter_ready`]: Iterates over every queued process, across all levels.
+/// - [`dispatched_level`]: The level the last process returned by
+///   `next_process` came from.
+/// - [`heap_size`]: Bytes of heap memory the scheduler's queues hold, for
+///   the memory footprint reported in the run summary.
+/// - [`info`]: What kind of scheduler it is, such as whether it preempts,
+///   for `scheduler list-schedulers --detailed`.
+///
+/// # Tie-Breaking
+/// Schedulers must be deterministic. When several ready processes have the
+/// same priority and were added at the same tick, the one added first must be
+/// returned first; the simulator's `--strict` mode checks this (see
+/// [`strict`]).
 pub trait Schedule {
     fn add_process(&mut self, process: PCB) -> bool;
-    fn next_process(&mut self) -> (Option<PCB>, u32);
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice);
     fn has_process(&self) -> bool;
+
+    /// Retrieves the next process to execute along with its time slice.
+    ///
+    /// # Panics
+    /// If `next_process` returns a process with `TimeSlice::Ticks(0)`.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::{NoProcess, PCB, Quantum, Schedule, TimeSlice};
+    ///
+    /// struct Lifo(Vec<PCB>);
+    /// impl Schedule for Lifo {
+    ///     fn add_process(&mut self, p: PCB) -> bool { self.0.push(p); true }
+    ///     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop(), TimeSlice::Infinite) }
+    ///     fn has_process(&self) -> bool { !self.0.is_empty() }
+    /// }
+    ///
+    /// let mut sched = Lifo(Vec::new());
+    /// sched.add_process(PCB { id: 7, priority: 0, time_added: None, time_scheduled: None });
+    /// let (process, quantum) = sched.try_next().unwrap();
+    /// assert_eq!((process.id, quantum), (7, Quantum::FCFS));
+    /// assert_eq!(sched.try_next(), Err(NoProcess));
+    /// ```
+    fn try_next(&mut self) -> Result<(PCB, Quantum), NoProcess> {
+        match self.next_process() {
+            (Some(process), slice) => Ok((process, Quantum::from(slice))),
+            (None, _) => Err(NoProcess),
+        }
+    }
+
+    fn levels(&self) -> usize {
+        1
+    }
+
+    fn level_quantum(&self, _level: usize) -> TimeSlice {
+        TimeSlice::Infinite
+    }
+
+    fn iter_level(&self, _level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        Box::new(std::iter::empty())
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        None
+    }
+
+    /// Defaults to walking [`iter_level`](Schedule::iter_level) from level `0`
+    /// down, so it is empty unless the scheduler exposes its levels.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::{PCB, Schedule, TimeSlice};
+    ///
+    /// // priority 0 goes to the top level, anything else to the bottom one
+    /// struct TwoLevel([Vec<PCB>; 2]);
+    /// impl Schedule for TwoLevel {
+    ///     fn add_process(&mut self, p: PCB) -> bool { self.0[(p.priority > 0) as usize].push(p); true }
+    ///     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (None, TimeSlice::Infinite) }
+    ///     fn has_process(&self) -> bool { self.0.iter().any(|level| !level.is_empty()) }
+    ///     fn levels(&self) -> usize { 2 }
+    ///     fn iter_level(&self, level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> { Box::new(self.0[level].iter()) }
+    /// }
+    ///
+    /// let mut sched = TwoLevel([Vec::new(), Vec::new()]);
+    /// for (id, priority) in [(1, 3), (2, 0), (3, 1), (4, 0)] {
+    ///     sched.add_process(PCB { id, priority, time_added: None, time_scheduled: None });
+    /// }
+    /// let ids: Vec<u32> = sched.iter_ready().map(|p| p.id).collect();
+    /// assert_eq!(ids, [2, 4, 1, 3]);
+    /// ```
+    fn iter_ready(&self) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        Box::new((0..self.levels()).flat_map(move |level| self.iter_level(level)))
+    }
+
+    /// Defaults to `None`, meaning unknown. The simulator then tells the level
+    /// by matching the returned time slice against the configured quanta,
+    /// which only works while every level has its own quantum. Multi-level
+    /// schedulers should remember the level in `next_process` and return it
+    /// here, so level residence in the summary and results is exact. It is
+    /// asked right after every dispatch.
+    fn dispatched_level(&self) -> Option<usize> {
+        None
+    }
+
+    /// Defaults to `0`, meaning unknown. Count allocated capacity rather than
+    /// length, since that is what the scheduler actually holds on to, e.g.
+    /// `self.queue.capacity() * size_of::<PCB>()` for a single `VecDeque`.
+    /// The simulator samples it at every dispatch, so keep it cheap.
+    fn heap_size(&self) -> usize {
+        0
+    }
+
+    /// Defaults to `None`, meaning unknown. Describes the scheduler as
+    /// configured, not its current state, so it can be asked before the
+    /// first process is added; see [`SchedulerInfo`].
+    ///
+    /// The assignment's schedulers answer it before they are implemented, so
+    /// `scheduler list-schedulers --detailed` can show what to aim for.
+    /// Update the answer if your implementation differs.
+    fn info(&self) -> Option<SchedulerInfo> {
+        None
+    }
+
+    /// Defaults to ignoring it. Called when the nice value of process `id`,
+    /// queued or running, changes to `nice`, including right after it
+    /// arrives with a nice value other than `0`. A scheduler that shares
+    /// the CPU by weight looks the weight up with [`nice::weight`].
+    fn renice(&mut self, _id: u32, _nice: i8) {}
 }
 
 /// Provides timing utilities for simulated scheduling operations.
@@ -69,7 +478,142 @@ pub trait Schedule {
 /// The [`Clock`] module can be used to track the current simulation time,
 /// record when processes are added, and measure CPU burst durations.
 pub mod clock;
-pub use clock::{CLOCK, Clock};
+pub use clock::{CLOCK, Clock, TimeUnit};
+
+/// Parses workload files into job descriptions for the simulators.
+pub mod input;
+
+/// Example workloads built into the binary.
+pub mod workloads;
+
+/// A seeded random number generator for reproducible random workloads.
+pub mod random;
+
+/// Probability distributions for arrivals, bursts and think times.
+pub mod distribution;
+
+/// Generates random workloads for experiments.
+pub mod generate;
+
+/// Renumbers, rescales and strips workloads into practice versions.
+pub mod transform;
+
+/// Builds the worst-case workload of a scheduling policy.
+pub mod adversary;
+
+/// Summarizes what a workload asks of the CPU, before running it.
+pub mod inspect;
+
+/// Nice values and their weights, as in Linux.
+pub mod nice;
+
+/// The events a simulation run is described by.
+pub mod event;
+
+/// Consumers of simulation events, including the classic text trace.
+pub mod sink;
+
+/// Turnaround, waiting and response time metrics computed from events.
+pub mod metrics;
+
+/// Report bundles: a run's workload, parameters and results in one zip archive.
+pub mod bundle;
+
+/// An event sink that writes runs into a SQLite database.
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// The versioned JSON results document.
+pub mod results;
+
+/// Analyses of a run's event log, such as convoy detection.
+pub mod analysis;
+
+/// Mean waiting time binned by arrival time and burst length.
+pub mod heatmap;
+
+/// The idealized fair processor-sharing baseline.
+pub mod ps;
+
+/// Compares two runs and reports where they first diverge.
+pub mod diff;
+/// Means and confidence intervals of metrics over repeated runs.
+pub mod stats;
+/// Mean waiting times predicted by M/M/1 and M/G/1 queueing theory.
+pub mod queueing;
+/// Canned experiments such as the round-robin quantum sweep.
+pub mod experiment;
+/// Sanity checks of a run against the `Schedule` contract.
+pub mod selfcheck;
+/// The seeded workloads and layout of a regression corpus.
+pub mod corpus;
+/// Scores a scheduler against a corpus by a rubric, for the autograder.
+pub mod grade;
+/// JUnit XML and JSON reports of the checks the checking commands make.
+pub mod report;
+/// Resource limits that keep a broken scheduler from taking grading down.
+pub mod limits;
+
+/// The simulator's engine, with hooks for observing and steering a run.
+pub mod simulation;
+/// The loops `simple`, `simplerr` and `mlrr` run on, as the reference traces expect.
+pub mod classic;
+/// Catches a panicking scheduler and describes what it was doing.
+pub mod fault;
+/// Queues of timers for the simulator's engine, e.g. a timing wheel.
+pub mod timer;
+
+/// Values stored once and referred to by small handles.
+pub mod slab;
+/// Very long runs with endless arrivals, watching for growth.
+pub mod soak;
+/// Schedulers that must behave identically, checked against each other.
+pub mod equivalence;
+/// The wording of the plain text trace, by stable message code and locale.
+pub mod messages;
+/// Standalone HTML reports of a run, with inline SVG charts.
+pub mod html;
+/// Exact accounting of a job's CPU burst across preemptions.
+pub mod burst;
+
+/// `cpu.max`-style CPU limits for groups of processes.
+pub mod bandwidth;
+
+/// Steers a running simulation from outside, e.g. by injecting jobs.
+pub mod control;
+
+/// Several CPUs with a run queue each, balanced by pulling or pushing.
+pub mod smp;
+
+/// Turn-based runs in which an outside agent decides every dispatch.
+pub mod cosim;
+
+/// Draws runs as Gantt charts, stacked on a shared time axis.
+pub mod gantt;
+
+/// Audits a scheduler's tie-breaking for the simulator's `--strict` mode.
+pub mod strict;
+
+/// Injects faults into a scheduler, for testing the checks that catch them.
+pub mod chaos;
+
+/// Scheduler unit tests written with the [`sim!`] macro.
+pub mod scenario;
+
+/// Property checks of a scheduler's queue discipline: FIFO, rotation, priority.
+pub mod testing;
+
+/// Exhaustive starvation checks over every same-tick arrival ordering.
+pub mod starvation;
+
+/// Runs schedulers from other crates as plugin processes.
+pub mod plugin;
+
+/// Where results are written: stdout, or a file that is replaced atomically.
+pub mod output;
+
+/// Per-scheduler parameters, read from a `--config` file.
+pub mod config;
 
 /// Contains a basic scheduler implementation template.
 pub mod simple;
@@ -85,3 +629,18 @@ pub mod simplemlf;
 
 /// Contains a more complete **MLFQ** scheduler implementation.
 pub mod mlf;
+
+/// Contains a **decay-usage** scheduler in the style of 4.3BSD.
+pub mod bsd;
+
+/// Contains the textbook's **foreground/background** two-queue scheduler.
+pub mod fgbg;
+
+/// Contains a non-preemptive **priority** scheduler with FCFS tie-breaking.
+pub mod prio_fcfs;
+
+/// Contains complete single-queue policies: **RR**, **FCFS** and **SJF**.
+pub mod policy;
+
+/// Contains an **MLFQ** built from a scheduling policy per level.
+pub mod feedback;
@@ -0,0 +1,199 @@
+//! # Limits Module
+//!
+//! Resource limits of a run, so that grading survives a broken scheduler.
+//!
+//! `scheduler grade` runs the scheduler on every workload in a child
+//! process of its own, and watches it: a `next_process` that loops forever,
+//! leaks memory or keeps the run going without end takes down that one run
+//! and fails its workload with a [`ResourceLimit`], while the others are
+//! graded as usual. [`Limits`] bounds three things:
+//!
+//! - **wall-clock time**: the grader kills a child that runs for longer;
+//! - **memory**: the child's [`CappedAllocator`] counts the bytes it has
+//!   allocated and ends the process on the first allocation past the cap;
+//! - **events**: an [`EventBudget`] sink ends the process on the first
+//!   event past the budget, which catches a run that never finishes, such
+//!   as a scheduler that hands out a finished process again and again.
+//!
+//! A child that hits a limit prints the [`ResourceLimit`] as the first line
+//! on stderr and exits with [`EXIT_CODE`], for the grader to tell it from a
+//! run that failed.
+//!
+//! ## Example
+//! ```
+//! use std::alloc::{GlobalAlloc, Layout};
+//! use std::time::Duration;
+//! use scheduler::limits::{CappedAllocator, Limits, ResourceLimit};
+//!
+//! let limits = Limits::default();
+//! assert_eq!((limits.wall_clock, limits.memory_mib, limits.events), (Duration::from_secs(10), 1024, 10_000_000));
+//! assert_eq!(ResourceLimit::WallClock(limits.wall_clock).to_string(), "ResourceLimit: ran for more than 10s of wall-clock time");
+//! assert_eq!(ResourceLimit::Memory(512).to_string(), "ResourceLimit: allocated more than 512 MiB");
+//!
+//! // the allocator counts the bytes in use
+//! let alloc = CappedAllocator::new();
+//! let layout = Layout::from_size_align(4096, 8).unwrap();
+//! unsafe {
+//!     let block = alloc.alloc(layout);
+//!     assert_eq!(alloc.used(), 4096);
+//!     alloc.dealloc(block, layout);
+//! }
+//! assert_eq!(alloc.used(), 0);
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::event::Event;
+use crate::sink::EventSink;
+
+/// The exit status of a process that hit one of its limits.
+pub const EXIT_CODE: i32 = 75;
+
+/// Bytes in a mebibyte.
+const MIB: u64 = 1024 * 1024;
+
+/// How much a run may use.
+///
+/// # Fields
+/// - `wall_clock`: Real time the run may take.
+/// - `memory_mib`: Memory the run may have allocated at once, in MiB.
+/// - `events`: Events the run may emit, ticks of execution included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub wall_clock: Duration,
+    pub memory_mib: u64,
+    pub events: u64,
+}
+
+impl Default for Limits {
+    /// 10 seconds, 1 GiB and 10 million events, far more than any corpus
+    /// workload needs.
+    fn default() -> Self {
+        Self { wall_clock: Duration::from_secs(10), memory_mib: 1024, events: 10_000_000 }
+    }
+}
+
+/// The limit a run hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    /// The run took longer than this.
+    WallClock(Duration),
+    /// The run allocated more than this many MiB.
+    Memory(u64),
+    /// The run emitted more than this many events.
+    Events(u64),
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceLimit::WallClock(limit) => write!(f, "ResourceLimit: ran for more than {:?} of wall-clock time", limit),
+            ResourceLimit::Memory(mib) => write!(f, "ResourceLimit: allocated more than {} MiB", mib),
+            ResourceLimit::Events(events) => write!(f, "ResourceLimit: emitted more than {} events", events),
+        }
+    }
+}
+
+/// Ends the process for hitting `limit`: prints it on stderr and exits
+/// with [`EXIT_CODE`].
+pub fn exceeded(limit: ResourceLimit) -> ! {
+    let _ = writeln!(std::io::stderr(), "{}", limit);
+    std::process::exit(EXIT_CODE)
+}
+
+/// The system allocator, counting the bytes in use and ending the process
+/// once they pass a cap, see [`cap`](Self::cap). Installed as the
+/// `#[global_allocator]` of the simulator.
+///
+/// Once the cap is hit, allocations go through uncapped, so the process can
+/// report it and exit.
+pub struct CappedAllocator {
+    used: AtomicU64,
+    cap: AtomicU64,
+    tripped: AtomicBool,
+}
+
+impl CappedAllocator {
+    /// An allocator with no cap.
+    pub const fn new() -> Self {
+        Self { used: AtomicU64::new(0), cap: AtomicU64::new(u64::MAX), tripped: AtomicBool::new(false) }
+    }
+
+    /// Caps the memory in use at `mib` MiB, from now on.
+    pub fn cap(&self, mib: u64) {
+        self.cap.store(mib.saturating_mul(MIB), Ordering::Relaxed);
+    }
+
+    /// Bytes allocated and not yet freed.
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    /// Counts `bytes` more in use, ending the process if that passes the cap.
+    fn grow(&self, bytes: usize) {
+        let used = self.used.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
+        let cap = self.cap.load(Ordering::Relaxed);
+        if used > cap && !self.tripped.swap(true, Ordering::Relaxed) {
+            exceeded(ResourceLimit::Memory(cap / MIB));
+        }
+    }
+}
+
+impl Default for CappedAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl GlobalAlloc for CappedAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.grow(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.grow(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.used.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        match new_size.checked_sub(layout.size()) {
+            Some(more) => self.grow(more),
+            None => {
+                self.used.fetch_sub((layout.size() - new_size) as u64, Ordering::Relaxed);
+            }
+        }
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// A sink that ends the process on the first event past its budget.
+pub struct EventBudget {
+    limit: u64,
+    seen: u64,
+}
+
+impl EventBudget {
+    /// A budget of `limit` events.
+    pub fn new(limit: u64) -> Self {
+        Self { limit, seen: 0 }
+    }
+}
+
+impl EventSink for EventBudget {
+    fn event(&mut self, _event: &Event) {
+        self.seen += 1;
+        if self.seen > self.limit {
+            exceeded(ResourceLimit::Events(self.limit));
+        }
+    }
+}
@@ -1,574 +1,3316 @@
-use std::env;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
-use std::io::{self, BufRead};
-use std::path::Path;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::time::Duration;
-use clap::Parser;
-use scheduler::{CLOCK, PCB, Schedule, simple::SimpleSchedule, simplerr::SimpleRRSchedule,mlrr::MLRRSchedule,simplemlf::SimpleMLFSchedule,mlf::MLFSchedule};
+use clap::{Parser, Subcommand, ValueEnum};
+use scheduler::adversary::{Adversary, Target};
+use scheduler::analysis;
+use scheduler::bandwidth::{Bandwidth, CpuMax};
+use scheduler::burst::Burst;
+use scheduler::classic::{Classic, Run};
+use scheduler::bundle::{self, Bundle, Manifest};
+use scheduler::clock::Pacer;
+use scheduler::config::{EngineConfig, MlfqRules, SchedulerConfig};
+use scheduler::control::{ControlFifo, Controls};
+use scheduler::corpus;
+use scheduler::diff;
+use scheduler::equivalence;
+use scheduler::fault::{self, Fault};
+use scheduler::experiment::{self, ErrorPoint, QuantumPoint, RulesPoint, SaturationPoint};
+use scheduler::gantt::{self, Timeline};
+use scheduler::grade::{Outcome, Rubric};
+use scheduler::heatmap::Heatmap;
+use scheduler::html;
+use scheduler::distribution::Distribution;
+use scheduler::generate::{self, Generator, JobClass};
+use scheduler::event::{Annotation, Event, EventKind, IDLE};
+use scheduler::input::{Mode, Renice, Step};
+use scheduler::metrics::Window;
+use scheduler::random::Rng;
+use scheduler::output::Output;
+use scheduler::policy::SPN;
+use scheduler::ps::Baseline;
+use scheduler::queueing;
+use scheduler::report::{ReportFormat, TestCase, TestReport};
+use scheduler::selfcheck;
+use scheduler::simulation::{self, ArrivalOrder, ExpiryOrder, Simulation, SimulationError};
+use scheduler::cosim::{self, CoSimulation};
+use scheduler::smp::{Balance, Placement, Smp, SmpStats};
+use scheduler::soak::{Soak, WindowStats};
+use scheduler::stats::BatchReport;
+use scheduler::plugin::{self, PluginSchedule};
+use scheduler::results::{Provenance, Results, RunConfig};
+use scheduler::strict::{StrictSchedule, TieBreak};
+use scheduler::inspect::Inspection;
+use scheduler::messages::Catalog;
+use scheduler::limits::{self, CappedAllocator, EventBudget, Limits, ResourceLimit};
+use scheduler::transform::{self, Transform};
+use scheduler::sink::{CpuShareSink, JsonStreamSink, PrettySink, ProgressSink, Recorder, StopConditions, TextSink, Verbosity};
+use scheduler::{CLOCK, PCB, Quantum, Schedule, TimeUnit, input, workloads, simple::SimpleSchedule, simplerr::SimpleRRSchedule,mlrr::MLRRSchedule,simplemlf::SimpleMLFSchedule,mlf::MLFSchedule,bsd::BsdSchedule,fgbg::FgBgSchedule,prio_fcfs::PrioFcfsSchedule,feedback::Stack};
+
+//counts the memory in use, for --max-memory
+#[global_allocator]
+static ALLOCATOR: CappedAllocator = CappedAllocator::new();
 
 /// Simple args to set which scheduler to use and which input file to feed it
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version = env!("SCHEDULER_VERSION"), about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Name of scheduler
+    #[arg(short, long, required = true)]
+    scheduler: Option<String>,
+
+    /// input file, or `builtin:<name>` for a built-in example workload
+    #[arg(short, long, required = true)]
+    input_file: Option<String>,
+
+    /// Unit that plain times in the input file are written in; also the length of one tick
+    #[arg(long, default_value = "ns")]
+    time_unit: TimeUnit,
+
+    /// Pause after every simulated tick and wait for a command on stdin
+    #[arg(long)]
+    step: bool,
+
+    /// Only print an end-of-run summary; shows a progress bar instead when stdout is a terminal
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Also print dispatches and preemptions (-v), and the tick of every line (-vv)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Output format: the classic per-tick trace, or a JSON results document
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Never use the colorized trace, even when stdout is a terminal
+    #[arg(long)]
+    no_color: bool,
+
+    /// Language of the plain trace: en (the reference format), es, codes (each line as its message code and values) or a TOML file of message templates; implies --no-color
+    #[arg(long, value_name = "LOCALE", value_parser = parse_locale)]
+    locale: Option<Catalog>,
+
+    /// Write the output to this file instead of stdout; it only appears once the run has finished
     #[arg(short, long)]
-    scheduler: String,
+    output_file: Option<String>,
+
+    /// With `--format json`, stream results as JSON Lines, flushing a metrics snapshot every N ticks
+    #[arg(long, value_name = "N")]
+    flush_every: Option<u64>,
+
+    /// Report every dispatch that breaks the deterministic tie-breaking order, and exit with status 1 if any did
+    #[arg(long)]
+    strict: bool,
+
+    /// TOML file with scheduler parameters such as quanta; defaults to the assignment's values
+    #[arg(long, value_name = "FILE")]
+    config: Option<String>,
+
+    /// After the run, report jobs that waited over RATIO times their CPU time behind one long job
+    #[arg(long, value_name = "RATIO", num_args = 0..=1, default_missing_value = "4")]
+    convoy: Option<u64>,
+
+    /// After the run, report jobs that gamed their time slices by blocking with at most MARGIN ticks left
+    #[arg(long, value_name = "MARGIN", num_args = 0..=1, default_missing_value = "1")]
+    gaming: Option<u64>,
+
+    /// After the run, report how I/O-bound jobs, which spent at least PERCENT of their CPU and I/O time on I/O, fared against CPU-bound ones
+    #[arg(long, value_name = "PERCENT", num_args = 0..=1, default_missing_value = "50", value_parser = clap::value_parser!(u64).range(1..=100))]
+    io_bound: Option<u64>,
+
+    /// After the run, break every job's time off the CPU down into its causes, charging each context switch COST ticks
+    #[arg(long, value_name = "COST", num_args = 0..=1, default_missing_value = "0", conflicts_with = "cpus")]
+    wait_breakdown: Option<u64>,
+
+    /// After the run, compare every job's CPU time with its fair share by the weight of its nice value
+    #[arg(long, conflicts_with = "cpus")]
+    fair_share: bool,
+
+    /// Leave jobs arriving before TIME out of the aggregate metrics, to skip the warm-up phase
+    #[arg(long, value_name = "TIME")]
+    measure_from: Option<String>,
+
+    /// Leave jobs arriving at or after TIME out of the aggregate metrics, to skip the drain phase
+    #[arg(long, value_name = "TIME")]
+    measure_until: Option<String>,
+
+    /// End the run when the clock reaches TIME, even if jobs are left
+    #[arg(long, value_name = "TIME")]
+    stop_after_ticks: Option<String>,
+
+    /// End the run once N jobs have finished
+    #[arg(long, value_name = "N")]
+    stop_after_completions: Option<usize>,
+
+    /// End the run once the CPU has been idle for TIME in a row
+    #[arg(long, value_name = "TIME")]
+    stop_when_idle_for: Option<String>,
+
+    /// Write every live process's CPU share over time to FILE as CSV, for stacked-area plots
+    #[arg(long, value_name = "FILE")]
+    cpu_share: Option<String>,
+
+    /// Also append the run's events and metrics to the SQLite database FILE, created if missing
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "FILE")]
+    sqlite: Option<PathBuf>,
+
+    /// Record NAME=VALUE with the run's provenance in every output file, to find it again with `scheduler results ls --where`; repeat for several tags
+    #[arg(long = "tag", value_name = "NAME=VALUE", value_parser = parse_tag)]
+    tags: Vec<(String, String)>,
+
+    /// Also write the workload, scheduler parameters, results, metrics summary and Gantt chart to the zip archive FILE, to replay with `scheduler replay`
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["cpus", "agent", "cpu_max", "inject"])]
+    bundle: Option<PathBuf>,
+
+    /// Ticks between the samples written by --cpu-share
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "cpu_share")]
+    cpu_share_every: u64,
+
+    /// Also write a standalone HTML page of the run to FILE: summary and per-process tables, Gantt chart and charts, for a lab write-up
+    #[arg(long, value_name = "FILE")]
+    report_html: Option<PathBuf>,
+
+    /// Write the mean waiting time by arrival time and burst length to FILE, as SVG if it ends in .svg and CSV otherwise
+    #[arg(long, value_name = "FILE")]
+    heatmap: Option<String>,
+
+    /// Number of arrival and of burst bins of --heatmap
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "heatmap", value_parser = clap::value_parser!(u64).range(1..))]
+    heatmap_bins: u64,
+
+    /// Limit job class CLASS to QUOTA ticks of CPU time in every PERIOD ticks, like a cgroup's cpu.max, e.g. batch=20/100; repeat for every class
+    #[arg(long = "cpu-max", value_name = "CLASS=QUOTA/PERIOD", value_parser = parse_cpu_max)]
+    cpu_max: Vec<(String, CpuMax)>,
+
+    /// Fire the scheduling timer HZ times per second of simulated time, e.g. 100 or 1000, and count time slices in timer ticks instead of --time-unit ticks; not supported by the assignment's schedulers
+    #[arg(long, value_name = "HZ", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["cpus", "bundle"])]
+    hz: Option<u32>,
+
+    /// Program the scheduling timer only for the next quantum expiry or arrival instead of firing it periodically, and report how many timer interrupts that avoids; not supported by the assignment's schedulers
+    #[arg(long, conflicts_with_all = ["cpus", "bundle"])]
+    tickless: bool,
+
+    /// Order that jobs arriving at the same tick are handed to the scheduler in: input (the order of the input file) or id (lowest job ID first)
+    #[arg(long, value_name = "ORDER", default_value = "input")]
+    arrival_order: ArrivalOrder,
+
+    /// Whether jobs arriving at the tick a quantum expires queue up before the preempted process (arrivals-first) or after it (preempted-first); the default is preempted-first for simplerr and mlrr, as their reference outputs were made, and arrivals-first for every other scheduler
+    #[arg(long, value_name = "ORDER", conflicts_with_all = ["cpus", "bundle"])]
+    expiry_order: Option<ExpiryOrder>,
+
+    /// Let every dispatched process run until it finishes or blocks, ignoring the scheduler's time slices, to run any policy in batch mode
+    #[arg(long, conflicts_with_all = ["cpus", "bundle"])]
+    non_preemptive: bool,
+
+    /// When the scheduler panics, print the clock, the last events and its queue instead of a backtrace, and exit with status 70
+    #[arg(long, conflicts_with = "cpus")]
+    catch_panics: bool,
+
+    /// Run at TICKS_PER_SEC simulated ticks per second of real time, to watch the run live; space pauses, s steps and the arrow keys change the pace
+    #[arg(long, value_name = "TICKS_PER_SEC", value_parser = parse_pace, conflicts_with = "step")]
+    pace: Option<f64>,
+
+    /// Read jobs to add, one input line each, from the named pipe FIFO while the run is paced; created if missing
+    #[arg(long, value_name = "FIFO", requires = "pace")]
+    inject: Option<PathBuf>,
+
+    /// Run on N CPUs, each with its own run queue and instance of the scheduler
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with_all = ["strict", "step", "pace", "cpu_max"])]
+    cpus: Option<u64>,
+
+    /// How --cpus balances the run queues: none, pull[:EVERY] (an idle CPU pulls work) or push[:EVERY] (work is pushed every EVERY ticks)
+    #[arg(long, value_name = "POLICY", default_value = "pull", requires = "cpus")]
+    balance: Balance,
+
+    /// Write the --cpus load imbalance and migrations at every tick to FILE as CSV
+    #[arg(long, value_name = "FILE", requires = "cpus")]
+    imbalance: Option<String>,
+
+    /// Group the --cpus into N NUMA nodes of consecutive CPUs; a process dispatched off the node it was placed on stalls for --numa-penalty ticks
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), requires = "cpus")]
+    numa: Option<u64>,
+
+    /// Ticks a remote dispatch stalls for with --numa
+    #[arg(long, value_name = "TICKS", default_value_t = 2, requires = "numa")]
+    numa_penalty: u32,
+
+    /// Where --cpus places processes: spread (the least busy CPU), numa (the least busy node, balancing within nodes first) or cores (the least busy core, keeping --smt siblings apart)
+    #[arg(long, value_name = "PLACEMENT", default_value = "spread", requires = "cpus")]
+    placement: Placement,
+
+    /// Make every THREADS consecutive --cpus hardware threads of one core, which slow each other down while busy
+    #[arg(long, value_name = "THREADS", value_parser = clap::value_parser!(u64).range(1..), requires = "cpus")]
+    smt: Option<u64>,
+
+    /// The share of a tick's work each busy --smt sibling gets done per tick, more than 0 and at most 1
+    #[arg(long, value_name = "FRACTION", default_value_t = 0.6, value_parser = parse_share, requires = "smt")]
+    smt_share: f64,
+
+    /// With -s agent, the program, and its arguments split at whitespace, that decides every dispatch over its stdin and stdout
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["cpus", "strict", "step", "pace"])]
+    agent: Option<String>,
+
+    /// Exit with a ResourceLimit error, status 75, as soon as the run has more than MIB mebibytes of memory allocated
+    #[arg(long, value_name = "MIB", value_parser = clap::value_parser!(u64).range(1..))]
+    max_memory: Option<u64>,
+
+    /// Exit with a ResourceLimit error, status 75, as soon as the run emits more than N events, ticks of execution included
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_events: Option<u64>,
+}
+
+///Reads an `--smt-share` fraction
+fn parse_share(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(share) if share > 0.0 && share <= 1.0 => Ok(share),
+        _ => Err(format!("'{}': expected a fraction more than 0 and at most 1", s)),
+    }
+}
+
+///Reads a `--tag` or `--where` pair
+fn parse_tag(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) if !name.is_empty() && !value.is_empty() && !s.contains(char::is_whitespace) => {
+            Ok((name.to_string(), value.to_string()))
+        }
+        _ => Err(format!("'{}': expected NAME=VALUE without spaces, e.g. experiment=quantum-sweep", s)),
+    }
+}
+
+///Reads a `--cpu-max` limit
+fn parse_cpu_max(s: &str) -> Result<(String, CpuMax), String> {
+    let Some((class, max)) = s.split_once('=') else {
+        return Err(format!("'{}': expected CLASS=QUOTA/PERIOD, e.g. batch=20/100", s));
+    };
+    Ok((class.to_string(), max.parse()?))
+}
+
+///Reads a `transform --scale` factor
+fn parse_scale(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(scale) if scale > 0.0 && scale.is_finite() => Ok(scale),
+        _ => Err(format!("'{}': the scale must be a positive number", s)),
+    }
+}
+
+///Reads a positive number of `soak`, such as `--hours-simulated`
+fn parse_positive(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value),
+        _ => Err(format!("'{}': expected a positive number", s)),
+    }
+}
+
+///Reads a `--locale`, a built-in catalog or a TOML file of templates
+fn parse_locale(s: &str) -> Result<Catalog, String> {
+    if let Some(catalog) = Catalog::builtin(s) {
+        return Ok(catalog);
+    }
+    let text = std::fs::read_to_string(expand_home(s)).map_err(|err| format!("'{}': not en, es or codes, and cannot be read: {}", s, err))?;
+    Catalog::from_toml(&text).map_err(|err| format!("'{}': {}", s, err))
+}
+
+///Reads a `--sweep-rates` arrival rate
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        _ => Err(format!("'{}': the arrival rate must be a positive number of jobs per tick", s)),
+    }
+}
+
+///Reads a `--pace` rate
+fn parse_pace(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(pace) if pace > 0.0 && pace.is_finite() => Ok(pace),
+        _ => Err(format!("'{}': the pace must be a positive number of ticks per second", s)),
+    }
+}
+
+///Tools that prepare or compare runs instead of running a simulation
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Write a random workload in the input file format to stdout
+    Gen {
+        #[command(flatten)]
+        workload: GenArgs,
+        /// Write a workload known to be pathological for POLICY instead of a random one: fcfs (a convoy), rr (jobs one tick longer than the quantum), mlfq (quantum gaming, written as JSON) or priority (thundering arrivals). Only --jobs is used of the random workload options
+        #[arg(long, value_name = "POLICY")]
+        adversarial: Option<Target>,
+        /// The time slice of the policy --adversarial targets, in ticks
+        #[arg(long, value_name = "N", default_value_t = 4, requires = "adversarial", value_parser = clap::value_parser!(u32).range(1..))]
+        quantum: u32,
+    },
+    /// Write a practice version of a workload to stdout: jobs renumbered, times rescaled, class labels and annotations stripped
+    Transform {
+        /// Input file, or `builtin:<name>` for a built-in example workload
+        #[arg(short, long)]
+        input: String,
+        /// Renumber the jobs in a random order and strip their class labels and annotations
+        #[arg(long)]
+        anonymize: bool,
+        /// Multiply every time by FACTOR, rounding to whole ticks
+        #[arg(long, value_name = "FACTOR", default_value_t = 1.0, value_parser = parse_scale)]
+        scale: f64,
+        /// Seed of the new numbering with --anonymize; the same seed gives the same numbering
+        #[arg(long, default_value_t = 0, requires = "anonymize")]
+        seed: u64,
+    },
+    /// Summarize a workload before running it: arrivals over time, CPU burst histogram, priorities and offered load
+    Inspect {
+        /// Input file, or `builtin:<name>` for a built-in example workload; a random workload by default
+        #[arg(short, long)]
+        input_file: Option<String>,
+        /// Unit that plain times in the input file are written in; also the length of one tick
+        #[arg(long, default_value = "ns")]
+        time_unit: TimeUnit,
+        /// Number of bins the arrivals are counted in
+        #[arg(long, value_name = "N", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        bins: u64,
+        #[command(flatten)]
+        workload: GenArgs,
+    },
+    /// Run schedulers on several random workloads and report each metric with a 95% confidence interval
+    Batch {
+        /// Name of a scheduler to run; repeat to compare several
+        #[arg(short, long = "scheduler", required = true)]
+        schedulers: Vec<String>,
+        /// Number of runs; run i uses the workload generated with seed SEED + i
+        #[arg(long, value_name = "N", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        runs: u64,
+        /// TOML file with scheduler parameters, passed on to every run
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+        /// Run the batch once per Poisson arrival rate, in jobs per tick, comma separated, and report throughput and response time per rate; replaces --arrival
+        #[arg(long, value_name = "RATES", value_delimiter = ',', value_parser = parse_rate, conflicts_with = "classes")]
+        sweep_rates: Vec<f64>,
+        /// Where to write the rate sweep as CSV
+        #[arg(long, value_name = "FILE", default_value = "saturation.csv", requires = "sweep_rates")]
+        csv: PathBuf,
+        /// Also chart the rate sweep as SVG to FILE
+        #[arg(long, value_name = "FILE", requires = "sweep_rates")]
+        svg: Option<PathBuf>,
+        #[command(flatten)]
+        workload: GenArgs,
+    },
+    /// Run schedulers on tiny built-in workloads and check that they keep the Schedule contract
+    Selfcheck {
+        /// Schedulers to check, built-in or plugins; all built-in schedulers by default
+        schedulers: Vec<String>,
+        /// Also write a pass/fail report of every check to FILE, in FORMAT junit (XML) or json
+        #[arg(long, num_args = 2, value_names = ["FORMAT", "FILE"])]
+        report: Vec<String>,
+    },
+    /// List the built-in schedulers
+    ListSchedulers {
+        /// Also describe each scheduler: whether it preempts, whether it starves no one, its levels, top-level quantum and the cost of a dispatch
+        #[arg(long)]
+        detailed: bool,
+        /// TOML file with scheduler parameters, for the quanta and levels shown with --detailed
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+    },
+    /// Check that schedulers configured to behave alike, such as simplerr and mlrr with one level, log the same events on the built-in workloads and on random ones
+    Equivalence {
+        /// Number of random workloads to try besides the built-in ones; workload i uses seed SEED + i
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        runs: u64,
+        /// TOML file whose simplerr and simplemlf parameters the pairs are configured from
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+        /// Also write a pass/fail report of every check to FILE, in FORMAT junit (XML) or json
+        #[arg(long, num_args = 2, value_names = ["FORMAT", "FILE"])]
+        report: Vec<String>,
+        #[command(flatten)]
+        workload: GenArgs,
+    },
+    /// Run a canned experiment and chart its result
+    Experiment {
+        #[command(subcommand)]
+        experiment: Experiment,
+    },
+    /// Build a regression corpus of seeded workloads and reference results, or verify against one
+    Corpus {
+        #[command(subcommand)]
+        action: CorpusAction,
+    },
+    /// Score a scheduler against the reference results of a corpus and write the score for the autograder
+    Grade {
+        /// Scheduler to grade
+        #[arg(short, long)]
+        scheduler: String,
+        /// Directory of a corpus built from the reference solutions
+        #[arg(long, default_value = "corpus")]
+        corpus: PathBuf,
+        /// TOML file with the points of each check; 40 for the dispatch order, 30 for the metrics and 30 for the invariants by default
+        #[arg(long, value_name = "FILE")]
+        rubric: Option<PathBuf>,
+        /// Where to write the score as JSON in the autograder's upload format
+        #[arg(long, value_name = "FILE", default_value = "results.json")]
+        score: PathBuf,
+        /// Also write a pass/fail report of every check to FILE, in FORMAT junit (XML) or json
+        #[arg(long, num_args = 2, value_names = ["FORMAT", "FILE"])]
+        report: Vec<String>,
+        /// Fail a workload with a ResourceLimit once the scheduler has run on it for SECS seconds of wall-clock time
+        #[arg(long, value_name = "SECS", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        max_wall_clock: u64,
+        /// Fail a workload with a ResourceLimit once the run has more than MIB mebibytes of memory allocated
+        #[arg(long, value_name = "MIB", default_value_t = 1024, value_parser = clap::value_parser!(u64).range(1..))]
+        max_memory: u64,
+        /// Fail a workload with a ResourceLimit once the run emits more than N events, ticks of execution included
+        #[arg(long, value_name = "N", default_value_t = 10_000_000, value_parser = clap::value_parser!(u64).range(1..))]
+        max_events: u64,
+    },
+    /// Compare two JSON results files and report where the runs first diverge
+    Diff {
+        /// Results of the reference run
+        expected: String,
+        /// Results of the run being checked
+        actual: String,
+    },
+    /// Draw the Gantt charts of two JSON results files one above the other, on a shared time axis
+    Compare {
+        /// Results of the run drawn on top
+        first: String,
+        /// Results of the run drawn below
+        second: String,
+        /// Columns of the text chart; longer runs are scaled down to fit
+        #[arg(long, default_value_t = 100)]
+        width: usize,
+        /// Also write the charts as an SVG image to FILE
+        #[arg(long, value_name = "FILE")]
+        svg: Option<String>,
+    },
+    /// Run the scheduler again on the workload and parameters of a --bundle archive and report where the new run diverges from the bundled results
+    Replay {
+        /// Bundle written by --bundle
+        bundle: PathBuf,
+    },
+    /// Run the same scheduler on the same workload several times and check that every run logs the very same events
+    VerifyDeterminism {
+        /// Scheduler to check
+        #[arg(short, long)]
+        scheduler: String,
+        /// Input file, or `builtin:<name>` for a built-in example workload
+        #[arg(short, long)]
+        input: String,
+        /// Number of runs, each in a fresh process
+        #[arg(long, value_name = "N", default_value_t = 5, value_parser = clap::value_parser!(u64).range(2..))]
+        runs: u64,
+        /// TOML file with scheduler parameters, passed on to every run
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+    },
+    /// Run a scheduler for a very long simulated time with jobs arriving all along, and check that memory, queue length and dispatch latency stay flat
+    Soak {
+        /// Scheduler to soak, built-in or a plugin
+        #[arg(short, long)]
+        scheduler: String,
+        /// Simulated time to run for, in hours
+        #[arg(long, value_name = "HOURS", default_value_t = 24.0, value_parser = parse_positive)]
+        hours_simulated: f64,
+        /// Number of windows the run is measured in; the first is a warm-up and the second the reference the others are checked against
+        #[arg(long, value_name = "N", default_value_t = 24, value_parser = clap::value_parser!(u32).range(3..))]
+        windows: u32,
+        /// Fraction of the time the CPU is busy on average, more than 0 and at most 1
+        #[arg(long, value_name = "FRACTION", default_value_t = 0.7, value_parser = parse_share)]
+        load: f64,
+        /// Mean CPU burst of a job, in ticks
+        #[arg(long, value_name = "TICKS", default_value_t = 10.0, value_parser = parse_positive)]
+        mean_burst: f64,
+        /// Number of priorities jobs get at random, 0 being the highest
+        #[arg(long, value_name = "N", default_value_t = 4, value_parser = clap::value_parser!(u32).range(1..))]
+        priorities: u32,
+        /// Seed of the arrivals, bursts and priorities
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Length of one tick
+        #[arg(long, default_value = "ms")]
+        time_unit: TimeUnit,
+        /// Fail when a window has more than FACTOR times the reference's memory, jobs in the system or mean dispatch latency
+        #[arg(long, value_name = "FACTOR", default_value_t = 2.0, value_parser = parse_positive)]
+        tolerance: f64,
+        /// TOML file with scheduler parameters
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+    },
+    /// Work with the JSON results files of earlier runs
+    Results {
+        #[command(subcommand)]
+        action: ResultsAction,
+    },
+}
+
+///The actions of `scheduler results`
+#[derive(Subcommand, Debug)]
+enum ResultsAction {
+    /// List the runs whose JSON results are in DIR or its subdirectories, with their tags
+    Ls {
+        /// Directory to search for results files
+        dir: PathBuf,
+        /// Only list runs where KEY is VALUE: a --tag, scheduler, input, seed or a scheduler parameter such as quantum
+        #[arg(long = "where", value_name = "KEY=VALUE", num_args = 1.., value_parser = parse_tag)]
+        filters: Vec<(String, String)>,
+    },
+}
+
+///The experiments of `scheduler experiment`
+#[derive(Subcommand, Debug)]
+enum Experiment {
+    /// Sweep the round-robin quantum and chart mean turnaround against it, the classic U-shaped curve
+    RrQuantum {
+        /// Input file, or `builtin:<name>` for a built-in example workload
+        #[arg(short, long, default_value = "builtin:mixed")]
+        input: String,
+        /// Round-robin scheduler to sweep; its configuration must have a `quantum` option
+        #[arg(short, long, default_value = "simplerr")]
+        scheduler: String,
+        /// Quanta to run, comma separated
+        #[arg(long, value_delimiter = ',', default_values_t = experiment::DEFAULT_QUANTA, value_parser = clap::value_parser!(u32).range(1..))]
+        quanta: Vec<u32>,
+        /// Ticks every context switch is charged, since the simulator switches for free
+        #[arg(long, value_name = "TICKS", default_value_t = 1)]
+        switch_cost: u64,
+        /// Where to write the sweep as CSV
+        #[arg(long, value_name = "FILE", default_value = "rr-quantum.csv")]
+        csv: PathBuf,
+        /// Where to write the chart as SVG
+        #[arg(long, value_name = "FILE", default_value = "rr-quantum.svg")]
+        svg: PathBuf,
+    },
+    /// Run spn with more and more wrong burst estimates and chart how mean turnaround degrades
+    EstimateError {
+        /// Input file, or `builtin:<name>` for a built-in example workload
+        #[arg(short, long, default_value = "builtin:mixed")]
+        input: String,
+        /// Estimation errors to run, in percent of the true burst, comma separated
+        #[arg(long, value_delimiter = ',', default_values_t = experiment::DEFAULT_ERRORS)]
+        errors: Vec<u32>,
+        /// Runs per error, with seeds 0 to N - 1, averaged
+        #[arg(long, value_name = "N", default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        seeds: u64,
+        /// Where to write the sweep as CSV
+        #[arg(long, value_name = "FILE", default_value = "estimate-error.csv")]
+        csv: PathBuf,
+        /// Where to write the chart as SVG
+        #[arg(long, value_name = "FILE", default_value = "estimate-error.svg")]
+        svg: PathBuf,
+    },
+    /// Run an MLFQ once per set of feedback rules and compare how each set treats I/O-bound and CPU-bound jobs
+    MlfqRules {
+        /// Input file, or `builtin:<name>` for a built-in example workload
+        #[arg(short, long, default_value = "builtin:gaming")]
+        input: String,
+        /// MLFQ scheduler to run; its configuration must have a `rules` table
+        #[arg(short, long, default_value = "mlf")]
+        scheduler: String,
+        /// Rule sets to compare, comma separated, each its rules joined with `+`: expiry, allotment, boost=<ticks>, io or none
+        #[arg(long, value_delimiter = ',', default_values = experiment::DEFAULT_RULES)]
+        rules: Vec<MlfqRules>,
+        /// Where to write the comparison as CSV
+        #[arg(long, value_name = "FILE", default_value = "mlfq-rules.csv")]
+        csv: PathBuf,
+    },
+}
+
+///What `scheduler corpus` does
+#[derive(Subcommand, Debug)]
+enum CorpusAction {
+    /// Write the corpus workloads and every scheduler's results on them
+    Build {
+        /// Directory to write the corpus to
+        #[arg(long, default_value = "corpus")]
+        out: PathBuf,
+        /// Scheduler to record results of; repeat for several. Defaults to every built-in scheduler
+        #[arg(short, long = "scheduler")]
+        schedulers: Vec<String>,
+    },
+    /// Re-run every scheduler of the corpus on its workloads and diff the results against the stored ones
+    Verify {
+        /// Directory of the corpus
+        #[arg(long, default_value = "corpus")]
+        dir: PathBuf,
+        /// Scheduler to verify; repeat for several. Defaults to every scheduler in the corpus
+        #[arg(short, long = "scheduler")]
+        schedulers: Vec<String>,
+        /// Also write a pass/fail report of every check to FILE, in FORMAT junit (XML) or json
+        #[arg(long, num_args = 2, value_names = ["FORMAT", "FILE"])]
+        report: Vec<String>,
+    },
+}
+
+///The random workload options shared by `gen` and `batch`
+#[derive(clap::Args, Debug)]
+struct GenArgs {
+    /// Number of jobs
+    #[arg(long, default_value_t = 100)]
+    jobs: u32,
+    /// Distribution of the gaps between arrivals, e.g. poisson:0.1 or constant:5
+    #[arg(long, value_name = "DIST", default_value = "poisson:0.1")]
+    arrival: Distribution,
+    /// Distribution of CPU bursts, e.g. exponential:20 or pareto:1.5,4
+    #[arg(long, value_name = "DIST", default_value = "exponential:10")]
+    burst: Distribution,
+    /// Number of priority levels to spread the jobs over
+    #[arg(long, default_value_t = 1)]
+    priorities: u32,
+    /// A class of jobs to mix in, as name/share/arrival/burst[/priority], e.g.
+    /// batch/0.1/poisson:0.01/pareto:1.5,50/2; repeat for every class. Classes replace
+    /// --arrival, --burst and --priorities
+    #[arg(long = "class", value_name = "CLASS")]
+    classes: Vec<JobClass>,
+    /// Seed for the random number generator; the same seed gives the same workload
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+}
+
+impl GenArgs {
+    fn generator(&self, seed: u64) -> Generator {
+        Generator {
+            jobs: self.jobs,
+            arrival: self.arrival,
+            burst: self.burst,
+            priorities: self.priorities,
+            classes: self.classes.clone(),
+            seed,
+        }
+    }
+}
+
+impl Args {
+    fn scheduler(&self) -> &str {
+        self.scheduler.as_deref().unwrap_or_default()
+    }
+
+    fn input_file(&self) -> &str {
+        self.input_file.as_deref().unwrap_or_default()
+    }
+}
+
+///Output formats selectable with `--format`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+///Exit statuses for failures that scripts may want to tell apart, numbered
+///as in `sysexits.h`. Every other error exits with status 1.
+#[derive(Debug, Clone, Copy)]
+enum Exit {
+    ///The input file is missing or cannot be read
+    InputError = 66,
+    ///The scheduler panicked, with --catch-panics
+    SchedulerFault = 70,
+}
+
+impl Exit {
+    fn exit(self) -> ! {
+        std::process::exit(self as i32)
+    }
+}
+
+///Simple struct to track the input job information for the simulations
+#[derive(Debug, Clone, Copy)]
+struct Job {
+    id: u32,
+    time_inserted: u64,
+    burst: Burst,
+    priority: u32,
+    //ticks after its arrival the job is given up on
+    timeout: Option<u64>,
+}
+
+///Interactive single-stepping used by `--step`.
+///
+///After each simulated tick the simulator calls [`Stepper::pause`], which reads
+///commands from stdin until told to move on. Everything is written to stderr so
+///the normal trace on stdout stays diffable against the reference output.
+struct Stepper {
+    enabled: bool,
+    unit: TimeUnit,
+    arrivals: BTreeMap<u32, u64>,
+}
+
+impl Stepper {
+    fn new(args: &Args, jobs_by_id: &BTreeMap<u32, Job>) -> Self {
+        let arrivals = jobs_by_id.values().map(|job| (job.id, job.time_inserted)).collect();
+        Self { enabled: args.step, unit: args.time_unit, arrivals }
+    }
+
+    ///Waits for a command. `remaining` is the quantum left for the running
+    ///process, or `None` when it runs to completion.
+    fn pause(&mut self, sched: &dyn Schedule, running: &PCB, remaining: Option<u32>) {
+        if !self.enabled {
+            return;
+        }
+        loop {
+            eprint!(
+                "[t={}] step (enter=next, p=print queues, c=continue, q=quit)> ",
+                self.unit.format_ns(CLOCK.now_ns())
+            );
+            io::stderr().flush().ok();
+            let mut command = String::new();
+            if io::stdin().read_line(&mut command).unwrap_or(0) == 0 {
+                //stdin closed, nothing left to wait on
+                self.enabled = false;
+                return;
+            }
+            match command.trim() {
+                "" | "n" => return,
+                "p" => self.print_queues(sched, running, remaining),
+                "c" => {
+                    self.enabled = false;
+                    return;
+                }
+                "q" => std::process::exit(0),
+                other => eprintln!("Unknown command '{}'", other),
+            }
+        }
+    }
+
+    ///Pretty-prints the running process and every queue level of the scheduler.
+    fn print_queues(&self, sched: &dyn Schedule, running: &PCB, remaining: Option<u32>) {
+        eprintln!("--- run queue at t={} ---", self.unit.format_ns(CLOCK.now_ns()));
+        eprintln!(
+            "running  P{:<4} priority {:<3} quantum left {}",
+            running.id,
+            running.priority,
+            remaining.map_or("FCFS".to_string(), |left| left.to_string())
+        );
+        if let Some(next) = sched.peek() {
+            eprintln!("next     P{}", next.id);
+        }
+        eprintln!("ready    {} process(es)", sched.iter_ready().count());
+        for level in 0..sched.levels() {
+            let level_quantum = sched.level_quantum(level);
+            eprintln!("level {} (quantum {})", level, level_quantum);
+            let mut empty = true;
+            for pcb in sched.iter_level(level) {
+                empty = false;
+                let since = pcb
+                    .time_added
+                    .or_else(|| self.arrivals.get(&pcb.id).copied())
+                    .unwrap_or(0);
+                eprintln!(
+                    "  P{:<4} priority {:<3} quantum {:<5} waited {}",
+                    pcb.id,
+                    pcb.priority,
+                    level_quantum,
+                    self.unit.format_ns(CLOCK.now_ns().saturating_sub(since))
+                );
+            }
+            if empty {
+                eprintln!("  (empty)");
+            }
+        }
+    }
+}
+
+///What `--pace` and `--inject` add to a run: every tick is held back to the
+///wall clock, with keys to pause, step and change the pace when stdin is a
+///terminal, and the jobs and annotations written to the pipe join the run.
+struct Live {
+    pacer: Option<Pacer>,
+    controls: Option<Controls>,
+    fifo: Option<ControlFifo>,
+    unit: TimeUnit,
+    //the scheduler, when it runs on a loop without nice values and timeouts
+    classic: Option<String>,
+    //IDs taken, which an injected job may not reuse
+    used: BTreeSet<u32>,
+}
+
+///A line read from the `--inject` pipe, its times counted from the tick it
+///was read at
+enum Injected {
+    Annotation(Annotation),
+    Renice(Renice),
+    Job(input::JobSpec),
+}
+
+impl Live {
+    ///Sets up the run's pacing and pipe, if any. `classic` says whether the
+    ///run is on one of the loops of the assignment's schedulers.
+    fn new(args: &Args, jobs_by_id: &BTreeMap<u32, Job>, classic: bool) -> Self {
+        let fifo = args.inject.as_deref().map(|path| {
+            ControlFifo::open(path).unwrap_or_else(|err| {
+                eprintln!("Error: cannot open {}: {}", path.display(), err);
+                std::process::exit(1);
+            })
+        });
+        Self {
+            pacer: args.pace.map(Pacer::new),
+            controls: args.pace.and_then(|_| Controls::stdin()),
+            fifo,
+            unit: args.time_unit,
+            classic: classic.then(|| args.scheduler().to_string()),
+            used: jobs_by_id.keys().copied().collect(),
+        }
+    }
+
+    ///Waits for the wall clock to catch up with the tick just simulated, then
+    ///returns what was written to the pipe meanwhile. Lines that cannot join
+    ///the run are reported and left out.
+    fn tick(&mut self) -> Vec<Injected> {
+        match (&mut self.pacer, &mut self.controls) {
+            (Some(pacer), Some(controls)) => controls.wait(pacer),
+            (Some(pacer), None) => pacer.wait(),
+            _ => {}
+        }
+        let lines: Vec<String> = self.fifo.iter().flat_map(ControlFifo::lines).collect();
+        lines
+            .into_iter()
+            .filter_map(|line| {
+                self.parse(&line).map_err(|err| eprintln!("Error: cannot inject '{}': {}", line.trim(), err)).ok()
+            })
+            .collect()
+    }
+
+    ///Reads one line written to the pipe
+    fn parse(&mut self, line: &str) -> Result<Injected, String> {
+        let now = CLOCK.now_ticks();
+        if line.trim_start().starts_with("%annotate") {
+            let annotation = input::parse_annotation(line, self.unit)?;
+            return Ok(Injected::Annotation(Annotation { time: now + annotation.time, ..annotation }));
+        }
+        let unsupported = |what| match &self.classic {
+            Some(name) => Err(format!("{} has no {} on the loop its reference outputs were made with", name, what)),
+            None => Ok(()),
+        };
+        if line.trim_start().starts_with("%renice") {
+            unsupported("nice values")?;
+            let renice = input::parse_renice(line, self.unit)?;
+            return Ok(Injected::Renice(Renice { time: now + renice.time, ..renice }));
+        }
+        let spec = input::parse_line(line, self.unit, true)?;
+        if spec.nice != 0 {
+            unsupported("nice values")?;
+        }
+        if spec.timeout.is_some() {
+            unsupported("timeouts")?;
+        }
+        if !self.used.insert(spec.id) {
+            return Err(format!("P{} already exists", spec.id));
+        }
+        Ok(Injected::Job(input::JobSpec { arrival: now + spec.arrival, ..spec }))
+    }
+}
+
+///Hands what was read from the `--inject` pipe to a simulator with a loop of
+///its own: markers join `markers`, and jobs join `jobs_by_time` and
+///`priorities` at the next tick at the earliest, as the arrivals of this one
+///were handed over already. The jobs are returned for the loop to add to its
+///jobs by ID when it is not borrowing them.
+fn join(
+    injected: Vec<Injected>,
+    jobs_by_time: &mut BTreeMap<u64, Vec<u32>>,
+    priorities: &mut BTreeMap<u32, u32>,
+    markers: &mut BTreeMap<u64, Vec<String>>,
+    out: &mut Recorder,
+) -> Vec<Job> {
+    let nanos = CLOCK.tick().as_nanos() as u64;
+    let mut jobs = Vec::new();
+    for injected in injected {
+        match injected {
+            Injected::Annotation(annotation) => markers.entry(annotation.time).or_default().push(annotation.label),
+            //refused when it was read
+            Injected::Renice(_) => {}
+            Injected::Job(spec) => {
+                if let Some(class) = spec.class {
+                    out.note_class(spec.id, class);
+                }
+                let time_inserted = spec.arrival.max(CLOCK.now_ticks() + 1) * nanos;
+                jobs_by_time.entry(time_inserted).or_default().push(spec.id);
+                priorities.insert(spec.id, spec.priority);
+                jobs.push(Job { id: spec.id, time_inserted, burst: Burst::new(spec.burst), priority: spec.priority, timeout: None });
+            }
+        }
+    }
+    jobs
+}
+
+///The lookup tables the simulators work from: jobs keyed by ID, and job IDs
+///keyed by the clock time (in nanoseconds) they arrive at. Both are ordered
+///maps so that every run of the same input behaves identically. Closed-loop
+///workloads also carry their clients, which add jobs as others finish, and
+///jobs with a burst script their alternating CPU and I/O steps.
+struct Workload {
+    jobs_by_id: BTreeMap<u32, Job>,
+    jobs_by_time: BTreeMap<u64, Vec<u32>>,
+    clients: Option<Clients>,
+    scripts: BTreeMap<u32, Vec<Step>>,
+    //seed of the closed-loop think times, if any
+    seed: Option<u64>,
+    //when every job would finish under processor sharing, for open workloads
+    baseline: Option<Baseline>,
+    //class label of every job that has one
+    classes: BTreeMap<u32, String>,
+    //labeled markers to put in the trace and charts
+    annotations: Vec<Annotation>,
+    //starting nice values and renices, in time order
+    renices: Vec<Renice>,
+}
+
+impl Workload {
+    ///How many jobs the run will see, counting every closed-loop request
+    fn total_jobs(&self) -> usize {
+        match &self.clients {
+            Some(clients) => clients.pending.values().map(|(_, left)| *left as usize + 1).sum(),
+            None => self.jobs_by_id.len(),
+        }
+    }
+
+    ///Whether any job is given up on after a timeout
+    fn has_timeouts(&self) -> bool {
+        self.jobs_by_id.values().any(|job| job.timeout.is_some())
+    }
+}
+
+///The clients of a closed-loop workload. Every job belongs to a client; when
+///it finishes, the client thinks for a random time and then submits the same
+///job again under a fresh ID, until it has made all of its requests.
+struct Clients {
+    think: Distribution,
+    tick_ns: u64,
+    rng: Rng,
+    next_id: u32,
+    //the job each client submits, and how many requests it has left, keyed by its current job's ID
+    pending: BTreeMap<u32, (Job, u32)>,
+}
+
+impl Clients {
+    ///Returns the next job of the client whose job `id` just finished, or
+    ///`None` once that client is done. Think times are at least one tick.
+    fn resubmit(&mut self, id: u32) -> Option<Job> {
+        let (job, left) = self.pending.remove(&id)?;
+        if left == 0 {
+            return None;
+        }
+        let think = (self.think.sample(&mut self.rng).round() as u64).max(1);
+        let next = Job { id: self.next_id, time_inserted: CLOCK.now_ns() + think * self.tick_ns, ..job };
+        self.next_id += 1;
+        self.pending.insert(next.id, (job, left - 1));
+        Some(next)
+    }
+}
+
+///Parses the input file into a [`Workload`], exiting with an error message on
+///the first malformed line. Files ending in `.json` use the JSON format, and
+///files ending in `.csv` the CSV format. Jobs arriving at the same tick are
+///listed in the `--arrival-order`.
+fn load_jobs(lines: Vec<String>, args: &Args, with_priority: bool) -> Workload {
+    let parsed = parse_workload(args.input_file(), lines, args.time_unit, with_priority);
+    let nanos = args.time_unit.nanos();
+    let jobs_by_id: BTreeMap<u32, Job> = parsed
+        .jobs
+        .values()
+        .map(|spec| {
+            //a scripted job starts with its first CPU step
+            let time_to_run = match parsed.scripts.get(&spec.id).map(|script| script[0]) {
+                Some(Step::Cpu(ticks)) => ticks,
+                _ => spec.burst,
+            };
+            //the clock counts nanoseconds, so a late enough arrival in a coarse unit does not fit
+            let time_inserted = spec.arrival.checked_mul(nanos).unwrap_or_else(|| {
+                eprintln!("Invalid time_inserted on job {}: {}{} is past the end of the clock", spec.id, spec.arrival, args.time_unit.suffix());
+                std::process::exit(1);
+            });
+            let job = Job {
+                id: spec.id,
+                time_inserted,
+                burst: Burst::new(time_to_run),
+                priority: spec.priority,
+                timeout: spec.timeout,
+            };
+            (job.id, job)
+        })
+        .collect();
+    let clients = match parsed.mode {
+        Mode::Open => None,
+        Mode::Closed { think, rounds, seed } => Some(Clients {
+            think: Distribution::Exponential { mean: think as f64 },
+            tick_ns: nanos,
+            rng: Rng::new(seed),
+            next_id: jobs_by_id.keys().max().map_or(0, |id| id + 1),
+            pending: jobs_by_id.values().map(|job| (job.id, (*job, rounds - 1))).collect(),
+        }),
+    };
+    let baseline = Baseline::new(&parsed);
+    let classes = parsed.jobs.values().filter_map(|spec| Some((spec.id, spec.class.clone()?))).collect();
+    let renices = parsed.nice_changes();
+    let mut jobs_by_time: BTreeMap<u64, Vec<u32>> = parsed.arrivals.into_iter().map(|(tick, ids)| (tick * nanos, ids)).collect();
+    if args.arrival_order == ArrivalOrder::Id {
+        jobs_by_time.values_mut().for_each(|ids| ids.sort_unstable());
+    }
+    let seed = match parsed.mode {
+        Mode::Open => None,
+        Mode::Closed { seed, .. } => Some(seed),
+    };
+    Workload {
+        jobs_by_id,
+        jobs_by_time,
+        clients,
+        scripts: parsed.scripts,
+        seed,
+        baseline,
+        classes,
+        annotations: parsed.annotations,
+        renices,
+    }
+}
+
+///Whether the lines of the input file `name` have a priority column, which
+///JSON and CSV workloads always name if they have it
+fn priority_column(name: &str, lines: &[String]) -> bool {
+    let builtin = name.strip_prefix(workloads::PREFIX).and_then(workloads::get);
+    name.ends_with(".json") || name.ends_with(".csv") || builtin.is_some_and(|builtin| builtin.json) || input::has_priority_column(lines)
+}
+
+///Parses the lines of the input file `name` in the format its name tells,
+///exiting with an error message on the first malformed line
+fn parse_workload(name: &str, lines: Vec<String>, unit: TimeUnit, with_priority: bool) -> input::Workload {
+    let builtin = name.strip_prefix(workloads::PREFIX).and_then(workloads::get);
+    let parsed = if name.ends_with(".json") || builtin.is_some_and(|builtin| builtin.json) {
+        input::Workload::from_json(&lines.join("\n"), unit)
+    } else if name.ends_with(".csv") {
+        input::Workload::from_csv(&lines.join("\n"), unit)
+    } else {
+        input::Workload::parse(lines, unit, with_priority)
+    };
+    parsed.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+///The `--expiry-order` of a run, by default the one the scheduler's
+///reference traces were made with: the preempted process first for simplerr
+///and mlrr, and the arrivals first for every other scheduler
+fn expiry_order(args: &Args) -> ExpiryOrder {
+    args.expiry_order.unwrap_or(match args.scheduler() {
+        "simplerr" | "mlrr" => ExpiryOrder::PreemptedFirst,
+        _ => ExpiryOrder::ArrivalsFirst,
+    })
+}
+
+///Simulator for the MLF scheduler
+fn mlf(workload: Workload, args: &Args, config: &SchedulerConfig, out: &mut Recorder) -> Vec<TieBreak> {
+    let mut sched = StrictSchedule::new(MLFSchedule::with_config(config.mlf.clone()), args.strict);
+    let expiry = expiry_order(args);
+    //Initialize clock to 0
+    CLOCK.set_now(Duration::from_millis(0));
+    CLOCK.set_tick(args.time_unit.tick());
+    let Workload { mut jobs_by_id, mut jobs_by_time, annotations, .. } = workload;
+    let mut stepper = Stepper::new(args, &jobs_by_id);
+    let mut live = Live::new(args, &jobs_by_id, true);
+    //jobs injected while a process runs, added to jobs_by_id once it is off the CPU
+    let mut joined = Vec::new();
+    let mut markers = markers(annotations);
+    //priorities never change, so arrivals look them up here instead of cloning the jobs
+    let mut priorities: BTreeMap<u32, u32> = jobs_by_id.iter().map(|(id, job)| (*id, job.priority)).collect();
+    //RUN Simulation
+    let simulated = guarded(args, || {
+        while !jobs_by_id.is_empty() && !out.stopped() {
+            let mut current_time = CLOCK.now().as_nanos();
+            mark(&mut markers, out);
+            if let  Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
+                for job in jobs {
+                    let pcb = PCB { id: *job, priority: priorities[job], time_added:None, time_scheduled:None};
+                    out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+                    sched.add_process(pcb);
+                }
+            }
+            if !sched.has_process() {
+                idle(&jobs_by_id, &jobs_by_time, out);
+                let injected = join(live.tick(), &mut jobs_by_time, &mut priorities, &mut markers, out);
+                jobs_by_id.extend(injected.into_iter().map(|job| (job.id, job)));
+                continue;
+            }
+            while sched.has_process() && !out.stopped() {
+                if let (Some(process), slice) = sched.next_process() {
+                    //the level is the scheduler's, whatever the process is let run for
+                    let level = Quantum::from(slice);
+                    let quantum = if args.non_preemptive { Quantum::FCFS } else { level };
+                    out.emit(process.id, EventKind::Dispatch { quantum: quantum.raw() });
+                    note_level(out, sched.dispatched_level(), &config.mlf.quanta, process.id, level.raw());
+                    out.note_heap_size(sched.heap_size());
+                    if let Some(job) = jobs_by_id.get_mut(&process.id) {
+                        match quantum.limit() {
+                            None => { //FCFS
+                                job.burst.skip_first_tick();
+                                loop {
+                                    out.emit(process.id, EventKind::Execute);
+                                    CLOCK.advance(CLOCK.tick());
+                                    current_time = CLOCK.now().as_nanos();
+                                    mark(&mut markers, out);
+                                    if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
+                                        for j in jobs {
+                                            if let Some(&priority) = priorities.get(j){
+                                                let pcb = PCB { id: *j, priority, time_added:None, time_scheduled:None};
+                                                out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+                                                sched.add_process(pcb);
+                                            }
+                                        }
+                                    }
+                                    stepper.pause(&sched, &process, None);
+                                    joined.extend(join(live.tick(), &mut jobs_by_time, &mut priorities, &mut markers, out));
+                                    if job.burst.run() {
+                                        out.emit(process.id, EventKind::Finish);
+                                        jobs_by_id.remove(&process.id);
+                                        break;
+                                    }
+                                    if out.stopped() {
+                                        break;
+                                    }
+                                    let pi = process;
+                                    //a run without preemption does not ask
+                                    if !args.non_preemptive && sched.interrupt(pi, pi.priority){
+                                        out.emit(process.id, EventKind::Preempt);
+                                        job.burst.split();
+                                        break;
+                                    }
+                                }
+                            }
+                            Some(mut time) => {
+                                let mut interrupt = false;
+                                //the jobs arriving as the slice runs out are handed over after the process is added back
+                                let mut deferred;
+                                loop {
+                                    out.emit(process.id, EventKind::Execute);
+                                    CLOCK.advance(CLOCK.tick());
+                                    current_time = CLOCK.now().as_nanos();
+                                    mark(&mut markers, out);
+                                    deferred = expiry == ExpiryOrder::PreemptedFirst && time == 1 && job.burst.left() > 1;
+                                    if let Some(jobs) = jobs_by_time.get(&(current_time as u64)).filter(|_| !deferred) {
+                                        for j in jobs {
+                                            let pcb = PCB { id: *j, priority: priorities[j], time_added:None, time_scheduled:None};
+                                            out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+                                            sched.add_process(pcb);
+                                        }
+                                    }
+                                    time -= 1;
+                                    job.burst.run();
+                                    stepper.pause(&sched, &process, Some(time));
+                                    joined.extend(join(live.tick(), &mut jobs_by_time, &mut priorities, &mut markers, out));
+                                    if job.burst.is_done() || time == 0 || out.stopped() {
+                                        break;
+                                    }
+                                    let pi = process;
+                                    if sched.interrupt(pi, pi.priority){
+                                        out.emit(process.id, EventKind::Preempt);
+                                        job.burst.split();
+                                        interrupt = true;
+                                        break;
+                                    }
+                                }
+                                if !interrupt {
+                                    if  job.burst.is_done() {
+                                        out.emit(process.id, EventKind::Finish);
+                                        jobs_by_id.remove(&process.id);
+                                    }
+                                    else {
+                                        out.emit(process.id, EventKind::Preempt);
+                                        job.burst.split();
+                                        sched.add_process(process);
+                                    }
+                                }
+                                if let Some(jobs) = jobs_by_time.get(&(current_time as u64)).filter(|_| deferred) {
+                                    for j in jobs {
+                                        let pcb = PCB { id: *j, priority: priorities[j], time_added:None, time_scheduled:None};
+                                        out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+                                        sched.add_process(pcb);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    jobs_by_id.extend(joined.drain(..).map(|job| (job.id, job)));
+                }
+                else{
+                    println!("None Process, something went wrong in your code.");
+                    std::process::exit(1);
+                }
+            }
+        }
+    });
+    if let Err(panic) = simulated {
+        scheduler_fault(&Fault::new(panic, &sched, out));
+    }
+    sched.into_violations()
+}
+
+///Simulator for the Simple MLF scheduler that only promotes tasks
+fn simplemlf(workload: Workload, args: &Args, config: &SchedulerConfig, out: &mut Recorder) -> Vec<TieBreak> {
+    let mut sched = StrictSchedule::new(SimpleMLFSchedule::with_config(config.simplemlf.clone()), args.strict);
+    let expiry = expiry_order(args);
+    //Initialize clock to 0
+    CLOCK.set_now(Duration::from_millis(0));
+    CLOCK.set_tick(args.time_unit.tick());
+    let Workload { mut jobs_by_id, mut jobs_by_time, annotations, .. } = workload;
+    let mut stepper = Stepper::new(args, &jobs_by_id);
+    let mut live = Live::new(args, &jobs_by_id, true);
+    //jobs injected while a process runs, added to jobs_by_id once it is off the CPU
+    let mut joined = Vec::new();
+    let mut markers = markers(annotations);
+    //priorities never change, so arrivals look them up here instead of cloning the jobs
+    let mut priorities: BTreeMap<u32, u32> = jobs_by_id.iter().map(|(id, job)| (*id, job.priority)).collect();
+    //RUN Simulation
+    let simulated = guarded(args, || {
+        while !jobs_by_id.is_empty() && !out.stopped() {
+            let mut current_time = CLOCK.now().as_nanos();
+            mark(&mut markers, out);
+            if let  Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
+                for job in jobs {
+                    let pcb = PCB { id: *job, priority: priorities[job], time_added:None, time_scheduled:None};
+                    out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+                    sched.add_process(pcb);
+                }
+            }
+            if !sched.has_process() {
+                idle(&jobs_by_id, &jobs_by_time, out);
+                let injected = join(live.tick(), &mut jobs_by_time, &mut priorities, &mut markers, out);
+                jobs_by_id.extend(injected.into_iter().map(|job| (job.id, job)));
+                continue;
+            }
+            while sched.has_process() && !out.stopped() {
+                if let (Some(process), slice) = sched.next_process() {
+                    //the level is the scheduler's, whatever the process is let run for
+                    let level = Quantum::from(slice);
+                    let quantum = if args.non_preemptive { Quantum::FCFS } else { level };
+                    out.emit(process.id, EventKind::Dispatch { quantum: quantum.raw() });
+                    note_level(out, sched.dispatched_level(), &config.simplemlf.quanta, process.id, level.raw());
+                    out.note_heap_size(sched.heap_size());
+                    if let Some(job) = jobs_by_id.get_mut(&process.id) {
+                        match quantum.limit() {
+                            None => { //FCFS
+                                job.burst.skip_first_tick();
+                                loop {
+                                    out.emit(process.id, EventKind::Execute);
+                                    CLOCK.advance(CLOCK.tick());
+                                    current_time = CLOCK.now().as_nanos();
+                                    mark(&mut markers, out);
+                                    if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
+                                        for j in jobs {
+                                            if let Some(&priority) = priorities.get(j){
+                                                let pcb = PCB { id: *j, priority, time_added:None, time_scheduled:None};
+                                                out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+                                                sched.add_process(pcb);
+                                            }
+                                        }
+                                    }
+                                    stepper.pause(&sched, &process, None);
+                                    joined.extend(join(live.tick(), &mut jobs_by_time, &mut priorities, &mut markers, out));
+                                    if job.burst.run() {
+                                        out.emit(process.id, EventKind::Finish);
+                                        jobs_by_id.remove(&process.id);
+                                        break;
+                                    }
+                                    if out.stopped() {
+                                        break;
+                                    }
+                                }
+                            }
+                            Some(mut time) => {
+                                //the jobs arriving as the slice runs out are handed over after the process is added back
+                                let mut deferred;
+                                loop {
+                                    out.emit(process.id, EventKind::Execute);
+                                    CLOCK.advance(CLOCK.tick());
+                                    current_time = CLOCK.now().as_nanos();
+                                    mark(&mut markers, out);
+                                    deferred = expiry == ExpiryOrder::PreemptedFirst && time == 1 && job.burst.left() > 1;
+                                    if let Some(jobs) = jobs_by_time.get(&(current_time as u64)).filter(|_| !deferred) {
+                                        for j in jobs {
+                                            let pcb = PCB { id: *j, priority: priorities[j], time_added:None, time_scheduled:None};
+                                            out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+                                            sched.add_process(pcb);
+                                        }
+                                    }
+                                    time -= 1;
+                                    job.burst.run();
+                                    stepper.pause(&sched, &process, Some(time));
+                                    joined.extend(join(live.tick(), &mut jobs_by_time, &mut priorities, &mut markers, out));
+                                    if job.burst.is_done() || time == 0 || out.stopped() {
+                                        break;
+                                    }
+
+                                }
+                                if job.burst.is_done() {
+                                    out.emit(process.id, EventKind::Finish);
+                                    jobs_by_id.remove(&process.id);
+                                }
+                                else {
+                                    out.emit(process.id, EventKind::Preempt);
+                                    job.burst.split();
+                                    sched.add_process(process);
+                                }
+                                if let Some(jobs) = jobs_by_time.get(&(current_time as u64)).filter(|_| deferred) {
+                                    for j in jobs {
+                                        let pcb = PCB { id: *j, priority: priorities[j], time_added:None, time_scheduled:None};
+                                        out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+                                        sched.add_process(pcb);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    jobs_by_id.extend(joined.drain(..).map(|job| (job.id, job)));
+                }
+                else{
+                    println!("None Process, something went wrong in your code.");
+                    std::process::exit(1);
+                }
+            }
+        }
+    });
+    if let Err(panic) = simulated {
+        scheduler_fault(&Fault::new(panic, &sched, out));
+    }
+    sched.into_violations()
+}
+
+///Simulator for the MLRR scheduler
+fn mlrr(workload: Workload, args: &Args, config: &SchedulerConfig, out: &mut Recorder) -> Vec<TieBreak> {
+    let sched = MLRRSchedule::with_config(config.mlrr.clone());
+    classic(sched, Run::ByQuantum, &config.mlrr.quanta, true, workload, args, out)
+}
+
+///Simulator for the SimpleRR scheduler
+fn simplerr(workload: Workload, args: &Args, config: &SchedulerConfig, out: &mut Recorder) -> Vec<TieBreak> {
+    let sched = SimpleRRSchedule::with_config(config.simplerr.clone());
+    classic(sched, Run::ByQuantum, &[], false, workload, args, out)
+}
+
+///Simulator for the Simple FIFO scheduler
+fn simple(workload: Workload, args: &Args, out: &mut Recorder) -> Vec<TieBreak> {
+    classic(SimpleSchedule::new(), Run::ToCompletion, &[], false, workload, args, out)
+}
+
+///Runs `sched` on one of the classic loops the reference outputs in tests/
+///were made with, see [`scheduler::classic`]. Jobs arrive with their
+///priority only `with_priority`, and with priority 0 otherwise. `--step`,
+///`--pace` and `--inject` work as on the generic simulator, but an injected
+///line may not change nice values or set a timeout.
+fn classic<S: Schedule>(sched: S, run: Run, quanta: &[u32], with_priority: bool, workload: Workload, args: &Args, out: &mut Recorder) -> Vec<TieBreak> {
+    CLOCK.set_tick(args.time_unit.tick());
+    let nanos = args.time_unit.nanos();
+    let Workload { jobs_by_id, jobs_by_time, annotations, .. } = workload;
+    let mut stepper = Stepper::new(args, &jobs_by_id);
+    let mut live = Live::new(args, &jobs_by_id, true);
+    //jobs arriving together are handed over in the --arrival-order order, sorted when the workload was loaded
+    let jobs = jobs_by_time.values().flatten().map(|id| {
+        let job = &jobs_by_id[id];
+        let priority = if with_priority { job.priority } else { 0 };
+        simulation::Job { id: job.id, arrival: job.time_inserted / nanos, burst: job.burst.total(), priority, script: None, timeout: None }
+    });
+    let run = Classic::new(StrictSchedule::new(sched, args.strict), run);
+    let mut run = annotations.into_iter().fold(run, |run, annotation| run.annotate(annotation.time, annotation.label))
+        .jobs(jobs.collect::<Vec<_>>())
+        .quanta(quanta)
+        .expiry_order(expiry_order(args))
+        .on_tick(|ctx| {
+            if let Some(running) = ctx.running() {
+                stepper.pause(ctx.scheduler(), running, ctx.quantum_left());
+            }
+            for injected in live.tick() {
+                match injected {
+                    Injected::Annotation(annotation) => ctx.annotate(annotation.time, annotation.label),
+                    //refused when it was read
+                    Injected::Renice(_) => {}
+                    Injected::Job(spec) => {
+                        if let Some(class) = spec.class {
+                            ctx.recorder().note_class(spec.id, class);
+                        }
+                        let priority = if with_priority { spec.priority } else { 0 };
+                        ctx.submit(simulation::Job { id: spec.id, arrival: spec.arrival, burst: spec.burst, priority, script: None, timeout: None });
+                    }
+                }
+            }
+        });
+    if args.non_preemptive {
+        run = run.non_preemptive();
+    }
+    if args.catch_panics {
+        run = run.catch_panics();
+    }
+    match run.run(out) {
+        Ok(()) => {}
+        Err(SimulationError::Fault(fault)) => scheduler_fault(&fault),
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    }
+    run.into_scheduler().into_violations()
+}
+
+///Simulator for schedulers without a loop of their own, such as plugins.
+///
+///Runs the library's [`Simulation`]: an FCFS quantum runs the dispatched
+///process to completion; otherwise it runs for at most the quantum and is
+///then added back. Arrivals are handed to the scheduler at the tick they
+///happen, and when nothing is ready the idle task runs. A job with a burst
+///script blocks when one of its CPU steps is done, and is handed back to the
+///scheduler once its I/O step is over. `--step` pauses after every tick, and
+///closed-loop clients submit their next request when one finishes. `--pace`
+///holds every tick back to the wall clock, with keys to pause, step and
+///change the pace when stdin is a terminal, and `--inject` adds the jobs
+///and annotations written to its pipe, timed from the tick they are read at.
+///`quanta` are the scheduler's per-level time slices, used to tell which
+///level each dispatch came from when the scheduler does not report it; leave
+///it empty when they are not known. `engine` says how the engine keeps its
+///timers.
+fn generic<S: Schedule>(sched: S, quanta: &[u32], workload: Workload, args: &Args, engine: &EngineConfig, out: &mut Recorder) -> Vec<TieBreak> {
+    CLOCK.set_tick(args.time_unit.tick());
+    let Workload { jobs_by_id, jobs_by_time, clients, scripts, annotations, renices, .. } = workload;
+    let mut stepper = Stepper::new(args, &jobs_by_id);
+    let nanos = args.time_unit.nanos();
+    let job = |job: &Job, script: Option<Vec<Step>>| simulation::Job {
+        id: job.id,
+        arrival: job.time_inserted / nanos,
+        burst: job.burst.total(),
+        priority: job.priority,
+        script,
+        timeout: job.timeout,
+    };
+    //jobs arriving together are handed over in the --arrival-order order, sorted when the workload was loaded
+    let jobs: Vec<simulation::Job> =
+        jobs_by_time.values().flatten().map(|id| job(&jobs_by_id[id], scripts.get(id).cloned())).collect();
+    let bandwidth = args.cpu_max.iter().fold(Bandwidth::new(), |bandwidth, (class, max)| bandwidth.limit(class, *max));
+    CLOCK.set_now(Duration::ZERO);
+    let mut live = Live::new(args, &jobs_by_id, false);
+    let (clients, scripts) = (RefCell::new(clients), RefCell::new(scripts));
+    let next_request = |ctx: &mut simulation::Context, process: &PCB| {
+        let script = scripts.borrow_mut().remove(&process.id);
+        let Some(next) = clients.borrow_mut().as_mut().and_then(|clients| clients.resubmit(process.id)) else {
+            return;
+        };
+        //the client's next request belongs to the same class
+        if let Some(class) = ctx.recorder().metrics().process(process.id).and_then(|p| p.class.clone()) {
+            ctx.recorder().note_class(next.id, class);
+        }
+        if let Some(script) = &script {
+            scripts.borrow_mut().insert(next.id, script.clone());
+        }
+        ctx.submit(job(&next, script));
+    };
+    let builder = Simulation::builder(StrictSchedule::new(sched, args.strict));
+    let builder = annotations.into_iter().fold(builder, |builder, annotation| builder.annotate(annotation.time, annotation.label));
+    let builder = renices.into_iter().fold(builder, |builder, renice| builder.renice(renice.time, renice.id, renice.nice));
+    let builder = builder.jobs(jobs).quanta(quanta).bandwidth(bandwidth);
+    let builder = match args.hz {
+        Some(hz) => builder.hz(hz),
+        None => builder,
+    };
+    let builder = if args.tickless { builder.tickless() } else { builder };
+    let builder = if args.non_preemptive { builder.non_preemptive() } else { builder };
+    let builder = if args.catch_panics { builder.catch_panics() } else { builder };
+    let builder = builder.expiry_order(expiry_order(args)).arrival_order(args.arrival_order);
+    let builder = builder.timers(engine.timers);
+    let mut sim = builder
+        .on_tick(|ctx| {
+            if let Some(running) = ctx.running() {
+                stepper.pause(ctx.scheduler(), running, ctx.quantum_left());
+            }
+            for injected in live.tick() {
+                match injected {
+                    Injected::Annotation(annotation) => ctx.annotate(annotation.time, annotation.label),
+                    Injected::Renice(renice) => ctx.renice(renice.time, renice.id, renice.nice),
+                    Injected::Job(spec) => {
+                        if let Some(class) = spec.class {
+                            ctx.recorder().note_class(spec.id, class);
+                        }
+                        if spec.nice != 0 {
+                            ctx.renice(spec.arrival, spec.id, spec.nice);
+                        }
+                        ctx.submit(simulation::Job {
+                            id: spec.id,
+                            arrival: spec.arrival,
+                            burst: spec.burst,
+                            priority: spec.priority,
+                            script: None,
+                            timeout: spec.timeout,
+                        });
+                    }
+                }
+            }
+        })
+        .on_finish(|ctx, process| next_request(ctx, process))
+        //a client whose request was given up on goes on to its next one all the same
+        .on_timeout(|ctx, process| next_request(ctx, process))
+        .build();
+    match sim.run(out) {
+        Ok(()) => {}
+        Err(SimulationError::Fault(fault)) => scheduler_fault(&fault),
+        Err(err) => {
+            println!("{}", err);
+            std::process::exit(1);
+        }
+    }
+    if !args.cpu_max.is_empty() {
+        out.note_throttling(sim.bandwidth().stats());
+    }
+    if args.hz.is_some() || args.tickless {
+        out.note_timer(sim.timer());
+    }
+    sim.into_scheduler().into_violations()
+}
+
+///A fresh instance of the built-in scheduler `name`, or `None` if there is
+///none by that name. SPN needs the bursts of the jobs to estimate them.
+fn builtin(name: &str, schedulers: &SchedulerConfig, bursts: Option<&[(u32, u32)]>) -> Option<Box<dyn Schedule>> {
+    Some(match name {
+        "simple" => Box::new(SimpleSchedule::new()),
+        "simplerr" => Box::new(SimpleRRSchedule::with_config(schedulers.simplerr.clone())),
+        "mlrr" => Box::new(MLRRSchedule::with_config(schedulers.mlrr.clone())),
+        "simplemlf" => Box::new(SimpleMLFSchedule::with_config(schedulers.simplemlf.clone())),
+        "mlf" => Box::new(MLFSchedule::with_config(schedulers.mlf.clone())),
+        "bsd" => Box::new(BsdSchedule::with_config(schedulers.bsd.clone())),
+        "prio_fcfs" => Box::new(PrioFcfsSchedule::new()),
+        "stack" => Box::new(Stack::with_config(schedulers.stack.clone())),
+        "fgbg" => Box::new(FgBgSchedule::with_config(schedulers.fgbg.clone())),
+        "spn" => Box::new(SPN::with_error(bursts?.iter().copied(), &schedulers.spn)),
+        _ => return None,
+    })
+}
+
+///Runs `scheduler list-schedulers`: the name of every built-in scheduler,
+///or with `detailed` a table of what its [`Schedule::info`] says about it,
+///configured by `config`. A scheduler that does not describe itself is shown
+///with `-` throughout.
+fn list_schedulers(detailed: bool, config: Option<&str>) {
+    if !detailed {
+        for name in selfcheck::SCHEDULERS {
+            println!("{}", name);
+        }
+        return;
+    }
+    let schedulers = load_config(config);
+    let yes_no = |yes: bool| if yes { "yes" } else { "no" };
+    println!("{:<10} {:>10} {:>15} {:>6} {:>7} {:>9}", "scheduler", "preemptive", "starvation-free", "levels", "quantum", "dispatch");
+    for name in selfcheck::SCHEDULERS {
+        match builtin(name, &schedulers, Some(&[])).and_then(|sched| sched.info()) {
+            Some(info) => println!(
+                "{:<10} {:>10} {:>15} {:>6} {:>7} {:>9}",
+                name,
+                yes_no(info.is_preemptive),
+                yes_no(info.starvation_free),
+                info.levels,
+                info.default_quantum,
+                info.dispatch
+            ),
+            None => println!("{:<10} {:>10} {:>15} {:>6} {:>7} {:>9}", name, "-", "-", "-", "-", "-"),
+        }
+    }
+}
+
+///Runs `scheduler soak`, see [`scheduler::soak`]: prints each window as it
+///ends, then what grew, and exits with status 1 if anything did or the
+///scheduler misbehaved or panicked.
+fn soak(name: &str, run: &Soak, tolerance: f64, config: Option<&str>, time_unit: TimeUnit) {
+    let schedulers = load_config(config);
+    let mut sched = match (builtin(name, &schedulers, None), plugin::find(name)) {
+        (Some(sched), _) => sched,
+        (None, Some(path)) => Box::new(PluginSchedule::spawn(&path).unwrap_or_else(|err| {
+            eprintln!("Error: cannot start scheduler plugin '{}': {}", path.display(), err);
+            std::process::exit(1);
+        })),
+        (None, None) if name == "spn" => {
+            eprintln!("Error: spn needs the bursts of a workload and cannot be soaked");
+            std::process::exit(1);
+        }
+        (None, None) => {
+            eprintln!("Error: unknown scheduler '{}' (no built-in scheduler or scheduler-{} plugin)", name, name);
+            std::process::exit(1);
+        }
+    };
+    println!("soaking {} for {} ticks of {} in {} windows, load {}", name, run.horizon, time_unit, run.windows, run.load);
+    println!("{:>14} {:>10} {:>10} {:>10} {:>11} {:>10} {:>10} {:>12} {:>12}", "end", "arrived", "finished", "in system", "dispatches", "heap KiB", "mem MiB", "mean disp.", "max disp.");
+    let mut print = |window: &WindowStats| {
+        println!(
+            "{:>14} {:>10} {:>10} {:>10} {:>11} {:>10.1} {:>10.1} {:>12} {:>12}",
+            window.end,
+            window.arrived,
+            window.finished,
+            window.in_system,
+            window.dispatches,
+            window.heap_size as f64 / 1024.0,
+            window.memory as f64 / (1024.0 * 1024.0),
+            format!("{:?}", window.mean_latency),
+            format!("{:?}", window.max_latency),
+        );
+    };
+    let result = fault::catch(|| run.run(sched.as_mut(), &|| ALLOCATOR.used(), &mut print)).unwrap_or_else(|panic| {
+        let location = panic.location.map(|location| format!(" at {}", location)).unwrap_or_default();
+        eprintln!("Error: {} panicked at tick {}{}: {}", name, CLOCK.now_ticks(), location, panic.message);
+        std::process::exit(1);
+    });
+    let report = result.unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    });
+    let problems = report.problems(tolerance);
+    if problems.is_empty() {
+        println!("{} stayed flat over {} windows", name, report.windows.len());
+        return;
+    }
+    for problem in &problems {
+        println!("{}: {}", name, problem);
+    }
+    std::process::exit(1);
+}
+
+///Runs the workload on `--cpus` CPUs with a fresh instance of the scheduler
+///each, see [`scheduler::smp`]. Exits when the scheduler is not built in or
+///the workload needs the single-CPU simulator.
+fn smp(name: &str, schedulers: &SchedulerConfig, workload: Workload, args: &Args, out: &mut Recorder) -> SmpStats {
+    if workload.clients.is_some() || !workload.scripts.is_empty() || workload.has_timeouts() {
+        eprintln!("Error: --cpus does not simulate closed-loop clients, burst scripts or timeouts");
+        std::process::exit(1);
+    }
+    let bursts: Vec<(u32, u32)> = workload.jobs_by_id.values().map(|job| (job.id, job.burst.total())).collect();
+    let instance = |_| {
+        builtin(name, schedulers, Some(&bursts)).unwrap_or_else(|| {
+            eprintln!("Error: --cpus only runs built-in schedulers, not '{}'", name);
+            std::process::exit(1);
+        })
+    };
+    CLOCK.set_tick(args.time_unit.tick());
+    let nanos = args.time_unit.nanos();
+    let Workload { jobs_by_id, jobs_by_time, .. } = workload;
+    let jobs = jobs_by_time.values().flatten().map(|id| {
+        let job = &jobs_by_id[id];
+        simulation::Job { id: job.id, arrival: job.time_inserted / nanos, burst: job.burst.total(), priority: job.priority, script: None, timeout: None }
+    });
+    let cpus = args.cpus.unwrap_or(1) as usize;
+    let nodes = args.numa.unwrap_or(1) as usize;
+    if nodes > cpus {
+        eprintln!("Error: --numa {} needs at least as many --cpus", nodes);
+        std::process::exit(1);
+    }
+    let run = Smp::new((0..cpus).map(instance).collect())
+        .numa(nodes, args.numa_penalty)
+        .smt(args.smt.unwrap_or(1) as usize, args.smt_share)
+        .placement(args.placement)
+        .balance(args.balance)
+        .jobs(jobs.collect::<Vec<_>>())
+        .run(out);
+    run.unwrap_or_else(|err| {
+        println!("{}", err);
+        std::process::exit(1);
+    })
+}
+
+///Runs the workload with the `--agent` program deciding every dispatch, see
+///[`scheduler::cosim`]. Exits when the agent cannot be started, fails, or
+///the workload needs the generic simulator.
+fn agent(workload: Workload, args: &Args, out: &mut Recorder) {
+    if workload.clients.is_some() || !workload.scripts.is_empty() || workload.has_timeouts() {
+        eprintln!("Error: -s agent does not simulate closed-loop clients, burst scripts or timeouts");
+        std::process::exit(1);
+    }
+    let mut command = args.agent.as_deref().unwrap_or_default().split_whitespace();
+    let Some(program) = command.next() else {
+        eprintln!("Error: -s agent needs the agent's command, given with --agent");
+        std::process::exit(1);
+    };
+    let child = std::process::Command::new(program).args(command).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn();
+    let mut child = child.unwrap_or_else(|err| {
+        eprintln!("Error: cannot start agent '{}': {}", program, err);
+        std::process::exit(1);
+    });
+    CLOCK.set_tick(args.time_unit.tick());
+    let nanos = args.time_unit.nanos();
+    let Workload { jobs_by_id, jobs_by_time, .. } = workload;
+    let jobs = jobs_by_time.values().flatten().map(|id| {
+        let job = &jobs_by_id[id];
+        simulation::Job { id: job.id, arrival: job.time_inserted / nanos, burst: job.burst.total(), priority: job.priority, script: None, timeout: None }
+    });
+    let mut sim = CoSimulation::new(jobs.collect::<Vec<_>>());
+    let actions = io::BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let driven = cosim::drive(&mut sim, out, actions, child.stdin.take().expect("stdin is piped"));
+    //the agent may wait for more after the run is done
+    child.kill().ok();
+    child.wait().ok();
+    if let Err(err) = driven {
+        eprintln!("Error: agent '{}' failed: {}", program, err);
+        std::process::exit(1);
+    }
+}
+
+///Notes which queue level a dispatch came from, see [`simulation::dispatch_level`].
+fn note_level(out: &mut Recorder, reported: Option<usize>, quanta: &[u32], id: u32, quantum: u32) {
+    if let Some(level) = simulation::dispatch_level(reported, quanta, quantum) {
+        out.note_level(id, level as u32);
+    }
+}
+
+///The labels of `annotations`, keyed by their tick, for the simulators with
+///a loop of their own
+fn markers(annotations: Vec<Annotation>) -> BTreeMap<u64, Vec<String>> {
+    let mut markers: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+    for annotation in annotations {
+        markers.entry(annotation.time).or_default().push(annotation.label);
+    }
+    markers
+}
+
+///Makes the markers due by now. They only label the trace, so a run with
+///them is the same run as without.
+fn mark(markers: &mut BTreeMap<u64, Vec<String>>, out: &mut Recorder) {
+    while let Some(marker) = markers.first_entry().filter(|marker| *marker.key() <= CLOCK.now_ticks()) {
+        for label in marker.remove() {
+            out.annotate(label);
+        }
+    }
+}
+
+///Runs `simulate`, the loop of a simulator with a loop of its own. With
+///--catch-panics a panic in it is caught and returned instead of unwinding.
+fn guarded(args: &Args, simulate: impl FnOnce()) -> Result<(), fault::Panic> {
+    if args.catch_panics {
+        return fault::catch(simulate);
+    }
+    simulate();
+    Ok(())
+}
+
+///Prints `fault`, a panic of the scheduler caught with --catch-panics, after
+///the trace so far, and exits with status 70
+fn scheduler_fault(fault: &Fault) -> ! {
+    io::stdout().flush().ok();
+    eprint!("{}", fault);
+    Exit::SchedulerFault.exit()
+}
+
+///Runs the idle task for one tick because no process is ready. When no job
+///is left to arrive either, the remaining processes can never run, and the
+///run ends with an error instead of idling forever.
+fn idle(jobs_by_id: &BTreeMap<u32, Job>, jobs_by_time: &BTreeMap<u64, Vec<u32>>, out: &mut Recorder) {
+    if jobs_by_time.range(CLOCK.now_ns() + 1..).next().is_none() {
+        println!("{}", SimulationError::Stuck(jobs_by_id.keys().copied().collect()));
+        std::process::exit(1);
+    }
+    idle_tick(out);
+}
+
+///Runs the idle task for one tick
+fn idle_tick(out: &mut Recorder) {
+    out.emit(IDLE, EventKind::Idle);
+    CLOCK.advance(CLOCK.tick());
+}
+
+///Exits with an error message if the run's options cannot be combined
+fn check_options(args: &Args) {
+    //the reports written as text, which a JSON run has nowhere to put
+    let text_reports = [
+        ("--convoy", args.convoy.is_some()),
+        ("--gaming", args.gaming.is_some()),
+        ("--io-bound", args.io_bound.is_some()),
+        ("--wait-breakdown", args.wait_breakdown.is_some()),
+        ("--fair-share", args.fair_share),
+    ];
+    if args.format == Format::Json
+        && let Some((flag, _)) = text_reports.iter().find(|(_, set)| *set)
+    {
+        eprintln!("Error: {} writes a text report and cannot be combined with --format json", flag);
+        std::process::exit(1);
+    }
+    if let Some(hz) = args.hz {
+        let period = 1_000_000_000 / hz as u64;
+        if !1_000_000_000u64.is_multiple_of(hz as u64) || !period.is_multiple_of(args.time_unit.nanos()) {
+            eprintln!("Error: the period of a {} HZ timer is not a whole number of {} ticks", hz, args.time_unit);
+            std::process::exit(1);
+        }
+    }
+    //the options the agent scheduler has no use for, with the reason why
+    let agent_unsupported = [
+        ("--hz", args.hz.is_some(), "which is dispatched one tick at a time"),
+        ("--tickless", args.tickless, "which is dispatched one tick at a time"),
+        ("--expiry-order", args.expiry_order.is_some(), "which is dispatched one tick at a time"),
+        ("--non-preemptive", args.non_preemptive, "which is dispatched one tick at a time"),
+        ("--catch-panics", args.catch_panics, "whose decisions come from another program"),
+    ];
+    if args.scheduler() == "agent"
+        && let Some((flag, _, why)) = agent_unsupported.iter().find(|(_, set, _)| *set)
+    {
+        eprintln!("Error: {} is not supported by the agent scheduler, {}", flag, why);
+        std::process::exit(1);
+    }
+    //the timer of the Simulation engine, which the assignment's schedulers keep off to follow their reference outputs
+    let timer_options = [("--hz", args.hz.is_some()), ("--tickless", args.tickless)];
+    if matches!(args.scheduler(), "simple" | "simplerr" | "mlrr" | "simplemlf" | "mlf")
+        && let Some((flag, _)) = timer_options.iter().find(|(_, set)| *set)
+    {
+        eprintln!("Error: {} is not supported by {}, which runs on the loop its reference outputs were made with and has no scheduling timer", flag, args.scheduler());
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+    //Parse the inputs for which scheduler and which input file to use
+    let args = Args::parse();
+    match args.command {
+        Some(Command::Diff { ref expected, ref actual }) => {
+            diff_runs(expected, actual);
+            return;
+        }
+        Some(Command::Experiment { experiment: Experiment::RrQuantum { ref input, ref scheduler, ref quanta, switch_cost, ref csv, ref svg } }) => {
+            rr_quantum(input, scheduler, quanta, switch_cost, csv, svg);
+            return;
+        }
+        Some(Command::Experiment { experiment: Experiment::EstimateError { ref input, ref errors, seeds, ref csv, ref svg } }) => {
+            estimate_error(input, errors, seeds, csv, svg);
+            return;
+        }
+        Some(Command::Experiment { experiment: Experiment::MlfqRules { ref input, ref scheduler, ref rules, ref csv } }) => {
+            mlfq_rules(input, scheduler, rules, csv);
+            return;
+        }
+        Some(Command::Compare { ref first, ref second, width, ref svg }) => {
+            compare_runs(first, second, width, svg.as_deref());
+            return;
+        }
+        Some(Command::Gen { ref workload, adversarial: Some(target), quantum }) => {
+            match (Adversary { target, quantum, jobs: workload.jobs }).render() {
+                Ok(text) => print!("{}", text),
+                Err(err) => {
+                    eprintln!("Error: {}", err);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Gen { ref workload, .. }) => {
+            print!("{}", generate::to_text(&workload.generator(workload.seed).generate()));
+            return;
+        }
+        Some(Command::Transform { ref input, anonymize, scale, seed }) => {
+            let transform = match anonymize {
+                true => Transform { scale, ..Transform::anonymize(seed) },
+                false => Transform { scale, ..Transform::default() },
+            };
+            let lines = read_workload(input, &expand_home(input), TimeUnit::Ns);
+            let priority = priority_column(input, &lines);
+            let workload = transform.apply(&parse_workload(input, lines, TimeUnit::Ns, priority));
+            match transform::to_text(&workload, priority) {
+                Ok(text) => print!("{}", text),
+                Err(err) => {
+                    eprintln!("Error: cannot transform '{}': {}", input, err);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Inspect { ref input_file, time_unit, bins, ref workload }) => {
+            let workload = match input_file {
+                Some(input) => {
+                    let lines = read_workload(input, &expand_home(input), time_unit);
+                    let priority = priority_column(input, &lines);
+                    parse_workload(input, lines, time_unit, priority)
+                }
+                None => {
+                    let text = generate::to_text(&workload.generator(workload.seed).generate());
+                    parse_workload("", text.lines().map(String::from).collect(), time_unit, true)
+                }
+            };
+            print!("{}", Inspection::new(&workload, bins as usize).report(time_unit));
+            return;
+        }
+        Some(Command::Corpus { action: CorpusAction::Build { ref out, ref schedulers } }) => {
+            corpus_build(out, schedulers);
+            return;
+        }
+        Some(Command::Corpus { action: CorpusAction::Verify { ref dir, ref schedulers, ref report } }) => {
+            corpus_verify(dir, schedulers, report_target(report));
+            return;
+        }
+        Some(Command::Equivalence { runs, ref config, ref report, ref workload }) => {
+            equivalence(runs, config.as_deref(), report_target(report), workload);
+            return;
+        }
+        Some(Command::ListSchedulers { detailed, ref config }) => {
+            list_schedulers(detailed, config.as_deref());
+            return;
+        }
+        Some(Command::Selfcheck { ref schedulers, ref report }) => {
+            self_check(schedulers, report_target(report));
+            return;
+        }
+        Some(Command::Grade { ref scheduler, ref corpus, ref rubric, ref score, ref report, max_wall_clock, max_memory, max_events }) => {
+            let limits = Limits { wall_clock: Duration::from_secs(max_wall_clock), memory_mib: max_memory, events: max_events };
+            grade(scheduler, corpus, rubric.as_deref(), score, report_target(report), &limits);
+            return;
+        }
+        Some(Command::Batch { ref schedulers, runs, ref config, ref sweep_rates, ref csv, ref svg, ref workload }) => {
+            match sweep_rates.is_empty() {
+                true => batch(schedulers, runs, config.as_deref(), workload),
+                false => saturation(schedulers, runs, config.as_deref(), workload, sweep_rates, csv, svg.as_deref()),
+            }
+            return;
+        }
+        Some(Command::Replay { ref bundle }) => {
+            replay(bundle);
+            return;
+        }
+        Some(Command::VerifyDeterminism { ref scheduler, ref input, runs, ref config }) => {
+            verify_determinism(scheduler, input, runs, config.as_deref());
+            return;
+        }
+        Some(Command::Soak { ref scheduler, hours_simulated, windows, load, mean_burst, priorities, seed, time_unit, tolerance, ref config }) => {
+            CLOCK.set_tick(time_unit.tick());
+            let horizon = (hours_simulated * 3600.0 * 1e9 / time_unit.nanos() as f64) as u64;
+            let run = Soak { horizon, windows, load, mean_burst, priorities, seed };
+            soak(scheduler, &run, tolerance, config.as_deref(), time_unit);
+            return;
+        }
+        Some(Command::Results { action: ResultsAction::Ls { ref dir, ref filters } }) => {
+            results_ls(dir, filters);
+            return;
+        }
+        None => {}
+    }
+    if let Some(mib) = args.max_memory {
+        ALLOCATOR.cap(mib);
+    }
+    check_options(&args);
+    //Read all the lines from the input file
+    let lines = read_input(&args);
+    let config = RunConfig {
+        scheduler: args.scheduler().to_string(),
+        input_file: args.input_file().to_string(),
+        time_unit: args.time_unit,
+        scheduler_config: load_config(args.config.as_deref()),
+        measure: measure_window(&args),
+        stop: stop_conditions(&args),
+    };
+    let engine = &config.scheduler_config.engine;
+    if *engine != EngineConfig::default() && (args.cpus.is_some() || args.scheduler() == "agent") {
+        eprintln!("Error: engine.timers is only supported by the single-CPU simulator, not with --cpus or the agent scheduler");
+        std::process::exit(1);
+    }
+    if *engine != EngineConfig::default() && matches!(args.scheduler(), "simple" | "simplerr" | "mlrr" | "simplemlf" | "mlf") {
+        eprintln!("Error: engine.timers is not supported by {}, which runs on the loop its reference outputs were made with and keeps no timers", args.scheduler());
+        std::process::exit(1);
+    }
+    let with_priority = !matches!(args.scheduler(), "simple" | "simplerr");
+    let input = lines.join("\n");
+    let mut workload = load_jobs(lines, &args, with_priority);
+    let provenance = Provenance { tags: args.tags.iter().cloned().collect(), ..Provenance::new(&config, &input, workload.seed) };
+    let provenance_comment = provenance.to_csv_comment();
+    let output = match &args.output_file {
+        Some(path) => Output::file(Path::new(path)).unwrap_or_else(|err| {
+            eprintln!("Error: cannot create output file '{}': {}", path, err);
+            std::process::exit(1);
+        }),
+        None => Output::stdout(),
+    };
+    let mut out = Recorder::with_window(config.measure);
+    out.stop_when(config.stop);
+    if let Some(events) = args.max_events {
+        out.add_sink(Box::new(EventBudget::new(events)));
+    }
+    if (args.quiet || args.output_file.is_some()) && io::stdout().is_terminal() {
+        let total = workload.total_jobs();
+        out.add_sink(Box::new(ProgressSink::new(total, args.time_unit)));
+    }
+    let cpu_share = args.cpu_share.as_ref().map(|path| {
+        let mut file = Output::file(Path::new(path)).unwrap_or_else(|err| {
+            eprintln!("Error: cannot create CPU share file '{}': {}", path, err);
+            std::process::exit(1);
+        });
+        writeln!(file, "{}", provenance_comment).unwrap_or_else(|err| {
+            eprintln!("Error: cannot write CPU share file '{}': {}", path, err);
+            std::process::exit(1);
+        });
+        out.add_sink(Box::new(CpuShareSink::new(file.clone(), args.cpu_share_every)));
+        file
+    });
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &args.sqlite {
+        let sink = scheduler::sqlite::SqliteSink::open(path, &config)
+            .and_then(|sink| provenance.tags.iter().try_for_each(|(name, value)| sink.tag(name, value)).map(|_| sink))
+            .unwrap_or_else(|err| {
+                eprintln!("Error: cannot open SQLite database '{}': {}", path.display(), err);
+                std::process::exit(1);
+            });
+        out.add_sink(Box::new(sink));
+    }
+    let verbosity = Verbosity::from_flags(args.quiet, args.verbose);
+    match (args.format, args.flush_every) {
+        (Format::Text, _) if use_color(&args) => {
+            out.add_sink(Box::new(PrettySink { verbosity, out: output.clone() }));
+        }
+        (Format::Text, _) => {
+            let messages = args.locale.clone().unwrap_or_default();
+            out.add_sink(Box::new(TextSink { with_priority, verbosity, messages, out: output.clone() }));
+        }
+        (Format::Json, Some(every)) => {
+            out.add_sink(Box::new(JsonStreamSink::new(output.clone(), config.clone(), Some(provenance.clone()), every)));
+        }
+        (Format::Json, None) => {}
+    }
+    //Now determine what scheduler to run the inputs on
+    //Closed-loop clients, burst scripts, timeouts, nice values and CPU limits are only supported by the generic simulator
+    let needs_generic = workload.clients.is_some()
+        || !workload.scripts.is_empty()
+        || workload.has_timeouts()
+        || !workload.renices.is_empty()
+        || !args.cpu_max.is_empty();
+    //processor sharing is a single-CPU baseline
+    let baseline = workload.baseline.take().filter(|_| args.cpus.is_none());
+    for (id, class) in std::mem::take(&mut workload.classes) {
+        out.note_class(id, class);
+    }
+    let schedulers = &config.scheduler_config;
+    let mut smp_stats = None;
+    let violations = match args.scheduler() {
+        name if args.cpus.is_some() => {
+            smp_stats = Some(smp(name, schedulers, workload, &args, &mut out));
+            Vec::new()
+        }
+        "simple" if needs_generic => generic(SimpleSchedule::new(), &[], workload, &args, &schedulers.engine, &mut out),
+        "simplerr" if needs_generic => generic(SimpleRRSchedule::with_config(schedulers.simplerr.clone()), &[], workload, &args, &schedulers.engine, &mut out),
+        "mlrr" if needs_generic => generic(MLRRSchedule::with_config(schedulers.mlrr.clone()), &schedulers.mlrr.quanta, workload, &args, &schedulers.engine, &mut out),
+        "simplemlf" if needs_generic => generic(SimpleMLFSchedule::with_config(schedulers.simplemlf.clone()), &schedulers.simplemlf.quanta, workload, &args, &schedulers.engine, &mut out),
+        "mlf" if needs_generic => generic(MLFSchedule::with_config(schedulers.mlf.clone()), &schedulers.mlf.quanta, workload, &args, &schedulers.engine, &mut out),
+        "simple" => simple(workload, &args, &mut out),
+        "simplerr" => simplerr(workload, &args, schedulers, &mut out),
+        "mlrr" => mlrr(workload, &args, schedulers, &mut out),
+        "simplemlf"=> simplemlf(workload, &args, schedulers, &mut out),
+        "mlf"=> mlf(workload, &args, schedulers, &mut out),
+        "bsd" => generic(BsdSchedule::with_config(schedulers.bsd.clone()), &[], workload, &args, &schedulers.engine, &mut out),
+        "prio_fcfs" => generic(PrioFcfsSchedule::new(), &[], workload, &args, &schedulers.engine, &mut out),
+        "stack" => generic(Stack::with_config(schedulers.stack.clone()), &[], workload, &args, &schedulers.engine, &mut out),
+        "spn" => {
+            let bursts = workload.jobs_by_id.values().map(|job| (job.id, job.burst.total()));
+            let sched = SPN::with_error(bursts.collect::<Vec<_>>(), &schedulers.spn);
+            generic(sched, &[], workload, &args, &schedulers.engine, &mut out)
+        }
+        "agent" => {
+            agent(workload, &args, &mut out);
+            Vec::new()
+        }
+        "fgbg" => {
+            let quanta = [schedulers.fgbg.quantum, schedulers.fgbg.background_slice];
+            generic(FgBgSchedule::with_config(schedulers.fgbg.clone()), &quanta, workload, &args, &schedulers.engine, &mut out)
+        }
+        other => match plugin::find(other) {
+            Some(path) => {
+                //the plugin learns the tick length when it starts
+                CLOCK.set_tick(args.time_unit.tick());
+                let sched = PluginSchedule::spawn(&path).unwrap_or_else(|err| {
+                    eprintln!("Error: cannot start scheduler plugin '{}': {}", path.display(), err);
+                    std::process::exit(1);
+                });
+                generic(sched, &[], workload, &args, &schedulers.engine, &mut out)
+            }
+            None => {
+                eprintln!("Error: unknown scheduler '{}' (no built-in scheduler or scheduler-{} plugin)", other, other);
+                std::process::exit(1);
+            }
+        },
+    };
+    out.finish();
+    if let Some(reason) = out.stop_reason() {
+        eprintln!("Stopped early: {}", reason);
+    }
+    if let Some(path) = &args.bundle {
+        write_bundle(path, &args, &input, &config, &provenance, &out);
+    }
+    if let Some(path) = &args.report_html {
+        let results = Results { provenance: Some(provenance.clone()), ..Results::new(config.clone(), &out) };
+        let written = Output::file(path).and_then(|mut file| {
+            file.write_all(html::report(&results).as_bytes())?;
+            file.commit()
+        });
+        if let Err(err) = written {
+            eprintln!("Error: cannot write HTML report '{}': {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+    let mut writer = output.clone();
+    let written = if args.format == Format::Json && args.flush_every.is_none() {
+        let results = Results { provenance: Some(provenance), ..Results::new(config, &out) };
+        writeln!(writer, "{}", results.to_json())
+    } else if args.format == Format::Text && verbosity != Verbosity::Normal {
+        write!(writer, "{}", out.metrics().summary(args.time_unit)).and_then(|_| match &baseline {
+            Some(baseline) => write!(writer, "{}", baseline.compare(out.metrics(), args.time_unit)),
+            None => Ok(()),
+        })
+    } else {
+        Ok(())
+    };
+    let written = written.and_then(|_| match args.convoy {
+        Some(ratio) => write!(writer, "{}", analysis::convoys(out.events(), ratio).report(args.time_unit)),
+        None => Ok(()),
+    });
+    let written = written.and_then(|_| match args.gaming {
+        Some(margin) => write!(writer, "{}", analysis::gaming(out.events(), margin).report(args.time_unit)),
+        None => Ok(()),
+    });
+    let written = written.and_then(|_| match args.io_bound {
+        Some(percent) => write!(writer, "{}", analysis::boundness(&out.metrics().processes(), percent).report(args.time_unit)),
+        None => Ok(()),
+    });
+    let written = written.and_then(|_| match args.wait_breakdown {
+        Some(cost) => write!(writer, "{}", analysis::wait_breakdown(out.events(), cost).report(args.time_unit)),
+        None => Ok(()),
+    });
+    let written = written.and_then(|_| match args.fair_share {
+        true => write!(writer, "{}", analysis::fair_share(out.events()).report(args.time_unit)),
+        false => Ok(()),
+    });
+    let written = written.and_then(|_| match (&smp_stats, args.format) {
+        (Some(stats), Format::Text) => write!(writer, "{}", stats.report(args.time_unit)),
+        _ => Ok(()),
+    });
+    if let (Some(stats), Some(path)) = (&smp_stats, &args.imbalance) {
+        let written = Output::file(Path::new(path)).and_then(|mut file| {
+            file.write_all(format!("{}\n{}", provenance_comment, stats.to_csv()).as_bytes())?;
+            file.commit()
+        });
+        if let Err(err) = written {
+            eprintln!("Error: cannot write imbalance file '{}': {}", path, err);
+            std::process::exit(1);
+        }
+    }
+    let written = written.and_then(|_| cpu_share.map_or(Ok(()), |file| file.commit()));
+    if let Some(path) = &args.heatmap {
+        write_heatmap(path, &out, &provenance_comment, args.heatmap_bins as usize);
+    }
+    if let Err(err) = written.and_then(|_| output.commit()) {
+        eprintln!("Error: failed to write output: {}", err);
+        std::process::exit(1);
+    }
+    if !violations.is_empty() {
+        eprintln!("strict: {} dispatch(es) broke the tie-breaking order", violations.len());
+        std::process::exit(1);
+    }
+}
+
+
+///Writes the `--heatmap` of a finished run to `path`, as SVG or as CSV
+///headed by the run's provenance
+fn write_heatmap(path: &str, out: &Recorder, provenance: &str, bins: usize) {
+    let heatmap = Heatmap::new(&out.metrics().processes(), bins, bins);
+    let text = match Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg")) {
+        true => heatmap.svg(),
+        false => format!("{}\n{}", provenance, heatmap.to_csv()),
+    };
+    let written = Output::file(Path::new(path)).and_then(|mut file| {
+        file.write_all(text.as_bytes())?;
+        file.commit()
+    });
+    if let Err(err) = written {
+        eprintln!("Error: cannot write heatmap file '{}': {}", path, err);
+        std::process::exit(1);
+    }
+}
+
+///Writes the `--bundle` of a finished run to `path`, see [`scheduler::bundle`]
+fn write_bundle(path: &Path, args: &Args, input: &str, config: &RunConfig, provenance: &Provenance, out: &Recorder) {
+    let extension = match args.input_file().strip_prefix(workloads::PREFIX).and_then(workloads::get) {
+        Some(builtin) if builtin.json => "json",
+        Some(_) => "in",
+        None => {
+            let extension = Path::new(args.input_file()).extension().and_then(|ext| ext.to_str());
+            extension.filter(|ext| matches!(*ext, "json" | "csv")).unwrap_or("in")
+        }
+    };
+    let manifest = Manifest {
+        bundle_version: bundle::BUNDLE_VERSION,
+        workload: format!("workload.{}", extension),
+        command: std::env::args().collect(),
+        provenance: provenance.clone(),
+    };
+    let results = Results { provenance: Some(provenance.clone()), ..Results::new(config.clone(), out) };
+    let timeline = Timeline::from_events(config.scheduler.as_str(), out.events()).with_annotations(out.annotations());
+    let mut contents = Bundle::new();
+    contents.add(bundle::MANIFEST, serde_json::to_string_pretty(&manifest).expect("manifests are always serializable"));
+    contents.add(manifest.workload.as_str(), format!("{}\n", input));
+    contents.add(bundle::CONFIG, toml::to_string(&config.scheduler_config).expect("configs are always serializable"));
+    contents.add(bundle::RESULTS, format!("{}\n", results.to_json()));
+    contents.add(bundle::SUMMARY, out.metrics().summary(args.time_unit));
+    contents.add(bundle::GANTT, gantt::svg(&[timeline]));
+    let written = Output::file(path).and_then(|mut file| {
+        file.write_all(&contents.to_zip())?;
+        file.commit()
+    });
+    if let Err(err) = written {
+        eprintln!("Error: cannot write bundle '{}': {}", path.display(), err);
+        std::process::exit(1);
+    }
+}
+
+///Reads the time given to `--<flag>`, exiting with an error message if it is
+///not a time
+fn time_arg(args: &Args, flag: &str, value: &Option<String>) -> Option<u64> {
+    value.as_deref().map(|v| input::parse_time(v, args.time_unit)).transpose().unwrap_or_else(|err| {
+        eprintln!("Error: --{}: {}", flag, err);
+        std::process::exit(1);
+    })
+}
+
+///Reads the `--measure-from` and `--measure-until` times, exiting with an
+///error message if they leave an empty window
+fn measure_window(args: &Args) -> Window {
+    let window = Window {
+        from: time_arg(args, "measure-from", &args.measure_from).unwrap_or(0),
+        until: time_arg(args, "measure-until", &args.measure_until),
+    };
+    if window.until.is_some_and(|until| until <= window.from) {
+        eprintln!("Error: --measure-until must be later than --measure-from");
+        std::process::exit(1);
+    }
+    window
+}
+
+///Reads the `--stop-*` options
+fn stop_conditions(args: &Args) -> StopConditions {
+    StopConditions {
+        after_ticks: time_arg(args, "stop-after-ticks", &args.stop_after_ticks),
+        after_completions: args.stop_after_completions,
+        when_idle_for: time_arg(args, "stop-when-idle-for", &args.stop_when_idle_for),
+    }
+}
 
-    /// input file
-    #[arg(short, long)]
-    input_file: String,
+///Reads and validates the `--config` file, exiting with an error message if
+///it is unusable. Without one every scheduler gets its default configuration.
+fn load_config(path: Option<&str>) -> SchedulerConfig {
+    let Some(path) = path else {
+        return SchedulerConfig::default();
+    };
+    std::fs::read_to_string(expand_home(path))
+        .map_err(|err| err.to_string())
+        .and_then(|text| SchedulerConfig::from_toml(&text))
+        .unwrap_or_else(|err| {
+            eprintln!("Error: invalid config '{}': {}", path, err);
+            std::process::exit(1);
+        })
 }
 
-///Simple struct to track the input job information for the simulations
-#[derive(Debug, Clone, Copy)]
-struct Job {
-    id: u32,
-    time_inserted: u64,
-    time_to_run: u32,
-    priority: u32,
+///Reads a results file for `diff` or `compare`, exiting with status 2 if it
+///cannot be read
+fn load_results(path: &str) -> Results {
+    std::fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| Results::load(&text))
+        .unwrap_or_else(|err| {
+            eprintln!("Error: cannot read results '{}': {}", path, err);
+            std::process::exit(2);
+        })
 }
 
-///Simulator for the MLF scheduler
-fn mlf(lines: io::Lines<io::BufReader<File>>){
-    let mut sched = MLFSchedule::new();
-    //Initialize clock to 0
-    CLOCK.set_now(Duration::from_millis(0));
-    // HashMap keyed by ID
-    let mut jobs_by_id: HashMap<u32, Job> = HashMap::new();
-
-    // Optionally, a secondary index keyed by time_inserted
-    let mut jobs_by_time: HashMap<u64, Vec<u32>> = HashMap::new(); // time_inserted -> IDs
-
-    // Consumes the iterator, returns an (Optional) String
-    // Parses input file into two HashMaps to make manipulation easier
-    for line in lines.map_while(Result::ok) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let id: u32 = parts[0].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid ID on line: {}", line);
-            std::process::exit(1);
-        });
-        let time_inserted: u64 = parts[1].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_inserted on line: {}", line);
-            std::process::exit(1);
+///Runs `scheduler diff`, exiting with status 1 when the runs differ
+fn diff_runs(expected: &str, actual: &str) {
+    diff_runs_loaded(&load_results(expected), &load_results(actual));
+}
+
+///Prints the diff of two runs, exiting with status 1 when they differ
+fn diff_runs_loaded(expected: &Results, actual: &Results) {
+    let report = diff::diff(expected, actual);
+    print!("{}", report);
+    if !report.is_identical() {
+        std::process::exit(1);
+    }
+}
+
+///Runs `scheduler compare`, printing the text charts and writing the SVG
+fn compare_runs(first: &str, second: &str, width: usize, svg: Option<&str>) {
+    let runs = [load_results(first), load_results(second)];
+    if let [Some(a), Some(b)] = runs.each_ref().map(|run| run.provenance.as_ref())
+        && a.input_hash != b.input_hash
+    {
+        eprintln!("Warning: the runs used different workloads");
+    }
+    let timelines = [first, second]
+        .iter()
+        .zip(&runs)
+        .map(|(path, run)| {
+            Timeline::from_events(format!("{} ({})", run.config.scheduler, path), &run.events).with_annotations(&run.annotations)
+        })
+        .collect::<Vec<_>>();
+    print!("{}", gantt::ascii(&timelines, width));
+    if let Some(path) = svg {
+        let written = Output::file(Path::new(path)).and_then(|mut file| {
+            file.write_all(gantt::svg(&timelines).as_bytes())?;
+            file.commit()
         });
-        let time_to_run: u32 = parts[2].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_to_run on line: {}", line);
+        if let Err(err) = written {
+            eprintln!("Error: cannot write '{}': {}", path, err);
             std::process::exit(1);
-        });
+        }
+    }
+}
 
-        let priority: u32 = parts[3].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_to_run on line: {}", line);
-            std::process::exit(1);
-        });
+///Why a run in a child process produced no results
+enum ChildError {
+    ///The scheduler is still a stub and printed "Not Implemented"
+    NotImplemented,
+    ///The run failed, with its output
+    Failed(String),
+    ///The run hit a resource limit, as the child reported it
+    ResourceLimit(String),
+}
+
+impl std::fmt::Display for ChildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ChildError::NotImplemented => write!(f, "Not Implemented"),
+            ChildError::Failed(output) => write!(f, "{}", output.trim_end()),
+            ChildError::ResourceLimit(limit) => write!(f, "{}", limit),
+        }
+    }
+}
 
-        let job = Job { id, time_inserted, time_to_run, priority };
-        jobs_by_id.insert(id, job);
+///Runs `scheduler` on `input` in a child process of the simulator `exe` and
+///reads back its JSON results
+fn run_child(exe: &Path, scheduler: &str, input: &std::ffi::OsStr, config: Option<&str>) -> Result<Results, ChildError> {
+    run_child_with(exe, scheduler, input, config, &[])
+}
 
-        // build secondary index for time_inserted
-        jobs_by_time.entry(time_inserted).or_default().push(id);
+///Like [`run_child`], passing `extra` arguments on to the child
+fn run_child_with(exe: &Path, scheduler: &str, input: &std::ffi::OsStr, config: Option<&str>, extra: &[String]) -> Result<Results, ChildError> {
+    let mut command = std::process::Command::new(exe);
+    command.args(["-s", scheduler, "--format", "json", "-i"]).arg(input);
+    if let Some(config) = config {
+        command.args(["--config", config]);
     }
-    //RUN Simulation
-    while !jobs_by_id.is_empty() {
-        let mut current_time = CLOCK.now().as_nanos();
-        // println!("t = {} ", current_time);
-        if let  Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-            for job in jobs {
-                let jid = jobs_by_id.clone();
-                let pcb = PCB { id: *job, priority: jid.get(&job).unwrap().priority, time_added:None, time_scheduled:None};
-                println!("Scheduled Process: {:?}, Priority:{}", pcb.id, pcb.priority);
-                sched.add_process(pcb);
-            }
-        }
-        while sched.has_process(){
-            if let (Some(mut process), mut time) = sched.next_process() {
-                let priority = match time {
-                    0 => 0,
-                    4 => 1,
-                    1 => 2,
-                    _ => 3, // default or handle other cases as needed
-                };
-                // println!("{:?}",process);
-                let mut jid = jobs_by_id.clone();
-                if let Some(job) = jobs_by_id.get_mut(&process.id) {
-                    if time == 0 { //FCFS
-                        loop {
-                            println!("Process {} executed", process.id);
-                            CLOCK.advance(Duration::from_nanos(1));
-                            current_time = CLOCK.now().as_nanos();
-                            // if current_time >=1800 {
-                            //     println!("t = {} ", current_time);
-                            // }
-                            // println!("t = {} ", current_time);
-                            if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-                                // println!("Shouldn't be here");
-                                for j in jobs {
-                                    let jid2 = jid.clone();
-                                    if let Some(tmp_job) = jid2.get(&j){
-                                        let pcb = PCB { id: *j, priority: tmp_job.priority, time_added:None, time_scheduled:None};
-                                        println!("Scheduled Process: {:?}, Priority:{}", pcb.id, pcb.priority);
-                                        sched.add_process(pcb);
-                                    }
-                                }
-                            }
+    command.args(extra);
+    let output = command.output().map_err(|err| ChildError::Failed(err.to_string()))?;
+    child_results(output.status, &output.stdout, &output.stderr)
+}
 
-                            if job.time_to_run <= 0 {
-                                println!("Process {} Finished", process.id);
-                                jobs_by_id.remove(&process.id);
-                                break;
-                            }
-                            let pi = process.clone();
-                            if sched.interrupt(pi, pi.priority){
-                                break;
-                            }
-                            job.time_to_run -= 1;
-                        }
-                    }
-                    else {
-                        let mut interrupt = false;
-                        loop {
-                            println!("Process {} executed", process.id);
-                            CLOCK.advance(Duration::from_nanos(1));
-                            current_time = CLOCK.now().as_nanos();
-                            // if current_time >=1800 {
-                            //     println!("t = {} ", current_time);
-                            // }
-                            // println!("t = {} ", current_time);
-                            if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-                                // println!("Shouldn't be here");
-                                for j in jobs {
-                                    let jid2 = jid.clone();
-                                    let pcb = PCB { id: *j, priority: jid2.get(&j).unwrap().priority, time_added:None, time_scheduled:None};
-                                    println!("Scheduled Process: {:?}, Priority:{}", pcb.id, pcb.priority);
-                                    sched.add_process(pcb);
-                                }
-                            }
-                            time -= 1;
-                            job.time_to_run -= 1;
-                            if job.time_to_run <= 0 || time <= 0{
-                                break;
-                            }
-                            let pi = process.clone();
-                            if sched.interrupt(pi, pi.priority){
-                                interrupt = true;
-                                break;
-                            }
-                        }
-                        if !interrupt {
-                            if  job.time_to_run <= 0 {
-                                println!("Process {} Finished", process.id);
-                                jobs_by_id.remove(&process.id);
-                            }
-                            else {
-                                sched.add_process(process);
-                            }
-                        }
-                    }
-                }
-            }
-            else{
-                println!("None Process, something went wrong in your code.");
-                std::process::exit(1);
+///The options every run on a corpus workload is made with, whether it
+///records, verifies or grades a result, so all of them run the same loop
+const CORPUS_RUN: [&str; 1] = ["--catch-panics"];
+
+///Like [`run_child`], in a watchdog for grading: the child is given the
+///memory and event `limits` and the [`CORPUS_RUN`] options, and is killed
+///once it has run for longer than their wall-clock time
+fn run_child_limited(exe: &Path, scheduler: &str, input: &std::ffi::OsStr, limits: &Limits) -> Result<Results, ChildError> {
+    let mut command = std::process::Command::new(exe);
+    command.args(["-s", scheduler, "--format", "json", "-i"]).arg(input);
+    command.args(["--max-memory", &limits.memory_mib.to_string(), "--max-events", &limits.events.to_string()]).args(CORPUS_RUN);
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| ChildError::Failed(err.to_string()))?;
+    //drain the pipes while the child runs, so one with a lot to say is not stuck on a full pipe
+    fn drain(mut pipe: impl io::Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            pipe.read_to_end(&mut bytes).ok();
+            bytes
+        })
+    }
+    let (stdout, stderr) = (child.stdout.take().map(drain), child.stderr.take().map(drain));
+    let started = std::time::Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if started.elapsed() < limits.wall_clock => std::thread::sleep(Duration::from_millis(10)),
+            Ok(None) => {
+                child.kill().ok();
+                child.wait().ok();
+                return Err(ChildError::ResourceLimit(ResourceLimit::WallClock(limits.wall_clock).to_string()));
             }
+            Err(err) => return Err(ChildError::Failed(err.to_string())),
         }
+    };
+    let read = |pipe: Option<std::thread::JoinHandle<Vec<u8>>>| pipe.and_then(|pipe| pipe.join().ok()).unwrap_or_default();
+    let (stdout, stderr) = (read(stdout), read(stderr));
+    if status.code() == Some(limits::EXIT_CODE) {
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(ChildError::ResourceLimit(stderr.lines().next().unwrap_or("ResourceLimit").to_string()));
     }
+    child_results(status, &stdout, &stderr)
 }
 
-///Simulator for the Simple MLF scheduler that only promotes tasks
-fn simplemlf(lines: io::Lines<io::BufReader<File>>){
-    let mut sched = SimpleMLFSchedule::new();
-    //Initialize clock to 0
-    CLOCK.set_now(Duration::from_millis(0));
-    // HashMap keyed by ID
-    let mut jobs_by_id: HashMap<u32, Job> = HashMap::new();
-
-    // Optionally, a secondary index keyed by time_inserted
-    let mut jobs_by_time: HashMap<u64, Vec<u32>> = HashMap::new(); // time_inserted -> IDs
-
-    // Consumes the iterator, returns an (Optional) String
-    // Parses input file into two HashMaps to make manipulation easier
-    for line in lines.map_while(Result::ok) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let id: u32 = parts[0].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid ID on line: {}", line);
-            std::process::exit(1);
-        });
-        let time_inserted: u64 = parts[1].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_inserted on line: {}", line);
-            std::process::exit(1);
-        });
-        let time_to_run: u32 = parts[2].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_to_run on line: {}", line);
-            std::process::exit(1);
-        });
-
-        let priority: u32 = parts[3].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_to_run on line: {}", line);
-            std::process::exit(1);
-        });
+///Reads back the JSON results a child run wrote to `stdout`, or why it
+///failed
+fn child_results(status: std::process::ExitStatus, stdout: &[u8], stderr: &[u8]) -> Result<Results, ChildError> {
+    let stdout = String::from_utf8_lossy(stdout);
+    if !status.success() {
+        return Err(ChildError::Failed(format!("{}{}", stdout, String::from_utf8_lossy(stderr))));
+    }
+    if stdout.lines().next() == Some("Not Implemented") {
+        return Err(ChildError::NotImplemented);
+    }
+    Results::load(&stdout).map_err(|err| {
+        ChildError::Failed(format!("not a results document ({}): {}", err, stdout.lines().next().unwrap_or_default()))
+    })
+}
 
-        let job = Job { id, time_inserted, time_to_run, priority };
-        jobs_by_id.insert(id, job);
+///Runs `scheduler replay`: the bundled scheduler in a child process on the
+///bundled workload and parameters, with the bundled time unit, measurement
+///window and stop conditions, diffed against the bundled results. Exits
+///with status 1 when the runs differ, and 2 when the bundle is unusable.
+fn replay(path: &Path) {
+    let unusable = |err: String| -> ! {
+        eprintln!("Error: cannot read bundle '{}': {}", path.display(), err);
+        std::process::exit(2);
+    };
+    let contents = std::fs::read(path).map_err(|err| err.to_string()).and_then(|zip| Bundle::from_zip(&zip)).unwrap_or_else(|err| unusable(err));
+    let file = |name: &str| contents.text(name).ok_or_else(|| format!("it has no {}", name));
+    let manifest: Manifest = file(bundle::MANIFEST)
+        .and_then(|text| serde_json::from_str(text).map_err(|err| err.to_string()))
+        .unwrap_or_else(|err| unusable(err));
+    if manifest.bundle_version != bundle::BUNDLE_VERSION {
+        unusable(format!("unsupported bundle_version {} (expected {})", manifest.bundle_version, bundle::BUNDLE_VERSION));
+    }
+    let expected = file(bundle::RESULTS).and_then(Results::from_json).unwrap_or_else(|err| unusable(err));
+    let (workload, config) = match (file(&manifest.workload), file(bundle::CONFIG)) {
+        (Ok(workload), Ok(config)) => (workload, config),
+        (Err(err), _) | (_, Err(err)) => unusable(err),
+    };
+    if manifest.provenance.version != env!("CARGO_PKG_VERSION") {
+        eprintln!("Warning: the bundle was written by version {}, this is {}", manifest.provenance.version, env!("CARGO_PKG_VERSION"));
+    }
+    let dir = std::env::temp_dir().join(format!("scheduler-replay-{}", std::process::id()));
+    let (workload_path, config_path) = (dir.join(&manifest.workload), dir.join(bundle::CONFIG));
+    let written = std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&workload_path, workload))
+        .and_then(|_| std::fs::write(&config_path, config));
+    if let Err(err) = written {
+        eprintln!("Error: cannot write '{}': {}", dir.display(), err);
+        std::process::exit(1);
+    }
+    let run = &expected.config;
+    let mut extra = vec!["--time-unit".to_string(), run.time_unit.to_string()];
+    let times = [
+        ("--measure-from", Some(run.measure.from).filter(|&from| from > 0)),
+        ("--measure-until", run.measure.until),
+        ("--stop-after-ticks", run.stop.after_ticks),
+        ("--stop-after-completions", run.stop.after_completions.map(|n| n as u64)),
+        ("--stop-when-idle-for", run.stop.when_idle_for),
+    ];
+    for (flag, value) in times {
+        extra.extend(value.map(|value| [flag.to_string(), value.to_string()]).into_iter().flatten());
+    }
+    let actual = run_child_with(&current_exe(), &run.scheduler, workload_path.as_os_str(), config_path.to_str(), &extra);
+    let _ = std::fs::remove_dir_all(&dir);
+    let actual = actual.unwrap_or_else(|err| {
+        eprintln!("Error: {} failed on the bundled workload:\n{}", run.scheduler, err);
+        std::process::exit(1);
+    });
+    println!("replayed {} on {} from {}", run.scheduler, run.input_file, path.display());
+    diff_runs_loaded(&expected, &actual);
+}
 
-        // build secondary index for time_inserted
-        jobs_by_time.entry(time_inserted).or_default().push(id);
+///Runs `scheduler verify-determinism`: `scheduler` on `input` `runs` times,
+///each in a child process so that every run gets fresh hash seeds, and
+///byte-compares the JSON event log of every run against the first. Exits
+///with status 1 at the first run that differs, showing where it diverged.
+fn verify_determinism(scheduler: &str, input: &str, runs: u64, config: Option<&str>) {
+    let exe = current_exe();
+    let run = |n: u64| {
+        run_child(&exe, scheduler, input.as_ref(), config).unwrap_or_else(|err| {
+            match err {
+                ChildError::NotImplemented => eprintln!("Error: {} is not implemented yet", scheduler),
+                err => eprintln!("Error: {} failed in run {}:\n{}", scheduler, n, err),
+            }
+            std::process::exit(1);
+        })
+    };
+    let log = |results: &Results| serde_json::to_string(&results.events).expect("events are always serializable");
+    let first = run(1);
+    let expected = log(&first);
+    for n in 2..=runs {
+        let results = run(n);
+        if log(&results) != expected {
+            println!("{} on {} is not deterministic: run {} differs from run 1", scheduler, input, n);
+            print!("{}", diff::diff(&first, &results));
+            std::process::exit(1);
+        }
     }
-    //RUN Simulation
-    while !jobs_by_id.is_empty() {
-        let mut current_time = CLOCK.now().as_nanos();
-        // println!("t = {} ", current_time);
-        if let  Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-            for job in jobs {
-                let jid = jobs_by_id.clone();
-                let pcb = PCB { id: *job, priority: jid.get(&job).unwrap().priority, time_added:None, time_scheduled:None};
-                println!("Scheduled Process: {:?}, Priority:{}", pcb.id, pcb.priority);
-                sched.add_process(pcb);
-            }
-        }
-        while sched.has_process(){
-            if let (Some(process),mut time) = sched.next_process() {
-                // println!("{:?}",process);
-                let mut jid = jobs_by_id.clone();
-                if let Some(job) = jobs_by_id.get_mut(&process.id) {
-                    if time == 0 { //FCFS
-                        loop {
-                            println!("Process {} executed", process.id);
-                            CLOCK.advance(Duration::from_nanos(1));
-                            current_time = CLOCK.now().as_nanos();
-                            // if current_time >=1800 {
-                            //     println!("t = {} ", current_time);
-                            // }
-                            // println!("t = {} ", current_time);
-                            if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-                                // println!("Shouldn't be here");
-                                for j in jobs {
-                                    let jid2 = jid.clone();
-                                    if let Some(tmp_job) = jid2.get(&j){
-                                        let pcb = PCB { id: *j, priority: tmp_job.priority, time_added:None, time_scheduled:None};
-                                        println!("Scheduled Process: {:?}, Priority:{}", pcb.id, pcb.priority);
-                                        sched.add_process(pcb);
-                                    }
-                                }
-                            }
-                            if job.time_to_run <= 0 {
-                                println!("Process {} Finished", process.id);
-                                jobs_by_id.remove(&process.id);
-                                break;
-                            }
-                            job.time_to_run -= 1;
+    println!("{} on {} is deterministic: {} runs logged the same {} events", scheduler, input, runs, first.events.len());
+}
 
+///Runs `scheduler selfcheck`: every scheduler on every built-in workload,
+///with the results of each run checked by [`selfcheck::check`]. Exits with
+///status 1 if any scheduler failed; stubs are reported but do not fail.
+fn self_check(schedulers: &[String], report: Option<(ReportFormat, PathBuf)>) {
+    let exe = current_exe();
+    let mut cases = TestReport::new("selfcheck");
+    let schedulers: Vec<&str> = match schedulers.is_empty() {
+        true => selfcheck::SCHEDULERS.to_vec(),
+        false => schedulers.iter().map(String::as_str).collect(),
+    };
+    let width = schedulers.iter().map(|name| name.len()).max().unwrap_or(0);
+    let mut failed = 0;
+    for scheduler in schedulers {
+        let mut problems = Vec::new();
+        let mut implemented = true;
+        for builtin in workloads::ALL {
+            let workload = match builtin.json {
+                true => input::Workload::from_json(builtin.text, TimeUnit::Ns),
+                false => input::Workload::parse(builtin.text.lines(), TimeUnit::Ns, true),
+            }
+            .expect("built-in workloads parse");
+            let name = format!("{}{}", workloads::PREFIX, builtin.name);
+            let found = problems.len();
+            match run_child(&exe, scheduler, name.as_ref(), None) {
+                Ok(results) => {
+                    problems.extend(selfcheck::check(&workload, &results).into_iter().map(|problem| (builtin.name, problem)));
+                }
+                Err(ChildError::NotImplemented) => {
+                    implemented = false;
+                    cases.push(TestCase::skip(scheduler, "all workloads", "not implemented"));
+                    break;
+                }
+                Err(err) => problems.push((builtin.name, err.to_string())),
+            }
+            let found: Vec<&str> = problems[found..].iter().map(|(_, problem)| problem.as_str()).collect();
+            cases.push(match found.is_empty() {
+                true => TestCase::pass(scheduler, builtin.name),
+                false => TestCase::fail(scheduler, builtin.name, found.join("\n")),
+            });
+        }
+        match (implemented, problems.is_empty()) {
+            (false, _) => println!("{:<width$} not implemented", scheduler),
+            (true, true) => println!("{:<width$} pass ({} workloads)", scheduler, workloads::ALL.len()),
+            (true, false) => {
+                failed += 1;
+                println!("{:<width$} FAIL", scheduler);
+                for (workload, problem) in problems {
+                    for (n, line) in problem.lines().enumerate() {
+                        match n {
+                            0 => println!("  {}: {}", workload, line),
+                            _ => println!("    {}", line),
                         }
                     }
-                    else {
-                        loop {
-                            println!("Process {} executed", process.id);
-                            CLOCK.advance(Duration::from_nanos(1));
-                            current_time = CLOCK.now().as_nanos();
-                            // if current_time >=1800 {
-                            //     println!("t = {} ", current_time);
-                            // }
-                            // println!("t = {} ", current_time);
-                            if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-                                // println!("Shouldn't be here");
-                                for j in jobs {
-                                    let jid2 = jid.clone();
-                                    let pcb = PCB { id: *j, priority: jid2.get(&j).unwrap().priority, time_added:None, time_scheduled:None};
-                                    println!("Scheduled Process: {:?}, Priority:{}", pcb.id, pcb.priority);
-                                    sched.add_process(pcb);
-                                }
-                            }
-                            time -= 1;
-                            job.time_to_run -= 1;
-                            if job.time_to_run <= 0 || time <= 0{
-                                break;
-                            }
+                }
+            }
+        }
+    }
+    write_report(report, &cases);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
 
+///Runs `scheduler equivalence`: both schedulers of every pair of
+///[`equivalence::equivalences`] on every built-in workload and `runs`
+///generated ones, each in a child process, comparing their event logs. Exits
+///with status 1 if any pair logged different events; pairs with a stub are
+///reported but do not fail.
+fn equivalence(runs: u64, config: Option<&str>, report: Option<(ReportFormat, PathBuf)>, workload: &GenArgs) {
+    let exe = current_exe();
+    let base = load_config(config);
+    let config = std::env::temp_dir().join(format!("scheduler-equivalence-{}.toml", std::process::id()));
+    let generated = std::env::temp_dir().join(format!("scheduler-equivalence-{}.in", std::process::id()));
+    let cleanup = || {
+        let _ = std::fs::remove_file(&config);
+        let _ = std::fs::remove_file(&generated);
+    };
+    let mut inputs: Vec<(String, Option<u64>)> = workloads::ALL.iter().map(|builtin| (format!("{}{}", workloads::PREFIX, builtin.name), None)).collect();
+    inputs.extend((0..runs).map(|run| (format!("seed {}", workload.seed + run), Some(workload.seed + run))));
+    let mut cases = TestReport::new("equivalence");
+    let mut failed = 0;
+    for pair in equivalence::equivalences(&base) {
+        let name = pair.to_string();
+        if let Err(err) = std::fs::write(&config, toml::to_string(&pair.config).expect("configs are always serializable")) {
+            cleanup();
+            eprintln!("Error: cannot write '{}': {}", config.display(), err);
+            std::process::exit(1);
+        }
+        let mut problems = Vec::new();
+        let mut implemented = true;
+        for (input, seed) in &inputs {
+            let path = match seed {
+                Some(seed) => {
+                    if let Err(err) = std::fs::write(&generated, generate::to_text(&workload.generator(*seed).generate())) {
+                        cleanup();
+                        eprintln!("Error: cannot write '{}': {}", generated.display(), err);
+                        std::process::exit(1);
+                    }
+                    generated.as_os_str()
+                }
+                None => input.as_ref(),
+            };
+            let left = run_child(&exe, pair.left, path, config.to_str());
+            let right = run_child(&exe, pair.right, path, config.to_str());
+            let problem = match (left, right) {
+                (Err(ChildError::NotImplemented), _) | (_, Err(ChildError::NotImplemented)) => {
+                    implemented = false;
+                    cases.push(TestCase::skip(&name, "all workloads", "not implemented"));
+                    break;
+                }
+                (Err(err), _) => Some(format!("{} failed: {}", pair.left, err)),
+                (_, Err(err)) => Some(format!("{} failed: {}", pair.right, err)),
+                (Ok(left), Ok(right)) => equivalence::compare(&left, &right).map(|d| {
+                    let show = |event: Option<Event>| event.map_or("(no more events)".to_string(), |e| e.to_string());
+                    let tick = d.expected.or(d.actual).map_or(0, |e| e.time);
+                    let width = pair.left.len().max(pair.right.len()) + 1;
+                    format!(
+                        "first difference at event #{} (tick {}):\n  {:<width$} {}\n  {:<width$} {}",
+                        d.index,
+                        tick,
+                        format!("{}:", pair.left),
+                        show(d.expected),
+                        format!("{}:", pair.right),
+                        show(d.actual)
+                    )
+                }),
+            };
+            cases.push(match &problem {
+                None => TestCase::pass(&name, input),
+                Some(problem) => TestCase::fail(&name, input, problem.clone()),
+            });
+            problems.extend(problem.map(|problem| (input, problem)));
+        }
+        match (implemented, problems.is_empty()) {
+            (false, _) => println!("{}: not implemented", name),
+            (true, true) => println!("{}: pass ({} workloads)", name, inputs.len()),
+            (true, false) => {
+                failed += 1;
+                println!("{}: FAIL on {} of {} workloads", name, problems.len(), inputs.len());
+                //the first workload shows where they part, the others only which they were
+                for (n, (input, problem)) in problems.iter().enumerate() {
+                    match n {
+                        0 => {
+                            println!("  {}:", input);
+                            for line in problem.lines() {
+                                println!("    {}", line);
+                            }
                         }
-                        if job.time_to_run <= 0 {
-                            println!("Process {} Finished", process.id);
-                            jobs_by_id.remove(&process.id);
-                        }
-                        else {
-                            sched.add_process(process);
-                        }
+                        _ => println!("  {}", input),
                     }
                 }
             }
-            else{
-                println!("None Process, something went wrong in your code.");
-                std::process::exit(1);
-            }
         }
     }
+    cleanup();
+    write_report(report, &cases);
+    if failed > 0 {
+        std::process::exit(1);
+    }
 }
 
-///Simulator for the MLRR scheduler
-fn mlrr(lines: io::Lines<io::BufReader<File>>){
-    let mut sched = MLRRSchedule::new();
-    //Initialize clock to 0
-    CLOCK.set_now(Duration::from_millis(0));
-    // HashMap keyed by ID
-    let mut jobs_by_id: HashMap<u32, Job> = HashMap::new();
-
-    // Optionally, a secondary index keyed by time_inserted
-    let mut jobs_by_time: HashMap<u64, Vec<u32>> = HashMap::new(); // time_inserted -> IDs
-
-    // Consumes the iterator, returns an (Optional) String
-    // Parses input file into two HashMaps to make manipulation easier
-    for line in lines.map_while(Result::ok) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let id: u32 = parts[0].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid ID on line: {}", line);
+///Runs `scheduler experiment rr-quantum`: `scheduler` on `input` once per
+///quantum, each in a child process with a configuration setting the quantum,
+///then writes the sweep as CSV and SVG and prints it with its knee
+fn rr_quantum(input: &str, scheduler: &str, quanta: &[u32], switch_cost: u64, csv: &Path, svg: &Path) {
+    let exe = current_exe();
+    let config = std::env::temp_dir().join(format!("scheduler-rr-quantum-{}.toml", std::process::id()));
+    let mut points = Vec::new();
+    for &quantum in quanta {
+        if let Err(err) = std::fs::write(&config, format!("[{}]\nquantum = {}\n", scheduler, quantum)) {
+            eprintln!("Error: cannot write '{}': {}", config.display(), err);
             std::process::exit(1);
-        });
-        let time_inserted: u64 = parts[1].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_inserted on line: {}", line);
+        }
+        let results = run_child(&exe, scheduler, input.as_ref(), config.to_str()).unwrap_or_else(|err| {
+            let _ = std::fs::remove_file(&config);
+            match err {
+                ChildError::NotImplemented => eprintln!("Error: {} is not implemented yet", scheduler),
+                err => eprintln!("Error: {} failed with quantum {}:\n{}", scheduler, quantum, err),
+            }
             std::process::exit(1);
         });
-        let time_to_run: u32 = parts[2].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_to_run on line: {}", line);
-            std::process::exit(1);
+        let dispatches = results.events.iter().filter(|event| matches!(event.kind, EventKind::Dispatch { .. })).count();
+        let mean_turnaround = experiment::charged_turnaround(&results.processes, &results.events, switch_cost);
+        points.push(QuantumPoint { quantum, mean_turnaround, dispatches });
+    }
+    let _ = std::fs::remove_file(&config);
+    for (path, text) in [(csv, experiment::to_csv(&points)), (svg, experiment::svg(&points))] {
+        let written = Output::file(path).and_then(|mut file| {
+            file.write_all(text.as_bytes())?;
+            file.commit()
         });
-        let priority: u32 = parts[3].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_to_run on line: {}", line);
+        if let Err(err) = written {
+            eprintln!("Error: cannot write '{}': {}", path.display(), err);
             std::process::exit(1);
-        });
-
-        let job = Job { id, time_inserted, time_to_run, priority };
-        jobs_by_id.insert(id, job);
-
-        // build secondary index for time_inserted
-        jobs_by_time.entry(time_inserted).or_default().push(id);
+        }
     }
+    println!("{} on {}, every context switch costing {} tick{}", scheduler, input, switch_cost, if switch_cost == 1 { "" } else { "s" });
+    println!("{:>8} {:>16} {:>10}", "quantum", "mean turnaround", "dispatches");
+    for point in &points {
+        println!("{:>8} {:>16.2} {:>10}", point.quantum, point.mean_turnaround, point.dispatches);
+    }
+    if let Some(knee) = experiment::knee(&points) {
+        println!("knee: quantum {} (mean turnaround {:.2})", knee.quantum, knee.mean_turnaround);
+    }
+    println!("wrote {} and {}", csv.display(), svg.display());
+}
 
-    //RUN Simulation
-    while !jobs_by_id.is_empty() {
-        let mut current_time = CLOCK.now().as_nanos();
-        // println!("t = {} ", current_time);
-        if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-            for j in jobs {
-                let pcb = PCB { id: *j, priority: jobs_by_id.get(&j).unwrap().priority, time_added:None, time_scheduled:None};
-                println!("Scheduled Process: {:?}, Priority:{}", pcb.id, pcb.priority);
-                sched.add_process(pcb);
-
-            }
-        }
-        while sched.has_process(){
-            if let (Some(process),mut time) = sched.next_process() {
-                // println!("{:?}",process);
-                if let Some(job) = jobs_by_id.get_mut(&process.id) {
-                    loop {
-                        println!("Process {} executed", process.id);
-                        CLOCK.advance(Duration::from_nanos(1));
-                        time -= 1;
-                        job.time_to_run -= 1;
-                        if job.time_to_run <= 0 || time <= 0{
-                            break;
-                        }
-                    }
-                    if job.time_to_run <= 0 {
-                        println!("Process {} Finished", process.id);
-                        jobs_by_id.remove(&process.id);
-                    }
-                    else {
-                        sched.add_process(process);
-                    }
-                    // println!("HERE");
-                    current_time = CLOCK.now().as_nanos();
-                    // println!("t = {} ", current_time);
-                    if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-                        // println!("Shouldn't be here");
-                        for j in jobs {
-                            let pcb = PCB { id: *j, priority: jobs_by_id.get(&j).unwrap().priority, time_added:None, time_scheduled:None};
-                            println!("Scheduled Process: {:?}, Priority:{}", pcb.id, pcb.priority);
-                            sched.add_process(pcb);
-
-                        }
-                    }
-                    else{
-                        // println!("Should be here");
-                        continue;
-                    }
-                }
-            }
-            else{
-                println!("None Process, something went wrong in your code.");
+///Runs `scheduler experiment estimate-error`: `spn` on `input` once per
+///estimation error and seed, each in a child process with a configuration
+///setting both, then writes the sweep as CSV and SVG and prints it with the
+///increase in mean turnaround over exact estimates
+fn estimate_error(input: &str, errors: &[u32], seeds: u64, csv: &Path, svg: &Path) {
+    let exe = current_exe();
+    let config = std::env::temp_dir().join(format!("scheduler-estimate-error-{}.toml", std::process::id()));
+    let mut points = Vec::new();
+    for &error in errors {
+        let (mut turnaround, mut waiting) = (0.0, 0.0);
+        for seed in 0..seeds {
+            if let Err(err) = std::fs::write(&config, format!("[spn]\nerror = {}\nseed = {}\n", error, seed)) {
+                eprintln!("Error: cannot write '{}': {}", config.display(), err);
                 std::process::exit(1);
             }
+            let results = run_child(&exe, "spn", input.as_ref(), config.to_str()).unwrap_or_else(|err| {
+                let _ = std::fs::remove_file(&config);
+                eprintln!("Error: spn failed with error {}% and seed {}:\n{}", error, seed, err);
+                std::process::exit(1);
+            });
+            turnaround += results.aggregates.mean_turnaround / seeds as f64;
+            waiting += results.aggregates.mean_waiting / seeds as f64;
+        }
+        points.push(ErrorPoint { error, mean_turnaround: turnaround, mean_waiting: waiting });
+    }
+    let _ = std::fs::remove_file(&config);
+    for (path, text) in [(csv, experiment::error_csv(&points)), (svg, experiment::error_svg(&points))] {
+        let written = Output::file(path).and_then(|mut file| {
+            file.write_all(text.as_bytes())?;
+            file.commit()
+        });
+        if let Err(err) = written {
+            eprintln!("Error: cannot write '{}': {}", path.display(), err);
+            std::process::exit(1);
         }
     }
+    println!("spn on {}, {} run{} per error", input, seeds, if seeds == 1 { "" } else { "s" });
+    println!("{:>8} {:>16} {:>13} {:>10}", "error", "mean turnaround", "mean waiting", "increase");
+    for point in &points {
+        let increase = experiment::increase(&points, point).map_or("-".to_string(), |increase| format!("{:+.1}%", increase));
+        println!("{:>7}% {:>16.2} {:>13.2} {:>10}", point.error, point.mean_turnaround, point.mean_waiting, increase);
+    }
+    println!("wrote {} and {}", csv.display(), svg.display());
 }
 
-///Simulator for the SimpleRR scheduler
-fn simplerr(lines: io::Lines<io::BufReader<File>>){
-    let mut sched = SimpleRRSchedule::new();
-    //Initialize clock to 0
-    CLOCK.set_now(Duration::from_millis(0));
-    // HashMap keyed by ID
-    let mut jobs_by_id: HashMap<u32, Job> = HashMap::new();
-
-    // Optionally, a secondary index keyed by time_inserted
-    let mut jobs_by_time: HashMap<u64, Vec<u32>> = HashMap::new(); // time_inserted -> IDs
-
-    // Consumes the iterator, returns an (Optional) String
-    // Parses input file into two HashMaps to make manipulation easier
-    for line in lines.map_while(Result::ok) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let id: u32 = parts[0].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid ID on line: {}", line);
-            std::process::exit(1);
-        });
-        let time_inserted: u64 = parts[1].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_inserted on line: {}", line);
+///Runs `scheduler experiment mlfq-rules`
+fn mlfq_rules(input: &str, scheduler: &str, rule_sets: &[MlfqRules], csv: &Path) {
+    let exe = current_exe();
+    let config = std::env::temp_dir().join(format!("scheduler-mlfq-rules-{}.toml", std::process::id()));
+    let mut points = Vec::new();
+    for &rules in rule_sets {
+        let table = toml::to_string(&rules).expect("rules serialize to TOML");
+        if let Err(err) = std::fs::write(&config, format!("[{}.rules]\n{}", scheduler, table)) {
+            eprintln!("Error: cannot write '{}': {}", config.display(), err);
             std::process::exit(1);
-        });
-        let time_to_run: u32 = parts[2].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_to_run on line: {}", line);
+        }
+        let results = run_child(&exe, scheduler, input.as_ref(), config.to_str()).unwrap_or_else(|err| {
+            let _ = std::fs::remove_file(&config);
+            match err {
+                ChildError::NotImplemented => eprintln!("Error: {} is not implemented yet", scheduler),
+                err => eprintln!("Error: {} failed with rules {}:\n{}", scheduler, rules, err),
+            }
             std::process::exit(1);
         });
+        points.push(RulesPoint::measure(rules, &results.aggregates, &results.processes));
+    }
+    let _ = std::fs::remove_file(&config);
+    let written = Output::file(csv).and_then(|mut file| {
+        file.write_all(experiment::rules_csv(&points).as_bytes())?;
+        file.commit()
+    });
+    if let Err(err) = written {
+        eprintln!("Error: cannot write '{}': {}", csv.display(), err);
+        std::process::exit(1);
+    }
+    let slowdown = |slowdown: Option<f64>| slowdown.map_or("-".to_string(), |slowdown| format!("{:.2}x", slowdown));
+    println!("{} on {}", scheduler, input);
+    println!(
+        "{:<24} {:>16} {:>14} {:>12} {:>12} {:>12}",
+        "rules", "mean turnaround", "mean response", "interaction", "I/O-bound", "CPU-bound"
+    );
+    for point in &points {
+        println!(
+            "{:<24} {:>16.2} {:>14.2} {:>12.2} {:>12} {:>12}",
+            point.rules.to_string(),
+            point.mean_turnaround,
+            point.mean_response,
+            point.mean_interaction_response,
+            slowdown(point.io_slowdown),
+            slowdown(point.cpu_slowdown)
+        );
+    }
+    println!("wrote {}", csv.display());
+}
 
-        let priority: u32 = 0;
-        let job = Job { id, time_inserted, time_to_run, priority };
-        jobs_by_id.insert(id, job);
-
-        // build secondary index for time_inserted
-        jobs_by_time.entry(time_inserted).or_default().push(id);
+///Runs `scheduler corpus build`: writes every corpus workload, then the
+///results of every scheduler on each. Stubs are skipped, and a run that
+///fails is reported and left out of the corpus, ending the build with
+///status 1.
+fn corpus_build(dir: &Path, schedulers: &[String]) {
+    let exe = current_exe();
+    let schedulers: Vec<&str> = match schedulers.is_empty() {
+        true => selfcheck::SCHEDULERS.to_vec(),
+        false => schedulers.iter().map(String::as_str).collect(),
+    };
+    let entries = corpus::entries();
+    let write = |path: &Path, text: &str| {
+        let written = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(path, text));
+        if let Err(err) = written {
+            eprintln!("Error: cannot write '{}': {}", path.display(), err);
+            std::process::exit(1);
+        }
+    };
+    for entry in &entries {
+        write(&corpus::workload_path(dir, &entry.name), &generate::to_text(&entry.generator.generate()));
+    }
+    println!("{} workloads in {}", entries.len(), dir.join(corpus::WORKLOADS).display());
+    let (mut failed, mut total) = (0, 0);
+    for scheduler in schedulers {
+        let (mut recorded, mut failures) = (0, Vec::new());
+        for entry in &entries {
+            let input = corpus::workload_path(dir, &entry.name);
+            match run_child_with(&exe, scheduler, input.as_os_str(), None, &CORPUS_RUN.map(String::from)) {
+                Ok(results) => {
+                    write(&corpus::results_path(dir, scheduler, &entry.name), &format!("{}\n", results.to_json()));
+                    recorded += 1;
+                }
+                Err(ChildError::NotImplemented) => break,
+                Err(err) => failures.push((&entry.name, err)),
+            }
+        }
+        match (recorded, failures.len()) {
+            (0, 0) => println!("{}: skipped, not implemented", scheduler),
+            (_, 0) => println!("{}: {} results", scheduler, recorded),
+            (_, n) => println!("{}: {} results, failed on {} workloads", scheduler, recorded, n),
+        }
+        for (entry, err) in &failures {
+            eprintln!("Error: {} failed on {}: {}", scheduler, entry, err.to_string().lines().next().unwrap_or_default());
+        }
+        failed += failures.len();
+        total += recorded;
+    }
+    //a corpus without reference results has nothing to grade or verify against
+    if total == 0 {
+        eprintln!("Error: no reference results were recorded in '{}'; every scheduler is a stub or failed", dir.display());
+        std::process::exit(1);
     }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
 
-    //RUN Simulation
-    while !jobs_by_id.is_empty() {
-        let current_time = CLOCK.now().as_nanos();
-        // println!("t = {} ", current_time);
-        if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-            for job in jobs {
-                let pcb = PCB { id: *job, priority: 0, time_added:None, time_scheduled:None};
-                println!("Scheduled Process: {:?}", pcb.id);
-                sched.add_process(pcb);
-
-            }
-        }
-        while sched.has_process(){
-            if let (Some(process),mut time) = sched.next_process() {
-                // println!("{:?}",process);
-                if let Some(job) = jobs_by_id.get_mut(&process.id) {
-                    loop {
-                        println!("Process {} executed", process.id);
-                        CLOCK.advance(Duration::from_nanos(1));
-                        time -= 1;
-                        job.time_to_run -= 1;
-                        if job.time_to_run <= 0 || time <= 0{
-                            break;
-                        }
-                    }
-                    if job.time_to_run <= 0 {
-                        println!("Process {} Finished", process.id);
-                        jobs_by_id.remove(&process.id);
-                    }
-                    else {
-                        sched.add_process(process);
-                    }
+///Runs `scheduler corpus verify`: re-runs every stored result and diffs it
+///against the stored one. Exits with status 1 if any run differs.
+fn corpus_verify(dir: &Path, schedulers: &[String], report: Option<(ReportFormat, PathBuf)>) {
+    let exe = current_exe();
+    let mut cases = TestReport::new("corpus verify");
+    let stored = std::fs::read_dir(dir).and_then(|entries| {
+        entries
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+            .collect::<io::Result<Vec<String>>>()
+    });
+    let mut stored = stored.unwrap_or_else(|err| {
+        eprintln!("Error: cannot read corpus '{}': {}", dir.display(), err);
+        Exit::InputError.exit();
+    });
+    stored.retain(|name| name != corpus::WORKLOADS && dir.join(name).is_dir());
+    stored.sort();
+    let schedulers: Vec<String> = match schedulers.is_empty() {
+        true => stored,
+        false => schedulers.to_vec(),
+    };
+    let width = schedulers.iter().map(String::len).max().unwrap_or(0);
+    let mut failed = 0;
+    for scheduler in &schedulers {
+        let mut problems = Vec::new();
+        let mut verified = 0;
+        for entry in corpus::entries() {
+            let reference = corpus::results_path(dir, scheduler, &entry.name);
+            if !reference.exists() {
+                continue;
+            }
+            verified += 1;
+            let expected = load_results(&reference.to_string_lossy());
+            let input = corpus::workload_path(dir, &entry.name);
+            let problem = match run_child_with(&exe, scheduler, input.as_os_str(), None, &CORPUS_RUN.map(String::from)) {
+                Ok(actual) => Some(diff::diff(&expected, &actual)).filter(|diff| !diff.is_identical()).map(|diff| diff.to_string()),
+                Err(err) => Some(err.to_string()),
+            };
+            match problem {
+                Some(problem) => {
+                    cases.push(TestCase::fail(scheduler.as_str(), entry.name.as_str(), problem.as_str()));
+                    problems.push((entry.name, problem));
                 }
+                None => cases.push(TestCase::pass(scheduler.as_str(), entry.name)),
             }
-            else{
-                println!("None Process, something went wrong in your code.");
-                std::process::exit(1);
+        }
+        if verified == 0 {
+            cases.push(TestCase::fail(scheduler.as_str(), "all workloads", "no results in the corpus"));
+            failed += 1;
+            println!("{:<width$} FAIL: no results in the corpus", scheduler);
+        } else if problems.is_empty() {
+            println!("{:<width$} pass ({} workloads)", scheduler, verified);
+        } else {
+            failed += 1;
+            println!("{:<width$} FAIL ({} of {} workloads differ)", scheduler, problems.len(), verified);
+            for (entry, problem) in problems {
+                println!("  {}:", entry);
+                for line in problem.lines().take(3) {
+                    println!("    {}", line);
+                }
             }
         }
     }
+    write_report(report, &cases);
+    if failed > 0 {
+        std::process::exit(1);
+    }
 }
 
-///Simulator for the Simple FIFO scheduler
-fn simple(lines: io::Lines<io::BufReader<File>>){
-    let mut sched = SimpleSchedule::new();
-    //Initialize clock to 0
-    CLOCK.set_now(Duration::from_millis(0));
-    // HashMap keyed by ID
-    let mut jobs_by_id: HashMap<u32, Job> = HashMap::new();
-
-    // Optionally, a secondary index keyed by time_inserted
-    let mut jobs_by_time: HashMap<u64, Vec<u32>> = HashMap::new(); // time_inserted -> IDs
-
-    // Consumes the iterator, returns an (Optional) String
-    // Parses input file into two HashMaps to make manipulation easier
-    for line in lines.map_while(Result::ok) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        let id: u32 = parts[0].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid ID on line: {}", line);
-            std::process::exit(1);
-        });
-        let time_inserted: u64 = parts[1].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_inserted on line: {}", line);
-            std::process::exit(1);
+///Runs `scheduler grade`: `scheduler` on every workload of the corpus in
+///`dir` that it has reference results for, scored by the `rubric` file, and
+///writes the score to `path`. Each run is held to the resource `limits`,
+///and one that hits them fails its workload.
+fn grade(scheduler: &str, dir: &Path, rubric: Option<&Path>, path: &Path, report: Option<(ReportFormat, PathBuf)>, limits: &Limits) {
+    let rubric = match rubric {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Rubric::from_toml(&text))
+            .unwrap_or_else(|err| {
+                eprintln!("Error: invalid rubric '{}': {}", path.display(), err);
+                std::process::exit(1);
+            }),
+        None => Rubric::default(),
+    };
+    let exe = current_exe();
+    let mut outcomes = Vec::new();
+    for entry in corpus::entries() {
+        let reference = corpus::results_path(dir, scheduler, &entry.name);
+        if !reference.exists() {
+            continue;
+        }
+        let expected = load_results(&reference.to_string_lossy());
+        let input = corpus::workload_path(dir, &entry.name);
+        let workload = std::fs::read_to_string(&input).map_err(|err| err.to_string()).and_then(|text| {
+            input::Workload::parse(text.lines(), TimeUnit::Ns, true).map_err(|err| err.to_string())
         });
-        let time_to_run: u32 = parts[2].parse().unwrap_or_else(|_| {
-            eprintln!("Invalid time_to_run on line: {}", line);
-            std::process::exit(1);
+        let workload = workload.unwrap_or_else(|err| {
+            eprintln!("Error: cannot read corpus workload '{}': {}", input.display(), err);
+            Exit::InputError.exit();
         });
+        let outcome = match run_child_limited(&exe, scheduler, input.as_os_str(), limits) {
+            Ok(actual) => {
+                let problems = selfcheck::check(&workload, &actual);
+                Outcome::new(entry.name, &expected, &actual, rubric.metrics.tolerance, &problems)
+            }
+            Err(err) => Outcome::failed(entry.name, err.to_string().lines().next().unwrap_or("the run failed")),
+        };
+        outcomes.push(outcome);
+    }
+    if outcomes.is_empty() {
+        eprintln!("Error: corpus '{}' has no reference results for {}", dir.display(), scheduler);
+        std::process::exit(1);
+    }
+    let score = rubric.score(&outcomes);
+    for test in &score.tests {
+        println!("{:<15} {:>6.2}/{:<6.2} {}", test.name, test.score, test.max_score, test.output.lines().next().unwrap_or_default());
+    }
+    println!("{:<15} {:>6.2}/{:<6.2}", "total", score.score, rubric.max_score());
+    let written = Output::file(path).and_then(|mut file| {
+        writeln!(file, "{}", score.to_json())?;
+        file.commit()
+    });
+    if let Err(err) = written {
+        eprintln!("Error: cannot write score file '{}': {}", path.display(), err);
+        std::process::exit(1);
+    }
+    let mut cases = TestReport::new("grade");
+    for outcome in &outcomes {
+        let checks = [
+            ("dispatch order", &outcome.dispatch_order),
+            ("metrics", &outcome.metrics),
+            ("invariants", &outcome.invariants),
+        ];
+        for (check, result) in checks {
+            let classname = format!("{}.{}", scheduler, check);
+            cases.push(match result {
+                Ok(()) => TestCase::pass(classname, outcome.workload.as_str()),
+                Err(err) => TestCase::fail(classname, outcome.workload.as_str(), err.as_str()),
+            });
+        }
+    }
+    write_report(report, &cases);
+}
 
-        let priority: u32 = 0;
-        let job = Job { id, time_inserted, time_to_run, priority };
-        jobs_by_id.insert(id, job);
+///Reads the FORMAT and FILE given to `--report`, exiting with an error
+///message if the format is unknown
+fn report_target(report: &[String]) -> Option<(ReportFormat, PathBuf)> {
+    let [format, path] = report else {
+        return None;
+    };
+    let format = format.parse().unwrap_or_else(|err| {
+        eprintln!("Error: --report: {}", err);
+        std::process::exit(2);
+    });
+    Some((format, PathBuf::from(path)))
+}
 
-        // build secondary index for time_inserted
-        jobs_by_time.entry(time_inserted).or_default().push(id);
+///Writes the checks a command made to its `--report` file, if it has one
+fn write_report(report: Option<(ReportFormat, PathBuf)>, cases: &TestReport) {
+    let Some((format, path)) = report else {
+        return;
+    };
+    let written = Output::file(&path).and_then(|mut file| {
+        file.write_all(cases.render(format).as_bytes())?;
+        file.commit()
+    });
+    if let Err(err) = written {
+        eprintln!("Error: cannot write report '{}': {}", path.display(), err);
+        std::process::exit(1);
     }
+}
 
-    //RUN Simulation
-    while !jobs_by_id.is_empty() {
-        let current_time = CLOCK.now().as_nanos();
-        // println!("t = {} ", current_time);
-        if let Some(jobs) = jobs_by_time.get(&(current_time as u64)) {
-            for job in jobs {
-                let pcb = PCB { id: *job, priority: 0, time_added:None,time_scheduled:None};
-                println!("Scheduled Process: {:?}", pcb.id);
-                sched.add_process(pcb);
-
-            }
-        }
-        while sched.has_process(){
-            if let (Some(process),_) = sched.next_process() {
-                // println!("{:?}",process);
-                if let Some(job) = jobs_by_id.get_mut(&process.id) {
-                    loop {
-                        println!("Process {} executed", process.id);
-                        CLOCK.advance(Duration::from_nanos(1));
-                        if job.time_to_run <= 0 {
-                            break;
-                        }
-                        job.time_to_run -= 1;
-                    }
-                    println!("Process {} Finished", process.id);
-                    jobs_by_id.remove(&process.id);
-                }
-            }
-            else{
-                println!("None Process, something went wrong in your code.");
-                std::process::exit(1);
+///The path of the running simulator, to run children of, exiting with an
+///error message if it cannot be found
+fn current_exe() -> PathBuf {
+    std::env::current_exe().unwrap_or_else(|err| {
+        eprintln!("Error: cannot find the simulator executable: {}", err);
+        std::process::exit(1);
+    })
+}
+
+///Runs `scheduler results ls`: lists every run with results in `dir` that
+///matches all of `filters`, in path order. Files that are not results are
+///skipped.
+fn results_ls(dir: &Path, filters: &[(String, String)]) {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(&path, files)?;
+            } else if path.extension().is_some_and(|ext| ext == "json" || ext == "jsonl") {
+                files.push(path);
             }
         }
+        Ok(())
+    }
+    let mut files = Vec::new();
+    if let Err(err) = walk(dir, &mut files) {
+        eprintln!("Error: cannot read results directory '{}': {}", dir.display(), err);
+        std::process::exit(1);
+    }
+    files.sort();
+    let mut skipped = 0;
+    let mut rows = Vec::new();
+    for path in files {
+        let Some(results) = std::fs::read_to_string(&path).ok().and_then(|text| Results::load(&text).ok()) else {
+            skipped += 1;
+            continue;
+        };
+        if !results.matches(filters) {
+            continue;
+        }
+        let tags = results.provenance.as_ref().map(|p| &p.tags).filter(|tags| !tags.is_empty());
+        let tags = tags.map_or("-".to_string(), |tags| {
+            tags.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join(" ")
+        });
+        let name = path.strip_prefix(dir).unwrap_or(&path).display().to_string();
+        rows.push((name, results, tags));
+    }
+    let width = rows.iter().map(|(name, ..)| name.len()).max().unwrap_or(0).max(4);
+    println!("{:<width$}  {:<10} {:>9} {:>11}  tags", "file", "scheduler", "completed", "turnaround");
+    for (name, results, tags) in &rows {
+        let agg = &results.aggregates;
+        println!("{:<width$}  {:<10} {:>9} {:>11.2}  {}", name, results.config.scheduler, agg.completed, agg.mean_turnaround, tags);
+    }
+    println!("{} run(s)", rows.len());
+    if skipped > 0 {
+        eprintln!("skipped {} file(s) that are not results", skipped);
     }
 }
 
-fn main() {
-    //Parse the inputs for which scheduler and which input file to use
-    let args = Args::parse();
-    //Assuming input file exists, read all the lines from the input file
-    if let Ok(lines) = read_lines(args.input_file) {
-        //Now determine what scheduler to run the inputs on
-        match args.scheduler.as_str() {
-            "simple" => simple(lines),
-            "simplerr" => simplerr(lines),
-            "mlrr" => mlrr(lines),
-            "simplemlf"=> simplemlf(lines),
-            "mlf"=> mlf(lines),
-            other => {
-                eprintln!("Error: unknown scheduler '{}'", other);
-                std::process::exit(1);
+///Runs `scheduler batch`: every scheduler on each of `runs` generated
+///workloads, each run in a child process of this binary so that a scheduler
+///that exits or panics cannot take the others down.
+fn batch(schedulers: &[String], runs: u64, config: Option<&str>, workload: &GenArgs) {
+    let report = batch_runs(schedulers, runs, config, workload, workload.arrival);
+    println!("{} run{} of {} jobs, seeds {} to {}", runs, if runs == 1 { "" } else { "s" }, workload.jobs, workload.seed, workload.seed + runs - 1);
+    print!("{}", report.report());
+    if workload.classes.is_empty() {
+        print!("{}", queueing_report(&report, workload));
+    }
+}
+
+///Runs every scheduler on `runs` workloads generated from `workload` with
+///gaps between arrivals drawn from `arrival`, each in a child process
+fn batch_runs(schedulers: &[String], runs: u64, config: Option<&str>, workload: &GenArgs, arrival: Distribution) -> BatchReport {
+    let exe = current_exe();
+    let path = std::env::temp_dir().join(format!("scheduler-batch-{}.in", std::process::id()));
+    let mut report = BatchReport::default();
+    for run in 0..runs {
+        let seed = workload.seed + run;
+        let generator = Generator { arrival, ..workload.generator(seed) };
+        if let Err(err) = std::fs::write(&path, generate::to_text(&generator.generate())) {
+            eprintln!("Error: cannot write '{}': {}", path.display(), err);
+            std::process::exit(1);
+        }
+        for scheduler in schedulers {
+            match run_child(&exe, scheduler, path.as_os_str(), config) {
+                Ok(results) => report.add(scheduler, results.aggregates),
+                Err(err) => {
+                    let _ = std::fs::remove_file(&path);
+                    eprintln!("Error: {} failed on the workload with seed {}:\n{}", scheduler, seed, err);
+                    std::process::exit(1);
+                }
             }
         }
     }
+    let _ = std::fs::remove_file(&path);
+    report
+}
+
+///Runs `scheduler batch --sweep-rates`: the batch once per arrival rate,
+///then writes throughput and mean response time per rate and scheduler as
+///CSV, and SVG if asked, and prints them with the rate each scheduler
+///saturates at
+fn saturation(schedulers: &[String], runs: u64, config: Option<&str>, workload: &GenArgs, rates: &[f64], csv: &Path, svg: Option<&Path>) {
+    let mean_burst = workload.burst.mean();
+    let mut points = Vec::new();
+    for &rate in rates {
+        let report = batch_runs(schedulers, runs, config, workload, Distribution::Exponential { mean: 1.0 / rate });
+        for scheduler in report.runs.keys() {
+            points.push(SaturationPoint {
+                rate,
+                load: mean_burst.map(|mean| rate * mean),
+                scheduler: scheduler.clone(),
+                throughput: report.estimate(scheduler, "throughput").expect("every batch reports throughput"),
+                response: report.estimate(scheduler, "response").expect("every batch reports response"),
+            });
+        }
+    }
+    let mut files = vec![(csv, experiment::saturation_csv(&points))];
+    files.extend(svg.map(|svg| (svg, experiment::saturation_svg(&points))));
+    for (path, text) in files {
+        let written = Output::file(path).and_then(|mut file| {
+            file.write_all(text.as_bytes())?;
+            file.commit()
+        });
+        if let Err(err) = written {
+            eprintln!("Error: cannot write '{}': {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+    println!("{} run{} of {} jobs per rate, bursts {}", runs, if runs == 1 { "" } else { "s" }, workload.jobs, workload.burst);
+    let width = schedulers.iter().map(String::len).max().unwrap_or(0).max("scheduler".len());
+    println!("{:>8} {:>6} {:<width$} {:>11} {:>14}", "rate", "load", "scheduler", "throughput", "mean response");
+    for point in &points {
+        let load = point.load.map_or("-".to_string(), |load| format!("{:.2}", load));
+        println!(
+            "{:>8} {:>6} {:<width$} {:>11.4} {:>14.2}",
+            point.rate, load, point.scheduler, point.throughput.mean, point.response.mean
+        );
+    }
+    for scheduler in points.iter().map(|point| &point.scheduler).collect::<BTreeSet<_>>() {
+        match experiment::saturation(&points, scheduler) {
+            Some(rate) => println!("{} saturates at rate {}: throughput below 90% of the arrival rate", scheduler, rate),
+            None => println!("{} kept up at every rate", scheduler),
+        }
+    }
+    match svg {
+        Some(svg) => println!("wrote {} and {}", csv.display(), svg.display()),
+        None => println!("wrote {}", csv.display()),
+    }
+}
+
+///The waiting times queueing theory predicts for the workload of a batch,
+///next to the simulated ones
+fn queueing_report(report: &BatchReport, workload: &GenArgs) -> String {
+    let prediction = match queueing::predict(&workload.arrival, &workload.burst) {
+        Ok(prediction) => prediction,
+        Err(reason) => return format!("queueing theory: no prediction, {}\n", reason),
+    };
+    let fcfs = prediction.fcfs_waiting.map_or("infinite".to_string(), |waiting| format!("{:.4}", waiting));
+    let mut out = format!(
+        "queueing theory ({}, utilization {:.2}): mean waiting {} under FCFS, {:.4} under processor sharing\n",
+        prediction.model, prediction.utilization, fcfs, prediction.ps_waiting
+    );
+    for scheduler in report.runs.keys() {
+        let waiting = report.estimate(scheduler, "waiting").expect("every batch reports waiting");
+        out += &format!("  {}: simulated mean waiting {:.4}\n", scheduler, waiting.mean);
+    }
+    out
+}
+
+///Whether the trace should be colorized: only when it goes straight to a
+///terminal, and never with `--no-color`, a `--locale` or the `NO_COLOR`
+///environment variable.
+fn use_color(args: &Args) -> bool {
+    !args.no_color
+        && args.locale.is_none()
+        && args.output_file.is_none()
+        && std::env::var_os("NO_COLOR").is_none()
+        && io::stdout().is_terminal()
+}
+
+///Reads the lines of the input file, with its directives expanded, or of a
+///built-in workload when the name starts with `builtin:`, exiting with an
+///error message if there is none
+fn read_input(args: &Args) -> Vec<String> {
+    read_workload(args.input_file(), &input_path(args), args.time_unit)
+}
+
+///Reads the lines of the workload `name`, found at `path` unless it is
+///built in, as [`read_input`] does
+fn read_workload(name: &str, path: &Path, unit: TimeUnit) -> Vec<String> {
+    let Some(name) = name.strip_prefix(workloads::PREFIX) else {
+        let lines: Vec<String> = read_lines(path).and_then(Iterator::collect).unwrap_or_else(|err| {
+            eprintln!("Error: cannot read input file '{}': {}", path.display(), err);
+            Exit::InputError.exit();
+        });
+        //plain workload files may include others and repeat blocks of lines
+        if path.extension().is_some_and(|ext| ext == "json" || ext == "csv") {
+            return lines;
+        }
+        return input::expand(&lines, path, unit, |path: &Path| std::fs::read_to_string(path)).unwrap_or_else(|err| {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        });
+    };
+    let Some(builtin) = workloads::get(name) else {
+        eprintln!("Error: unknown built-in workload '{}'. Available workloads:", name);
+        for builtin in workloads::ALL {
+            eprintln!("  {}{:<12} {}", workloads::PREFIX, builtin.name, builtin.description);
+        }
+        std::process::exit(1);
+    };
+    builtin.text.lines().map(String::from).collect()
+}
+
+///Resolves `--input-file`. A relative path that does not exist from the
+///working directory is looked up next to the `--config` file instead, so a
+///config can be shipped together with its workloads.
+fn input_path(args: &Args) -> PathBuf {
+    let path = expand_home(args.input_file());
+    if let Some(config) = &args.config
+        && path.is_relative()
+        && !path.exists()
+    {
+        let beside = expand_home(config).with_file_name(&path);
+        if beside.exists() {
+            return beside;
+        }
+    }
+    path
 }
 
+///Replaces a leading `~` with the home directory, for paths the shell did
+///not expand such as `--input-file=~/jobs.in`
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            Path::new(&home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
 
 // The output is wrapped in a Result to allow matching on errors.
 // Returns an Iterator to the Reader of the lines of the file.
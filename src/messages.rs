@@ -0,0 +1,341 @@
+//! # Messages Module
+//!
+//! The catalog of the lines the plain text trace is made of, such as
+//! `Process 3 Finished`, for `--locale`.
+//!
+//! Every line of the trace is one [`Message`], and every message has a
+//! stable code, such as `finish` or `dispatch_to_completion`, that does not
+//! change with the wording. The codes of the events are the `kind`s of the
+//! JSON event log. A [`Catalog`] gives each message its wording in one
+//! language, as a template whose placeholders, such as `{id}`, are filled in
+//! from the event; [`Message::placeholders`] lists the ones each message
+//! has.
+//!
+//! [`Catalog::english`], the default, is the format the reference outputs in
+//! `tests/` are written in, word for word. Two more catalogs are built in:
+//! `es`, for Spanish-speaking offerings of the course, and `codes`, which
+//! prints every line as its code followed by its values (`finish 3`), for
+//! graders and scripts that should not depend on the wording of any
+//! language. Any other language is a TOML file of codes and templates, such
+//! as `locales/es.toml`; messages it leaves out are printed in English.
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::messages::{Catalog, Message};
+//!
+//! let finish = Event { time: 9, id: 3, kind: EventKind::Finish };
+//! assert_eq!(Message::of(&finish.kind, false), Message::Finish);
+//! assert_eq!(Message::Finish.code(), "finish");
+//! assert_eq!(Catalog::english().render(&finish, false), "Process 3 Finished");
+//! assert_eq!(Catalog::builtin("es").unwrap().render(&finish, false), "Proceso 3 Terminado");
+//! assert_eq!(Catalog::builtin("codes").unwrap().render(&finish, false), "finish 3");
+//!
+//! let arrive = Event { time: 0, id: 3, kind: EventKind::Arrive { priority: 2 } };
+//! let pirate = Catalog::from_toml("arrive_priority = \"Ahoy, {id} boards at rank {priority}\"").unwrap();
+//! assert_eq!(pirate.render(&arrive, true), "Ahoy, 3 boards at rank 2");
+//! assert_eq!(pirate.render(&arrive, false), "Scheduled Process: 3");
+//!
+//! assert_eq!(
+//!     Catalog::from_toml("finish = \"Process {id} done after {quantum}\"").unwrap_err(),
+//!     "finish: unknown placeholder {quantum}, expected {id}"
+//! );
+//! ```
+
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::event::{Event, EventKind};
+
+/// The lines of the plain text trace, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Message {
+    /// A process arrived, printed without its priority.
+    Arrive,
+    /// A process arrived, printed with its priority.
+    ArrivePriority,
+    /// A process was dispatched for a quantum.
+    Dispatch,
+    /// A process was dispatched to run until it finishes.
+    DispatchToCompletion,
+    /// A process ran for a tick.
+    Execute,
+    /// A process was preempted.
+    Preempt,
+    /// A process blocked for I/O.
+    Block,
+    /// A process woke from I/O.
+    Wake,
+    /// A process was throttled by a CPU limit.
+    Throttle,
+    /// A process was let go by a CPU limit.
+    Unthrottle,
+    /// A process got a new nice value.
+    Renice,
+    /// A process finished.
+    Finish,
+    /// A process timed out.
+    Timeout,
+    /// Nothing ran for a tick.
+    Idle,
+    /// A labeled marker of the run.
+    Annotation,
+}
+
+/// A value a template can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placeholder {
+    /// The process ID.
+    Id,
+    /// The priority of an arriving process.
+    Priority,
+    /// The quantum of a dispatch.
+    Quantum,
+    /// The new nice value.
+    Nice,
+    /// The label of an annotation.
+    Label,
+}
+
+impl Placeholder {
+    /// How the placeholder is written in a template, without the braces.
+    pub fn name(self) -> &'static str {
+        match self {
+            Placeholder::Id => "id",
+            Placeholder::Priority => "priority",
+            Placeholder::Quantum => "quantum",
+            Placeholder::Nice => "nice",
+            Placeholder::Label => "label",
+        }
+    }
+}
+
+impl Message {
+    /// Every message, in the order of [`EventKind`].
+    pub const ALL: [Message; 15] = [
+        Message::Arrive,
+        Message::ArrivePriority,
+        Message::Dispatch,
+        Message::DispatchToCompletion,
+        Message::Execute,
+        Message::Preempt,
+        Message::Block,
+        Message::Wake,
+        Message::Throttle,
+        Message::Unthrottle,
+        Message::Renice,
+        Message::Finish,
+        Message::Timeout,
+        Message::Idle,
+        Message::Annotation,
+    ];
+
+    /// The message an event of `kind` is printed as, with the priority of
+    /// arrivals if `with_priority`.
+    pub fn of(kind: &EventKind, with_priority: bool) -> Message {
+        match kind {
+            EventKind::Arrive { .. } if with_priority => Message::ArrivePriority,
+            EventKind::Arrive { .. } => Message::Arrive,
+            EventKind::Dispatch { quantum: 0 } => Message::DispatchToCompletion,
+            EventKind::Dispatch { .. } => Message::Dispatch,
+            EventKind::Execute => Message::Execute,
+            EventKind::Preempt => Message::Preempt,
+            EventKind::Block => Message::Block,
+            EventKind::Wake => Message::Wake,
+            EventKind::Throttle => Message::Throttle,
+            EventKind::Unthrottle => Message::Unthrottle,
+            EventKind::Renice { .. } => Message::Renice,
+            EventKind::Finish => Message::Finish,
+            EventKind::Timeout => Message::Timeout,
+            EventKind::Idle => Message::Idle,
+        }
+    }
+
+    /// The stable code of the message, e.g. `"finish"`.
+    pub fn code(self) -> &'static str {
+        match self {
+            Message::Arrive => "arrive",
+            Message::ArrivePriority => "arrive_priority",
+            Message::Dispatch => "dispatch",
+            Message::DispatchToCompletion => "dispatch_to_completion",
+            Message::Execute => "execute",
+            Message::Preempt => "preempt",
+            Message::Block => "block",
+            Message::Wake => "wake",
+            Message::Throttle => "throttle",
+            Message::Unthrottle => "unthrottle",
+            Message::Renice => "renice",
+            Message::Finish => "finish",
+            Message::Timeout => "timeout",
+            Message::Idle => "idle",
+            Message::Annotation => "annotation",
+        }
+    }
+
+    /// The values a template of the message can show.
+    pub fn placeholders(self) -> &'static [Placeholder] {
+        match self {
+            Message::ArrivePriority => &[Placeholder::Id, Placeholder::Priority],
+            Message::Dispatch => &[Placeholder::Id, Placeholder::Quantum],
+            Message::Renice => &[Placeholder::Id, Placeholder::Nice],
+            Message::Idle => &[],
+            Message::Annotation => &[Placeholder::Label],
+            _ => &[Placeholder::Id],
+        }
+    }
+
+    /// The English template, the wording of the reference outputs.
+    pub fn english(self) -> &'static str {
+        match self {
+            Message::Arrive => "Scheduled Process: {id}",
+            Message::ArrivePriority => "Scheduled Process: {id}, Priority:{priority}",
+            Message::Dispatch => "Process {id} dispatched for {quantum}",
+            Message::DispatchToCompletion => "Process {id} dispatched to completion",
+            Message::Execute => "Process {id} executed",
+            Message::Preempt => "Process {id} preempted",
+            Message::Block => "Process {id} blocked for I/O",
+            Message::Wake => "Process {id} woke from I/O",
+            Message::Throttle => "Process {id} throttled",
+            Message::Unthrottle => "Process {id} unthrottled",
+            Message::Renice => "Process {id} reniced to {nice}",
+            Message::Finish => "Process {id} Finished",
+            Message::Timeout => "Process {id} timed out",
+            Message::Idle => "CPU idle",
+            Message::Annotation => "--- {label} ---",
+        }
+    }
+
+    /// The message with the stable code `code`.
+    pub fn from_code(code: &str) -> Option<Message> {
+        Message::ALL.into_iter().find(|message| message.code() == code)
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// A piece of a parsed template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Value(Placeholder),
+}
+
+/// The wording of every [`Message`] in one language, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    /// the template of every message, parsed, in the order of [`Message::ALL`]
+    templates: Vec<Vec<Piece>>,
+}
+
+/// Splits `template` of `message` into text and placeholders.
+fn parse(message: Message, template: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            pieces.push(Piece::Text(rest[..open].to_string()));
+        }
+        let Some(close) = rest[open..].find('}') else {
+            return Err(format!("{}: unclosed {{ in \"{}\"", message, template));
+        };
+        let name = &rest[open + 1..open + close];
+        let placeholder = message.placeholders().iter().find(|placeholder| placeholder.name() == name).ok_or_else(|| {
+            let expected: Vec<String> = message.placeholders().iter().map(|placeholder| format!("{{{}}}", placeholder.name())).collect();
+            match expected.is_empty() {
+                true => format!("{}: unknown placeholder {{{}}}, the message has none", message, name),
+                false => format!("{}: unknown placeholder {{{}}}, expected {}", message, name, expected.join(" or ")),
+            }
+        })?;
+        pieces.push(Piece::Value(*placeholder));
+        rest = &rest[open + close + 1..];
+    }
+    if !rest.is_empty() {
+        pieces.push(Piece::Text(rest.to_string()));
+    }
+    Ok(pieces)
+}
+
+impl Catalog {
+    /// The English catalog, the default.
+    pub fn english() -> Self {
+        let templates = Message::ALL.iter().map(|&message| parse(message, message.english()).expect("the English templates parse"));
+        Self { templates: templates.collect() }
+    }
+
+    /// The built-in catalog `name`: `en`, `es` or `codes`.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let text = match name {
+            "en" => return Some(Self::english()),
+            "es" => include_str!("../locales/es.toml"),
+            "codes" => include_str!("../locales/codes.toml"),
+            _ => return None,
+        };
+        Some(Self::from_toml(text).expect("the built-in catalogs parse"))
+    }
+
+    /// Reads a catalog from a TOML table of message codes and templates.
+    /// Messages it leaves out keep their English wording.
+    ///
+    /// # Errors
+    /// If the text is not TOML, names a message that does not exist, or a
+    /// template uses a placeholder its message does not have.
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        let table: toml::Table = toml::from_str(text).map_err(|err| err.message().trim().to_string())?;
+        let mut catalog = Self::english();
+        for (code, template) in table {
+            let message = Message::from_code(&code).ok_or_else(|| format!("unknown message '{}'", code))?;
+            let template = template.as_str().ok_or_else(|| format!("{}: expected a string", code))?;
+            catalog.templates[message as usize] = parse(message, template)?;
+        }
+        Ok(catalog)
+    }
+
+    /// Writes `message` with the values of `event`, or of `label` for an
+    /// annotation, without a line break. Writes piece by piece, so that a
+    /// shown tick never allocates.
+    fn write(&self, out: &mut impl Write, message: Message, event: Option<&Event>, label: &str) -> io::Result<()> {
+        for piece in &self.templates[message as usize] {
+            match (piece, event.map(|event| event.kind)) {
+                (Piece::Text(text), _) => out.write_all(text.as_bytes()),
+                (Piece::Value(Placeholder::Label), _) => out.write_all(label.as_bytes()),
+                (Piece::Value(Placeholder::Priority), Some(EventKind::Arrive { priority })) => write!(out, "{}", priority),
+                (Piece::Value(Placeholder::Quantum), Some(EventKind::Dispatch { quantum })) => write!(out, "{}", quantum),
+                (Piece::Value(Placeholder::Nice), Some(EventKind::Renice { nice })) => write!(out, "{}", nice),
+                (Piece::Value(_), _) => write!(out, "{}", event.map_or(0, |event| event.id)),
+            }?;
+        }
+        Ok(())
+    }
+
+    /// Writes the line for `event`, with the priority of arrivals if
+    /// `with_priority`, and a line break.
+    pub fn write_event(&self, out: &mut impl Write, event: &Event, with_priority: bool) -> io::Result<()> {
+        self.write(out, Message::of(&event.kind, with_priority), Some(event), "")?;
+        out.write_all(b"\n")
+    }
+
+    /// Writes the line for an annotation labeled `label`, and a line break.
+    pub fn write_annotation(&self, out: &mut impl Write, label: &str) -> io::Result<()> {
+        self.write(out, Message::Annotation, None, label)?;
+        out.write_all(b"\n")
+    }
+
+    /// The line for `event`, without a line break.
+    pub fn render(&self, event: &Event, with_priority: bool) -> String {
+        let mut line = Vec::new();
+        self.write(&mut line, Message::of(&event.kind, with_priority), Some(event), "").expect("writing to a Vec cannot fail");
+        String::from_utf8(line).expect("templates and values are UTF-8")
+    }
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::english()
+    }
+}
@@ -0,0 +1,983 @@
+//! # Metrics Module
+//!
+//! Computes the classic scheduling metrics from a run's [`Event`] stream:
+//!
+//! - **Turnaround time**: finish time minus arrival time.
+//! - **Waiting time**: turnaround time minus the CPU time the process used
+//!   and the time it spent blocked on I/O.
+//! - **Response time**: first dispatch minus arrival time.
+//! - **Interaction response time**: for jobs that alternate CPU and I/O
+//!   bursts, the time from each I/O completion to the next dispatch. This is
+//!   what an interactive user feels, and what MLFQ is designed to keep low;
+//!   it is reported as a mean and a 99th percentile.
+//! - **Scheduling latency**: for every dispatch, the time since the process
+//!   last became ready, by arriving, waking from I/O, being preempted or
+//!   being let go by a CPU limit. Response time only counts the first
+//!   dispatch; scheduling latency counts the wait before every one, and is
+//!   reported as a mean, a 99th percentile and a maximum.
+//! - **Completion within deadline**: for jobs with a timeout (see
+//!   [Timeouts](crate::input#timeouts)), the share that finished by their
+//!   deadline rather than timing out. A job that timed out is out of the
+//!   system from then on, but has no finish time, so it counts towards none
+//!   of the other means.
+//!
+//! plus run-wide aggregates such as throughput, CPU utilization and the time
+//! the CPU spent running the idle task. All times are in ticks.
+//!
+//! For multi-level schedulers the simulator also notes the queue level every
+//! dispatch came from, and each process keeps the [`LevelVisit`]s that make
+//! up its path through the levels: the evidence that demotion and promotion
+//! work as intended.
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::metrics::Metrics;
+//!
+//! let mut metrics = Metrics::new();
+//! for event in [
+//!     Event { time: 0, id: 1, kind: EventKind::Arrive { priority: 0 } },
+//!     Event { time: 2, id: 1, kind: EventKind::Dispatch { quantum: 0 } },
+//!     Event { time: 2, id: 1, kind: EventKind::Execute },
+//!     Event { time: 3, id: 1, kind: EventKind::Execute },
+//!     Event { time: 4, id: 1, kind: EventKind::Finish },
+//! ] {
+//!     metrics.record(&event);
+//! }
+//! let p = &metrics.processes()[0];
+//! assert_eq!((p.turnaround, p.waiting, p.response), (Some(4), Some(2), Some(2)));
+//! assert_eq!(metrics.aggregates().utilization, 0.5);
+//! ```
+//!
+//! Two jobs sharing the CPU in slices of 2 ticks:
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::metrics::Metrics;
+//!
+//! let mut metrics = Metrics::new();
+//! for (time, id, kind) in [
+//!     (0, 1, EventKind::Arrive { priority: 0 }),
+//!     (0, 2, EventKind::Arrive { priority: 0 }),
+//!     (0, 1, EventKind::Dispatch { quantum: 2 }),
+//!     (2, 1, EventKind::Preempt),
+//!     (2, 2, EventKind::Dispatch { quantum: 2 }),
+//!     (4, 2, EventKind::Finish),
+//!     (4, 1, EventKind::Dispatch { quantum: 2 }),
+//!     (5, 1, EventKind::Finish),
+//! ] {
+//!     metrics.record(&Event { time, id, kind });
+//! }
+//! // P1 waited 0 and 2 ticks, P2 2 ticks
+//! assert_eq!(metrics.process(1).unwrap().max_latency, Some(2));
+//! let agg = metrics.aggregates();
+//! assert_eq!((agg.mean_latency, agg.p99_latency, agg.max_latency), (4.0 / 3.0, 2, 2));
+//! ```
+//!
+//! Two requests that must be done within 3 ticks of arriving, one of which
+//! is given up on:
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::metrics::Metrics;
+//!
+//! let mut metrics = Metrics::new();
+//! for (time, id, kind) in [
+//!     (0, 1, EventKind::Arrive { priority: 0 }),
+//!     (0, 2, EventKind::Arrive { priority: 0 }),
+//!     (0, 1, EventKind::Dispatch { quantum: 0 }),
+//!     (2, 1, EventKind::Finish),
+//!     (2, 2, EventKind::Dispatch { quantum: 0 }),
+//!     (3, 2, EventKind::Timeout),
+//! ] {
+//!     metrics.record(&Event { time, id, kind });
+//!     if let EventKind::Arrive { .. } = kind {
+//!         metrics.note_deadline(id, time + 3);
+//!     }
+//! }
+//! assert_eq!(metrics.process(2).unwrap().timed_out, Some(3));
+//! let agg = metrics.aggregates();
+//! assert_eq!((agg.completed, agg.deadlines, agg.timed_out), (1, 2, 1));
+//! assert_eq!(agg.within_deadline, 0.5);
+//! ```
+//!
+//! A job that blocks for I/O twice, and is dispatched 1 and then 3 ticks
+//! after each I/O completes:
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::metrics::Metrics;
+//!
+//! let mut metrics = Metrics::new();
+//! for (time, kind) in [
+//!     (0, EventKind::Arrive { priority: 0 }),
+//!     (0, EventKind::Dispatch { quantum: 0 }),
+//!     (1, EventKind::Block),
+//!     (5, EventKind::Wake),
+//!     (6, EventKind::Dispatch { quantum: 0 }),
+//!     (7, EventKind::Block),
+//!     (9, EventKind::Wake),
+//!     (12, EventKind::Dispatch { quantum: 0 }),
+//!     (13, EventKind::Finish),
+//! ] {
+//!     metrics.record(&Event { time, id: 1, kind });
+//! }
+//! let p = metrics.process(1).unwrap();
+//! assert_eq!((p.io, p.interactions), (6, 2));
+//! assert_eq!(p.mean_interaction_response, Some(2.0));
+//! assert_eq!(p.p99_interaction_response, Some(3));
+//! // 13 ticks in the system, 6 of them blocked (and no execute events, so no CPU time)
+//! assert_eq!(p.waiting, Some(7));
+//! ```
+//!
+//! ## Measurement Windows
+//! With a steady stream of arrivals the first jobs find an empty system and
+//! the last ones an emptying one, and both phases skew the averages. A
+//! [`Window`] restricts the aggregates to the jobs that *arrive* inside it,
+//! and utilization and idle time to the ticks inside it. Per-process metrics
+//! are always kept for every job.
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::metrics::{Metrics, Window};
+//!
+//! let mut metrics = Metrics::with_window(Window { from: 10, until: Some(20) });
+//! for (time, id, kind) in [
+//!     (0, 1, EventKind::Arrive { priority: 0 }),
+//!     (0, 1, EventKind::Dispatch { quantum: 0 }),
+//!     (12, 1, EventKind::Finish),
+//!     (12, 2, EventKind::Arrive { priority: 0 }),
+//!     (12, 2, EventKind::Dispatch { quantum: 0 }),
+//!     (15, 2, EventKind::Finish),
+//! ] {
+//!     metrics.record(&Event { time, id, kind });
+//! }
+//! let agg = metrics.aggregates();
+//! // only P2 arrived inside the window
+//! assert_eq!((agg.processes, agg.mean_turnaround), (1, 3.0));
+//! assert_eq!(metrics.processes().len(), 2);
+//! ```
+//!
+//! ## Job Classes
+//! Jobs labeled with a class, e.g. `interactive` or `batch`, are also
+//! measured per class, since a scheduler is judged by how it treats each
+//! kind of job rather than by one mean over all of them. Labels are noted
+//! with [`Metrics::note_class`] and the breakdown is in
+//! [`Aggregates::classes`].
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::metrics::Metrics;
+//!
+//! let mut metrics = Metrics::new();
+//! metrics.note_class(1, "interactive".to_string());
+//! metrics.note_class(2, "batch".to_string());
+//! for (time, id, kind) in [
+//!     (0, 2, EventKind::Arrive { priority: 0 }),
+//!     (0, 2, EventKind::Dispatch { quantum: 0 }),
+//!     (1, 1, EventKind::Arrive { priority: 0 }),
+//!     (10, 2, EventKind::Finish),
+//!     (10, 1, EventKind::Dispatch { quantum: 0 }),
+//!     (11, 1, EventKind::Finish),
+//! ] {
+//!     metrics.record(&Event { time, id, kind });
+//! }
+//! // the interactive job waited behind the batch job
+//! let classes = metrics.aggregates().classes;
+//! assert_eq!(classes["interactive"].mean_response, 9.0);
+//! assert_eq!(classes["batch"].mean_response, 0.0);
+//! assert_eq!(metrics.process(1).unwrap().class.as_deref(), Some("interactive"));
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bandwidth::GroupStats;
+use crate::clock::TimeUnit;
+use crate::event::{Event, EventKind};
+use crate::simulation::TimerStats;
+
+/// Metrics for a single process.
+///
+/// The derived times are `None` while the process has not reached the
+/// corresponding point (e.g. `finish` for a process that never completed).
+/// `deadline` is the tick a job with a timeout had to finish by, and
+/// `timed_out` the tick it was cancelled at if it did not. The I/O,
+/// scheduling latency and deadline fields were added after version 1 of
+/// the results format and read back as zero or `None` from older
+/// documents, as does `class` for jobs without a class label.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessMetrics {
+    pub id: u32,
+    pub priority: u32,
+    pub arrival: u64,
+    pub first_run: Option<u64>,
+    pub finish: Option<u64>,
+    pub cpu: u64,
+    pub dispatches: u32,
+    pub turnaround: Option<u64>,
+    pub waiting: Option<u64>,
+    pub response: Option<u64>,
+    #[serde(default)]
+    pub io: u64,
+    #[serde(default)]
+    pub interactions: u32,
+    #[serde(default)]
+    pub mean_interaction_response: Option<f64>,
+    #[serde(default)]
+    pub p99_interaction_response: Option<u64>,
+    #[serde(default)]
+    pub mean_latency: Option<f64>,
+    #[serde(default)]
+    pub max_latency: Option<u64>,
+    #[serde(default)]
+    pub deadline: Option<u64>,
+    #[serde(default)]
+    pub timed_out: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub levels: Vec<LevelVisit>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+}
+
+impl ProcessMetrics {
+    fn new(id: u32, priority: u32, arrival: u64, class: Option<String>) -> Self {
+        Self {
+            id,
+            priority,
+            arrival,
+            first_run: None,
+            finish: None,
+            cpu: 0,
+            dispatches: 0,
+            turnaround: None,
+            waiting: None,
+            response: None,
+            io: 0,
+            interactions: 0,
+            mean_interaction_response: None,
+            p99_interaction_response: None,
+            mean_latency: None,
+            max_latency: None,
+            deadline: None,
+            timed_out: None,
+            levels: Vec::new(),
+            class,
+        }
+    }
+}
+
+/// A stretch of time a process spent at one queue level of a multi-level
+/// scheduler, from `entered` until `exited` (`None` while it is still there).
+///
+/// The simulator only sees a process's level when it is dispatched, so a
+/// process is taken to have moved to a new level when it last left the CPU
+/// (which is when demotions happen), or when it arrived. A promotion while
+/// it waits is therefore counted from when it started waiting.
+///
+/// # Example
+/// ```
+/// use scheduler::event::{Event, EventKind};
+/// use scheduler::metrics::{LevelVisit, Metrics};
+///
+/// let mut metrics = Metrics::new();
+/// let mut at = |time, kind, level: Option<u32>| {
+///     metrics.record(&Event { time, id: 1, kind });
+///     if let Some(level) = level {
+///         metrics.note_level(1, level);
+///     }
+/// };
+/// at(0, EventKind::Arrive { priority: 0 }, None);
+/// at(2, EventKind::Dispatch { quantum: 4 }, Some(0));
+/// at(6, EventKind::Preempt, None);
+/// at(9, EventKind::Dispatch { quantum: 8 }, Some(1));
+/// at(17, EventKind::Preempt, None);
+/// at(20, EventKind::Dispatch { quantum: 8 }, Some(1));
+/// at(23, EventKind::Finish, None);
+/// assert_eq!(
+///     metrics.process(1).unwrap().levels,
+///     [
+///         LevelVisit { level: 0, entered: 0, exited: Some(6) },
+///         LevelVisit { level: 1, entered: 6, exited: Some(23) },
+///     ]
+/// );
+/// assert_eq!(metrics.residence(1), [(0, 6), (1, 17)].into());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LevelVisit {
+    pub level: u32,
+    pub entered: u64,
+    pub exited: Option<u64>,
+}
+
+/// Run-wide metrics. Means are taken over completed processes only.
+///
+/// `idle` counts the ticks the idle task ran because no process was ready;
+/// `utilization` is the share of the makespan some process was running.
+/// `peak_heap` is the most heap memory the scheduler reported holding (see
+/// [`Schedule::heap_size`](crate::Schedule::heap_size)), in bytes.
+/// When the metrics have a measurement [`Window`], only processes arriving
+/// inside it are counted and `makespan` is the part of the window the run
+/// covered. `deadlines` counts the processes with a deadline, `timed_out`
+/// those of them that timed out, and `within_deadline` is the share that
+/// finished in time, `0` without deadlines. `classes` breaks the
+/// per-process means down by job class, and is empty when no job has a
+/// class.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Aggregates {
+    pub processes: usize,
+    pub completed: usize,
+    pub makespan: u64,
+    pub cpu_time: u64,
+    pub mean_turnaround: f64,
+    pub mean_waiting: f64,
+    pub mean_response: f64,
+    pub throughput: f64,
+    pub utilization: f64,
+    #[serde(default)]
+    pub idle: u64,
+    #[serde(default)]
+    pub interactions: usize,
+    #[serde(default)]
+    pub mean_interaction_response: f64,
+    #[serde(default)]
+    pub p99_interaction_response: u64,
+    #[serde(default)]
+    pub mean_latency: f64,
+    #[serde(default)]
+    pub p99_latency: u64,
+    #[serde(default)]
+    pub max_latency: u64,
+    #[serde(default)]
+    pub deadlines: usize,
+    #[serde(default)]
+    pub timed_out: usize,
+    #[serde(default)]
+    pub within_deadline: f64,
+    #[serde(default)]
+    pub peak_heap: usize,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub classes: BTreeMap<String, ClassMetrics>,
+}
+
+/// The [`Aggregates`] of the processes of one job class. Means are taken
+/// over the class's completed processes only.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClassMetrics {
+    pub processes: usize,
+    pub completed: usize,
+    pub mean_turnaround: f64,
+    pub mean_waiting: f64,
+    pub mean_response: f64,
+    pub interactions: usize,
+    pub mean_interaction_response: f64,
+    pub p99_interaction_response: u64,
+    #[serde(default)]
+    pub mean_latency: f64,
+    #[serde(default)]
+    pub p99_latency: u64,
+    #[serde(default)]
+    pub max_latency: u64,
+    #[serde(default)]
+    pub deadlines: usize,
+    #[serde(default)]
+    pub timed_out: usize,
+    #[serde(default)]
+    pub within_deadline: f64,
+}
+
+/// The part of a run the aggregates are computed over: ticks `from` up to,
+/// but not including, `until`. Without `until` the window runs to the end.
+///
+/// The default window covers the whole run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Window {
+    pub from: u64,
+    pub until: Option<u64>,
+}
+
+impl Window {
+    /// Returns `true` if `tick` falls inside the window.
+    pub fn contains(&self, tick: u64) -> bool {
+        tick >= self.from && self.until.is_none_or(|until| tick < until)
+    }
+
+    /// Returns `true` for the default window, which covers the whole run.
+    pub fn is_whole_run(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A consistency check of the metrics by Little's law, `L = λW`.
+///
+/// `mean_in_system` (L) is the time-average number of jobs in the system,
+/// counted from arrivals and finishes as they happen; `arrival_rate` (λ) the
+/// arrivals per tick; and `mean_time_in_system` (W) the mean time from
+/// arrival to finish of the jobs, computed per job. Unfinished jobs count as
+/// in the system until the last event. All three cover the whole run, even
+/// with a measurement [`Window`], and then the law holds exactly: a
+/// `relative_error` of `|L - λW| / L` above rounding means the event stream
+/// is inconsistent, e.g. a process arrived or finished twice, or finished
+/// without arriving.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LittlesLaw {
+    pub mean_in_system: f64,
+    pub arrival_rate: f64,
+    pub mean_time_in_system: f64,
+    pub relative_error: f64,
+}
+
+impl LittlesLaw {
+    /// Whether the law holds, up to floating-point rounding.
+    pub fn holds(&self) -> bool {
+        self.relative_error < 1e-9
+    }
+}
+
+/// Accumulates [`ProcessMetrics`] from events as a simulation runs.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    by_id: BTreeMap<u32, ProcessMetrics>,
+    window: Window,
+    last_time: u64,
+    //ticks inside the window spent running a process
+    busy: u64,
+    idle: u64,
+    //tick each process blocked or woke at, until the matching wake or dispatch
+    blocked_since: BTreeMap<u32, u64>,
+    woke_at: BTreeMap<u32, u64>,
+    //every interaction response time, sorted, per process
+    interaction_responses: BTreeMap<u32, Vec<u64>>,
+    //tick each ready process last became ready at, until its next dispatch
+    ready_since: BTreeMap<u32, u64>,
+    //every scheduling latency, sorted, per process
+    latencies: BTreeMap<u32, Vec<u64>>,
+    peak_heap: usize,
+    //tick each process last left the CPU at
+    left_cpu: BTreeMap<u32, u64>,
+    //class label of each labeled process, including those yet to arrive
+    classes: BTreeMap<u32, String>,
+    //jobs in the system, counted by arrivals and finishes alone, the tick
+    //that count last changed at, and its integral over time up to that tick
+    in_system: u64,
+    in_system_since: u64,
+    job_ticks: u64,
+    arrivals: u64,
+    //how each group with a CPU limit fared, once noted
+    throttling: Vec<GroupStats>,
+    //how often a timer of its own frequency interrupted the run, once noted
+    timer: Option<TimerStats>,
+}
+
+impl Metrics {
+    /// Creates an empty collector.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty collector whose aggregates only cover `window`.
+    pub fn with_window(window: Window) -> Self {
+        Self { window, ..Self::default() }
+    }
+
+    /// Returns the window the aggregates are computed over.
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// Updates the metrics with one event.
+    pub fn record(&mut self, event: &Event) {
+        self.last_time = self.last_time.max(event.time);
+        if event.kind == EventKind::Idle {
+            if self.window.contains(event.time) {
+                self.idle += 1;
+            }
+            self.last_time = self.last_time.max(event.time + 1);
+            return;
+        }
+        if matches!(event.kind, EventKind::Arrive { .. } | EventKind::Finish | EventKind::Timeout) {
+            self.job_ticks += self.in_system * event.time.saturating_sub(self.in_system_since);
+            self.in_system_since = self.in_system_since.max(event.time);
+            match event.kind {
+                EventKind::Finish | EventKind::Timeout => self.in_system = self.in_system.saturating_sub(1),
+                _ => {
+                    self.in_system += 1;
+                    self.arrivals += 1;
+                }
+            }
+        }
+        if let EventKind::Arrive { priority } = event.kind {
+            self.ready_since.insert(event.id, event.time);
+            self.by_id
+                .entry(event.id)
+                .or_insert_with(|| ProcessMetrics::new(event.id, priority, event.time, self.classes.get(&event.id).cloned()));
+            return;
+        }
+        let Some(p) = self.by_id.get_mut(&event.id) else {
+            return;
+        };
+        match event.kind {
+            EventKind::Arrive { .. } | EventKind::Idle | EventKind::Renice { .. } => {}
+            EventKind::Throttle => {
+                self.ready_since.remove(&event.id);
+            }
+            EventKind::Unthrottle => {
+                self.ready_since.insert(event.id, event.time);
+            }
+            EventKind::Dispatch { .. } => {
+                p.dispatches += 1;
+                if p.first_run.is_none() {
+                    p.first_run = Some(event.time);
+                    p.response = Some(event.time - p.arrival);
+                }
+                if let Some(woke) = self.woke_at.remove(&event.id) {
+                    let samples = self.interaction_responses.entry(event.id).or_default();
+                    let response = event.time - woke;
+                    samples.insert(samples.partition_point(|&r| r <= response), response);
+                    p.interactions += 1;
+                    p.mean_interaction_response = Some(mean(samples));
+                    p.p99_interaction_response = Some(p99(samples));
+                }
+                if let Some(ready) = self.ready_since.remove(&event.id) {
+                    let samples = self.latencies.entry(event.id).or_default();
+                    let latency = event.time - ready;
+                    samples.insert(samples.partition_point(|&l| l <= latency), latency);
+                    p.mean_latency = Some(mean(samples));
+                    p.max_latency = samples.last().copied();
+                }
+            }
+            EventKind::Preempt => {
+                self.left_cpu.insert(event.id, event.time);
+                self.ready_since.insert(event.id, event.time);
+            }
+            EventKind::Block => {
+                self.ready_since.remove(&event.id);
+                self.blocked_since.insert(event.id, event.time);
+                self.left_cpu.insert(event.id, event.time);
+            }
+            EventKind::Wake => {
+                if let Some(since) = self.blocked_since.remove(&event.id) {
+                    p.io += event.time - since;
+                }
+                self.woke_at.insert(event.id, event.time);
+                self.ready_since.insert(event.id, event.time);
+            }
+            EventKind::Execute => {
+                p.cpu += 1;
+                if self.window.contains(event.time) {
+                    self.busy += 1;
+                }
+                // a tick covers [time, time + 1)
+                self.last_time = self.last_time.max(event.time + 1);
+            }
+            EventKind::Finish => {
+                let turnaround = event.time - p.arrival;
+                p.finish = Some(event.time);
+                p.turnaround = Some(turnaround);
+                p.waiting = Some(turnaround.saturating_sub(p.cpu + p.io));
+                if let Some(visit) = p.levels.last_mut().filter(|visit| visit.exited.is_none()) {
+                    visit.exited = Some(event.time);
+                }
+                self.left_cpu.remove(&event.id);
+                self.ready_since.remove(&event.id);
+            }
+            EventKind::Timeout => {
+                p.timed_out = Some(event.time);
+                if let Some(visit) = p.levels.last_mut().filter(|visit| visit.exited.is_none()) {
+                    visit.exited = Some(event.time);
+                }
+                self.left_cpu.remove(&event.id);
+                self.ready_since.remove(&event.id);
+                self.blocked_since.remove(&event.id);
+                self.woke_at.remove(&event.id);
+            }
+        }
+    }
+
+    /// Notes how much heap memory the scheduler holds right now, keeping the
+    /// peak for [`Aggregates::peak_heap`].
+    pub fn note_heap_size(&mut self, bytes: usize) {
+        self.peak_heap = self.peak_heap.max(bytes);
+    }
+
+    /// Notes how each group with a CPU limit fared, for the summary, see
+    /// [`crate::bandwidth`].
+    pub fn note_throttling(&mut self, stats: Vec<GroupStats>) {
+        self.throttling = stats;
+    }
+
+    /// How each group with a CPU limit fared, as noted.
+    pub fn throttling(&self) -> &[GroupStats] {
+        &self.throttling
+    }
+
+    /// Notes how often the timer interrupted a run with a timer frequency
+    /// of its own or a tickless one, for the summary, see
+    /// [`SimulationBuilder::hz`](crate::simulation::SimulationBuilder::hz).
+    pub fn note_timer(&mut self, stats: TimerStats) {
+        self.timer = Some(stats);
+    }
+
+    /// How often the timer interrupted the run, as noted.
+    pub fn timer(&self) -> Option<TimerStats> {
+        self.timer
+    }
+
+    /// Labels process `id` with its job class, before or after it arrives.
+    pub fn note_class(&mut self, id: u32, class: String) {
+        if let Some(p) = self.by_id.get_mut(&id) {
+            p.class = Some(class.clone());
+        }
+        self.classes.insert(id, class);
+    }
+
+    /// Notes that process `id`, which has arrived, times out unless it has
+    /// finished by tick `deadline`.
+    pub fn note_deadline(&mut self, id: u32, deadline: u64) {
+        if let Some(p) = self.by_id.get_mut(&id) {
+            p.deadline = Some(deadline);
+        }
+    }
+
+    /// Notes that process `id` was just dispatched from queue `level`, see
+    /// [`LevelVisit`].
+    pub fn note_level(&mut self, id: u32, level: u32) {
+        let Some(p) = self.by_id.get_mut(&id) else {
+            return;
+        };
+        let current = p.levels.last_mut().filter(|visit| visit.exited.is_none());
+        if current.as_ref().is_some_and(|visit| visit.level == level) {
+            return;
+        }
+        let moved = self.left_cpu.get(&id).copied().unwrap_or(p.arrival);
+        let entered = match current {
+            Some(visit) => {
+                let moved = moved.max(visit.entered);
+                visit.exited = Some(moved);
+                moved
+            }
+            None => moved,
+        };
+        p.levels.push(LevelVisit { level, entered, exited: None });
+    }
+
+    /// Returns the ticks process `id` spent at each queue level, by level. A
+    /// level the process is still at counts up to the last event. Empty
+    /// unless levels were noted.
+    pub fn residence(&self, id: u32) -> BTreeMap<u32, u64> {
+        let mut ticks = BTreeMap::new();
+        for visit in self.by_id.get(&id).map_or(&[][..], |p| &p.levels) {
+            *ticks.entry(visit.level).or_default() += visit.exited.unwrap_or(self.last_time) - visit.entered;
+        }
+        ticks
+    }
+
+    /// Returns the per-process metrics, ordered by process id.
+    pub fn processes(&self) -> Vec<ProcessMetrics> {
+        self.by_id.values().cloned().collect()
+    }
+
+    /// Returns the metrics for one process, if it has arrived.
+    pub fn process(&self, id: u32) -> Option<&ProcessMetrics> {
+        self.by_id.get(&id)
+    }
+
+    /// Computes the run-wide aggregates.
+    pub fn aggregates(&self) -> Aggregates {
+        let measured: Vec<&ProcessMetrics> =
+            self.by_id.values().filter(|p| self.window.contains(p.arrival)).collect();
+        let mut by_class: BTreeMap<String, Vec<&ProcessMetrics>> = BTreeMap::new();
+        for p in &measured {
+            if let Some(class) = &p.class {
+                by_class.entry(class.clone()).or_default().push(p);
+            }
+        }
+        let all = self.class_metrics(&measured);
+        let cpu_time = self.busy;
+        let end = self.window.until.map_or(self.last_time, |until| until.min(self.last_time));
+        let makespan = end.saturating_sub(self.window.from);
+        let per_tick = |n: f64| if makespan == 0 { 0.0 } else { n / makespan as f64 };
+        Aggregates {
+            processes: all.processes,
+            completed: all.completed,
+            makespan,
+            cpu_time,
+            mean_turnaround: all.mean_turnaround,
+            mean_waiting: all.mean_waiting,
+            mean_response: all.mean_response,
+            throughput: per_tick(all.completed as f64),
+            utilization: per_tick(cpu_time as f64),
+            idle: self.idle,
+            interactions: all.interactions,
+            mean_interaction_response: all.mean_interaction_response,
+            p99_interaction_response: all.p99_interaction_response,
+            mean_latency: all.mean_latency,
+            p99_latency: all.p99_latency,
+            max_latency: all.max_latency,
+            deadlines: all.deadlines,
+            timed_out: all.timed_out,
+            within_deadline: all.within_deadline,
+            peak_heap: self.peak_heap,
+            classes: by_class.into_iter().map(|(class, processes)| (class, self.class_metrics(&processes))).collect(),
+        }
+    }
+
+    /// Checks the run against Little's law, see [`LittlesLaw`]. `None` before
+    /// anything has arrived or any time has passed.
+    ///
+    /// # Example
+    /// ```
+    /// use scheduler::event::{Event, EventKind};
+    /// use scheduler::metrics::Metrics;
+    ///
+    /// let mut metrics = Metrics::new();
+    /// for (time, id, kind) in [
+    ///     (0, 1, EventKind::Arrive { priority: 0 }),
+    ///     (0, 2, EventKind::Arrive { priority: 0 }),
+    ///     (3, 1, EventKind::Finish),
+    ///     (4, 2, EventKind::Finish),
+    /// ] {
+    ///     metrics.record(&Event { time, id, kind });
+    /// }
+    /// let law = metrics.littles_law().unwrap();
+    /// assert_eq!((law.mean_in_system, law.arrival_rate, law.mean_time_in_system), (1.75, 0.5, 3.5));
+    /// assert_eq!(law.relative_error, 0.0);
+    ///
+    /// // P1 finishing twice takes P3 out of the count while it is still there
+    /// metrics.record(&Event { time: 4, id: 3, kind: EventKind::Arrive { priority: 0 } });
+    /// metrics.record(&Event { time: 4, id: 1, kind: EventKind::Finish });
+    /// metrics.record(&Event { time: 8, id: 3, kind: EventKind::Finish });
+    /// assert!(metrics.littles_law().unwrap().relative_error > 0.1);
+    /// ```
+    pub fn littles_law(&self) -> Option<LittlesLaw> {
+        if self.last_time == 0 || self.by_id.is_empty() {
+            return None;
+        }
+        let elapsed = self.last_time as f64;
+        let job_ticks = self.job_ticks + self.in_system * self.last_time.saturating_sub(self.in_system_since);
+        let in_system: u64 =
+            self.by_id.values().map(|p| p.finish.or(p.timed_out).unwrap_or(self.last_time).saturating_sub(p.arrival)).sum();
+        let mean_in_system = job_ticks as f64 / elapsed;
+        let arrival_rate = self.arrivals as f64 / elapsed;
+        let mean_time_in_system = in_system as f64 / self.by_id.len() as f64;
+        let predicted = arrival_rate * mean_time_in_system;
+        let relative_error = match mean_in_system {
+            0.0 if predicted == 0.0 => 0.0,
+            0.0 => f64::INFINITY,
+            l => (l - predicted).abs() / l,
+        };
+        Some(LittlesLaw { mean_in_system, arrival_rate, mean_time_in_system, relative_error })
+    }
+
+    /// Computes the per-process means over `measured`.
+    fn class_metrics(&self, measured: &[&ProcessMetrics]) -> ClassMetrics {
+        let completed: Vec<&ProcessMetrics> = measured.iter().copied().filter(|p| p.finish.is_some()).collect();
+        let mut all: Vec<u64> = measured
+            .iter()
+            .filter_map(|p| self.interaction_responses.get(&p.id))
+            .flatten()
+            .copied()
+            .collect();
+        all.sort_unstable();
+        let mut latencies: Vec<u64> =
+            measured.iter().filter_map(|p| self.latencies.get(&p.id)).flatten().copied().collect();
+        latencies.sort_unstable();
+        let deadlines = measured.iter().filter(|p| p.deadline.is_some()).count();
+        let in_time = measured.iter().filter(|p| p.deadline.is_some() && p.finish.is_some()).count();
+        let mean_of = |f: &dyn Fn(&ProcessMetrics) -> Option<u64>| {
+            if completed.is_empty() {
+                0.0
+            } else {
+                completed.iter().filter_map(|p| f(p)).sum::<u64>() as f64 / completed.len() as f64
+            }
+        };
+        ClassMetrics {
+            processes: measured.len(),
+            completed: completed.len(),
+            mean_turnaround: mean_of(&|p| p.turnaround),
+            mean_waiting: mean_of(&|p| p.waiting),
+            mean_response: mean_of(&|p| p.response),
+            interactions: all.len(),
+            mean_interaction_response: mean(&all),
+            p99_interaction_response: p99(&all),
+            mean_latency: mean(&latencies),
+            p99_latency: p99(&latencies),
+            max_latency: latencies.last().copied().unwrap_or(0),
+            deadlines,
+            timed_out: measured.iter().filter(|p| p.timed_out.is_some()).count(),
+            within_deadline: if deadlines == 0 { 0.0 } else { in_time as f64 / deadlines as f64 },
+        }
+    }
+
+    /// Renders a human-readable summary table, with times shown in `unit`.
+    ///
+    /// # Example
+    /// ```
+    /// use scheduler::clock::TimeUnit;
+    /// use scheduler::event::{Event, EventKind};
+    /// use scheduler::metrics::Metrics;
+    ///
+    /// let mut metrics = Metrics::new();
+    /// metrics.record(&Event { time: 0, id: 1, kind: EventKind::Arrive { priority: 0 } });
+    /// metrics.record(&Event { time: 0, id: 1, kind: EventKind::Dispatch { quantum: 0 } });
+    /// metrics.record(&Event { time: 0, id: 1, kind: EventKind::Execute });
+    /// metrics.record(&Event { time: 1, id: 1, kind: EventKind::Finish });
+    /// let summary = metrics.summary(TimeUnit::Us);
+    /// assert!(summary.contains("mean turnaround: 1us"));
+    /// ```
+    pub fn summary(&self, unit: TimeUnit) -> String {
+        let time = |ticks: u64| unit.format_ns(ticks * unit.nanos());
+        let opt = |ticks: Option<u64>| ticks.map_or("-".to_string(), time);
+        let mean = |ticks: f64| unit.format_ns((ticks * unit.nanos() as f64).round() as u64);
+        let mut out = format!(
+            "{:>6} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+            "id", "priority", "arrival", "finish", "turnaround", "waiting", "response"
+        );
+        for p in self.by_id.values() {
+            out += &format!(
+                "{:>6} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+                p.id,
+                p.priority,
+                time(p.arrival),
+                opt(p.finish),
+                opt(p.turnaround),
+                opt(p.waiting),
+                opt(p.response)
+            );
+        }
+        let agg = self.aggregates();
+        if !self.window.is_whole_run() {
+            let until = self.window.until.map_or("end".to_string(), time);
+            out += &format!(
+                "measured: {} to {} ({} of {} processes arrived in the window)\n",
+                time(self.window.from),
+                until,
+                agg.processes,
+                self.by_id.len()
+            );
+        }
+        out += &format!("completed: {}/{}\n", agg.completed, agg.processes);
+        out += &format!("makespan: {}\n", time(agg.makespan));
+        out += &format!("mean turnaround: {}\n", mean(agg.mean_turnaround));
+        out += &format!("mean waiting: {}\n", mean(agg.mean_waiting));
+        out += &format!("mean response: {}\n", mean(agg.mean_response));
+        out += &format!(
+            "scheduling latency: mean {}, p99 {}, max {}\n",
+            mean(agg.mean_latency),
+            time(agg.p99_latency),
+            time(agg.max_latency)
+        );
+        if agg.deadlines > 0 {
+            out += &format!(
+                "deadlines: {:.1}% finished in time, {} of {} timed out\n",
+                agg.within_deadline * 100.0,
+                agg.timed_out,
+                agg.deadlines
+            );
+        }
+        out += &format!("utilization: {:.1}% (idle {})\n", agg.utilization * 100.0, time(agg.idle));
+        if let Some(law) = self.littles_law() {
+            out += &format!(
+                "Little's law: L = {:.2} jobs, λW = {:.4}/tick × {:.2} ticks = {:.2} (off by {:.1}%{})\n",
+                law.mean_in_system,
+                law.arrival_rate,
+                law.mean_time_in_system,
+                law.arrival_rate * law.mean_time_in_system,
+                law.relative_error * 100.0,
+                if law.holds() { "" } else { ", events are inconsistent" }
+            );
+        }
+        if agg.interactions > 0 {
+            out += &format!(
+                "interaction response: mean {}, p99 {} over {} interactions\n",
+                mean(agg.mean_interaction_response),
+                time(agg.p99_interaction_response),
+                agg.interactions
+            );
+        }
+        if agg.peak_heap > 0 {
+            out += &format!("scheduler heap: peak {} bytes\n", agg.peak_heap);
+        }
+        if !agg.classes.is_empty() {
+            let width = agg.classes.keys().map(String::len).max().unwrap_or(0).max("class".len());
+            out += "by class:\n";
+            out += &format!(
+                "{:>width$} {:>9} {:>10} {:>10} {:>10}\n",
+                "class", "completed", "turnaround", "waiting", "response"
+            );
+            for (class, metrics) in &agg.classes {
+                out += &format!(
+                    "{:>width$} {:>9} {:>10} {:>10} {:>10}\n",
+                    class,
+                    format!("{}/{}", metrics.completed, metrics.processes),
+                    mean(metrics.mean_turnaround),
+                    mean(metrics.mean_waiting),
+                    mean(metrics.mean_response)
+                );
+            }
+        }
+        if !self.throttling.is_empty() {
+            let width = self.throttling.iter().map(|s| s.group.len()).max().unwrap_or(0).max("group".len());
+            out += "throttling:\n";
+            out += &format!("{:>width$} {:>12} {:>8} {:>9} {:>10}\n", "group", "cpu.max", "periods", "throttled", "time");
+            for stats in &self.throttling {
+                out += &format!(
+                    "{:>width$} {:>12} {:>8} {:>9} {:>10}\n",
+                    stats.group,
+                    stats.max.to_string(),
+                    stats.periods,
+                    stats.throttled,
+                    time(stats.throttled_ticks)
+                );
+            }
+        }
+        match self.timer {
+            Some(timer) if timer.tickless => {
+                let difference = match timer.interrupts > timer.periodic {
+                    true => format!("{} more", timer.interrupts - timer.periodic),
+                    false => format!("{} avoided", timer.avoided()),
+                };
+                out += &format!(
+                    "timer: tickless, {} interrupts; a periodic tick every {} would have taken {}, {}\n",
+                    timer.interrupts,
+                    time(timer.period),
+                    timer.periodic,
+                    difference
+                );
+            }
+            Some(TimerStats { hz: Some(hz), period, interrupts, .. }) => {
+                out += &format!("timer: {} HZ, an interrupt every {}, {} interrupts\n", hz, time(period), interrupts);
+            }
+            _ => {}
+        }
+        let levels = self.by_id.values().flat_map(|p| &p.levels).map(|visit| visit.level + 1).max().unwrap_or(0);
+        if levels > 0 {
+            out += "time per level:\n";
+            out += &format!("{:>6}", "id");
+            for level in 0..levels {
+                out += &format!(" {:>10}", format!("L{}", level));
+            }
+            out.push('\n');
+            for p in self.by_id.values() {
+                let residence = self.residence(p.id);
+                out += &format!("{:>6}", p.id);
+                for level in 0..levels {
+                    out += &format!(" {:>10}", residence.get(&level).map_or("-".to_string(), |&ticks| time(ticks)));
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+fn mean(samples: &[u64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().sum::<u64>() as f64 / samples.len() as f64
+}
+
+//nearest-rank 99th percentile of sorted samples
+fn p99(sorted: &[u64]) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    sorted[(sorted.len() * 99).div_ceil(100) - 1]
+}
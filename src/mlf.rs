@@ -1,7 +1,11 @@
-use crate::{Schedule, PCB, CLOCK};// <-- Import Job from crate root
+#[allow(unused_imports)]
+use crate::{Complexity, Schedule, SchedulerInfo, PCB, TimeSlice, CLOCK};// <-- Import Job from crate root
+use crate::config::MlfqConfig;
 
 pub struct MLFSchedule {
     implemented: bool,
+    #[allow(dead_code)] // read this in your implementation
+    config: MlfqConfig,
 }
 
 impl MLFSchedule {
@@ -11,9 +15,29 @@ impl MLFSchedule {
     /// A new [`MLFSchedule`] with the elements in its struct set to initial values.
     ///
     pub fn new() -> Self {
-        Self {
-            implemented: false,
-        }
+        Self::with_config(MlfqConfig::default())
+    }
+
+    /// Creates a new instance of the MLF scheduler using `config`.
+    ///
+    /// The simulator passes in the configuration from `--config`. Read the
+    /// level quanta and the promotion and demotion thresholds from it instead
+    /// of hard-coding them; the default is the configuration the assignment
+    /// describes. [`MlfqConfig::demotes`] applies the demotion threshold the
+    /// way `config.demotion` asks for. `config.rules` switches on further
+    /// OSTEP rules, such as a periodic boost or a boost on I/O (see
+    /// [`MlfqRules`](crate::config::MlfqRules)); [`MlfqConfig::level_after`]
+    /// works out the level a process goes back to under all of them, so
+    /// supporting the rules lets `scheduler experiment mlfq-rules` compare
+    /// them on your scheduler.
+    pub fn with_config(config: MlfqConfig) -> Self {
+        Self { implemented: false, config }
+    }
+}
+
+impl Default for MLFSchedule {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -34,7 +58,7 @@ impl Schedule for MLFSchedule {
     /// You do not need to maintain this struct element or functionality if you implement this
     /// scheduler, but if you don't this is the behavior it should have when submitted for
     /// grading if not implemented.
-    fn add_process(&mut self, mut process: PCB) -> bool{
+    fn add_process(&mut self, _process: PCB) -> bool{
         if !self.implemented {
             println!("Not Implemented");
             std::process::exit(0);
@@ -45,10 +69,11 @@ impl Schedule for MLFSchedule {
     /// Retrieves the next process to run from the scheduler.
     ///
     /// # Returns
-    /// A tuple `(Option<PCB>, u32)` where:
+    /// A tuple `(Option<PCB>, TimeSlice)` where:
     /// - The first element is the next process to run, or `None` if no process is available.
-    /// - The second element is a `u32` value (for example, representing the time slice,
-    ///   priority, or cycle count associated with the returned process).
+    /// - The second element is the [`TimeSlice`] the returned process may run for:
+    ///   `TimeSlice::Ticks(n)` to preempt it after `n` ticks, or `TimeSlice::Infinite`
+    ///   to run it to completion (FCFS).
     ///
     /// # Behavior
     /// If the scheduler has not been implemented yet (`self.implemented == false`),
@@ -56,12 +81,12 @@ impl Schedule for MLFSchedule {
     /// You do not need to maintain this struct element or functionality if you implement this
     /// scheduler, but if you don't this is the behavior it should have when submitted for
     /// grading if not implemented.
-    fn next_process(&mut self) -> (Option<PCB>, u32){
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice){
         if !self.implemented {
             println!("Not Implemented");
             std::process::exit(0);
         }
-        (None,0)
+        (None,TimeSlice::Infinite)
     }
     /// Checks whether the scheduler currently has any processes pending.
     ///
@@ -82,6 +107,19 @@ impl Schedule for MLFSchedule {
         }
         false
     }
+
+    /// Describes the MLFQ the assignment asks for, with the configured
+    /// quanta. Promoting processes that waited too long keeps any from
+    /// starving, at the cost of looking at every waiting process.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: self.config.quanta.iter().any(|&quantum| quantum > 0),
+            starvation_free: true,
+            levels: self.config.quanta.len(),
+            default_quantum: TimeSlice::from_raw(self.config.quanta.first().copied().unwrap_or(0)),
+            dispatch: Complexity::Linear,
+        })
+    }
 }
 
 impl MLFSchedule {
@@ -104,8 +142,10 @@ impl MLFSchedule {
     /// # Behavior
     /// Currently, this method is not implemented and always returns `false`.
     /// Implementations should determine if a process has exceed the max running time
-    /// and if so implement the reverse feedback and return true that it should be interrupted
-    pub fn interrupt(&mut self, mut process: PCB, mut priority: u32) -> bool{
+    /// and if so implement the reverse feedback and return true that it should be interrupted.
+    /// With `demotion = "allotment"` the time the process already used at its level in
+    /// earlier dispatches counts as well, see [`MlfqConfig::demotes`].
+    pub fn interrupt(&mut self, _process: PCB, _priority: u32) -> bool{
         false
     }
     //Any additional helper functions you'd like to have
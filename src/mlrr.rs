@@ -1,7 +1,10 @@
-use crate::{Schedule, PCB};
+use crate::{Complexity, Schedule, SchedulerInfo, PCB, TimeSlice};
+use crate::config::MlrrConfig;
 
 pub struct MLRRSchedule {
     implemented: bool,
+    #[allow(dead_code)] // read this in your implementation
+    config: MlrrConfig,
 }
 
 impl MLRRSchedule {
@@ -11,9 +14,22 @@ impl MLRRSchedule {
     /// A new [`MLRRchedule`] with the elements in its struct set to initial values.
     ///
     pub fn new() -> Self {
-        Self {
-            implemented: false,
-        }
+        Self::with_config(MlrrConfig::default())
+    }
+
+    /// Creates a new instance of the MLRR scheduler using `config`.
+    ///
+    /// The simulator passes in the configuration from `--config`. Read the
+    /// number of levels and their quanta from it instead of hard-coding them;
+    /// the default is the configuration the assignment describes.
+    pub fn with_config(config: MlrrConfig) -> Self {
+        Self { implemented: false, config }
+    }
+}
+
+impl Default for MLRRSchedule {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -34,7 +50,7 @@ impl Schedule for MLRRSchedule {
     /// You do not need to maintain this struct element or functionality if you implement this
     /// scheduler, but if you don't this is the behavior it should have when submitted for
     /// grading if not implemented.
-    fn add_process(&mut self, mut process: PCB) -> bool{
+    fn add_process(&mut self, _process: PCB) -> bool{
         if !self.implemented {
             println!("Not Implemented");
             std::process::exit(0);
@@ -45,10 +61,11 @@ impl Schedule for MLRRSchedule {
     /// Retrieves the next process to run from the scheduler.
     ///
     /// # Returns
-    /// A tuple `(Option<PCB>, u32)` where:
+    /// A tuple `(Option<PCB>, TimeSlice)` where:
     /// - The first element is the next process to run, or `None` if no process is available.
-    /// - The second element is a `u32` value (for example, representing the time slice,
-    ///   priority, or cycle count associated with the returned process).
+    /// - The second element is the [`TimeSlice`] the returned process may run for:
+    ///   `TimeSlice::Ticks(n)` to preempt it after `n` ticks, or `TimeSlice::Infinite`
+    ///   to run it to completion (FCFS).
     ///
     /// # Behavior
     /// If the scheduler has not been implemented yet (`self.implemented == false`),
@@ -56,12 +73,12 @@ impl Schedule for MLRRSchedule {
     /// You do not need to maintain this struct element or functionality if you implement this
     /// scheduler, but if you don't this is the behavior it should have when submitted for
     /// grading if not implemented.
-    fn next_process(&mut self) -> (Option<PCB>, u32){
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice){
         if !self.implemented {
             println!("Not Implemented");
             std::process::exit(0);
         }
-        (None,0)
+        (None,TimeSlice::Infinite)
     }
     /// Checks whether the scheduler currently has any processes pending.
     ///
@@ -82,4 +99,17 @@ impl Schedule for MLRRSchedule {
         }
         false
     }
+
+    /// Describes the multi-level round robin the assignment asks for: a
+    /// queue per priority with the configured quanta, visited in turn, so
+    /// every level gets the CPU and no process starves.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: true,
+            starvation_free: true,
+            levels: self.config.quanta.len(),
+            default_quantum: TimeSlice::from_raw(self.config.quanta.first().copied().unwrap_or(0)),
+            dispatch: Complexity::Constant,
+        })
+    }
 }
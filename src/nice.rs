@@ -0,0 +1,66 @@
+//! # Nice Module
+//!
+//! Nice values and their CPU weights, as in Linux.
+//!
+//! A job's nice value runs from `-20` (greediest) to `19` (nicest) and is `0`
+//! unless the workload says otherwise. Like Linux's CFS, the simulator turns
+//! it into a weight through the kernel's `prio_to_weight` table: nice `0`
+//! weighs 1024, and every step of nice is worth about 10% of CPU time
+//! against a job one step apart, since neighbouring weights differ by a
+//! factor of 1.25. Two jobs sharing the CPU fairly get it in proportion to
+//! their weights, which is what the [fair-share report](crate::analysis::fair_share)
+//! measures a scheduler against.
+//!
+//! A workload gives a job its nice value with a `nice=<n>` field on its
+//! line, a `"nice"` field in JSON or a `nice` column in CSV, and changes it
+//! while the job runs with a renice (see [Renice](crate::input#renice)).
+//!
+//! ## Example
+//! ```
+//! use scheduler::nice;
+//!
+//! assert_eq!(nice::weight(0), 1024);
+//! assert_eq!((nice::weight(-20), nice::weight(19)), (88761, 15));
+//! // one step apart, the nicer job gets about 45% of the CPU
+//! let share = nice::weight(1) as f64 / (nice::weight(0) + nice::weight(1)) as f64;
+//! assert!((share - 0.445).abs() < 0.001);
+//!
+//! assert_eq!(nice::parse("-5"), Ok(-5));
+//! assert_eq!(nice::parse("20").unwrap_err(), "nice must be between -20 and 19, got 20");
+//! ```
+
+/// The greediest nice value.
+pub const MIN: i8 = -20;
+
+/// The nicest nice value.
+pub const MAX: i8 = 19;
+
+/// The weight of nice `0`.
+pub const NICE_0_WEIGHT: u32 = 1024;
+
+/// The weight of every nice value from [`MIN`] to [`MAX`], Linux's
+/// `sched_prio_to_weight`.
+pub const PRIO_TO_WEIGHT: [u32; 40] = [
+    88761, 71755, 56483, 46273, 36291, // -20 to -16
+    29154, 23254, 18705, 14949, 11916, // -15 to -11
+    9548, 7620, 6100, 4904, 3906, // -10 to -6
+    3121, 2501, 1991, 1586, 1277, // -5 to -1
+    1024, 820, 655, 526, 423, // 0 to 4
+    335, 272, 215, 172, 137, // 5 to 9
+    110, 87, 70, 56, 45, // 10 to 14
+    36, 29, 23, 18, 15, // 15 to 19
+];
+
+/// The weight of `nice`, clamped to [`MIN`] and [`MAX`].
+pub fn weight(nice: i8) -> u32 {
+    PRIO_TO_WEIGHT[(nice.clamp(MIN, MAX) - MIN) as usize]
+}
+
+/// Parses a nice value, which must be between [`MIN`] and [`MAX`].
+pub fn parse(text: &str) -> Result<i8, String> {
+    match text.trim().parse::<i32>() {
+        Ok(nice) if (MIN as i32..=MAX as i32).contains(&nice) => Ok(nice as i8),
+        Ok(nice) => Err(format!("nice must be between {} and {}, got {}", MIN, MAX, nice)),
+        Err(_) => Err(format!("'{}' is not a nice value", text.trim())),
+    }
+}
@@ -0,0 +1,282 @@
+//! # Plugin Module
+//!
+//! Lets schedulers that live in other crates run in this simulator without
+//! forking it.
+//!
+//! A plugin is a small executable named `scheduler-<name>` that wraps a
+//! [`Schedule`] implementation with the [`scheduler_plugin!`] macro:
+//! ```no_run
+//! use scheduler::{scheduler_plugin, Schedule, TimeSlice, PCB};
+//!
+//! #[derive(Default)]
+//! struct Lifo(Vec<PCB>);
+//!
+//! impl Schedule for Lifo {
+//!     fn add_process(&mut self, process: PCB) -> bool { self.0.push(process); true }
+//!     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop(), TimeSlice::Infinite) }
+//!     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! }
+//!
+//! scheduler_plugin!("lifo", Lifo::default());
+//! ```
+//! Running `scheduler -s lifo -i input.in` then finds `scheduler-lifo` (see
+//! [`find`]), starts it, and drives it like a built-in scheduler.
+//!
+//! ## Protocol
+//! The simulator and the plugin exchange one JSON object per line over the
+//! plugin's stdin and stdout: a [`Request`] from the simulator, answered by a
+//! [`Response`]. Every request carries the simulated time, which the plugin
+//! side copies into its own [`CLOCK`] before calling the scheduler, so a
+//! plugin can use `CLOCK` exactly like a built-in scheduler does. Running
+//! plugins as separate processes means they can be built with any compiler
+//! version and cannot corrupt the simulator's memory.
+//!
+//! ## Example
+//! [`serve`] answers requests from any reader and writer:
+//! ```
+//! use scheduler::plugin::serve;
+//! # use scheduler::{Schedule, TimeSlice, PCB};
+//! # #[derive(Default)]
+//! # struct Fifo(std::collections::VecDeque<PCB>);
+//! # impl Schedule for Fifo {
+//! #     fn add_process(&mut self, process: PCB) -> bool { self.0.push_back(process); true }
+//! #     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop_front(), TimeSlice::Infinite) }
+//! #     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! # }
+//!
+//! let requests = concat!(
+//!     r#"{"call":"hello","protocol":1,"tick_ns":1}"#, "\n",
+//!     r#"{"call":"add_process","now":0,"process":{"id":4,"priority":0,"time_added":null,"time_scheduled":null}}"#, "\n",
+//!     r#"{"call":"next_process","now":0}"#, "\n",
+//!     r#"{"call":"has_process","now":0}"#, "\n",
+//! );
+//! let mut replies = Vec::new();
+//! serve("fifo", Fifo::default(), requests.as_bytes(), &mut replies).unwrap();
+//! let replies = String::from_utf8(replies).unwrap();
+//! let replies: Vec<&str> = replies.lines().collect();
+//! assert_eq!(replies[0], r#"{"reply":"hello","protocol":1,"name":"fifo"}"#);
+//! assert_eq!(replies[1], r#"{"reply":"added","added":true}"#);
+//! assert!(replies[2].contains(r#""id":4"#));
+//! assert_eq!(replies[3], r#"{"reply":"has_process","has_process":false}"#);
+//! ```
+
+use std::cell::RefCell;
+use std::env;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CLOCK, PCB, Schedule, TimeSlice};
+
+/// The protocol version this build speaks.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Environment variable with extra directories to search for plugins, in the
+/// same format as `PATH`. They are searched before `PATH` itself.
+pub const PLUGIN_PATH_VAR: &str = "SCHEDULER_PLUGIN_PATH";
+
+/// A call from the simulator to the plugin. `now` is the simulated time in
+/// nanoseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "call", rename_all = "snake_case")]
+pub enum Request {
+    Hello { protocol: u32, tick_ns: u64 },
+    AddProcess { now: u64, process: PCB },
+    NextProcess { now: u64 },
+    HasProcess { now: u64 },
+}
+
+/// The plugin's answer to a [`Request`] of the same name. The `quantum` of
+/// `NextProcess` is the [`TimeSlice`] written as a number, `0` for infinite,
+/// and its `level` the scheduler's [`Schedule::dispatched_level`], left out
+/// when unknown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reply", rename_all = "snake_case")]
+pub enum Response {
+    Hello { protocol: u32, name: String },
+    Added { added: bool },
+    NextProcess {
+        process: Option<PCB>,
+        quantum: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        level: Option<usize>,
+    },
+    HasProcess { has_process: bool },
+}
+
+/// Answers requests read from `input` by calling `sched`, until `input` ends.
+///
+/// # Errors
+/// Fails on I/O errors, and on requests that are not valid JSON or that ask
+/// for an unsupported protocol version.
+pub fn serve<S, R, W>(name: &str, mut sched: S, input: R, mut output: W) -> io::Result<()>
+where
+    S: Schedule,
+    R: BufRead,
+    W: Write,
+{
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: Request = serde_json::from_str(&line).map_err(io::Error::other)?;
+        let response = match request {
+            Request::Hello { protocol, tick_ns } => {
+                if protocol != PROTOCOL_VERSION {
+                    return Err(io::Error::other(format!("unsupported protocol version {}", protocol)));
+                }
+                CLOCK.set_tick(Duration::from_nanos(tick_ns));
+                Response::Hello { protocol: PROTOCOL_VERSION, name: name.to_string() }
+            }
+            Request::AddProcess { now, process } => {
+                CLOCK.set_now(Duration::from_nanos(now));
+                Response::Added { added: sched.add_process(process) }
+            }
+            Request::NextProcess { now } => {
+                CLOCK.set_now(Duration::from_nanos(now));
+                let (process, slice) = sched.next_process();
+                Response::NextProcess { process, quantum: slice.raw(), level: sched.dispatched_level() }
+            }
+            Request::HasProcess { now } => {
+                CLOCK.set_now(Duration::from_nanos(now));
+                Response::HasProcess { has_process: sched.has_process() }
+            }
+        };
+        serde_json::to_writer(&mut output, &response).map_err(io::Error::other)?;
+        writeln!(output)?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Turns a [`Schedule`] implementation into a plugin executable by generating
+/// its `main`, which serves requests on stdin and stdout.
+///
+/// The first argument is the scheduler's name, the second an expression that
+/// creates the scheduler. See the [module docs](crate::plugin) for an example.
+#[macro_export]
+macro_rules! scheduler_plugin {
+    ($name:expr, $new:expr) => {
+        fn main() {
+            let stdin = ::std::io::stdin().lock();
+            let stdout = ::std::io::stdout().lock();
+            if let Err(err) = $crate::plugin::serve($name, $new, stdin, stdout) {
+                eprintln!("Error: scheduler plugin '{}': {}", $name, err);
+                ::std::process::exit(1);
+            }
+        }
+    };
+}
+
+/// Looks for the plugin executable of the scheduler called `name`.
+///
+/// The executable is `scheduler-<name>` (with the platform's executable
+/// suffix), searched for in [`PLUGIN_PATH_VAR`] and then in `PATH`.
+pub fn find(name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return None;
+    }
+    let file = format!("scheduler-{}{}", name, env::consts::EXE_SUFFIX);
+    [PLUGIN_PATH_VAR, "PATH"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .flat_map(|paths| env::split_paths(&paths).collect::<Vec<_>>())
+        .map(|dir| dir.join(&file))
+        .find(|path| path.is_file())
+}
+
+/// A scheduler running in a plugin process.
+///
+/// Every [`Schedule`] call becomes a request to the plugin. Since the trait
+/// gives no way to report failures, a plugin that dies or answers nonsense
+/// ends the simulation with an error message.
+pub struct PluginSchedule {
+    name: String,
+    child: Child,
+    pipes: RefCell<(ChildStdin, BufReader<ChildStdout>)>,
+    /// the level the plugin reported for its last dispatch
+    level: Option<usize>,
+}
+
+impl PluginSchedule {
+    /// Starts the plugin at `path` and checks that it speaks this protocol.
+    pub fn spawn(path: &Path) -> io::Result<Self> {
+        let mut child = Command::new(path).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let name = path.display().to_string();
+        let plugin = Self { name, child, pipes: RefCell::new((stdin, stdout)), level: None };
+        let hello = Request::Hello { protocol: PROTOCOL_VERSION, tick_ns: CLOCK.tick().as_nanos() as u64 };
+        match plugin.call(&hello)? {
+            Response::Hello { protocol: PROTOCOL_VERSION, .. } => Ok(plugin),
+            other => Err(io::Error::other(format!("unexpected handshake {:?}", other))),
+        }
+    }
+
+    fn call(&self, request: &Request) -> io::Result<Response> {
+        let mut pipes = self.pipes.borrow_mut();
+        let (stdin, stdout) = &mut *pipes;
+        serde_json::to_writer(&mut *stdin, request).map_err(io::Error::other)?;
+        writeln!(stdin)?;
+        stdin.flush()?;
+        let mut line = String::new();
+        if stdout.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "plugin exited"));
+        }
+        serde_json::from_str(&line).map_err(io::Error::other)
+    }
+
+    fn call_or_exit(&self, request: Request) -> Response {
+        self.call(&request).unwrap_or_else(|err| {
+            eprintln!("Error: scheduler plugin '{}' failed: {}", self.name, err);
+            std::process::exit(1);
+        })
+    }
+
+    fn unexpected(&self, response: Response) -> ! {
+        eprintln!("Error: scheduler plugin '{}' sent an unexpected reply: {:?}", self.name, response);
+        std::process::exit(1);
+    }
+}
+
+impl Schedule for PluginSchedule {
+    fn add_process(&mut self, process: PCB) -> bool {
+        match self.call_or_exit(Request::AddProcess { now: CLOCK.now_ns(), process }) {
+            Response::Added { added } => added,
+            other => self.unexpected(other),
+        }
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        match self.call_or_exit(Request::NextProcess { now: CLOCK.now_ns() }) {
+            Response::NextProcess { process, quantum, level } => {
+                self.level = level;
+                (process, TimeSlice::from_raw(quantum))
+            }
+            other => self.unexpected(other),
+        }
+    }
+
+    fn has_process(&self) -> bool {
+        match self.call_or_exit(Request::HasProcess { now: CLOCK.now_ns() }) {
+            Response::HasProcess { has_process } => has_process,
+            other => self.unexpected(other),
+        }
+    }
+
+    fn dispatched_level(&self) -> Option<usize> {
+        self.level
+    }
+}
+
+impl Drop for PluginSchedule {
+    fn drop(&mut self) {
+        //the simulation is over, so the plugin has nothing left to answer
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
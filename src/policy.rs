@@ -0,0 +1,637 @@
+//! # Policy Module
+//!
+//! Single-queue scheduling policies, complete and ready to use on their own
+//! or as the levels of a multi-level scheduler (see [`crate::feedback`]).
+//!
+//! - [`RR`]: round robin with a fixed quantum.
+//! - [`FCFS`]: first come, first served, every process to completion.
+//! - [`SJF`]: shortest job first, non-preemptive, by predicted CPU burst.
+//! - [`SPN`]: shortest process next, non-preemptive, by an estimate of each
+//!   process's CPU time given up front.
+//! - [`SRTF`]: shortest remaining time first by the CPU time given up front,
+//!   preemptive or not, kept in a binary heap for large run queues.
+//!
+//! A scheduler is never told how long a process will run, so [`SJF`]
+//! predicts it the textbook way: each process's next CPU burst is estimated
+//! as the exponential average `τ = αt + (1 - α)τ` of its measured bursts
+//! `t`, starting from an initial guess. A burst is measured from the
+//! dispatch until the next process is dispatched, or until the process is
+//! added back if that comes first. Processes with the same estimate run in
+//! the order they were added.
+//!
+//! [`SPN`] instead plays the textbook's idealized shortest-job scheduler,
+//! which is told how long every job will run. Real systems only ever have
+//! an estimate, so [`SPN::with_error`] spoils the true bursts with a seeded
+//! error of up to a given percentage, to see how much of SPN's advantage
+//! survives estimates that are off.
+//! ```
+//! use scheduler::config::SpnConfig;
+//! use scheduler::policy::SPN;
+//!
+//! let exact = SPN::with_error([(1, 100), (2, 10)], &SpnConfig::default());
+//! assert_eq!((exact.estimate(1), exact.estimate(2)), (100.0, 10.0));
+//!
+//! let noisy = SPN::with_error([(1, 100), (2, 10)], &SpnConfig { error: 50, seed: 3 });
+//! assert!((50.0..=150.0).contains(&noisy.estimate(1)));
+//! assert_ne!(noisy.estimate(1), 100.0);
+//! assert_eq!(noisy.estimate(1), SPN::with_error([(1, 100)], &SpnConfig { error: 50, seed: 3 }).estimate(1));
+//! // a process without an estimate runs last
+//! assert_eq!(noisy.estimate(7), f64::INFINITY);
+//! ```
+//!
+//! [`SPN`] finds the shortest process by scanning its whole queue, which is
+//! as simple as it gets and fine for a few dozen processes, but makes every
+//! dispatch cost `O(n)`. [`SRTF`] keeps its queue in a
+//! [`BinaryHeap`] keyed by remaining time instead, for `O(log n)` dispatches;
+//! the `sjf_queue` benchmark compares the two (`cargo bench -- sjf_queue`).
+//! A heap cannot change the key of an entry in place, so
+//! [`set_remaining`](SRTF::set_remaining) emulates decrease-key: it pushes a
+//! new entry, and the old one is skipped as stale when it comes up. Stale
+//! entries are swept out once they make up half the heap. Non-preemptive,
+//! it dispatches exactly like an exact [`SPN`]:
+//! ```
+//! use scheduler::config::SpnConfig;
+//! use scheduler::policy::{SPN, SRTF};
+//! use scheduler::{PCB, Schedule};
+//!
+//! let bursts = [(1, 30), (2, 10), (3, 20), (4, 10)];
+//! let mut scan = SPN::with_error(bursts, &SpnConfig::default());
+//! let mut heap = SRTF::non_preemptive(bursts);
+//! for id in 1..=4 {
+//!     let pcb = PCB { id, priority: 0, time_added: None, time_scheduled: None };
+//!     scan.add_process(pcb);
+//!     heap.add_process(pcb);
+//! }
+//! let order = |sched: &mut dyn Schedule| (0..4).map(|_| sched.next_process().0.unwrap().id).collect::<Vec<_>>();
+//! // ties go to the process added first
+//! assert_eq!(order(&mut scan), [2, 4, 3, 1]);
+//! assert_eq!(order(&mut heap), [2, 4, 3, 1]);
+//! ```
+//!
+//! A [`Policy`] names one of them in a config file: `rr:<quantum>`, `fcfs`
+//! or `sjf`.
+//!
+//! ## Example
+//! ```
+//! use std::time::Duration;
+//! use scheduler::policy::SJF;
+//! use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! CLOCK.set_now(Duration::ZERO);
+//! let pcb = |id| PCB { id, priority: 0, time_added: None, time_scheduled: None };
+//! let mut sched = SJF::new();
+//! sched.add_process(pcb(1));
+//! sched.add_process(pcb(2));
+//!
+//! // P1 runs for 20 ticks, P2 for 2, then both come back
+//! let (p1, slice) = sched.next_process();
+//! assert_eq!((p1.unwrap().id, slice), (1, TimeSlice::Infinite));
+//! CLOCK.advance(Duration::from_nanos(20));
+//! let (p2, _) = sched.next_process();
+//! CLOCK.advance(Duration::from_nanos(2));
+//! sched.add_process(p2.unwrap());
+//! sched.add_process(p1.unwrap());
+//! assert_eq!((sched.estimate(1), sched.estimate(2)), (15.0, 6.0));
+//! // P2's shorter predicted burst goes first
+//! assert_eq!(sched.next_process().0.unwrap().id, 2);
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::SpnConfig;
+use crate::random::Rng;
+use crate::{CLOCK, Complexity, PCB, Schedule, SchedulerInfo, TimeSlice};
+
+/// One of the policies, as written in a config file.
+///
+/// ## Example
+/// ```
+/// use scheduler::policy::Policy;
+///
+/// assert_eq!("rr:4".parse(), Ok(Policy::RR(4)));
+/// assert_eq!(Policy::SJF.to_string(), "sjf");
+/// assert_eq!("rr:0".parse::<Policy>().unwrap_err(), "rr:0: the quantum must be positive");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Policy {
+    /// [`RR`] with the given quantum.
+    RR(u32),
+    /// [`FCFS`].
+    FCFS,
+    /// [`SJF`] with its default estimate.
+    SJF,
+}
+
+impl Policy {
+    /// A new, empty scheduler following the policy.
+    pub fn build(&self) -> Box<dyn Schedule> {
+        match *self {
+            Policy::RR(quantum) => Box::new(RR::with_quantum(quantum)),
+            Policy::FCFS => Box::new(FCFS::new()),
+            Policy::SJF => Box::new(SJF::new()),
+        }
+    }
+}
+
+impl FromStr for Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.split_once(':') {
+            Some(("rr", quantum)) => match quantum.parse::<u32>() {
+                Ok(0) => Err(format!("{}: the quantum must be positive", s)),
+                Ok(quantum) => Ok(Policy::RR(quantum)),
+                Err(_) => Err(format!("{}: the quantum must be a whole number of ticks", s)),
+            },
+            None if s == "fcfs" => Ok(Policy::FCFS),
+            None if s == "sjf" => Ok(Policy::SJF),
+            _ => Err(format!("{}: unknown policy, expected rr:<quantum>, fcfs or sjf", s)),
+        }
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Policy::RR(quantum) => write!(f, "rr:{}", quantum),
+            Policy::FCFS => f.write_str("fcfs"),
+            Policy::SJF => f.write_str("sjf"),
+        }
+    }
+}
+
+impl TryFrom<String> for Policy {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        s.parse()
+    }
+}
+
+impl From<Policy> for String {
+    fn from(policy: Policy) -> String {
+        policy.to_string()
+    }
+}
+
+/// Round robin: processes take turns in arrival order, each for at most one
+/// quantum.
+pub struct RR {
+    quantum: u32,
+    queue: VecDeque<PCB>,
+}
+
+impl RR {
+    /// Round robin with a quantum of `quantum` ticks.
+    ///
+    /// # Panics
+    /// If `quantum` is `0`; use [`FCFS`] to run processes to completion.
+    pub fn with_quantum(quantum: u32) -> Self {
+        assert!(quantum > 0, "a round-robin quantum of 0 ticks, use FCFS to run to completion");
+        Self { quantum, queue: VecDeque::new() }
+    }
+}
+
+impl Schedule for RR {
+    fn add_process(&mut self, process: PCB) -> bool {
+        self.queue.push_back(process);
+        true
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        (self.queue.pop_front(), TimeSlice::Ticks(self.quantum))
+    }
+
+    fn has_process(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn level_quantum(&self, _level: usize) -> TimeSlice {
+        TimeSlice::Ticks(self.quantum)
+    }
+
+    fn iter_level(&self, _level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        Box::new(self.queue.iter())
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        self.queue.front()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.queue.capacity() * size_of::<PCB>()
+    }
+
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: true,
+            starvation_free: true,
+            levels: 1,
+            default_quantum: TimeSlice::Ticks(self.quantum),
+            dispatch: Complexity::Constant,
+        })
+    }
+}
+
+/// First come, first served: processes run to completion in arrival order.
+#[derive(Default)]
+pub struct FCFS {
+    queue: VecDeque<PCB>,
+}
+
+impl FCFS {
+    /// An empty FCFS queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Schedule for FCFS {
+    fn add_process(&mut self, process: PCB) -> bool {
+        self.queue.push_back(process);
+        true
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        (self.queue.pop_front(), TimeSlice::Infinite)
+    }
+
+    fn has_process(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn iter_level(&self, _level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        Box::new(self.queue.iter())
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        self.queue.front()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.queue.capacity() * size_of::<PCB>()
+    }
+
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: false,
+            starvation_free: true,
+            levels: 1,
+            default_quantum: TimeSlice::Infinite,
+            dispatch: Complexity::Constant,
+        })
+    }
+}
+
+/// Non-preemptive shortest job first by predicted burst, see the
+/// [module documentation](self).
+pub struct SJF {
+    initial: f64,
+    alpha: f64,
+    /// ready processes, in the order they were added
+    ready: Vec<PCB>,
+    /// predicted next CPU burst of every process that has run
+    estimates: BTreeMap<u32, f64>,
+    /// the process last dispatched and the tick it was dispatched at
+    running: Option<(u32, u64)>,
+    /// the length of each process's last burst, once it has ended
+    bursts: BTreeMap<u32, u64>,
+}
+
+impl SJF {
+    /// SJF with the textbook's initial estimate of 10 ticks and `α = 1/2`.
+    pub fn new() -> Self {
+        Self::with_estimate(10.0, 0.5)
+    }
+
+    /// SJF predicting `initial` ticks for a process that has not run yet,
+    /// and weighting the last measured burst by `alpha`, between `0` and `1`.
+    pub fn with_estimate(initial: f64, alpha: f64) -> Self {
+        Self {
+            initial,
+            alpha: alpha.clamp(0.0, 1.0),
+            ready: Vec::new(),
+            estimates: BTreeMap::new(),
+            running: None,
+            bursts: BTreeMap::new(),
+        }
+    }
+
+    /// The predicted next CPU burst of process `id`, in ticks.
+    pub fn estimate(&self, id: u32) -> f64 {
+        self.estimates.get(&id).copied().unwrap_or(self.initial)
+    }
+
+    /// Notes that the process last dispatched stopped running by now.
+    fn settle(&mut self) {
+        if let Some((id, since)) = self.running.take() {
+            self.bursts.insert(id, CLOCK.now_ticks() - since);
+        }
+    }
+}
+
+impl Default for SJF {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Schedule for SJF {
+    fn add_process(&mut self, process: PCB) -> bool {
+        if self.running.is_some_and(|(id, _)| id == process.id) {
+            self.settle();
+        }
+        //a burst that took no time says nothing about the next one
+        if let Some(burst) = self.bursts.remove(&process.id).filter(|&burst| burst > 0) {
+            let estimate = self.alpha * burst as f64 + (1.0 - self.alpha) * self.estimate(process.id);
+            self.estimates.insert(process.id, estimate);
+        }
+        self.ready.push(process);
+        true
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        self.settle();
+        let shortest = self
+            .ready
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| self.estimate(a.id).total_cmp(&self.estimate(b.id)))
+            .map(|(index, _)| index);
+        let Some(index) = shortest else {
+            return (None, TimeSlice::Infinite);
+        };
+        let process = self.ready.remove(index);
+        self.running = Some((process.id, CLOCK.now_ticks()));
+        (Some(process), TimeSlice::Infinite)
+    }
+
+    fn has_process(&self) -> bool {
+        !self.ready.is_empty()
+    }
+
+    fn iter_level(&self, _level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        Box::new(self.ready.iter())
+    }
+
+    fn heap_size(&self) -> usize {
+        self.ready.capacity() * size_of::<PCB>()
+    }
+
+    /// Long jobs can starve while shorter ones keep arriving.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: false,
+            starvation_free: false,
+            levels: 1,
+            default_quantum: TimeSlice::Infinite,
+            dispatch: Complexity::Linear,
+        })
+    }
+}
+
+/// Non-preemptive shortest process next by estimated CPU time, see the
+/// [module documentation](self).
+///
+/// Processes with the same estimate run in the order they were added, and
+/// processes without one, such as the later requests of closed-loop
+/// clients, after every estimated one.
+#[derive(Default)]
+pub struct SPN {
+    /// ready processes, in the order they were added
+    ready: Vec<PCB>,
+    /// the estimated CPU time of every process, in ticks
+    estimates: BTreeMap<u32, f64>,
+}
+
+impl SPN {
+    /// SPN told the estimated CPU time of every process, keyed by ID.
+    pub fn with_estimates(estimates: BTreeMap<u32, f64>) -> Self {
+        Self { ready: Vec::new(), estimates }
+    }
+
+    /// SPN told the `(id, burst)` CPU time of every process, off by up to
+    /// `config.error` percent either way. Each process's error is drawn from
+    /// `config.seed` and its ID alone, so it is the same in every run.
+    pub fn with_error(bursts: impl IntoIterator<Item = (u32, u32)>, config: &SpnConfig) -> Self {
+        let estimates = bursts.into_iter().map(|(id, burst)| {
+            let off = 2.0 * Rng::new(config.seed ^ ((id as u64) << 32)).next_f64() - 1.0;
+            (id, (burst as f64 * (1.0 + off * config.error as f64 / 100.0)).max(0.0))
+        });
+        Self::with_estimates(estimates.collect())
+    }
+
+    /// The estimated CPU time of process `id`, in ticks; infinite when it
+    /// has none.
+    pub fn estimate(&self, id: u32) -> f64 {
+        self.estimates.get(&id).copied().unwrap_or(f64::INFINITY)
+    }
+}
+
+impl Schedule for SPN {
+    fn add_process(&mut self, process: PCB) -> bool {
+        self.ready.push(process);
+        true
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        let shortest = self
+            .ready
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| self.estimate(a.id).total_cmp(&self.estimate(b.id)))
+            .map(|(index, _)| index);
+        (shortest.map(|index| self.ready.remove(index)), TimeSlice::Infinite)
+    }
+
+    fn has_process(&self) -> bool {
+        !self.ready.is_empty()
+    }
+
+    fn iter_level(&self, _level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        Box::new(self.ready.iter())
+    }
+
+    fn heap_size(&self) -> usize {
+        self.ready.capacity() * size_of::<PCB>()
+    }
+
+    /// Long jobs can starve while shorter ones keep arriving.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: false,
+            starvation_free: false,
+            levels: 1,
+            default_quantum: TimeSlice::Infinite,
+            dispatch: Complexity::Linear,
+        })
+    }
+}
+
+/// Shortest remaining time first by CPU time given up front, on a binary
+/// heap, see the [module documentation](self).
+///
+/// The preemptive variant hands out one tick at a time, so that a process
+/// arriving with less time left than the running one takes over at the next
+/// tick. Processes with the same remaining time run in the order they were
+/// added, except that the process just preempted stays ahead of them, as it
+/// would have kept running; processes without a burst run last.
+///
+/// ## Example
+/// ```
+/// use scheduler::policy::SRTF;
+/// use scheduler::simulation::{Job, Simulation};
+/// use scheduler::sink::Recorder;
+///
+/// // P1 arrives while P0 has 6 ticks left, and is shorter
+/// let jobs = [Job::new(0, 0, 8), Job::new(1, 2, 3)];
+/// let finish = |sched: SRTF| {
+///     let mut sim = Simulation::builder(sched).jobs(jobs.clone()).build();
+///     let mut out = Recorder::new();
+///     sim.run(&mut out).unwrap();
+///     (out.metrics().process(0).unwrap().finish, out.metrics().process(1).unwrap().finish)
+/// };
+/// assert_eq!(finish(SRTF::non_preemptive([(0, 8), (1, 3)])), (Some(8), Some(11)));
+/// assert_eq!(finish(SRTF::new([(0, 8), (1, 3)])), (Some(11), Some(5)));
+///
+/// // a better estimate moves a queued process to its new place
+/// let mut sched = SRTF::non_preemptive([(0, 8), (1, 3)]);
+/// sched.set_remaining(0, 2);
+/// assert_eq!(sched.remaining(0), 2);
+/// ```
+pub struct SRTF {
+    preemptive: bool,
+    /// entries of ready processes by remaining time, then by the order they
+    /// were added; an entry whose process has been re-keyed since is stale
+    heap: BinaryHeap<Reverse<(u64, i64, u32)>>,
+    /// ready processes by ID, with the order they were added in
+    queued: BTreeMap<u32, (i64, PCB)>,
+    /// the CPU time every process has left, in ticks
+    remaining: BTreeMap<u32, u64>,
+    /// stale entries still in the heap
+    stale: usize,
+    /// processes added so far, for the order of ties
+    added: i64,
+    /// the process last dispatched and the tick it was dispatched at
+    running: Option<(u32, u64)>,
+}
+
+impl SRTF {
+    /// Preemptive SRTF told the `(id, burst)` CPU time of every process.
+    pub fn new(bursts: impl IntoIterator<Item = (u32, u32)>) -> Self {
+        Self {
+            preemptive: true,
+            heap: BinaryHeap::new(),
+            queued: BTreeMap::new(),
+            remaining: bursts.into_iter().map(|(id, burst)| (id, burst as u64)).collect(),
+            stale: 0,
+            added: 0,
+            running: None,
+        }
+    }
+
+    /// Non-preemptive shortest job first told the `(id, burst)` CPU time of
+    /// every process: the process dispatched runs to completion.
+    pub fn non_preemptive(bursts: impl IntoIterator<Item = (u32, u32)>) -> Self {
+        Self { preemptive: false, ..Self::new(bursts) }
+    }
+
+    /// The CPU time process `id` has left, in ticks, as of its last
+    /// dispatch; `u64::MAX` when it has no burst.
+    pub fn remaining(&self, id: u32) -> u64 {
+        self.remaining.get(&id).copied().unwrap_or(u64::MAX)
+    }
+
+    /// Sets the CPU time process `id` has left to `ticks`, moving it to its
+    /// new place if it is queued.
+    pub fn set_remaining(&mut self, id: u32, ticks: u64) {
+        if self.remaining.insert(id, ticks) == Some(ticks) {
+            return;
+        }
+        if let Some(&(order, _)) = self.queued.get(&id) {
+            self.heap.push(Reverse((ticks, order, id)));
+            self.stale += 1;
+            self.sweep();
+        }
+    }
+
+    /// Charges the process last dispatched for the time it has run by now.
+    fn settle(&mut self) {
+        if let Some((id, since)) = self.running.take()
+            && let Some(remaining) = self.remaining.get_mut(&id)
+        {
+            *remaining = remaining.saturating_sub(CLOCK.now_ticks() - since);
+        }
+    }
+
+    /// Rebuilds the heap from the queued processes once half of it is stale.
+    fn sweep(&mut self) {
+        if self.stale * 2 < self.heap.len() {
+            return;
+        }
+        let entries: Vec<_> = self.queued.iter().map(|(&id, &(order, _))| Reverse((self.remaining(id), order, id))).collect();
+        self.heap = BinaryHeap::from(entries);
+        self.stale = 0;
+    }
+}
+
+impl Schedule for SRTF {
+    fn add_process(&mut self, process: PCB) -> bool {
+        let preempted = self.running.is_some_and(|(id, _)| id == process.id);
+        if preempted {
+            self.settle();
+        }
+        self.added += 1;
+        //the process just preempted goes ahead of every tie, latest first
+        let order = if preempted { -self.added } else { self.added };
+        self.heap.push(Reverse((self.remaining(process.id), order, process.id)));
+        self.queued.insert(process.id, (order, process));
+        true
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        self.settle();
+        let slice = if self.preemptive { TimeSlice::Ticks(1) } else { TimeSlice::Infinite };
+        while let Some(Reverse((remaining, order, id))) = self.heap.pop() {
+            let live = self.queued.get(&id).is_some_and(|&(queued, _)| queued == order) && self.remaining(id) == remaining;
+            if !live {
+                self.stale -= 1;
+                continue;
+            }
+            let (_, process) = self.queued.remove(&id).expect("a live entry is queued");
+            self.running = Some((id, CLOCK.now_ticks()));
+            return (Some(process), slice);
+        }
+        (None, slice)
+    }
+
+    fn has_process(&self) -> bool {
+        !self.queued.is_empty()
+    }
+
+    fn iter_level(&self, _level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        Box::new(self.queued.values().map(|(_, process)| process))
+    }
+
+    fn heap_size(&self) -> usize {
+        self.heap.capacity() * size_of::<Reverse<(u64, i64, u32)>>() + self.queued.len() * size_of::<(u32, (i64, PCB))>()
+    }
+
+    /// Long jobs can starve while shorter ones keep arriving.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: self.preemptive,
+            starvation_free: false,
+            levels: 1,
+            default_quantum: if self.preemptive { TimeSlice::Ticks(1) } else { TimeSlice::Infinite },
+            dispatch: Complexity::Logarithmic,
+        })
+    }
+}
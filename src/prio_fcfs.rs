@@ -0,0 +1,157 @@
+//! # Priority FCFS Module
+//!
+//! A plain non-preemptive priority scheduler, for sanity-checking workloads
+//! and as a reference for what deterministic tie-breaking looks like.
+//!
+//! Every process runs to completion (or until it blocks) once dispatched.
+//! The next process is always the first one in this order:
+//! 1. **priority**, lowest number first, as in the multi-level schedulers
+//!    where level `0` is the highest;
+//! 2. **arrival**, the tick the process was added at, earliest first; a
+//!    process coming back from I/O arrives anew;
+//! 3. **order added**, the process added first among those added at the
+//!    same tick, which for jobs arriving together is the order of the
+//!    workload file, or ID order with `--arrival-order id`.
+//!
+//! That is the tie-breaking rule `--strict` audits, so prio_fcfs is a
+//! reference for it and never reported by it.
+//!
+//! ## Example
+//! Whatever order the processes of a tick are added in, they run by
+//! priority, and within a priority in that order:
+//! ```
+//! use std::time::Duration;
+//! use scheduler::prio_fcfs::PrioFcfsSchedule;
+//! use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! // (id, priority, arrival)
+//! let processes = [(4, 1, 0), (2, 0, 5), (7, 0, 5), (1, 0, 9), (3, 1, 0), (0, 2, 0)];
+//! let expected = [2, 7, 1, 3, 4, 0];
+//!
+//! fn permutations(items: &[(u32, u32, u64)]) -> Vec<Vec<(u32, u32, u64)>> {
+//!     if items.len() <= 1 {
+//!         return vec![items.to_vec()];
+//!     }
+//!     let mut all = Vec::new();
+//!     for i in 0..items.len() {
+//!         let mut rest = items.to_vec();
+//!         let first = rest.remove(i);
+//!         for mut tail in permutations(&rest) {
+//!             tail.insert(0, first);
+//!             all.push(tail);
+//!         }
+//!     }
+//!     all
+//! }
+//!
+//! let orders = permutations(&processes);
+//! assert_eq!(orders.len(), 720);
+//! for order in orders {
+//!     let mut sched = PrioFcfsSchedule::new();
+//!     // add in arrival order, but otherwise in this permutation's order
+//!     let mut order = order;
+//!     order.sort_by_key(|&(_, _, arrival)| arrival);
+//!     for &(id, priority, arrival) in &order {
+//!         CLOCK.set_now(Duration::from_nanos(arrival));
+//!         sched.add_process(PCB { id, priority, time_added: None, time_scheduled: None });
+//!     }
+//!     let mut ran = Vec::new();
+//!     while let (Some(process), quantum) = sched.next_process() {
+//!         assert_eq!(quantum, TimeSlice::Infinite);
+//!         ran.push(process.id);
+//!     }
+//!     order.sort_by_key(|&(_, priority, arrival)| (priority, arrival));
+//!     assert!(ran.iter().eq(order.iter().map(|(id, _, _)| id)));
+//!     // only P2 and P7 tie, and P3 and P4
+//!     assert_eq!([ran[0], ran[1]].map(|id| [2, 7].contains(&id)), [true, true]);
+//!     assert_eq!([ran[2], ran[5]], [1, 0]);
+//! }
+//! ```
+//!
+//! It is what `--strict` expects, with jobs arriving together listed out of
+//! ID order:
+//! ```
+//! use std::time::Duration;
+//! use scheduler::prio_fcfs::PrioFcfsSchedule;
+//! use scheduler::simulation::{Job, Simulation};
+//! use scheduler::sink::Recorder;
+//! use scheduler::strict::StrictSchedule;
+//! use scheduler::CLOCK;
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let jobs = [Job::new(3, 0, 5), Job::new(1, 0, 5), Job::new(4, 2, 1), Job::new(2, 2, 1)];
+//! let mut sim = Simulation::builder(StrictSchedule::new(PrioFcfsSchedule::new(), true)).jobs(jobs).build();
+//! let mut out = Recorder::new();
+//! sim.run(&mut out).unwrap();
+//! assert_eq!(out.metrics().process(3).unwrap().finish, Some(5));
+//! assert!(sim.into_scheduler().violations().is_empty());
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::{CLOCK, Complexity, PCB, Schedule, SchedulerInfo, TimeSlice};
+
+/// A non-preemptive priority scheduler, see the
+/// [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct PrioFcfsSchedule {
+    /// ready processes by priority, arrival and the order they were added in
+    queue: BTreeMap<(u32, u64, u64), PCB>,
+    /// how many processes were added so far
+    added: u64,
+}
+
+impl PrioFcfsSchedule {
+    /// Creates a new, empty instance of the scheduler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Schedule for PrioFcfsSchedule {
+    /// Queues `process`, recording the current tick as its arrival.
+    fn add_process(&mut self, mut process: PCB) -> bool {
+        let arrival = CLOCK.now_ticks();
+        process.time_added = Some(arrival);
+        self.queue.insert((process.priority, arrival, self.added), process);
+        self.added += 1;
+        true
+    }
+
+    /// Returns the first process in priority, arrival and insertion order,
+    /// to run to completion.
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        (self.queue.pop_first().map(|(_, process)| process), TimeSlice::Infinite)
+    }
+
+    fn has_process(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn iter_level(&self, level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        match level {
+            0 => Box::new(self.queue.values()),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        self.queue.values().next()
+    }
+
+    /// An estimate, since `BTreeMap` does not report its allocations.
+    fn heap_size(&self) -> usize {
+        self.queue.len() * size_of::<((u32, u64, u64), PCB)>()
+    }
+
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: false,
+            starvation_free: false,
+            levels: 1,
+            default_quantum: TimeSlice::Infinite,
+            dispatch: Complexity::Logarithmic,
+        })
+    }
+}
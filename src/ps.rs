@@ -0,0 +1,200 @@
+//! # Processor Sharing Module
+//!
+//! The theoretical fair baseline a real scheduler can be measured against.
+//!
+//! Under **processor sharing** the CPU is divided equally between all ready
+//! jobs at every instant: with `n` jobs ready, each progresses at rate
+//! `1 / n`. It is the limit of round robin as the quantum shrinks to zero,
+//! so no real scheduler can implement it, but nothing is fairer: no job ever
+//! waits behind another, and every job's slowdown depends only on how busy
+//! the system was while it ran. [`Baseline`] computes exactly when every job
+//! of an open workload would finish under processor sharing, and
+//! [`Baseline::compare`] sets a run's measured turnaround times against it.
+//!
+//! A job with a burst script leaves the shared CPU while it waits for I/O
+//! and rejoins it when the wait is over, just as in the simulator. Closed
+//! workloads have no baseline, since their arrivals depend on how fast the
+//! scheduler under test finished the previous requests.
+//!
+//! ## Example
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::Workload;
+//! use scheduler::ps::Baseline;
+//!
+//! let workload = Workload::parse(["0 0 4", "1 0 2", "2 1 1"], TimeUnit::Ns, false).unwrap();
+//! let baseline = Baseline::new(&workload).unwrap();
+//! // P0 and P1 share the CPU until P2 arrives at 1, then all three do;
+//! // P2 is done at 4, P1 at 5, and P0 at 7
+//! assert_eq!(baseline.finish[&2], 4.0);
+//! assert_eq!(baseline.finish[&1], 5.0);
+//! assert_eq!(baseline.finish[&0], 7.0);
+//! assert_eq!(baseline.turnaround(2), Some(3.0));
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt::Write;
+
+use crate::clock::TimeUnit;
+use crate::input::{Mode, Step, Workload};
+use crate::metrics::Metrics;
+
+/// When every job of a workload finishes under processor sharing.
+///
+/// # Fields
+/// - `arrival`: Arrival tick of every job, by id.
+/// - `finish`: The time every job finishes at, by id. Times are fractional,
+///   since jobs progress at rates such as `1 / 3`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Baseline {
+    pub arrival: BTreeMap<u32, u64>,
+    pub finish: BTreeMap<u32, f64>,
+}
+
+/// An event, ordered so that a [`BinaryHeap`] pops the earliest first and
+/// breaks ties in the order the events were created.
+#[derive(Debug, Clone, Copy)]
+struct Earliest(f64, u64, u32);
+
+impl PartialEq for Earliest {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Earliest {}
+
+impl PartialOrd for Earliest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Earliest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+impl Baseline {
+    /// Computes the baseline of an open workload, or `None` for a closed
+    /// one.
+    ///
+    /// Runs in `O(n log n)` for `n` CPU bursts: instead of updating every
+    /// ready job as time passes, it tracks the *virtual time*, the service
+    /// each ready job has received, which grows at rate `1 / n`. A burst
+    /// finishes when the virtual time reaches the virtual time it started
+    /// at plus its length.
+    pub fn new(workload: &Workload) -> Option<Self> {
+        if workload.mode != Mode::Open {
+            return None;
+        }
+        let mut baseline = Baseline::default();
+        let mut steps: BTreeMap<u32, &[Step]> = BTreeMap::new();
+        // jobs joining the shared CPU, by real time
+        let mut joining = BinaryHeap::new();
+        // jobs sharing the CPU, by the virtual time their burst ends at
+        let mut ready = BinaryHeap::new();
+        let mut order = 0;
+        for job in workload.jobs.values() {
+            baseline.arrival.insert(job.id, job.arrival);
+            if let Some(script) = workload.scripts.get(&job.id) {
+                steps.insert(job.id, script);
+            }
+        }
+        for ids in workload.arrivals.values() {
+            for &id in ids {
+                joining.push(Earliest(workload.jobs[&id].arrival as f64, order, id));
+                order += 1;
+            }
+        }
+
+        let (mut now, mut virtual_time) = (0.0, 0.0);
+        loop {
+            let done = ready.peek().map(|&Earliest(end, ..)| now + (end - virtual_time) * ready.len() as f64);
+            let join = joining.peek().map(|&Earliest(at, ..)| at);
+            match (done, join) {
+                (None, None) => break,
+                // the next job joins before the earliest burst ends
+                (done, Some(at)) if done.is_none_or(|done| at < done) => {
+                    if !ready.is_empty() {
+                        virtual_time += (at - now) / ready.len() as f64;
+                    }
+                    now = at;
+                    let Earliest(_, _, id) = joining.pop().unwrap();
+                    let burst = match steps.get(&id).and_then(|steps| steps.first()) {
+                        Some(Step::Cpu(ticks)) => *ticks,
+                        _ => workload.jobs[&id].burst,
+                    };
+                    ready.push(Earliest(virtual_time + burst as f64, order, id));
+                    order += 1;
+                }
+                (Some(done), _) => {
+                    let Earliest(end, _, id) = ready.pop().unwrap();
+                    now = done;
+                    virtual_time = end;
+                    // the burst is over: block for the next I/O step, or finish
+                    match steps.get_mut(&id) {
+                        Some(script) if script.len() >= 3 => {
+                            let Step::Io(io) = script[1] else { unreachable!("scripts alternate cpu and io") };
+                            *script = &script[2..];
+                            joining.push(Earliest(now + io as f64, order, id));
+                            order += 1;
+                        }
+                        _ => {
+                            baseline.finish.insert(id, now);
+                        }
+                    }
+                }
+                (None, Some(_)) => unreachable!(),
+            }
+        }
+        Some(baseline)
+    }
+
+    /// The turnaround time of job `id` under processor sharing.
+    pub fn turnaround(&self, id: u32) -> Option<f64> {
+        Some(self.finish.get(&id)? - *self.arrival.get(&id)? as f64)
+    }
+
+    /// Compares a run's turnaround times with the baseline, over the
+    /// completed jobs that arrived in the metrics' measurement window.
+    ///
+    /// A job's *slowdown* is its measured turnaround divided by its
+    /// turnaround under processor sharing: `1.00x` is perfectly fair, and
+    /// values below `1` mean the scheduler favored the job at others'
+    /// expense.
+    pub fn compare(&self, metrics: &Metrics, unit: TimeUnit) -> String {
+        let mean = |ticks: f64| unit.format_ns((ticks * unit.nanos() as f64).round() as u64);
+        let mut pairs = Vec::new();
+        for p in metrics.processes() {
+            if let (true, Some(measured), Some(ideal)) =
+                (metrics.window().contains(p.arrival), p.turnaround, self.turnaround(p.id))
+                && ideal > 0.0
+            {
+                pairs.push((p.id, measured as f64, ideal));
+            }
+        }
+        if pairs.is_empty() {
+            return "processor sharing: no completed jobs to compare\n".to_string();
+        }
+        let n = pairs.len() as f64;
+        let ideal = pairs.iter().map(|&(_, _, ideal)| ideal).sum::<f64>() / n;
+        let measured = pairs.iter().map(|&(_, measured, _)| measured).sum::<f64>() / n;
+        let slowdown = |&(_, measured, ideal): &(u32, f64, f64)| measured / ideal;
+        let mean_slowdown = pairs.iter().map(slowdown).sum::<f64>() / n;
+        let worst = pairs.iter().max_by(|a, b| slowdown(a).total_cmp(&slowdown(b))).unwrap();
+        let mut out = String::new();
+        writeln!(out, "processor sharing: mean turnaround {} (measured {:.2}x)", mean(ideal), measured / ideal).unwrap();
+        writeln!(
+            out,
+            "slowdown vs processor sharing: mean {:.2}x, worst {:.2}x (P{})",
+            mean_slowdown,
+            slowdown(worst),
+            worst.0
+        )
+        .unwrap();
+        out
+    }
+}
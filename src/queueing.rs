@@ -0,0 +1,112 @@
+//! # Queueing Module
+//!
+//! What queueing theory predicts for a generated workload, to set next to
+//! the simulated results of `scheduler batch`.
+//!
+//! A workload whose arrivals form a Poisson process (`poisson:<rate>` or
+//! `exponential:<mean>` gaps) on one CPU is an **M/G/1** queue, and an
+//! **M/M/1** queue when its bursts are exponential too. For these the mean
+//! waiting time is known in closed form, given the arrival rate `λ`, the
+//! mean burst `E[S]` and the utilization `ρ = λE[S]`:
+//!
+//! - First-come, first-served, by the Pollaczek-Khinchine formula:
+//!   `W = λE[S²] / (2(1 - ρ))`. Any non-preemptive order that does not look
+//!   at burst lengths, such as by priority drawn at random, has the same
+//!   mean.
+//! - Processor sharing, the limit of round robin with a tiny quantum:
+//!   `W = ρE[S] / (1 - ρ)`, which does not depend on the burst distribution
+//!   beyond its mean.
+//!
+//! For M/M/1 both come to `ρ / (μ - λ)` with `μ = 1 / E[S]`.
+//!
+//! The formulas describe the long-run average of an endless stream of jobs
+//! in continuous time. A simulated run is finite, starts with an empty
+//! system, and rounds every time to whole ticks (with bursts of at least
+//! one tick), so expect it to come out somewhat lower, and closer the more
+//! jobs it has.
+//!
+//! ## Example
+//! ```
+//! use scheduler::queueing::{predict, Model};
+//!
+//! let mm1 = predict(&"poisson:0.05".parse().unwrap(), &"exponential:10".parse().unwrap()).unwrap();
+//! assert_eq!((mm1.model, mm1.utilization), (Model::MM1, 0.5));
+//! assert_eq!((mm1.fcfs_waiting, mm1.ps_waiting), (Some(10.0), 10.0));
+//!
+//! // constant bursts wait half as long under FCFS
+//! let md1 = predict(&"poisson:0.05".parse().unwrap(), &"constant:10".parse().unwrap()).unwrap();
+//! assert_eq!((md1.model, md1.fcfs_waiting), (Model::MG1, Some(5.0)));
+//!
+//! assert_eq!(
+//!     predict(&"constant:5".parse().unwrap(), &"exponential:10".parse().unwrap()).unwrap_err(),
+//!     "arrivals are not a Poisson process"
+//! );
+//! ```
+
+use std::fmt;
+
+use crate::distribution::Distribution;
+
+/// The queueing model a workload fits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    /// Poisson arrivals, exponential bursts.
+    MM1,
+    /// Poisson arrivals, bursts of any distribution.
+    MG1,
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Model::MM1 => "M/M/1",
+            Model::MG1 => "M/G/1",
+        })
+    }
+}
+
+/// The mean waiting times queueing theory predicts for a workload, in ticks.
+///
+/// # Fields
+/// - `model`: The queueing model the workload fits.
+/// - `utilization`: The share of time the CPU is busy, `ρ`.
+/// - `fcfs_waiting`: Mean waiting time under first-come, first-served,
+///   `None` where it is infinite because bursts are too heavy tailed to have
+///   a finite second moment.
+/// - `ps_waiting`: Mean waiting time under processor sharing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub model: Model,
+    pub utilization: f64,
+    pub fcfs_waiting: Option<f64>,
+    pub ps_waiting: f64,
+}
+
+/// Predicts the mean waiting times of a workload with gaps between arrivals
+/// drawn from `arrival` and bursts from `burst`, see the
+/// [module documentation](self).
+///
+/// # Errors
+/// When the arrivals are not a Poisson process, the mean burst is infinite,
+/// or the CPU cannot keep up (`ρ >= 1`), so no steady state exists.
+pub fn predict(arrival: &Distribution, burst: &Distribution) -> Result<Prediction, String> {
+    let Distribution::Exponential { mean: gap } = *arrival else {
+        return Err("arrivals are not a Poisson process".to_string());
+    };
+    let mean = burst.mean().ok_or("the mean burst is infinite")?;
+    let rate = 1.0 / gap;
+    let utilization = rate * mean;
+    if utilization >= 1.0 {
+        return Err(format!("the CPU is overloaded (utilization {:.2}), so waits grow without bound", utilization));
+    }
+    let model = match burst {
+        Distribution::Exponential { .. } => Model::MM1,
+        _ => Model::MG1,
+    };
+    Ok(Prediction {
+        model,
+        utilization,
+        fcfs_waiting: burst.second_moment().map(|moment| rate * moment / (2.0 * (1.0 - utilization))),
+        ps_waiting: utilization * mean / (1.0 - utilization),
+    })
+}
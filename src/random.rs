@@ -0,0 +1,56 @@
+//! # Random Module
+//!
+//! A small seeded random number generator for workloads that need
+//! randomness, such as the think times of closed-loop clients.
+//!
+//! Simulations must be reproducible, so every random workload carries its
+//! own seed and the same seed always produces the same numbers, on every
+//! platform and in every build. The generator is SplitMix64: fast, with good
+//! enough statistical quality for simulations, and not suitable for anything
+//! security related.
+//!
+//! ## Example
+//! ```
+//! use scheduler::random::Rng;
+//!
+//! let mut a = Rng::new(42);
+//! let mut b = Rng::new(42);
+//! assert_eq!(a.next_u64(), b.next_u64());
+//!
+//! let samples: Vec<f64> = (0..10_000).map(|_| a.exponential(50.0)).collect();
+//! let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+//! assert!((mean - 50.0).abs() < 2.5);
+//! ```
+
+/// A seeded SplitMix64 generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator; equal seeds give equal sequences.
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Returns the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number uniformly distributed in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        //the top 53 bits fill an f64 mantissa exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns an exponentially distributed number with the given mean.
+    pub fn exponential(&mut self, mean: f64) -> f64 {
+        -mean * (1.0 - self.next_f64()).ln()
+    }
+}
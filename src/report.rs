@@ -0,0 +1,195 @@
+//! # Report Module
+//!
+//! Machine-readable pass/fail reports of the checking commands, for CI.
+//!
+//! `scheduler selfcheck`, `scheduler corpus verify` and `scheduler grade`
+//! print for people. Given `--report <format> <file>` they also write every
+//! check they made as a [`TestCase`] to `file`, so CI-based autograding,
+//! e.g. GitHub Classroom, can tell which checks passed without scraping the
+//! output. The formats are:
+//!
+//! - `junit`: JUnit XML, which CI systems display as test results;
+//! - `json`: the same as JSON, with a `status` of `pass`, `fail` or `skip`
+//!   per case.
+//!
+//! ## Example
+//! ```
+//! use scheduler::report::{ReportFormat, TestCase, TestReport};
+//!
+//! let mut report = TestReport::new("selfcheck");
+//! report.push(TestCase::pass("fgbg", "convoy"));
+//! report.push(TestCase::fail("mlf", "convoy", "P3 was dispatched twice <at tick 4>"));
+//! report.push(TestCase::skip("simple", "convoy", "not implemented"));
+//! assert_eq!((report.failures(), report.skipped()), (1, 1));
+//!
+//! let xml = report.render("junit".parse().unwrap());
+//! assert!(xml.contains(r#"<testsuite name="selfcheck" tests="3" failures="1" skipped="1">"#));
+//! assert!(xml.contains(r#"<failure message="P3 was dispatched twice &lt;at tick 4&gt;">"#));
+//! let json = report.render(ReportFormat::Json);
+//! assert!(json.contains(r#"{"classname":"mlf","name":"convoy","status":"fail","message":"P3 was dispatched twice <at tick 4>"}"#));
+//! ```
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// The formats a [`TestReport`] can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// JUnit XML.
+    Junit,
+    /// JSON.
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "junit" => Ok(ReportFormat::Junit),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!("unknown report format '{}', expected junit or json", s)),
+        }
+    }
+}
+
+/// Whether a check passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Fail,
+    /// The check could not be made, e.g. because the scheduler is a stub.
+    Skip,
+}
+
+/// One check.
+///
+/// # Fields
+/// - `classname`: What was checked, e.g. the scheduler.
+/// - `name`: The check, e.g. the workload it was made on.
+/// - `status`: Whether it passed.
+/// - `message`: Why it failed or was skipped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestCase {
+    pub classname: String,
+    pub name: String,
+    pub status: Status,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl TestCase {
+    /// A check that passed.
+    pub fn pass(classname: impl Into<String>, name: impl Into<String>) -> Self {
+        Self { classname: classname.into(), name: name.into(), status: Status::Pass, message: None }
+    }
+
+    /// A check that failed, and why.
+    pub fn fail(classname: impl Into<String>, name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { classname: classname.into(), name: name.into(), status: Status::Fail, message: Some(message.into()) }
+    }
+
+    /// A check that could not be made, and why.
+    pub fn skip(classname: impl Into<String>, name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { classname: classname.into(), name: name.into(), status: Status::Skip, message: Some(message.into()) }
+    }
+}
+
+/// Every check a command made.
+///
+/// # Fields
+/// - `suite`: The command, e.g. `selfcheck`.
+/// - `cases`: The checks, in the order they were made.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestReport {
+    pub suite: String,
+    pub cases: Vec<TestCase>,
+}
+
+impl TestReport {
+    /// A report of no checks yet.
+    pub fn new(suite: impl Into<String>) -> Self {
+        Self { suite: suite.into(), cases: Vec::new() }
+    }
+
+    /// Adds a check.
+    pub fn push(&mut self, case: TestCase) {
+        self.cases.push(case);
+    }
+
+    /// The number of checks that failed.
+    pub fn failures(&self) -> usize {
+        self.cases.iter().filter(|case| case.status == Status::Fail).count()
+    }
+
+    /// The number of checks that were skipped.
+    pub fn skipped(&self) -> usize {
+        self.cases.iter().filter(|case| case.status == Status::Skip).count()
+    }
+
+    /// The report in `format`.
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Junit => self.to_junit(),
+            ReportFormat::Json => serde_json::to_string(self).expect("reports serialize") + "\n",
+        }
+    }
+
+    /// The report as JUnit XML, one `testsuite` with a `testcase` per check.
+    pub fn to_junit(&self) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(
+            out,
+            r#"<testsuite name="{}" tests="{}" failures="{}" skipped="{}">"#,
+            escape(&self.suite),
+            self.cases.len(),
+            self.failures(),
+            self.skipped()
+        )
+        .unwrap();
+        for case in &self.cases {
+            let (classname, name) = (escape(&case.classname), escape(&case.name));
+            let message = case.message.as_deref().unwrap_or_default();
+            match case.status {
+                Status::Pass => writeln!(out, r#"  <testcase classname="{}" name="{}"/>"#, classname, name),
+                Status::Fail => writeln!(
+                    out,
+                    "  <testcase classname=\"{}\" name=\"{}\">\n    <failure message=\"{}\">{}</failure>\n  </testcase>",
+                    classname,
+                    name,
+                    escape(message.lines().next().unwrap_or_default()),
+                    escape(message)
+                ),
+                Status::Skip => writeln!(
+                    out,
+                    "  <testcase classname=\"{}\" name=\"{}\">\n    <skipped message=\"{}\"/>\n  </testcase>",
+                    classname,
+                    name,
+                    escape(message)
+                ),
+            }
+            .unwrap();
+        }
+        out += "</testsuite>\n";
+        out
+    }
+}
+
+/// Escapes `text` for XML text and attribute values.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out += "&amp;",
+            '<' => out += "&lt;",
+            '>' => out += "&gt;",
+            '"' => out += "&quot;",
+            '\'' => out += "&apos;",
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,366 @@
+//! # Results Module
+//!
+//! Defines the machine-readable document written by `--format json`.
+//!
+//! ## Versioning
+//! Every document carries a `results_version`. Version [`RESULTS_VERSION`]
+//! contains these sections:
+//!
+//! - `provenance`: The build and inputs that produced the results, see
+//!   [`Provenance`]. Documents written before it was added have none.
+//! - `config`: How the run was set up (scheduler, input file, time unit,
+//!   the scheduler parameters from [`SchedulerConfig`] and the measurement
+//!   [`Window`] the aggregates cover).
+//! - `processes`: One [`ProcessMetrics`] entry per process, ordered by id.
+//! - `aggregates`: Run-wide [`Aggregates`].
+//! - `events`: The run's [`Event`] log, without per-tick `execute` events.
+//! - `annotations`: The run's [`Annotation`]s, left out when it has none.
+//!
+//! Fields may be **added** to any section without changing the version, so
+//! consumers must ignore fields they do not know about. Removing or renaming
+//! a field, or changing its meaning, bumps the version. All times are in
+//! ticks of `config.time_unit`.
+//!
+//! ## Streaming
+//! With `--flush-every N` the same information is written incrementally as
+//! JSON Lines instead, one [`StreamRecord`] per line: a `header` with the
+//! version, provenance and config, every non-`execute` `event` as it
+//! happens, every `annotation` as it is made, a `snapshot` of the
+//! aggregates every `N` ticks, and a final `summary`. A run that dies part
+//! way through still leaves everything up to its last flush.
+//! ```
+//! use scheduler::results::StreamRecord;
+//!
+//! let line = r#"{"record":"event","time":5,"id":2,"kind":"finish"}"#;
+//! let record: StreamRecord = serde_json::from_str(line).unwrap();
+//! assert!(matches!(record, StreamRecord::Event(e) if e.id == 2));
+//! ```
+//!
+//! ## Compatibility
+//! A version 1 document written by an older build, or one with fields added
+//! by a newer build, must keep parsing:
+//! ```
+//! use scheduler::results::Results;
+//!
+//! let v1 = r#"{
+//!   "results_version": 1,
+//!   "config": { "scheduler": "simple", "input_file": "t1.in", "time_unit": "ns", "added_later": true },
+//!   "processes": [
+//!     { "id": 0, "priority": 0, "arrival": 0, "first_run": 0, "finish": 3, "cpu": 3,
+//!       "dispatches": 1, "turnaround": 3, "waiting": 0, "response": 0 }
+//!   ],
+//!   "aggregates": { "processes": 1, "completed": 1, "makespan": 3, "cpu_time": 3,
+//!     "mean_turnaround": 3.0, "mean_waiting": 0.0, "mean_response": 0.0,
+//!     "throughput": 0.3333333333333333, "utilization": 1.0 },
+//!   "events": [
+//!     { "time": 0, "id": 0, "kind": "arrive", "priority": 0 },
+//!     { "time": 0, "id": 0, "kind": "dispatch", "quantum": 0 },
+//!     { "time": 3, "id": 0, "kind": "finish" }
+//!   ]
+//! }"#;
+//! let results = Results::from_json(v1).unwrap();
+//! assert_eq!(results.processes[0].turnaround, Some(3));
+//! assert_eq!(results.events.len(), 3);
+//!
+//! // and it round-trips through the current writer
+//! assert_eq!(Results::from_json(&results.to_json()).unwrap(), results);
+//!
+//! // documents from a newer, incompatible version are rejected
+//! let v2 = v1.replace(r#""results_version": 1"#, r#""results_version": 2"#);
+//! assert!(Results::from_json(&v2).is_err());
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::TimeUnit;
+use crate::config::SchedulerConfig;
+use crate::event::{Annotation, Event};
+use crate::metrics::{Aggregates, Metrics, ProcessMetrics, Window};
+use crate::sink::{Recorder, StopConditions};
+
+/// The results schema version this build writes and reads.
+pub const RESULTS_VERSION: u32 = 1;
+
+/// How a run was configured.
+///
+/// `scheduler_config`, `measure` and `stop` were added after version 1 was
+/// first written; documents without them read back with the default
+/// configuration, a window covering the whole run and no stop conditions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunConfig {
+    pub scheduler: String,
+    pub input_file: String,
+    pub time_unit: TimeUnit,
+    #[serde(default)]
+    pub scheduler_config: SchedulerConfig,
+    #[serde(default)]
+    pub measure: Window,
+    #[serde(default)]
+    pub stop: StopConditions,
+}
+
+/// Where a run's results came from, so that a graded artifact can be traced
+/// back to the exact code, parameters and workload that produced it.
+///
+/// # Fields
+/// - `version`: Version of the simulator crate.
+/// - `git_hash`: Commit the simulator was built from, when it was built from
+///   a git checkout.
+/// - `scheduler`: Name of the scheduler that ran.
+/// - `config_hash`: Hash of the scheduler parameters
+///   ([`RunConfig::scheduler_config`]).
+/// - `input_hash`: Hash of the workload's contents.
+/// - `seed`: Seed of a random (closed-loop) workload, if it has one.
+/// - `tags`: `name=value` pairs given with `--tag`, to find the run again,
+///   e.g. by `scheduler results ls`; see [`Results::matches`].
+///
+/// The hashes are 64-bit FNV-1a in hex: equal hashes mean equal inputs on
+/// every platform and build, but they are not meant to be tamper proof.
+///
+/// # Example
+/// ```
+/// use scheduler::results::{Provenance, RunConfig};
+///
+/// let config = RunConfig { scheduler: "mlf".into(), ..Default::default() };
+/// let provenance = Provenance::new(&config, "0 0 5 1\n", None);
+/// assert_eq!(provenance.version, env!("CARGO_PKG_VERSION"));
+/// assert_eq!(provenance, Provenance::new(&config, "0 0 5 1\n", None));
+/// assert_ne!(provenance.input_hash, Provenance::new(&config, "0 0 6 1\n", None).input_hash);
+/// assert!(provenance.to_csv_comment().starts_with("# version="));
+///
+/// let tags = [("experiment".to_string(), "sweep".to_string())].into();
+/// let tagged = Provenance { tags, ..provenance };
+/// assert!(tagged.to_csv_comment().ends_with(" tag.experiment=sweep"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub version: String,
+    pub git_hash: Option<String>,
+    pub scheduler: String,
+    pub config_hash: String,
+    pub input_hash: String,
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl Provenance {
+    /// Describes a run of this build with `config` on the workload `input`.
+    pub fn new(config: &RunConfig, input: &str, seed: Option<u64>) -> Self {
+        let parameters = serde_json::to_string(&config.scheduler_config).expect("configs are always serializable");
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: option_env!("SCHEDULER_GIT_HASH").map(String::from),
+            scheduler: config.scheduler.clone(),
+            config_hash: fnv1a(parameters.as_bytes()),
+            input_hash: fnv1a(input.as_bytes()),
+            seed,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    /// Renders the provenance as a `#` comment line, for the top of CSV
+    /// files. Tags come last, as `tag.<name>=<value>`.
+    pub fn to_csv_comment(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "none".to_string());
+        let tags: String = self.tags.iter().map(|(name, value)| format!(" tag.{}={}", name, value)).collect();
+        format!(
+            "# version={} git={} scheduler={} config_hash={} input_hash={} seed={}{}",
+            self.version,
+            optional(self.git_hash.clone()),
+            self.scheduler,
+            self.config_hash,
+            self.input_hash,
+            optional(self.seed.map(|seed| seed.to_string())),
+            tags
+        )
+    }
+}
+
+/// 64-bit FNV-1a, in hex.
+fn fnv1a(bytes: &[u8]) -> String {
+    let hash = bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// One line of the streamed (`--flush-every`) results format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)] // there is only one header per stream
+pub enum StreamRecord {
+    /// Always the first line.
+    Header {
+        results_version: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        provenance: Option<Provenance>,
+        config: RunConfig,
+    },
+    /// A simulation event, written as it happens.
+    Event(Event),
+    /// An annotation, written as it is made.
+    Annotation(Annotation),
+    /// The aggregates so far, written every `--flush-every` ticks.
+    Snapshot { time: u64, aggregates: Aggregates },
+    /// Always the last line of a run that completed.
+    Summary { processes: Vec<ProcessMetrics>, aggregates: Aggregates },
+}
+
+/// The complete, versioned results of one simulation run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Results {
+    pub results_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+    pub config: RunConfig,
+    pub processes: Vec<ProcessMetrics>,
+    pub aggregates: Aggregates,
+    pub events: Vec<Event>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+impl Results {
+    /// Builds the results document for a finished run. It has no provenance
+    /// until one is filled in.
+    ///
+    /// # Parameters
+    /// - `config`: How the run was set up.
+    /// - `recorder`: The recorder the simulation reported into.
+    pub fn new(config: RunConfig, recorder: &Recorder) -> Self {
+        Self {
+            results_version: RESULTS_VERSION,
+            provenance: None,
+            config,
+            processes: recorder.metrics().processes(),
+            aggregates: recorder.metrics().aggregates(),
+            events: recorder.events().to_vec(),
+            annotations: recorder.annotations().to_vec(),
+        }
+    }
+
+    /// Serializes the results as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("results are always serializable")
+    }
+
+    /// Parses either a results document or a `--flush-every` JSON Lines
+    /// stream. A stream cut short (no `summary` line) yields the events that
+    /// were written, with metrics recomputed from them.
+    pub fn load(text: &str) -> Result<Self, String> {
+        if text.trim_start().starts_with(r#"{"record":"#) {
+            Self::from_stream(text)
+        } else {
+            Self::from_json(text)
+        }
+    }
+
+    /// Parses a `--flush-every` JSON Lines stream, see [`Results::load`].
+    pub fn from_stream(text: &str) -> Result<Self, String> {
+        let mut config = None;
+        let mut provenance = None;
+        let mut events = Vec::new();
+        let mut annotations = Vec::new();
+        let mut metrics = Metrics::new();
+        let mut summary = None;
+        for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let record: StreamRecord =
+                serde_json::from_str(line).map_err(|e| format!("line {}: {}", n + 1, e))?;
+            match record {
+                StreamRecord::Header { results_version, provenance: p, config: c } => {
+                    if results_version != RESULTS_VERSION {
+                        return Err(format!("unsupported results_version {}", results_version));
+                    }
+                    metrics = Metrics::with_window(c.measure);
+                    provenance = p;
+                    config = Some(c);
+                }
+                StreamRecord::Event(event) => {
+                    metrics.record(&event);
+                    events.push(event);
+                }
+                StreamRecord::Annotation(annotation) => annotations.push(annotation),
+                StreamRecord::Snapshot { .. } => {}
+                StreamRecord::Summary { processes, aggregates } => summary = Some((processes, aggregates)),
+            }
+        }
+        let config = config.ok_or("stream has no header line")?;
+        let (processes, aggregates) = summary.unwrap_or_else(|| (metrics.processes(), metrics.aggregates()));
+        Ok(Self {
+            results_version: RESULTS_VERSION,
+            provenance,
+            config,
+            processes,
+            aggregates,
+            events,
+            annotations,
+        })
+    }
+
+    /// What the run can be looked up by, as `key=value` text: its tags, the
+    /// `scheduler`, `input` file and `time_unit`, the `version`, `git`,
+    /// `seed`, `config_hash` and `input_hash` of its provenance, and the
+    /// parameters of its scheduler, e.g. `quantum` or `quanta`. A tag hides
+    /// anything else of the same name.
+    pub fn fields(&self) -> BTreeMap<String, String> {
+        let text = |value: &serde_json::Value| match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let mut fields = BTreeMap::new();
+        let parameters = serde_json::to_value(&self.config.scheduler_config).expect("configs are always serializable");
+        if let Some(parameters) = parameters.get(&self.config.scheduler).and_then(|p| p.as_object()) {
+            fields.extend(parameters.iter().map(|(name, value)| (name.clone(), text(value))));
+        }
+        fields.insert("scheduler".to_string(), self.config.scheduler.clone());
+        fields.insert("input".to_string(), self.config.input_file.clone());
+        fields.insert("time_unit".to_string(), text(&serde_json::to_value(self.config.time_unit).expect("units serialize")));
+        if let Some(provenance) = &self.provenance {
+            fields.insert("version".to_string(), provenance.version.clone());
+            fields.extend(provenance.git_hash.clone().map(|git| ("git".to_string(), git)));
+            fields.extend(provenance.seed.map(|seed| ("seed".to_string(), seed.to_string())));
+            fields.insert("config_hash".to_string(), provenance.config_hash.clone());
+            fields.insert("input_hash".to_string(), provenance.input_hash.clone());
+            fields.extend(provenance.tags.clone());
+        }
+        fields
+    }
+
+    /// Whether every `key=value` filter matches one of the run's
+    /// [`fields`](Results::fields).
+    ///
+    /// # Example
+    /// ```
+    /// use scheduler::results::{Provenance, Results, RunConfig};
+    /// use scheduler::sink::Recorder;
+    ///
+    /// let config = RunConfig { scheduler: "simplerr".into(), ..Default::default() };
+    /// let mut provenance = Provenance::new(&config, "", None);
+    /// provenance.tags.insert("experiment".into(), "sweep".into());
+    /// let results = Results { provenance: Some(provenance), ..Results::new(config, &Recorder::new()) };
+    /// let filter = |key: &str, value: &str| [(key.to_string(), value.to_string())];
+    /// assert!(results.matches(&filter("experiment", "sweep")));
+    /// assert!(results.matches(&filter("quantum", "4")));
+    /// assert!(!results.matches(&filter("scheduler", "mlf")));
+    /// assert!(!results.matches(&filter("quanta", "1")));
+    /// ```
+    pub fn matches(&self, filters: &[(String, String)]) -> bool {
+        let fields = self.fields();
+        filters.iter().all(|(key, value)| fields.get(key) == Some(value))
+    }
+
+    /// Parses a results document, rejecting versions this build does not know.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let results: Results = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if results.results_version != RESULTS_VERSION {
+            return Err(format!(
+                "unsupported results_version {} (expected {})",
+                results.results_version, RESULTS_VERSION
+            ));
+        }
+        Ok(results)
+    }
+}
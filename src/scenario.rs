@@ -0,0 +1,272 @@
+//! # Scenario Module
+//!
+//! Readable unit tests for schedulers.
+//!
+//! The [`sim!`](crate::sim) macro describes a small workload and what should
+//! happen to it, one statement per line:
+//! - `at <tick> add P<id> burst <ticks>;` adds a job, optionally followed by
+//!   `priority <n>` before the `;`;
+//! - `expect <what> P<id> at <tick>;` expects the process to be dispatched,
+//!   preempted, blocked, woken, finished or to arrive at that tick, written
+//!   `dispatch`, `preempt`, `block`, `wake`, `finish` and `arrive`.
+//!
+//! The macro builds a [`Scenario`], which runs any [`Schedule`] through the
+//! jobs with the library's [`Simulation`] and checks every expectation
+//! against the run's events. [`Scenario::assert`] panics with every
+//! expectation that did not hold and what happened to the process instead,
+//! so it drops straight into a `#[test]`. Events that are not expected are
+//! not checked, so a test only needs to state what it is about.
+//!
+//! Scenarios take turns on the global [`CLOCK`](crate::CLOCK), so tests that
+//! use them can run in parallel.
+//!
+//! ## Example
+//! ```
+//! use std::collections::VecDeque;
+//! use scheduler::{sim, PCB, Schedule, TimeSlice};
+//!
+//! struct RoundRobin(VecDeque<PCB>);
+//! impl Schedule for RoundRobin {
+//!     fn add_process(&mut self, p: PCB) -> bool { self.0.push_back(p); true }
+//!     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop_front(), TimeSlice::Ticks(2)) }
+//!     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! }
+//!
+//! let scenario = sim! {
+//!     at 0 add P1 burst 5;
+//!     at 1 add P2 burst 3 priority 1;
+//!     expect dispatch P1 at 0;
+//!     expect preempt P1 at 2;
+//!     expect dispatch P2 at 2;
+//!     expect finish P2 at 7;
+//!     expect finish P1 at 8;
+//! };
+//! scenario.assert(RoundRobin(VecDeque::new()));
+//!
+//! // a scheduler that never preempts fails the test, saying what it did
+//! struct Fifo(VecDeque<PCB>);
+//! impl Schedule for Fifo {
+//!     fn add_process(&mut self, p: PCB) -> bool { self.0.push_back(p); true }
+//!     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop_front(), TimeSlice::Infinite) }
+//!     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! }
+//! let problems = scenario.check(Fifo(VecDeque::new()));
+//! assert_eq!(problems[0], "expected P1 to be preempted at tick 2, but it arrived at 0, was dispatched at 0, finished at 5");
+//! ```
+
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::Schedule;
+use crate::event::{Event, EventKind};
+use crate::simulation::{Job, Simulation};
+use crate::sink::Recorder;
+
+/// Runs of scenarios share the global clock, so only one runs at a time.
+pub(crate) static RUNNING: Mutex<()> = Mutex::new(());
+
+/// What a [`Scenario`] expects to happen to a process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expect {
+    Arrive,
+    Dispatch,
+    Preempt,
+    Block,
+    Wake,
+    Finish,
+}
+
+impl Expect {
+    /// The expectation written `word` in a [`sim!`](crate::sim) statement.
+    ///
+    /// # Panics
+    /// If `word` is none of `arrive`, `dispatch`, `preempt`, `block`, `wake`
+    /// or `finish`.
+    pub fn from_word(word: &str) -> Self {
+        match word {
+            "arrive" => Expect::Arrive,
+            "dispatch" => Expect::Dispatch,
+            "preempt" => Expect::Preempt,
+            "block" => Expect::Block,
+            "wake" => Expect::Wake,
+            "finish" => Expect::Finish,
+            word => panic!("unknown expectation '{}', expected arrive, dispatch, preempt, block, wake or finish", word),
+        }
+    }
+
+    /// Whether an event of `kind` meets the expectation.
+    fn matches(self, kind: EventKind) -> bool {
+        matches!(
+            (self, kind),
+            (Expect::Arrive, EventKind::Arrive { .. })
+                | (Expect::Dispatch, EventKind::Dispatch { .. })
+                | (Expect::Preempt, EventKind::Preempt)
+                | (Expect::Block, EventKind::Block)
+                | (Expect::Wake, EventKind::Wake)
+                | (Expect::Finish, EventKind::Finish)
+        )
+    }
+}
+
+impl fmt::Display for Expect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Expect::Arrive => "arrive",
+            Expect::Dispatch => "be dispatched",
+            Expect::Preempt => "be preempted",
+            Expect::Block => "block",
+            Expect::Wake => "wake",
+            Expect::Finish => "finish",
+        })
+    }
+}
+
+/// An event a [`Scenario`] expects.
+///
+/// # Fields
+/// - `time`: Tick the event should happen at.
+/// - `id`: The process it should happen to.
+/// - `what`: What should happen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expectation {
+    pub time: u64,
+    pub id: u32,
+    pub what: Expect,
+}
+
+/// A workload and the events expected when it is scheduled, usually written
+/// with [`sim!`](crate::sim). See the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scenario {
+    pub jobs: Vec<Job>,
+    pub expectations: Vec<Expectation>,
+}
+
+impl Scenario {
+    /// An empty scenario.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the job of process `name`, e.g. `"P1"`, arriving at `time`.
+    ///
+    /// # Panics
+    /// If `name` is not `P` followed by a process ID.
+    pub fn add(&mut self, time: u64, name: &str, burst: u32, priority: u32) {
+        self.jobs.push(Job { priority, ..Job::new(parse_id(name), time, burst) });
+    }
+
+    /// Expects `what` (a word such as `"dispatch"`, see [`Expect::from_word`])
+    /// to happen to process `name` at `time`.
+    ///
+    /// # Panics
+    /// If `what` or `name` cannot be read.
+    pub fn expect(&mut self, time: u64, what: &str, name: &str) {
+        self.expectations.push(Expectation { time, id: parse_id(name), what: Expect::from_word(what) });
+    }
+
+    /// Runs `sched` through the jobs, returning the recorded run, or why the
+    /// run could not go on.
+    pub fn run<S: Schedule>(&self, sched: S) -> Result<Recorder, String> {
+        let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut sim = Simulation::builder(sched).jobs(self.jobs.iter().cloned()).build();
+        let mut out = Recorder::new();
+        sim.run(&mut out).map_err(|err| err.to_string())?;
+        Ok(out)
+    }
+
+    /// Runs `sched` through the jobs and checks every expectation.
+    ///
+    /// # Returns
+    /// A description of every expectation that did not hold, empty when they
+    /// all did.
+    pub fn check<S: Schedule>(&self, sched: S) -> Vec<String> {
+        let out = match self.run(sched) {
+            Ok(out) => out,
+            Err(err) => return vec![format!("the run failed: {}", err)],
+        };
+        let events = out.events();
+        self.expectations
+            .iter()
+            .filter(|e| !events.iter().any(|ev| ev.time == e.time && ev.id == e.id && e.what.matches(ev.kind)))
+            .map(|e| format!("expected P{} to {} at tick {}, but it {}", e.id, e.what, e.time, history(events, e.id)))
+            .collect()
+    }
+
+    /// Runs `sched` through the jobs and checks every expectation.
+    ///
+    /// # Panics
+    /// If any expectation did not hold, listing all that did not.
+    pub fn assert<S: Schedule>(&self, sched: S) {
+        let problems = self.check(sched);
+        if !problems.is_empty() {
+            panic!("scenario failed:\n  {}", problems.join("\n  "));
+        }
+    }
+}
+
+/// Reads a process name such as `P1`.
+fn parse_id(name: &str) -> u32 {
+    name.strip_prefix('P')
+        .and_then(|id| id.parse().ok())
+        .unwrap_or_else(|| panic!("'{}' is not a process, write processes as P<id>, e.g. P1", name))
+}
+
+/// What happened to process `id`, e.g. `arrived at 0, finished at 5`.
+fn history(events: &[Event], id: u32) -> String {
+    let history: Vec<String> = events
+        .iter()
+        .filter(|event| event.id == id)
+        .map(|event| {
+            let verb = match event.kind {
+                EventKind::Arrive { .. } => "arrived",
+                EventKind::Dispatch { .. } => "was dispatched",
+                EventKind::Preempt => "was preempted",
+                EventKind::Block => "blocked",
+                EventKind::Wake => "woke",
+                EventKind::Throttle => "was throttled",
+                EventKind::Unthrottle => "was unthrottled",
+                EventKind::Renice { .. } => "was reniced",
+                EventKind::Finish => "finished",
+                EventKind::Timeout => "timed out",
+                EventKind::Execute | EventKind::Idle => "ran",
+            };
+            format!("{} at {}", verb, event.time)
+        })
+        .collect();
+    match history.is_empty() {
+        true => "never arrived".to_string(),
+        false => history.join(", "),
+    }
+}
+
+/// Writes a scheduler unit test as a list of statements, building a
+/// [`Scenario`](crate::scenario::Scenario).
+///
+/// Each statement ends with `;` and is one of
+/// - `at <tick> add P<id> burst <ticks>;`
+/// - `at <tick> add P<id> burst <ticks> priority <n>;`
+/// - `expect <dispatch|preempt|block|wake|finish|arrive> P<id> at <tick>;`
+///
+/// See the [module docs](crate::scenario) for an example.
+#[macro_export]
+macro_rules! sim {
+    (@steps $s:ident;) => {};
+    (@steps $s:ident; at $time:literal add $p:ident burst $burst:literal priority $prio:literal; $($rest:tt)*) => {
+        $s.add($time, stringify!($p), $burst, $prio);
+        $crate::sim!(@steps $s; $($rest)*);
+    };
+    (@steps $s:ident; at $time:literal add $p:ident burst $burst:literal; $($rest:tt)*) => {
+        $s.add($time, stringify!($p), $burst, 0);
+        $crate::sim!(@steps $s; $($rest)*);
+    };
+    (@steps $s:ident; expect $what:ident $p:ident at $time:literal; $($rest:tt)*) => {
+        $s.expect($time, stringify!($what), stringify!($p));
+        $crate::sim!(@steps $s; $($rest)*);
+    };
+    ($($body:tt)*) => {{
+        let mut scenario = $crate::scenario::Scenario::new();
+        $crate::sim!(@steps scenario; $($body)*);
+        scenario
+    }};
+}
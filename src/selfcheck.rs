@@ -0,0 +1,177 @@
+//! # Self-Check Module
+//!
+//! Sanity checks a finished run, for `scheduler selfcheck`.
+//!
+//! The simulator trusts the scheduler to hand back exactly the processes it
+//! was given, one at a time. [`check`] replays a run's event log and reports
+//! every way the run breaks that contract or produced impossible metrics:
+//! - every job arrives once and finishes or times out once;
+//! - a process is only dispatched while ready, and only leaves the CPU while
+//!   running, so the scheduler never returns a process twice, or one it was
+//!   never given;
+//! - every job got its CPU time, and the CPU time of all jobs adds up to the
+//!   run's. The simulator's classic loops, like the reference traces, run a
+//!   job for one tick more than its burst when it finishes, so one extra
+//!   tick is allowed;
+//! - no wait is negative: a job's CPU and I/O time fit in its turnaround,
+//!   and it first ran after it arrived;
+//! - every tick of the run was spent either running a process or idle.
+//!
+//! A run with no problems is not necessarily *correct*, since every policy
+//! passes, but one with problems is certainly broken.
+//!
+//! ## Example
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::event::EventKind;
+//! use scheduler::input::Workload;
+//! use scheduler::results::{Results, RunConfig};
+//! use scheduler::selfcheck;
+//! use scheduler::sink::Recorder;
+//! use scheduler::CLOCK;
+//! use std::time::Duration;
+//!
+//! let workload = Workload::parse(["0 0 2", "1 0 1"], TimeUnit::Ns, false).unwrap();
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let run = |order: &[u32]| {
+//!     CLOCK.set_now(Duration::ZERO);
+//!     let mut out = Recorder::new();
+//!     out.emit(0, EventKind::Arrive { priority: 0 });
+//!     out.emit(1, EventKind::Arrive { priority: 0 });
+//!     for &id in order {
+//!         out.emit(id, EventKind::Dispatch { quantum: 0 });
+//!         for _ in 0..workload.jobs[&id].burst {
+//!             out.emit(id, EventKind::Execute);
+//!             CLOCK.advance(Duration::from_nanos(1));
+//!         }
+//!         out.emit(id, EventKind::Finish);
+//!     }
+//!     Results::new(RunConfig::default(), &out)
+//! };
+//! assert!(selfcheck::check(&workload, &run(&[1, 0])).is_empty());
+//! // a scheduler that returns P1 twice
+//! let problems = selfcheck::check(&workload, &run(&[1, 0, 1]));
+//! assert!(problems.contains(&"P1 was dispatched after it finished".to_string()));
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::event::EventKind;
+use crate::input::Workload;
+use crate::results::Results;
+
+/// The built-in schedulers, in the order `selfcheck` checks them.
+pub const SCHEDULERS: [&str; 10] = ["simple", "simplerr", "mlrr", "simplemlf", "mlf", "bsd", "fgbg", "prio_fcfs", "stack", "spn"];
+
+/// Where a process is, as far as the event log tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ready,
+    Running,
+    Blocked,
+    Throttled,
+    Finished,
+}
+
+/// Checks a run of an open `workload`, see the
+/// [module documentation](self).
+///
+/// # Returns
+/// A description of every problem found, empty when the run is sane.
+pub fn check(workload: &Workload, results: &Results) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut states: BTreeMap<u32, State> = BTreeMap::new();
+    for event in &results.events {
+        let id = event.id;
+        let state = states.get(&id).copied();
+        let next = match (event.kind, state) {
+            (EventKind::Execute | EventKind::Idle, _) => continue,
+            (EventKind::Arrive { .. }, None) => State::Ready,
+            (EventKind::Arrive { .. }, Some(_)) => {
+                problems.push(format!("P{} arrived twice", id));
+                continue;
+            }
+            (_, None) => {
+                problems.push(format!("P{} was {} before it arrived", id, verb(event.kind)));
+                continue;
+            }
+            (EventKind::Dispatch { .. }, Some(State::Ready)) => State::Running,
+            (EventKind::Preempt, Some(State::Running)) => State::Ready,
+            (EventKind::Block, Some(State::Running)) => State::Blocked,
+            (EventKind::Finish, Some(State::Running)) => State::Finished,
+            (EventKind::Timeout, Some(state)) if state != State::Finished => State::Finished,
+            (EventKind::Wake, Some(State::Blocked)) => State::Ready,
+            (EventKind::Throttle, Some(State::Ready)) => State::Throttled,
+            (EventKind::Unthrottle, Some(State::Throttled)) => State::Ready,
+            (EventKind::Renice { .. }, Some(state)) if state != State::Finished => continue,
+            (kind, Some(state)) => {
+                problems.push(format!("P{} was {} {}", id, verb(kind), describe(state)));
+                continue;
+            }
+        };
+        states.insert(id, next);
+    }
+    for id in workload.jobs.keys() {
+        match states.get(id) {
+            None => problems.push(format!("P{} never arrived", id)),
+            Some(State::Finished) => {}
+            Some(_) => problems.push(format!("P{} never finished", id)),
+        }
+    }
+
+    let mut cpu = 0;
+    for p in &results.processes {
+        cpu += p.cpu;
+        if let Some(job) = workload.jobs.get(&p.id)
+            && p.finish.is_some()
+            && !(job.burst as u64..=job.burst as u64 + 1).contains(&p.cpu)
+        {
+            problems.push(format!("P{} ran for {} ticks instead of {}", p.id, p.cpu, job.burst));
+        }
+        if let Some(turnaround) = p.turnaround
+            && p.cpu + p.io > turnaround
+        {
+            problems.push(format!("P{} has a negative wait: {} ticks of CPU and I/O in a turnaround of {}", p.id, p.cpu + p.io, turnaround));
+        }
+        if p.first_run.is_some_and(|first| first < p.arrival) {
+            problems.push(format!("P{} ran before it arrived", p.id));
+        }
+    }
+    let agg = &results.aggregates;
+    if cpu != agg.cpu_time {
+        problems.push(format!("the jobs ran for {} ticks in all, but the CPU was busy for {}", cpu, agg.cpu_time));
+    }
+    if agg.cpu_time + agg.idle != agg.makespan {
+        problems.push(format!(
+            "{} busy and {} idle ticks do not add up to the makespan of {}",
+            agg.cpu_time, agg.idle, agg.makespan
+        ));
+    }
+    problems
+}
+
+fn verb(kind: EventKind) -> &'static str {
+    match kind {
+        EventKind::Arrive { .. } => "added",
+        EventKind::Dispatch { .. } => "dispatched",
+        EventKind::Preempt => "preempted",
+        EventKind::Block => "blocked",
+        EventKind::Wake => "woken",
+        EventKind::Throttle => "throttled",
+        EventKind::Unthrottle => "unthrottled",
+        EventKind::Renice { .. } => "reniced",
+        EventKind::Finish => "finished",
+        EventKind::Timeout => "timed out",
+        EventKind::Execute | EventKind::Idle => "run",
+    }
+}
+
+fn describe(state: State) -> &'static str {
+    match state {
+        State::Ready => "while it was ready",
+        State::Running => "while it was running",
+        State::Blocked => "while it was blocked",
+        State::Throttled => "while it was throttled",
+        State::Finished => "after it finished",
+    }
+}
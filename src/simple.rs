@@ -1,4 +1,4 @@
-use crate::{Schedule, PCB};
+use crate::{Complexity, Schedule, SchedulerInfo, PCB, TimeSlice};
 
 pub struct SimpleSchedule {
     implemented: bool,
@@ -15,6 +15,12 @@ impl SimpleSchedule {
     }
 }
 
+impl Default for SimpleSchedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Schedule for SimpleSchedule {
     /// Adds a new process to the scheduler.
     ///
@@ -32,7 +38,7 @@ impl Schedule for SimpleSchedule {
     /// You do not need to maintain this struct element or functionality if you implement this
     /// scheduler, but if you don't this is the behavior it should have when submitted for
     /// grading if not implemented.
-    fn add_process(&mut self, mut process: PCB) -> bool{
+    fn add_process(&mut self, _process: PCB) -> bool{
         if !self.implemented {
             println!("Not Implemented");
             std::process::exit(0);
@@ -43,10 +49,11 @@ impl Schedule for SimpleSchedule {
     /// Retrieves the next process to run from the scheduler.
     ///
     /// # Returns
-    /// A tuple `(Option<PCB>, u32)` where:
+    /// A tuple `(Option<PCB>, TimeSlice)` where:
     /// - The first element is the next process to run, or `None` if no process is available.
-    /// - The second element is a `u32` value (for example, representing the time slice,
-    ///   priority, or cycle count associated with the returned process).
+    /// - The second element is the [`TimeSlice`] the returned process may run for:
+    ///   `TimeSlice::Ticks(n)` to preempt it after `n` ticks, or `TimeSlice::Infinite`
+    ///   to run it to completion (FCFS).
     ///
     /// # Behavior
     /// If the scheduler has not been implemented yet (`self.implemented == false`),
@@ -54,12 +61,12 @@ impl Schedule for SimpleSchedule {
     /// You do not need to maintain this struct element or functionality if you implement this
     /// scheduler, but if you don't this is the behavior it should have when submitted for
     /// grading if not implemented.
-    fn next_process(&mut self) -> (Option<PCB>, u32){
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice){
         if !self.implemented {
             println!("Not Implemented");
             std::process::exit(0);
         }
-        (None,0)
+        (None,TimeSlice::Infinite)
     }
     /// Checks whether the scheduler currently has any processes pending.
     ///
@@ -80,4 +87,16 @@ impl Schedule for SimpleSchedule {
         }
         false
     }
+
+    /// Describes the first come, first served scheduler the assignment asks
+    /// for: one queue, every process to completion.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: false,
+            starvation_free: true,
+            levels: 1,
+            default_quantum: TimeSlice::Infinite,
+            dispatch: Complexity::Constant,
+        })
+    }
 }
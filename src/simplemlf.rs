@@ -1,7 +1,11 @@
-use crate::{Schedule, PCB, CLOCK};// <-- Import Job from crate root
+#[allow(unused_imports)]
+use crate::{Complexity, Schedule, SchedulerInfo, PCB, TimeSlice, CLOCK};// <-- Import Job from crate root
+use crate::config::SimpleMlfConfig;
 
 pub struct SimpleMLFSchedule {
     implemented: bool,
+    #[allow(dead_code)] // read this in your implementation
+    config: SimpleMlfConfig,
 }
 
 impl SimpleMLFSchedule {
@@ -11,7 +15,23 @@ impl SimpleMLFSchedule {
     /// A new [`SimpleMLFSchedule`] with the elements in its struct set to initial values.
     ///
     pub fn new() -> Self {
-        Self { implemented: false, }
+        Self::with_config(SimpleMlfConfig::default())
+    }
+
+    /// Creates a new instance of the SimpleMLF scheduler using `config`.
+    ///
+    /// The simulator passes in the configuration from `--config`. Read the
+    /// level quanta and the promotion threshold from it instead of
+    /// hard-coding them; the default is the configuration the assignment
+    /// describes.
+    pub fn with_config(config: SimpleMlfConfig) -> Self {
+        Self { implemented: false, config }
+    }
+}
+
+impl Default for SimpleMLFSchedule {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -32,7 +52,7 @@ impl Schedule for SimpleMLFSchedule {
     /// You do not need to maintain this struct element or functionality if you implement this
     /// scheduler, but if you don't this is the behavior it should have when submitted for
     /// grading if not implemented.
-    fn add_process(&mut self, mut process: PCB) -> bool{
+    fn add_process(&mut self, _process: PCB) -> bool{
         if !self.implemented {
             println!("Not Implemented");
             std::process::exit(0);
@@ -43,10 +63,11 @@ impl Schedule for SimpleMLFSchedule {
     /// Retrieves the next process to run from the scheduler.
     ///
     /// # Returns
-    /// A tuple `(Option<PCB>, u32)` where:
+    /// A tuple `(Option<PCB>, TimeSlice)` where:
     /// - The first element is the next process to run, or `None` if no process is available.
-    /// - The second element is a `u32` value (for example, representing the time slice,
-    ///   priority, or cycle count associated with the returned process).
+    /// - The second element is the [`TimeSlice`] the returned process may run for:
+    ///   `TimeSlice::Ticks(n)` to preempt it after `n` ticks, or `TimeSlice::Infinite`
+    ///   to run it to completion (FCFS).
     ///
     /// # Behavior
     /// If the scheduler has not been implemented yet (`self.implemented == false`),
@@ -54,12 +75,12 @@ impl Schedule for SimpleMLFSchedule {
     /// You do not need to maintain this struct element or functionality if you implement this
     /// scheduler, but if you don't this is the behavior it should have when submitted for
     /// grading if not implemented.
-    fn next_process(&mut self) -> (Option<PCB>, u32){
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice){
         if !self.implemented {
             println!("Not Implemented");
             std::process::exit(0);
         }
-        (None,0)
+        (None,TimeSlice::Infinite)
     }
     /// Checks whether the scheduler currently has any processes pending.
     ///
@@ -80,6 +101,19 @@ impl Schedule for SimpleMLFSchedule {
         }
         false
     }
+
+    /// Describes the simplified MLFQ the assignment asks for, with the configured
+    /// quanta. Promoting processes that waited too long keeps any from
+    /// starving, at the cost of looking at every waiting process.
+    fn info(&self) -> Option<SchedulerInfo> {
+        Some(SchedulerInfo {
+            is_preemptive: self.config.quanta.iter().any(|&quantum| quantum > 0),
+            starvation_free: true,
+            levels: self.config.quanta.len(),
+            default_quantum: TimeSlice::from_raw(self.config.quanta.first().copied().unwrap_or(0)),
+            dispatch: Complexity::Linear,
+        })
+    }
 }
 
 impl SimpleMLFSchedule {
@@ -0,0 +1,1291 @@
+//! # Simulation Module
+//!
+//! The simulator's engine, for running a scheduler from library code.
+//!
+//! [`Simulation`] drives any [`Schedule`] through a set of [`Job`]s the way
+//! `scheduler -s <name>` does for plugins and scripted or closed-loop
+//! workloads: arrivals are handed to the scheduler at the tick they happen,
+//! the dispatched process runs for at most its [`Quantum`] and is then added
+//! back, a job with a burst script blocks for each of its I/O steps, and the
+//! idle task runs while nothing is ready. Everything that happens is emitted
+//! to a [`Recorder`], so the run's metrics and trace come out exactly as from
+//! the command line.
+//!
+//! [`SimulationBuilder`] sets up a run, including hooks that are called at
+//! defined points of it: [`on_dispatch`](SimulationBuilder::on_dispatch),
+//! [`on_preempt`](SimulationBuilder::on_preempt),
+//! [`on_finish`](SimulationBuilder::on_finish),
+//! [`on_timeout`](SimulationBuilder::on_timeout) and
+//! [`on_tick`](SimulationBuilder::on_tick). A hook sees the scheduler and the
+//! recorder through its [`Context`], and can also steer the run by
+//! submitting new jobs or stopping it, so tests, visualizers and research
+//! scripts need no engine of their own.
+//!
+//! Groups of processes can be given `cpu.max`-style CPU limits with
+//! [`bandwidth`](SimulationBuilder::bandwidth), see [`crate::bandwidth`].
+//!
+//! [`hz`](SimulationBuilder::hz) decouples the scheduling timer from the
+//! clock: the timer interrupt fires that many times per second of simulated
+//! time instead of every tick, and a [`Quantum`] counts timer interrupts, as
+//! jiffies do in Linux. A process dispatched between two interrupts runs
+//! for a little less than its quantum, and [`timer`](Simulation::timer)
+//! says how many interrupts the run took.
+//!
+//! A [`tickless`](SimulationBuilder::tickless) run has no periodic timer
+//! at all. The timer is only programmed for the next event that needs it,
+//! the running process's quantum expiry or an arrival, so an idle CPU or a
+//! process running to completion takes no interrupts, and a quantum lasts
+//! exactly as many timer periods as it says. [`TimerStats`] compares the
+//! interrupts with the ones a periodic tick would have taken.
+//!
+//! Jobs arriving at the same tick are handed to the scheduler in the order
+//! they were added, unless the [`ArrivalOrder`] says to hand them over by
+//! ID; see [`arrival_order`](SimulationBuilder::arrival_order). A first come,
+//! first served scheduler runs them in that order, so either way a tie
+//! between simultaneous arrivals has one documented outcome.
+//!
+//! When jobs arrive at the very tick a quantum expires, the
+//! [`ExpiryOrder`] says whether they are handed to the scheduler before the
+//! preempted process is added back, the default, or after it; see
+//! [`expiry_order`](SimulationBuilder::expiry_order). For a round robin
+//! queue that decides which of them runs first.
+//!
+//! A [`non_preemptive`](SimulationBuilder::non_preemptive) run ignores the
+//! scheduler's quantum: a dispatched process keeps the CPU until it
+//! finishes or blocks, so any policy runs as a batch system. CPU limits and
+//! timeouts still take it off the CPU.
+//!
+//! A run set up to [`catch_panics`](SimulationBuilder::catch_panics) does
+//! not unwind when the scheduler panics, but fails with a
+//! [`Fault`](crate::fault::Fault) describing the panic, the clock, the last
+//! events and the scheduler's queue; see [`crate::fault`].
+//!
+//! The arrivals, I/O completions and timeouts still to come are kept in
+//! [`Timers`] queues, a `BTreeMap` unless the run is set up with other
+//! [`timers`](SimulationBuilder::timers), such as a timing wheel. The choice
+//! changes how fast a run with many timers is, never what happens in it.
+//!
+//! [`renice`](SimulationBuilder::renice) changes a job's
+//! [nice value](crate::nice) at a tick, after the tick's arrivals: the
+//! scheduler is told through [`Schedule::renice`] and the recorder gets an
+//! [`EventKind::Renice`]. A job that is not in the system at the time is
+//! left alone.
+//!
+//! A job with a [`timeout`](Job::timeout) is cancelled if it has not
+//! finished that many ticks after it arrived: the recorder gets an
+//! [`EventKind::Timeout`] and the job is gone, whether it was running,
+//! blocked for I/O, throttled or waiting in the scheduler. The scheduler
+//! has no way to give a process up, so one that timed out while it waited
+//! there is dropped when the scheduler hands it out next. A job finishing
+//! at the very tick of its deadline has made it.
+//!
+//! [`annotate`](SimulationBuilder::annotate) puts a labeled marker at a tick,
+//! which the recorder gets, before that tick's arrivals, as an
+//! [`Annotation`](crate::event::Annotation); markers past the end of the run
+//! are dropped.
+//!
+//! Times are in ticks of the [`CLOCK`], whose tick length the caller sets.
+//!
+//! ## Example
+//! ```
+//! use std::cell::Cell;
+//! use std::collections::VecDeque;
+//! use std::time::Duration;
+//! use scheduler::simulation::{Job, Simulation};
+//! use scheduler::sink::Recorder;
+//! use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+//!
+//! struct RoundRobin(VecDeque<PCB>);
+//! impl Schedule for RoundRobin {
+//!     fn add_process(&mut self, p: PCB) -> bool { self.0.push_back(p); true }
+//!     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop_front(), TimeSlice::Ticks(2)) }
+//!     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! }
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let preempted = Cell::new(0);
+//! let mut finished = Vec::new();
+//! let mut sim = Simulation::builder(RoundRobin(VecDeque::new()))
+//!     .job(Job::new(0, 0, 5))
+//!     .job(Job::new(1, 1, 2))
+//!     .on_preempt(|_, _| preempted.set(preempted.get() + 1))
+//!     .on_finish(|ctx, process| {
+//!         finished.push((process.id, CLOCK.now_ticks()));
+//!         // a follow-up job arrives two ticks after P1 is done
+//!         if process.id == 1 {
+//!             ctx.submit(Job::new(2, CLOCK.now_ticks() + 2, 1));
+//!         }
+//!     })
+//!     .build();
+//! let mut out = Recorder::new();
+//! sim.run(&mut out).unwrap();
+//! drop(sim);
+//! // P2 arrives at tick 6, as P0's second slice runs out, and goes first
+//! assert_eq!(finished, [(1, 4), (2, 7), (0, 8)]);
+//! assert_eq!(preempted.get(), 2);
+//! assert_eq!(out.metrics().aggregates().completed, 3);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::bandwidth::Bandwidth;
+use crate::burst::Burst;
+use crate::event::{EventKind, IDLE};
+use crate::fault::{self, Fault};
+use crate::input::{Step, Workload};
+use crate::sink::Recorder;
+use crate::timer::{TimerKind, Timers};
+use crate::{CLOCK, PCB, Quantum, Schedule};
+
+/// A job to simulate.
+///
+/// # Fields
+/// - `id`: The job's ID, unique within the run.
+/// - `arrival`: Tick the job arrives at.
+/// - `burst`: CPU time the job needs, in ticks. For a job with a `script`,
+///   the length of its first CPU step.
+/// - `priority`: Priority the job's [`PCB`] is created with.
+/// - `script`: The job's alternating CPU and I/O steps, if it has a burst
+///   script; see [Burst Scripts](crate::input#burst-scripts).
+/// - `timeout`: Ticks after its arrival the job is cancelled at unless it
+///   has finished; see [Timeouts](crate::input#timeouts).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub id: u32,
+    pub arrival: u64,
+    pub burst: u32,
+    pub priority: u32,
+    pub script: Option<Vec<Step>>,
+    pub timeout: Option<u64>,
+}
+
+impl Job {
+    /// A job of priority `0` without a burst script or a timeout.
+    pub fn new(id: u32, arrival: u64, burst: u32) -> Self {
+        Self { id, arrival, burst, priority: 0, script: None, timeout: None }
+    }
+
+    /// The job with a timeout of `ticks`.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::event::EventKind;
+    /// use scheduler::policy::FCFS;
+    /// use scheduler::simulation::{Job, Simulation};
+    /// use scheduler::sink::Recorder;
+    ///
+    /// // P1 waits behind P0 until tick 4, so it times out at 3; P2 finishes right at its deadline
+    /// let mut sim = Simulation::builder(FCFS::new())
+    ///     .job(Job::new(0, 0, 4))
+    ///     .job(Job::new(1, 0, 2).timeout(3))
+    ///     .job(Job::new(2, 0, 2).timeout(6))
+    ///     .build();
+    /// let mut out = Recorder::new();
+    /// sim.run(&mut out).unwrap();
+    /// assert!(out.events().iter().any(|event| event.to_string() == "tick 3: P1 timed out"));
+    /// assert!(!out.events().iter().any(|event| event.id == 1 && matches!(event.kind, EventKind::Dispatch { .. })));
+    /// let agg = out.metrics().aggregates();
+    /// assert_eq!((agg.completed, agg.timed_out, agg.within_deadline), (2, 1, 0.5));
+    /// assert_eq!(out.metrics().process(2).unwrap().finish, Some(6));
+    /// ```
+    pub fn timeout(mut self, ticks: u64) -> Self {
+        self.timeout = Some(ticks);
+        self
+    }
+}
+
+/// How often the timer interrupted a run, see
+/// [`SimulationBuilder::hz`].
+///
+/// # Fields
+/// - `hz`: Timer interrupts per second of simulated time, `None` when the
+///   timer fired every tick.
+/// - `period`: Ticks between two interrupts of the periodic tick.
+/// - `tickless`: Whether the run was [tickless](SimulationBuilder::tickless).
+/// - `interrupts`: Interrupts during the run.
+/// - `periodic`: Interrupts a periodic tick would have taken in the same
+///   time, the same as `interrupts` unless the run was tickless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerStats {
+    pub hz: Option<u32>,
+    pub period: u64,
+    pub tickless: bool,
+    pub interrupts: u64,
+    pub periodic: u64,
+}
+
+impl TimerStats {
+    /// Interrupts the run avoided by being tickless.
+    pub fn avoided(&self) -> u64 {
+        self.periodic.saturating_sub(self.interrupts)
+    }
+}
+
+/// Who goes first when jobs arrive at the tick a quantum expires, see
+/// [`SimulationBuilder::expiry_order`].
+///
+/// Wake-ups and processes let go by a CPU limit at that tick count as
+/// arrivals. Without `--expiry-order`, `scheduler -s mlrr` and
+/// `-s simplerr` add the preempted process first, whichever loop they run
+/// on, and every other scheduler its arrivals.
+///
+/// ## Example
+/// ```
+/// use scheduler::simulation::ExpiryOrder;
+///
+/// assert_eq!("preempted-first".parse(), Ok(ExpiryOrder::PreemptedFirst));
+/// assert_eq!(ExpiryOrder::default().to_string(), "arrivals-first");
+/// assert!("last".parse::<ExpiryOrder>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpiryOrder {
+    /// The arrivals are added first, so they queue up ahead of the
+    /// preempted process.
+    #[default]
+    ArrivalsFirst,
+    /// The preempted process is added back first, ahead of the arrivals.
+    PreemptedFirst,
+}
+
+impl fmt::Display for ExpiryOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExpiryOrder::ArrivalsFirst => "arrivals-first",
+            ExpiryOrder::PreemptedFirst => "preempted-first",
+        })
+    }
+}
+
+impl FromStr for ExpiryOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "arrivals-first" => Ok(ExpiryOrder::ArrivalsFirst),
+            "preempted-first" => Ok(ExpiryOrder::PreemptedFirst),
+            _ => Err(format!("unknown expiry order '{}', expected arrivals-first or preempted-first", s)),
+        }
+    }
+}
+
+/// The order jobs arriving at the same tick are handed to the scheduler in,
+/// see [`SimulationBuilder::arrival_order`].
+///
+/// ## Example
+/// ```
+/// use scheduler::simulation::ArrivalOrder;
+///
+/// assert_eq!("id".parse(), Ok(ArrivalOrder::Id));
+/// assert_eq!(ArrivalOrder::default().to_string(), "input");
+/// assert!("random".parse::<ArrivalOrder>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrivalOrder {
+    /// The order the jobs were added in, which for a workload file is the
+    /// order of its lines.
+    #[default]
+    Input,
+    /// The lowest job ID first.
+    Id,
+}
+
+impl fmt::Display for ArrivalOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ArrivalOrder::Input => "input",
+            ArrivalOrder::Id => "id",
+        })
+    }
+}
+
+impl FromStr for ArrivalOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "input" => Ok(ArrivalOrder::Input),
+            "id" => Ok(ArrivalOrder::Id),
+            _ => Err(format!("unknown arrival order '{}', expected input or id", s)),
+        }
+    }
+}
+
+/// Why a run could not go on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimulationError {
+    /// The scheduler said it had a process ready, but returned none.
+    NoProcess,
+    /// Nothing is ready, blocked or still to arrive, yet these processes
+    /// have not finished, so they never will.
+    Stuck(Vec<u32>),
+    /// The scheduler panicked, in a run that catches panics.
+    Fault(Box<Fault>),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulationError::NoProcess => f.write_str("None Process, something went wrong in your code."),
+            SimulationError::Stuck(ids) => {
+                let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+                write!(f, "Processes {} never finished: none of them is ready and no more jobs arrive.", ids.join(", "))
+            }
+            SimulationError::Fault(fault) => write!(f, "{}", fault),
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+/// What a hook can see and do.
+pub struct Context<'a> {
+    sched: &'a dyn Schedule,
+    out: &'a mut Recorder,
+    running: Option<&'a PCB>,
+    left: Option<u32>,
+    submitted: &'a mut Vec<Job>,
+    annotations: &'a mut BTreeMap<u64, Vec<String>>,
+    renices: &'a mut BTreeMap<u64, Vec<(u32, i8)>>,
+    stop: &'a mut bool,
+}
+
+impl<'a> Context<'a> {
+    /// The scheduler being simulated.
+    pub fn scheduler(&self) -> &dyn Schedule {
+        self.sched
+    }
+
+    /// Where the run's events go, with the metrics recorded so far.
+    pub fn recorder(&mut self) -> &mut Recorder {
+        self.out
+    }
+
+    /// The process on the CPU, `None` while the idle task runs.
+    pub fn running(&self) -> Option<&PCB> {
+        self.running
+    }
+
+    /// Ticks left of the running process's quantum, `None` when it runs to
+    /// completion or nothing is running. With [`hz`](SimulationBuilder::hz)
+    /// these are timer ticks.
+    pub fn quantum_left(&self) -> Option<u32> {
+        self.left
+    }
+
+    /// Adds a job to the run. It arrives at its `arrival` tick, or at the
+    /// next tick if that has already passed.
+    pub fn submit(&mut self, job: Job) {
+        self.submitted.push(job);
+    }
+
+    /// Puts a marker labeled `label` at tick `time`, or at the current tick
+    /// if that has already passed.
+    pub fn annotate(&mut self, time: u64, label: impl Into<String>) {
+        match time > CLOCK.now_ticks() {
+            true => self.annotations.entry(time).or_default().push(label.into()),
+            false => self.out.annotate(label),
+        }
+    }
+
+    /// Changes the nice value of job `id` to `nice` at tick `time`, or at
+    /// the next tick if that has already passed.
+    pub fn renice(&mut self, time: u64, id: u32, nice: i8) {
+        self.renices.entry(time.max(CLOCK.now_ticks() + 1)).or_default().push((id, nice));
+    }
+
+    /// Ends the run once the current tick is over. The running process is
+    /// preempted, and the run returns without error.
+    pub fn stop(&mut self) {
+        *self.stop = true;
+    }
+}
+
+type DispatchHook<'h> = Box<dyn FnMut(&mut Context, &PCB, Quantum) + 'h>;
+type ProcessHook<'h> = Box<dyn FnMut(&mut Context, &PCB) + 'h>;
+type TickHook<'h> = Box<dyn FnMut(&mut Context) + 'h>;
+
+/// The hooks of a run, each optional.
+#[derive(Default)]
+struct Hooks<'h> {
+    dispatch: Option<DispatchHook<'h>>,
+    preempt: Option<ProcessHook<'h>>,
+    finish: Option<ProcessHook<'h>>,
+    timeout: Option<ProcessHook<'h>>,
+    tick: Option<TickHook<'h>>,
+}
+
+/// Sets up a [`Simulation`].
+pub struct SimulationBuilder<'h, S> {
+    sched: S,
+    jobs: Vec<Job>,
+    quanta: Vec<u32>,
+    hooks: Hooks<'h>,
+    bandwidth: Bandwidth,
+    annotations: BTreeMap<u64, Vec<String>>,
+    renices: BTreeMap<u64, Vec<(u32, i8)>>,
+    hz: Option<u32>,
+    tickless: bool,
+    non_preemptive: bool,
+    catch_panics: bool,
+    expiry: ExpiryOrder,
+    ties: ArrivalOrder,
+    timers: TimerKind,
+}
+
+impl<'h, S: Schedule> SimulationBuilder<'h, S> {
+    /// Adds a job. Jobs arriving at the same tick are handed to the
+    /// scheduler in the order they were added, unless the
+    /// [`arrival_order`](Self::arrival_order) says otherwise.
+    pub fn job(mut self, job: Job) -> Self {
+        self.jobs.push(job);
+        self
+    }
+
+    /// Adds several jobs, see [`job`](Self::job).
+    pub fn jobs(mut self, jobs: impl IntoIterator<Item = Job>) -> Self {
+        self.jobs.extend(jobs);
+        self
+    }
+
+    /// Adds every job of a parsed workload, with its burst script and
+    /// timeout, in input order, its annotations and its nice values (see
+    /// [`Workload::nice_changes`]). Closed-loop clients are not simulated;
+    /// submit their next request from [`on_finish`](Self::on_finish) instead.
+    pub fn workload(mut self, workload: &Workload) -> Self {
+        for annotation in &workload.annotations {
+            self = self.annotate(annotation.time, annotation.label.clone());
+        }
+        for renice in workload.nice_changes() {
+            self = self.renice(renice.time, renice.id, renice.nice);
+        }
+        let jobs = workload.arrivals.values().flatten().map(|id| {
+            let spec = &workload.jobs[id];
+            let script = workload.scripts.get(id).cloned();
+            let burst = match script.as_deref() {
+                Some([Step::Cpu(ticks), ..]) => *ticks,
+                _ => spec.burst,
+            };
+            Job { id: *id, arrival: spec.arrival, burst, priority: spec.priority, script, timeout: spec.timeout }
+        });
+        let jobs: Vec<Job> = jobs.collect();
+        self.jobs(jobs)
+    }
+
+    /// The scheduler's per-level time slices, as in its configuration, used
+    /// to tell which level a dispatch came from when the scheduler does not
+    /// report it (see [`Schedule::dispatched_level`]).
+    pub fn quanta(mut self, quanta: &[u32]) -> Self {
+        self.quanta = quanta.to_vec();
+        self
+    }
+
+    /// Puts a marker labeled `label` at tick `time`. Markers at the same tick
+    /// are made in the order they were added.
+    pub fn annotate(mut self, time: u64, label: impl Into<String>) -> Self {
+        self.annotations.entry(time).or_default().push(label.into());
+        self
+    }
+
+    /// Changes the nice value of job `id` to `nice` at tick `time`, once the
+    /// tick's arrivals have been handed to the scheduler. Changes at the
+    /// same tick are made in the order they were added.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::event::EventKind;
+    /// use scheduler::policy::FCFS;
+    /// use scheduler::simulation::{Job, Simulation};
+    /// use scheduler::sink::Recorder;
+    ///
+    /// let mut sim = Simulation::builder(FCFS::new())
+    ///     .job(Job::new(0, 0, 5))
+    ///     .renice(0, 0, -5)
+    ///     .renice(3, 0, 10)
+    ///     // P0 has finished by then
+    ///     .renice(9, 0, 0)
+    ///     .build();
+    /// let mut out = Recorder::new();
+    /// sim.run(&mut out).unwrap();
+    /// let renices: Vec<_> = out.events().iter().filter(|event| matches!(event.kind, EventKind::Renice { .. })).collect();
+    /// assert_eq!(renices.len(), 2);
+    /// assert_eq!(renices[1].to_string(), "tick 3: P0 reniced to 10");
+    /// ```
+    pub fn renice(mut self, time: u64, id: u32, nice: i8) -> Self {
+        self.renices.entry(time).or_default().push((id, nice));
+        self
+    }
+
+    /// Limits the CPU time of job classes, see [`crate::bandwidth`].
+    pub fn bandwidth(mut self, bandwidth: Bandwidth) -> Self {
+        self.bandwidth = bandwidth;
+        self
+    }
+
+    /// Fires the timer interrupt `hz` times per second of simulated time,
+    /// at every multiple of its period, instead of every tick, and counts
+    /// quanta in timer ticks. The period is rounded to a whole number of
+    /// [`CLOCK`] ticks, and is at least one.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::collections::VecDeque;
+    /// use std::time::Duration;
+    /// use scheduler::simulation::{Job, Simulation};
+    /// use scheduler::sink::Recorder;
+    /// use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+    ///
+    /// struct RoundRobin(VecDeque<PCB>);
+    /// impl Schedule for RoundRobin {
+    ///     fn add_process(&mut self, p: PCB) -> bool { self.0.push_back(p); true }
+    ///     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop_front(), TimeSlice::Ticks(1)) }
+    ///     fn has_process(&self) -> bool { !self.0.is_empty() }
+    /// }
+    ///
+    /// // 1ms ticks and a 100 HZ timer: a quantum of 1 runs until the next 10ms boundary
+    /// CLOCK.set_tick(Duration::from_millis(1));
+    /// let mut sim = Simulation::builder(RoundRobin(VecDeque::new()))
+    ///     .job(Job::new(0, 3, 12))
+    ///     .job(Job::new(1, 3, 5))
+    ///     .hz(100)
+    ///     .build();
+    /// let mut out = Recorder::new();
+    /// sim.run(&mut out).unwrap();
+    /// let finish = |id| out.metrics().process(id).unwrap().finish;
+    /// // P0 runs 3-10, P1 10-15, P0 15-20
+    /// assert_eq!((finish(0), finish(1)), (Some(20), Some(15)));
+    /// assert_eq!((sim.timer().period, sim.timer().interrupts), (10, 2));
+    /// ```
+    pub fn hz(mut self, hz: u32) -> Self {
+        self.hz = Some(hz);
+        self
+    }
+
+    /// Programs the timer only for the running process's quantum expiry and
+    /// the next arrival instead of firing it every period, see the
+    /// [module documentation](crate::simulation). A quantum then lasts
+    /// exactly its number of periods from the dispatch.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::collections::VecDeque;
+    /// use std::time::Duration;
+    /// use scheduler::simulation::{Job, Simulation};
+    /// use scheduler::sink::Recorder;
+    /// use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+    ///
+    /// struct RoundRobin(VecDeque<PCB>);
+    /// impl Schedule for RoundRobin {
+    ///     fn add_process(&mut self, p: PCB) -> bool { self.0.push_back(p); true }
+    ///     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop_front(), TimeSlice::Ticks(1)) }
+    ///     fn has_process(&self) -> bool { !self.0.is_empty() }
+    /// }
+    ///
+    /// CLOCK.set_tick(Duration::from_millis(1));
+    /// let mut sim = Simulation::builder(RoundRobin(VecDeque::new()))
+    ///     .job(Job::new(0, 3, 12))
+    ///     .job(Job::new(1, 3, 5))
+    ///     .job(Job::new(2, 50, 25))
+    ///     .hz(100)
+    ///     .tickless()
+    ///     .build();
+    /// let mut out = Recorder::new();
+    /// sim.run(&mut out).unwrap();
+    /// let finish = |id| out.metrics().process(id).unwrap().finish;
+    /// // P0 runs 3-13, P1 13-18, P0 18-20, the CPU idles until P2 runs 50-75
+    /// assert_eq!((finish(0), finish(1), finish(2)), (Some(20), Some(18), Some(75)));
+    /// // two arrivals and expiries at 13, 60 and 70, instead of the ticks at 10 to 70
+    /// let timer = sim.timer();
+    /// assert_eq!((timer.interrupts, timer.periodic, timer.avoided()), (5, 7, 2));
+    /// ```
+    pub fn tickless(mut self) -> Self {
+        self.tickless = true;
+        self
+    }
+
+    /// Lets every dispatched process run until it finishes or blocks,
+    /// whatever quantum the scheduler gives it, see the
+    /// [module documentation](crate::simulation). Dispatches are emitted
+    /// with a quantum of `0`, as for a first come, first served scheduler.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::policy::RR;
+    /// use scheduler::simulation::{Job, Simulation};
+    /// use scheduler::sink::Recorder;
+    ///
+    /// let finish = |non_preemptive| {
+    ///     let builder = Simulation::builder(RR::with_quantum(2)).job(Job::new(0, 0, 6)).job(Job::new(1, 0, 2));
+    ///     let mut sim = if non_preemptive { builder.non_preemptive() } else { builder }.build();
+    ///     let mut out = Recorder::new();
+    ///     sim.run(&mut out).unwrap();
+    ///     (out.metrics().process(0).unwrap().finish, out.metrics().process(1).unwrap().finish)
+    /// };
+    /// // round robin lets P1 go after P0's first slice, batch mode only once P0 is done
+    /// assert_eq!(finish(false), (Some(8), Some(4)));
+    /// assert_eq!(finish(true), (Some(6), Some(8)));
+    /// ```
+    pub fn non_preemptive(mut self) -> Self {
+        self.non_preemptive = true;
+        self
+    }
+
+    /// Catches a panic of the scheduler, or of a hook, and fails the run with
+    /// a [`SimulationError::Fault`] instead of unwinding; see
+    /// [`crate::fault`].
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::simulation::{Job, Simulation, SimulationError};
+    /// use scheduler::sink::Recorder;
+    /// use scheduler::{PCB, Schedule, TimeSlice};
+    ///
+    /// // forgets to check whether it has a process at all
+    /// struct Careless(Vec<PCB>);
+    /// impl Schedule for Careless {
+    ///     fn add_process(&mut self, p: PCB) -> bool { self.0.push(p); true }
+    ///     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (Some(self.0.remove(1)), TimeSlice::Infinite) }
+    ///     fn has_process(&self) -> bool { !self.0.is_empty() }
+    ///     fn iter_ready(&self) -> Box<dyn Iterator<Item = &PCB> + '_> { Box::new(self.0.iter()) }
+    /// }
+    ///
+    /// let mut sim = Simulation::builder(Careless(Vec::new()))
+    ///     .jobs([Job::new(0, 0, 2), Job::new(1, 0, 2), Job::new(2, 1, 2)])
+    ///     .catch_panics()
+    ///     .build();
+    /// let mut out = Recorder::new();
+    /// let Err(SimulationError::Fault(fault)) = sim.run(&mut out) else { panic!("the run should fail") };
+    /// // P1 and P2 take the second place in turn, until P0 is left alone in the queue
+    /// assert_eq!(fault.time, 4);
+    /// assert!(fault.panic.message.starts_with("removal index (is 1) should be < len (is 1)"));
+    /// assert_eq!(fault.ready.unwrap().iter().map(|p| p.id).collect::<Vec<_>>(), [0]);
+    /// ```
+    pub fn catch_panics(mut self) -> Self {
+        self.catch_panics = true;
+        self
+    }
+
+    /// Sets the order jobs arriving at the same tick are handed to the
+    /// scheduler in, jobs submitted during the run included.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::event::EventKind;
+    /// use scheduler::policy::FCFS;
+    /// use scheduler::simulation::{ArrivalOrder, Job, Simulation};
+    /// use scheduler::sink::Recorder;
+    ///
+    /// let dispatches = |order| {
+    ///     let mut sim = Simulation::builder(FCFS::new())
+    ///         .jobs([Job::new(3, 0, 1), Job::new(1, 0, 1), Job::new(2, 0, 1)])
+    ///         .arrival_order(order)
+    ///         .build();
+    ///     let mut out = Recorder::new();
+    ///     sim.run(&mut out).unwrap();
+    ///     let events = out.events().iter();
+    ///     events.filter(|event| matches!(event.kind, EventKind::Dispatch { .. })).map(|event| event.id).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(dispatches(ArrivalOrder::Input), [3, 1, 2]);
+    /// assert_eq!(dispatches(ArrivalOrder::Id), [1, 2, 3]);
+    /// ```
+    pub fn arrival_order(mut self, order: ArrivalOrder) -> Self {
+        self.ties = order;
+        self
+    }
+
+    /// Sets who goes first when jobs arrive at the tick a quantum expires.
+    /// With [`ExpiryOrder::PreemptedFirst`] the tick's arrivals, wake-ups
+    /// and markers wait until the preempted process is back in the
+    /// scheduler, and the `on_tick` hook is called after them.
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::policy::RR;
+    /// use scheduler::simulation::{ExpiryOrder, Job, Simulation};
+    /// use scheduler::sink::Recorder;
+    ///
+    /// // P1 arrives as P0's first slice of 2 runs out
+    /// let finish = |order| {
+    ///     let mut sim = Simulation::builder(RR::with_quantum(2))
+    ///         .job(Job::new(0, 0, 4))
+    ///         .job(Job::new(1, 2, 2))
+    ///         .expiry_order(order)
+    ///         .build();
+    ///     let mut out = Recorder::new();
+    ///     sim.run(&mut out).unwrap();
+    ///     (out.metrics().process(0).unwrap().finish, out.metrics().process(1).unwrap().finish)
+    /// };
+    /// assert_eq!(finish(ExpiryOrder::ArrivalsFirst), (Some(6), Some(4)));
+    /// assert_eq!(finish(ExpiryOrder::PreemptedFirst), (Some(4), Some(6)));
+    /// ```
+    pub fn expiry_order(mut self, order: ExpiryOrder) -> Self {
+        self.expiry = order;
+        self
+    }
+
+    /// Sets how the run keeps its arrivals, I/O completions and timeouts,
+    /// see [`crate::timer`].
+    ///
+    /// ## Example
+    /// ```
+    /// use scheduler::input::Step;
+    /// use scheduler::policy::RR;
+    /// use scheduler::simulation::{Job, Simulation};
+    /// use scheduler::sink::Recorder;
+    /// use scheduler::timer::TimerKind;
+    ///
+    /// // arrivals, I/O and timeouts out to tick 5 000
+    /// let events = |kind| {
+    ///     let jobs = (0..300).map(|id| {
+    ///         let script = vec![Step::Cpu(2), Step::Io(id as u64 % 90 + 1), Step::Cpu(1)];
+    ///         Job { script: Some(script), ..Job::new(id, id as u64 * 17 % 5_000, 2).timeout(60) }
+    ///     });
+    ///     let mut sim = Simulation::builder(RR::with_quantum(2)).jobs(jobs).timers(kind).build();
+    ///     let mut out = Recorder::new();
+    ///     sim.run(&mut out).unwrap();
+    ///     out.events().to_vec()
+    /// };
+    /// assert_eq!(events(TimerKind::Wheel), events(TimerKind::BTree));
+    /// assert_eq!(events(TimerKind::Heap), events(TimerKind::BTree));
+    /// ```
+    pub fn timers(mut self, kind: TimerKind) -> Self {
+        self.timers = kind;
+        self
+    }
+
+    /// Calls `hook` with every dispatched process and its quantum, before it
+    /// runs.
+    pub fn on_dispatch(mut self, hook: impl FnMut(&mut Context, &PCB, Quantum) + 'h) -> Self {
+        self.hooks.dispatch = Some(Box::new(hook));
+        self
+    }
+
+    /// Calls `hook` with every process whose quantum ran out, before it is
+    /// added back to the scheduler.
+    pub fn on_preempt(mut self, hook: impl FnMut(&mut Context, &PCB) + 'h) -> Self {
+        self.hooks.preempt = Some(Box::new(hook));
+        self
+    }
+
+    /// Calls `hook` with every process that finished, once it is gone.
+    pub fn on_finish(mut self, hook: impl FnMut(&mut Context, &PCB) + 'h) -> Self {
+        self.hooks.finish = Some(Box::new(hook));
+        self
+    }
+
+    /// Calls `hook` with every process that timed out, once it is gone, see
+    /// [`Job::timeout`].
+    pub fn on_timeout(mut self, hook: impl FnMut(&mut Context, &PCB) + 'h) -> Self {
+        self.hooks.timeout = Some(Box::new(hook));
+        self
+    }
+
+    /// Calls `hook` after every tick, busy or idle, once the arrivals and
+    /// wake-ups of the new tick have been handed to the scheduler.
+    pub fn on_tick(mut self, hook: impl FnMut(&mut Context) + 'h) -> Self {
+        self.hooks.tick = Some(Box::new(hook));
+        self
+    }
+
+    /// Finishes the setup.
+    pub fn build(self) -> Simulation<'h, S> {
+        Simulation {
+            sched: self.sched,
+            jobs: self.jobs,
+            quanta: self.quanta,
+            hooks: self.hooks,
+            bandwidth: self.bandwidth,
+            annotations: self.annotations,
+            renices: self.renices,
+            hz: self.hz,
+            tickless: self.tickless,
+            non_preemptive: self.non_preemptive,
+            catch_panics: self.catch_panics,
+            expiry: self.expiry,
+            ties: self.ties,
+            period: 1,
+            interrupts: 0,
+            periodic: 0,
+            pending: BTreeMap::new(),
+            arrivals: Timers::new(self.timers),
+            scripts: BTreeMap::new(),
+            steps: BTreeMap::new(),
+            blocked: Timers::new(self.timers),
+            deadlines: Timers::new(self.timers),
+            cancelled: BTreeMap::new(),
+            submitted: Vec::new(),
+            stopped: false,
+        }
+    }
+}
+
+/// Which queue level a dispatch with `quantum` came from: the level the
+/// scheduler `reported`, or else the one found by matching the quantum against
+/// the per-level `quanta`. Single-level schedulers have no levels; when
+/// several levels share a quantum the first is used.
+///
+/// ## Example
+/// ```
+/// use scheduler::simulation::dispatch_level;
+///
+/// assert_eq!(dispatch_level(None, &[0, 4, 1], 4), Some(1));
+/// assert_eq!(dispatch_level(Some(2), &[0, 4, 1], 4), Some(2));
+/// assert_eq!(dispatch_level(None, &[4], 4), None);
+/// ```
+pub fn dispatch_level(reported: Option<usize>, quanta: &[u32], quantum: u32) -> Option<usize> {
+    reported.or_else(|| quanta.iter().position(|&q| q == quantum).filter(|_| quanta.len() > 1))
+}
+
+/// A job that has not finished yet.
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    priority: u32,
+    /// the job's current CPU step
+    burst: Burst,
+    arrived: bool,
+    /// clock time (in nanoseconds) the job times out at
+    deadline: Option<u64>,
+}
+
+/// A run of a scheduler, see the [module documentation](self).
+pub struct Simulation<'h, S> {
+    sched: S,
+    jobs: Vec<Job>,
+    quanta: Vec<u32>,
+    hooks: Hooks<'h>,
+    bandwidth: Bandwidth,
+    /// labels of the markers still to be made, keyed by their tick
+    annotations: BTreeMap<u64, Vec<String>>,
+    /// nice value changes still to be made, keyed by their tick
+    renices: BTreeMap<u64, Vec<(u32, i8)>>,
+    hz: Option<u32>,
+    tickless: bool,
+    non_preemptive: bool,
+    catch_panics: bool,
+    expiry: ExpiryOrder,
+    ties: ArrivalOrder,
+    /// ticks between two timer interrupts, set when the run starts
+    period: u64,
+    interrupts: u64,
+    /// interrupts a periodic tick would have taken
+    periodic: u64,
+    /// jobs that have not finished, by ID
+    pending: BTreeMap<u32, Pending>,
+    /// IDs of the jobs still to arrive, due at their arrival tick
+    arrivals: Timers<u32>,
+    scripts: BTreeMap<u32, Vec<Step>>,
+    /// index of the script step each scripted job is on
+    steps: BTreeMap<u32, usize>,
+    /// processes waiting for I/O, due at the tick their I/O completes at
+    blocked: Timers<PCB>,
+    /// job IDs, due at the tick they time out at
+    deadlines: Timers<u32>,
+    /// processes of jobs that timed out while the scheduler or a CPU limit
+    /// held them, by ID, to be dropped when they come back
+    cancelled: BTreeMap<u32, u32>,
+    /// jobs the hooks submitted since they were last added
+    submitted: Vec<Job>,
+    stopped: bool,
+}
+
+impl<'h, S: Schedule> Simulation<'h, S> {
+    /// Starts setting up a run of `sched`.
+    pub fn builder(sched: S) -> SimulationBuilder<'h, S> {
+        SimulationBuilder {
+            sched,
+            jobs: Vec::new(),
+            quanta: Vec::new(),
+            hooks: Hooks::default(),
+            bandwidth: Bandwidth::new(),
+            annotations: BTreeMap::new(),
+            renices: BTreeMap::new(),
+            hz: None,
+            tickless: false,
+            non_preemptive: false,
+            catch_panics: false,
+            expiry: ExpiryOrder::default(),
+            ties: ArrivalOrder::default(),
+            timers: TimerKind::default(),
+        }
+    }
+
+    /// The scheduler being simulated.
+    pub fn scheduler(&self) -> &S {
+        &self.sched
+    }
+
+    /// The CPU limits of the run, with how often each group was throttled.
+    pub fn bandwidth(&self) -> &Bandwidth {
+        &self.bandwidth
+    }
+
+    /// How often the timer interrupted the run.
+    pub fn timer(&self) -> TimerStats {
+        TimerStats {
+            hz: self.hz,
+            period: self.period,
+            tickless: self.tickless,
+            interrupts: self.interrupts,
+            periodic: self.periodic,
+        }
+    }
+
+    /// Consumes the run, returning the scheduler.
+    pub fn into_scheduler(self) -> S {
+        self.sched
+    }
+
+    /// Runs every job to completion, emitting what happens to `out`. The
+    /// [`CLOCK`] starts at `0`. The run ends early when `out` reaches one of
+    /// its stop conditions or a hook stops it.
+    ///
+    /// # Errors
+    /// When the scheduler misbehaves so badly the run cannot go on; see
+    /// [`SimulationError`].
+    pub fn run(&mut self, out: &mut Recorder) -> Result<(), SimulationError> {
+        if !self.catch_panics {
+            return self.simulate(out);
+        }
+        fault::catch(|| self.simulate(out)).unwrap_or_else(|panic| Err(SimulationError::Fault(Box::new(Fault::new(panic, &self.sched, out)))))
+    }
+
+    /// Runs the jobs, see [`run`](Self::run).
+    fn simulate(&mut self, out: &mut Recorder) -> Result<(), SimulationError> {
+        CLOCK.set_now(Duration::ZERO);
+        let tick = CLOCK.tick().as_nanos() as f64;
+        self.period = self.hz.map_or(1, |hz| ((1e9 / hz as f64 / tick).round() as u64).max(1));
+        (self.interrupts, self.periodic) = (0, 0);
+        for job in std::mem::take(&mut self.jobs) {
+            self.add(job, 0);
+        }
+        self.admit(out);
+        while !self.pending.is_empty() && !out.stopped() && !self.stopped {
+            if !self.sched.has_process() {
+                if self.blocked.is_empty() && !self.bandwidth.holds_any() && self.arrivals.is_empty() {
+                    return Err(SimulationError::Stuck(self.pending.keys().copied().collect()));
+                }
+                out.emit(IDLE, EventKind::Idle);
+                CLOCK.advance(CLOCK.tick());
+                self.timer_tick();
+                self.expire(out, None);
+                self.admit(out);
+                self.wake(out);
+                self.refill(out);
+                self.tick(out, None, None);
+                continue;
+            }
+            let Ok((process, quantum)) = self.sched.try_next() else {
+                return Err(SimulationError::NoProcess);
+            };
+            if self.dropped(process.id) {
+                continue;
+            }
+            //a process of a throttled group may not run until its quota is refilled
+            let group = self.limited_group(out, process.id);
+            if let Some(group) = group.as_deref().filter(|group| self.bandwidth.is_throttled(group)) {
+                out.emit(process.id, EventKind::Throttle);
+                self.bandwidth.hold(group, process);
+                continue;
+            }
+            //the level is the scheduler's, whatever the process is let run for
+            let level = quantum.raw();
+            let quantum = if self.non_preemptive { Quantum::FCFS } else { quantum };
+            out.emit(process.id, EventKind::Dispatch { quantum: quantum.raw() });
+            self.note_level(out, process.id, level);
+            out.note_heap_size(self.sched.heap_size());
+            if let Some(hook) = &mut self.hooks.dispatch {
+                let mut ctx = Context {
+                    sched: &self.sched,
+                    out,
+                    running: Some(&process),
+                    left: quantum.limit(),
+                    submitted: &mut self.submitted,
+                    annotations: &mut self.annotations,
+                    renices: &mut self.renices,
+                    stop: &mut self.stopped,
+                };
+                hook(&mut ctx, &process, quantum);
+            }
+            self.add_submitted();
+            let Some(mut burst) = self.pending.get(&process.id).map(|job| job.burst) else {
+                continue;
+            };
+            //timer ticks since the dispatch, which the quantum counts: periods
+            //since the dispatch when tickless, else the periodic ticks
+            let (mut ran, mut expired) = (0, 0);
+            //set when the tick the quantum expired at is left to finish once the process is back
+            let mut deferred = false;
+            let mut timed_out = false;
+            let throttled = |bandwidth: &Bandwidth| group.as_deref().is_some_and(|group| bandwidth.is_throttled(group));
+            while !burst.is_done()
+                && quantum.limit().is_none_or(|limit| expired < limit)
+                && !throttled(&self.bandwidth)
+                && !out.stopped()
+                && !self.stopped
+            {
+                out.emit(process.id, EventKind::Execute);
+                CLOCK.advance(CLOCK.tick());
+                burst.run();
+                ran += 1;
+                let fired = self.timer_tick();
+                expired = match self.tickless {
+                    true => (ran / self.period) as u32,
+                    false => expired + fired as u32,
+                };
+                if let Some(group) = &group {
+                    self.bandwidth.charge(group, CLOCK.now_ticks());
+                }
+                if !burst.is_done() && self.pending[&process.id].deadline.is_some_and(|deadline| CLOCK.now_ns() >= deadline) {
+                    timed_out = true;
+                    break;
+                }
+                if self.expiry == ExpiryOrder::PreemptedFirst && !burst.is_done() && quantum.limit().is_some_and(|limit| expired >= limit) {
+                    deferred = true;
+                    break;
+                }
+                self.expire(out, Some(process.id));
+                self.admit(out);
+                self.wake(out);
+                self.refill(out);
+                self.tick(out, Some(&process), quantum.limit().map(|limit| limit - expired));
+            }
+            if self.tickless && quantum.limit().is_some_and(|limit| expired >= limit) {
+                self.interrupts += 1;
+            }
+            if timed_out {
+                out.emit(process.id, EventKind::Timeout);
+                self.forget(process.id);
+                self.process_hook(out, &process, |hooks| &mut hooks.timeout);
+                self.expire(out, None);
+                self.admit(out);
+                self.wake(out);
+                self.refill(out);
+                self.tick(out, None, None);
+                continue;
+            }
+            if !burst.is_done() {
+                out.emit(process.id, EventKind::Preempt);
+                burst.split();
+                self.pending.entry(process.id).and_modify(|job| job.burst = burst);
+                self.process_hook(out, &process, |hooks| &mut hooks.preempt);
+                match group.as_deref().filter(|_| throttled(&self.bandwidth)) {
+                    Some(group) => {
+                        out.emit(process.id, EventKind::Throttle);
+                        self.bandwidth.hold(group, process);
+                    }
+                    None => {
+                        self.sched.add_process(process);
+                    }
+                }
+                if deferred {
+                    self.expire(out, None);
+                    self.admit(out);
+                    self.wake(out);
+                    self.refill(out);
+                    self.tick(out, Some(&process), Some(0));
+                }
+                continue;
+            }
+            let step = self.steps.entry(process.id).or_default();
+            if let Some(&[Step::Io(io), Step::Cpu(next)]) =
+                self.scripts.get(&process.id).and_then(|s| s.get(*step + 1..*step + 3))
+            {
+                *step += 2;
+                out.emit(process.id, EventKind::Block);
+                self.pending.entry(process.id).and_modify(|job| job.burst = Burst::new(next));
+                self.blocked.insert(CLOCK.now_ticks() + io, process);
+                continue;
+            }
+            out.emit(process.id, EventKind::Finish);
+            self.forget(process.id);
+            self.process_hook(out, &process, |hooks| &mut hooks.finish);
+        }
+        Ok(())
+    }
+
+    /// Adds a job, to arrive at its arrival tick, but not before `earliest`
+    /// (in nanoseconds).
+    fn add(&mut self, job: Job, earliest: u64) {
+        let tick_ns = CLOCK.tick().as_nanos() as u64;
+        let arrival = (job.arrival * tick_ns).max(earliest);
+        self.arrivals.insert(arrival / tick_ns, job.id);
+        let deadline = job.timeout.map(|ticks| arrival + ticks * tick_ns);
+        self.pending.insert(job.id, Pending { priority: job.priority, burst: Burst::new(job.burst), arrived: false, deadline });
+        if let Some(deadline) = deadline {
+            self.deadlines.insert(deadline / tick_ns, job.id);
+        }
+        if let Some(script) = job.script {
+            self.scripts.insert(job.id, script);
+        }
+    }
+
+    /// Adds the jobs the hooks submitted. The arrivals of the current tick
+    /// have already been handed to the scheduler, so they arrive at the next
+    /// tick at the earliest.
+    fn add_submitted(&mut self) {
+        let next = CLOCK.now_ns() + CLOCK.tick().as_nanos() as u64;
+        for job in std::mem::take(&mut self.submitted) {
+            self.add(job, next);
+        }
+    }
+
+    /// Counts an interrupt of the periodic tick if the clock is on one,
+    /// returning whether it was. A tickless run only counts it as one it
+    /// would have taken.
+    fn timer_tick(&mut self) -> bool {
+        let fired = CLOCK.now_ticks().is_multiple_of(self.period);
+        self.periodic += fired as u64;
+        if !self.tickless {
+            self.interrupts += fired as u64;
+        }
+        fired
+    }
+
+    /// Calls the `on_tick` hook.
+    fn tick(&mut self, out: &mut Recorder, running: Option<&PCB>, left: Option<u32>) {
+        if let Some(hook) = &mut self.hooks.tick {
+            let mut ctx = Context {
+                sched: &self.sched,
+                out,
+                running,
+                left,
+                submitted: &mut self.submitted,
+                annotations: &mut self.annotations,
+                renices: &mut self.renices,
+                stop: &mut self.stopped,
+            };
+            hook(&mut ctx);
+        }
+        self.add_submitted();
+    }
+
+    /// Calls the `on_preempt`, `on_finish` or `on_timeout` hook, as picked
+    /// by `pick`.
+    fn process_hook(
+        &mut self,
+        out: &mut Recorder,
+        process: &PCB,
+        pick: impl for<'s> FnOnce(&'s mut Hooks<'h>) -> &'s mut Option<ProcessHook<'h>>,
+    ) {
+        let Self { sched, hooks, submitted, annotations, renices, stopped, .. } = self;
+        if let Some(hook) = pick(hooks) {
+            let mut ctx = Context { sched, out, running: None, left: None, submitted, annotations, renices, stop: stopped };
+            hook(&mut ctx, process);
+        }
+        self.add_submitted();
+    }
+
+    /// Notes which queue level a dispatch came from, see [`dispatch_level`].
+    fn note_level(&self, out: &mut Recorder, id: u32, quantum: u32) {
+        if let Some(level) = dispatch_level(self.sched.dispatched_level(), &self.quanta, quantum) {
+            out.note_level(id, level as u32);
+        }
+    }
+
+    /// Makes the markers of the current time, hands every job arriving at
+    /// it to the scheduler and then makes its nice value changes.
+    fn admit(&mut self, out: &mut Recorder) {
+        for label in self.annotations.remove(&CLOCK.now_ticks()).into_iter().flatten() {
+            out.annotate(label);
+        }
+        let mut ids = self.arrivals.expire(CLOCK.now_ticks());
+        //a tickless timer is programmed for the next arrival
+        if self.tickless && !ids.is_empty() {
+            self.interrupts += 1;
+        }
+        if self.ties == ArrivalOrder::Id {
+            ids.sort_unstable();
+        }
+        for id in ids {
+            let Some(job) = self.pending.get_mut(&id) else {
+                continue;
+            };
+            job.arrived = true;
+            let pcb = PCB { id, priority: job.priority, time_added: None, time_scheduled: None };
+            out.emit(pcb.id, EventKind::Arrive { priority: pcb.priority });
+            if let Some(deadline) = job.deadline {
+                out.note_deadline(id, deadline / CLOCK.tick().as_nanos() as u64);
+            }
+            self.sched.add_process(pcb);
+        }
+        for (id, nice) in self.renices.remove(&CLOCK.now_ticks()).into_iter().flatten() {
+            if self.pending.get(&id).is_some_and(|job| job.arrived) {
+                out.emit(id, EventKind::Renice { nice });
+                self.sched.renice(id, nice);
+            }
+        }
+    }
+
+    /// The class of process `id`, if it is a group with a CPU limit.
+    fn limited_group(&self, out: &Recorder, id: u32) -> Option<String> {
+        if self.bandwidth.is_empty() {
+            return None;
+        }
+        let class = out.metrics().process(id)?.class.as_deref()?;
+        self.bandwidth.max(class).map(|_| class.to_string())
+    }
+
+    /// Cancels every job that times out at the current time, other than the
+    /// `running` one, which the run loop sees to.
+    fn expire(&mut self, out: &mut Recorder, running: Option<u32>) {
+        for id in self.deadlines.expire(CLOCK.now_ticks()) {
+            //the job may have finished, and its ID been taken by a job submitted since
+            let due = self.pending.get(&id).is_some_and(|job| job.arrived && job.deadline == Some(CLOCK.now_ns()));
+            if Some(id) == running || !due {
+                continue;
+            }
+            out.emit(id, EventKind::Timeout);
+            let pcb = PCB { id, priority: self.pending[&id].priority, time_added: None, time_scheduled: None };
+            self.forget(id);
+            let before = self.blocked.len();
+            self.blocked.retain(|pcb| pcb.id != id);
+            if self.blocked.len() == before {
+                *self.cancelled.entry(id).or_default() += 1;
+            }
+            self.process_hook(out, &pcb, |hooks| &mut hooks.timeout);
+        }
+    }
+
+    /// Whether the process of job `id` the scheduler or a CPU limit just
+    /// handed back is one of a job that timed out, and so is dropped.
+    fn dropped(&mut self, id: u32) -> bool {
+        let Some(count) = self.cancelled.get_mut(&id) else {
+            return false;
+        };
+        *count -= 1;
+        if *count == 0 {
+            self.cancelled.remove(&id);
+        }
+        true
+    }
+
+    /// Forgets a job that finished or timed out.
+    fn forget(&mut self, id: u32) {
+        self.pending.remove(&id);
+        self.steps.remove(&id);
+        self.scripts.remove(&id);
+    }
+
+    /// Hands the processes of every group whose quota is refilled at the
+    /// current time back to the scheduler.
+    fn refill(&mut self, out: &mut Recorder) {
+        for pcb in self.bandwidth.refill(CLOCK.now_ticks()) {
+            if self.dropped(pcb.id) {
+                continue;
+            }
+            out.emit(pcb.id, EventKind::Unthrottle);
+            self.sched.add_process(pcb);
+        }
+    }
+
+    /// Hands every process whose I/O completes at the current time back to
+    /// the scheduler.
+    fn wake(&mut self, out: &mut Recorder) {
+        for pcb in self.blocked.expire(CLOCK.now_ticks()) {
+            out.emit(pcb.id, EventKind::Wake);
+            self.sched.add_process(pcb);
+        }
+    }
+}
@@ -0,0 +1,734 @@
+//! # Sink Module
+//!
+//! The simulators report what happens through a [`Recorder`], which stamps
+//! each [`Event`] with the current [`CLOCK`](crate::CLOCK) time, keeps the
+//! event log and [`Metrics`] for the results, and forwards every event to the
+//! registered [`EventSink`]s (console output and the like).
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::EventKind;
+//! use scheduler::sink::Recorder;
+//!
+//! let mut out = Recorder::new();
+//! out.emit(7, EventKind::Arrive { priority: 0 });
+//! out.emit(7, EventKind::Dispatch { quantum: 0 });
+//! out.emit(7, EventKind::Execute);
+//! // Execute events feed the metrics but are not kept in the log
+//! assert_eq!(out.events().len(), 2);
+//! assert_eq!(out.metrics().process(7).unwrap().cpu, 1);
+//! ```
+//!
+//! ## Allocation-Free Ticks
+//! Most of a run is `Execute` ticks, so once a process is running a tick
+//! must not allocate: execute events are not kept in the log, metrics update
+//! in place, and the console sinks write straight to their output (nothing at
+//! all when quiet). Under a counting allocator, no tick of a round robin run
+//! allocates while a process runs on within its time slice:
+//! ```standalone_crate
+//! use std::alloc::{GlobalAlloc, Layout, System};
+//! use std::cell::Cell;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use std::time::Duration;
+//!
+//! use scheduler::CLOCK;
+//! use scheduler::output::Output;
+//! use scheduler::policy::RR;
+//! use scheduler::simulation::{Job, Simulation};
+//! use scheduler::sink::{Recorder, TextSink, Verbosity};
+//!
+//! struct Counting;
+//! static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! unsafe impl GlobalAlloc for Counting {
+//!     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+//!         ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+//!         unsafe { System.alloc(layout) }
+//!     }
+//!     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+//!         unsafe { System.dealloc(ptr, layout) }
+//!     }
+//! }
+//!
+//! #[global_allocator]
+//! static GLOBAL: Counting = Counting;
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let mut out = Recorder::new();
+//! let trace = TextSink { with_priority: false, verbosity: Verbosity::Quiet, messages: Default::default(), out: Output::stdout() };
+//! out.add_sink(Box::new(trace));
+//! // the process of the previous tick, the ticks left of its slice and the allocations by then
+//! let previous: Cell<Option<(u32, Option<u32>, usize)>> = Cell::new(None);
+//! let (steady, allocating) = (Cell::new(0), Cell::new(0));
+//! let mut sim = Simulation::builder(RR::with_quantum(100))
+//!     .jobs([Job::new(0, 0, 5_000), Job::new(1, 0, 5_000)])
+//!     .on_tick(|ctx| {
+//!         let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+//!         let running = ctx.running().map(|process| (process.id, ctx.quantum_left()));
+//!         if let (Some((id, _)), Some((before, Some(1..), count))) = (running, previous.get())
+//!             && id == before
+//!         {
+//!             steady.set(steady.get() + 1);
+//!             if allocations != count {
+//!                 allocating.set(allocating.get() + 1);
+//!             }
+//!         }
+//!         previous.set(running.map(|(id, left)| (id, left, allocations)));
+//!     })
+//!     .build();
+//! sim.run(&mut out).unwrap();
+//! assert!(steady.get() > 9_000);
+//! assert_eq!(allocating.get(), 0);
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bandwidth::GroupStats;
+use crate::simulation::TimerStats;
+use crate::event::{Annotation, Event, EventKind};
+use crate::messages::Catalog;
+use crate::metrics::{Metrics, Window};
+use crate::output::Output;
+use crate::clock::TimeUnit;
+use crate::results::{Provenance, RESULTS_VERSION, RunConfig, StreamRecord};
+use crate::CLOCK;
+
+/// A consumer of simulation events.
+pub trait EventSink {
+    /// Called for every event, in the order they happen.
+    fn event(&mut self, event: &Event);
+
+    /// Called for every annotation, in order with the events.
+    fn annotate(&mut self, _annotation: &Annotation) {}
+
+    /// Called once after the simulation has finished.
+    fn finish(&mut self) {}
+}
+
+/// How much of the event stream the console trace shows.
+///
+/// - `Quiet` (`-q`): nothing per event; only the end-of-run summary.
+/// - `Normal`: the classic trace the reference outputs in `tests/` use.
+/// - `Verbose` (`-v`): also dispatches, preemptions, I/O and idle ticks.
+/// - `Debug` (`-vv`): as `Verbose`, with every line prefixed by its tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Verbosity {
+    Quiet,
+    #[default]
+    Normal,
+    Verbose,
+    Debug,
+}
+
+impl Verbosity {
+    /// Maps the `-q` flag and the number of `-v` flags onto a level.
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::Debug,
+        }
+    }
+
+    /// Returns whether events of `kind` are printed at this level.
+    ///
+    /// # Example
+    /// ```
+    /// use scheduler::event::EventKind;
+    /// use scheduler::sink::Verbosity;
+    ///
+    /// assert!(Verbosity::Normal.shows(&EventKind::Execute));
+    /// assert!(!Verbosity::Normal.shows(&EventKind::Preempt));
+    /// assert!(Verbosity::Verbose.shows(&EventKind::Preempt));
+    /// assert!(!Verbosity::Quiet.shows(&EventKind::Finish));
+    /// ```
+    pub fn shows(self, kind: &EventKind) -> bool {
+        match kind {
+            EventKind::Arrive { .. } | EventKind::Execute | EventKind::Finish | EventKind::Timeout => {
+                self >= Verbosity::Normal
+            }
+            EventKind::Dispatch { .. }
+            | EventKind::Preempt
+            | EventKind::Block
+            | EventKind::Wake
+            | EventKind::Throttle
+            | EventKind::Unthrottle
+            | EventKind::Renice { .. }
+            | EventKind::Idle => {
+                self >= Verbosity::Verbose
+            }
+        }
+    }
+}
+
+/// Prints the line-per-event trace. At [`Verbosity::Normal`] this is exactly
+/// the format the reference outputs in `tests/` are written in.
+///
+/// # Fields
+/// - `with_priority`: Whether arrivals print the process priority, which only
+///   the priority-based schedulers do.
+/// - `verbosity`: Which events are printed.
+/// - `messages`: The wording of the lines, see [`crate::messages`]; English
+///   by default.
+/// - `out`: Where the trace is written.
+pub struct TextSink {
+    pub with_priority: bool,
+    pub verbosity: Verbosity,
+    pub messages: Catalog,
+    pub out: Output,
+}
+
+impl EventSink for TextSink {
+    fn event(&mut self, event: &Event) {
+        if !self.verbosity.shows(&event.kind) {
+            return;
+        }
+        let out = &mut self.out;
+        if self.verbosity == Verbosity::Debug {
+            write!(out, "[t={}] ", event.time).expect("failed to write trace");
+        }
+        self.messages.write_event(out, event, self.with_priority).expect("failed to write trace");
+    }
+
+    fn annotate(&mut self, annotation: &Annotation) {
+        if self.verbosity == Verbosity::Quiet {
+            return;
+        }
+        if self.verbosity == Verbosity::Debug {
+            write!(self.out, "[t={}] ", annotation.time).expect("failed to write trace");
+        }
+        self.messages.write_annotation(&mut self.out, &annotation.label).expect("failed to write trace");
+    }
+}
+
+/// A colorized console trace for terminals: every process id gets its own
+/// color, columns are aligned, and preemptions and finishes stand out.
+///
+/// Uses the same [`Verbosity`] filtering as [`TextSink`], but is meant for
+/// people watching a run rather than for diffing against reference outputs.
+///
+/// # Example
+/// ```
+/// use scheduler::sink::PrettySink;
+///
+/// // a process is always drawn in the same color
+/// assert_eq!(PrettySink::color(3), PrettySink::color(3));
+/// assert_ne!(PrettySink::color(3), PrettySink::color(4));
+/// ```
+pub struct PrettySink {
+    pub verbosity: Verbosity,
+    pub out: Output,
+}
+
+impl PrettySink {
+    const RESET: &'static str = "\x1b[0m";
+    const COLORS: [&'static str; 12] = [
+        "\x1b[31m", "\x1b[32m", "\x1b[33m", "\x1b[34m", "\x1b[35m", "\x1b[36m",
+        "\x1b[91m", "\x1b[92m", "\x1b[93m", "\x1b[94m", "\x1b[95m", "\x1b[96m",
+    ];
+
+    /// Returns the ANSI escape sequence used for process `id`.
+    pub fn color(id: u32) -> &'static str {
+        Self::COLORS[id as usize % Self::COLORS.len()]
+    }
+}
+
+impl EventSink for PrettySink {
+    fn event(&mut self, event: &Event) {
+        if !self.verbosity.shows(&event.kind) {
+            return;
+        }
+        // written piece by piece so that a shown tick never allocates
+        let out = &mut self.out;
+        write!(out, "{:>8}  {}", event.time, Self::color(event.id)).expect("failed to write trace");
+        match event.kind {
+            EventKind::Idle => write!(out, "{:<6}", "idle"),
+            _ => write!(out, "P{:<5}", event.id),
+        }
+        .expect("failed to write trace");
+        write!(out, "{}  ", Self::RESET).expect("failed to write trace");
+        match event.kind {
+            EventKind::Arrive { priority } => writeln!(out, "arrived, priority {}", priority),
+            EventKind::Dispatch { quantum: 0 } => writeln!(out, "dispatched to completion"),
+            EventKind::Dispatch { quantum } => writeln!(out, "dispatched for {}", quantum),
+            EventKind::Execute => writeln!(out, "executed"),
+            EventKind::Preempt => writeln!(out, "\x1b[1;33mpreempted{}", Self::RESET),
+            EventKind::Block => writeln!(out, "\x1b[2mblocked for I/O{}", Self::RESET),
+            EventKind::Wake => writeln!(out, "woke from I/O"),
+            EventKind::Throttle => writeln!(out, "\x1b[2mthrottled{}", Self::RESET),
+            EventKind::Unthrottle => writeln!(out, "unthrottled"),
+            EventKind::Renice { nice } => writeln!(out, "reniced to {}", nice),
+            EventKind::Finish => writeln!(out, "\x1b[1;32mfinished{}", Self::RESET),
+            EventKind::Timeout => writeln!(out, "\x1b[1;31mtimed out{}", Self::RESET),
+            EventKind::Idle => writeln!(out),
+        }
+        .expect("failed to write trace");
+    }
+
+    fn annotate(&mut self, annotation: &Annotation) {
+        if self.verbosity == Verbosity::Quiet {
+            return;
+        }
+        writeln!(self.out, "{:>8}  \x1b[1m--- {} ---{}", annotation.time, annotation.label, Self::RESET)
+            .expect("failed to write trace");
+    }
+}
+
+/// Streams results as JSON Lines (see [`StreamRecord`]) while the simulation
+/// runs, flushing the writer every `flush_every` ticks so long runs can be
+/// monitored and partially recovered.
+///
+/// # Example
+/// ```
+/// use scheduler::event::{Event, EventKind};
+/// use scheduler::results::RunConfig;
+/// use scheduler::sink::{EventSink, JsonStreamSink};
+/// use std::io::Cursor;
+///
+/// let config = RunConfig { scheduler: "simple".into(), input_file: "t1.in".into(), ..Default::default() };
+/// let mut sink = JsonStreamSink::new(Cursor::new(Vec::new()), config, None, 10);
+/// sink.event(&Event { time: 0, id: 1, kind: EventKind::Arrive { priority: 0 } });
+/// sink.event(&Event { time: 12, id: 1, kind: EventKind::Finish });
+/// sink.finish();
+/// let text = String::from_utf8(sink.into_inner().into_inner()).unwrap();
+/// let records: Vec<String> = text
+///     .lines()
+///     .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["record"].to_string())
+///     .collect();
+/// assert_eq!(records, [r#""header""#, r#""event""#, r#""snapshot""#, r#""event""#, r#""summary""#]);
+/// ```
+pub struct JsonStreamSink<W: Write> {
+    writer: W,
+    metrics: Metrics,
+    flush_every: u64,
+    next_flush: u64,
+}
+
+impl<W: Write> JsonStreamSink<W> {
+    /// Creates the sink and writes the header line.
+    ///
+    /// # Parameters
+    /// - `writer`: Where the JSON Lines go.
+    /// - `config`: The run configuration recorded in the header.
+    /// - `provenance`: Where the results come from, also for the header.
+    /// - `flush_every`: Ticks between snapshots and flushes (at least `1`).
+    pub fn new(writer: W, config: RunConfig, provenance: Option<Provenance>, flush_every: u64) -> Self {
+        let flush_every = flush_every.max(1);
+        let metrics = Metrics::with_window(config.measure);
+        let mut sink = Self { writer, metrics, flush_every, next_flush: flush_every };
+        sink.write(&StreamRecord::Header { results_version: RESULTS_VERSION, provenance, config });
+        sink
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write(&mut self, record: &StreamRecord) {
+        let line = serde_json::to_string(record).expect("records are always serializable");
+        writeln!(self.writer, "{}", line).expect("failed to write results stream");
+    }
+
+    fn flush(&mut self) {
+        self.writer.flush().expect("failed to flush results stream");
+    }
+}
+
+impl<W: Write> EventSink for JsonStreamSink<W> {
+    fn event(&mut self, event: &Event) {
+        if event.time >= self.next_flush {
+            let time = event.time - event.time % self.flush_every;
+            let aggregates = self.metrics.aggregates();
+            self.write(&StreamRecord::Snapshot { time, aggregates });
+            self.flush();
+            self.next_flush = time + self.flush_every;
+        }
+        self.metrics.record(event);
+        if !event.kind.is_tick() {
+            self.write(&StreamRecord::Event(*event));
+        }
+    }
+
+    fn annotate(&mut self, annotation: &Annotation) {
+        self.write(&StreamRecord::Annotation(annotation.clone()));
+    }
+
+    fn finish(&mut self) {
+        let processes = self.metrics.processes();
+        let aggregates = self.metrics.aggregates();
+        self.write(&StreamRecord::Summary { processes, aggregates });
+        self.flush();
+    }
+}
+
+/// Samples every live process's share of the CPU at a fixed interval and
+/// writes the samples as CSV, one row per process and sample:
+///
+/// ```text
+/// time,id,cpu,share,interval_share
+/// ```
+///
+/// `cpu` is the CPU time the process has had so far and `share` that time
+/// divided by `time`; `interval_share` is the fraction of the last interval
+/// it spent running. A process is sampled from the interval it arrives in up
+/// to the one it finishes in, so the `interval_share`s of one sample add up
+/// to the CPU utilization over that interval, ready for a stacked-area plot.
+/// All times are in ticks. The simulator starts the file with a `#` comment
+/// line recording the run's [`Provenance`], so tell CSV readers to skip
+/// comments (e.g. `pandas.read_csv(path, comment="#")`).
+///
+/// # Example
+/// ```
+/// use scheduler::event::{Event, EventKind};
+/// use scheduler::sink::{CpuShareSink, EventSink};
+///
+/// let mut sink = CpuShareSink::new(Vec::new(), 2);
+/// for (time, id, kind) in [
+///     (0, 1, EventKind::Arrive { priority: 0 }),
+///     (0, 2, EventKind::Arrive { priority: 0 }),
+///     (0, 1, EventKind::Execute),
+///     (1, 1, EventKind::Execute),
+///     (2, 1, EventKind::Finish),
+///     (2, 2, EventKind::Execute),
+///     (3, 2, EventKind::Finish),
+/// ] {
+///     sink.event(&Event { time, id, kind });
+/// }
+/// sink.finish();
+/// let csv = String::from_utf8(sink.into_inner()).unwrap();
+/// assert_eq!(csv, "time,id,cpu,share,interval_share\n\
+///                  2,1,2,1.000,1.000\n\
+///                  2,2,0,0.000,0.000\n\
+///                  3,2,1,0.333,1.000\n");
+/// ```
+pub struct CpuShareSink<W: Write> {
+    writer: W,
+    every: u64,
+    next_sample: u64,
+    last_sample: u64,
+    //CPU time of every process sampled in the current interval, and what it was at the last sample
+    cpu: BTreeMap<u32, (u64, u64)>,
+    finished: Vec<u32>,
+    now: u64,
+}
+
+impl<W: Write> CpuShareSink<W> {
+    /// Creates the sink and writes the CSV header. `every` is the sampling
+    /// interval in ticks (at least `1`).
+    pub fn new(mut writer: W, every: u64) -> Self {
+        writeln!(writer, "time,id,cpu,share,interval_share").expect("failed to write CPU shares");
+        let every = every.max(1);
+        Self { writer, every, next_sample: every, last_sample: 0, cpu: BTreeMap::new(), finished: Vec::new(), now: 0 }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn sample(&mut self, time: u64) {
+        let interval = (time - self.last_sample) as f64;
+        for (id, (cpu, before)) in &mut self.cpu {
+            writeln!(
+                self.writer,
+                "{},{},{},{:.3},{:.3}",
+                time,
+                id,
+                cpu,
+                *cpu as f64 / time as f64,
+                (*cpu - *before) as f64 / interval
+            )
+            .expect("failed to write CPU shares");
+            *before = *cpu;
+        }
+        for id in self.finished.drain(..) {
+            self.cpu.remove(&id);
+        }
+        self.last_sample = time;
+    }
+}
+
+impl<W: Write> EventSink for CpuShareSink<W> {
+    fn event(&mut self, event: &Event) {
+        //a sample covers the ticks before it and everything else that happens at its time
+        while event.time > self.next_sample || (event.kind.is_tick() && event.time == self.next_sample) {
+            self.sample(self.next_sample);
+            self.next_sample += self.every;
+        }
+        match event.kind {
+            EventKind::Arrive { .. } => {
+                self.cpu.insert(event.id, (0, 0));
+            }
+            EventKind::Execute => {
+                if let Some((cpu, _)) = self.cpu.get_mut(&event.id) {
+                    *cpu += 1;
+                }
+                self.now = event.time + 1;
+            }
+            EventKind::Finish => self.finished.push(event.id),
+            _ => {}
+        }
+        self.now = self.now.max(event.time);
+    }
+
+    fn finish(&mut self) {
+        if self.now > self.last_sample {
+            self.sample(self.now);
+        }
+        self.writer.flush().expect("failed to write CPU shares");
+    }
+}
+
+/// Draws a progress bar on stderr keyed on completed jobs and simulated time.
+///
+/// Redraws are throttled to a few per second of wall-clock time, so the bar
+/// costs next to nothing even on runs with millions of events.
+pub struct ProgressSink {
+    total: usize,
+    finished: usize,
+    now: u64,
+    unit: TimeUnit,
+    last_draw: Option<Instant>,
+}
+
+impl ProgressSink {
+    const WIDTH: usize = 40;
+    const REDRAW: Duration = Duration::from_millis(100);
+
+    /// Creates a progress bar for a run of `total` jobs, showing time in `unit`.
+    pub fn new(total: usize, unit: TimeUnit) -> Self {
+        Self { total, finished: 0, now: 0, unit, last_draw: None }
+    }
+
+    fn draw(&mut self) {
+        let filled = (self.finished * Self::WIDTH).checked_div(self.total).unwrap_or(Self::WIDTH);
+        eprint!(
+            "\r[{}{}] {}/{} jobs  t={}",
+            "#".repeat(filled),
+            "-".repeat(Self::WIDTH - filled),
+            self.finished,
+            self.total,
+            self.unit.format_ns(self.now * self.unit.nanos())
+        );
+        io::stderr().flush().ok();
+        self.last_draw = Some(Instant::now());
+    }
+}
+
+impl EventSink for ProgressSink {
+    fn event(&mut self, event: &Event) {
+        self.now = event.time;
+        if event.kind == EventKind::Finish {
+            self.finished += 1;
+        } else if event.kind.is_tick() && !event.time.is_multiple_of(1024) {
+            // only look at the wall clock now and then on the hot path
+            return;
+        }
+        if self.last_draw.is_none_or(|at| at.elapsed() >= Self::REDRAW) {
+            self.draw();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.draw();
+        eprintln!();
+    }
+}
+
+/// When to end a run before every job has finished, for workloads that never
+/// run out of work on their own such as closed-loop clients.
+///
+/// # Fields
+/// - `after_ticks`: Stop once the clock reaches this tick; no tick from it on
+///   is simulated.
+/// - `after_completions`: Stop once this many jobs have finished.
+/// - `when_idle_for`: Stop once the CPU has been idle for this many ticks in
+///   a row.
+///
+/// # Example
+/// ```
+/// use scheduler::event::EventKind;
+/// use scheduler::sink::{Recorder, StopConditions};
+/// use scheduler::CLOCK;
+/// use std::time::Duration;
+///
+/// let mut out = Recorder::new();
+/// out.stop_when(StopConditions { after_completions: Some(1), ..Default::default() });
+/// CLOCK.set_now(Duration::ZERO);
+/// for id in [1, 2] {
+///     out.emit(id, EventKind::Arrive { priority: 0 });
+///     out.emit(id, EventKind::Dispatch { quantum: 0 });
+///     out.emit(id, EventKind::Finish);
+/// }
+/// assert_eq!(out.stop_reason(), Some("completion limit reached"));
+/// // everything after the stop is dropped
+/// assert_eq!(out.events().len(), 3);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StopConditions {
+    pub after_ticks: Option<u64>,
+    pub after_completions: Option<usize>,
+    pub when_idle_for: Option<u64>,
+}
+
+/// Collects a run's events and metrics and fans events out to sinks.
+#[derive(Default)]
+pub struct Recorder {
+    sinks: Vec<Box<dyn EventSink>>,
+    log: Vec<Event>,
+    annotations: Vec<Annotation>,
+    metrics: Metrics,
+    stop: StopConditions,
+    completions: usize,
+    idle_since: Option<u64>,
+    stopped: Option<&'static str>,
+}
+
+impl Recorder {
+    /// Creates a recorder with no sinks attached.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a recorder whose aggregates only cover `window`.
+    pub fn with_window(window: Window) -> Self {
+        Self { metrics: Metrics::with_window(window), ..Self::default() }
+    }
+
+    /// Attaches a sink that will receive every subsequent event.
+    pub fn add_sink(&mut self, sink: Box<dyn EventSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Ends the run early once any of `stop` is met. From then on
+    /// [`Recorder::stopped`] returns `true` and every event is dropped.
+    pub fn stop_when(&mut self, stop: StopConditions) {
+        self.stop = stop;
+    }
+
+    /// Whether the run has been stopped early. Simulators check this to leave
+    /// their loops.
+    pub fn stopped(&self) -> bool {
+        self.stopped.is_some()
+    }
+
+    /// Which stop condition ended the run, if one did.
+    pub fn stop_reason(&self) -> Option<&'static str> {
+        self.stopped
+    }
+
+    /// Records an event for process `id` at the current clock time.
+    pub fn emit(&mut self, id: u32, kind: EventKind) {
+        let event = Event { time: CLOCK.now_ticks(), id, kind };
+        if self.stopped.is_some() {
+            return;
+        }
+        if kind.is_tick() && self.stop.after_ticks.is_some_and(|ticks| event.time >= ticks) {
+            self.stopped = Some("tick limit reached");
+            return;
+        }
+        self.metrics.record(&event);
+        if !kind.is_tick() {
+            self.log.push(event);
+        }
+        for sink in &mut self.sinks {
+            sink.event(&event);
+        }
+        match kind {
+            EventKind::Finish => {
+                self.completions += 1;
+                if self.stop.after_completions.is_some_and(|n| self.completions >= n) {
+                    self.stopped = Some("completion limit reached");
+                }
+            }
+            EventKind::Idle => {
+                let since = *self.idle_since.get_or_insert(event.time);
+                if self.stop.when_idle_for.is_some_and(|ticks| event.time + 1 - since >= ticks) {
+                    self.stopped = Some("idle limit reached");
+                }
+            }
+            EventKind::Execute => self.idle_since = None,
+            _ => {}
+        }
+    }
+
+    /// Puts a marker labeled `label` at the current clock time, see
+    /// [`Annotation`].
+    pub fn annotate(&mut self, label: impl Into<String>) {
+        if self.stopped.is_some() {
+            return;
+        }
+        let annotation = Annotation { time: CLOCK.now_ticks(), label: label.into() };
+        for sink in &mut self.sinks {
+            sink.annotate(&annotation);
+        }
+        self.annotations.push(annotation);
+    }
+
+    /// Notes the scheduler's current heap size, see [`Metrics::note_heap_size`].
+    pub fn note_heap_size(&mut self, bytes: usize) {
+        if self.stopped.is_none() {
+            self.metrics.note_heap_size(bytes);
+        }
+    }
+
+    /// Notes how each group with a CPU limit fared, see
+    /// [`Metrics::note_throttling`].
+    pub fn note_throttling(&mut self, stats: Vec<GroupStats>) {
+        self.metrics.note_throttling(stats);
+    }
+
+    /// Notes how often the timer interrupted the run, see
+    /// [`Metrics::note_timer`].
+    pub fn note_timer(&mut self, stats: TimerStats) {
+        self.metrics.note_timer(stats);
+    }
+
+    /// Labels process `id` with its job class, see [`Metrics::note_class`].
+    pub fn note_class(&mut self, id: u32, class: String) {
+        self.metrics.note_class(id, class);
+    }
+
+    /// Notes the deadline of process `id`, see [`Metrics::note_deadline`].
+    pub fn note_deadline(&mut self, id: u32, deadline: u64) {
+        if self.stopped.is_none() {
+            self.metrics.note_deadline(id, deadline);
+        }
+    }
+
+    /// Notes the queue level process `id` was just dispatched from, see
+    /// [`Metrics::note_level`].
+    pub fn note_level(&mut self, id: u32, level: u32) {
+        if self.stopped.is_none() {
+            self.metrics.note_level(id, level);
+        }
+    }
+
+    /// Tells every sink the simulation is over.
+    pub fn finish(&mut self) {
+        for sink in &mut self.sinks {
+            sink.finish();
+        }
+    }
+
+    /// Returns the event log, without `Execute` events.
+    pub fn events(&self) -> &[Event] {
+        &self.log
+    }
+
+    /// Returns the annotations, in the order they were made.
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    /// Returns the metrics collected so far.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+}
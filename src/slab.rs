@@ -0,0 +1,193 @@
+//! # Slab Module
+//!
+//! A slab of values, such as [`PCB`](crate::PCB)s, handed out by [`Pid`]
+//! handles.
+//!
+//! A scheduler's queues move their processes around all the time: a queue
+//! ordered by estimated burst removes from the middle of a `Vec` and shifts
+//! everything behind, a heap sifts entries up and down, a map rebalances its
+//! nodes. With the PCB itself in the queue, every such move copies the
+//! whole struct. A kernel keeps its process descriptors in one place
+//! instead (Linux allocates every `task_struct` from a slab cache) and
+//! queues pointers to them. [`Slab`] does the same for the simulator: the
+//! PCB goes into the slab once, when it is added, and the queue holds its
+//! [`Pid`], 8 bytes, until it is dispatched and taken out again.
+//!
+//! A slot freed by [`remove`](Slab::remove) is reused by the next
+//! [`insert`](Slab::insert), so a slab stays as large as the most values it
+//! ever held at once. Each slot counts how often it has been reused, and a
+//! [`Pid`] remembers the count it was handed out with, so a handle kept
+//! after its value was removed finds nothing rather than whichever value
+//! took the slot over.
+//!
+//! Whether that pays depends on what the queue does with its entries. A
+//! scheduler that looks at every ready process on every dispatch, such as
+//! [`SJF`](crate::policy::SJF), now follows a handle for each of them, and
+//! that costs more than the copies it saves: draining 20 000 processes from
+//! an `SJF` that kept them in a slab took five times as long, and an
+//! [`SRTF`](crate::policy::SRTF) with a million slightly longer. So the
+//! built-in policies keep their PCBs in their queues, and a slab is for a
+//! scheduler whose entries are large, or moved much more often than they
+//! are looked at. Either way the [`Schedule`](crate::Schedule) API takes
+//! and returns PCBs by value; a scheduler using a slab inserts the PCB in
+//! [`add_process`](crate::Schedule::add_process) and removes it in
+//! [`next_process`](crate::Schedule::next_process).
+//!
+//! ## Example
+//! ```
+//! use scheduler::PCB;
+//! use scheduler::slab::Slab;
+//!
+//! let pcb = |id| PCB { id, priority: 0, time_added: None, time_scheduled: None };
+//! let mut slab = Slab::new();
+//! let first = slab.insert(pcb(1));
+//! let second = slab.insert(pcb(2));
+//! assert_eq!(slab[second].id, 2);
+//! assert_eq!(slab.remove(first).map(|p| p.id), Some(1));
+//!
+//! // P3 reuses P1's slot, and P1's handle no longer finds anything
+//! let third = slab.insert(pcb(3));
+//! assert_eq!(third.index(), first.index());
+//! assert_eq!(slab.get(first), None);
+//! assert_eq!(slab.len(), 2);
+//! assert_eq!(size_of_val(&third), 8);
+//! ```
+
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// A handle to a value in a [`Slab`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pid {
+    index: u32,
+    generation: u32,
+}
+
+impl Pid {
+    /// The slot of the slab the value is in.
+    pub fn index(self) -> u32 {
+        self.index
+    }
+}
+
+impl fmt::Display for Pid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}.{}", self.index, self.generation)
+    }
+}
+
+/// A slot of a [`Slab`].
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    /// how often the slot has been handed out
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Values stored once and referred to by [`Pid`], see the
+/// [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct Slab<T> {
+    slots: Vec<Slot<T>>,
+    /// indexes of the empty slots, the last freed last
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> Slab<T> {
+    /// An empty slab.
+    pub fn new() -> Self {
+        Self { slots: Vec::new(), free: Vec::new(), len: 0 }
+    }
+
+    /// Stores `value`, in the slot freed last if there is one.
+    ///
+    /// # Returns
+    /// The handle to the value.
+    pub fn insert(&mut self, value: T) -> Pid {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.generation = slot.generation.wrapping_add(1);
+                slot.value = Some(value);
+                Pid { index, generation: slot.generation }
+            }
+            None => {
+                let index = u32::try_from(self.slots.len()).expect("a slab holds at most u32::MAX values");
+                self.slots.push(Slot { generation: 0, value: Some(value) });
+                Pid { index, generation: 0 }
+            }
+        }
+    }
+
+    /// The value `pid` refers to, or `None` if it has been removed.
+    pub fn get(&self, pid: Pid) -> Option<&T> {
+        self.slots.get(pid.index as usize).filter(|slot| slot.generation == pid.generation)?.value.as_ref()
+    }
+
+    /// The value `pid` refers to, or `None` if it has been removed.
+    pub fn get_mut(&mut self, pid: Pid) -> Option<&mut T> {
+        self.slots.get_mut(pid.index as usize).filter(|slot| slot.generation == pid.generation)?.value.as_mut()
+    }
+
+    /// Takes the value `pid` refers to out of the slab, freeing its slot.
+    ///
+    /// # Returns
+    /// The value, or `None` if it had been removed already.
+    pub fn remove(&mut self, pid: Pid) -> Option<T> {
+        let slot = self.slots.get_mut(pid.index as usize).filter(|slot| slot.generation == pid.generation)?;
+        let value = slot.value.take()?;
+        self.free.push(pid.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// How many values the slab holds.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the slab holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Every value with its handle, by slot.
+    pub fn iter(&self) -> impl Iterator<Item = (Pid, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let pid = Pid { index: index as u32, generation: slot.generation };
+            slot.value.as_ref().map(|value| (pid, value))
+        })
+    }
+
+    /// Bytes the slab has allocated, for
+    /// [`Schedule::heap_size`](crate::Schedule::heap_size).
+    pub fn heap_size(&self) -> usize {
+        self.slots.capacity() * size_of::<Slot<T>>() + self.free.capacity() * size_of::<u32>()
+    }
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<Pid> for Slab<T> {
+    type Output = T;
+
+    /// # Panics
+    /// If the value has been removed.
+    fn index(&self, pid: Pid) -> &T {
+        self.get(pid).unwrap_or_else(|| panic!("no value for handle {} in the slab", pid))
+    }
+}
+
+impl<T> IndexMut<Pid> for Slab<T> {
+    /// # Panics
+    /// If the value has been removed.
+    fn index_mut(&mut self, pid: Pid) -> &mut T {
+        self.get_mut(pid).unwrap_or_else(|| panic!("no value for handle {} in the slab", pid))
+    }
+}
@@ -0,0 +1,773 @@
+//! # SMP Module
+//!
+//! A multiprocessor version of the simulator's engine, with load balancing.
+//!
+//! [`Smp`] runs several CPUs in lockstep, each with a run queue of its own:
+//! its own instance of the scheduler. An arriving job is placed on the CPU
+//! with the fewest runnable processes (the lowest-numbered one on a tie), and
+//! from then on stays there unless the load balancer migrates it. Like
+//! Linux, the balancer has two mechanisms, picked with [`Balance`]:
+//!
+//! - **pull** (`newidle` balancing): a CPU that has nothing to run pulls a
+//!   process from the busiest CPU, trying at most once every `every` ticks;
+//! - **push** (periodic balancing): every `every` ticks, processes are
+//!   pushed from the busiest CPUs to the least busy ones until their loads
+//!   differ by at most one.
+//!
+//! A CPU only gives a process away at a scheduling point, when its running
+//! process stops: the scheduler picks the next process as usual and it is
+//! handed to the other CPU instead, so every scheduler keeps working
+//! unchanged. A CPU running a process to completion is therefore only
+//! balanced once the process finishes.
+//!
+//! Events go to a [`Recorder`] as on one CPU, so per-process metrics come
+//! out as usual; CPU utilization then counts the time of every CPU, so it
+//! goes up to the number of CPUs. [`SmpStats`] adds what only a
+//! multiprocessor has: per-CPU busy and idle time, every migration, and the
+//! imbalance, the busiest CPU's load minus the least busy one's, over time.
+//!
+//! Jobs are [`Job`]s of the single-CPU engine; burst scripts are not
+//! simulated, every job runs its burst in one go.
+//!
+//! ## NUMA
+//! [`Smp::numa`] groups the CPUs into NUMA nodes, consecutive CPUs sharing
+//! a node. A process's memory is on the node of the CPU it was first placed
+//! on, its home node. Running anywhere else is slower: every dispatch on a
+//! CPU of another node, a remote dispatch, first stalls for `penalty` ticks
+//! that hold the CPU without getting the process any further. Stalls do not
+//! count towards the quantum, and count as CPU time in the metrics.
+//!
+//! [`Placement`] decides how NUMA-aware the run is. `spread` ignores the
+//! nodes: arrivals go to the least busy CPU and the balancer moves processes
+//! anywhere. `numa` places an arrival on the least busy node first, and
+//! balances within a node before it balances across nodes: an idle CPU
+//! pulls from its own node when it can, and pushing evens out every node
+//! before the whole machine. [`SmpStats`] then counts the local and remote
+//! dispatches of every CPU.
+//!
+//! ```
+//! use std::time::Duration;
+//! use scheduler::policy::RR;
+//! use scheduler::simulation::Job;
+//! use scheduler::sink::Recorder;
+//! use scheduler::smp::{Balance, Placement, Smp};
+//! use scheduler::{CLOCK, Schedule};
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let run = |placement: Placement| {
+//!     let cpus: Vec<Box<dyn Schedule>> = (0..4).map(|_| Box::new(RR::with_quantum(2)) as Box<dyn Schedule>).collect();
+//!     // P3 and P7 are short and end up on CPU 3, which runs out of work first
+//!     let jobs = (0..9).map(|id| Job::new(id, 0, if id % 4 == 3 { 1 } else { 10 }));
+//!     let smp = Smp::new(cpus).numa(2, 3).placement(placement).balance(Balance::Pull { every: 1 });
+//!     smp.jobs(jobs).run(&mut Recorder::new()).unwrap()
+//! };
+//!
+//! let (spread, numa) = (run(Placement::Spread), run(Placement::Numa));
+//! // at tick 2, CPU 3 pulls from the busiest CPU, CPU 0 on the other node,
+//! // or from CPU 2 on its own node when it is NUMA-aware
+//! assert_eq!((spread.migrations[0].from, numa.migrations[0].from), (0, 2));
+//! // P4 is remote on CPU 3, and stalls for 3 ticks on each of its 5 dispatches
+//! assert_eq!((spread.remote_dispatches(), spread.cpus[3].stalled), (5, 15));
+//! assert_eq!(numa.migrations[0].id, 5);
+//! ```
+//!
+//! ## SMT
+//! [`Smp::smt`] makes consecutive CPUs hardware threads of one core, like
+//! hyperthreads. Siblings share the core's execution units: while more than
+//! one of them is busy, each gets only `share` of a tick's work done per
+//! tick, so two busy siblings at a share of `0.6` do 1.2 ticks of work
+//! between them instead of 2. Quanta still count every tick. Processes are
+//! placed on the least busy CPU, which may well be the sibling of a busy
+//! one; `cores` [`Placement`] places them on the least busy core instead,
+//! and pushes to idle cores first, so siblings only share a core once every
+//! core is busy. [`SmpStats`] counts the ticks each CPU ran alongside a
+//! busy sibling, and the work lost to that. The partial ticks of work add
+//! up exactly, see [`burst`](crate::burst).
+//!
+//! ```
+//! use std::time::Duration;
+//! use scheduler::policy::RR;
+//! use scheduler::simulation::Job;
+//! use scheduler::sink::Recorder;
+//! use scheduler::smp::{Placement, Smp};
+//! use scheduler::{CLOCK, Schedule};
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let run = |placement: Placement| {
+//!     let cpus: Vec<Box<dyn Schedule>> = (0..4).map(|_| Box::new(RR::with_quantum(10)) as Box<dyn Schedule>).collect();
+//!     let jobs = [Job::new(0, 0, 4), Job::new(1, 0, 4)];
+//!     let mut out = Recorder::new();
+//!     let stats = Smp::new(cpus).smt(2, 0.5).placement(placement).jobs(jobs).run(&mut out).unwrap();
+//!     (out.metrics().aggregates().makespan, stats)
+//! };
+//!
+//! // spread, P0 and P1 go to CPUs 0 and 1, the two threads of core 0
+//! let (makespan, stats) = run(Placement::Spread);
+//! assert_eq!((makespan, stats.cpus[0].shared, stats.lost_work()), (8, 8, 8.0));
+//! // by core, P1 goes to CPU 2 and has core 1 to itself
+//! let (makespan, stats) = run(Placement::Cores);
+//! assert_eq!((makespan, stats.lost_work()), (4, 0.0));
+//! ```
+//!
+//! ## Example
+//! ```
+//! use std::time::Duration;
+//! use scheduler::policy::RR;
+//! use scheduler::simulation::Job;
+//! use scheduler::sink::Recorder;
+//! use scheduler::smp::{Balance, Smp};
+//! use scheduler::{CLOCK, Schedule};
+//!
+//! CLOCK.set_tick(Duration::from_nanos(1));
+//! let run = |balance: Balance| {
+//!     let cpus: Vec<Box<dyn Schedule>> = vec![Box::new(RR::with_quantum(2)), Box::new(RR::with_quantum(2))];
+//!     let jobs = [Job::new(0, 0, 2), Job::new(1, 0, 6), Job::new(2, 0, 2), Job::new(3, 0, 6)];
+//!     let mut out = Recorder::new();
+//!     let stats = Smp::new(cpus).balance(balance).jobs(jobs).run(&mut out).unwrap();
+//!     (out.metrics().aggregates().makespan, stats)
+//! };
+//!
+//! // CPU 0 is done with P0 and P2 after 4 ticks while CPU 1 has 8 to go
+//! let (makespan, stats) = run(Balance::None);
+//! assert_eq!((makespan, stats.migrations.len()), (12, 0));
+//! // pulling, idle CPU 0 takes over the process CPU 1 would run next, P1
+//! let (makespan, stats) = run(Balance::Pull { every: 1 });
+//! assert_eq!((makespan, stats.migrations.len()), (8, 1));
+//! assert_eq!((stats.migrations[0].id, stats.migrations[0].from, stats.migrations[0].to), (1, 1, 0));
+//! assert_eq!(stats.cpus[0].busy + stats.cpus[1].busy, 16);
+//! // pushing every 2 ticks, the balancer finds the imbalance at tick 4
+//! let (makespan, stats) = run("push:2".parse().unwrap());
+//! assert_eq!((makespan, stats.migrations[0].time), (8, 4));
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::burst::{self, Burst};
+use crate::clock::TimeUnit;
+use crate::event::{EventKind, IDLE};
+use crate::simulation::{Job, SimulationError};
+use crate::sink::Recorder;
+use crate::{CLOCK, PCB, Schedule};
+
+/// How the load of the CPUs is balanced, see the
+/// [module documentation](self).
+///
+/// Parsed from `none`, `pull`, `pull:<every>`, `push` or `push:<every>`;
+/// `pull` tries every tick and `push` every 10 ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Balance {
+    /// Processes stay on the CPU they were placed on.
+    None,
+    /// An idle CPU pulls a process from the busiest CPU, trying at most once
+    /// every `every` ticks.
+    Pull { every: u64 },
+    /// Every `every` ticks, processes are pushed from the busiest CPUs to
+    /// the least busy ones.
+    Push { every: u64 },
+}
+
+impl FromStr for Balance {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (policy, every) = match s.split_once(':') {
+            Some((policy, every)) => match every.parse::<u64>() {
+                Ok(every) if every > 0 => (policy, Some(every)),
+                _ => return Err(format!("'{}': the interval must be a positive number of ticks", s)),
+            },
+            None => (s, None),
+        };
+        match policy {
+            "none" if every.is_none() => Ok(Balance::None),
+            "pull" => Ok(Balance::Pull { every: every.unwrap_or(1) }),
+            "push" => Ok(Balance::Push { every: every.unwrap_or(10) }),
+            _ => Err(format!("'{}': unknown balancing, expected none, pull[:<every>] or push[:<every>]", s)),
+        }
+    }
+}
+
+impl fmt::Display for Balance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Balance::None => write!(f, "none"),
+            Balance::Pull { every } => write!(f, "pull:{}", every),
+            Balance::Push { every } => write!(f, "push:{}", every),
+        }
+    }
+}
+
+/// Where arriving processes are placed and balanced to, see
+/// [NUMA](self#numa).
+///
+/// Parsed from `spread`, `numa` or `cores`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Placement {
+    /// On the least busy CPU, regardless of nodes.
+    #[default]
+    Spread,
+    /// On the least busy node, balancing within nodes first.
+    Numa,
+    /// On the least busy core, see [SMT](self#smt).
+    Cores,
+}
+
+impl FromStr for Placement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "spread" => Ok(Placement::Spread),
+            "numa" => Ok(Placement::Numa),
+            "cores" => Ok(Placement::Cores),
+            _ => Err(format!("'{}': unknown placement, expected spread, numa or cores", s)),
+        }
+    }
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Placement::Spread => write!(f, "spread"),
+            Placement::Numa => write!(f, "numa"),
+            Placement::Cores => write!(f, "cores"),
+        }
+    }
+}
+
+/// A process moved from one CPU's run queue to another's.
+///
+/// # Fields
+/// - `time`: Tick of the move.
+/// - `id`: The process.
+/// - `from`: The CPU it left.
+/// - `to`: The CPU it moved to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    pub time: u64,
+    pub id: u32,
+    pub from: usize,
+    pub to: usize,
+}
+
+/// How one CPU spent a run.
+///
+/// # Fields
+/// - `busy`: Ticks it ran a process.
+/// - `idle`: Ticks it had nothing to run.
+/// - `dispatches`: Processes it dispatched.
+/// - `remote`: Of those, the processes whose home node is another one.
+/// - `stalled`: Busy ticks spent on the remote dispatch penalty.
+/// - `shared`: Busy ticks it ran while a sibling was busy too, see
+///   [SMT](self#smt); stalls not included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuStats {
+    pub busy: u64,
+    pub idle: u64,
+    pub dispatches: u64,
+    pub remote: u64,
+    pub stalled: u64,
+    pub shared: u64,
+}
+
+/// What a multiprocessor run did that a single CPU cannot.
+///
+/// # Fields
+/// - `balance`: How the load was balanced.
+/// - `placement`: Where processes were placed.
+/// - `nodes`: The NUMA nodes, `1` when the run did not model them.
+/// - `penalty`: Ticks a remote dispatch stalls for.
+/// - `threads`: Hardware threads per core, `1` without SMT.
+/// - `share`: The share of a tick's work a busy sibling gets done per tick.
+/// - `cpus`: How each CPU spent the run.
+/// - `migrations`: Every migration, in order.
+/// - `imbalance`: For every tick, the runnable processes of the busiest CPU
+///   minus those of the least busy one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmpStats {
+    pub balance: Balance,
+    pub placement: Placement,
+    pub nodes: usize,
+    pub penalty: u32,
+    pub threads: usize,
+    pub share: f64,
+    pub cpus: Vec<CpuStats>,
+    pub migrations: Vec<Migration>,
+    pub imbalance: Vec<u32>,
+}
+
+impl SmpStats {
+    /// The mean imbalance over the run.
+    pub fn mean_imbalance(&self) -> f64 {
+        match self.imbalance.len() {
+            0 => 0.0,
+            n => self.imbalance.iter().map(|&i| i as f64).sum::<f64>() / n as f64,
+        }
+    }
+
+    /// The dispatches of all CPUs.
+    pub fn dispatches(&self) -> u64 {
+        self.cpus.iter().map(|cpu| cpu.dispatches).sum()
+    }
+
+    /// The remote dispatches of all CPUs.
+    pub fn remote_dispatches(&self) -> u64 {
+        self.cpus.iter().map(|cpu| cpu.remote).sum()
+    }
+
+    /// Ticks of work lost to siblings sharing a core.
+    pub fn lost_work(&self) -> f64 {
+        self.cpus.iter().map(|cpu| cpu.shared).sum::<u64>() as f64 * (1.0 - self.share)
+    }
+
+    /// The report printed after a run, with times in `unit`.
+    pub fn report(&self, unit: TimeUnit) -> String {
+        let time = |ticks: u64| unit.format_ns(ticks * unit.nanos());
+        let mut out = format!("smp: {} cpus, balancing {}\n", self.cpus.len(), self.balance);
+        writeln!(out, "{:>6} {:>10} {:>10} {:>11} {:>10}", "cpu", "busy", "idle", "utilization", "dispatches").unwrap();
+        for (cpu, stats) in self.cpus.iter().enumerate() {
+            let total = (stats.busy + stats.idle).max(1);
+            writeln!(
+                out,
+                "{:>6} {:>10} {:>10} {:>10.1}% {:>10}",
+                cpu,
+                time(stats.busy),
+                time(stats.idle),
+                stats.busy as f64 * 100.0 / total as f64,
+                stats.dispatches
+            )
+            .unwrap();
+        }
+        if self.nodes > 1 {
+            let (all, remote) = (self.dispatches(), self.remote_dispatches());
+            let percent = |n: u64| n as f64 * 100.0 / all.max(1) as f64;
+            writeln!(out, "numa: {} nodes, placement {}, remote penalty {} ticks", self.nodes, self.placement, self.penalty)
+                .unwrap();
+            writeln!(
+                out,
+                "dispatches: {} local ({:.1}%), {} remote ({:.1}%), {} stalled",
+                all - remote,
+                percent(all - remote),
+                remote,
+                percent(remote),
+                time(self.cpus.iter().map(|cpu| cpu.stalled).sum())
+            )
+            .unwrap();
+        }
+        if self.threads > 1 {
+            let busy: u64 = self.cpus.iter().map(|cpu| cpu.busy - cpu.stalled).sum();
+            let shared: u64 = self.cpus.iter().map(|cpu| cpu.shared).sum();
+            writeln!(out, "smt: {} threads per core, busy siblings run at {:.0}%", self.threads, self.share * 100.0).unwrap();
+            writeln!(
+                out,
+                "sibling contention: {} shared, {:.1} ticks of work lost ({:.1}% of the busy time)",
+                time(shared),
+                self.lost_work(),
+                self.lost_work() * 100.0 / busy.max(1) as f64
+            )
+            .unwrap();
+        }
+        writeln!(out, "migrations: {}", self.migrations.len()).unwrap();
+        writeln!(
+            out,
+            "imbalance: mean {:.2}, max {} runnable processes",
+            self.mean_imbalance(),
+            self.imbalance.iter().max().unwrap_or(&0)
+        )
+        .unwrap();
+        out
+    }
+
+    /// The imbalance and the migrations so far at every tick, as CSV:
+    /// `time,imbalance,migrations`.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("time,imbalance,migrations\n");
+        let mut migrations = self.migrations.iter().peekable();
+        let mut moved = 0;
+        for (time, imbalance) in self.imbalance.iter().enumerate() {
+            while migrations.next_if(|m| m.time <= time as u64).is_some() {
+                moved += 1;
+            }
+            writeln!(out, "{},{},{}", time, imbalance, moved).unwrap();
+        }
+        out
+    }
+}
+
+/// The process a CPU is running.
+struct Running {
+    pcb: PCB,
+    /// the most ticks it may run, `None` for to completion
+    limit: Option<u32>,
+    ran: u32,
+    /// ticks of the remote dispatch penalty left
+    stall: u32,
+}
+
+/// One CPU and its run queue.
+struct Cpu {
+    sched: Box<dyn Schedule>,
+    node: usize,
+    core: usize,
+    /// processes running or waiting here
+    load: usize,
+    running: Option<Running>,
+    /// the CPUs the next processes picked here go to instead
+    moves: VecDeque<usize>,
+    /// the tick this CPU last tried to pull at
+    pulled_at: Option<u64>,
+    stats: CpuStats,
+}
+
+/// A multiprocessor run, see the [module documentation](self).
+pub struct Smp {
+    cpus: Vec<Cpu>,
+    balance: Balance,
+    placement: Placement,
+    nodes: usize,
+    penalty: u32,
+    threads: usize,
+    share: f64,
+    jobs: Vec<Job>,
+    /// the priority and burst of every job that has not finished
+    pending: BTreeMap<u32, (u32, Burst)>,
+    /// job IDs keyed by their arrival tick
+    arrivals: BTreeMap<u64, Vec<u32>>,
+    /// the home node of every job placed so far
+    home: BTreeMap<u32, usize>,
+    migrations: Vec<Migration>,
+    imbalance: Vec<u32>,
+}
+
+impl Smp {
+    /// A run with one CPU per scheduler in `cpus`, each scheduling its own
+    /// run queue, and no balancing.
+    ///
+    /// # Panics
+    /// If `cpus` is empty.
+    pub fn new(cpus: Vec<Box<dyn Schedule>>) -> Self {
+        assert!(!cpus.is_empty(), "an SMP run needs at least one CPU");
+        let cpus = cpus
+            .into_iter()
+            .enumerate()
+            .map(|(core, sched)| Cpu {
+                sched,
+                node: 0,
+                core,
+                load: 0,                 running: None,
+                moves: VecDeque::new(),
+                pulled_at: None,
+                stats: CpuStats::default(),
+            })
+            .collect();
+        Self {
+            cpus,
+            balance: Balance::None,
+            placement: Placement::Spread,
+            nodes: 1,
+            penalty: 0,
+            threads: 1,
+            share: 1.0,
+            jobs: Vec::new(),
+            pending: BTreeMap::new(),
+            arrivals: BTreeMap::new(),
+            home: BTreeMap::new(),
+            migrations: Vec::new(),
+            imbalance: Vec::new(),
+        }
+    }
+
+    /// Balances the load with `balance`.
+    pub fn balance(mut self, balance: Balance) -> Self {
+        self.balance = balance;
+        self
+    }
+
+    /// Groups the CPUs into `nodes` NUMA nodes of consecutive CPUs, as even
+    /// as they divide, with remote dispatches stalling for `penalty` ticks.
+    ///
+    /// # Panics
+    /// If `nodes` is `0` or more than the CPUs.
+    pub fn numa(mut self, nodes: usize, penalty: u32) -> Self {
+        assert!((1..=self.cpus.len()).contains(&nodes), "every NUMA node needs a CPU");
+        let count = self.cpus.len();
+        for (i, cpu) in self.cpus.iter_mut().enumerate() {
+            cpu.node = i * nodes / count;
+        }
+        self.nodes = nodes;
+        self.penalty = penalty;
+        self
+    }
+
+    /// Makes every `threads` consecutive CPUs the hardware threads of one
+    /// core, busy siblings each getting `share` of a tick's work done per
+    /// tick.
+    ///
+    /// # Panics
+    /// If `threads` is `0`, or `share` is not more than `0` and at most `1`.
+    pub fn smt(mut self, threads: usize, share: f64) -> Self {
+        assert!(threads > 0, "a core needs a thread");
+        assert!(share > 0.0 && share <= 1.0, "a sibling's share must be more than 0 and at most 1");
+        for (i, cpu) in self.cpus.iter_mut().enumerate() {
+            cpu.core = i / threads;
+        }
+        self.threads = threads;
+        self.share = share;
+        self
+    }
+
+    /// Places processes with `placement`.
+    pub fn placement(mut self, placement: Placement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    /// Adds jobs. Jobs arriving at the same tick are placed in the order
+    /// they were added.
+    pub fn jobs(mut self, jobs: impl IntoIterator<Item = Job>) -> Self {
+        self.jobs.extend(jobs);
+        self
+    }
+
+    /// Runs every job to completion, emitting what happens to `out`. The
+    /// [`CLOCK`] starts at `0`, and the run ends early when `out` reaches
+    /// one of its stop conditions.
+    ///
+    /// # Errors
+    /// When a scheduler misbehaves so badly the run cannot go on; see
+    /// [`SimulationError`].
+    pub fn run(mut self, out: &mut Recorder) -> Result<SmpStats, SimulationError> {
+        CLOCK.set_now(Duration::ZERO);
+        for job in std::mem::take(&mut self.jobs) {
+            self.arrivals.entry(job.arrival).or_default().push(job.id);
+            self.pending.insert(job.id, (job.priority, Burst::new(job.burst)));
+        }
+        loop {
+            let now = CLOCK.now_ticks();
+            self.admit(now, out);
+            self.settle(out);
+            if self.pending.is_empty() || out.stopped() {
+                break;
+            }
+            self.plan(now);
+            self.migrate(now)?;
+            self.dispatch(out)?;
+            if self.cpus.iter().all(|cpu| cpu.running.is_none()) {
+                if self.arrivals.range(now + 1..).next().is_none() {
+                    return Err(SimulationError::Stuck(self.pending.keys().copied().collect()));
+                }
+                out.emit(IDLE, EventKind::Idle);
+            }
+            for cpu in &mut self.cpus {
+                match &cpu.running {
+                    Some(running) => {
+                        out.emit(running.pcb.id, EventKind::Execute);
+                        cpu.stats.busy += 1;
+                        cpu.stats.stalled += u64::from(running.stall > 0);
+                    }
+                    None => cpu.stats.idle += 1,
+                }
+            }
+            let loads = self.cpus.iter().map(|cpu| cpu.load);
+            let (low, high) = loads.fold((usize::MAX, 0), |(low, high), load| (low.min(load), high.max(load)));
+            self.imbalance.push((high - low) as u32);
+            CLOCK.advance(CLOCK.tick());
+            let busy: Vec<usize> = (0..self.cpus.len()).filter(|&cpu| self.cpus[cpu].running.is_some()).collect();
+            for (i, cpu) in self.cpus.iter_mut().enumerate() {
+                let Some(running) = cpu.running.as_mut() else {
+                    continue;
+                };
+                if running.stall > 0 {
+                    running.stall -= 1;
+                    continue;
+                }
+                running.ran += 1;
+                let shared = busy.iter().any(|&other| other != i && other / self.threads == cpu.core);
+                cpu.stats.shared += u64::from(shared);
+                let units = if shared { burst::units(self.share) } else { burst::UNITS };
+                //a job without a burst still takes its dispatch tick
+                self.pending.entry(running.pcb.id).and_modify(|(_, burst)| {
+                    if !burst.is_done() {
+                        burst.work(units);
+                    }
+                });
+            }
+        }
+        Ok(SmpStats {
+            balance: self.balance,
+            placement: self.placement,
+            nodes: self.nodes,
+            penalty: self.penalty,
+            threads: self.threads,
+            share: self.share,
+            cpus: self.cpus.iter().map(|cpu| cpu.stats).collect(),
+            migrations: self.migrations,
+            imbalance: self.imbalance,
+        })
+    }
+
+    /// Places every job arriving at tick `now` on the least busy CPU, of the
+    /// least busy node or core when the placement says so.
+    fn admit(&mut self, now: u64, out: &mut Recorder) {
+        for id in self.arrivals.get(&now).into_iter().flatten() {
+            let Some(&(priority, _)) = self.pending.get(id) else {
+                continue;
+            };
+            let node = match self.placement {
+                Placement::Spread | Placement::Cores => None,
+                Placement::Numa => (0..self.nodes).min_by(|&a, &b| self.node_load(a).total_cmp(&self.node_load(b))),
+            };
+            let loads: Vec<usize> = self.cpus.iter().map(|cpu| cpu.load).collect();
+            let core_load = |core: usize| match self.placement {
+                Placement::Cores => self.core_load(&loads, core),
+                _ => 0,
+            };
+            let cpu = (0..self.cpus.len()).filter(|&cpu| node.is_none_or(|node| self.cpus[cpu].node == node));
+            let cpu = cpu.min_by_key(|&cpu| (core_load(self.cpus[cpu].core), loads[cpu])).expect("there is a CPU");
+            let cpu = &mut self.cpus[cpu];
+            self.home.insert(*id, cpu.node);
+            out.emit(*id, EventKind::Arrive { priority });
+            cpu.load += 1;
+            cpu.sched.add_process(PCB { id: *id, priority, time_added: None, time_scheduled: None });
+        }
+    }
+
+    /// The runnable processes per CPU of `node`.
+    fn node_load(&self, node: usize) -> f64 {
+        let cpus = self.cpus.iter().filter(|cpu| cpu.node == node);
+        let (load, count) = cpus.fold((0, 0), |(load, count), cpu| (load + cpu.load, count + 1));
+        load as f64 / count as f64
+    }
+
+    /// The runnable processes of the CPUs of `core`, by their `loads`.
+    fn core_load(&self, loads: &[usize], core: usize) -> usize {
+        (0..loads.len()).filter(|&cpu| self.cpus[cpu].core == core).map(|cpu| loads[cpu]).sum()
+    }
+
+    /// Takes every process that finished or used up its quantum off its CPU.
+    fn settle(&mut self, out: &mut Recorder) {
+        for cpu in &mut self.cpus {
+            let Some(running) = cpu.running.take_if(|running| {
+                self.pending[&running.pcb.id].1.is_done() || running.limit.is_some_and(|limit| running.ran >= limit)
+            }) else {
+                continue;
+            };
+            if self.pending[&running.pcb.id].1.is_done() {
+                out.emit(running.pcb.id, EventKind::Finish);
+                self.pending.remove(&running.pcb.id);
+                cpu.load -= 1;
+            } else {
+                out.emit(running.pcb.id, EventKind::Preempt);
+                self.pending.entry(running.pcb.id).and_modify(|(_, burst)| {
+                    burst.split();
+                });
+                cpu.sched.add_process(running.pcb);
+            }
+        }
+    }
+
+    /// Decides which CPUs give processes to which, by the balancing policy.
+    fn plan(&mut self, now: u64) {
+        match self.balance {
+            Balance::None => {}
+            Balance::Pull { every } => {
+                for to in 0..self.cpus.len() {
+                    let cpu = &self.cpus[to];
+                    let wanted = self.cpus.iter().any(|cpu| cpu.moves.contains(&to));
+                    if cpu.load > 0 || wanted || cpu.pulled_at.is_some_and(|at| now < at + every) {
+                        continue;
+                    }
+                    self.cpus[to].pulled_at = Some(now);
+                    let node = self.cpus[to].node;
+                    let local = match self.placement {
+                        Placement::Spread | Placement::Cores => None,
+                        Placement::Numa => self.donor(|cpu| cpu.node == node),
+                    };
+                    if let Some(from) = local.or_else(|| self.donor(|_| true)) {
+                        self.cpus[from].moves.push_back(to);
+                    }
+                }
+            }
+            Balance::Push { every } if now.is_multiple_of(every) => {
+                let mut loads: Vec<usize> = self.cpus.iter().map(|cpu| cpu.load).collect();
+                for cpu in &mut self.cpus {
+                    cpu.moves.clear();
+                }
+                let mut groups: Vec<Vec<usize>> = match self.placement {
+                    Placement::Spread | Placement::Cores => Vec::new(),
+                    Placement::Numa => {
+                        (0..self.nodes).map(|node| (0..loads.len()).filter(|&cpu| self.cpus[cpu].node == node).collect()).collect()
+                    }
+                };
+                groups.push((0..loads.len()).collect());
+                for group in groups {
+                    loop {
+                        let from = group.iter().copied().max_by_key(|&cpu| (loads[cpu], std::cmp::Reverse(cpu)));
+                        let core_load = |cpu: usize| match self.placement {
+                            Placement::Cores => self.core_load(&loads, self.cpus[cpu].core),
+                            _ => 0,
+                        };
+                        let to = group.iter().copied().min_by_key(|&cpu| (loads[cpu], core_load(cpu)));
+                        let (from, to) = from.zip(to).expect("every group has a CPU");
+                        if loads[from] < loads[to] + 2 {
+                            break;
+                        }
+                        loads[from] -= 1;
+                        loads[to] += 1;
+                        self.cpus[from].moves.push_back(to);
+                    }
+                }
+            }
+            Balance::Push { .. } => {}
+        }
+    }
+
+    /// The busiest CPU `among` those with a process to give away, the
+    /// lowest-numbered one on a tie.
+    fn donor(&self, among: impl Fn(&Cpu) -> bool) -> Option<usize> {
+        let donors = (0..self.cpus.len()).filter(|&cpu| self.cpus[cpu].load >= 2 && among(&self.cpus[cpu]));
+        donors.max_by_key(|&cpu| (self.cpus[cpu].load, std::cmp::Reverse(cpu)))
+    }
+
+    /// Carries out the planned moves of every CPU at a scheduling point.
+    fn migrate(&mut self, now: u64) -> Result<(), SimulationError> {
+        for from in 0..self.cpus.len() {
+            while self.cpus[from].running.is_none() && self.cpus[from].load >= 2 {
+                let Some(to) = self.cpus[from].moves.pop_front() else {
+                    break;
+                };
+                //a pull is only worth it while the puller is still idle
+                if matches!(self.balance, Balance::Pull { .. }) && self.cpus[to].load > 0 {
+                    continue;
+                }
+                let Ok((pcb, _)) = self.cpus[from].sched.try_next() else {
+                    return Err(SimulationError::NoProcess);
+                };
+                self.cpus[from].load -= 1;
+                self.cpus[to].load += 1;
+                self.cpus[to].sched.add_process(pcb);
+                self.migrations.push(Migration { time: now, id: pcb.id, from, to });
+            }
+        }
+        Ok(())
+    }
+
+    /// Dispatches a process on every CPU that is free and has one.
+    fn dispatch(&mut self, out: &mut Recorder) -> Result<(), SimulationError> {
+        for cpu in self.cpus.iter_mut().filter(|cpu| cpu.running.is_none() && cpu.sched.has_process()) {
+            let Ok((pcb, quantum)) = cpu.sched.try_next() else {
+                return Err(SimulationError::NoProcess);
+            };
+            out.emit(pcb.id, EventKind::Dispatch { quantum: quantum.raw() });
+            cpu.stats.dispatches += 1;
+            let remote = self.home.get(&pcb.id).is_some_and(|&home| home != cpu.node);
+            cpu.stats.remote += u64::from(remote);
+            let stall = if remote { self.penalty } else { 0 };
+            cpu.running = Some(Running { pcb, limit: quantum.limit(), ran: 0, stall });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! # Soak Module
+//!
+//! Endurance runs, for `scheduler soak`.
+//!
+//! The workloads of the corpus last a few thousand ticks, far too short to
+//! notice a scheduler that forgets to drop finished processes from a map, a
+//! queue whose stale entries pile up, or a tick counter kept in a `u32` that
+//! wraps after 49 days of milliseconds. A [`Soak`] runs a scheduler for a
+//! very long simulated time instead, with jobs arriving for as long as it
+//! lasts: Poisson arrivals, exponential CPU bursts, at a load below `1` so
+//! that a working scheduler keeps up. The run is cut into windows, and at
+//! the end of each a [`WindowStats`] notes the jobs still in the system,
+//! the memory in use and how long `next_process` took. For a sound
+//! scheduler they all stay flat, window after window;
+//! [`SoakReport::problems`] reports any that grew.
+//!
+//! A scheduler is never told that a process finished: it just is not added
+//! back. State kept per process ID must therefore go once the process is
+//! dispatched for the last time, or it piles up. Among the built-in
+//! schedulers, `bsd` keeps the decayed usage of every process with a nice
+//! value for good, and [`Stack`](crate::feedback::Stack) the level of every
+//! process; over a simulated hour or two both grow by megabytes, and their
+//! dispatches slow down.
+//!
+//! The run checks the scheduler on the way, too. It fails with a
+//! [`SoakError`] as soon as the scheduler hands out a process that is not
+//! ready, has no process to hand out when it should, or loses one. Process
+//! IDs wrap around at `u32::MAX` as PIDs do, skipping those still in use.
+//!
+//! ## Example
+//! ```
+//! use std::time::Duration;
+//! use scheduler::CLOCK;
+//! use scheduler::policy::RR;
+//! use scheduler::soak::Soak;
+//!
+//! CLOCK.set_tick(Duration::from_millis(1));
+//! let soak = Soak { horizon: 200_000, windows: 4, ..Soak::default() };
+//! let report = soak.run(&mut RR::with_quantum(4), &|| 0, &mut |_| {}).unwrap();
+//! assert_eq!(report.windows.len(), 4);
+//! assert_eq!(report.windows[3].end, 200_000);
+//! // about 70% of 200 000 ticks of work, in 10-tick bursts
+//! let arrived: u64 = report.windows.iter().map(|window| window.arrived).sum();
+//! assert!((13_000..15_000).contains(&arrived), "{}", arrived);
+//! assert!(report.problems(2.0).is_empty());
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::random::Rng;
+use crate::{CLOCK, PCB, Schedule};
+
+/// Bytes in a mebibyte.
+const MIB: u64 = 1024 * 1024;
+
+/// An endurance run, see the [module documentation](self).
+///
+/// # Fields
+/// - `horizon`: Ticks to simulate.
+/// - `windows`: How many windows the run is cut into.
+/// - `load`: Fraction of the time the CPU is busy, on average, between `0`
+///   and `1`.
+/// - `mean_burst`: Mean CPU burst of a job, in ticks.
+/// - `priorities`: Jobs get a priority from `0` to `priorities - 1`, at
+///   random.
+/// - `seed`: Seed of the arrivals, bursts and priorities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Soak {
+    pub horizon: u64,
+    pub windows: u32,
+    pub load: f64,
+    pub mean_burst: f64,
+    pub priorities: u32,
+    pub seed: u64,
+}
+
+impl Default for Soak {
+    /// A day of milliseconds in 24 windows of an hour, at a load of `0.7`
+    /// with bursts of 10 ticks and 4 priorities.
+    fn default() -> Self {
+        Self { horizon: 24 * 3_600_000, windows: 24, load: 0.7, mean_burst: 10.0, priorities: 4, seed: 0 }
+    }
+}
+
+/// What happened in a window of a [`Soak`].
+///
+/// # Fields
+/// - `end`: Tick the window ended at.
+/// - `arrived`: Jobs that arrived during the window.
+/// - `finished`: Jobs that finished during the window.
+/// - `dispatches`: Calls of `next_process` during the window.
+/// - `in_system`: Jobs that had arrived and not finished at its end.
+/// - `heap_size`: What the scheduler's
+///   [`heap_size`](crate::Schedule::heap_size) said at its end.
+/// - `memory`: Bytes in use at its end, as the run's memory probe said.
+/// - `mean_latency`: Mean wall-clock time a `next_process` call took.
+/// - `max_latency`: Longest wall-clock time a `next_process` call took.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowStats {
+    pub end: u64,
+    pub arrived: u64,
+    pub finished: u64,
+    pub dispatches: u64,
+    pub in_system: usize,
+    pub heap_size: usize,
+    pub memory: u64,
+    pub mean_latency: Duration,
+    pub max_latency: Duration,
+}
+
+/// The windows of a finished [`Soak`], oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SoakReport {
+    pub windows: Vec<WindowStats>,
+}
+
+impl SoakReport {
+    /// Checks that the run stayed flat, taking the second window as the
+    /// reference, as the first starts from an empty system and warms up.
+    /// A later window grew if it has more than `tolerance` times the
+    /// reference's jobs in the system (plus 100), memory in use (plus 1 MiB),
+    /// scheduler heap size (plus 64 KiB) or mean dispatch latency (plus 1µs).
+    ///
+    /// # Returns
+    /// A description of every measure that grew, at the window it was
+    /// largest at; empty with fewer than three windows.
+    pub fn problems(&self, tolerance: f64) -> Vec<String> {
+        let [_, reference, later @ ..] = self.windows.as_slice() else {
+            return Vec::new();
+        };
+        if later.is_empty() {
+            return Vec::new();
+        }
+        let mut problems = Vec::new();
+        let mut check = |what: &str, measure: &dyn Fn(&WindowStats) -> f64, slack: f64, show: &dyn Fn(f64) -> String| {
+            let (index, peak) = later.iter().enumerate().map(|(i, window)| (i + 3, measure(window))).fold((0, f64::MIN), |a, b| if b.1 > a.1 { b } else { a });
+            let base = measure(reference);
+            if peak > tolerance * base + slack {
+                problems.push(format!("{} grew from {} in window 2 to {} in window {}", what, show(base), show(peak), index));
+            }
+        };
+        check("jobs in the system", &|w| w.in_system as f64, 100.0, &|v| format!("{}", v));
+        check("memory in use", &|w| w.memory as f64, MIB as f64, &|v| format!("{:.1} MiB", v / MIB as f64));
+        check("the scheduler's heap size", &|w| w.heap_size as f64, 64.0 * 1024.0, &|v| format!("{:.0} KiB", v / 1024.0));
+        check("mean dispatch latency", &|w| w.mean_latency.as_nanos() as f64, 1_000.0, &|v| format!("{:?}", Duration::from_nanos(v as u64)));
+        problems
+    }
+}
+
+/// Why a [`Soak`] could not go on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SoakError {
+    /// The scheduler said it had a process, but returned none, at this tick.
+    NoProcess(u64),
+    /// The scheduler returned this process at this tick, but it was not
+    /// ready: it never was, it was running or it had finished.
+    NotReady(u32, u64),
+    /// The scheduler said it had no process at this tick, yet these were
+    /// ready.
+    Lost(Vec<u32>, u64),
+}
+
+impl fmt::Display for SoakError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoakError::NoProcess(time) => write!(f, "at tick {}, the scheduler said it had a process, but returned none", time),
+            SoakError::NotReady(id, time) => write!(f, "at tick {}, the scheduler returned P{}, which was not ready", time, id),
+            SoakError::Lost(ids, time) => {
+                let shown: Vec<String> = ids.iter().take(10).map(|id| format!("P{}", id)).collect();
+                let more = if ids.len() > 10 { format!(" and {} more", ids.len() - 10) } else { String::new() };
+                write!(f, "at tick {}, the scheduler said it had no process, yet {}{} were ready", time, shown.join(", "), more)
+            }
+        }
+    }
+}
+
+/// The state of a [`Soak`] in progress.
+struct Run<'a> {
+    soak: &'a Soak,
+    rng: Rng,
+    /// tick the next job arrives at, not rounded down yet
+    next_arrival: f64,
+    next_id: u32,
+    /// CPU time left of every job in the system
+    left: BTreeMap<u32, u64>,
+    /// jobs the scheduler holds
+    ready: BTreeSet<u32>,
+    /// ticks in a window
+    width: u64,
+    window: WindowStats,
+    /// total time the window's dispatches took
+    latency: Duration,
+    windows: Vec<WindowStats>,
+}
+
+impl Run<'_> {
+    /// Tick the next job arrives at.
+    fn arrival(&self) -> u64 {
+        self.next_arrival as u64
+    }
+
+    /// Hands every job arriving by `now` to the scheduler.
+    fn admit(&mut self, sched: &mut dyn Schedule, now: u64) {
+        while self.arrival() <= now {
+            while self.left.contains_key(&self.next_id) {
+                self.next_id = self.next_id.wrapping_add(1);
+            }
+            let id = self.next_id;
+            self.next_id = self.next_id.wrapping_add(1);
+            let burst = (self.rng.exponential(self.soak.mean_burst).round() as u64).max(1);
+            let priority = (self.rng.next_u64() % self.soak.priorities.max(1) as u64) as u32;
+            self.left.insert(id, burst);
+            self.ready.insert(id);
+            self.window.arrived += 1;
+            sched.add_process(PCB { id, priority, time_added: None, time_scheduled: None });
+            self.next_arrival += self.rng.exponential(self.soak.mean_burst / self.soak.load);
+        }
+    }
+
+    /// Tick the current window ends at.
+    fn window_end(&self) -> u64 {
+        match self.windows.len() + 1 == self.soak.windows.max(1) as usize {
+            true => self.soak.horizon,
+            false => self.width * (self.windows.len() as u64 + 1),
+        }
+    }
+
+    /// Ends every window that is over by `now`.
+    fn close(&mut self, sched: &dyn Schedule, memory: &dyn Fn() -> u64, on_window: &mut dyn FnMut(&WindowStats), now: u64) {
+        while self.windows.len() < self.soak.windows.max(1) as usize && now >= self.window_end() {
+            let mut window = std::mem::take(&mut self.window);
+            window.end = self.window_end();
+            window.in_system = self.left.len();
+            window.heap_size = sched.heap_size();
+            window.memory = memory();
+            if window.dispatches > 0 {
+                window.mean_latency = self.latency / window.dispatches as u32;
+            }
+            self.latency = Duration::ZERO;
+            on_window(&window);
+            self.windows.push(window);
+        }
+    }
+}
+
+impl Soak {
+    /// Runs `sched` for the whole horizon, starting the [`CLOCK`] at `0`.
+    /// `memory` tells the bytes in use at the end of each window, and
+    /// `on_window` gets each window as it ends.
+    ///
+    /// A dispatched job runs for its quantum, or until it finishes, and jobs
+    /// arriving meanwhile are handed to the scheduler at their tick.
+    ///
+    /// # Errors
+    /// When the scheduler misbehaves, see [`SoakError`].
+    pub fn run(
+        &self,
+        sched: &mut dyn Schedule,
+        memory: &dyn Fn() -> u64,
+        on_window: &mut dyn FnMut(&WindowStats),
+    ) -> Result<SoakReport, SoakError> {
+        let tick_ns = CLOCK.tick().as_nanos() as u64;
+        let set = |tick: u64| CLOCK.set_now(Duration::from_nanos(tick.saturating_mul(tick_ns)));
+        let mut rng = Rng::new(self.seed);
+        let next_arrival = rng.exponential(self.mean_burst / self.load);
+        let mut run = Run {
+            soak: self,
+            rng,
+            next_arrival,
+            next_id: 0,
+            left: BTreeMap::new(),
+            ready: BTreeSet::new(),
+            width: (self.horizon / self.windows.max(1) as u64).max(1),
+            window: WindowStats::default(),
+            latency: Duration::ZERO,
+            windows: Vec::new(),
+        };
+        let mut now = 0;
+        set(now);
+        while now < self.horizon {
+            run.admit(sched, now);
+            run.close(sched, memory, on_window, now);
+            if !sched.has_process() {
+                if !run.ready.is_empty() {
+                    return Err(SoakError::Lost(run.ready.iter().copied().collect(), now));
+                }
+                now = run.arrival().min(self.horizon);
+                set(now);
+                continue;
+            }
+            let started = Instant::now();
+            let next = sched.try_next();
+            let took = started.elapsed();
+            let Ok((process, quantum)) = next else {
+                return Err(SoakError::NoProcess(now));
+            };
+            if !run.ready.remove(&process.id) {
+                return Err(SoakError::NotReady(process.id, now));
+            }
+            run.window.dispatches += 1;
+            run.latency += took;
+            run.window.max_latency = run.window.max_latency.max(took);
+            let left = run.left[&process.id];
+            let ran = quantum.limit().map_or(left, |limit| left.min(limit as u64));
+            let end = now + ran;
+            //jobs arriving while it runs are handed over at their tick
+            while now < end {
+                now = run.arrival().clamp(now + 1, end);
+                set(now);
+                run.admit(sched, now);
+            }
+            if left == ran {
+                run.left.remove(&process.id);
+                run.window.finished += 1;
+            } else {
+                run.left.insert(process.id, left - ran);
+                run.ready.insert(process.id);
+                sched.add_process(process);
+            }
+        }
+        run.close(sched, memory, on_window, now.max(self.horizon));
+        Ok(SoakReport { windows: run.windows })
+    }
+}
@@ -0,0 +1,254 @@
+//! # SQLite Module
+//!
+//! An [`EventSink`] that writes runs into a SQLite database, so large sets
+//! of experiments can be queried with SQL instead of grepping JSON. Only
+//! built with the `sqlite` feature:
+//! ```text
+//! $ cargo run --features sqlite -- -s fgbg -i input.in --sqlite runs.db
+//! $ sqlite3 runs.db "SELECT scheduler, mean_turnaround FROM runs JOIN aggregates ON aggregates.run = runs.id"
+//! ```
+//!
+//! Every run is appended to the database as a new row of `runs`, so one file
+//! can collect a whole experiment. The tables are:
+//!
+//! - `runs`: `id`, `scheduler`, `input_file` and the whole run
+//!   configuration as JSON in `config`;
+//! - `events`: `run`, `time`, `process`, `kind` (`arrive`, `dispatch`,
+//!   `preempt`, `block`, `wake`, `throttle`, `unthrottle`, `renice`, `finish` or `timeout`), and the
+//!   `priority` of an arrival or the `quantum` of a dispatch, indexed by process and by kind.
+//!   A renice keeps its new nice value in `priority`.
+//!   Like the results event log, it leaves out the per-tick events;
+//! - `annotations`: `run`, `time` and `label` of every
+//!   [annotation](crate::input#annotations);
+//! - `tags`: `run`, `name` and `value` of every tag the run was given with
+//!   [`SqliteSink::tag`], e.g. from `--tag`, indexed by name and value;
+//! - `processes`: the [`ProcessMetrics`] of every process, keyed by `run`
+//!   and `id`;
+//! - `aggregates`: the [`Aggregates`] of every run, keyed by `run`.
+//!
+//! Metrics are measured in the run's [`Window`](crate::metrics::Window).
+//! Everything a run writes is one transaction, committed when the run
+//! finishes, so an aborted run leaves no half-written rows behind.
+//!
+//! ## Example
+//! ```
+//! use scheduler::event::{Event, EventKind};
+//! use scheduler::results::RunConfig;
+//! use scheduler::sink::EventSink;
+//! use scheduler::sqlite::SqliteSink;
+//!
+//! let path = std::env::temp_dir().join(format!("scheduler-doctest-{}.db", std::process::id()));
+//! let config = RunConfig { scheduler: "fgbg".to_string(), ..RunConfig::default() };
+//! for _ in 0..2 {
+//!     let mut sink = SqliteSink::open(&path, &config).unwrap();
+//!     sink.event(&Event { time: 0, id: 1, kind: EventKind::Arrive { priority: 0 } });
+//!     sink.event(&Event { time: 0, id: 1, kind: EventKind::Dispatch { quantum: 0 } });
+//!     sink.event(&Event { time: 0, id: 1, kind: EventKind::Execute });
+//!     sink.event(&Event { time: 1, id: 1, kind: EventKind::Finish });
+//!     sink.finish();
+//! }
+//!
+//! let db = rusqlite::Connection::open(&path).unwrap();
+//! let count = |sql: &str| db.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+//! assert_eq!(count("SELECT COUNT(*) FROM runs WHERE scheduler = 'fgbg'"), 2);
+//! assert_eq!(count("SELECT COUNT(*) FROM events WHERE run = 2"), 3);
+//! assert_eq!(count("SELECT turnaround FROM processes WHERE run = 2 AND id = 1"), 1);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+//!
+//! [`ProcessMetrics`]: crate::metrics::ProcessMetrics
+//! [`Aggregates`]: crate::metrics::Aggregates
+
+use std::path::Path;
+
+use rusqlite::{Connection, params};
+
+use crate::event::{Annotation, Event, EventKind};
+use crate::metrics::Metrics;
+use crate::results::RunConfig;
+use crate::sink::EventSink;
+
+/// The tables and indexes, created when missing.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    scheduler TEXT NOT NULL,
+    input_file TEXT NOT NULL,
+    config TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS events (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    time INTEGER NOT NULL,
+    process INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    priority INTEGER,
+    quantum INTEGER
+);
+CREATE INDEX IF NOT EXISTS events_by_process ON events (run, process, time);
+CREATE INDEX IF NOT EXISTS events_by_kind ON events (run, kind, time);
+CREATE TABLE IF NOT EXISTS annotations (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    time INTEGER NOT NULL,
+    label TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tags (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (run, name)
+);
+CREATE INDEX IF NOT EXISTS tags_by_value ON tags (name, value);
+CREATE TABLE IF NOT EXISTS processes (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    id INTEGER NOT NULL,
+    priority INTEGER NOT NULL,
+    arrival INTEGER NOT NULL,
+    first_run INTEGER,
+    finish INTEGER,
+    cpu INTEGER NOT NULL,
+    io INTEGER NOT NULL,
+    dispatches INTEGER NOT NULL,
+    turnaround INTEGER,
+    waiting INTEGER,
+    response INTEGER,
+    PRIMARY KEY (run, id)
+);
+CREATE TABLE IF NOT EXISTS aggregates (
+    run INTEGER PRIMARY KEY REFERENCES runs (id),
+    processes INTEGER NOT NULL,
+    completed INTEGER NOT NULL,
+    makespan INTEGER NOT NULL,
+    cpu_time INTEGER NOT NULL,
+    idle INTEGER NOT NULL,
+    mean_turnaround REAL NOT NULL,
+    mean_waiting REAL NOT NULL,
+    mean_response REAL NOT NULL,
+    throughput REAL NOT NULL,
+    utilization REAL NOT NULL
+);
+";
+
+/// Writes a run into a SQLite database, see the
+/// [module documentation](self).
+///
+/// Like the other sinks, it cannot report failures while the run goes on, so
+/// it panics when the database cannot be written.
+pub struct SqliteSink {
+    db: Connection,
+    run: i64,
+    metrics: Metrics,
+}
+
+impl SqliteSink {
+    /// Opens the database at `path`, creating it and its tables if needed,
+    /// and starts a new run with `config`.
+    ///
+    /// # Errors
+    /// If the database cannot be opened or is not one of this simulator's.
+    pub fn open(path: &Path, config: &RunConfig) -> rusqlite::Result<Self> {
+        let db = Connection::open(path)?;
+        db.execute_batch(SCHEMA)?;
+        db.execute_batch("BEGIN")?;
+        let json = serde_json::to_string(config).expect("run configurations serialize");
+        db.execute(
+            "INSERT INTO runs (scheduler, input_file, config) VALUES (?1, ?2, ?3)",
+            params![config.scheduler, config.input_file, json],
+        )?;
+        let run = db.last_insert_rowid();
+        Ok(Self { db, run, metrics: Metrics::with_window(config.measure) })
+    }
+
+    /// Tags the run with `name` = `value`.
+    ///
+    /// # Errors
+    /// If the tag cannot be written, e.g. because the run already has a tag
+    /// `name`.
+    pub fn tag(&self, name: &str, value: &str) -> rusqlite::Result<()> {
+        self.db.execute("INSERT INTO tags (run, name, value) VALUES (?1, ?2, ?3)", params![self.run, name, value])?;
+        Ok(())
+    }
+
+    /// The ID of the run in the `runs` table.
+    pub fn run(&self) -> i64 {
+        self.run
+    }
+
+    fn write_metrics(&self) -> rusqlite::Result<()> {
+        let mut insert = self.db.prepare(
+            "INSERT INTO processes (run, id, priority, arrival, first_run, finish, cpu, io, dispatches, turnaround, waiting, response)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        for p in self.metrics.processes() {
+            let tick = |time: Option<u64>| time.map(|time| time as i64);
+            insert.execute(params![
+                self.run,
+                p.id,
+                p.priority,
+                p.arrival as i64,
+                tick(p.first_run),
+                tick(p.finish),
+                p.cpu as i64,
+                p.io as i64,
+                p.dispatches,
+                tick(p.turnaround),
+                tick(p.waiting),
+                tick(p.response)
+            ])?;
+        }
+        let agg = self.metrics.aggregates();
+        self.db.execute(
+            "INSERT INTO aggregates (run, processes, completed, makespan, cpu_time, idle, mean_turnaround, mean_waiting,
+             mean_response, throughput, utilization) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                self.run,
+                agg.processes as i64,
+                agg.completed as i64,
+                agg.makespan as i64,
+                agg.cpu_time as i64,
+                agg.idle as i64,
+                agg.mean_turnaround,
+                agg.mean_waiting,
+                agg.mean_response,
+                agg.throughput,
+                agg.utilization
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl EventSink for SqliteSink {
+    fn event(&mut self, event: &Event) {
+        self.metrics.record(event);
+        let (kind, priority, quantum) = match event.kind {
+            EventKind::Execute | EventKind::Idle => return,
+            EventKind::Arrive { priority } => ("arrive", Some(priority as i64), None),
+            EventKind::Dispatch { quantum } => ("dispatch", None, Some(quantum)),
+            EventKind::Preempt => ("preempt", None, None),
+            EventKind::Block => ("block", None, None),
+            EventKind::Wake => ("wake", None, None),
+            EventKind::Throttle => ("throttle", None, None),
+            EventKind::Unthrottle => ("unthrottle", None, None),
+            EventKind::Renice { nice } => ("renice", Some(nice as i64), None),
+            EventKind::Finish => ("finish", None, None),
+            EventKind::Timeout => ("timeout", None, None),
+        };
+        self.db
+            .prepare_cached("INSERT INTO events (run, time, process, kind, priority, quantum) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")
+            .and_then(|mut insert| insert.execute(params![self.run, event.time as i64, event.id, kind, priority, quantum]))
+            .expect("failed to write SQLite database");
+    }
+
+    fn annotate(&mut self, annotation: &Annotation) {
+        self.db
+            .execute(
+                "INSERT INTO annotations (run, time, label) VALUES (?1, ?2, ?3)",
+                params![self.run, annotation.time as i64, annotation.label],
+            )
+            .expect("failed to write SQLite database");
+    }
+
+    fn finish(&mut self) {
+        self.write_metrics().and_then(|_| self.db.execute_batch("COMMIT")).expect("failed to write SQLite database");
+    }
+}
@@ -0,0 +1,229 @@
+//! # Starvation Module
+//!
+//! An exhaustive check that a scheduler cannot starve a job, for grading
+//! rules such as the MLFQ priority boost.
+//!
+//! A single run only shows that a job was not starved *that time*. Jobs
+//! arriving at the same tick are handed to the scheduler one after the
+//! other, and a scheduler can behave quite differently depending on which
+//! of them it sees first. [`check`] runs a tiny workload once for every
+//! order the jobs of each tick can arrive in, with a fresh scheduler each
+//! time, and measures how long any job was ready before it got the CPU. If
+//! no job ever waited more than the bound, that is proved for every
+//! ordering; otherwise the first ordering that broke it is returned as a
+//! [`Counterexample`], with the trace of the run up to the dispatch that
+//! came too late.
+//!
+//! The orderings multiply: three jobs at each of two ticks already make
+//! `3! * 3! = 36` runs. The check is meant for workloads of a handful of
+//! jobs, and refuses more than [`MAX_ORDERINGS`].
+//!
+//! Time a job spends blocked for I/O or held back by a CPU limit is not
+//! waiting for the scheduler, so the wait starts over when the job becomes
+//! ready again. A run that cannot go on, because the scheduler lost a job,
+//! is a counterexample too. The check moves the [`CLOCK`](crate::CLOCK)
+//! and takes turns on it with the [`scenario`](crate::scenario) tests.
+//!
+//! ## Example
+//! ```
+//! use scheduler::feedback::Stack;
+//! use scheduler::policy::RR;
+//! use scheduler::simulation::Job;
+//! use scheduler::starvation::{self, Violation};
+//!
+//! // a long job, a short one arriving with it, and a stream of short jobs
+//! // that keeps the top level busy
+//! let mut jobs = vec![Job::new(0, 0, 8), Job::new(1, 0, 1)];
+//! jobs.extend((2..16).map(|id| Job::new(id, id as u64, 1)));
+//!
+//! let mlfq = || Stack::new().level(RR::with_quantum(2)).level(RR::with_quantum(4));
+//! let counterexample = starvation::check(mlfq, &jobs, 6).unwrap_err();
+//! assert_eq!(counterexample.violation, Violation::Starved { id: 0, waited: 15 });
+//! assert_eq!(counterexample.ties, [(0, vec![0, 1])]);
+//!
+//! // boosting every 5 ticks brings the long job back in time, however the first two arrive
+//! let boosted = || Stack::new().level(RR::with_quantum(2)).level(RR::with_quantum(4)).boost_every(5);
+//! let proof = starvation::check(boosted, &jobs, 6).unwrap();
+//! assert_eq!(proof.orderings, 2);
+//! assert!(proof.worst <= 6);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::Schedule;
+use crate::event::{Event, EventKind};
+use crate::scenario::RUNNING;
+use crate::simulation::{Job, Simulation, SimulationError};
+use crate::sink::Recorder;
+
+/// The most orderings [`check`] runs.
+pub const MAX_ORDERINGS: u64 = 100_000;
+
+/// What [`check`] proved.
+///
+/// # Fields
+/// - `orderings`: How many arrival orderings were run.
+/// - `worst`: The longest any job was ready before it got the CPU, in
+///   ticks, over every ordering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Proof {
+    pub orderings: u64,
+    pub worst: u64,
+}
+
+/// How a run broke the bound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Job `id` was ready for `waited` ticks before it was dispatched.
+    Starved { id: u32, waited: u64 },
+    /// The run could not go on.
+    Failed(SimulationError),
+}
+
+/// An arrival ordering that starved a job, see [`check`].
+///
+/// # Fields
+/// - `ties`: The order the jobs of every tick at which several jobs arrive
+///   were handed to the scheduler, by tick.
+/// - `violation`: What went wrong.
+/// - `trace`: The events of the run, without `Execute` events, up to the
+///   dispatch that came too late or the end of the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub ties: Vec<(u64, Vec<u32>)>,
+    pub violation: Violation,
+    pub trace: Vec<Event>,
+}
+
+impl fmt::Display for Counterexample {
+    /// Describes the violation, the ordering and the trace, one event per
+    /// line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.violation {
+            Violation::Starved { id, waited } => writeln!(f, "P{} was ready for {} ticks before it ran", id, waited)?,
+            Violation::Failed(err) => writeln!(f, "the run failed: {}", err)?,
+        }
+        for (tick, ids) in &self.ties {
+            let ids: Vec<String> = ids.iter().map(|id| format!("P{}", id)).collect();
+            writeln!(f, "when the jobs arriving at tick {} were added as {}", tick, ids.join(", "))?;
+        }
+        for event in &self.trace {
+            writeln!(f, "  {}", event)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `jobs` through a scheduler made by `new` once for every order the
+/// jobs arriving at the same tick can be added in, and checks that no job
+/// is ever ready for more than `bound` ticks before it is dispatched.
+///
+/// # Returns
+/// The [`Proof`] that no ordering starves a job, or the first ordering that
+/// does. Orderings are tried tick by tick, in the order of the `jobs` first.
+///
+/// # Panics
+/// If the jobs have more than [`MAX_ORDERINGS`] orderings.
+pub fn check<S: Schedule>(mut new: impl FnMut() -> S, jobs: &[Job], bound: u64) -> Result<Proof, Counterexample> {
+    let mut ticks: BTreeMap<u64, Vec<&Job>> = BTreeMap::new();
+    for job in jobs {
+        ticks.entry(job.arrival).or_default().push(job);
+    }
+    let groups: Vec<Vec<&Job>> = ticks.into_values().collect();
+    let orderings = groups.iter().try_fold(1u64, |total, group| total.checked_mul(factorial(group.len())?));
+    match orderings {
+        Some(orderings) if orderings <= MAX_ORDERINGS => {}
+        _ => panic!("the jobs have more than {} arrival orderings, too many to check", MAX_ORDERINGS),
+    }
+
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let orders: Vec<Vec<Vec<usize>>> = groups.iter().map(|group| permutations(group.len())).collect();
+    //which permutation of every group the current ordering uses
+    let mut picks = vec![0; groups.len()];
+    let mut proof = Proof { orderings: 0, worst: 0 };
+    loop {
+        let ordered: Vec<&Job> =
+            groups.iter().zip(&orders).zip(&picks).flat_map(|((group, orders), &pick)| orders[pick].iter().map(|&i| group[i])).collect();
+        let mut sim = Simulation::builder(new()).jobs(ordered.iter().map(|&job| job.clone())).build();
+        let mut out = Recorder::new();
+        let result = sim.run(&mut out);
+        proof.orderings += 1;
+
+        let events = out.events();
+        let violation = match longest_wait(events) {
+            Some((id, waited, at)) if waited > bound => Some((Violation::Starved { id, waited }, at + 1)),
+            Some((_, waited, _)) => {
+                proof.worst = proof.worst.max(waited);
+                None
+            }
+            None => None,
+        };
+        let violation = violation.or_else(|| result.err().map(|err| (Violation::Failed(err), events.len())));
+        if let Some((violation, end)) = violation {
+            let mut ties: Vec<(u64, Vec<u32>)> = Vec::new();
+            for job in ordered {
+                match ties.last_mut() {
+                    Some((tick, ids)) if *tick == job.arrival => ids.push(job.id),
+                    _ => ties.push((job.arrival, vec![job.id])),
+                }
+            }
+            ties.retain(|(_, ids)| ids.len() > 1);
+            return Err(Counterexample { ties, violation, trace: events[..end].to_vec() });
+        }
+
+        //the next ordering, counting up with the last tick's order changing fastest
+        let Some(group) = (0..groups.len()).rev().find(|&group| picks[group] + 1 < orders[group].len()) else {
+            return Ok(proof);
+        };
+        picks[group] += 1;
+        picks[group + 1..].fill(0);
+    }
+}
+
+/// The longest wait of a run: the job, how long it was ready before a
+/// dispatch, and the index of that dispatch in `events`. The first of
+/// equally long waits counts.
+fn longest_wait(events: &[Event]) -> Option<(u32, u64, usize)> {
+    let mut ready: BTreeMap<u32, u64> = BTreeMap::new();
+    let mut longest: Option<(u32, u64, usize)> = None;
+    for (index, event) in events.iter().enumerate() {
+        match event.kind {
+            EventKind::Arrive { .. } | EventKind::Preempt | EventKind::Wake | EventKind::Unthrottle => {
+                ready.insert(event.id, event.time);
+            }
+            EventKind::Dispatch { .. } => {
+                let waited = ready.remove(&event.id).map_or(0, |since| event.time - since);
+                if longest.is_none_or(|(_, longest, _)| waited > longest) {
+                    longest = Some((event.id, waited, index));
+                }
+            }
+            EventKind::Throttle | EventKind::Timeout => {
+                ready.remove(&event.id);
+            }
+            _ => {}
+        }
+    }
+    longest
+}
+
+/// `n!`, or `None` if it does not fit.
+fn factorial(n: usize) -> Option<u64> {
+    (1..=n as u64).try_fold(1u64, |product, k| product.checked_mul(k))
+}
+
+/// Every order of the indices `0` to `n - 1`, the identity first.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut out = Vec::new();
+    for rest in permutations(n - 1) {
+        for at in (0..n).rev() {
+            let mut order = rest.clone();
+            order.insert(at, n - 1);
+            out.push(order);
+        }
+    }
+    out
+}
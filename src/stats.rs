@@ -0,0 +1,226 @@
+//! # Stats Module
+//!
+//! Summarizes repeated runs, for `scheduler batch`.
+//!
+//! A single run of a random workload says little about a scheduler: another
+//! seed may well reverse the ranking. [`Estimate`] summarizes one metric over
+//! several runs as its mean, sample standard deviation and a 95% confidence
+//! interval for the mean, using Student's t distribution since lab batches
+//! are small. [`BatchReport`] collects the [`Aggregates`] of every run of
+//! every scheduler and reports each metric per scheduler, then compares the
+//! schedulers pairwise: when two confidence intervals overlap, the runs do not
+//! show that one scheduler is better than the other.
+//!
+//! Overlapping intervals are a conservative test. Two means whose intervals
+//! overlap slightly can still differ significantly, but two means whose
+//! intervals do not overlap always do.
+//!
+//! ## Example
+//! ```
+//! use scheduler::stats::Estimate;
+//!
+//! let fast = Estimate::from_samples(&[10.0, 12.0, 11.0, 13.0]);
+//! assert_eq!(fast.mean, 11.5);
+//! // t(3) = 3.182, stddev = 1.29
+//! assert!((fast.half_width - 2.054).abs() < 0.001);
+//!
+//! let slow = Estimate::from_samples(&[20.0, 22.0, 21.0, 23.0]);
+//! let close = Estimate::from_samples(&[12.0, 15.0, 13.0, 14.0]);
+//! assert!(!fast.overlaps(&slow));
+//! assert!(fast.overlaps(&close));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::metrics::Aggregates;
+
+/// Two-sided 95% critical values of Student's t distribution for 1 to 30
+/// degrees of freedom.
+const T95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160, 2.145, 2.131,
+    2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+/// The two-sided 95% critical value of Student's t distribution. Beyond the
+/// table the value of the nearest tabulated lower degree of freedom is used,
+/// which makes the interval slightly wider, never narrower.
+fn t95(degrees: usize) -> f64 {
+    match degrees {
+        0 => f64::INFINITY,
+        1..=30 => T95[degrees - 1],
+        31..=40 => 2.042,
+        41..=60 => 2.021,
+        61..=120 => 2.000,
+        _ => 1.980,
+    }
+}
+
+/// One metric over several runs.
+///
+/// # Fields
+/// - `runs`: Number of runs.
+/// - `mean`: Mean over the runs.
+/// - `stddev`: Sample standard deviation.
+/// - `half_width`: Half the width of the 95% confidence interval for the
+///   mean, which is `mean ± half_width`. Infinite for a single run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub runs: usize,
+    pub mean: f64,
+    pub stddev: f64,
+    pub half_width: f64,
+}
+
+impl Estimate {
+    /// Summarizes the value of a metric in each run.
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let runs = samples.len();
+        if runs == 0 {
+            return Estimate { runs, mean: 0.0, stddev: 0.0, half_width: f64::INFINITY };
+        }
+        let mean = samples.iter().sum::<f64>() / runs as f64;
+        let stddev = if runs < 2 {
+            0.0
+        } else {
+            (samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (runs - 1) as f64).sqrt()
+        };
+        let half_width = t95(runs - 1) * stddev / (runs as f64).sqrt();
+        // a single run says nothing about the spread
+        let half_width = if runs < 2 { f64::INFINITY } else { half_width };
+        Estimate { runs, mean, stddev, half_width }
+    }
+
+    /// The lower end of the 95% confidence interval.
+    pub fn low(&self) -> f64 {
+        self.mean - self.half_width
+    }
+
+    /// The upper end of the 95% confidence interval.
+    pub fn high(&self) -> f64 {
+        self.mean + self.half_width
+    }
+
+    /// Returns `true` if the confidence intervals of the two estimates
+    /// overlap, so the runs do not show a difference between them.
+    pub fn overlaps(&self, other: &Estimate) -> bool {
+        self.low() <= other.high() && other.low() <= self.high()
+    }
+}
+
+/// A metric a batch reports.
+///
+/// # Fields
+/// - `name`: The metric's name in the report.
+/// - `value`: Reads the metric from a run's aggregates.
+/// - `lower_is_better`: Whether a lower value means a better scheduler.
+#[derive(Debug, Clone, Copy)]
+pub struct Metric {
+    pub name: &'static str,
+    pub value: fn(&Aggregates) -> f64,
+    pub lower_is_better: bool,
+}
+
+/// The metrics a batch reports.
+pub const METRICS: [Metric; 6] = [
+    Metric { name: "turnaround", value: |agg| agg.mean_turnaround, lower_is_better: true },
+    Metric { name: "waiting", value: |agg| agg.mean_waiting, lower_is_better: true },
+    Metric { name: "response", value: |agg| agg.mean_response, lower_is_better: true },
+    Metric { name: "latency", value: |agg| agg.mean_latency, lower_is_better: true },
+    Metric { name: "throughput", value: |agg| agg.throughput, lower_is_better: false },
+    Metric { name: "utilization", value: |agg| agg.utilization, lower_is_better: false },
+];
+
+/// The aggregates of every run of several schedulers on the same workloads.
+///
+/// ## Example
+/// ```
+/// use scheduler::metrics::{Aggregates, Metrics};
+/// use scheduler::stats::BatchReport;
+///
+/// let mut batch = BatchReport::default();
+/// for turnaround in [10.0, 12.0, 11.0] {
+///     let agg = Metrics::new().aggregates();
+///     batch.add("rr", Aggregates { mean_turnaround: turnaround, ..agg.clone() });
+///     batch.add("fcfs", Aggregates { mean_turnaround: turnaround + 0.5, ..agg });
+/// }
+/// let rr = batch.estimate("rr", "turnaround").unwrap();
+/// assert_eq!((rr.runs, rr.mean), (3, 11.0));
+/// assert!(batch.report().contains("turnaround: fcfs vs rr: intervals overlap"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    pub runs: BTreeMap<String, Vec<Aggregates>>,
+}
+
+impl BatchReport {
+    /// Adds the aggregates of one run of `scheduler`.
+    pub fn add(&mut self, scheduler: &str, aggregates: Aggregates) {
+        self.runs.entry(scheduler.to_string()).or_default().push(aggregates);
+    }
+
+    /// Summarizes `metric`, one of the [`METRICS`], over the runs of
+    /// `scheduler`.
+    pub fn estimate(&self, scheduler: &str, metric: &str) -> Option<Estimate> {
+        let metric = METRICS.iter().find(|m| m.name == metric)?;
+        let samples: Vec<f64> = self.runs.get(scheduler)?.iter().map(metric.value).collect();
+        Some(Estimate::from_samples(&samples))
+    }
+
+    /// Renders a table of every metric of every scheduler, followed by the
+    /// pairwise comparisons. Times are in ticks, and throughput is in jobs per
+    /// tick.
+    pub fn report(&self) -> String {
+        let width = self.runs.keys().map(String::len).max().unwrap_or(0).max("scheduler".len());
+        let mut out = String::new();
+        writeln!(
+            out,
+            "{:<width$} {:<11} {:>12} {:>12} {:>27}",
+            "scheduler", "metric", "mean", "stddev", "95% confidence interval"
+        )
+        .unwrap();
+        for scheduler in self.runs.keys() {
+            for metric in METRICS.map(|m| m.name) {
+                let estimate = self.estimate(scheduler, metric).unwrap();
+                writeln!(
+                    out,
+                    "{:<width$} {:<11} {:>12.4} {:>12.4} {:>27}",
+                    scheduler,
+                    metric,
+                    estimate.mean,
+                    estimate.stddev,
+                    interval(&estimate)
+                )
+                .unwrap();
+            }
+        }
+        let schedulers: Vec<&String> = self.runs.keys().collect();
+        if schedulers.len() < 2 {
+            return out;
+        }
+        out += "comparisons:\n";
+        for Metric { name: metric, lower_is_better, .. } in METRICS {
+            for (i, a) in schedulers.iter().enumerate() {
+                for b in &schedulers[i + 1..] {
+                    let (x, y) = (self.estimate(a, metric).unwrap(), self.estimate(b, metric).unwrap());
+                    if x.overlaps(&y) {
+                        writeln!(out, "{}: {} vs {}: intervals overlap, no significant difference", metric, a, b)
+                            .unwrap();
+                    } else {
+                        let better = if (x.mean < y.mean) == lower_is_better { a } else { b };
+                        writeln!(out, "{}: {} vs {}: {} is better", metric, a, b, better).unwrap();
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn interval(estimate: &Estimate) -> String {
+    if estimate.half_width.is_finite() {
+        format!("[{:.4}, {:.4}]", estimate.low(), estimate.high())
+    } else {
+        "-".to_string()
+    }
+}
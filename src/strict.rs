@@ -0,0 +1,206 @@
+//! # Strict Module
+//!
+//! Audits the choices a scheduler makes, for the simulator's `--strict` mode.
+//!
+//! The reference outputs assume schedulers are **deterministic**: given the
+//! same workload they must always pick the same process. Whenever several
+//! ready processes are otherwise indistinguishable the documented order is
+//! used to break the tie:
+//!
+//! > Among ready processes with the same priority that were handed to the
+//! > scheduler at the same tick, the one that was added first runs first.
+//!
+//! For processes that arrive together that is input-file order, or ID order
+//! with the simulator's `--arrival-order id`. A scheduler that keeps its
+//! queues in a `HashMap` or `HashSet` breaks this rule only some of the
+//! time, depending on the hash seed of the run, so the resulting bugs are
+//! hard to reproduce. [`StrictSchedule`] wraps a scheduler, keeps
+//! its own record of what was added and in which order, and reports every
+//! dispatch that skips over an earlier tied candidate as a [`TieBreak`].
+//!
+//! Only processes that pass through [`Schedule::add_process`] are audited. A
+//! process a scheduler re-queues internally (for instance from an `interrupt`
+//! method) is not checked when it is dispatched again.
+//!
+//! ## Example
+//! ```
+//! use scheduler::strict::StrictSchedule;
+//! use scheduler::{Schedule, PCB, TimeSlice};
+//!
+//! //a LIFO stack: runs the most recently added process first
+//! #[derive(Default)]
+//! struct Stack(Vec<PCB>);
+//! impl Schedule for Stack {
+//!     fn add_process(&mut self, process: PCB) -> bool { self.0.push(process); true }
+//!     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop(), TimeSlice::Infinite) }
+//!     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! }
+//!
+//! let mut sched = StrictSchedule::new(Stack::default(), true);
+//! for id in [1, 2] {
+//!     sched.add_process(PCB { id, priority: 0, time_added: None, time_scheduled: None });
+//! }
+//! assert_eq!(sched.next_process().0.unwrap().id, 2);
+//! let tie = &sched.violations()[0];
+//! assert_eq!((tie.chosen, tie.expected), (2, 1));
+//! ```
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use crate::{CLOCK, PCB, Schedule, SchedulerInfo, TimeSlice};
+
+/// A dispatch that broke the deterministic tie-breaking order.
+///
+/// # Fields
+/// - `time`: Tick of the dispatch.
+/// - `chosen`: The process the scheduler returned.
+/// - `expected`: The earlier-added process that should have run instead.
+/// - `priority`: The priority both processes were added with.
+/// - `ready_since`: The tick both processes were added at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TieBreak {
+    pub time: u64,
+    pub chosen: u32,
+    pub expected: u32,
+    pub priority: u32,
+    pub ready_since: u64,
+}
+
+impl fmt::Display for TieBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tick {}: P{} was dispatched ahead of P{} (both priority {}, ready since tick {}); \
+             ties must go to the process added first",
+            self.time, self.chosen, self.expected, self.priority, self.ready_since
+        )
+    }
+}
+
+/// A process waiting in the wrapped scheduler, as recorded by the audit.
+#[derive(Debug, Clone, Copy)]
+struct Waiting {
+    id: u32,
+    priority: u32,
+    added: u64,
+}
+
+/// Wraps a scheduler and audits its tie-breaking.
+///
+/// Every [`Schedule`] method is forwarded to the wrapped scheduler, and
+/// [`Deref`] gives access to its own methods. When the audit is disabled the
+/// wrapper does no bookkeeping at all.
+pub struct StrictSchedule<S> {
+    inner: S,
+    enabled: bool,
+    ready: Vec<Waiting>,
+    violations: Vec<TieBreak>,
+}
+
+impl<S: Schedule> StrictSchedule<S> {
+    /// Wraps `inner`, auditing it only if `enabled` is set.
+    pub fn new(inner: S, enabled: bool) -> Self {
+        Self { inner, enabled, ready: Vec::new(), violations: Vec::new() }
+    }
+
+    /// Every tie-breaking violation found so far, in the order they happened.
+    pub fn violations(&self) -> &[TieBreak] {
+        &self.violations
+    }
+
+    /// Consumes the wrapper, returning the violations found during the run.
+    pub fn into_violations(self) -> Vec<TieBreak> {
+        self.violations
+    }
+
+    fn audit(&mut self, chosen: &PCB) {
+        let Some(pos) = self.ready.iter().position(|w| w.id == chosen.id) else {
+            return;
+        };
+        let picked = self.ready.remove(pos);
+        let skipped = self.ready[..pos]
+            .iter()
+            .find(|w| w.priority == picked.priority && w.added == picked.added);
+        if let Some(skipped) = skipped {
+            let tie = TieBreak {
+                time: CLOCK.now_ticks(),
+                chosen: picked.id,
+                expected: skipped.id,
+                priority: picked.priority,
+                ready_since: picked.added,
+            };
+            eprintln!("strict: {}", tie);
+            self.violations.push(tie);
+        }
+    }
+}
+
+impl<S> Deref for StrictSchedule<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S> DerefMut for StrictSchedule<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+}
+
+impl<S: Schedule> Schedule for StrictSchedule<S> {
+    fn add_process(&mut self, process: PCB) -> bool {
+        if self.enabled {
+            self.ready.push(Waiting { id: process.id, priority: process.priority, added: CLOCK.now_ticks() });
+        }
+        self.inner.add_process(process)
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        let next = self.inner.next_process();
+        if let (Some(process), _) = &next
+            && self.enabled
+        {
+            self.audit(process);
+        }
+        next
+    }
+
+    fn has_process(&self) -> bool {
+        self.inner.has_process()
+    }
+
+    fn levels(&self) -> usize {
+        self.inner.levels()
+    }
+
+    fn level_quantum(&self, level: usize) -> TimeSlice {
+        self.inner.level_quantum(level)
+    }
+
+    fn dispatched_level(&self) -> Option<usize> {
+        self.inner.dispatched_level()
+    }
+
+    fn iter_level(&self, level: usize) -> Box<dyn Iterator<Item = &PCB> + '_> {
+        self.inner.iter_level(level)
+    }
+
+    fn peek(&self) -> Option<&PCB> {
+        self.inner.peek()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.inner.heap_size()
+    }
+
+    fn info(&self) -> Option<SchedulerInfo> {
+        self.inner.info()
+    }
+
+    fn renice(&mut self, id: u32, nice: i8) {
+        self.inner.renice(id, nice)
+    }
+}
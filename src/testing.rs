@@ -0,0 +1,259 @@
+//! # Testing Module
+//!
+//! Property checks of a scheduler's queue discipline, for unit tests.
+//!
+//! A [`sim!`](crate::sim) scenario checks one run end to end; the checks
+//! here check the rule a scheduler is meant to follow, whatever the
+//! workload. Each takes any [`Schedule`] and a [`Discipline`] describing
+//! what it should do, feeds it a small set of processes directly through
+//! `add_process` and `next_process`, and returns every way the scheduler
+//! strayed from the discipline, empty when it did not:
+//! - [`fifo_discipline`]: processes run in the order they were added, ties
+//!   at the same tick included, whatever their IDs;
+//! - [`rr_rotation`]: a process whose time slice ran out goes to the back of
+//!   the queue, behind a process that arrived as the slice ran out;
+//! - [`priority_ordering`]: processes run by priority as the discipline
+//!   says, and in the order they were added within a priority.
+//!
+//! Every check also compares the time slice of each dispatch with the
+//! discipline's and expects the scheduler to be empty afterwards. The
+//! checks move the [`CLOCK`] and take turns on it with the
+//! [`scenario`](crate::scenario) tests, so they can run in parallel.
+//!
+//! ## Example
+//! ```
+//! use scheduler::policy::{FCFS, RR};
+//! use scheduler::prio_fcfs::PrioFcfsSchedule;
+//! use scheduler::testing::{self, Discipline};
+//! use scheduler::{PCB, Schedule, TimeSlice};
+//!
+//! assert!(testing::fifo_discipline(&mut FCFS::new(), &Discipline::fcfs()).is_empty());
+//! assert!(testing::rr_rotation(&mut RR::with_quantum(4), &Discipline::round_robin(4)).is_empty());
+//! assert!(testing::priority_ordering(&mut FCFS::new(), &Discipline::fcfs()).is_empty());
+//!
+//! // a LIFO stack is no queue
+//! struct Lifo(Vec<PCB>);
+//! impl Schedule for Lifo {
+//!     fn add_process(&mut self, p: PCB) -> bool { self.0.push(p); true }
+//!     fn next_process(&mut self) -> (Option<PCB>, TimeSlice) { (self.0.pop(), TimeSlice::Infinite) }
+//!     fn has_process(&self) -> bool { !self.0.is_empty() }
+//! }
+//! let problems = testing::fifo_discipline(&mut Lifo(Vec::new()), &Discipline::fcfs());
+//! assert_eq!(problems[0], "fifo: dispatch 1 returned P0 instead of P3");
+//! // and round robin is not first come, first served
+//! let problems = testing::fifo_discipline(&mut RR::with_quantum(4), &Discipline::fcfs());
+//! assert_eq!(problems[0], "fifo: P3 was dispatched for 4 ticks instead of to completion");
+//!
+//! // prio_fcfs runs by priority, and within one in the order processes were added
+//! assert!(testing::priority_ordering(&mut PrioFcfsSchedule::new(), &Discipline::priority()).is_empty());
+//! ```
+
+use std::time::Duration;
+
+use crate::scenario::RUNNING;
+use crate::{CLOCK, PCB, Schedule, TimeSlice};
+
+/// Which processes a [`Discipline`] runs first by their priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PriorityOrder {
+    /// Priorities make no difference; processes run in the order they
+    /// were added.
+    #[default]
+    Ignored,
+    /// The lowest priority number runs first, as level `0` is the highest
+    /// in the multi-level schedulers.
+    LowestFirst,
+    /// The highest priority number runs first.
+    HighestFirst,
+}
+
+/// What a scheduler is expected to do, for the checks of the
+/// [module documentation](self).
+///
+/// # Fields
+/// - `slice`: The time slice every dispatch hands out, or `None` not to
+///   check it, e.g. for a scheduler with a different quantum per level.
+/// - `priority`: How priorities order the processes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Discipline {
+    pub slice: Option<TimeSlice>,
+    pub priority: PriorityOrder,
+}
+
+impl Discipline {
+    /// First come, first served: every process to completion, in the
+    /// order added.
+    pub fn fcfs() -> Self {
+        Self { slice: Some(TimeSlice::Infinite), priority: PriorityOrder::Ignored }
+    }
+
+    /// Round robin with a `quantum`-tick time slice.
+    pub fn round_robin(quantum: u32) -> Self {
+        Self { slice: Some(TimeSlice::Ticks(quantum)), priority: PriorityOrder::Ignored }
+    }
+
+    /// Non-preemptive priority scheduling, the lowest priority number first.
+    pub fn priority() -> Self {
+        Self { slice: Some(TimeSlice::Infinite), priority: PriorityOrder::LowestFirst }
+    }
+}
+
+/// Checks that `sched` runs processes in the order they were added: four
+/// processes added at tick `0` with their IDs out of order, then one added
+/// at tick `1`.
+///
+/// # Returns
+/// A description of every deviation from `discipline`, empty when there
+/// was none.
+pub fn fifo_discipline(sched: &mut impl Schedule, discipline: &Discipline) -> Vec<String> {
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut check = Check::new("fifo", discipline);
+    for id in [3, 1, 4, 2] {
+        sched.add_process(pcb(id, 0));
+    }
+    check.advance(1);
+    sched.add_process(pcb(0, 0));
+    for id in [3, 1, 4, 2, 0] {
+        check.dispatch(sched, id);
+    }
+    check.finish(sched)
+}
+
+/// Checks that `sched` rotates its processes: four processes added at tick
+/// `0` each run for one time slice and are added back, for three rounds,
+/// and a process arriving as the first slice runs out queues up ahead of
+/// the process whose slice it was, as it does in the simulator.
+///
+/// # Returns
+/// A description of every deviation from `discipline`, empty when there
+/// was none. A discipline without a time slice cannot rotate, which is
+/// reported as the only problem.
+pub fn rr_rotation(sched: &mut impl Schedule, discipline: &Discipline) -> Vec<String> {
+    let Some(TimeSlice::Ticks(quantum)) = discipline.slice else {
+        return vec!["rr: the discipline has no time slice to rotate by".to_string()];
+    };
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut check = Check::new("rr", discipline);
+    for id in [3, 1, 4, 2] {
+        sched.add_process(pcb(id, 0));
+    }
+    let Some(first) = check.dispatch(sched, 3) else {
+        return check.finish(sched);
+    };
+    check.advance(quantum as u64);
+    sched.add_process(pcb(5, 0));
+    sched.add_process(first);
+    let order = [1, 4, 2, 5, 3];
+    for _ in 0..2 {
+        for id in order {
+            let Some(process) = check.dispatch(sched, id) else {
+                return check.finish(sched);
+            };
+            check.advance(quantum as u64);
+            sched.add_process(process);
+        }
+    }
+    //in the last round every process finishes
+    for id in order {
+        check.dispatch(sched, id);
+    }
+    check.finish(sched)
+}
+
+/// Checks that `sched` orders processes by priority as `discipline` says:
+/// five processes of three priorities added at tick `0`, out of order, run
+/// by priority and, within a priority, in the order they were added.
+///
+/// # Returns
+/// A description of every deviation from `discipline`, empty when there
+/// was none.
+pub fn priority_ordering(sched: &mut impl Schedule, discipline: &Discipline) -> Vec<String> {
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut check = Check::new("priority", discipline);
+    // (id, priority) in the order they are added
+    let processes = [(5, 2), (2, 0), (3, 1), (4, 0), (1, 2)];
+    for (id, priority) in processes {
+        sched.add_process(pcb(id, priority));
+    }
+    let mut expected = processes.to_vec();
+    match discipline.priority {
+        PriorityOrder::Ignored => {}
+        PriorityOrder::LowestFirst => expected.sort_by_key(|&(_, priority)| priority),
+        PriorityOrder::HighestFirst => expected.sort_by_key(|&(_, priority)| std::cmp::Reverse(priority)),
+    }
+    for (id, _) in expected {
+        check.dispatch(sched, id);
+    }
+    check.finish(sched)
+}
+
+/// A new process, as the simulator creates them.
+fn pcb(id: u32, priority: u32) -> PCB {
+    PCB { id, priority, time_added: None, time_scheduled: None }
+}
+
+/// The state of one check: its name, for the problems it finds, and the
+/// dispatches so far.
+struct Check<'d> {
+    name: &'static str,
+    discipline: &'d Discipline,
+    dispatches: usize,
+    problems: Vec<String>,
+}
+
+impl<'d> Check<'d> {
+    /// Starts a check at tick `0`.
+    fn new(name: &'static str, discipline: &'d Discipline) -> Self {
+        CLOCK.set_now(Duration::ZERO);
+        Self { name, discipline, dispatches: 0, problems: Vec::new() }
+    }
+
+    /// Moves the clock on by `ticks`.
+    fn advance(&self, ticks: u64) {
+        CLOCK.advance(CLOCK.tick() * ticks as u32);
+    }
+
+    /// Takes the next process from `sched`, expecting it to be `expected`
+    /// with the discipline's time slice.
+    fn dispatch(&mut self, sched: &mut impl Schedule, expected: u32) -> Option<PCB> {
+        self.dispatches += 1;
+        let (process, slice) = sched.next_process();
+        let Some(process) = process else {
+            self.problems.push(format!("{}: dispatch {} returned no process instead of P{}", self.name, self.dispatches, expected));
+            return None;
+        };
+        if process.id != expected {
+            self.problems.push(format!(
+                "{}: dispatch {} returned P{} instead of P{}",
+                self.name, self.dispatches, process.id, expected
+            ));
+        }
+        if let Some(wanted) = self.discipline.slice.filter(|&wanted| wanted != slice) {
+            self.problems.push(format!(
+                "{}: P{} was dispatched for {} instead of {}",
+                self.name,
+                process.id,
+                describe(slice),
+                describe(wanted)
+            ));
+        }
+        Some(process)
+    }
+
+    /// Expects `sched` to be empty, returning the problems found.
+    fn finish(mut self, sched: &impl Schedule) -> Vec<String> {
+        if sched.has_process() {
+            self.problems.push(format!("{}: processes were left over after every process ran", self.name));
+        }
+        self.problems
+    }
+}
+
+/// A time slice for people, e.g. `4 ticks` or `to completion`.
+fn describe(slice: TimeSlice) -> String {
+    match slice {
+        TimeSlice::Ticks(1) => "1 tick".to_string(),
+        TimeSlice::Ticks(ticks) => format!("{} ticks", ticks),
+        TimeSlice::Infinite => "to completion".to_string(),
+    }
+}
@@ -0,0 +1,387 @@
+//! # Timer Module
+//!
+//! The queues the simulator's engine keeps its timers in: the arrivals, I/O
+//! completions and timeouts still to come, each due at a tick.
+//!
+//! An operating system has the same problem. Every sleep, timeout and
+//! network retransmission is a timer, there may be hundreds of thousands of
+//! them, and most are cancelled before they fire. A [`Timers`] queue can be
+//! kept three ways, chosen with the `timers` option of the `[engine]` table
+//! of a config file (see [`TimerKind`]):
+//!
+//! - `btree`: a `BTreeMap` from the tick to the timers due at it, which the
+//!   engine always used. Adding and expiring a timer both take
+//!   `O(log n)` time.
+//! - `heap`: a `BinaryHeap`, the textbook priority queue, `O(log n)` too.
+//! - `wheel`: a hierarchical timing wheel, as in the Linux kernel
+//!   (`kernel/time/timer.c`) and most event loops. See [`TimerWheel`].
+//!
+//! All three hand back the timers due at the same tick in the order they
+//! were added, so a run gives the same events with any of them.
+//!
+//! ## Example
+//! ```
+//! use scheduler::timer::{TimerKind, Timers};
+//!
+//! for kind in [TimerKind::BTree, TimerKind::Heap, TimerKind::Wheel] {
+//!     let mut timers = Timers::new(kind);
+//!     timers.insert(5, "wake P1");
+//!     timers.insert(3, "arrive P2");
+//!     timers.insert(5, "time out P3");
+//!     timers.insert(10_000, "arrive P4");
+//!     assert_eq!(timers.expire(2), Vec::<&str>::new());
+//!     assert_eq!(timers.expire(5), vec!["arrive P2", "wake P1", "time out P3"]);
+//!     timers.retain(|&timer| timer != "arrive P4");
+//!     assert!(timers.is_empty());
+//! }
+//! assert_eq!("wheel".parse::<TimerKind>(), Ok(TimerKind::Wheel));
+//! ```
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// How a [`Timers`] queue is kept, see the [module documentation](self).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimerKind {
+    /// A `BTreeMap` keyed by tick.
+    #[default]
+    BTree,
+    /// A `BinaryHeap` ordered by tick.
+    Heap,
+    /// A hierarchical [`TimerWheel`].
+    Wheel,
+}
+
+impl fmt::Display for TimerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TimerKind::BTree => "btree",
+            TimerKind::Heap => "heap",
+            TimerKind::Wheel => "wheel",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for TimerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "btree" => Ok(TimerKind::BTree),
+            "heap" => Ok(TimerKind::Heap),
+            "wheel" => Ok(TimerKind::Wheel),
+            _ => Err(format!("unknown timer queue '{}', expected btree, heap or wheel", s)),
+        }
+    }
+}
+
+/// A timer in a [`Timers::Heap`]: ordered by its tick, then by when it was
+/// added.
+#[derive(Debug, Clone)]
+pub struct HeapEntry<T> {
+    at: u64,
+    seq: u64,
+    item: T,
+}
+
+impl<T> PartialEq for HeapEntry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl<T> Eq for HeapEntry<T> {}
+
+impl<T> PartialOrd for HeapEntry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for HeapEntry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+/// A queue of timers, each an item due at a tick.
+#[derive(Debug, Clone)]
+pub enum Timers<T> {
+    /// The items due at each tick, in the order they were added.
+    BTree(BTreeMap<u64, Vec<T>>),
+    /// A min-heap of the items, with the number of items added so far.
+    Heap(BinaryHeap<Reverse<HeapEntry<T>>>, u64),
+    /// A timing wheel.
+    Wheel(TimerWheel<T>),
+}
+
+impl<T> Timers<T> {
+    /// An empty queue kept the `kind` way.
+    pub fn new(kind: TimerKind) -> Self {
+        match kind {
+            TimerKind::BTree => Timers::BTree(BTreeMap::new()),
+            TimerKind::Heap => Timers::Heap(BinaryHeap::new(), 0),
+            TimerKind::Wheel => Timers::Wheel(TimerWheel::new()),
+        }
+    }
+
+    /// Adds `item`, due at tick `at`.
+    pub fn insert(&mut self, at: u64, item: T) {
+        match self {
+            Timers::BTree(map) => map.entry(at).or_default().push(item),
+            Timers::Heap(heap, added) => {
+                heap.push(Reverse(HeapEntry { at, seq: *added, item }));
+                *added += 1;
+            }
+            Timers::Wheel(wheel) => wheel.insert(at, item),
+        }
+    }
+
+    /// Removes and returns every item due at or before tick `now`, earliest
+    /// first and in the order they were added.
+    pub fn expire(&mut self, now: u64) -> Vec<T> {
+        match self {
+            Timers::BTree(map) => {
+                let mut due = Vec::new();
+                while map.first_key_value().is_some_and(|(&at, _)| at <= now) {
+                    due.extend(map.pop_first().into_iter().flat_map(|(_, items)| items));
+                }
+                due
+            }
+            Timers::Heap(heap, _) => {
+                let mut due = Vec::new();
+                while heap.peek().is_some_and(|Reverse(entry)| entry.at <= now) {
+                    due.extend(heap.pop().map(|Reverse(entry)| entry.item));
+                }
+                due
+            }
+            Timers::Wheel(wheel) => wheel.expire(now),
+        }
+    }
+
+    /// Keeps only the items `keep` returns `true` for.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        match self {
+            Timers::BTree(map) => {
+                map.values_mut().for_each(|items| items.retain(&mut keep));
+                map.retain(|_, items| !items.is_empty());
+            }
+            Timers::Heap(heap, _) => heap.retain(|Reverse(entry)| keep(&entry.item)),
+            Timers::Wheel(wheel) => wheel.retain(keep),
+        }
+    }
+
+    /// How many items are queued.
+    pub fn len(&self) -> usize {
+        match self {
+            Timers::BTree(map) => map.values().map(Vec::len).sum(),
+            Timers::Heap(heap, _) => heap.len(),
+            Timers::Wheel(wheel) => wheel.len(),
+        }
+    }
+
+    /// Whether no items are queued.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Timers::BTree(map) => map.is_empty(),
+            Timers::Heap(heap, _) => heap.is_empty(),
+            Timers::Wheel(wheel) => wheel.is_empty(),
+        }
+    }
+}
+
+/// Bits of a tick each level of a [`TimerWheel`] resolves.
+const BITS: u32 = 6;
+/// Slots in each level of a [`TimerWheel`].
+const SLOTS: usize = 1 << BITS;
+/// Mask of a tick's slot in the lowest level.
+const MASK: u64 = SLOTS as u64 - 1;
+/// Levels of a [`TimerWheel`], enough for any `u64` tick.
+const LEVELS: usize = u64::BITS.div_ceil(BITS) as usize;
+
+/// One level of a [`TimerWheel`].
+#[derive(Debug, Clone)]
+struct Level<T> {
+    /// the timers in each slot, with the tick they are due at
+    slots: Vec<Vec<(u64, T)>>,
+    /// bit `i` is set when slot `i` holds a timer
+    occupied: u64,
+}
+
+impl<T> Level<T> {
+    fn new() -> Self {
+        Self { slots: (0..SLOTS).map(|_| Vec::new()).collect(), occupied: 0 }
+    }
+
+    fn push(&mut self, slot: usize, at: u64, item: T) {
+        self.slots[slot].push((at, item));
+        self.occupied |= 1 << slot;
+    }
+
+    fn take(&mut self, slot: usize) -> Vec<(u64, T)> {
+        self.occupied &= !(1 << slot);
+        std::mem::take(&mut self.slots[slot])
+    }
+}
+
+/// A hierarchical timing wheel.
+///
+/// The lowest level is a ring of 64 slots, one per tick: a timer due within
+/// the next 64 ticks goes straight into the slot of its tick, and expiring
+/// the timers of a tick empties one slot, without comparing any timers. A
+/// timer due further out goes into a coarser level, whose slots each span
+/// 64 slots of the level below: 64 ticks, 4 096 ticks, and so on, eleven
+/// levels for any `u64` tick. Whenever the clock reaches the start of a
+/// slot of a coarser level, its timers are *cascaded*, put back in at the
+/// level their tick now belongs to, which is further down.
+///
+/// So adding a timer takes constant time, and every timer is cascaded at
+/// most once per level, `O(log n)` times for a timer due `n` ticks out,
+/// while the clock skips over empty slots with the help of a bitmap per
+/// level. Most timers in a kernel are cancelled long before they are due,
+/// and while they are still in a coarse level they have cost almost
+/// nothing.
+///
+/// ## Example
+/// ```
+/// use scheduler::timer::TimerWheel;
+///
+/// let mut wheel = TimerWheel::new();
+/// wheel.insert(3, 'a');
+/// wheel.insert(100, 'b');
+/// wheel.insert(70, 'c');
+/// assert_eq!(wheel.len(), 3);
+/// assert_eq!(wheel.expire(69), vec!['a']);
+/// assert_eq!(wheel.expire(200), vec!['c', 'b']);
+/// //a timer already due expires at once
+/// wheel.insert(10, 'd');
+/// assert_eq!(wheel.expire(201), vec!['d']);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TimerWheel<T> {
+    levels: Vec<Level<T>>,
+    /// the first tick not expired yet
+    elapsed: u64,
+    /// timers added when they were already due, with the tick they were due at
+    overdue: Vec<(u64, T)>,
+    len: usize,
+}
+
+impl<T> TimerWheel<T> {
+    /// An empty wheel, at tick `0`.
+    pub fn new() -> Self {
+        Self { levels: (0..LEVELS).map(|_| Level::new()).collect(), elapsed: 0, overdue: Vec::new(), len: 0 }
+    }
+
+    /// Adds `item`, due at tick `at`.
+    pub fn insert(&mut self, at: u64, item: T) {
+        self.len += 1;
+        if at < self.elapsed {
+            self.overdue.push((at, item));
+            return;
+        }
+        self.place(at, item);
+    }
+
+    /// Puts a timer due at or after `elapsed` into the level of the highest
+    /// bits its tick differs from `elapsed` in.
+    fn place(&mut self, at: u64, item: T) {
+        let level = match at ^ self.elapsed {
+            0 => 0,
+            differ => ((u64::BITS - 1 - differ.leading_zeros()) / BITS) as usize,
+        };
+        let slot = ((at >> (level as u32 * BITS)) & MASK) as usize;
+        self.levels[level].push(slot, at, item);
+    }
+
+    /// Removes and returns every item due at or before tick `now`, earliest
+    /// first and in the order they were added.
+    pub fn expire(&mut self, now: u64) -> Vec<T> {
+        let mut overdue = std::mem::take(&mut self.overdue);
+        overdue.sort_by_key(|&(at, _)| at);
+        let mut due: Vec<T> = overdue.into_iter().map(|(_, item)| item).collect();
+        while self.elapsed <= now {
+            let Some(level) = self.levels.iter().position(|level| level.occupied != 0) else {
+                self.elapsed = now.saturating_add(1);
+                break;
+            };
+            //the lowest level's timers are the next ones, all in slots ahead of
+            //`elapsed` within its slot of the level above, so the first of
+            //them marks the next tick anything happens at
+            let shift = level as u32 * BITS;
+            let slot = self.levels[level].occupied.trailing_zeros() as u64;
+            let above = shift + BITS;
+            let block = if above >= u64::BITS { 0 } else { self.elapsed >> above << above };
+            let at = block | slot << shift;
+            if at > now {
+                self.elapsed = now + 1;
+            } else if level == 0 {
+                due.extend(self.levels[0].take(slot as usize).into_iter().map(|(_, item)| item));
+                let Some(next) = at.checked_add(1) else {
+                    break;
+                };
+                self.elapsed = next;
+            } else {
+                self.elapsed = at;
+            }
+            if self.elapsed & MASK == 0 {
+                self.cascade();
+            }
+        }
+        self.len -= due.len();
+        due
+    }
+
+    /// Moves the timers of the slots `elapsed` has just reached the start
+    /// of down the wheel, coarsest level first.
+    fn cascade(&mut self) {
+        for level in (1..LEVELS).rev() {
+            let shift = level as u32 * BITS;
+            if self.elapsed & ((1 << shift) - 1) != 0 {
+                continue;
+            }
+            let slot = ((self.elapsed >> shift) & MASK) as usize;
+            for (at, item) in self.levels[level].take(slot) {
+                self.place(at, item);
+            }
+        }
+    }
+
+    /// Keeps only the items `keep` returns `true` for.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        self.overdue.retain(|(_, item)| keep(item));
+        let mut len = self.overdue.len();
+        for level in &mut self.levels {
+            for (slot, items) in level.slots.iter_mut().enumerate() {
+                items.retain(|(_, item)| keep(item));
+                if items.is_empty() {
+                    level.occupied &= !(1 << slot);
+                }
+                len += items.len();
+            }
+        }
+        self.len = len;
+    }
+
+    /// How many items are queued.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no items are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T> Default for TimerWheel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
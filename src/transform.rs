@@ -0,0 +1,176 @@
+//! # Transform Module
+//!
+//! Rewrites a workload into an equivalent but not identical one, for the
+//! simulator's `transform` subcommand, so that practice versions of the
+//! grading workloads can be handed out without handing out the workloads
+//! themselves.
+//!
+//! A [`Transform`] can
+//! - renumber the jobs: the IDs `0` to `n - 1` are dealt out to the jobs in
+//!   a random order drawn from a seed, so the same seed always gives the
+//!   same numbering;
+//! - rescale every time, arrivals, CPU bursts, I/O waits, annotations,
+//!   renices and timeouts alike, by a factor, rounded to whole ticks and
+//!   keeping CPU steps and timeouts at least one tick long;
+//! - strip the class labels and annotations, which often name the very
+//!   thing a workload is testing.
+//!
+//! Jobs arriving together keep their input-file order, so a transformed
+//! workload has the same shape as the original. [`to_text`] writes it in the
+//! input file format.
+//!
+//! ## Example
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::Workload;
+//! use scheduler::transform::{self, Transform};
+//!
+//! let text = "10 0 5 1 batch\n%annotate 4 hog\n20 4 2 0 interactive\n";
+//! let workload = Workload::parse(text.lines(), TimeUnit::Ns, true).unwrap();
+//! let practice = Transform { scale: 2.0, ..Transform::anonymize(7) }.apply(&workload);
+//! assert_eq!(practice.jobs.len(), 2);
+//! assert!(practice.jobs.keys().eq([0, 1].iter()));
+//! assert!(practice.jobs.values().all(|job| job.class.is_none()));
+//! assert!(practice.annotations.is_empty());
+//! let bursts: Vec<u32> = practice.arrivals.values().flatten().map(|id| practice.jobs[id].burst).collect();
+//! assert_eq!(bursts, [10, 4]);
+//! assert_eq!(practice.arrivals.keys().collect::<Vec<_>>(), [&0, &8]);
+//! assert_eq!(practice, Transform { scale: 2.0, ..Transform::anonymize(7) }.apply(&workload));
+//!
+//! let text = transform::to_text(&practice, true).unwrap();
+//! assert_eq!(Workload::parse(text.lines(), TimeUnit::Ns, true).unwrap(), practice);
+//!
+//! // a file without the priority column is written back without it
+//! let workload = Workload::parse(["3 0 5", "4 2 1"], TimeUnit::Ns, false).unwrap();
+//! assert_eq!(transform::to_text(&workload, false).unwrap(), "3 0 5\n4 2 1\n");
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::event::Annotation;
+use crate::generate;
+use crate::input::{JobSpec, Mode, Renice, Step, Workload};
+use crate::random::Rng;
+
+/// How to rewrite a workload, see the [module documentation](self).
+///
+/// # Fields
+/// - `renumber`: Seed of the new numbering, or `None` to keep the IDs.
+/// - `scale`: Factor every time is multiplied by; `1.0` keeps them.
+/// - `strip_tags`: Whether to drop the class labels and annotations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub renumber: Option<u64>,
+    pub scale: f64,
+    pub strip_tags: bool,
+}
+
+impl Default for Transform {
+    /// Keeps the workload as it is.
+    fn default() -> Self {
+        Self { renumber: None, scale: 1.0, strip_tags: false }
+    }
+}
+
+impl Transform {
+    /// Renumbers the jobs with `seed` and strips their tags, keeping the
+    /// times.
+    pub fn anonymize(seed: u64) -> Self {
+        Self { renumber: Some(seed), scale: 1.0, strip_tags: true }
+    }
+
+    /// The rewritten workload.
+    pub fn apply(&self, workload: &Workload) -> Workload {
+        let ids = self.numbering(workload);
+        let time = |ticks: u64| (ticks as f64 * self.scale).round() as u64;
+        let cpu = |ticks: u32| ((ticks as f64 * self.scale).round() as u32).max(1);
+        let jobs = workload.jobs.values().map(|job| {
+            let spec = JobSpec {
+                id: ids[&job.id],
+                arrival: time(job.arrival),
+                burst: cpu(job.burst),
+                priority: job.priority,
+                class: job.class.clone().filter(|_| !self.strip_tags),
+                nice: job.nice,
+                timeout: job.timeout.map(|ticks| time(ticks).max(1)),
+            };
+            (spec.id, spec)
+        });
+        let mut arrivals: BTreeMap<u64, Vec<u32>> = BTreeMap::new();
+        for id in workload.arrivals.values().flatten() {
+            arrivals.entry(time(workload.jobs[id].arrival)).or_default().push(ids[id]);
+        }
+        let scripts = workload.scripts.iter().map(|(id, script)| {
+            let steps = script.iter().map(|step| match *step {
+                Step::Cpu(ticks) => Step::Cpu(cpu(ticks)),
+                Step::Io(ticks) => Step::Io(time(ticks).max(1)),
+            });
+            (ids[id], steps.collect())
+        });
+        let annotations = match self.strip_tags {
+            true => Vec::new(),
+            false => workload
+                .annotations
+                .iter()
+                .map(|annotation| Annotation { time: time(annotation.time), label: annotation.label.clone() })
+                .collect(),
+        };
+        let renices = workload.renices.iter().filter(|renice| ids.contains_key(&renice.id));
+        let renices = renices.map(|renice| Renice { time: time(renice.time), id: ids[&renice.id], nice: renice.nice }).collect();
+        let mode = match workload.mode {
+            Mode::Closed { think, rounds, seed } => Mode::Closed { think: time(think), rounds, seed },
+            Mode::Open => Mode::Open,
+        };
+        let mut transformed = Workload { jobs: jobs.collect(), arrivals, mode, scripts: scripts.collect(), annotations, renices };
+        //a scripted job's burst is its total CPU time, as the parsers have it
+        for (id, script) in &transformed.scripts {
+            if let Some(job) = transformed.jobs.get_mut(id) {
+                job.burst = script.iter().map(|step| if let Step::Cpu(ticks) = step { *ticks } else { 0 }).sum();
+            }
+        }
+        transformed
+    }
+
+    /// The new ID of every job, keyed by its old one.
+    fn numbering(&self, workload: &Workload) -> BTreeMap<u32, u32> {
+        let Some(seed) = self.renumber else {
+            return workload.jobs.keys().map(|&id| (id, id)).collect();
+        };
+        let mut new: Vec<u32> = (0..workload.jobs.len() as u32).collect();
+        let mut rng = Rng::new(seed);
+        for i in (1..new.len()).rev() {
+            new.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+        }
+        workload.jobs.keys().copied().zip(new).collect()
+    }
+}
+
+/// Writes `workload` in the input file format, jobs in arrival order, with
+/// the priority column when `priority` is `true` (see
+/// [`generate::to_columns`]).
+///
+/// # Errors
+/// If the workload has closed-loop clients or burst scripts, which only the
+/// JSON format can describe.
+pub fn to_text(workload: &Workload, priority: bool) -> Result<String, String> {
+    if workload.mode != Mode::Open || !workload.scripts.is_empty() {
+        return Err("closed-loop clients and burst scripts cannot be written as a text workload".to_string());
+    }
+    let mut text = String::new();
+    let mut annotations = workload.annotations.iter().peekable();
+    for (&tick, ids) in &workload.arrivals {
+        while let Some(annotation) = annotations.next_if(|annotation| annotation.time <= tick) {
+            text += &format!("%annotate {} {}\n", annotation.time, annotation.label);
+        }
+        for id in ids {
+            text += &generate::to_columns(std::slice::from_ref(&workload.jobs[id]), priority);
+        }
+    }
+    for annotation in annotations {
+        text += &format!("%annotate {} {}\n", annotation.time, annotation.label);
+    }
+    for renice in &workload.renices {
+        text += &format!("%renice {} {} {}\n", renice.time, renice.id, renice.nice);
+    }
+    Ok(text)
+}
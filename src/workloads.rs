@@ -0,0 +1,94 @@
+//! # Workloads Module
+//!
+//! Example workloads built into the binary, for demonstrating the classic
+//! scheduling phenomena without writing an input file first. The simulator
+//! loads them with `--input-file builtin:<name>`.
+//!
+//! Every workload includes the priority column, so it can be fed to any
+//! scheduler, and all jobs arrive at tick `0` unless the workload is about
+//! arrivals. Workloads that need burst
+//! scripts are written as [JSON workloads](crate::input#json-workloads).
+//!
+//! ## Example
+//! ```
+//! use scheduler::clock::TimeUnit;
+//! use scheduler::input::Workload;
+//! use scheduler::workloads;
+//!
+//! let convoy = workloads::get("convoy").unwrap();
+//! let workload = Workload::parse(convoy.text.lines(), TimeUnit::Ns, true).unwrap();
+//! assert_eq!(workload.jobs[&0].burst, 500);
+//!
+//! for builtin in workloads::ALL {
+//!     let parsed = if builtin.json {
+//!         Workload::from_json(builtin.text, TimeUnit::Ns)
+//!     } else {
+//!         Workload::parse(builtin.text.lines(), TimeUnit::Ns, true)
+//!     };
+//!     assert!(parsed.is_ok(), "{}", builtin.name);
+//! }
+//! assert!(workloads::get("missing").is_none());
+//! ```
+
+/// Prefix of `--input-file` values that name a built-in workload.
+pub const PREFIX: &str = "builtin:";
+
+/// A workload embedded in the binary.
+///
+/// # Fields
+/// - `name`: What follows `builtin:` on the command line.
+/// - `description`: The phenomenon the workload demonstrates.
+/// - `text`: The workload, in the input file format.
+/// - `json`: Whether `text` is a JSON workload rather than lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub text: &'static str,
+    pub json: bool,
+}
+
+/// Every built-in workload.
+pub const ALL: &[Builtin] = &[
+    Builtin {
+        name: "convoy",
+        description: "one 500-tick job queued in front of five 5-tick jobs; compare simple with simplerr",
+        text: include_str!("../workloads/convoy.in"),
+        json: false,
+    },
+    Builtin {
+        name: "starvation",
+        description: "one low-priority job behind twelve high-priority ones; watch mlf promote it",
+        text: include_str!("../workloads/starvation.in"),
+        json: false,
+    },
+    Builtin {
+        name: "interactive",
+        description: "short high-priority jobs interleaved with long low-priority batch jobs",
+        text: include_str!("../workloads/interactive.in"),
+        json: false,
+    },
+    Builtin {
+        name: "rr_quantum",
+        description: "four equal 12-tick jobs; vary the simplerr quantum with --config",
+        text: include_str!("../workloads/rr_quantum.in"),
+        json: false,
+    },
+    Builtin {
+        name: "mixed",
+        description: "long and short jobs arriving over 25 ticks; the input of `experiment rr-quantum`",
+        text: include_str!("../workloads/mixed.in"),
+        json: false,
+    },
+    Builtin {
+        name: "gaming",
+        description: "a job that blocks just before each 4-tick slice ends, next to an honest one; try mlf with --gaming",
+        text: include_str!("../workloads/gaming.json"),
+        json: true,
+    },
+];
+
+/// Looks up a built-in workload by name.
+pub fn get(name: &str) -> Option<&'static Builtin> {
+    ALL.iter().find(|builtin| builtin.name == name)
+}
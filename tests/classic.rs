@@ -0,0 +1,350 @@
+//! Correct reference policies run on the classic loops `simple`, `simplerr`
+//! and `mlrr` are simulated with, on the workloads the simulator's checking
+//! commands use. A correct policy must pass all of them, so a failure here
+//! is the simulator's fault and not the scheduler's.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use scheduler::classic::{Classic, Run};
+use scheduler::clock::TimeUnit;
+use scheduler::config::{MlrrConfig, SchedulerConfig};
+use scheduler::distribution::Distribution;
+use scheduler::equivalence;
+use scheduler::experiment;
+use scheduler::generate::{self, Generator};
+use scheduler::input::Workload;
+use scheduler::messages::Catalog;
+use scheduler::output::Output;
+use scheduler::policy::{FCFS, RR};
+use scheduler::results::{Results, RunConfig};
+use scheduler::selfcheck;
+use scheduler::simulation::{ExpiryOrder, Job, Simulation, SimulationError};
+use scheduler::sink::{Recorder, TextSink, Verbosity};
+use scheduler::stats::BatchReport;
+use scheduler::workloads;
+use scheduler::{CLOCK, PCB, Schedule, TimeSlice};
+
+/// The tests take turns on the global [`CLOCK`].
+static RUNNING: Mutex<()> = Mutex::new(());
+
+/// Multi-level round robin as the assignment describes it: a queue per
+/// priority, visited in turn, each with its quantum from [`MlrrConfig`].
+struct Mlrr {
+    queues: Vec<VecDeque<PCB>>,
+    quanta: Vec<u32>,
+    next: usize,
+}
+
+impl Mlrr {
+    fn new() -> Self {
+        Self::with_quanta(MlrrConfig::default().quanta)
+    }
+
+    fn with_quanta(quanta: Vec<u32>) -> Self {
+        Self { queues: vec![VecDeque::new(); quanta.len()], quanta, next: 0 }
+    }
+}
+
+impl Schedule for Mlrr {
+    fn add_process(&mut self, process: PCB) -> bool {
+        let level = (process.priority as usize).min(self.queues.len() - 1);
+        self.queues[level].push_back(process);
+        true
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        for level in (self.next..self.queues.len()).chain(0..self.next) {
+            if let Some(process) = self.queues[level].pop_front() {
+                self.next = (level + 1) % self.queues.len();
+                return (Some(process), TimeSlice::Ticks(self.quanta[level]));
+            }
+        }
+        (None, TimeSlice::Infinite)
+    }
+
+    fn has_process(&self) -> bool {
+        self.queues.iter().any(|queue| !queue.is_empty())
+    }
+}
+
+/// Round robin that panics when asked for a process from tick `at` on.
+struct Late {
+    rr: RR,
+    at: u64,
+}
+
+impl Schedule for Late {
+    fn add_process(&mut self, process: PCB) -> bool {
+        self.rr.add_process(process)
+    }
+
+    fn next_process(&mut self) -> (Option<PCB>, TimeSlice) {
+        assert!(CLOCK.now_ticks() < self.at, "too late");
+        self.rr.next_process()
+    }
+
+    fn has_process(&self) -> bool {
+        self.rr.has_process()
+    }
+}
+
+/// The jobs of `workload`, in input order.
+fn jobs(workload: &Workload) -> Vec<Job> {
+    let jobs = workload.arrivals.values().flatten().map(|id| &workload.jobs[id]);
+    jobs.map(|spec| Job { priority: spec.priority, ..Job::new(spec.id, spec.arrival, spec.burst) }).collect()
+}
+
+/// The workload `scheduler batch` generates from `seed` with its default
+/// options, but for the gaps between arrivals.
+fn generated(arrival: Distribution, seed: u64) -> String {
+    let generator = Generator {
+        jobs: 100,
+        arrival,
+        burst: "exponential:10".parse().unwrap(),
+        priorities: 1,
+        classes: Vec::new(),
+        seed,
+    };
+    generate::to_text(&generator.generate())
+}
+
+/// Runs `sched` on `workload`, its markers included, emitting to `out`.
+fn simulate(sched: impl Schedule, how: Run, workload: &Workload, out: &mut Recorder) -> Result<(), String> {
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    CLOCK.set_tick(Duration::from_nanos(1));
+    let classic = Classic::new(sched, how);
+    let classic = workload.annotations.iter().fold(classic, |classic, annotation| classic.annotate(annotation.time, annotation.label.clone()));
+    classic.jobs(jobs(workload)).run(out).map_err(|err| err.to_string())
+}
+
+/// Runs `sched` on `workload` and checks the run as `scheduler selfcheck`
+/// does.
+fn run(sched: impl Schedule, how: Run, workload: &Workload) -> Result<Results, String> {
+    let mut out = Recorder::new();
+    simulate(sched, how, workload, &mut out)?;
+    let results = Results::new(RunConfig::default(), &out);
+    match selfcheck::check(workload, &results) {
+        problems if problems.is_empty() => Ok(results),
+        problems => Err(problems.join("\n")),
+    }
+}
+
+#[test]
+fn arrivals_while_running() {
+    //P1 and P2 arrive while P0 runs, at ticks where no run ends
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    CLOCK.set_tick(Duration::from_nanos(1));
+    for how in [Run::ToCompletion, Run::ByQuantum] {
+        let mut run = Classic::new(RR::with_quantum(4), how).jobs([Job::new(0, 0, 10), Job::new(1, 3, 2), Job::new(2, 5, 2)]);
+        let mut out = Recorder::new();
+        run.run(&mut out).unwrap_or_else(|err| panic!("{:?}: {}", how, err));
+        for id in 0..3 {
+            assert!(out.metrics().process(id).is_some_and(|process| process.finish.is_some()), "{:?}: P{} did not finish", how, id);
+        }
+    }
+}
+
+#[test]
+fn runs_to_completion_one_tick_past_the_burst() {
+    //the classic loop follows the reference traces, the Simulation engine the burst
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    CLOCK.set_tick(Duration::from_nanos(1));
+    let jobs = [Job::new(0, 0, 5), Job::new(1, 1, 3)];
+    let mut out = Recorder::new();
+    Classic::new(FCFS::new(), Run::ToCompletion).jobs(jobs.clone()).run(&mut out).unwrap();
+    let classic: Vec<_> = (0..2).map(|id| out.metrics().process(id).unwrap().finish).collect();
+    let mut out = Recorder::new();
+    Simulation::builder(FCFS::new()).jobs(jobs.clone()).build().run(&mut out).unwrap();
+    let exact: Vec<_> = (0..2).map(|id| out.metrics().process(id).unwrap().finish).collect();
+    assert_eq!(classic, [Some(6), Some(10)]);
+    assert_eq!(exact, [Some(5), Some(8)]);
+    //by quantum every tick is charged, so the two agree
+    let mut out = Recorder::new();
+    Classic::new(RR::with_quantum(2), Run::ByQuantum).jobs(jobs.clone()).run(&mut out).unwrap();
+    let classic: Vec<_> = (0..2).map(|id| out.metrics().process(id).unwrap().finish).collect();
+    let mut out = Recorder::new();
+    Simulation::builder(RR::with_quantum(2)).jobs(jobs).build().run(&mut out).unwrap();
+    assert_eq!(classic, (0..2).map(|id| out.metrics().process(id).unwrap().finish).collect::<Vec<_>>());
+}
+
+#[test]
+fn expiry_order_matches_the_simulation() {
+    //P1 and P2 arrive as a slice of P0 runs out
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    CLOCK.set_tick(Duration::from_nanos(1));
+    let jobs = [Job::new(0, 0, 9), Job::new(1, 3, 4), Job::new(2, 6, 2)];
+    let finishes = |out: &Recorder| (0..3).map(|id| out.metrics().process(id).unwrap().finish).collect::<Vec<_>>();
+    for order in [ExpiryOrder::ArrivalsFirst, ExpiryOrder::PreemptedFirst] {
+        let mut out = Recorder::new();
+        Classic::new(RR::with_quantum(3), Run::ByQuantum).expiry_order(order).jobs(jobs.clone()).run(&mut out).unwrap();
+        let classic = finishes(&out);
+        let mut out = Recorder::new();
+        Simulation::builder(RR::with_quantum(3)).expiry_order(order).jobs(jobs.clone()).build().run(&mut out).unwrap();
+        assert_eq!(classic, finishes(&out), "{}", order);
+    }
+}
+
+#[test]
+fn non_preemptive_matches_the_simulation() {
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    CLOCK.set_tick(Duration::from_nanos(1));
+    let jobs = [Job::new(0, 0, 9), Job::new(1, 3, 4), Job::new(2, 6, 2)];
+    let finishes = |out: &Recorder| (0..3).map(|id| out.metrics().process(id).unwrap().finish).collect::<Vec<_>>();
+    let mut out = Recorder::new();
+    Classic::new(RR::with_quantum(3), Run::ByQuantum).non_preemptive().jobs(jobs.clone()).run(&mut out).unwrap();
+    assert_eq!(finishes(&out), [Some(9), Some(13), Some(15)]);
+    let mut out = Recorder::new();
+    Simulation::builder(RR::with_quantum(3)).non_preemptive().jobs(jobs).build().run(&mut out).unwrap();
+    assert_eq!(finishes(&out), [Some(9), Some(13), Some(15)]);
+}
+
+#[test]
+fn hook_submits_jobs() {
+    //P0 arrives after an idle tick, and the hook adds P1 once P0 has run a tick
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    CLOCK.set_tick(Duration::from_nanos(1));
+    let (mut idle, mut submitted) = (0, false);
+    let mut run = Classic::new(RR::with_quantum(2), Run::ByQuantum).jobs([Job::new(0, 1, 4)]).on_tick(|ctx| {
+        match ctx.running() {
+            None => idle += 1,
+            Some(_) if !submitted => {
+                //already passed, so it arrives as soon as jobs are handed over again
+                ctx.submit(Job::new(1, 0, 1));
+                ctx.annotate(0, "P1 submitted");
+                submitted = true;
+            }
+            Some(_) => {}
+        }
+    });
+    let mut out = Recorder::new();
+    run.run(&mut out).unwrap();
+    drop(run);
+    assert_eq!(idle, 1);
+    let finish = |id| out.metrics().process(id).unwrap().finish;
+    //P0's slice runs out as P1 arrives, and the preempted P0 queues up first
+    assert_eq!((finish(0), finish(1)), (Some(5), Some(6)));
+    assert_eq!(out.annotations().iter().map(|marker| marker.time).collect::<Vec<_>>(), [2]);
+}
+
+#[test]
+fn batch_seeds() {
+    //the workloads of `scheduler batch --runs 5` with its default workload options
+    let mut report = BatchReport::default();
+    for seed in 0..5 {
+        let text = generated("poisson:0.1".parse().unwrap(), seed);
+        let workload = Workload::parse(text.lines(), TimeUnit::Ns, false).unwrap();
+        let results = run(RR::with_quantum(4), Run::ByQuantum, &workload).unwrap_or_else(|err| panic!("seed {}: {}", seed, err));
+        assert_eq!(results.aggregates.completed, 100, "seed {}", seed);
+        report.add("simplerr", results.aggregates);
+    }
+    assert_eq!(report.estimate("simplerr", "turnaround").unwrap().runs, 5);
+}
+
+#[test]
+fn selfcheck_builtins() {
+    //the JSON workloads have burst scripts, which only the Simulation engine runs
+    for builtin in workloads::ALL.iter().filter(|builtin| !builtin.json) {
+        //simple and simplerr are handed every job with priority 0
+        let workload = |with_priority| Workload::parse(builtin.text.lines(), TimeUnit::Ns, with_priority).unwrap();
+        let runs = [
+            ("simple", run(FCFS::new(), Run::ToCompletion, &workload(false))),
+            ("simplerr", run(RR::with_quantum(4), Run::ByQuantum, &workload(false))),
+            ("mlrr", run(Mlrr::new(), Run::ByQuantum, &workload(true))),
+        ];
+        for (scheduler, result) in runs {
+            if let Err(problems) = result {
+                panic!("{} on {}: {}", scheduler, builtin.name, problems);
+            }
+        }
+    }
+}
+
+#[test]
+fn rr_quantum_preset() {
+    //`scheduler experiment rr-quantum` with its defaults: simplerr on builtin:mixed
+    let mixed = workloads::get("mixed").unwrap();
+    let workload = Workload::parse(mixed.text.lines(), TimeUnit::Ns, false).unwrap();
+    for quantum in experiment::DEFAULT_QUANTA {
+        let results = run(RR::with_quantum(quantum), Run::ByQuantum, &workload).unwrap_or_else(|err| panic!("quantum {}: {}", quantum, err));
+        assert_eq!(results.aggregates.completed, workload.jobs.len(), "quantum {}", quantum);
+    }
+}
+
+#[test]
+fn annotations_are_trace_only() {
+    let trace = |text: &str, name: &str| {
+        let workload = Workload::parse(text.lines(), TimeUnit::Ns, true).unwrap();
+        let path = std::env::temp_dir().join(format!("scheduler-classic-{}-{}.out", name, std::process::id()));
+        let output = Output::file(&path).unwrap();
+        let mut out = Recorder::new();
+        out.add_sink(Box::new(TextSink { with_priority: true, verbosity: Verbosity::Normal, messages: Catalog::default(), out: output.clone() }));
+        simulate(Mlrr::new(), Run::ByQuantum, &workload, &mut out).unwrap();
+        out.finish();
+        output.commit().unwrap();
+        let trace = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        trace
+    };
+    //markers before the first job, while a process runs and at the tick jobs 10 to 13 arrive
+    let plain = include_str!("mlrr/t1.in");
+    let annotated = format!("%annotate 0 start\n{}%annotate 50 halfway\n%annotate 400 late arrivals\n", plain);
+    let annotated = trace(&annotated, "annotated");
+    assert_eq!(annotated.lines().filter(|line| line.starts_with("--- ")).count(), 3);
+    let unmarked: Vec<&str> = annotated.lines().filter(|line| !line.starts_with("--- ")).collect();
+    assert_eq!(unmarked, trace(plain, "plain").lines().collect::<Vec<_>>());
+    assert_eq!(unmarked, include_str!("mlrr/t1.out").lines().collect::<Vec<_>>());
+}
+
+#[test]
+fn saturation_sweep() {
+    //`scheduler batch -s simple -s simplerr -s mlrr --sweep-rates ...` with the default workload options, past saturation
+    for rate in [0.02, 0.05, 0.08, 0.1, 0.12] {
+        for seed in 0..3 {
+            let text = generated(Distribution::Exponential { mean: 1.0 / rate }, seed);
+            let workload = |with_priority| Workload::parse(text.lines(), TimeUnit::Ns, with_priority).unwrap();
+            let runs = [
+                ("simple", run(FCFS::new(), Run::ToCompletion, &workload(false))),
+                ("simplerr", run(RR::with_quantum(4), Run::ByQuantum, &workload(false))),
+                ("mlrr", run(Mlrr::new(), Run::ByQuantum, &workload(true))),
+            ];
+            for (scheduler, result) in runs {
+                match result {
+                    Ok(results) => assert_eq!(results.aggregates.completed, 100, "{} at rate {}, seed {}", scheduler, rate, seed),
+                    Err(problems) => panic!("{} at rate {}, seed {}: {}", scheduler, rate, seed, problems),
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn catch_panics_keeps_the_schedule() {
+    let workload = Workload::parse(include_str!("simplerr/t1.in").lines(), TimeUnit::Ns, false).unwrap();
+    let mut plain = Recorder::new();
+    simulate(RR::with_quantum(4), Run::ByQuantum, &workload, &mut plain).unwrap();
+    let _running = RUNNING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    CLOCK.set_tick(Duration::from_nanos(1));
+    let mut caught = Recorder::new();
+    let mut run = Classic::new(Late { rr: RR::with_quantum(4), at: 20 }, Run::ByQuantum).jobs(jobs(&workload)).catch_panics();
+    let Err(SimulationError::Fault(fault)) = run.run(&mut caught) else { panic!("the run should fail") };
+    assert_eq!((fault.time, fault.panic.message.as_str()), (20, "too late"));
+    //the same run as without catching, up to the panic
+    assert!(!caught.events().is_empty());
+    assert_eq!(caught.events(), &plain.events()[..caught.events().len()]);
+}
+
+#[test]
+fn equivalence_rr() {
+    //`scheduler equivalence` with its defaults: simplerr against mlrr with a single level of its quantum
+    let quanta = equivalence::equivalences(&SchedulerConfig::default())[0].config.mlrr.quanta.clone();
+    let texts = workloads::ALL.iter().filter(|builtin| !builtin.json).map(|builtin| (builtin.name.to_string(), builtin.text.to_string()));
+    let texts = texts.chain((0..5).map(|seed| (format!("seed {}", seed), generated("poisson:0.1".parse().unwrap(), seed))));
+    for (name, text) in texts {
+        let workload = |with_priority| Workload::parse(text.lines(), TimeUnit::Ns, with_priority).unwrap();
+        let simplerr = run(RR::with_quantum(quanta[0]), Run::ByQuantum, &workload(false)).unwrap();
+        let mlrr = run(Mlrr::with_quanta(quanta.clone()), Run::ByQuantum, &workload(true)).unwrap();
+        assert_eq!(equivalence::compare(&simplerr, &mlrr), None, "{}", name);
+    }
+}